@@ -0,0 +1,273 @@
+use kill_doctor_lucky_rust::core::move_selector::Difficulty;
+use kill_doctor_lucky_rust::core::self_play::{EngineConfig, SelfPlayOutcome, SelfPlayRunner};
+use kill_doctor_lucky_rust::util::data_dir::{DATA_DIR_FLAG, DataPaths};
+use kill_doctor_lucky_rust::util::rating::{GameScore, elo_ratings};
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+struct Config {
+    engines: Vec<EngineConfig>,
+    board_names: Vec<String>,
+    data_paths: DataPaths,
+    rounds: usize,
+    max_normal_turns: usize,
+    seed: u64,
+    output_path: Option<String>,
+}
+
+struct ScheduledGame {
+    first_idx: usize,
+    second_idx: usize,
+    board_name: String,
+    first_engine_moves_first: bool,
+}
+
+fn main() {
+    let config = parse_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(2);
+    });
+    let boards = config
+        .board_names
+        .iter()
+        .map(|board_name| {
+            config
+                .data_paths
+                .load_board(board_name, std::iter::empty::<String>())
+                .unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    std::process::exit(2);
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let started = Instant::now();
+    let mut game_scores = Vec::new();
+    let mut game_lines = Vec::new();
+
+    for (game_idx, scheduled) in schedule(&config).into_iter().enumerate() {
+        let board_idx = config
+            .board_names
+            .iter()
+            .position(|board_name| *board_name == scheduled.board_name)
+            .unwrap_or(0);
+        let first_engine = &config.engines[scheduled.first_idx];
+        let second_engine = &config.engines[scheduled.second_idx];
+        // each game gets its own seed, so any one of them can be played again on its own
+        let runner = SelfPlayRunner::new(config.max_normal_turns)
+            .with_game_seed(config.seed.wrapping_add(game_idx as u64));
+        let game = runner.play_on_board(
+            boards[board_idx].clone(),
+            first_engine,
+            second_engine,
+            scheduled.first_engine_moves_first,
+        );
+        let outcome_text = match game.outcome {
+            SelfPlayOutcome::FirstEngineWon => format!("{} won", first_engine.name),
+            SelfPlayOutcome::SecondEngineWon => format!("{} won", second_engine.name),
+            SelfPlayOutcome::TurnLimitReached => "turn limit".to_string(),
+            SelfPlayOutcome::Draw(draw_reason) => format!("draw ({draw_reason})"),
+        };
+        let line = format!(
+            "{} vs {} on {} ({} first): {} after {} turns, states={}",
+            first_engine.name,
+            second_engine.name,
+            scheduled.board_name,
+            if scheduled.first_engine_moves_first {
+                &first_engine.name
+            } else {
+                &second_engine.name
+            },
+            outcome_text,
+            game.num_normal_turns,
+            game.num_states_visited
+        );
+        println!("{line}");
+        game_lines.push(line);
+        game_scores.push(GameScore::new(
+            scheduled.first_idx,
+            scheduled.second_idx,
+            game.outcome.first_engine_score(),
+        ));
+    }
+
+    let names = config
+        .engines
+        .iter()
+        .map(|engine| engine.name.clone())
+        .collect::<Vec<_>>();
+    let mut ratings = elo_ratings(&names, &game_scores);
+    ratings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+
+    let mut report = String::new();
+    let _ = writeln!(report, "engine rating report");
+    let _ = writeln!(report, "  boards: {}", config.board_names.join(", "));
+    let _ = writeln!(
+        report,
+        "  engines: {}",
+        config
+            .engines
+            .iter()
+            .map(|engine| engine.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let _ = writeln!(
+        report,
+        "  games: {} seed={} timeSec={:.2}",
+        game_scores.len(),
+        config.seed,
+        started.elapsed().as_secs_f64()
+    );
+    let _ = writeln!(report, "ratings (95% confidence):");
+    for rating in &ratings {
+        let _ = writeln!(report, "  {rating}");
+    }
+    let _ = writeln!(report, "games:");
+    for line in &game_lines {
+        let _ = writeln!(report, "  {line}");
+    }
+
+    print!("{report}");
+    if let Some(output_path) = config.output_path.as_ref() {
+        if let Err(err) = fs::write(output_path, &report) {
+            eprintln!("failed to write report to '{output_path}': {err}");
+            std::process::exit(1);
+        }
+        println!("report written to {output_path}");
+    }
+}
+
+fn schedule(config: &Config) -> Vec<ScheduledGame> {
+    let mut games = Vec::new();
+    for _ in 0..config.rounds {
+        for first_idx in 0..config.engines.len() {
+            for second_idx in first_idx + 1..config.engines.len() {
+                for board_name in &config.board_names {
+                    for first_engine_moves_first in [true, false] {
+                        games.push(ScheduledGame {
+                            first_idx,
+                            second_idx,
+                            board_name: board_name.clone(),
+                            first_engine_moves_first,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    games
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+    let mut engines = vec![
+        EngineConfig::from_analysis_level(1),
+        EngineConfig::from_analysis_level(2),
+        EngineConfig::from_analysis_level(3),
+    ];
+    let mut board_names = vec!["AltDown".to_string()];
+    let mut data_dir = None;
+    let mut rounds = 1usize;
+    let mut max_normal_turns = SelfPlayRunner::DEFAULT_MAX_NORMAL_TURNS;
+    let mut seed = 0u64;
+    let mut output_path = None;
+    let mut pending_flag = None::<String>;
+
+    for arg in args {
+        if let Some(flag) = pending_flag.take() {
+            match flag.as_str() {
+                "--levels" => {
+                    engines = arg
+                        .split(',')
+                        .map(|level| {
+                            let level = level.trim();
+                            match level.parse::<i32>() {
+                                Ok(level) => Ok(EngineConfig::from_analysis_level(level)),
+                                Err(_) => level
+                                    .parse::<Difficulty>()
+                                    .map(EngineConfig::from_difficulty)
+                                    .map_err(|_| {
+                                        format!("invalid level or difficulty for {flag}: {level}")
+                                    }),
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                "--boards" => {
+                    board_names = arg
+                        .split(',')
+                        .map(|board_name| board_name.trim().to_string())
+                        .collect::<Vec<_>>();
+                }
+                DATA_DIR_FLAG => data_dir = Some(PathBuf::from(arg)),
+                "--rounds" => {
+                    rounds = arg
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--max-turns" => {
+                    max_normal_turns = arg
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--seed" => {
+                    seed = arg
+                        .parse::<u64>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--output" => output_path = Some(arg),
+                _ => return Err(format!("unsupported flag {flag}")),
+            }
+            continue;
+        }
+
+        match arg.as_str() {
+            "--levels" | "--boards" | DATA_DIR_FLAG | "--rounds" | "--max-turns" | "--seed"
+            | "--output" => pending_flag = Some(arg),
+            "--help" | "-h" => return Err(help_text().to_owned()),
+            _ => return Err(format!("unrecognized argument '{arg}'\n\n{}", help_text())),
+        }
+    }
+
+    if let Some(flag) = pending_flag {
+        return Err(format!("missing value for {flag}\n\n{}", help_text()));
+    }
+
+    if engines.len() < 2 {
+        return Err("--levels needs at least two engine levels".to_owned());
+    }
+
+    if engines.iter().any(|engine| engine.analysis_level < 1) {
+        return Err("--levels must all be >= 1".to_owned());
+    }
+
+    if rounds == 0 {
+        return Err("--rounds must be >= 1".to_owned());
+    }
+
+    Ok(Config {
+        engines,
+        board_names,
+        data_paths: DataPaths::from_env(data_dir),
+        rounds,
+        max_normal_turns,
+        seed,
+        output_path,
+    })
+}
+
+fn help_text() -> &'static str {
+    concat!(
+        "engine_rating options:\n",
+        "  --levels <a,b,...>        Analysis levels or difficulties (easy, medium, hard) of the engines to rate. Default: 1,2,3\n",
+        "  --boards <a,b,...>        Boards to play on, from the data dir if there, else embedded. Default: AltDown\n",
+        "  --data-dir <path>         Reference data dir with boards. Default: $KDL_DATA_DIR\n",
+        "  --rounds <n>              Round-robin repetitions (each pairing plays both sides on each board). Default: 1\n",
+        "  --max-turns <n>           Normal turns before a game counts as a draw. Default: 200\n",
+        "  --seed <n>                Seed for the difficulty engines' picks; game k uses seed+k. Default: 0\n",
+        "  --output <path>           Also write the report to this file\n"
+    )
+}
@@ -0,0 +1,347 @@
+// seeded fuzzer for inputs that reach the engine from outside: turn plan json (wasm), turn text
+// (cli) and board json. each input has to come back as a result or an error; a panic stops the
+// run and prints the seed, iteration and input so it can be replayed
+use kill_doctor_lucky_rust::core::{
+    board::Board, common_game_state::CommonGameState, mutable_game_state::MutableGameState,
+    simple_turn::SimpleTurn,
+};
+use kill_doctor_lucky_rust::util::rng::SplitMix64;
+use kill_doctor_lucky_rust::{GameStateHandle, new_default_game_state};
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+const PIECE_IDS: &[&str] = &[
+    "player1",
+    "player2",
+    "stranger1",
+    "stranger2",
+    "stranger3",
+    "stranger4",
+    "doctor",
+    "cat",
+];
+const MAX_FUZZ_ROOM_ID: usize = 40; // past the end of every embedded board
+
+const DICTIONARY: &[&str] = &[
+    "\"pieceId\"",
+    "\"roomId\"",
+    "\"roomName\"",
+    "\"doctor\"",
+    "\"player1\"",
+    "\"player2\"",
+    "\"stranger1\"",
+    "\"stranger2\"",
+    "\"Id\"",
+    "\"Adjacent\"",
+    "\"Visible\"",
+    "\"Rooms\"",
+    "\"Wings\"",
+    "\"PlayerStartRoomIds\"",
+    "\"DoctorStartRoomIds\"",
+    "-1",
+    "0",
+    "1",
+    "4294967296",
+    "18446744073709551615",
+    "99999999999999999999",
+    "1e309",
+    "null",
+    "[]",
+    "{}",
+    "[",
+    "]",
+    "{",
+    "}",
+    ",",
+    ":",
+    "@",
+    ";",
+    " ",
+    "\"\"",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Target {
+    TurnPlanJson,
+    TurnText,
+    BoardJson,
+}
+
+impl Target {
+    fn parse(raw: &str) -> Result<Self, String> {
+        match raw {
+            "turn_plan_json" => Ok(Self::TurnPlanJson),
+            "turn_text" => Ok(Self::TurnText),
+            "board_json" => Ok(Self::BoardJson),
+            _ => Err(format!("unknown target '{raw}'")),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::TurnPlanJson => "turn_plan_json",
+            Self::TurnText => "turn_text",
+            Self::BoardJson => "board_json",
+        }
+    }
+}
+
+struct Config {
+    seed: u64,
+    iterations: usize,
+    targets: Vec<Target>,
+}
+
+// the engine's splitmix64, plus the picks mutations need
+trait MutationRng {
+    fn below(&mut self, bound: usize) -> usize;
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T;
+}
+
+impl MutationRng for SplitMix64 {
+    fn below(&mut self, bound: usize) -> usize {
+        if bound == 0 {
+            0
+        } else {
+            (self.next_u64() % bound as u64) as usize
+        }
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+fn main() {
+    let config = parse_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(2);
+    });
+    let board_seeds = board_seed_texts();
+    let mut rng = SplitMix64::new(config.seed);
+    let mut handle = fresh_handle();
+    let mut turn_text_state = fresh_state();
+
+    for iteration in 0..config.iterations {
+        let target = *rng.pick(&config.targets);
+        let seed_input = match target {
+            Target::TurnPlanJson => turn_plan_seed(&mut rng),
+            Target::TurnText => turn_text_seed(&mut rng, &turn_text_state),
+            Target::BoardJson => rng.pick(&board_seeds).clone(),
+        };
+        let input = mutate(&mut rng, &seed_input);
+
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| match target {
+            Target::TurnPlanJson => exercise_turn_plan_json(&mut handle, &input, &mut rng),
+            Target::TurnText => exercise_turn_text(&mut turn_text_state, &input),
+            Target::BoardJson => exercise_board_json(&input, &mut rng),
+        }));
+        if outcome.is_err() {
+            eprintln!(
+                "panic: seed={} iteration={iteration} target={}\ninput:\n{input}",
+                config.seed,
+                target.name()
+            );
+            std::process::exit(1);
+        }
+
+        if handle.has_winner() || handle.is_draw() {
+            handle = fresh_handle();
+        }
+        if turn_text_state.is_game_over() {
+            turn_text_state = fresh_state();
+        }
+    }
+
+    println!(
+        "seed={} iterations={} targets={} no panics",
+        config.seed,
+        config.iterations,
+        config
+            .targets
+            .iter()
+            .map(|target| target.name())
+            .collect::<Vec<_>>()
+            .join(",")
+    );
+}
+
+fn fresh_handle() -> GameStateHandle {
+    new_default_game_state().unwrap_or_else(|_| panic!("default game state should load"))
+}
+
+fn fresh_state() -> MutableGameState {
+    let board = Board::from_embedded_json("BoardAltDown").expect("BoardAltDown should load");
+    MutableGameState::at_start(CommonGameState::from_num_normal_players(false, board, 2))
+}
+
+fn board_seed_texts() -> Vec<String> {
+    let boards_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/core/boards");
+    let mut texts = fs::read_dir(&boards_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| fs::read_to_string(entry.ok()?.path()).ok())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if texts.is_empty() {
+        texts.push(
+            r#"{"Name":"seed","PlayerStartRoomIds":[1],"DoctorStartRoomIds":[2],"CatStartRoomIds":[1],"DogStartRoomIds":[2],"Rooms":[{"Id":"1","Name":"a","Adjacent":[2],"Visible":[2]},{"Id":"2","Name":"b","Adjacent":[1],"Visible":[1]}]}"#
+                .to_string(),
+        );
+    }
+    texts
+}
+
+fn turn_plan_seed(rng: &mut SplitMix64) -> String {
+    let num_moves = rng.below(3);
+    let entries = (0..num_moves)
+        .map(|_| {
+            let piece_id = rng.pick(PIECE_IDS);
+            if rng.below(4) == 0 {
+                format!(
+                    r#"{{"pieceId":"{piece_id}","roomName":"{}"}}"#,
+                    rng.pick(&["parlor", "d", "ki", ""])
+                )
+            } else {
+                format!(
+                    r#"{{"pieceId":"{piece_id}","roomId":{}}}"#,
+                    rng.below(MAX_FUZZ_ROOM_ID)
+                )
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", entries.join(","))
+}
+
+fn turn_text_seed(rng: &mut SplitMix64, state: &MutableGameState) -> String {
+    let turns = state.possible_turns();
+    if turns.is_empty() {
+        return ";".to_string();
+    }
+    rng.pick(&turns).to_string()
+}
+
+fn mutate(rng: &mut SplitMix64, seed: &str) -> String {
+    let mut bytes = seed.as_bytes().to_vec();
+    for _ in 0..=rng.below(4) {
+        let idx = rng.below(bytes.len() + 1);
+        match rng.below(5) {
+            0 if idx < bytes.len() => bytes[idx] = rng.next_u64() as u8,
+            1 if idx < bytes.len() => {
+                let end = (idx + 1 + rng.below(8)).min(bytes.len());
+                bytes.drain(idx..end);
+            }
+            2 if idx < bytes.len() => {
+                let end = (idx + 1 + rng.below(16)).min(bytes.len());
+                let copied = bytes[idx..end].to_vec();
+                bytes.splice(idx..idx, copied);
+            }
+            3 => {
+                bytes.splice(idx..idx, rng.pick(DICTIONARY).bytes());
+            }
+            _ => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn exercise_turn_plan_json(handle: &mut GameStateHandle, input: &str, rng: &mut SplitMix64) {
+    let _ = handle.validate_turn_plan(input);
+    let _ = handle.validate_turn_plan_json(input);
+    let _ = handle.turn_plan_cost_json(input);
+    let _ = handle.preview_turn_plan(input);
+    let _ = handle.stage_move(*rng.pick(PIECE_IDS), rng.below(MAX_FUZZ_ROOM_ID));
+    let _ = handle.staged_plan_json();
+    handle.rollback_staged_moves();
+    let _ = handle.apply_turn_plan(input);
+}
+
+fn exercise_turn_text(state: &mut MutableGameState, input: &str) {
+    let Ok(turn) = input.parse::<SimpleTurn>() else {
+        return;
+    };
+    if state.check_normal_turn(&turn).is_ok() {
+        *state = state.after_turn(turn);
+        let _ = state.prev_turn_summary(true);
+    }
+}
+
+// a board that loads has to survive a few turns of play too
+fn exercise_board_json(input: &str, rng: &mut SplitMix64) {
+    let Ok(board) = Board::from_json_str(input, "BoardFuzz") else {
+        return;
+    };
+    let _ = board.is_valid();
+    let mut state =
+        MutableGameState::at_start(CommonGameState::from_num_normal_players(false, board, 2));
+    for _ in 0..4 {
+        if state.is_game_over() {
+            break;
+        }
+        let turns = state.possible_turns();
+        if turns.is_empty() {
+            break;
+        }
+        let turn = rng.pick(&turns).clone();
+        state = state.after_turn(turn);
+        let _ = state.summary(0);
+        let _ = state.heuristic_score(state.current_player_id);
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+    let mut seed = 1u64;
+    let mut iterations = 10_000usize;
+    let mut targets = vec![Target::TurnPlanJson, Target::TurnText, Target::BoardJson];
+    let mut pending_flag = None::<String>;
+
+    for arg in args {
+        if let Some(flag) = pending_flag.take() {
+            match flag.as_str() {
+                "--seed" => {
+                    seed = arg
+                        .parse::<u64>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--iterations" => {
+                    iterations = arg
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--target" => {
+                    targets = vec![Target::parse(&arg)?];
+                }
+                _ => return Err(format!("unsupported flag {flag}")),
+            }
+            continue;
+        }
+
+        match arg.as_str() {
+            "--seed" | "--iterations" | "--target" => pending_flag = Some(arg),
+            "--help" | "-h" => return Err(help_text().to_owned()),
+            _ => return Err(format!("unrecognized argument '{arg}'\n\n{}", help_text())),
+        }
+    }
+
+    if let Some(flag) = pending_flag {
+        return Err(format!("missing value for {flag}\n\n{}", help_text()));
+    }
+
+    Ok(Config {
+        seed,
+        iterations,
+        targets,
+    })
+}
+
+fn help_text() -> &'static str {
+    concat!(
+        "fuzz_inputs options:\n",
+        "  --seed <n>         Seed for the mutation rng. Default: 1\n",
+        "  --iterations <n>   Number of mutated inputs to try. Default: 10000\n",
+        "  --target <name>    One of: turn_plan_json, turn_text, board_json. Default: all\n"
+    )
+}
@@ -0,0 +1,13 @@
+use kill_doctor_lucky_rust::server;
+use kill_doctor_lucky_rust::util::log::{self, ConsoleLogSink, LogLevel};
+
+fn main() {
+    log::set_log_sink(LogLevel::Info, ConsoleLogSink);
+    let address = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| server::DEFAULT_ADDRESS.to_string());
+    if let Err(message) = server::serve(&address) {
+        eprintln!("{message}");
+        std::process::exit(1);
+    }
+}
@@ -1,6 +1,8 @@
 use kill_doctor_lucky_rust::core::{
-    board::Board, common_game_state::CommonGameState, mutable_game_state::MutableGameState,
-    tree_search::TreeSearch,
+    board::Board,
+    common_game_state::CommonGameState,
+    mutable_game_state::MutableGameState,
+    tree_search::{SearchConfig, TreeSearch},
 };
 use kill_doctor_lucky_rust::util::cancellation::NeverCancelToken;
 use std::env;
@@ -36,6 +38,8 @@ struct Config {
     min_seconds: f64,
     warmup_iterations: usize,
     scenario: Scenario,
+    search_config: SearchConfig,
+    possible_turns_only: bool,
 }
 
 fn main() {
@@ -44,13 +48,18 @@ fn main() {
         std::process::exit(2);
     });
     let state = state_for_scenario(config.scenario);
+    if config.possible_turns_only {
+        bench_possible_turns(&state, &config);
+        return;
+    }
     let token = NeverCancelToken;
 
     for _ in 0..config.warmup_iterations {
         let mut warmup_states_visited = 0usize;
-        let warmup_turn = TreeSearch::find_best_turn(
+        let warmup_turn = TreeSearch::find_best_turn_with_config(
             &state,
             config.analysis_level,
+            &config.search_config,
             &token,
             &mut warmup_states_visited,
         );
@@ -67,9 +76,10 @@ fn main() {
     while iterations < config.min_iterations || started.elapsed().as_secs_f64() < config.min_seconds
     {
         let mut num_states_visited = 0usize;
-        let appraised_turn = TreeSearch::find_best_turn(
+        let appraised_turn = TreeSearch::find_best_turn_with_config(
             &state,
             config.analysis_level,
+            &config.search_config,
             &token,
             &mut num_states_visited,
         );
@@ -103,12 +113,40 @@ fn main() {
     );
 }
 
+// move generation alone, the loop that leans hardest on the board's distance and sight tables
+fn bench_possible_turns(state: &MutableGameState, config: &Config) {
+    for _ in 0..config.warmup_iterations {
+        black_box(state.possible_turns());
+    }
+
+    let started = Instant::now();
+    let mut iterations = 0usize;
+    let mut num_turns = 0usize;
+    while iterations < config.min_iterations || started.elapsed().as_secs_f64() < config.min_seconds
+    {
+        num_turns = black_box(state.possible_turns()).len();
+        iterations += 1;
+    }
+
+    let elapsed = started.elapsed();
+    println!(
+        "scenario={} possibleTurns={} iterations={} avgIterationUs={:.3} timeSec={:.4}",
+        config.scenario.name(),
+        num_turns,
+        iterations,
+        elapsed.as_secs_f64() * 1_000_000.0 / iterations as f64,
+        elapsed.as_secs_f64()
+    );
+}
+
 fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
     let mut analysis_level = 3;
     let mut min_iterations = 1usize;
     let mut min_seconds = 0.0;
     let mut warmup_iterations = 0usize;
     let mut scenario = Scenario::AltDownStart;
+    let mut search_config = SearchConfig::default();
+    let mut possible_turns_only = false;
     let mut pending_flag = None::<String>;
 
     for arg in args {
@@ -148,6 +186,9 @@ fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String>
             | "--min-seconds"
             | "--warmup-iterations"
             | "--scenario" => pending_flag = Some(arg),
+            "--late-move-reductions" => search_config.late_move_reductions = true,
+            "--pass-move-pruning" => search_config.pass_move_pruning = true,
+            "--possible-turns" => possible_turns_only = true,
             "--help" | "-h" => return Err(help_text().to_owned()),
             _ => return Err(format!("unrecognized argument '{arg}'\n\n{}", help_text())),
         }
@@ -175,6 +216,8 @@ fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String>
         min_seconds,
         warmup_iterations,
         scenario,
+        search_config,
+        possible_turns_only,
     })
 }
 
@@ -205,6 +248,9 @@ fn help_text() -> &'static str {
         "  --min-iterations <n>      Run at least this many measured iterations. Default: 1\n",
         "  --min-seconds <n>         Run measured iterations until this duration is reached. Default: 0\n",
         "  --warmup-iterations <n>   Run warmup iterations before measurement. Default: 0\n",
-        "  --scenario <name>         One of: alt_down_start, alt_down_after_opening\n"
+        "  --scenario <name>         One of: alt_down_start, alt_down_after_opening\n",
+        "  --late-move-reductions    Search clearly worse late children a level shallower\n",
+        "  --pass-move-pruning       Cut nodes where staying put already reaches beta\n",
+        "  --possible-turns          Time possible_turns for the scenario instead of a search\n"
     )
 }
@@ -0,0 +1,201 @@
+use kill_doctor_lucky_rust::core::{
+    evaluation::{EVALUATION_FEATURE_NAMES, EvaluationWeights},
+    self_play::{EngineConfig, SelfPlayRunner},
+    tuning::{self, FitOptions},
+};
+use kill_doctor_lucky_rust::util::data_dir::{DATA_DIR_FLAG, DataPaths};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Instant;
+
+struct Config {
+    engines: Vec<EngineConfig>,
+    board_names: Vec<String>,
+    data_paths: DataPaths,
+    max_normal_turns: usize,
+    iterations: usize,
+    learning_rate: f64,
+    l2_penalty: f64,
+    initial_weights_path: Option<String>,
+    output_path: Option<String>,
+}
+
+fn main() {
+    let config = parse_args(env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{message}");
+        std::process::exit(2);
+    });
+    let boards = config
+        .board_names
+        .iter()
+        .map(|board_name| {
+            config
+                .data_paths
+                .load_board(board_name, std::iter::empty::<String>())
+                .unwrap_or_else(|err| {
+                    eprintln!("{err}");
+                    std::process::exit(2);
+                })
+        })
+        .collect::<Vec<_>>();
+    let initial_weights = match config.initial_weights_path.as_ref() {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("failed to read '{path}': {err}"))
+            .and_then(|json| EvaluationWeights::from_json(&json))
+            .unwrap_or_else(|message| {
+                eprintln!("{message}");
+                std::process::exit(2);
+            }),
+        None => EvaluationWeights::default(),
+    };
+
+    let started = Instant::now();
+    let runner = SelfPlayRunner::new(config.max_normal_turns).with_recorded_positions();
+    let samples = tuning::collect_samples(&runner, boards, &config.engines);
+    println!(
+        "collected {} samples in {:.2}s",
+        samples.len(),
+        started.elapsed().as_secs_f64()
+    );
+
+    let fit_options = FitOptions::new(config.iterations, config.learning_rate, config.l2_penalty);
+    let fitted_weights = tuning::fit_weights(&samples, &initial_weights, &fit_options);
+    println!(
+        "logLoss initial={:.5} fitted={:.5}",
+        tuning::log_loss(&samples, &initial_weights),
+        tuning::log_loss(&samples, &fitted_weights)
+    );
+    for ((name, initial), fitted) in EVALUATION_FEATURE_NAMES
+        .iter()
+        .zip(initial_weights.to_array())
+        .zip(fitted_weights.to_array())
+    {
+        println!("  {name:<18} {initial:+.4} -> {fitted:+.4}");
+    }
+
+    let weights_json = fitted_weights.to_json();
+    match config.output_path.as_ref() {
+        Some(output_path) => {
+            if let Err(err) = fs::write(output_path, &weights_json) {
+                eprintln!("failed to write weights to '{output_path}': {err}");
+                std::process::exit(1);
+            }
+            println!("weights written to {output_path}");
+        }
+        None => println!("{weights_json}"),
+    }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+    let mut engines = vec![
+        EngineConfig::from_analysis_level(1),
+        EngineConfig::from_analysis_level(2),
+    ];
+    let mut board_names = vec!["AltDown".to_string()];
+    let mut data_dir = None;
+    let mut max_normal_turns = SelfPlayRunner::DEFAULT_MAX_NORMAL_TURNS;
+    let default_fit_options = FitOptions::default();
+    let mut iterations = default_fit_options.iterations;
+    let mut learning_rate = default_fit_options.learning_rate;
+    let mut l2_penalty = default_fit_options.l2_penalty;
+    let mut initial_weights_path = None;
+    let mut output_path = None;
+    let mut pending_flag = None::<String>;
+
+    for arg in args {
+        if let Some(flag) = pending_flag.take() {
+            match flag.as_str() {
+                "--levels" => {
+                    engines = arg
+                        .split(',')
+                        .map(|level| {
+                            level
+                                .trim()
+                                .parse::<i32>()
+                                .map(EngineConfig::from_analysis_level)
+                                .map_err(|_| format!("invalid integer for {flag}: {level}"))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                "--boards" => {
+                    board_names = arg
+                        .split(',')
+                        .map(|board_name| board_name.trim().to_string())
+                        .collect::<Vec<_>>();
+                }
+                DATA_DIR_FLAG => data_dir = Some(PathBuf::from(arg)),
+                "--max-turns" => {
+                    max_normal_turns = arg
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--iterations" => {
+                    iterations = arg
+                        .parse::<usize>()
+                        .map_err(|_| format!("invalid integer for {flag}: {arg}"))?;
+                }
+                "--learning-rate" => {
+                    learning_rate = arg
+                        .parse::<f64>()
+                        .map_err(|_| format!("invalid number for {flag}: {arg}"))?;
+                }
+                "--l2" => {
+                    l2_penalty = arg
+                        .parse::<f64>()
+                        .map_err(|_| format!("invalid number for {flag}: {arg}"))?;
+                }
+                "--initial" => initial_weights_path = Some(arg),
+                "--output" => output_path = Some(arg),
+                _ => return Err(format!("unsupported flag {flag}")),
+            }
+            continue;
+        }
+
+        match arg.as_str() {
+            "--levels" | "--boards" | DATA_DIR_FLAG | "--max-turns" | "--iterations"
+            | "--learning-rate" | "--l2" | "--initial" | "--output" => pending_flag = Some(arg),
+            "--help" | "-h" => return Err(help_text().to_owned()),
+            _ => return Err(format!("unrecognized argument '{arg}'\n\n{}", help_text())),
+        }
+    }
+
+    if let Some(flag) = pending_flag {
+        return Err(format!("missing value for {flag}\n\n{}", help_text()));
+    }
+
+    if engines.is_empty() || engines.iter().any(|engine| engine.analysis_level < 1) {
+        return Err("--levels must all be >= 1".to_owned());
+    }
+
+    if learning_rate <= 0.0 {
+        return Err("--learning-rate must be > 0".to_owned());
+    }
+
+    Ok(Config {
+        engines,
+        board_names,
+        data_paths: DataPaths::from_env(data_dir),
+        max_normal_turns,
+        iterations,
+        learning_rate,
+        l2_penalty,
+        initial_weights_path,
+        output_path,
+    })
+}
+
+fn help_text() -> &'static str {
+    concat!(
+        "tune_weights options:\n",
+        "  --levels <a,b,...>        Analysis levels of the self-play engines (every pairing plays). Default: 1,2\n",
+        "  --boards <a,b,...>        Boards to play on, from the data dir if there, else embedded. Default: AltDown\n",
+        "  --data-dir <path>         Reference data dir with boards. Default: $KDL_DATA_DIR\n",
+        "  --max-turns <n>           Normal turns before a game counts as a draw. Default: 200\n",
+        "  --iterations <n>          Gradient descent iterations. Default: 2000\n",
+        "  --learning-rate <x>       Gradient descent step size. Default: 0.05\n",
+        "  --l2 <x>                  L2 penalty on the weights. Default: 0.0001\n",
+        "  --initial <path>          Start from these weights json instead of the built-in heuristic\n",
+        "  --output <path>           Write the fitted weights json here instead of stdout\n"
+    )
+}
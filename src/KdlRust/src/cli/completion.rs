@@ -0,0 +1,166 @@
+use crate::session::{TAG_BOARD, TAG_BOARD_LONG, TAG_CLOSED_WINGS, TAG_CLOSED_WINGS_LONG};
+
+// tab-completion candidates for one prompt; the session rebuilds this before each read, so board,
+// wing and room words always match the loaded game
+pub struct Completer {
+    directive_tags: Vec<&'static str>,
+    board_names: Vec<String>,
+    wing_names: Vec<String>,
+    room_words: Vec<String>,
+}
+
+impl Completer {
+    pub fn new(
+        directive_tags: impl IntoIterator<Item = &'static str>,
+        board_names: impl IntoIterator<Item = String>,
+        wing_names: impl IntoIterator<Item = String>,
+        room_words: impl IntoIterator<Item = String>,
+    ) -> Self {
+        Self {
+            directive_tags: directive_tags.into_iter().collect::<Vec<_>>(),
+            board_names: board_names.into_iter().collect::<Vec<_>>(),
+            wing_names: wing_names.into_iter().collect::<Vec<_>>(),
+            room_words: room_words.into_iter().collect::<Vec<_>>(),
+        }
+    }
+
+    // room names as one token, since directives split on whitespace
+    pub fn room_word(room_name: &str) -> String {
+        room_name.trim().replace(' ', "_")
+    }
+
+    // byte offset where the word being completed starts
+    pub fn word_start(line: &str) -> usize {
+        line.rfind([' ', ';', '@', ',']).map_or(0, |idx| idx + 1)
+    }
+
+    // candidates for the word that ends at the end of line_before_cursor
+    pub fn complete(&self, line_before_cursor: &str) -> Vec<String> {
+        let segment = line_before_cursor
+            .rsplit(';')
+            .next()
+            .unwrap_or_default()
+            .trim_start();
+        let word = &segment[Self::word_start(segment)..];
+        let prior_tokens = segment[..segment.len() - word.len()]
+            .split_whitespace()
+            .collect::<Vec<_>>();
+        let is_room_word = segment[..segment.len() - word.len()].ends_with(['@', ',']);
+
+        let candidates = if is_room_word {
+            self.room_words
+                .iter()
+                .map(|word| word.as_str())
+                .collect::<Vec<_>>()
+        } else {
+            match prior_tokens.first().map(|tag| tag.to_lowercase()) {
+                None => self.directive_tags.clone(),
+                Some(tag) if tag == TAG_BOARD || tag == TAG_BOARD_LONG => self
+                    .board_names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>(),
+                Some(tag) if tag == TAG_CLOSED_WINGS || tag == TAG_CLOSED_WINGS_LONG => self
+                    .wing_names
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>(),
+                Some(_) => Vec::new(),
+            }
+        };
+
+        let word = word.to_lowercase();
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.to_lowercase().starts_with(&word))
+            .map(|candidate| candidate.to_string())
+            .collect::<Vec<_>>()
+    }
+}
+
+// longest prefix every candidate shares, compared case-insensitively
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut prefix_len = first.chars().count();
+    for candidate in &candidates[1..] {
+        prefix_len = first
+            .chars()
+            .zip(candidate.chars())
+            .take(prefix_len)
+            .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+            .count();
+    }
+    first.chars().take(prefix_len).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completer() -> Completer {
+        Completer::new(
+            [
+                TAG_BOARD,
+                TAG_BOARD_LONG,
+                TAG_CLOSED_WINGS,
+                "help",
+                "history",
+            ],
+            ["Tiny", "BoardAltDown", "BoardMain"].map(String::from),
+            ["east", "west"].map(String::from),
+            ["dining_hall", "drawing_room", "kitchen"].map(String::from),
+        )
+    }
+
+    #[test]
+    fn directive_names_complete_at_the_start_of_each_directive() {
+        let completer = completer();
+        assert_eq!(completer.complete(""), completer.directive_tags);
+        assert_eq!(completer.complete("h"), ["help", "history"]);
+        assert_eq!(completer.complete("HIS"), ["history"]);
+        assert_eq!(completer.complete("b Tiny; he"), ["help"]);
+        assert!(completer.complete("x").is_empty());
+    }
+
+    #[test]
+    fn board_and_wing_directives_complete_their_argument() {
+        let completer = completer();
+        assert_eq!(
+            completer.complete("b "),
+            ["Tiny", "BoardAltDown", "BoardMain"]
+        );
+        assert_eq!(completer.complete("board boardm"), ["BoardMain"]);
+        assert_eq!(completer.complete("B t"), ["Tiny"]);
+        assert_eq!(completer.complete("w we"), ["west"]);
+        assert_eq!(completer.complete("closedwings "), ["east", "west"]);
+        assert!(completer.complete("help t").is_empty());
+    }
+
+    #[test]
+    fn room_words_complete_after_at_and_comma() {
+        let completer = completer();
+        assert_eq!(completer.complete("1@d"), ["dining_hall", "drawing_room"]);
+        assert_eq!(completer.complete("1@dr"), ["drawing_room"]);
+        assert_eq!(completer.complete("1@dining_hall,Ki"), ["kitchen"]);
+        assert_eq!(completer.complete("1@").len(), 3);
+        assert_eq!(Completer::room_word(" Dining Hall "), "Dining_Hall");
+    }
+
+    #[test]
+    fn common_prefix_ignores_case_and_keeps_the_first_candidates_spelling() {
+        let prefix_of = |candidates: &[&str]| {
+            common_prefix(
+                &candidates
+                    .iter()
+                    .map(|candidate| candidate.to_string())
+                    .collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(prefix_of(&["dining_hall", "drawing_room"]), "d");
+        assert_eq!(prefix_of(&["BoardAltDown", "boardmain"]), "Board");
+        assert_eq!(prefix_of(&["kitchen"]), "kitchen");
+        assert_eq!(prefix_of(&[]), "");
+    }
+}
@@ -0,0 +1,450 @@
+use crate::completion::{self, Completer};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    terminal::{self, ClearType},
+};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+// reads directive lines; on a terminal it runs its own raw-mode loop so arrows walk the history
+// and ctrl-r searches it, otherwise (piped scripts) it falls back to plain line reads
+pub struct LineEditor {
+    history: Vec<String>,
+}
+
+struct EditBuffer {
+    chars: Vec<char>,
+    cursor: usize,
+    history_idx: usize, // history.len() means the line being typed, not a history entry
+    draft: Vec<char>,
+    search: Option<ReverseSearch>,
+}
+
+struct ReverseSearch {
+    query: String,
+    match_idx: Option<usize>,
+}
+
+enum KeyOutcome {
+    Continue,
+    ListCompletions(Vec<String>),
+    Submit,
+    Abandon,
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self {
+            history: Vec::new(),
+        }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    // 1-based, matching the numbers the history directive prints
+    pub fn history_entry(&self, entry_num: usize) -> Option<&str> {
+        entry_num
+            .checked_sub(1)
+            .and_then(|idx| self.history.get(idx))
+            .map(|line| line.as_str())
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        if !line.trim().is_empty() && self.history.last().map(|last| last.as_str()) != Some(line) {
+            self.history.push(line.to_string());
+        }
+    }
+
+    // None at end of input (eof, ctrl-d on an empty line, ctrl-c)
+    pub fn read_line(&mut self, prompt: &str, completer: &Completer) -> io::Result<Option<String>> {
+        if !io::stdin().is_terminal() || terminal::enable_raw_mode().is_err() {
+            return self.read_plain_line(prompt);
+        }
+        let result = self.read_edited_line(prompt, completer);
+        let _ = terminal::disable_raw_mode();
+        println!();
+        result
+    }
+
+    fn read_plain_line(&self, prompt: &str) -> io::Result<Option<String>> {
+        print!("{prompt}");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if io::stdin().lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+    }
+
+    fn read_edited_line(&self, prompt: &str, completer: &Completer) -> io::Result<Option<String>> {
+        let mut buffer = EditBuffer {
+            chars: Vec::new(),
+            cursor: 0,
+            history_idx: self.history.len(),
+            draft: Vec::new(),
+            search: None,
+        };
+        let mut stdout = io::stdout();
+        self.render(&mut stdout, prompt, &buffer)?;
+
+        loop {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match self.handle_key(&mut buffer, key, completer) {
+                KeyOutcome::Continue => self.render(&mut stdout, prompt, &buffer)?,
+                KeyOutcome::ListCompletions(candidates) => {
+                    write!(stdout, "\r\n{}\r\n", candidates.join("  "))?;
+                    self.render(&mut stdout, prompt, &buffer)?;
+                }
+                KeyOutcome::Submit => {
+                    buffer.search = None;
+                    self.render(&mut stdout, prompt, &buffer)?;
+                    return Ok(Some(buffer.chars.iter().collect()));
+                }
+                KeyOutcome::Abandon => return Ok(None),
+            }
+        }
+    }
+
+    fn handle_key(
+        &self,
+        buffer: &mut EditBuffer,
+        key: KeyEvent,
+        completer: &Completer,
+    ) -> KeyOutcome {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        if let Some(search) = buffer.search.as_mut() {
+            match key.code {
+                KeyCode::Char('r') if ctrl => {
+                    let before = search.match_idx.unwrap_or(self.history.len());
+                    if let Some(idx) = self.find_older(&search.query, before) {
+                        search.match_idx = Some(idx);
+                    }
+                    return KeyOutcome::Continue;
+                }
+                KeyCode::Char('c' | 'g') if ctrl => {
+                    buffer.search = None;
+                    return KeyOutcome::Continue;
+                }
+                KeyCode::Char(ch) if !ctrl => {
+                    search.query.push(ch);
+                    search.match_idx = self.find_older(&search.query, self.history.len());
+                    return KeyOutcome::Continue;
+                }
+                KeyCode::Backspace => {
+                    search.query.pop();
+                    search.match_idx = self.find_older(&search.query, self.history.len());
+                    return KeyOutcome::Continue;
+                }
+                _ => {
+                    if let Some(idx) = search.match_idx {
+                        buffer.chars = self.history[idx].chars().collect();
+                        buffer.cursor = buffer.chars.len();
+                    }
+                    buffer.search = None;
+                    if key.code == KeyCode::Esc {
+                        return KeyOutcome::Continue;
+                    }
+                }
+            }
+        }
+
+        match key.code {
+            KeyCode::Enter => return KeyOutcome::Submit,
+            KeyCode::Tab => return Self::complete_word(buffer, completer),
+            KeyCode::Char('c') if ctrl => return KeyOutcome::Abandon,
+            KeyCode::Char('d') if ctrl && buffer.chars.is_empty() => return KeyOutcome::Abandon,
+            KeyCode::Char('r') if ctrl => {
+                buffer.search = Some(ReverseSearch {
+                    query: String::new(),
+                    match_idx: None,
+                });
+            }
+            KeyCode::Char('a') if ctrl => buffer.cursor = 0,
+            KeyCode::Char('e') if ctrl => buffer.cursor = buffer.chars.len(),
+            KeyCode::Char('u') if ctrl => {
+                buffer.chars.drain(..buffer.cursor);
+                buffer.cursor = 0;
+            }
+            KeyCode::Char(ch) if !ctrl => {
+                buffer.chars.insert(buffer.cursor, ch);
+                buffer.cursor += 1;
+            }
+            KeyCode::Backspace if buffer.cursor > 0 => {
+                buffer.cursor -= 1;
+                buffer.chars.remove(buffer.cursor);
+            }
+            KeyCode::Delete if buffer.cursor < buffer.chars.len() => {
+                buffer.chars.remove(buffer.cursor);
+            }
+            KeyCode::Left => buffer.cursor = buffer.cursor.saturating_sub(1),
+            KeyCode::Right => buffer.cursor = (buffer.cursor + 1).min(buffer.chars.len()),
+            KeyCode::Home => buffer.cursor = 0,
+            KeyCode::End => buffer.cursor = buffer.chars.len(),
+            KeyCode::Up if buffer.history_idx > 0 => {
+                if buffer.history_idx == self.history.len() {
+                    buffer.draft = buffer.chars.clone();
+                }
+                buffer.history_idx -= 1;
+                buffer.chars = self.history[buffer.history_idx].chars().collect();
+                buffer.cursor = buffer.chars.len();
+            }
+            KeyCode::Down if buffer.history_idx < self.history.len() => {
+                buffer.history_idx += 1;
+                buffer.chars = self
+                    .history
+                    .get(buffer.history_idx)
+                    .map_or_else(|| buffer.draft.clone(), |line| line.chars().collect());
+                buffer.cursor = buffer.chars.len();
+            }
+            _ => {}
+        }
+        KeyOutcome::Continue
+    }
+
+    // fills in what every candidate agrees on; when that adds nothing, the candidates get listed
+    fn complete_word(buffer: &mut EditBuffer, completer: &Completer) -> KeyOutcome {
+        let before_cursor = buffer.chars[..buffer.cursor].iter().collect::<String>();
+        let candidates = completer.complete(&before_cursor);
+        if candidates.is_empty() {
+            return KeyOutcome::Continue;
+        }
+
+        let word_start = before_cursor[..Completer::word_start(&before_cursor)]
+            .chars()
+            .count();
+        let word_len = buffer.cursor - word_start;
+        let replacement = if candidates.len() == 1 {
+            format!("{} ", candidates[0])
+        } else {
+            completion::common_prefix(&candidates)
+        };
+        let replacement_len = replacement.chars().count();
+        buffer
+            .chars
+            .splice(word_start..buffer.cursor, replacement.chars());
+        buffer.cursor = word_start + replacement_len;
+
+        if candidates.len() > 1 && replacement_len == word_len {
+            KeyOutcome::ListCompletions(candidates)
+        } else {
+            KeyOutcome::Continue
+        }
+    }
+
+    fn find_older(&self, query: &str, before: usize) -> Option<usize> {
+        if query.is_empty() {
+            return None;
+        }
+        self.history[..before]
+            .iter()
+            .rposition(|line| line.contains(query))
+    }
+
+    fn render(&self, stdout: &mut io::Stdout, prompt: &str, buffer: &EditBuffer) -> io::Result<()> {
+        queue!(
+            stdout,
+            cursor::MoveToColumn(0),
+            terminal::Clear(ClearType::UntilNewLine)
+        )?;
+        if let Some(search) = buffer.search.as_ref() {
+            let found = search
+                .match_idx
+                .map_or("", |idx| self.history[idx].as_str());
+            write!(stdout, "(reverse-i-search)`{}': {found}", search.query)?;
+        } else {
+            let line = buffer.chars.iter().collect::<String>();
+            write!(stdout, "{prompt}{line}")?;
+            let cursor_col = prompt.chars().count() + buffer.cursor;
+            queue!(stdout, cursor::MoveToColumn(cursor_col as u16))?;
+        }
+        stdout.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor_with_history(lines: &[&str]) -> LineEditor {
+        let mut editor = LineEditor::new();
+        for line in lines {
+            editor.add_history(line);
+        }
+        editor
+    }
+
+    fn empty_buffer(editor: &LineEditor) -> EditBuffer {
+        EditBuffer {
+            chars: Vec::new(),
+            cursor: 0,
+            history_idx: editor.history.len(),
+            draft: Vec::new(),
+            search: None,
+        }
+    }
+
+    fn completer() -> Completer {
+        Completer::new(["help", "history"], [], [], [])
+    }
+
+    fn press(editor: &LineEditor, buffer: &mut EditBuffer, code: KeyCode) -> KeyOutcome {
+        editor.handle_key(buffer, KeyEvent::from(code), &completer())
+    }
+
+    fn press_ctrl(editor: &LineEditor, buffer: &mut EditBuffer, ch: char) -> KeyOutcome {
+        let key = KeyEvent::new(KeyCode::Char(ch), KeyModifiers::CONTROL);
+        editor.handle_key(buffer, key, &completer())
+    }
+
+    fn type_text(editor: &LineEditor, buffer: &mut EditBuffer, text: &str) {
+        for ch in text.chars() {
+            press(editor, buffer, KeyCode::Char(ch));
+        }
+    }
+
+    fn line(buffer: &EditBuffer) -> String {
+        buffer.chars.iter().collect()
+    }
+
+    #[test]
+    fn editing_keys_move_the_cursor_and_change_the_line() {
+        let editor = LineEditor::new();
+        let mut buffer = empty_buffer(&editor);
+        type_text(&editor, &mut buffer, "ac");
+        press(&editor, &mut buffer, KeyCode::Left);
+        type_text(&editor, &mut buffer, "b");
+        assert_eq!((line(&buffer), buffer.cursor), ("abc".to_string(), 2));
+
+        press(&editor, &mut buffer, KeyCode::Home);
+        press(&editor, &mut buffer, KeyCode::Delete);
+        assert_eq!((line(&buffer), buffer.cursor), ("bc".to_string(), 0));
+        press(&editor, &mut buffer, KeyCode::Backspace);
+        assert_eq!(line(&buffer), "bc");
+
+        press(&editor, &mut buffer, KeyCode::End);
+        press(&editor, &mut buffer, KeyCode::Backspace);
+        assert_eq!((line(&buffer), buffer.cursor), ("b".to_string(), 1));
+        press(&editor, &mut buffer, KeyCode::Right);
+        assert_eq!(buffer.cursor, 1);
+
+        type_text(&editor, &mut buffer, "cd");
+        press_ctrl(&editor, &mut buffer, 'a');
+        assert_eq!(buffer.cursor, 0);
+        press_ctrl(&editor, &mut buffer, 'e');
+        assert_eq!(buffer.cursor, 3);
+        press(&editor, &mut buffer, KeyCode::Left);
+        press_ctrl(&editor, &mut buffer, 'u');
+        assert_eq!((line(&buffer), buffer.cursor), ("d".to_string(), 0));
+    }
+
+    #[test]
+    fn enter_submits_and_ctrl_c_or_ctrl_d_on_an_empty_line_abandon() {
+        let editor = LineEditor::new();
+        let mut buffer = empty_buffer(&editor);
+        assert!(matches!(
+            press_ctrl(&editor, &mut buffer, 'd'),
+            KeyOutcome::Abandon
+        ));
+
+        type_text(&editor, &mut buffer, "x");
+        assert!(matches!(
+            press_ctrl(&editor, &mut buffer, 'd'),
+            KeyOutcome::Continue
+        ));
+        assert_eq!(line(&buffer), "x");
+        assert!(matches!(
+            press(&editor, &mut buffer, KeyCode::Enter),
+            KeyOutcome::Submit
+        ));
+        assert!(matches!(
+            press_ctrl(&editor, &mut buffer, 'c'),
+            KeyOutcome::Abandon
+        ));
+    }
+
+    #[test]
+    fn up_and_down_walk_the_history_and_return_to_the_draft() {
+        let editor = editor_with_history(&["first", "second"]);
+        let mut buffer = empty_buffer(&editor);
+        type_text(&editor, &mut buffer, "dra");
+
+        press(&editor, &mut buffer, KeyCode::Up);
+        assert_eq!((line(&buffer), buffer.cursor), ("second".to_string(), 6));
+        press(&editor, &mut buffer, KeyCode::Up);
+        assert_eq!(line(&buffer), "first");
+        press(&editor, &mut buffer, KeyCode::Up);
+        assert_eq!(line(&buffer), "first");
+
+        press(&editor, &mut buffer, KeyCode::Down);
+        assert_eq!(line(&buffer), "second");
+        press(&editor, &mut buffer, KeyCode::Down);
+        assert_eq!((line(&buffer), buffer.cursor), ("dra".to_string(), 3));
+        press(&editor, &mut buffer, KeyCode::Down);
+        assert_eq!(line(&buffer), "dra");
+    }
+
+    #[test]
+    fn ctrl_r_searches_older_matches_and_esc_or_other_keys_accept() {
+        let editor = editor_with_history(&["move 1", "help", "move 2"]);
+        let mut buffer = empty_buffer(&editor);
+
+        press_ctrl(&editor, &mut buffer, 'r');
+        type_text(&editor, &mut buffer, "mov");
+        assert_eq!(buffer.search.as_ref().unwrap().match_idx, Some(2));
+        press_ctrl(&editor, &mut buffer, 'r');
+        assert_eq!(buffer.search.as_ref().unwrap().match_idx, Some(0));
+        press_ctrl(&editor, &mut buffer, 'r');
+        assert_eq!(buffer.search.as_ref().unwrap().match_idx, Some(0));
+        assert_eq!(line(&buffer), "");
+
+        press(&editor, &mut buffer, KeyCode::Esc);
+        assert!(buffer.search.is_none());
+        assert_eq!((line(&buffer), buffer.cursor), ("move 1".to_string(), 6));
+
+        // ctrl-g drops the search and leaves the line alone
+        press_ctrl(&editor, &mut buffer, 'r');
+        type_text(&editor, &mut buffer, "help");
+        press_ctrl(&editor, &mut buffer, 'g');
+        assert!(buffer.search.is_none());
+        assert_eq!(line(&buffer), "move 1");
+
+        // enter takes the match and submits it in one press
+        press_ctrl(&editor, &mut buffer, 'r');
+        type_text(&editor, &mut buffer, "helx");
+        press(&editor, &mut buffer, KeyCode::Backspace);
+        assert!(matches!(
+            press(&editor, &mut buffer, KeyCode::Enter),
+            KeyOutcome::Submit
+        ));
+        assert_eq!(line(&buffer), "help");
+    }
+
+    #[test]
+    fn tab_completes_a_single_candidate_and_lists_ambiguous_ones() {
+        let editor = LineEditor::new();
+        let mut buffer = empty_buffer(&editor);
+        type_text(&editor, &mut buffer, "h");
+        match press(&editor, &mut buffer, KeyCode::Tab) {
+            KeyOutcome::ListCompletions(candidates) => {
+                assert_eq!(candidates, ["help", "history"])
+            }
+            _ => panic!("expected the candidates to be listed"),
+        }
+
+        type_text(&editor, &mut buffer, "i");
+        assert!(matches!(
+            press(&editor, &mut buffer, KeyCode::Tab),
+            KeyOutcome::Continue
+        ));
+        assert_eq!((line(&buffer), buffer.cursor), ("history ".to_string(), 8));
+    }
+}
@@ -1,3 +1,5 @@
+mod completion;
+mod line_editor;
 mod session;
 
 use session::Session;
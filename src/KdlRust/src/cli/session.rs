@@ -1,54 +1,271 @@
+use crate::completion::Completer;
+use crate::line_editor::LineEditor;
 use crossterm::{event, terminal};
+use itertools::Itertools;
 use kill_doctor_lucky_rust::core::{
-    board::Board,
+    asset_registry::{self, AssetKind},
+    board::{Board, BoardLoadError},
+    board_dot::DotOptions,
+    board_svg,
+    card_count::CardCount,
     common_game_state::CommonGameState,
-    mutable_game_state::MutableGameState,
-    player::{PieceMove, PlayerId},
+    evaluation::EvaluationWeights,
+    game_preset,
+    move_selector::{Difficulty, MoveSelector},
+    mutable_game_state::{GameResult, MutableGameState},
+    player::{AppraisedPlayerTurn, PieceMove, PlayerId},
     room::RoomId,
+    room_occupancy::RoomOccupancy,
+    rule_config::RuleConfig,
     rule_helper,
+    seat_map::{Side, StrangerCounts, Teams},
+    self_play::SelfPlayRunner,
+    session_stats::SessionStats,
     simple_turn::SimpleTurn,
-    tree_search::TreeSearch,
+    start_rooms::{StartOptions, StartRoomPool},
+    state_diff,
+    transposition_table::TranspositionTable,
+    tree_search::{AspirationWindow, RootTurns, SearchConfig, SearchStats, TreeSearch},
+    variation_tree::{ROOT_NODE_ID, VariationTree},
 };
 use kill_doctor_lucky_rust::util::cancellation::{AtomicCancellationToken, CancellationToken};
-use std::io::{self, Write};
+use kill_doctor_lucky_rust::util::data_dir::{DATA_DIR_FLAG, DataFileKind, DataPaths};
+use kill_doctor_lucky_rust::util::log::{self, ConsoleLogSink, LogLevel};
+use kill_doctor_lucky_rust::util::rng::SplitMix64;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
 };
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub(crate) const TAG_FIDDLE: &str = "f";
+pub(crate) const TAG_QUIT: &str = "q";
+pub(crate) const TAG_DISPLAY: &str = "d";
+pub(crate) const TAG_RESET: &str = "r";
+pub(crate) const TAG_REPEAT: &str = "x";
+pub(crate) const TAG_HISTORY: &str = "h";
+pub(crate) const TAG_COMMAND_HISTORY: &str = "history";
+pub(crate) const TAG_UNDO: &str = "u";
+pub(crate) const TAG_ABORT: &str = "abort";
+pub(crate) const TAG_ANALYZE: &str = "a";
+pub(crate) const TAG_ANALYZE_ASCENDING: &str = "aa";
+pub(crate) const TAG_ANALYZE_JSON: &str = "aj";
+pub(crate) const TAG_ANALYZE_INFINITE: &str = "ai";
+pub(crate) const TAG_EXECUTE_ANALYSIS: &str = "e";
+pub(crate) const TAG_EXECUTE_PREVIOUS_ANALYSIS: &str = "ep";
+pub(crate) const TAG_BOARD: &str = "b";
+pub(crate) const TAG_BOARD_LONG: &str = "board";
+pub(crate) const TAG_BOARD_FILE: &str = "bfile";
+pub(crate) const TAG_BOARD_IMPORT: &str = "bimport";
+pub(crate) const TAG_PLAYERS: &str = "p";
+pub(crate) const TAG_PLAYERS_LONG: &str = "numplayers";
+pub(crate) const TAG_CLOSED_WINGS: &str = "w";
+pub(crate) const TAG_CLOSED_WINGS_LONG: &str = "closedwings";
+pub(crate) const TAG_SET_VALUE: &str = "sv";
+pub(crate) const TAG_SET_VALUE_LONG: &str = "setvalue";
+pub(crate) const TAG_PRESET: &str = "preset";
+pub(crate) const TAG_ASSETS: &str = "assets";
+pub(crate) const TAG_DATA: &str = "data";
+pub(crate) const TAG_DECK: &str = "deck";
+pub(crate) const TAG_STRANGER_LOOPS: &str = "loops";
+pub(crate) const TAG_HEATMAP: &str = "heat";
+pub(crate) const TAG_DOT: &str = "dot";
+pub(crate) const TAG_SVG: &str = "svg";
+pub(crate) const TAG_DIFF: &str = "diff";
+pub(crate) const TAG_STRANGERS: &str = "strangers";
+pub(crate) const TAG_STATS: &str = "stats";
+pub(crate) const TAG_TEAMS: &str = "teams";
+pub(crate) const TAG_WEIGHTS: &str = "weights";
+pub(crate) const TAG_ASPIRATION: &str = "aspiration";
+pub(crate) const TAG_PASS_PRUNING: &str = "passprune";
+pub(crate) const TAG_LATE_MOVE_REDUCTIONS: &str = "lmr";
+pub(crate) const TAG_SEARCH_CACHE: &str = "cache";
+pub(crate) const TAG_VARIATION: &str = "var";
+pub(crate) const TAG_WATCH: &str = "watch";
+pub(crate) const TAG_DIFFICULTY: &str = "difficulty";
+pub(crate) const TAG_LIST_TURNS: &str = "lt";
+pub(crate) const TAG_PICK: &str = "pick";
+
+const WATCH_TURN_DELAY: Duration = Duration::from_millis(700);
+const LOG_LEVEL_FLAG: &str = "--log-level"; // engine messages shown: error, warn (default), info, debug or off
+
+pub(crate) const DIRECTIVE_TAGS: &[&str] = &[
+    TAG_FIDDLE,
+    TAG_QUIT,
+    TAG_DISPLAY,
+    TAG_RESET,
+    TAG_REPEAT,
+    TAG_HISTORY,
+    TAG_COMMAND_HISTORY,
+    TAG_UNDO,
+    TAG_ABORT,
+    TAG_ANALYZE,
+    TAG_ANALYZE_ASCENDING,
+    TAG_ANALYZE_JSON,
+    TAG_ANALYZE_INFINITE,
+    TAG_EXECUTE_ANALYSIS,
+    TAG_EXECUTE_PREVIOUS_ANALYSIS,
+    TAG_BOARD,
+    TAG_BOARD_LONG,
+    TAG_BOARD_FILE,
+    TAG_BOARD_IMPORT,
+    TAG_PLAYERS,
+    TAG_PLAYERS_LONG,
+    TAG_CLOSED_WINGS,
+    TAG_CLOSED_WINGS_LONG,
+    TAG_SET_VALUE,
+    TAG_SET_VALUE_LONG,
+    TAG_PRESET,
+    TAG_ASSETS,
+    TAG_DATA,
+    TAG_DECK,
+    TAG_STRANGER_LOOPS,
+    TAG_HEATMAP,
+    TAG_DOT,
+    TAG_SVG,
+    TAG_DIFF,
+    TAG_STRANGERS,
+    TAG_STATS,
+    TAG_TEAMS,
+    TAG_WEIGHTS,
+    TAG_ASPIRATION,
+    TAG_PASS_PRUNING,
+    TAG_LATE_MOVE_REDUCTIONS,
+    TAG_SEARCH_CACHE,
+    TAG_VARIATION,
+    TAG_WATCH,
+    TAG_DIFFICULTY,
+    TAG_LIST_TURNS,
+    TAG_PICK,
+];
+
+// one line per analyzed level under --json or the aj directive
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisJsonLine {
+    level: i32,
+    best_turn: String,
+    appraisal: f64,
+    appraisal_text: String,
+    pv: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    multi_pv: Vec<RankedTurnJson>, // every ranked turn, best first, when multipv is above 1
+    num_states_visited: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_stats: Option<SearchStats>, // when the level started from an aspiration window
+    elapsed_ms: f64,
+    cancelled: bool,
+}
+
+#[derive(Serialize)]
+struct RankedTurnJson {
+    turn: String,
+    appraisal: f64,
+}
 
 pub struct Session {
     num_normal_players: usize,
     num_normal_players_old: usize,
+    stranger_counts: Option<StrangerCounts>, // None means the standard count for the players
+    teams: Option<Teams>,
     deck_name: String,
     deck_name_old: String,
     board_name: String,
     board_name_old: String,
+    board_path: Option<PathBuf>,
+    board_path_is_text: bool, // board_path is board text (bimport) rather than json (bfile)
+    data_paths: DataPaths,
     closed_wing_names: Vec<String>,
     closed_wing_names_old: Vec<String>,
+    rule_config: RuleConfig,
     game_common: Option<CommonGameState>,
     game: Option<MutableGameState>,
     should_quit: bool,
     analysis_level: f64,
+    analysis_parallelization: i32,
+    multi_pv: usize,
+    json_output: bool,
+    search_config: SearchConfig,
+    weights_path: Option<PathBuf>,
     recent_analyzed_turn: Option<SimpleTurn>,
+    move_selector: Option<MoveSelector>, // when set, e plays at this difficulty instead of best
+    start_options: StartOptions,
+    start_rng: SplitMix64, // draws every reset's start rooms, so each game starts somewhere new
+    listed_turns: Option<(u64, Vec<SimpleTurn>)>, // the position key lt listed for, and its turns
+    session_stats: SessionStats,
+    variations: VariationTree,
+    line_editor: LineEditor,
 }
 
 impl Session {
-    pub fn new(_cli_args: impl IntoIterator<Item = String>) -> Self {
+    pub fn new(cli_args: impl IntoIterator<Item = String>) -> Self {
+        let mut cli_data_dir = None;
+        let mut json_output = false;
+        let mut log_level = Some(LogLevel::Warn);
+        let mut cli_args = cli_args.into_iter();
+        while let Some(arg) = cli_args.next() {
+            if arg == DATA_DIR_FLAG {
+                match cli_args.next() {
+                    Some(dir) => cli_data_dir = Some(PathBuf::from(dir)),
+                    None => println!("{DATA_DIR_FLAG} needs a directory"),
+                }
+            } else if arg == "--json" {
+                json_output = true;
+            } else if arg == LOG_LEVEL_FLAG {
+                match cli_args.next().as_deref() {
+                    Some("off") => log_level = None,
+                    Some(level_text) => match level_text.parse::<LogLevel>() {
+                        Ok(level) => log_level = Some(level),
+                        Err(err) => println!("{err}"),
+                    },
+                    None => println!("{LOG_LEVEL_FLAG} needs a level or off"),
+                }
+            } else {
+                println!("ignoring unrecognized argument '{arg}'");
+            }
+        }
+        match log_level {
+            Some(level) => log::set_log_sink(level, ConsoleLogSink),
+            None => log::clear_log_sink(),
+        }
+
         Self {
             num_normal_players: 2,
             num_normal_players_old: 0,
-            deck_name: "DeckStandard".to_string(),
+            stranger_counts: None,
+            teams: None,
+            deck_name: String::new(),
             deck_name_old: String::new(),
             board_name: "AltDown".to_string(),
             board_name_old: String::new(),
+            board_path: None,
+            board_path_is_text: false,
+            data_paths: DataPaths::from_env(cli_data_dir),
             closed_wing_names: Vec::new(),
             closed_wing_names_old: Vec::new(),
+            rule_config: RuleConfig::default(),
             game_common: None,
             game: None,
             should_quit: false,
             analysis_level: 1.0,
+            analysis_parallelization: 1,
+            multi_pv: 1,
+            json_output,
+            search_config: SearchConfig::default()
+                .with_transposition_table(TranspositionTable::DEFAULT_MAX_ENTRIES),
+            weights_path: None,
             recent_analyzed_turn: None,
+            move_selector: None,
+            start_options: StartOptions::default(),
+            start_rng: SplitMix64::new(clock_seed()),
+            listed_turns: None,
+            session_stats: SessionStats::default(),
+            variations: VariationTree::default(),
+            line_editor: LineEditor::new(),
         }
     }
 
@@ -59,27 +276,78 @@ impl Session {
     }
 
     fn interpretation_loop(&mut self) {
-        let stdin = io::stdin();
-
         loop {
             let prompt = self.user_prompt_text();
-            print!("{prompt}");
-            let _ = io::stdout().flush();
-
-            let mut line = String::new();
-            match stdin.read_line(&mut line) {
-                Ok(0) => return,
-                Ok(_) => {
-                    let line = line.trim_end_matches(['\r', '\n']).to_string();
-                    let sublines = line.split(';').collect::<Vec<_>>();
-                    for subline in sublines {
-                        self.interpret_directive(subline);
-                        if self.should_quit {
-                            return;
-                        }
-                    }
+            let completer = self.completer();
+            let line = match self.line_editor.read_line(&prompt, &completer) {
+                Ok(Some(line)) => line,
+                Ok(None) | Err(_) => return,
+            };
+            let Some(line) = self.expand_history_reference(&line) else {
+                continue;
+            };
+            self.line_editor.add_history(&line);
+
+            let sublines = line.split(';').collect::<Vec<_>>();
+            for subline in sublines {
+                self.interpret_directive(subline);
+                if self.should_quit {
+                    return;
                 }
-                Err(_) => return,
+            }
+        }
+    }
+
+    fn completer(&self) -> Completer {
+        let board = self.game.as_ref().map(|game| &game.common.board);
+        let board_names = Board::embedded_board_names()
+            .chain(Board::generated_board_names())
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let wing_names = board
+            .and_then(|board| board.spec.as_ref())
+            .map(|spec| {
+                spec.wings
+                    .iter()
+                    .map(|wing| wing.name.clone())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let room_words = board
+            .map(|board| {
+                board
+                    .room_ids
+                    .iter()
+                    .map(|room_id| Completer::room_word(&board.rooms[room_id].name))
+                    .filter(|room_word| !room_word.is_empty())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        Completer::new(
+            DIRECTIVE_TAGS.iter().copied(),
+            board_names,
+            wing_names,
+            room_words,
+        )
+    }
+
+    // "!n" re-runs history entry n; the expanded line is what lands in history
+    fn expand_history_reference(&self, line: &str) -> Option<String> {
+        let Some(entry_text) = line.trim().strip_prefix('!') else {
+            return Some(line.to_string());
+        };
+        let entry = entry_text
+            .parse::<usize>()
+            .ok()
+            .and_then(|entry_num| self.line_editor.history_entry(entry_num));
+        match entry {
+            Some(entry) => {
+                println!("{entry}");
+                Some(entry.to_string())
+            }
+            None => {
+                println!("  no history entry '{entry_text}' (see history directive)");
+                None
             }
         }
     }
@@ -99,26 +367,6 @@ impl Session {
     }
 
     fn interpret_directive(&mut self, directive: &str) {
-        const TAG_FIDDLE: &str = "f";
-        const TAG_QUIT: &str = "q";
-        const TAG_DISPLAY: &str = "d";
-        const TAG_RESET: &str = "r";
-        const TAG_REPEAT: &str = "x";
-        const TAG_HISTORY: &str = "h";
-        const TAG_UNDO: &str = "u";
-        const TAG_ANALYZE: &str = "a";
-        const TAG_ANALYZE_ASCENDING: &str = "aa";
-        const TAG_EXECUTE_ANALYSIS: &str = "e";
-        const TAG_EXECUTE_PREVIOUS_ANALYSIS: &str = "ep";
-        const TAG_BOARD: &str = "b";
-        const TAG_BOARD_LONG: &str = "board";
-        const TAG_PLAYERS: &str = "p";
-        const TAG_PLAYERS_LONG: &str = "numplayers";
-        const TAG_CLOSED_WINGS: &str = "w";
-        const TAG_CLOSED_WINGS_LONG: &str = "closedwings";
-        const TAG_SET_VALUE: &str = "sv";
-        const TAG_SET_VALUE_LONG: &str = "setvalue";
-
         let directive = self.without_comments(directive);
         let tokens = directive
             .split_whitespace()
@@ -143,10 +391,42 @@ impl Session {
                 println!("{}", game.summary(1));
             }
         } else if directive_tag == TAG_RESET {
+            if tokens.len() > 1 {
+                match parse_start_options(&tokens[1..]) {
+                    Ok((start_options, seed)) => {
+                        self.start_options = start_options;
+                        if let Some(seed) = seed {
+                            self.start_rng = SplitMix64::new(seed);
+                        }
+                    }
+                    Err(message) => {
+                        println!("  {message}");
+                        return;
+                    }
+                }
+            }
             println!("(RESET)");
             self.reset_game();
+        } else if directive_tag == TAG_ABORT {
+            if let Some(game) = self.game.as_mut() {
+                if game.abort() {
+                    println!("(ABORT)");
+                } else {
+                    println!("  game is already over");
+                }
+            }
         } else if directive_tag == TAG_UNDO {
             println!("(UNDO)");
+            // an abort is taken back before any turn
+            if let Some(game) = self.game.as_mut()
+                && game.resume()
+            {
+                println!("{}", game.summary(1));
+                return;
+            }
+            if let Some(game) = self.game.as_ref().filter(|game| game.is_game_over()) {
+                self.session_stats.forget_game(game);
+            }
             loop {
                 let prev_state = self
                     .game
@@ -167,6 +447,7 @@ impl Session {
                 }
             }
 
+            self.sync_variations();
             if let Some(game) = self.game.as_ref() {
                 println!("{}", game.summary(1));
             }
@@ -191,6 +472,25 @@ impl Session {
             }
         } else if directive_tag == TAG_HISTORY {
             println!("{TAG_PLAYERS} {};", self.num_normal_players_old);
+            if let Some(common) = self.game_common.as_ref()
+                && common.stranger_counts != StrangerCounts::default_for(common.num_normal_players)
+            {
+                println!(
+                    "{TAG_STRANGERS} {} {};",
+                    common.stranger_counts.side_a, common.stranger_counts.side_b
+                );
+            }
+            if let Some(teams) = self.game_common.as_ref().and_then(|common| common.teams) {
+                let side_a_nums = teams
+                    .players_of(Side::A)
+                    .iter()
+                    .map(|player_id| CommonGameState::to_player_display_num(*player_id).to_string())
+                    .collect::<Vec<_>>();
+                println!("{TAG_TEAMS} {};", side_a_nums.join(" "));
+            }
+            if !self.deck_name_old.is_empty() {
+                println!("{TAG_DECK} {};", self.deck_name_old);
+            }
             println!("{TAG_BOARD} {};", self.board_name_old);
             println!(
                 "{TAG_CLOSED_WINGS} {};",
@@ -202,32 +502,102 @@ impl Session {
             if let Some(game) = self.game.as_ref() {
                 println!("{}", game.normal_turn_hist());
             }
+        } else if directive_tag == TAG_COMMAND_HISTORY {
+            for (idx, line) in self.line_editor.history().iter().enumerate() {
+                println!("  {:>4}  {line}", idx + 1);
+            }
         } else if directive_tag == TAG_ANALYZE
             || directive_tag == TAG_ANALYZE_ASCENDING
+            || directive_tag == TAG_ANALYZE_JSON
+            || directive_tag == TAG_ANALYZE_INFINITE
             || directive_tag == TAG_EXECUTE_ANALYSIS
         {
+            let (multi_pv_tokens, tokens) = tokens
+                .into_iter()
+                .partition::<Vec<_>, _>(|token| token.to_lowercase().starts_with("multipv="));
+            for token in multi_pv_tokens {
+                match token["multipv=".len()..].parse::<usize>() {
+                    Ok(multi_pv) => self.multi_pv = multi_pv.max(1),
+                    Err(_) => println!("  {token} needs a whole number of turns"),
+                }
+            }
+            let (root_turn_tokens, tokens) = tokens.into_iter().partition::<Vec<_>, _>(|token| {
+                let token = token.to_lowercase();
+                token.starts_with("only=") || token.starts_with("skip=")
+            });
+            let root_turns = if root_turn_tokens.is_empty() {
+                None
+            } else {
+                match self.root_turns_from_tokens(&root_turn_tokens) {
+                    Ok(root_turns) => Some(root_turns),
+                    Err(err) => {
+                        println!("  {err}");
+                        return;
+                    }
+                }
+            };
             if let Some(token) = tokens.get(1) {
                 if let Ok(level) = token.parse::<f64>() {
                     self.analysis_level = level;
                 }
             }
+            if let Some(token) = tokens.get(2) {
+                if let Ok(parallelization) = token.parse::<i32>() {
+                    self.analysis_parallelization = parallelization.max(1);
+                }
+            }
 
             let do_suggested_move = directive_tag == TAG_EXECUTE_ANALYSIS;
+            if do_suggested_move && let Some(mut selector) = self.move_selector.take() {
+                self.search_config.root_turns = root_turns;
+                self.play_selected_turn(&mut selector);
+                self.search_config.root_turns = None;
+                self.move_selector = Some(selector);
+                return;
+            }
+            let as_json = self.json_output || directive_tag == TAG_ANALYZE_JSON;
 
-            let start_level = if directive_tag == TAG_ANALYZE_ASCENDING {
+            let is_infinite = directive_tag == TAG_ANALYZE_INFINITE;
+            let start_level = if directive_tag == TAG_ANALYZE_ASCENDING || is_infinite {
                 1
             } else {
                 self.analysis_level as i32
             };
+            if is_infinite && !io::stdin().is_terminal() {
+                println!("  {TAG_ANALYZE_INFINITE} needs an interactive terminal to stop it");
+                return;
+            }
+            if is_infinite && self.game.as_ref().is_some_and(|game| game.is_game_over()) {
+                println!("  game is over; nothing to analyze");
+                return;
+            }
+            if is_infinite {
+                println!("(ANALYZE until a key is pressed)");
+            }
 
+            // only for this analysis; the next one searches every turn again
+            self.search_config.root_turns = root_turns;
             let mut level = start_level;
-            while (level as f64) <= self.analysis_level {
-                let cancelled = self.analyze(do_suggested_move, level, 1);
-                if cancelled {
+            let mut prev_appraisal = None;
+            while is_infinite || (level as f64) <= self.analysis_level {
+                // a shallow level can finish before its key listener starts, so a key pressed
+                // between levels is checked here too
+                if is_infinite && level > start_level && key_was_pressed() {
                     break;
                 }
+                let Some(appraisal) = self.analyze(
+                    do_suggested_move,
+                    level,
+                    self.analysis_parallelization,
+                    as_json,
+                    prev_appraisal,
+                ) else {
+                    break;
+                };
+                prev_appraisal = Some(appraisal);
                 level += 1;
             }
+            self.search_config.root_turns = None;
         } else if directive_tag == "m" {
             println!("mcts analysis is not supported");
         } else if directive_tag == TAG_EXECUTE_PREVIOUS_ANALYSIS {
@@ -244,9 +614,34 @@ impl Session {
                 if !self.board_name.to_lowercase().contains("board") {
                     self.board_name = format!("Board{}", self.board_name);
                 }
+                self.board_path = None;
             }
 
             self.print_game_settings();
+        } else if directive_tag == TAG_BOARD_FILE {
+            if tokens.len() != 2 {
+                println!("  {TAG_BOARD_FILE} directive needs a board json path");
+            } else {
+                let board_path = self
+                    .data_paths
+                    .resolve_file(&tokens[1], DataFileKind::Board);
+                println!("(BOARD FILE {})", board_path.display());
+                self.board_path = Some(board_path);
+                self.board_path_is_text = false;
+                self.reset_game();
+            }
+        } else if directive_tag == TAG_BOARD_IMPORT {
+            if tokens.len() != 2 {
+                println!("  {TAG_BOARD_IMPORT} directive needs a board text path");
+            } else {
+                let board_path = self
+                    .data_paths
+                    .resolve_file(&tokens[1], DataFileKind::Board);
+                println!("(BOARD IMPORT {})", board_path.display());
+                self.board_path = Some(board_path);
+                self.board_path_is_text = true;
+                self.reset_game();
+            }
         } else if directive_tag == TAG_CLOSED_WINGS || directive_tag == TAG_CLOSED_WINGS_LONG {
             self.closed_wing_names = tokens.iter().skip(1).cloned().collect::<Vec<_>>();
             self.print_game_settings();
@@ -260,9 +655,322 @@ impl Session {
             }
 
             self.print_game_settings();
+        } else if directive_tag == TAG_STRANGERS {
+            match tokens[1..]
+                .iter()
+                .map(|token| token.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .as_deref()
+            {
+                Ok([]) => self.stranger_counts = None,
+                Ok([side_a, side_b]) => match StrangerCounts::new(*side_a, *side_b) {
+                    Ok(stranger_counts) => self.stranger_counts = Some(stranger_counts),
+                    Err(message) => println!("  {message}"),
+                },
+                _ => println!("  {TAG_STRANGERS} directive needs no tokens or two integer tokens"),
+            }
+
+            self.print_game_settings();
+        } else if directive_tag == TAG_STATS {
+            match tokens.get(1).map(|token| token.as_str()) {
+                None => println!("  {}", self.session_stats),
+                Some("reset") => {
+                    self.session_stats = SessionStats::default();
+                    println!("  {}", self.session_stats);
+                }
+                Some(_) => println!("  {TAG_STATS} directive needs no tokens or 'reset'"),
+            }
+        } else if directive_tag == TAG_TEAMS {
+            match tokens[1..]
+                .iter()
+                .map(|token| token.parse::<usize>())
+                .collect::<Result<Vec<_>, _>>()
+                .as_deref()
+            {
+                Ok([]) => self.teams = None,
+                Ok([first, second]) if *first > 0 && *second > 0 => {
+                    match Teams::with_side_a([
+                        CommonGameState::to_player_id(*first),
+                        CommonGameState::to_player_id(*second),
+                    ]) {
+                        Ok(teams) => self.teams = Some(teams),
+                        Err(message) => println!("  {message}"),
+                    }
+                }
+                _ => println!("  {TAG_TEAMS} directive needs no tokens or two player numbers"),
+            }
+
+            self.print_game_settings();
+        } else if directive_tag == TAG_PRESET {
+            match tokens.get(1) {
+                None => {
+                    for preset in game_preset::game_presets() {
+                        println!("  {:<16} {}", preset.name, preset.description);
+                    }
+                }
+                Some(preset_name) => match game_preset::find_game_preset(preset_name) {
+                    Some(preset) => {
+                        println!("(PRESET {})", preset.name);
+                        self.board_name = preset.board_name.to_string();
+                        self.board_path = None;
+                        self.num_normal_players = preset.num_normal_players;
+                        self.stranger_counts = preset.stranger_counts;
+                        self.teams = preset.teams;
+                        self.rule_config = preset.rule_config;
+                        self.deck_name = String::new();
+                        self.reset_game();
+                    }
+                    None => println!("  unknown preset '{preset_name}'"),
+                },
+            }
+        } else if directive_tag == TAG_ASSETS {
+            let kind = match tokens.get(1) {
+                None => None,
+                Some(kind_text) => match AssetKind::parse(kind_text) {
+                    Some(kind) => Some(kind),
+                    None => {
+                        println!(
+                            "  {TAG_ASSETS} directive kind must be board, puzzle, preset or deck"
+                        );
+                        return;
+                    }
+                },
+            };
+            for asset in asset_registry::list_assets(kind) {
+                println!(
+                    "  {:<7} {:<16} {} ({}-{} players): {}",
+                    asset.kind.as_str(),
+                    asset.name,
+                    asset.display_name,
+                    asset.min_players,
+                    asset.max_players,
+                    asset.description
+                );
+            }
+        } else if directive_tag == TAG_DATA {
+            println!("  data dir: {}", self.data_paths);
+            for (label, kind) in [
+                ("boards", DataFileKind::Board),
+                ("decks", DataFileKind::Deck),
+            ] {
+                println!("  {label}: {}", self.data_paths.list_names(kind).join(", "));
+            }
+        } else if directive_tag == TAG_DECK {
+            match tokens.get(1).map(|token| token.as_str()) {
+                None => {
+                    let data_deck_names = self.data_paths.list_names(DataFileKind::Deck);
+                    let deck_names = asset_registry::list_assets(Some(AssetKind::Deck))
+                        .into_iter()
+                        .map(|asset| asset.name.to_string())
+                        .chain(data_deck_names)
+                        .unique()
+                        .collect::<Vec<_>>();
+                    println!("  decks: {}", deck_names.join(", "));
+                    self.print_game_settings();
+                }
+                Some("none") => {
+                    self.deck_name = String::new();
+                    self.rule_config = self
+                        .rule_config
+                        .clone()
+                        .with_starting_cards(RuleConfig::default().starting_cards());
+                    self.reset_game();
+                }
+                Some(deck_name) => match self.data_paths.load_deck(deck_name) {
+                    Ok(deck) => {
+                        println!("(DECK {})", deck.name);
+                        self.rule_config = self.rule_config.clone().with_deck(&deck);
+                        self.deck_name = deck.name;
+                        self.reset_game();
+                    }
+                    Err(message) => println!("  {message}"),
+                },
+            }
+        } else if directive_tag == TAG_STRANGER_LOOPS {
+            self.print_stranger_loops();
+        } else if directive_tag == TAG_HEATMAP {
+            if let Some(game) = self.game.as_ref() {
+                for line in RoomOccupancy::from_state(game).table_text(game).lines() {
+                    println!("  {line}");
+                }
+            }
+        } else if directive_tag == TAG_DOT {
+            self.write_board_dot(&tokens[1..]);
+        } else if directive_tag == TAG_SVG {
+            if let Some(game) = self.game.as_ref() {
+                let svg_path = tokens.get(1).map(PathBuf::from).unwrap_or_else(|| {
+                    PathBuf::from(format!(
+                        "{}-turn{}.svg",
+                        game.common.board.name, game.turn_id
+                    ))
+                });
+                match fs::write(&svg_path, board_svg::render_svg(game)) {
+                    Ok(()) => println!("(SVG {})", svg_path.display()),
+                    Err(err) => println!("  failed to write '{}': {err}", svg_path.display()),
+                }
+            }
+        } else if directive_tag == TAG_DIFF {
+            self.print_state_diff(&tokens[1..]);
+        } else if directive_tag == TAG_WEIGHTS {
+            match tokens.get(1) {
+                None => {
+                    self.search_config.evaluation_weights = None;
+                    self.weights_path = None;
+                    self.clear_search_cache();
+                }
+                Some(weights_path) => {
+                    let weights_path = PathBuf::from(weights_path);
+                    match fs::read_to_string(&weights_path)
+                        .map_err(|err| {
+                            format!("failed to read '{}': {err}", weights_path.display())
+                        })
+                        .and_then(|json| EvaluationWeights::from_json(&json))
+                    {
+                        Ok(evaluation_weights) => {
+                            self.search_config.evaluation_weights = Some(evaluation_weights);
+                            self.weights_path = Some(weights_path);
+                            self.clear_search_cache();
+                        }
+                        Err(err) => println!("  {err}"),
+                    }
+                }
+            }
+            self.print_game_settings();
+        } else if directive_tag == TAG_ASPIRATION {
+            let numbers = tokens[1..]
+                .iter()
+                .map(|token| token.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>();
+            match (
+                tokens.get(1).map(|token| token.as_str()),
+                numbers.as_deref(),
+            ) {
+                (None, _) => {
+                    self.search_config.aspiration_window = Some(AspirationWindow::default());
+                }
+                (Some("off"), _) => self.search_config.aspiration_window = None,
+                (_, Ok([width])) => self.set_aspiration_window(*width, None),
+                (_, Ok([width, growth_factor])) => {
+                    self.set_aspiration_window(*width, Some(*growth_factor));
+                }
+                _ => println!(
+                    "  {TAG_ASPIRATION} directive needs 'off', no tokens, or a width and optional growth factor"
+                ),
+            }
+            self.print_game_settings();
+        } else if directive_tag == TAG_LATE_MOVE_REDUCTIONS {
+            match tokens.get(1).map(|token| token.parse::<bool>()) {
+                None => self.search_config.late_move_reductions = true,
+                Some(Ok(enabled)) => self.search_config.late_move_reductions = enabled,
+                Some(Err(_)) => {
+                    println!("  {TAG_LATE_MOVE_REDUCTIONS} directive needs true, false or nothing");
+                }
+            }
+            self.print_game_settings();
+        } else if directive_tag == TAG_PASS_PRUNING {
+            match tokens.get(1).map(|token| token.parse::<bool>()) {
+                None => self.search_config.pass_move_pruning = true,
+                Some(Ok(enabled)) => self.search_config.pass_move_pruning = enabled,
+                Some(Err(_)) => {
+                    println!("  {TAG_PASS_PRUNING} directive needs true, false or nothing")
+                }
+            }
+            self.print_game_settings();
+        } else if directive_tag == TAG_SEARCH_CACHE {
+            match tokens.get(1).map(|token| token.as_str()) {
+                None => {}
+                Some("clear") => self.clear_search_cache(),
+                Some("off") => self.search_config.transposition_table = None,
+                Some(token) => match token.parse::<usize>() {
+                    Ok(max_entries) if max_entries > 0 => {
+                        self.search_config = self
+                            .search_config
+                            .clone()
+                            .with_transposition_table(max_entries);
+                    }
+                    _ => println!(
+                        "  {TAG_SEARCH_CACHE} directive needs a positive entry count, clear, off or nothing"
+                    ),
+                },
+            }
+            self.print_game_settings();
+        } else if directive_tag == TAG_WATCH {
+            let engine_at = |idx: usize| {
+                let token = tokens.get(idx)?;
+                match token.parse::<i32>() {
+                    Ok(level) => Some(WatchEngine::Level(level.max(1))),
+                    Err(_) => token
+                        .parse::<Difficulty>()
+                        .ok()
+                        .map(|difficulty| WatchEngine::Selector(new_move_selector(difficulty))),
+                }
+            };
+            let delay = match tokens.get(3) {
+                Some(token) => token.parse::<u64>().ok().map(Duration::from_millis),
+                None => Some(WATCH_TURN_DELAY),
+            };
+            match (engine_at(1), engine_at(2), delay) {
+                (Some(engine_a), Some(engine_b), Some(delay)) if tokens.len() <= 4 => {
+                    self.watch(engine_a, engine_b, delay)
+                }
+                _ => println!(
+                    "  {TAG_WATCH} directive needs side A's and side B's analysis levels or difficulties and an optional delay in ms"
+                ),
+            }
+        } else if directive_tag == TAG_DIFFICULTY {
+            match tokens.get(1).map(|token| token.to_lowercase()).as_deref() {
+                None => match self.move_selector.as_ref() {
+                    Some(selector) => match selector.game_seed {
+                        Some(game_seed) => {
+                            println!("  difficulty {} seed {game_seed}", selector.difficulty)
+                        }
+                        None => println!("  difficulty {}", selector.difficulty),
+                    },
+                    None => println!("  difficulty off"),
+                },
+                Some("off") => {
+                    self.move_selector = None;
+                    println!("(DIFFICULTY off)");
+                }
+                Some(name) => match (
+                    name.parse::<Difficulty>(),
+                    tokens.get(2).map(|token| token.parse::<u64>()),
+                ) {
+                    (Ok(difficulty), None) => {
+                        self.move_selector = Some(new_move_selector(difficulty));
+                        println!("(DIFFICULTY {difficulty})");
+                    }
+                    (Ok(difficulty), Some(Ok(game_seed))) => {
+                        self.move_selector = Some(
+                            MoveSelector::new(difficulty, game_seed).with_game_seed(game_seed),
+                        );
+                        println!("(DIFFICULTY {difficulty} seed {game_seed})");
+                    }
+                    (Ok(_), Some(Err(_))) => {
+                        println!("  {TAG_DIFFICULTY} seed needs a whole number")
+                    }
+                    (Err(message), _) => println!("  {message}"),
+                },
+            }
+        } else if directive_tag == TAG_LIST_TURNS {
+            match tokens.get(1).map(|token| token.parse::<usize>()) {
+                None => self.list_turns(None),
+                Some(Ok(limit)) if limit > 0 && tokens.len() == 2 => self.list_turns(Some(limit)),
+                _ => {
+                    println!("  {TAG_LIST_TURNS} directive needs a positive turn count or nothing")
+                }
+            }
+        } else if directive_tag == TAG_PICK {
+            match tokens.get(1).map(|token| token.parse::<usize>()) {
+                Some(Ok(turn_num)) if tokens.len() == 2 => self.pick_listed_turn(turn_num),
+                _ => println!("  {TAG_PICK} directive needs the number {TAG_LIST_TURNS} showed"),
+            }
+        } else if directive_tag == TAG_VARIATION {
+            self.handle_variation(&tokens);
         } else if directive_tag == TAG_SET_VALUE || directive_tag == TAG_SET_VALUE_LONG {
             self.handle_set_value(&tokens);
         } else if directive_tag
+            .trim_start_matches('p')
             .chars()
             .next()
             .map(|ch| ch.is_ascii_digit())
@@ -271,23 +979,51 @@ impl Session {
             self.do_moves_tokens(&tokens);
         } else {
             let mut explanations = vec![
-                "a [int] | analyze next move [int] deep",
-                "aa [int] | analyze levels 1..[int]",
-                "b/board [boardName] | set board (prefixes Board if missing)",
+                "a [int] [threads] [multipv=k] [only=turns] [skip=turns] | analyze next move [int] deep (multipv=k also lists the top k turns; only=1@5,3@7;1@6 and skip= limit the turns considered)",
+                "aa [int] [threads] | analyze levels 1..[int]",
+                "abort   | end the game without a winner (u resumes play from the previous turn)",
+                "assets [kind] | list bundled boards, puzzles, presets and decks (kind narrows to one)",
+                "aspiration [width] [growth] | deepening analysis (aa, ai) starts each level in a window around the last appraisal; off turns it off",
+                "ai [_] [threads] | analyze levels 1, 2, ... until a key (or ctrl-c) is pressed",
+                "aj [int] [threads] | analyze, printing a json line with turn, appraisal and pv (--json does this for all analysis)",
+                "cache [entries|clear|off] | search results kept between turns so analysis after a reply starts warm (no token shows its use)",
+                "b/board [boardName] | set board (prefixes Board if missing; RingN, GridWxH, GridWxHLine generate one)",
+                "bfile [path] | load board json from path (or --data-dir) and reset",
+                "bimport [path] | load a tab-separated board text file (name, player/doctor/cat/dog start ids, room id name adjacent visible [wings] lines) and reset",
                 "closedwings/w [wing1] [wing2] [...] | set closed wings",
                 "d       | display game state",
-                "e [int] | analyze then execute suggested move",
+                "svg [path] | write a picture of the board, pieces and the doctor's next rooms (default <board>-turn<n>.svg)",
+                "diff [plyA] [plyB] | what changed from one ply to another: doctor, pieces, cards, strength and attacks (plyB defaults to now)",
+                "dot [path] [sight] [nowings] [nostarts] | write the board as a graphviz file (default <board>.dot): adjacency edges, wing boxes, start rooms, and sight as dashed edges when asked",
+                "deck [name|none] | start with a deck's average six-card hand (data dir first, then bundled) and reset; none restores the simple rules' cards; no name lists decks",
+                "data    | data dir (--data-dir, else KDL_DATA_DIR) and the boards and decks found in it",
+                "e [int] [threads] | analyze then execute suggested move",
                 "ep      | execute last analyzed move",
                 "f       | fiddle (dev hook)",
                 "h [bool] | display user-turn history",
+                "heat    | plies each piece started in each room this game",
+                "history | list entered commands (!n re-runs entry n; up/down recall, ctrl-r searches)",
+                "loops   | stranger loop rooms and counters for current player",
+                "lt [n]  | list legal turns, best first by the appraisal right after each (n keeps the top n), numbered for pick",
+                "lmr [bool] | search clearly worse late turns a level shallower, re-searching any that still look good",
                 "m       | mcts analysis (not supported)",
+                "passprune [bool] | cut search nodes where staying put already beats the opponent's best (a guess, like a null move)",
                 "numplayers/p [int] | set number of normal players",
+                "pick [n] | play turn n from the last lt listing",
+                "preset [name] | list presets, or apply one and reset",
                 "q       | quit",
-                "r       | reset game",
+                "r [board|candidates|any] [offset=n] [seed=n] | reset game; a pool picks start rooms at random (candidates from the board's start lists), offset=n starts the doctor n rooms past the players",
+                "stats [reset] | games finished this session: wins, average turns, attacks and loots",
+                "teams [p] [p] | with 4 players, put those two player numbers on one team against the other two; no ints plays every player for themselves",
+                "strangers [a] [b] | strangers helping P1 and its opponent, 0 to 2 each; no ints restores one each",
                 "sv/setvalue playerNum attributeName attributeValue | set r/s/m/w/f/t",
                 "u       | undo to previous normal turn",
+                "var [id|new id moves|del id] | list the lines played since reset (* current), switch to a node's line, play moves from a node as a new variation, or delete a node's subtree",
+                "watch [levelA] [levelB] [ms] | engines play out the game from here, side A (P1's) at levelA and side B at levelB (a level may be easy, medium or hard), printing each turn; a key stops it",
+                "difficulty [easy|medium|hard|off] [seed] | e plays a weaker engine's turn: a shallower search and a random pick among its best turns; with a seed, the same turn always gets the same pick (no token shows the setting)",
+                "weights [path] | evaluate with tuned weights json (no path: built-in heuristic)",
                 "x [n] [cmd] | repeat [cmd] n times",
-                "[playerNum@destRoomId] [destRoomIdForCurrentPlayer] submit turn of those moves",
+                "[playerNum@destRoomId] [destRoomIdForCurrentPlayer] submit turn of those moves (p2@parlor and unique name prefixes work too; tab completes)",
             ];
             explanations.sort();
             println!("  unrecognized directive '{directive}'");
@@ -299,62 +1035,202 @@ impl Session {
 
     fn fiddle(&mut self, _tokens: Option<&[String]>) {}
 
+    fn set_aspiration_window(&mut self, width: f64, growth_factor: Option<f64>) {
+        let growth_factor =
+            growth_factor.unwrap_or_else(|| AspirationWindow::default().growth_factor);
+        match AspirationWindow::new(width, growth_factor) {
+            Ok(window) => self.search_config.aspiration_window = Some(window),
+            Err(message) => println!("  {message}"),
+        }
+    }
+
+    fn clear_search_cache(&self) {
+        if let Some(table) = self.search_config.transposition_table.as_ref()
+            && let Ok(mut table) = table.lock()
+        {
+            table.clear();
+        }
+    }
+
     fn print_game_settings(&self) {
         println!("  NormalPlayers(p): {}", self.num_normal_players);
+        println!(
+            "  Strangers:        {}",
+            self.stranger_counts
+                .unwrap_or_else(|| StrangerCounts::default_for(self.num_normal_players))
+        );
+        if let Some(teams) = self.teams {
+            println!("  Teams(teams):     {teams}");
+        }
         println!("  Board(b):         {}", self.board_name);
+        if let Some(board_path) = self.board_path.as_ref() {
+            let tag = if self.board_path_is_text {
+                TAG_BOARD_IMPORT
+            } else {
+                TAG_BOARD_FILE
+            };
+            println!("  BoardFile({tag}): {}", board_path.display());
+        }
+        if self.data_paths.dir.is_some() {
+            println!("  DataDir(data):    {}", self.data_paths);
+        }
         println!("  ClosedWings(w):   {}", self.closed_wing_names.join(", "));
+        if !self.start_options.is_board_default() {
+            println!("  StartRooms(r):    {}", self.start_options);
+        }
+        if !self.deck_name.is_empty() {
+            println!("  Deck(deck):       {}", self.deck_name);
+        }
+        if self.rule_config != RuleConfig::default() {
+            println!("  Rules:            {:?}", self.rule_config);
+        }
         println!("  AnalysisLevel(a): {}", self.analysis_level);
+        println!("  AnalysisThreads:  {}", self.analysis_parallelization);
+        if let Some(weights_path) = self.weights_path.as_ref() {
+            println!("  Weights(weights): {}", weights_path.display());
+        }
+        if self.search_config.pass_move_pruning {
+            println!("  PassPruning:      on");
+        }
+        if self.search_config.late_move_reductions {
+            println!("  LateMoveRed(lmr): on");
+        }
+        match self.search_config.transposition_table.as_ref() {
+            Some(table) => {
+                if let Ok(table) = table.lock() {
+                    let stats = table.stats();
+                    println!(
+                        "  SearchCache:      {}/{} entries, {}/{} probes hit",
+                        stats.num_entries, stats.max_entries, stats.num_hits, stats.num_probes
+                    );
+                }
+            }
+            None => println!("  SearchCache:      off"),
+        }
+        if let Some(window) = self.search_config.aspiration_window {
+            println!(
+                "  Aspiration:       +-{} growing x{} (full window past {})",
+                window.initial_width, window.growth_factor, window.max_width
+            );
+        }
+    }
+
+    fn print_stranger_loops(&self) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+
+        let threats = game.stranger_loop_threats(game.current_player_id);
+        if threats.is_empty() {
+            println!("  no stranger loops");
+            return;
+        }
+
+        for threat in threats {
+            let counter_texts = threat
+                .counter_room_ids
+                .iter()
+                .map(|room_id| format!("R{}", room_id.0))
+                .collect::<Vec<_>>();
+            println!(
+                "  R{} counters={{{}}}{}{}",
+                threat.enemy_room_id.0,
+                counter_texts.join(","),
+                if threat.opposing_stranger_is_here {
+                    " OPPONENT-STRANGER-HERE"
+                } else {
+                    ""
+                },
+                if threat.is_countered {
+                    " COUNTERED"
+                } else {
+                    ""
+                }
+            );
+        }
     }
 
+    // only=1@5,3@7;1@6 searches just those turns (moves joined by commas, turns by semicolons);
+    // skip= takes the same form and searches everything else
+    fn root_turns_from_tokens(&self, tokens: &[String]) -> Result<RootTurns, String> {
+        let game = self.game.as_ref().ok_or("no game to analyze")?;
+        let mut only = Vec::new();
+        let mut skipped = Vec::new();
+        for token in tokens {
+            let (name, value) = token.split_once('=').unwrap_or((token, ""));
+            let turns = value
+                .split(';')
+                .filter(|turn_text| !turn_text.trim().is_empty())
+                .map(|turn_text| turn_text.replace(',', " ").parse::<SimpleTurn>())
+                .collect::<Result<Vec<_>, String>>()?;
+            if turns.is_empty() {
+                return Err(format!("{token} needs at least one turn, like {name}=1@5;"));
+            }
+            if name.eq_ignore_ascii_case("only") {
+                only.extend(turns);
+            } else {
+                skipped.extend(turns);
+            }
+        }
+        RootTurns::new(game, only, skipped)
+    }
+
+    // the appraisal, or None when cancelled; prev_appraisal is the level below's, when deepening
     fn analyze(
         &mut self,
         do_suggested_move: bool,
         analysis_level: i32,
-        _parallelization: i32,
-    ) -> bool {
-        let Some(game) = self.game.as_ref() else {
-            return false;
-        };
+        parallelization: i32,
+        as_json: bool,
+        prev_appraisal: Option<f64>,
+    ) -> Option<f64> {
+        let game = self.game.as_ref()?;
 
         let cancel_token = Arc::new(AtomicCancellationToken::new());
         let listener_done = Arc::new(AtomicBool::new(false));
-        let listener_cancel = cancel_token.clone();
-        let listener_done_flag = listener_done.clone();
-        let key_listener = std::thread::spawn(move || {
-            let raw_mode_enabled = terminal::enable_raw_mode().is_ok();
-            loop {
-                let poll_result = event::poll(Duration::from_millis(0));
-                let Ok(has_event) = poll_result else {
-                    break;
-                };
-                if !has_event {
-                    break;
-                }
-                let _ = event::read();
-            }
-            while !listener_done_flag.load(Ordering::SeqCst) {
-                let poll_result = event::poll(Duration::from_millis(50));
-                let Ok(has_event) = poll_result else {
-                    break;
-                };
-                if has_event && matches!(event::read(), Ok(event::Event::Key(_))) {
-                    listener_cancel.cancel();
-                    break;
-                }
-            }
-            if raw_mode_enabled {
-                let _ = terminal::disable_raw_mode();
-            }
-        });
+        let key_listener = spawn_key_listener(cancel_token.clone(), listener_done.clone());
 
         let mut num_states_visited = 0usize;
+        let mut search_stats = None;
         let watch = Instant::now();
-        let appraised_turn = TreeSearch::find_best_turn(
-            game,
-            analysis_level,
-            cancel_token.as_ref(),
-            &mut num_states_visited,
-        );
+        let mut ranked_turns = if self.multi_pv > 1 {
+            TreeSearch::find_best_turns_with_config(
+                game,
+                analysis_level,
+                self.multi_pv,
+                &self.search_config,
+                cancel_token.as_ref(),
+                &mut num_states_visited,
+            )
+        } else if self.search_config.aspiration_window.is_some()
+            && prev_appraisal.is_some()
+            && parallelization <= 1
+        {
+            let mut stats = SearchStats::default();
+            let best_turn = TreeSearch::find_best_turn_deepening(
+                game,
+                analysis_level,
+                prev_appraisal,
+                &self.search_config,
+                cancel_token.as_ref(),
+                &mut stats,
+            );
+            num_states_visited = stats.num_states_visited;
+            search_stats = stats.used_aspiration_window.then_some(stats);
+            vec![best_turn]
+        } else {
+            vec![self.search_best_turn(
+                game,
+                analysis_level,
+                parallelization,
+                cancel_token.as_ref(),
+                &mut num_states_visited,
+            )]
+        };
+        if ranked_turns.is_empty() {
+            ranked_turns.push(AppraisedPlayerTurn::empty_minimum());
+        }
+        let appraised_turn = &ranked_turns[0];
         let elapsed = watch.elapsed();
 
         listener_done.store(true, Ordering::SeqCst);
@@ -370,13 +1246,7 @@ impl Session {
             self.recent_analyzed_turn = Some(turn.clone());
         }
 
-        let score_text = if appraised_turn.appraisal == rule_helper::HEURISTIC_SCORE_WIN {
-            "WIN".to_string()
-        } else if appraised_turn.appraisal == rule_helper::HEURISTIC_SCORE_LOSS {
-            "LOSE".to_string()
-        } else {
-            format!("{:+0.4}", appraised_turn.appraisal)
-        };
+        let score_text = rule_helper::heuristic_score_text(appraised_turn.appraisal);
 
         let best_turn_text = if was_cancelled {
             String::new()
@@ -384,32 +1254,253 @@ impl Session {
             turn.to_string()
         };
 
-        println!(
-            "bestTurn={:<10} level={} appraisal={} states={} timeSec={:.2}",
-            best_turn_text,
-            analysis_level,
-            score_text,
-            num_states_visited,
-            elapsed.as_secs_f64()
-        );
+        if as_json {
+            let pv = if was_cancelled {
+                Vec::new()
+            } else {
+                TreeSearch::principal_variation(
+                    game,
+                    turn.clone(),
+                    analysis_level,
+                    &self.search_config,
+                    cancel_token.as_ref(),
+                )
+                .iter()
+                .map(|pv_turn| pv_turn.to_string())
+                .collect::<Vec<_>>()
+            };
+            let json_line = AnalysisJsonLine {
+                level: analysis_level,
+                best_turn: best_turn_text,
+                appraisal: appraised_turn.appraisal,
+                appraisal_text: score_text,
+                pv,
+                multi_pv: if ranked_turns.len() > 1 {
+                    ranked_turns
+                        .iter()
+                        .map(|ranked| RankedTurnJson {
+                            turn: ranked.turn.to_string(),
+                            appraisal: ranked.appraisal,
+                        })
+                        .collect::<Vec<_>>()
+                } else {
+                    Vec::new()
+                },
+                num_states_visited,
+                search_stats,
+                elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+                cancelled: was_cancelled,
+            };
+            match serde_json::to_string(&json_line) {
+                Ok(json) => println!("{json}"),
+                Err(err) => println!("failed to serialize analysis: {err}"),
+            }
+        } else {
+            println!(
+                "bestTurn={:<10} level={} appraisal={} states={} timeSec={:.2}",
+                best_turn_text,
+                analysis_level,
+                score_text,
+                num_states_visited,
+                elapsed.as_secs_f64()
+            );
+            if let Some(stats) = search_stats {
+                println!(
+                    "  aspiration failLows={} failHighs={} reSearchStates={}",
+                    stats.fail_lows, stats.fail_highs, stats.re_search_states_visited
+                );
+            }
+            if ranked_turns.len() > 1 {
+                for (rank, ranked) in ranked_turns.iter().enumerate() {
+                    println!(
+                        "  #{} {:<10} appraisal={}",
+                        rank + 1,
+                        ranked.turn.to_string(),
+                        rule_helper::heuristic_score_text(ranked.appraisal)
+                    );
+                }
+            }
+        }
 
+        let appraisal = appraised_turn.appraisal;
         if do_suggested_move && !was_cancelled {
             self.do_moves_turn(turn);
         }
-        was_cancelled
+        (!was_cancelled).then_some(appraisal)
     }
 
-    fn do_moves_tokens(&mut self, tokens: &[String]) {
+    fn play_selected_turn(&mut self, selector: &mut MoveSelector) {
         let Some(game) = self.game.as_ref() else {
             return;
         };
+        if game.is_game_over() {
+            println!("  game is over");
+            return;
+        }
+        let cancel_token = AtomicCancellationToken::new();
+        let mut num_states_visited = 0;
+        let selected = selector.select_turn(
+            game,
+            &self.search_config,
+            &cancel_token,
+            &mut num_states_visited,
+        );
+        let turn = selected.appraised_turn.turn;
+        println!(
+            "(DIFFICULTY {}) {turn} pick #{} of {} appraisal={}{}",
+            selector.difficulty,
+            selected.rank + 1,
+            selected.num_candidates,
+            rule_helper::heuristic_score_text(selected.appraised_turn.appraisal),
+            if selected.own_piece_only {
+                " (own piece only)"
+            } else {
+                ""
+            }
+        );
+        self.recent_analyzed_turn = Some(turn.clone());
+        self.do_moves_turn(turn);
+    }
 
-        if game.has_winner() {
-            println!(
-                "{} won already.  Moves not accepted.",
-                game.player_text_for(game.winner)
-            );
+    // plays the current game out engine against engine, so the turns land in its history
+    fn watch(&mut self, mut engine_a: WatchEngine, mut engine_b: WatchEngine, delay: Duration) {
+        println!("(WATCH A={engine_a} B={engine_b}, a key stops it)");
+        let watch = Instant::now();
+        let mut num_turns = 0usize;
+        let mut num_states_visited = 0usize;
+        let mut was_stopped = false;
+        while let Some(game) = self.game.as_ref().filter(|game| !game.is_game_over())
+            && num_turns < SelfPlayRunner::DEFAULT_MAX_NORMAL_TURNS
+        {
+            let mover_id = game.common.to_normal_player_id(game.current_player_id);
+            let side = watch_side(&game.common, mover_id);
+            let engine = if side == Side::A {
+                &mut engine_a
+            } else {
+                &mut engine_b
+            };
+
+            let cancel_token = Arc::new(AtomicCancellationToken::new());
+            let listener_done = Arc::new(AtomicBool::new(false));
+            let key_listener = spawn_key_listener(cancel_token.clone(), listener_done.clone());
+            let mut num_turn_states_visited = 0;
+            let appraised_turn = match engine {
+                WatchEngine::Level(level) => self.search_best_turn(
+                    game,
+                    *level,
+                    self.analysis_parallelization,
+                    cancel_token.as_ref(),
+                    &mut num_turn_states_visited,
+                ),
+                WatchEngine::Selector(selector) => {
+                    selector
+                        .select_turn(
+                            game,
+                            &self.search_config,
+                            cancel_token.as_ref(),
+                            &mut num_turn_states_visited,
+                        )
+                        .appraised_turn
+                }
+            };
+            num_states_visited += num_turn_states_visited;
+            let deadline = Instant::now() + delay;
+            while !cancel_token.is_cancellation_requested() && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(20).min(delay));
+            }
+            listener_done.store(true, Ordering::SeqCst);
+            let _ = key_listener.join();
+            if cancel_token.is_cancellation_requested() {
+                was_stopped = true;
+                break;
+            }
+
+            self.do_moves_turn(appraised_turn.turn);
+            num_turns += 1;
+            if let Some(game) = self.game.as_ref() {
+                println!("{}", game.prev_turn_summaries_since_normal(true));
+                println!(
+                    "  {side:?} {engine} eval {}",
+                    rule_helper::heuristic_score_text(appraised_turn.appraisal)
+                );
+            }
+        }
+
+        let Some(game) = self.game.as_ref() else {
             return;
+        };
+        let result_text = match game.result {
+            GameResult::Won { player } => {
+                let side = watch_side(&game.common, game.common.to_normal_player_id(player));
+                format!("{} won for side {side:?}", game.player_text_for(player))
+            }
+            GameResult::Draw { reason } => format!("draw ({reason})"),
+            GameResult::Aborted => "aborted".to_string(),
+            GameResult::InProgress if was_stopped => "stopped".to_string(),
+            GameResult::InProgress => "turn limit reached".to_string(),
+        };
+        println!(
+            "(WATCH END) {result_text} after {num_turns} turns, states={num_states_visited} timeSec={:.2}",
+            watch.elapsed().as_secs_f64()
+        );
+        println!("{}", game.summary(1));
+    }
+
+    fn search_best_turn(
+        &self,
+        game: &MutableGameState,
+        analysis_level: i32,
+        parallelization: i32,
+        cancel_token: &AtomicCancellationToken,
+        num_states_visited: &mut usize,
+    ) -> AppraisedPlayerTurn {
+        #[cfg(feature = "parallel")]
+        let appraised_turn = TreeSearch::find_best_turn_parallel_with_config(
+            game,
+            analysis_level,
+            &self.search_config,
+            cancel_token,
+            num_states_visited,
+            parallelization.max(1) as usize,
+        );
+        #[cfg(not(feature = "parallel"))]
+        let appraised_turn = {
+            if parallelization > 1 {
+                println!("parallel analysis needs the 'parallel' feature; searching on one thread");
+            }
+            TreeSearch::find_best_turn_with_config(
+                game,
+                analysis_level,
+                &self.search_config,
+                cancel_token,
+                num_states_visited,
+            )
+        };
+        appraised_turn
+    }
+
+    fn do_moves_tokens(&mut self, tokens: &[String]) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+
+        match game.result {
+            GameResult::Won { player } => {
+                println!(
+                    "{} won already.  Moves not accepted.",
+                    game.player_text_for(player)
+                );
+                return;
+            }
+            GameResult::Draw { reason } => {
+                println!("Game is a draw ({reason}).  Moves not accepted.");
+                return;
+            }
+            GameResult::Aborted => {
+                println!("Game was aborted.  Moves not accepted.");
+                return;
+            }
+            GameResult::InProgress => {}
         }
 
         let mut moves = Vec::new();
@@ -424,10 +1515,24 @@ impl Session {
                 subtokens[1]
             };
 
-            if let Ok(dest_room_id) = dest_room_subtoken.parse::<usize>() {
+            let dest_room_id = match dest_room_subtoken.parse::<usize>() {
+                Ok(room_id) => Some(RoomId(room_id)),
+                Err(_) if dest_room_subtoken.is_empty() => None,
+                Err(_) => match game.common.board.resolve_room_name(dest_room_subtoken) {
+                    Ok(room_id) => Some(room_id),
+                    Err(err) => {
+                        println!("  {err} (from '{token}')");
+                        has_parse_errors = true;
+                        continue;
+                    }
+                },
+            };
+            if let Some(dest_room_id) = dest_room_id {
                 let mut player_display_num = default_player_display_num;
                 if subtokens.len() >= 2 {
-                    if let Ok(parsed_num) = subtokens[0].parse::<usize>() {
+                    if let Ok(parsed_num) =
+                        subtokens[0].trim_start_matches(['p', 'P']).parse::<usize>()
+                    {
                         player_display_num = parsed_num;
                     } else {
                         println!(
@@ -446,7 +1551,7 @@ impl Session {
                 }
 
                 let player_id = PlayerId(player_display_num - 1);
-                moves.push(PieceMove::new(player_id, RoomId(dest_room_id)));
+                moves.push(PieceMove::new(player_id, dest_room_id));
             } else {
                 println!("  failed parse for room id from '{token}'");
                 has_parse_errors = true;
@@ -464,7 +1569,7 @@ impl Session {
         let is_valid = self
             .game
             .as_ref()
-            .map(|game| game.check_normal_turn(&turn))
+            .map(|game| game.check_normal_turn(&turn).map_err(|err| err.to_string()))
             .unwrap_or_else(|| Err("game not initialized".to_string()));
 
         if let Err(error_msg) = is_valid {
@@ -473,7 +1578,198 @@ impl Session {
         }
 
         if let Some(game) = self.game.as_ref() {
-            self.game = Some(game.after_turn(turn));
+            let mover_id = game.current_player_id;
+            let game = game.after_turn(turn);
+            if game.is_game_over() {
+                self.session_stats.record_game(&game);
+            } else {
+                let loop_room_texts = game
+                    .stranger_loop_warning_room_ids(mover_id)
+                    .iter()
+                    .map(|room_id| format!("R{}", room_id.0))
+                    .collect::<Vec<_>>();
+                if !loop_room_texts.is_empty() {
+                    println!(
+                        "  stranger loop warning for {}: {}",
+                        game.player_text_for(mover_id),
+                        loop_room_texts.join(",")
+                    );
+                }
+            }
+            self.game = Some(game);
+            self.sync_variations();
+        }
+    }
+
+    // ranks every legal turn by the appraisal of the position it leaves, a search of depth one
+    fn list_turns(&mut self, limit: Option<usize>) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+        let mut appraised_turns = game
+            .possible_turns()
+            .into_iter()
+            .map(|turn| {
+                let appraisal = self
+                    .search_config
+                    .appraise(&game.after_turn(turn.clone()), game.current_player_id);
+                (turn, appraisal)
+            })
+            .collect::<Vec<_>>();
+        appraised_turns.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        let num_turns = appraised_turns.len();
+        appraised_turns.truncate(limit.unwrap_or(num_turns));
+
+        println!(
+            "(TURNS {} of {num_turns} for {})",
+            appraised_turns.len(),
+            game.player_text()
+        );
+        for (idx, (turn, appraisal)) in appraised_turns.iter().enumerate() {
+            println!(
+                "  {:>4}  {:>8}  {turn}",
+                idx + 1,
+                rule_helper::heuristic_score_text(*appraisal)
+            );
+        }
+        self.listed_turns = Some((
+            game.position_key(),
+            appraised_turns
+                .into_iter()
+                .map(|(turn, _)| turn)
+                .collect::<Vec<_>>(),
+        ));
+    }
+
+    fn pick_listed_turn(&mut self, turn_num: usize) {
+        let Some(game) = self.game.as_ref() else {
+            return;
+        };
+        let turn = match self.listed_turns.as_ref() {
+            Some((position_key, turns)) if *position_key == game.position_key() => {
+                match turn_num.checked_sub(1).and_then(|idx| turns.get(idx)) {
+                    Some(turn) => turn.clone(),
+                    None => {
+                        println!("  {TAG_PICK} needs a number from 1 to {}", turns.len());
+                        return;
+                    }
+                }
+            }
+            _ => {
+                println!("  no turns listed for this position; {TAG_LIST_TURNS} lists them");
+                return;
+            }
+        };
+        println!("(PICK {turn})");
+        self.do_moves_turn(turn);
+    }
+
+    fn sync_variations(&mut self) {
+        if let Some(game) = self.game.as_ref() {
+            self.variations.sync_to_line(game.normal_turns());
+        }
+    }
+
+    // replays a node's line from the start of the game, so fiddled start values carry over
+    fn switch_variation(&mut self, node_id: usize) -> Result<(), String> {
+        if node_id != ROOT_NODE_ID && self.variations.node(node_id).is_none() {
+            return Err(format!("Unknown variation node {node_id}."));
+        }
+        let Some(mut game) = self.game.as_ref().map(|game| game.copy_state()) else {
+            return Err("game not initialized".to_string());
+        };
+        while let Some(prev_state) = game.prev_state.as_deref() {
+            game = prev_state.clone();
+        }
+        for (turn_idx, turn) in self.variations.line(node_id).into_iter().enumerate() {
+            game.check_normal_turn(&turn)
+                .map_err(|err| format!("Variation turn {} is invalid: {err}", turn_idx + 1))?;
+            game = game.after_turn(turn);
+        }
+        self.game = Some(game);
+        self.recent_analyzed_turn = None;
+        self.sync_variations();
+        Ok(())
+    }
+
+    fn handle_variation(&mut self, tokens: &[String]) {
+        let node_id_at = |idx: usize| {
+            tokens
+                .get(idx)
+                .and_then(|token| token.parse::<usize>().ok())
+        };
+        match tokens.get(1).map(|token| token.to_lowercase()).as_deref() {
+            None => self.print_variations(),
+            Some("new") => {
+                let Some(node_id) = node_id_at(2).filter(|_| tokens.len() > 3) else {
+                    println!("  {TAG_VARIATION} new needs a node id and the turn's moves");
+                    return;
+                };
+                let prior_node_id = self.variations.current_id();
+                if let Err(message) = self.switch_variation(node_id) {
+                    println!("  {message}");
+                    return;
+                }
+                self.do_moves_tokens(&tokens[3..]);
+                if self.variations.current_id() == node_id {
+                    let _ = self.switch_variation(prior_node_id);
+                }
+                self.print_variations();
+            }
+            Some("del") => match node_id_at(2) {
+                Some(node_id) => {
+                    if let Some(parent_id) = self.variations.parent_id(node_id)
+                        && self
+                            .variations
+                            .is_ancestor(node_id, self.variations.current_id())
+                        && let Err(message) = self.switch_variation(parent_id)
+                    {
+                        println!("  {message}");
+                        return;
+                    }
+                    match self.variations.delete(node_id) {
+                        Ok(()) => self.print_variations(),
+                        Err(message) => println!("  {message}"),
+                    }
+                }
+                None => println!("  {TAG_VARIATION} del needs a node id"),
+            },
+            Some(_) => match node_id_at(1) {
+                Some(node_id) => match self.switch_variation(node_id) {
+                    Ok(()) => {
+                        if let Some(game) = self.game.as_ref() {
+                            println!("{}", game.summary(1));
+                        }
+                    }
+                    Err(message) => println!("  {message}"),
+                },
+                None => println!(
+                    "  {TAG_VARIATION} directive needs no tokens, a node id, 'new' or 'del'"
+                ),
+            },
+        }
+    }
+
+    fn print_variations(&self) {
+        let current_line = self.variations.line_node_ids(self.variations.current_id());
+        let start_marker = if current_line.is_empty() { "*" } else { " " };
+        println!("  {start_marker}{ROOT_NODE_ID}: start");
+        for (node_id, depth) in self.variations.depth_first_ids() {
+            let Some(node) = self.variations.node(node_id) else {
+                continue;
+            };
+            let marker = if node_id == self.variations.current_id() {
+                "*"
+            } else if current_line.contains(&node_id) {
+                "+"
+            } else {
+                " "
+            };
+            println!(
+                "  {}{marker}{node_id}: {}",
+                "  ".repeat(depth + 1),
+                node.turn
+            );
         }
     }
 
@@ -531,7 +1827,7 @@ impl Session {
             if player_num == DOCTOR_PLAYER_NUM {
                 game.doctor_room_id = dest_room_id;
             } else if player_id >= 0 {
-                game.player_room_ids[player_id as usize] = dest_room_id;
+                game.move_piece(PlayerId(player_id as usize), dest_room_id);
             }
         } else if player_id < 0 {
             println!(
@@ -541,11 +1837,11 @@ impl Session {
         } else if attribute_name == "s" || attribute_name == "strength" {
             game.player_strengths[player_id as usize] = attribute_value as i32;
         } else if attribute_name == "m" || attribute_name == "moves" {
-            game.player_move_cards[player_id as usize] = attribute_value;
+            game.player_move_cards[player_id as usize] = CardCount::from_f64(attribute_value);
         } else if attribute_name == "w" || attribute_name == "weapons" {
-            game.player_weapons[player_id as usize] = attribute_value;
+            game.player_weapons[player_id as usize] = CardCount::from_f64(attribute_value);
         } else if attribute_name == "f" || attribute_name == "failures" {
-            game.player_failures[player_id as usize] = attribute_value;
+            game.player_failures[player_id as usize] = CardCount::from_f64(attribute_value);
         } else if attribute_name == "t" || attribute_name == "turn" {
             game.turn_id = attribute_value as i32;
             game.current_player_id = PlayerId(player_id as usize);
@@ -555,24 +1851,160 @@ impl Session {
         println!("{}", game.summary(1));
     }
 
+    // dot [path] [sight] [nowings] [nostarts]; the path defaults to the board's name
+    fn write_board_dot(&self, tokens: &[String]) {
+        let Some(game) = self.game.as_ref() else {
+            println!("  no board loaded");
+            return;
+        };
+        let board = &game.common.board;
+        let mut options = DotOptions::default();
+        let mut dot_path = None;
+        for token in tokens {
+            match token.to_lowercase().as_str() {
+                "sight" => options.sight_edges = true,
+                "nowings" => options.wing_clusters = false,
+                "nostarts" => options.start_highlights = false,
+                _ => dot_path = Some(PathBuf::from(token)),
+            }
+        }
+        let dot_path = dot_path.unwrap_or_else(|| PathBuf::from(format!("{}.dot", board.name)));
+        match fs::write(&dot_path, board.to_dot(&options)) {
+            Ok(()) => println!("(DOT {})", dot_path.display()),
+            Err(err) => println!("  failed to write '{}': {err}", dot_path.display()),
+        }
+    }
+
+    fn print_state_diff(&self, tokens: &[String]) {
+        let Some(game) = self.game.as_ref() else {
+            println!("  game not initialized");
+            return;
+        };
+        let current_ply = game.ply().max(0) as usize;
+        let plies = tokens
+            .iter()
+            .map(|token| token.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>();
+        let (from_ply, to_ply) = match plies.as_deref() {
+            Ok([from_ply]) => (*from_ply, current_ply),
+            Ok([from_ply, to_ply]) => (*from_ply, *to_ply),
+            _ => {
+                println!(
+                    "  {TAG_DIFF} needs one or two ply numbers (current ply is {current_ply})"
+                );
+                return;
+            }
+        };
+        let diff = match state_diff::diff_plies(game, from_ply, to_ply) {
+            Ok(diff) => diff,
+            Err(message) => {
+                println!("  {message}");
+                return;
+            }
+        };
+
+        println!("  ply {from_ply} -> {to_ply}");
+        if diff.is_empty() {
+            println!("  no changes");
+            return;
+        }
+        if diff.doctor_from_room_id != diff.doctor_to_room_id {
+            println!(
+                "  doctor: {} -> {}",
+                diff.doctor_from_room_id, diff.doctor_to_room_id
+            );
+        }
+        for mv in &diff.piece_moves {
+            println!(
+                "  {}: {} -> {}",
+                game.player_text_for(mv.player_id),
+                mv.from_room_id,
+                mv.to_room_id
+            );
+        }
+        let signed = |cards: CardCount| {
+            if cards.is_positive() {
+                format!("+{cards}")
+            } else {
+                cards.to_string()
+            }
+        };
+        for delta in &diff.card_deltas {
+            println!(
+                "  {} cards: move {}, weapon {}, failure {}",
+                game.player_text_for(delta.player_id),
+                signed(delta.move_cards),
+                signed(delta.weapons),
+                signed(delta.failures)
+            );
+        }
+        for delta in &diff.strength_deltas {
+            println!(
+                "  {} strength: {} -> {}",
+                game.player_text_for(delta.player_id),
+                delta.from_strength,
+                delta.to_strength
+            );
+        }
+        for (label, attacks) in [
+            ("attack", &diff.attacks),
+            ("undone attack", &diff.undone_attacks),
+        ] {
+            for attack in attacks {
+                println!(
+                    "  {label}: {} on turn {}, strength {}, defense {}, {}",
+                    game.player_text_for(attack.attacker),
+                    attack.turn_id,
+                    attack.strength,
+                    attack.defense_spent,
+                    if attack.succeeded {
+                        "succeeded"
+                    } else {
+                        "failed"
+                    }
+                );
+            }
+        }
+    }
+
     fn reset_game_with_problems(&mut self) -> Result<(), Vec<String>> {
-        let board = Board::from_embedded_json_with_options(
-            &self.board_name,
-            self.closed_wing_names.iter().map(String::as_str),
-            "",
-        )
-        .map_err(|err| {
-            println!("exception while constructing MutableGameState: {err:?}");
-            vec![format!("{err:?}")]
-        })?;
+        let closed_wing_names = self.closed_wing_names.iter().map(String::as_str);
+        let board = match self.board_path.clone() {
+            Some(board_path) if self.board_path_is_text => {
+                Board::from_text_file_with_options(board_path, closed_wing_names, "")
+            }
+            Some(board_path) => {
+                Board::from_json_file_with_options(board_path, closed_wing_names, "")
+            }
+            None => self
+                .data_paths
+                .load_board(&self.board_name, closed_wing_names),
+        }
+        .map_err(|err| board_load_problems(&err))?;
 
         if let Err(mistakes) = board.is_valid() {
             return Err(mistakes);
         }
 
-        let common = CommonGameState::from_num_normal_players(true, board, self.num_normal_players);
-        self.game = Some(MutableGameState::at_start(common.clone()));
+        let mut common =
+            CommonGameState::from_num_normal_players(true, board, self.num_normal_players)
+                .with_rule_config(self.rule_config.clone());
+        if let Some(stranger_counts) = self.stranger_counts {
+            common = common
+                .with_stranger_counts(stranger_counts)
+                .map_err(|message| vec![message])?;
+        }
+        if let Some(teams) = self.teams {
+            common = common.with_teams(teams).map_err(|message| vec![message])?;
+        }
+        let start_rooms = self
+            .start_options
+            .choose(&common.board, &mut self.start_rng)
+            .map_err(|message| vec![message])?;
+        self.game = Some(MutableGameState::at_start(common.clone()).with_start_rooms(start_rooms));
+        self.variations = VariationTree::default();
         self.game_common = Some(common);
+        self.clear_search_cache(); // the rules may have changed
         self.board_name_old = self.board_name.clone();
         self.deck_name_old = self.deck_name.clone();
         self.num_normal_players_old = self.num_normal_players;
@@ -606,10 +2038,143 @@ impl Session {
             return "> ".to_string();
         };
 
-        if game.has_winner() {
-            format!("{} WON> ", game.player_text_for(game.winner))
+        match game.result {
+            GameResult::Won { player } => format!("{} WON> ", game.player_text_for(player)),
+            GameResult::Draw { .. } => "DRAW> ".to_string(),
+            GameResult::Aborted => "ABORTED> ".to_string(),
+            GameResult::InProgress => format!("{}> ", game.player_text()),
+        }
+    }
+}
+
+// what plays a side in watch: the best turn at an analysis level, or a difficulty's pick
+enum WatchEngine {
+    Level(i32),
+    Selector(MoveSelector),
+}
+
+impl std::fmt::Display for WatchEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchEngine::Level(level) => write!(f, "L{level}"),
+            WatchEngine::Selector(selector) => write!(f, "{}", selector.difficulty),
+        }
+    }
+}
+
+// seeded from the clock, so each session's weaker engine errs differently
+fn new_move_selector(difficulty: Difficulty) -> MoveSelector {
+    MoveSelector::new(difficulty, clock_seed())
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+// r's tokens: an optional pool name, offset=n for the doctor's rooms past the players' and
+// seed=n to replay a run of starts
+fn parse_start_options(tokens: &[String]) -> Result<(StartOptions, Option<u64>), String> {
+    let mut start_options = StartOptions::default();
+    let mut seed = None;
+    for token in tokens {
+        if let Some(offset_text) = token.strip_prefix("offset=") {
+            let offset = offset_text
+                .parse::<i32>()
+                .map_err(|_| format!("{TAG_RESET} offset needs a whole number"))?;
+            start_options.doctor_offset = Some(offset);
+        } else if let Some(seed_text) = token.strip_prefix("seed=") {
+            seed = Some(
+                seed_text
+                    .parse::<u64>()
+                    .map_err(|_| format!("{TAG_RESET} seed needs a whole number"))?,
+            );
         } else {
-            format!("{}> ", game.player_text())
+            start_options.pool = token.parse::<StartRoomPool>()?;
         }
     }
+    Ok((start_options, seed))
+}
+
+// without sides, side A is P1 and side B everyone else
+fn watch_side(common: &CommonGameState, normal_player_id: PlayerId) -> Side {
+    common
+        .seat_map()
+        .side_of(normal_player_id)
+        .unwrap_or(if normal_player_id.0 == 0 {
+            Side::A
+        } else {
+            Side::B
+        })
+}
+
+// cancels the token on the first key press, until done is set
+fn spawn_key_listener(
+    cancel_token: Arc<AtomicCancellationToken>,
+    done: Arc<AtomicBool>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let raw_mode_enabled = terminal::enable_raw_mode().is_ok();
+        loop {
+            let poll_result = event::poll(Duration::from_millis(0));
+            let Ok(has_event) = poll_result else {
+                break;
+            };
+            if !has_event {
+                break;
+            }
+            let _ = event::read();
+        }
+        while !done.load(Ordering::SeqCst) {
+            let poll_result = event::poll(Duration::from_millis(50));
+            let Ok(has_event) = poll_result else {
+                break;
+            };
+            if has_event && matches!(event::read(), Ok(event::Event::Key(_))) {
+                cancel_token.cancel();
+                break;
+            }
+        }
+        if raw_mode_enabled {
+            let _ = terminal::disable_raw_mode();
+        }
+    })
+}
+
+// whether a key press is waiting, reading it if so
+fn key_was_pressed() -> bool {
+    let raw_mode_enabled = terminal::enable_raw_mode().is_ok();
+    let mut was_pressed = false;
+    while let Ok(true) = event::poll(Duration::from_millis(0)) {
+        if matches!(event::read(), Ok(event::Event::Key(_))) {
+            was_pressed = true;
+        }
+    }
+    if raw_mode_enabled {
+        let _ = terminal::disable_raw_mode();
+    }
+    was_pressed
+}
+
+fn board_load_problems(err: &BoardLoadError) -> Vec<String> {
+    let mut problems = vec![err.to_string()];
+    let (Some((line, column)), Some(board_path)) = (err.json_line_column(), err.board_path())
+    else {
+        return problems;
+    };
+
+    if let Some(line_text) = board_line_text(board_path, line) {
+        problems.push(format!("  {line_text}"));
+        problems.push(format!("  {}^", " ".repeat(column.saturating_sub(1))));
+    }
+    problems
+}
+
+fn board_line_text(board_path: &Path, line: usize) -> Option<String> {
+    let board_text = fs::read_to_string(board_path).ok()?;
+    board_text
+        .lines()
+        .nth(line.checked_sub(1)?)
+        .map(|line_text| line_text.to_string())
 }
@@ -0,0 +1,291 @@
+use crate::core::game_preset;
+use serde::Serialize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AssetKind {
+    Board,
+    Puzzle,
+    Preset,
+    Deck,
+}
+
+impl AssetKind {
+    pub const ALL: [AssetKind; 4] = [
+        AssetKind::Board,
+        AssetKind::Puzzle,
+        AssetKind::Preset,
+        AssetKind::Deck,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AssetKind::Board => "board",
+            AssetKind::Puzzle => "puzzle",
+            AssetKind::Preset => "preset",
+            AssetKind::Deck => "deck",
+        }
+    }
+
+    // singular or plural, any case
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let singular = text
+            .strip_suffix('s')
+            .or_else(|| text.strip_suffix('S'))
+            .unwrap_or(text);
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(singular))
+    }
+}
+
+// what a picker shows for an asset; players is the recommended number of normal players
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetMetadata {
+    pub kind: AssetKind,
+    pub name: &'static str,
+    pub display_name: &'static str,
+    pub description: &'static str,
+    pub min_players: usize,
+    pub max_players: usize,
+}
+
+impl AssetMetadata {
+    // ignores case and a .json suffix; boards and decks also answer to their names with the Board
+    // or Deck prefix of the reference data
+    pub fn matches_name(&self, requested_name: &str) -> bool {
+        let trimmed = trim_json_suffix(requested_name.trim());
+        let unprefixed = match self.kind {
+            AssetKind::Board => strip_board_prefix(trimmed),
+            AssetKind::Deck => strip_prefix_ignore_case(trimmed, "deck"),
+            AssetKind::Puzzle | AssetKind::Preset => trimmed,
+        };
+        self.name.eq_ignore_ascii_case(trimmed) || self.name.eq_ignore_ascii_case(unprefixed)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct EmbeddedAsset {
+    pub metadata: AssetMetadata,
+    pub json: &'static str,
+}
+
+// compiles each asset's json into the crate; paths are relative to this file
+macro_rules! embedded_assets {
+    ($kind:expr; $($name:literal, $display_name:literal, players $min_players:literal..=$max_players:literal, $path:literal, $description:literal;)*) => {
+        &[$(EmbeddedAsset {
+            metadata: AssetMetadata {
+                kind: $kind,
+                name: $name,
+                display_name: $display_name,
+                description: $description,
+                min_players: $min_players,
+                max_players: $max_players,
+            },
+            json: include_str!($path),
+        },)*]
+    };
+}
+
+pub const BOARD_ASSETS: &[EmbeddedAsset] = embedded_assets![AssetKind::Board;
+    "AltDown", "Alternate Downstairs", players 2..=4, "boards/AltDown.json",
+        "downstairs of the alternate board, 15 rooms";
+    "AltDownNoWarp", "Alternate Downstairs (no warps)", players 2..=4,
+        "../../../data/boards/BoardAltDownNoWarp.json",
+        "AltDown without its warp connections";
+    "AltFull", "Alternate Full", players 2..=8, "boards/AltFull.json",
+        "both floors of the alternate board, with wings to close";
+    "AltUp", "Alternate Upstairs", players 2..=4, "boards/AltUp.json",
+        "upstairs of the alternate board, 13 rooms";
+    "AltUpNoWarp", "Alternate Upstairs (no warps)", players 2..=4,
+        "../../../data/boards/BoardAltUpNoWarp.json",
+        "AltUp without its warp connections";
+    "Haunted", "Haunted House", players 2..=8, "boards/Haunted.json",
+        "both floors of the haunted house, with wings to close";
+    "HauntedDown", "Haunted Downstairs", players 2..=4,
+        "../../../data/boards/BoardHauntedDown.json",
+        "downstairs of the haunted house, 11 rooms";
+    "HauntedUp", "Haunted Upstairs", players 2..=4,
+        "../../../data/boards/BoardHauntedUp.json",
+        "upstairs of the haunted house, 13 rooms";
+    "Jacob1", "Jacob 1", players 2..=4, "../../../data/boards/BoardJacob1.json",
+        "hand-made 16-room board";
+    "Jacob1B", "Jacob 1B", players 2..=4, "../../../data/boards/BoardJacob1B.json",
+        "variant of Jacob1";
+    "LairFull", "Lair", players 2..=8, "boards/LairFull.json",
+        "both halves of the lair, 24 rooms";
+    "LairNorth", "Lair North", players 2..=4, "boards/LairNorth.json",
+        "north half of the lair, 13 rooms";
+    "LairSouth", "Lair South", players 2..=4, "boards/LairSouth.json",
+        "south half of the lair, 11 rooms";
+    "Main", "Main", players 2..=8, "boards/Main.json",
+        "the original game's board, with wings to close";
+    "MainEast", "Main East", players 2..=4, "../../../data/boards/BoardMainEast.json",
+        "east part of the main board, 19 rooms";
+    "MainWest", "Main West", players 2..=4, "../../../data/boards/BoardMainWest.json",
+        "west part of the main board, 19 rooms";
+    "Tiny", "Tiny", players 2..=2, "boards/Tiny.json",
+        "four rooms in a row that see nothing, for tests and puzzles";
+];
+
+pub const PUZZLE_ASSETS: &[EmbeddedAsset] = embedded_assets![AssetKind::Puzzle;
+    "first blood", "First Blood", players 2..=2, "puzzles/first_blood.json",
+        "Nobody can see the parlor, and player 2 is out of failure cards.";
+    "cornered", "Cornered", players 2..=2, "puzzles/cornered.json",
+        "Player 2 has two failure cards, so one attack won't do it.";
+    "long way round", "The Long Way Round", players 2..=2, "puzzles/long_way_round.json",
+        "The doctor is at the far end of the house from you.";
+];
+
+pub const DECK_ASSETS: &[EmbeddedAsset] = embedded_assets![AssetKind::Deck;
+    "Standard", "Standard", players 2..=8, "../../../data/decks/DeckStandard.json",
+        "the board game's 72 cards: 24 each of move, weapon and failure";
+];
+
+// boards built from their names by Board::from_generated_name; other sizes work too
+pub const GENERATED_BOARDS: &[AssetMetadata] = &[
+    generated_board(
+        "Ring8",
+        "Ring of 8",
+        "eight rooms in a cycle, each seeing its neighbors",
+    ),
+    generated_board(
+        "Ring12",
+        "Ring of 12",
+        "twelve rooms in a cycle, each seeing its neighbors",
+    ),
+    generated_board(
+        "Grid3x3",
+        "3x3 Grid",
+        "rooms that see their orthogonal neighbors",
+    ),
+    generated_board(
+        "Grid4x4",
+        "4x4 Grid",
+        "rooms that see their orthogonal neighbors",
+    ),
+    generated_board(
+        "Grid4x4Line",
+        "4x4 Grid (lines)",
+        "rooms that see along their row and column",
+    ),
+];
+
+const fn generated_board(
+    name: &'static str,
+    display_name: &'static str,
+    description: &'static str,
+) -> AssetMetadata {
+    AssetMetadata {
+        kind: AssetKind::Board,
+        name,
+        display_name,
+        description,
+        min_players: 2,
+        max_players: 4,
+    }
+}
+
+pub fn find_embedded(
+    assets: &'static [EmbeddedAsset],
+    name: &str,
+) -> Option<&'static EmbeddedAsset> {
+    assets
+        .iter()
+        .find(|asset| asset.metadata.matches_name(name))
+}
+
+// everything of a kind, or of every kind; boards list the embedded ones before the generated ones
+pub fn list_assets(kind: Option<AssetKind>) -> Vec<AssetMetadata> {
+    let boards = BOARD_ASSETS
+        .iter()
+        .map(|asset| asset.metadata)
+        .chain(GENERATED_BOARDS.iter().copied());
+    let puzzles = PUZZLE_ASSETS.iter().map(|asset| asset.metadata);
+    let presets = game_preset::game_presets()
+        .into_iter()
+        .map(|preset| preset.metadata());
+    let decks = DECK_ASSETS.iter().map(|asset| asset.metadata);
+    boards
+        .chain(puzzles)
+        .chain(presets)
+        .chain(decks)
+        .filter(|metadata| kind.is_none_or(|kind| metadata.kind == kind))
+        .collect::<Vec<_>>()
+}
+
+pub fn trim_json_suffix(name: &str) -> &str {
+    let lower = name.to_ascii_lowercase();
+    if lower.ends_with(".json") {
+        &name[..name.len() - 5]
+    } else {
+        name
+    }
+}
+
+pub fn strip_board_prefix(name: &str) -> &str {
+    strip_prefix_ignore_case(name, "board")
+}
+
+fn strip_prefix_ignore_case<'a>(name: &'a str, prefix: &str) -> &'a str {
+    match name.get(..prefix.len()) {
+        Some(head) if head.eq_ignore_ascii_case(prefix) => &name[prefix.len()..],
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, deck_spec::DeckSpec, puzzle::Puzzle};
+
+    #[test]
+    fn finds_assets_by_loose_names_and_lists_them_by_kind() {
+        let board = find_embedded(BOARD_ASSETS, "boardaltdown.JSON").expect("board should exist");
+        assert_eq!(board.metadata.name, "AltDown");
+        assert!(find_embedded(PUZZLE_ASSETS, "boardcornered").is_none());
+        assert_eq!(AssetKind::parse("Puzzles"), Some(AssetKind::Puzzle));
+        assert_eq!(AssetKind::parse("maps"), None);
+
+        let all = list_assets(None);
+        for kind in AssetKind::ALL {
+            let of_kind = list_assets(Some(kind));
+            assert!(!of_kind.is_empty());
+            assert!(of_kind.iter().all(|metadata| metadata.kind == kind));
+            assert!(
+                of_kind
+                    .iter()
+                    .all(|metadata| metadata.min_players <= metadata.max_players)
+            );
+        }
+        assert_eq!(
+            all.len(),
+            AssetKind::ALL
+                .into_iter()
+                .map(|kind| list_assets(Some(kind)).len())
+                .sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn generated_boards_build_and_puzzles_and_decks_match_their_metadata() {
+        for metadata in GENERATED_BOARDS {
+            assert!(
+                Board::from_generated_name(metadata.name).is_some(),
+                "{}",
+                metadata.name
+            );
+        }
+        for asset in PUZZLE_ASSETS {
+            let puzzle = Puzzle::from_json_str(asset.json).expect("puzzle should load");
+            assert_eq!(puzzle.name, asset.metadata.name);
+            assert_eq!(puzzle.description, asset.metadata.description);
+        }
+        for asset in DECK_ASSETS {
+            let deck = DeckSpec::find_embedded(asset.metadata.name).expect("deck should load");
+            assert_eq!(deck.name, asset.metadata.name);
+        }
+    }
+}
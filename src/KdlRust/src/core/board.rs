@@ -1,60 +1,52 @@
 use crate::core::{
+    asset_registry::{self, BOARD_ASSETS, GENERATED_BOARDS},
+    board_dot::{self, DotOptions},
+    board_text,
     room::{Room, RoomId, room_ids},
+    room_matrix::RoomMatrix,
+    validation_error::ValidationError,
     wing::Wing,
 };
 use itertools::Itertools;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
 };
 
-const EMBEDDED_BOARD_DATA: &[(&str, &str)] = &[
-    ("AltDown", include_str!("boards/AltDown.json")),
-    (
-        "AltDownNoWarp",
-        include_str!("../../../data/boards/BoardAltDownNoWarp.json"),
-    ),
-    ("AltFull", include_str!("boards/AltFull.json")),
-    ("AltUp", include_str!("boards/AltUp.json")),
-    (
-        "AltUpNoWarp",
-        include_str!("../../../data/boards/BoardAltUpNoWarp.json"),
-    ),
-    ("Haunted", include_str!("boards/Haunted.json")),
-    (
-        "HauntedDown",
-        include_str!("../../../data/boards/BoardHauntedDown.json"),
-    ),
-    (
-        "HauntedUp",
-        include_str!("../../../data/boards/BoardHauntedUp.json"),
-    ),
-    (
-        "Jacob1",
-        include_str!("../../../data/boards/BoardJacob1.json"),
-    ),
-    (
-        "Jacob1B",
-        include_str!("../../../data/boards/BoardJacob1B.json"),
-    ),
-    ("LairFull", include_str!("boards/LairFull.json")),
-    ("LairNorth", include_str!("boards/LairNorth.json")),
-    ("LairSouth", include_str!("boards/LairSouth.json")),
-    ("Main", include_str!("boards/Main.json")),
-    (
-        "MainEast",
-        include_str!("../../../data/boards/BoardMainEast.json"),
-    ),
-    (
-        "MainWest",
-        include_str!("../../../data/boards/BoardMainWest.json"),
-    ),
-    ("Tiny", include_str!("boards/Tiny.json")),
-];
-
-#[derive(Clone, Debug, PartialEq, Deserialize)]
+const MAX_GENERATED_ROOMS: usize = 100;
+// room ids size the board's matrices, so a stray huge id in board json would exhaust memory
+const MAX_BOARD_ROOM_ID: usize = 255;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GridSightRule {
+    Adjacent, // a room sees only its orthogonal neighbors
+    Line,     // a room sees every room in its row and column
+}
+
+// a relabeling of the rooms that leaves the game unchanged; room_map is indexed by room id
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoomSymmetry {
+    room_map: Vec<RoomId>,
+}
+
+impl RoomSymmetry {
+    // ids off the board, like an invalid turn's room 0, map to themselves
+    pub fn apply(&self, room_id: RoomId) -> RoomId {
+        self.room_map.get(room_id.0).copied().unwrap_or(room_id)
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut room_map = (0..self.room_map.len()).map(RoomId).collect::<Vec<_>>();
+        for (from_idx, to_room_id) in self.room_map.iter().enumerate() {
+            room_map[to_room_id.0] = RoomId(from_idx);
+        }
+        Self { room_map }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 #[readonly::make]
 pub struct BoardSpecification {
@@ -69,9 +61,38 @@ pub struct BoardSpecification {
 }
 
 impl BoardSpecification {
+    pub fn new(
+        name: impl Into<String>,
+        player_start_room_ids: impl IntoIterator<Item = RoomId>,
+        doctor_start_room_ids: impl IntoIterator<Item = RoomId>,
+        cat_start_room_ids: impl IntoIterator<Item = RoomId>,
+        dog_start_room_ids: impl IntoIterator<Item = RoomId>,
+        wings: impl IntoIterator<Item = Wing>,
+        rooms: impl IntoIterator<Item = Room>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            player_start_room_ids: player_start_room_ids.into_iter().collect(),
+            doctor_start_room_ids: doctor_start_room_ids.into_iter().collect(),
+            cat_start_room_ids: cat_start_room_ids.into_iter().collect(),
+            dog_start_room_ids: dog_start_room_ids.into_iter().collect(),
+            wings: wings.into_iter().collect(),
+            rooms: rooms.into_iter().collect(),
+        }
+    }
+
     pub fn from_json_str(json: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(json)
     }
+
+    // see board_text for the format
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        board_text::spec_from_text(text)
+    }
+
+    pub fn to_text(&self) -> String {
+        board_text::spec_to_text(self)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -79,16 +100,18 @@ impl BoardSpecification {
 pub struct Board {
     pub name: String,
     pub json_name: String,
-    pub rooms: HashMap<RoomId, Room>,       // key is room id
-    pub room_ids: Vec<RoomId>,              // sorted
-    pub room_visit_order_index: Vec<usize>, // indexed by room id
-    pub adjacency: Vec<Vec<bool>>,          // double-indexed by room id
-    pub sight: Vec<Vec<bool>>,              // double-indexed by room id
-    pub distance: Vec<Vec<i32>>,            // double-indexed by room id
-    pub adjacency_count: Vec<usize>,        // indexed by room id
-    pub doctor_future_visit_distance: Vec<Vec<i32>>, // [future first doctor room][target room]
-    pub doctor_future_near_distance: Vec<Vec<i32>>, // [future first doctor room][target or adjacent room]
+    pub rooms: HashMap<RoomId, Room>,         // key is room id
+    pub room_ids: Vec<RoomId>,                // sorted
+    pub room_visit_order_index: Vec<usize>,   // indexed by room id
+    pub adjacency: RoomMatrix<bool>,          // [(room, room)]
+    pub sight: RoomMatrix<bool>,              // [(room, room)]
+    pub seen_from_room_ids: Vec<Vec<RoomId>>, // indexed by room id; rooms that see it, itself included
+    pub distance: RoomMatrix<i32>,            // [(room, room)]
+    pub adjacency_count: Vec<usize>,          // indexed by room id
+    pub doctor_future_visit_distance: RoomMatrix<i32>, // [(future first doctor room, target room)]
+    pub doctor_future_near_distance: RoomMatrix<i32>, // [(future first doctor room, target or adjacent room)]
     pub stranger_loop_room_ids: HashMap<RoomId, HashSet<RoomId>>, // enemy room id -> allied stranger room ids
+    pub symmetries: Vec<RoomSymmetry>, // see room_symmetries; the identity is left out
     pub player_start_room_id: RoomId,
     pub doctor_start_room_id: RoomId,
     pub cat_start_room_id: RoomId,
@@ -148,26 +171,34 @@ impl Board {
             .unwrap_or(0)
             .saturating_add(1);
 
-        let mut adjacency = vec![vec![false; matrix_dim]; matrix_dim];
-        let mut sight = vec![vec![false; matrix_dim]; matrix_dim];
+        let mut adjacency = RoomMatrix::new(matrix_dim, false);
+        let mut sight = RoomMatrix::new(matrix_dim, false);
         let mut adjacency_count = vec![0usize; matrix_dim];
 
         for room in rooms.values() {
-            let id = room.id.0;
-            adjacency[id][id] = true;
-            sight[id][id] = true;
-            adjacency_count[id] = room.adjacent.len();
+            let id = room.id;
+            adjacency[(id, id)] = true;
+            sight[(id, id)] = true;
+            adjacency_count[id.0] = room.adjacent.len();
 
             for adjacent_room_id in &room.adjacent {
-                adjacency[id][adjacent_room_id.0] = true;
+                adjacency[(id, *adjacent_room_id)] = true;
             }
 
             for visible_room_id in &room.visible {
-                let idx = visible_room_id.0;
-                sight[id][idx] = true;
+                sight[(id, *visible_room_id)] = true;
             }
         }
 
+        let seen_from_room_ids = sight
+            .room_ids()
+            .map(|to| {
+                sight
+                    .room_ids()
+                    .filter(|from| sight[(*from, to)])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
         let distance = adjacency_to_distance(&adjacency);
         let room_visit_order_index = room_visit_order_index(matrix_dim, &room_ids);
         let doctor_future_visit_distance =
@@ -175,6 +206,7 @@ impl Board {
         let doctor_future_near_distance =
             doctor_future_near_distance(&room_ids, &distance, &doctor_future_visit_distance);
         let stranger_loop_room_ids = distance_to_stranger_loop_info(&room_ids, &distance, &sight);
+        let symmetries = room_symmetries(matrix_dim, &room_ids, &adjacency, &sight);
 
         Board {
             name: name.into(),
@@ -184,11 +216,13 @@ impl Board {
             room_visit_order_index,
             adjacency,
             sight,
+            seen_from_room_ids,
             distance,
             adjacency_count,
             doctor_future_visit_distance,
             doctor_future_near_distance,
             stranger_loop_room_ids,
+            symmetries,
             player_start_room_id,
             doctor_start_room_id,
             cat_start_room_id,
@@ -201,12 +235,154 @@ impl Board {
         Self::from_json_file_with_options(board_path, std::iter::empty::<String>(), "")
     }
 
+    // board json that didn't come from a file, like a pasted or uploaded board
+    pub fn from_json_str(board_json: &str, board_name: &str) -> Result<Self, BoardLoadError> {
+        let board_path = PathBuf::from(format!("{board_name}.json"));
+        let spec =
+            BoardSpecification::from_json_str(board_json).map_err(|err| BoardLoadError::Json {
+                board_path: board_path.clone(),
+                source: err,
+            })?;
+        Self::from_spec(
+            spec,
+            std::iter::empty::<String>(),
+            "",
+            board_path,
+            Some(board_name.to_string()),
+        )
+    }
+
+    // board text (see board_text) that didn't come from a file
+    pub fn from_text_str(board_text: &str, board_name: &str) -> Result<Self, BoardLoadError> {
+        let board_path = PathBuf::from(format!("{board_name}.tsv"));
+        let spec =
+            BoardSpecification::from_text(board_text).map_err(|message| BoardLoadError::Text {
+                board_path: board_path.clone(),
+                message,
+            })?;
+        Self::from_spec(
+            spec,
+            std::iter::empty::<String>(),
+            "",
+            board_path,
+            Some(board_name.to_string()),
+        )
+    }
+
     pub fn from_embedded_json(board_name: &str) -> Result<Self, BoardLoadError> {
         Self::from_embedded_json_with_options(board_name, std::iter::empty::<String>(), "")
     }
 
     pub fn embedded_board_names() -> impl Iterator<Item = &'static str> {
-        EMBEDDED_BOARD_DATA.iter().map(|(name, _)| *name)
+        BOARD_ASSETS.iter().map(|asset| asset.metadata.name)
+    }
+
+    pub fn generated_board_names() -> impl Iterator<Item = &'static str> {
+        GENERATED_BOARDS.iter().map(|metadata| metadata.name)
+    }
+
+    // rooms 1..=num_rooms in a cycle, each seeing only its two neighbors
+    pub fn ring(num_rooms: usize) -> Self {
+        assert!(num_rooms >= 3, "ring board needs at least 3 rooms");
+        let rooms = (1..=num_rooms)
+            .map(|idx| {
+                let neighbors = [
+                    RoomId((idx + num_rooms - 2) % num_rooms + 1),
+                    RoomId(idx % num_rooms + 1),
+                ];
+                Room::new(RoomId(idx), format!("R{idx}"), neighbors, neighbors)
+            })
+            .collect::<Vec<_>>();
+        Board::new(
+            format!("Ring{num_rooms}"),
+            rooms,
+            RoomId(1),
+            RoomId(num_rooms / 2 + 1),
+            RoomId(1),
+            RoomId(1),
+            None,
+        )
+    }
+
+    // rooms numbered row by row from the top left, connected to orthogonal neighbors
+    pub fn grid(width: usize, height: usize, sight_rule: GridSightRule) -> Self {
+        assert!(width * height >= 3, "grid board needs at least 3 rooms");
+        let room_id_at = |col: usize, row: usize| RoomId(row * width + col + 1);
+        let rooms = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                let adjacent = [
+                    (col > 0).then(|| room_id_at(col - 1, row)),
+                    (col + 1 < width).then(|| room_id_at(col + 1, row)),
+                    (row > 0).then(|| room_id_at(col, row - 1)),
+                    (row + 1 < height).then(|| room_id_at(col, row + 1)),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+                let visible = match sight_rule {
+                    GridSightRule::Adjacent => adjacent.clone(),
+                    GridSightRule::Line => (0..width)
+                        .filter(|other_col| *other_col != col)
+                        .map(|other_col| room_id_at(other_col, row))
+                        .chain(
+                            (0..height)
+                                .filter(|other_row| *other_row != row)
+                                .map(|other_row| room_id_at(col, other_row)),
+                        )
+                        .collect::<Vec<_>>(),
+                };
+                Room::new(
+                    room_id_at(col, row),
+                    format!("{}{}", (b'A' + (row % 26) as u8) as char, col + 1),
+                    adjacent,
+                    visible,
+                )
+            })
+            .collect::<Vec<_>>();
+        let suffix = match sight_rule {
+            GridSightRule::Adjacent => "",
+            GridSightRule::Line => "Line",
+        };
+        Board::new(
+            format!("Grid{width}x{height}{suffix}"),
+            rooms,
+            RoomId(1),
+            RoomId(width * height),
+            RoomId(1),
+            RoomId(1),
+            None,
+        )
+    }
+
+    // accepts any size, not just the listed generated_board_names
+    pub fn from_generated_name(board_name: &str) -> Option<Self> {
+        let name = asset_registry::strip_board_prefix(asset_registry::trim_json_suffix(board_name))
+            .to_ascii_lowercase();
+        // capping each side first keeps width * height from overflowing on a hostile name
+        let parse_size = |text: &str| {
+            text.parse::<usize>()
+                .ok()
+                .filter(|size| (1..=MAX_GENERATED_ROOMS).contains(size))
+        };
+
+        if let Some(num_rooms) = name.strip_prefix("ring").and_then(parse_size) {
+            return (3..=MAX_GENERATED_ROOMS)
+                .contains(&num_rooms)
+                .then(|| Self::ring(num_rooms));
+        }
+
+        let grid_text = name.strip_prefix("grid")?;
+        let (grid_text, sight_rule) = match grid_text.strip_suffix("line") {
+            Some(grid_text) => (grid_text, GridSightRule::Line),
+            None => (grid_text, GridSightRule::Adjacent),
+        };
+        let (width, height) = grid_text.split_once('x')?;
+        let (width, height) = (parse_size(width)?, parse_size(height)?);
+        width
+            .checked_mul(height)
+            .filter(|num_rooms| (3..=MAX_GENERATED_ROOMS).contains(num_rooms))
+            .map(|_| Self::grid(width, height, sight_rule))
     }
 
     pub fn from_json_file_with_options<P, S>(
@@ -228,6 +404,40 @@ impl Board {
                 board_path: board_path.clone(),
                 source: err,
             })?;
+        Self::from_file_spec(spec, closed_wing_names, board_name_suffix, board_path)
+    }
+
+    pub fn from_text_file_with_options<P, S>(
+        board_path: P,
+        closed_wing_names: impl IntoIterator<Item = S>,
+        board_name_suffix: &str,
+    ) -> Result<Self, BoardLoadError>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        let board_path = board_path.as_ref().to_path_buf();
+        let board_text = fs::read_to_string(&board_path).map_err(|err| BoardLoadError::Io {
+            board_path: board_path.clone(),
+            source: err,
+        })?;
+        let spec =
+            BoardSpecification::from_text(&board_text).map_err(|message| BoardLoadError::Text {
+                board_path: board_path.clone(),
+                message,
+            })?;
+        Self::from_file_spec(spec, closed_wing_names, board_name_suffix, board_path)
+    }
+
+    fn from_file_spec<S>(
+        spec: BoardSpecification,
+        closed_wing_names: impl IntoIterator<Item = S>,
+        board_name_suffix: &str,
+        board_path: PathBuf,
+    ) -> Result<Self, BoardLoadError>
+    where
+        S: AsRef<str>,
+    {
         let json_name = board_path
             .file_stem()
             .and_then(|stem| stem.to_str())
@@ -249,16 +459,20 @@ impl Board {
     where
         S: AsRef<str>,
     {
-        let Some((resolved_name, json)) = embedded_board_entry(board_name) else {
-            return Err(BoardLoadError::EmbeddedBoardNotFound {
-                board_name: board_name.to_string(),
+        let Some(asset) = asset_registry::find_embedded(BOARD_ASSETS, board_name) else {
+            return Self::from_generated_name(board_name).ok_or_else(|| {
+                BoardLoadError::EmbeddedBoardNotFound {
+                    board_name: board_name.to_string(),
+                }
             });
         };
+        let resolved_name = asset.metadata.name;
         let board_path = embedded_board_path(resolved_name);
-        let spec = BoardSpecification::from_json_str(json).map_err(|err| BoardLoadError::Json {
-            board_path: board_path.clone(),
-            source: err,
-        })?;
+        let spec =
+            BoardSpecification::from_json_str(asset.json).map_err(|err| BoardLoadError::Json {
+                board_path: board_path.clone(),
+                source: err,
+            })?;
         Self::from_spec(
             spec,
             closed_wing_names,
@@ -278,6 +492,14 @@ impl Board {
     where
         S: AsRef<str>,
     {
+        let problems = spec_problems(&spec);
+        if !problems.is_empty() {
+            return Err(BoardLoadError::InvalidSpec {
+                board_path,
+                problems,
+            });
+        }
+
         let closed_wing_name_set = closed_wing_names
             .into_iter()
             .map(|name| name.as_ref().to_lowercase())
@@ -329,6 +551,60 @@ impl Board {
         Ok(board)
     }
 
+    pub fn wings(&self) -> &[Wing] {
+        self.spec
+            .as_ref()
+            .map(|spec| spec.wings.as_slice())
+            .unwrap_or(&[])
+    }
+
+    // a closed wing's rooms were left off the board when it was built
+    pub fn is_wing_closed(&self, wing: &Wing) -> bool {
+        !wing.room_ids.is_empty()
+            && wing
+                .room_ids
+                .iter()
+                .all(|room_id| !self.rooms.contains_key(room_id))
+    }
+
+    pub fn wing_of(&self, room_id: RoomId) -> Option<&Wing> {
+        self.wings()
+            .iter()
+            .find(|wing| wing.room_ids.contains(&room_id))
+    }
+
+    // the board's shape without its names: room ids, doors, sight and start rooms. boards with the
+    // same hash play the same, so saved turns can move between them. fnv-1a keeps it stable across
+    // builds, since it gets stored in saves
+    pub fn structure_hash(&self) -> u64 {
+        let room_pairs = self.room_ids.iter().flat_map(|from_room_id| {
+            self.room_ids
+                .iter()
+                .map(move |to_room_id| (*from_room_id, *to_room_id))
+        });
+        let words =
+            self.room_ids
+                .iter()
+                .map(|room_id| room_id.0 as u64)
+                .chain(room_pairs.map(|pair| {
+                    u64::from(self.adjacency[pair]) | (u64::from(self.sight[pair]) << 1)
+                }))
+                .chain(
+                    [
+                        self.player_start_room_id,
+                        self.doctor_start_room_id,
+                        self.cat_start_room_id,
+                        self.dog_start_room_id,
+                    ]
+                    .map(|room_id| room_id.0 as u64),
+                );
+        words
+            .flat_map(u64::to_le_bytes)
+            .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            })
+    }
+
     pub fn is_valid(&self) -> Result<(), Vec<String>> {
         let mut mistakes = Vec::new();
 
@@ -371,13 +647,12 @@ impl Board {
             }
         }
 
-        let max_room_id = self.adjacency.len();
-        for r1 in 0..max_room_id {
-            for r2 in 0..max_room_id {
-                if self.adjacency[r1][r2] != self.adjacency[r2][r1] {
+        for r1 in self.adjacency.room_ids() {
+            for r2 in self.adjacency.room_ids() {
+                if self.adjacency[(r1, r2)] != self.adjacency[(r2, r1)] {
                     mistakes.push(format!("Adjacency[{},{}] contradiction", r1, r2));
                 }
-                if self.sight[r1][r2] != self.sight[r2][r1] {
+                if self.sight[(r1, r2)] != self.sight[(r2, r1)] {
                     mistakes.push(format!("Visibility[{},{}] contradiction", r1, r2));
                 }
             }
@@ -390,6 +665,64 @@ impl Board {
         }
     }
 
+    // like "parlor (R12)", or just "R12" when the room has no name of its own
+    pub fn room_label(&self, room_id: RoomId) -> String {
+        match self.rooms.get(&room_id).map(|room| room.name.as_str()) {
+            Some(name) if !name.is_empty() && name != format!("R{}", room_id.0) => {
+                format!("{name} (R{})", room_id.0)
+            }
+            _ => format!("R{}", room_id.0),
+        }
+    }
+
+    // graphviz source for the board; see board_dot
+    pub fn to_dot(&self, options: &DotOptions) -> String {
+        board_dot::board_to_dot(self, options)
+    }
+
+    pub fn room_id_by_name(&self, name: &str) -> Option<RoomId> {
+        let name = name.trim();
+        self.room_ids
+            .iter()
+            .copied()
+            .find(|room_id| self.rooms[room_id].name.eq_ignore_ascii_case(name))
+    }
+
+    // for typed input: an exact name wins, otherwise a unique name prefix; underscores stand in for
+    // spaces so a name fits in one token
+    pub fn resolve_room_name(&self, name: &str) -> Result<RoomId, ValidationError> {
+        let wanted = name.trim().replace('_', " ").to_lowercase();
+        let exact = self
+            .room_ids
+            .iter()
+            .copied()
+            .filter(|room_id| self.rooms[room_id].name.to_lowercase() == wanted)
+            .collect::<Vec<_>>();
+        let matches = if exact.is_empty() && !wanted.is_empty() {
+            self.room_ids
+                .iter()
+                .copied()
+                .filter(|room_id| self.rooms[room_id].name.to_lowercase().starts_with(&wanted))
+                .collect::<Vec<_>>()
+        } else {
+            exact
+        };
+
+        match matches.as_slice() {
+            [room_id] => Ok(*room_id),
+            [] => Err(ValidationError::UnknownRoomName {
+                name: name.trim().to_string(),
+            }),
+            _ => Err(ValidationError::AmbiguousRoomName {
+                name: name.trim().to_string(),
+                candidates: matches
+                    .iter()
+                    .map(|room_id| self.room_label(*room_id))
+                    .collect::<Vec<_>>(),
+            }),
+        }
+    }
+
     pub fn room_is_seen_by(
         &self,
         room_of_concern: RoomId,
@@ -397,14 +730,17 @@ impl Board {
     ) -> bool {
         rooms_with_other_people
             .into_iter()
-            .any(|room_id| self.sight[room_of_concern.0][room_id.0])
+            .any(|room_id| self.sight[(room_of_concern, room_id)])
     }
 
+    // a room missing from room_ids steps from the gap where it would sit
     pub fn next_room_id(room_id: RoomId, delta: i32, room_ids: &[RoomId]) -> RoomId {
-        let idx = room_ids
-            .binary_search_by_key(&room_id.0, |room| room.0)
-            .expect("room id not found in room_ids");
-        let next_idx = positive_remainder(idx as i32 + delta, room_ids.len());
+        let next_idx = match room_ids.binary_search_by_key(&room_id.0, |room| room.0) {
+            Ok(idx) => idx as i32 + delta,
+            Err(insert_idx) if delta > 0 => insert_idx as i32 + delta - 1,
+            Err(insert_idx) => insert_idx as i32 + delta,
+        };
+        let next_idx = positive_remainder(next_idx, room_ids.len());
         room_ids[next_idx]
     }
 
@@ -414,6 +750,21 @@ impl Board {
         self.room_ids[next_idx]
     }
 
+    pub fn doctor_visit_order_index(&self, room_id: RoomId) -> usize {
+        // an id past every room wraps around to the first
+        self.room_visit_order_index
+            .get(room_id.0)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // doctor moves from one room to another; either may be missing from the board
+    pub fn doctor_moves_between(&self, from_room_id: RoomId, to_room_id: RoomId) -> i32 {
+        let delta = self.doctor_visit_order_index(to_room_id) as i32
+            - self.doctor_visit_order_index(from_room_id) as i32;
+        positive_remainder(delta, self.room_ids.len().max(1)) as i32
+    }
+
     pub fn room_ids_in_doctor_visit_order(&self, start_room_id: RoomId) -> Vec<RoomId> {
         let start_idx = self.room_visit_order_index[start_room_id.0];
 
@@ -427,7 +778,7 @@ impl Board {
         start_room_id: RoomId,
         target_room_id: RoomId,
     ) -> i32 {
-        self.doctor_future_visit_distance[start_room_id.0][target_room_id.0]
+        self.doctor_future_visit_distance[(start_room_id, target_room_id)]
     }
 
     pub fn doctor_future_near_distance(
@@ -435,7 +786,7 @@ impl Board {
         start_room_id: RoomId,
         target_room_id: RoomId,
     ) -> i32 {
-        self.doctor_future_near_distance[start_room_id.0][target_room_id.0]
+        self.doctor_future_near_distance[(start_room_id, target_room_id)]
     }
 }
 
@@ -449,6 +800,10 @@ pub enum BoardLoadError {
         board_path: PathBuf,
         source: serde_json::Error,
     },
+    Text {
+        board_path: PathBuf,
+        message: String,
+    },
     MissingStartRoom {
         board_path: PathBuf,
         role: &'static str,
@@ -456,6 +811,30 @@ pub enum BoardLoadError {
     EmbeddedBoardNotFound {
         board_name: String,
     },
+    InvalidSpec {
+        board_path: PathBuf,
+        problems: Vec<String>,
+    },
+}
+
+impl BoardLoadError {
+    pub fn board_path(&self) -> Option<&Path> {
+        match self {
+            BoardLoadError::Io { board_path, .. }
+            | BoardLoadError::Json { board_path, .. }
+            | BoardLoadError::Text { board_path, .. }
+            | BoardLoadError::MissingStartRoom { board_path, .. }
+            | BoardLoadError::InvalidSpec { board_path, .. } => Some(board_path),
+            BoardLoadError::EmbeddedBoardNotFound { .. } => None,
+        }
+    }
+
+    pub fn json_line_column(&self) -> Option<(usize, usize)> {
+        match self {
+            BoardLoadError::Json { source, .. } => Some((source.line(), source.column())),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for BoardLoadError {
@@ -473,6 +852,15 @@ impl std::fmt::Display for BoardLoadError {
                 board_path.display(),
                 source
             ),
+            BoardLoadError::Text {
+                board_path,
+                message,
+            } => write!(
+                f,
+                "board text parse failed for '{}': {}",
+                board_path.display(),
+                message
+            ),
             BoardLoadError::MissingStartRoom { board_path, role } => write!(
                 f,
                 "board '{}' missing start room for {}",
@@ -482,6 +870,15 @@ impl std::fmt::Display for BoardLoadError {
             BoardLoadError::EmbeddedBoardNotFound { board_name } => {
                 write!(f, "embedded board '{}' not found", board_name)
             }
+            BoardLoadError::InvalidSpec {
+                board_path,
+                problems,
+            } => write!(
+                f,
+                "board '{}' is invalid: {}",
+                board_path.display(),
+                problems.join("; ")
+            ),
         }
     }
 }
@@ -491,36 +888,58 @@ impl std::error::Error for BoardLoadError {
         match self {
             BoardLoadError::Io { source, .. } => Some(source),
             BoardLoadError::Json { source, .. } => Some(source),
+            BoardLoadError::Text { .. } => None,
             BoardLoadError::MissingStartRoom { .. } => None,
             BoardLoadError::EmbeddedBoardNotFound { .. } => None,
+            BoardLoadError::InvalidSpec { .. } => None,
         }
     }
 }
 
-fn embedded_board_entry(board_name: &str) -> Option<(&'static str, &'static str)> {
-    let trimmed = trim_json_suffix(board_name);
-    let normalized = strip_board_prefix(trimmed);
-    EMBEDDED_BOARD_DATA.iter().copied().find(|(name, _)| {
-        name.eq_ignore_ascii_case(trimmed) || name.eq_ignore_ascii_case(normalized)
-    })
-}
+// problems that would otherwise panic while building the board's matrices
+fn spec_problems(spec: &BoardSpecification) -> Vec<String> {
+    let mut problems = Vec::new();
+    if spec.rooms.is_empty() {
+        problems.push("no rooms".to_string());
+    }
 
-fn trim_json_suffix(board_name: &str) -> &str {
-    let lower = board_name.to_ascii_lowercase();
-    if lower.ends_with(".json") {
-        &board_name[..board_name.len() - 5]
-    } else {
-        board_name
+    let mut seen_room_ids = HashSet::new();
+    for room in &spec.rooms {
+        if room.id.0 == 0 || room.id.0 > MAX_BOARD_ROOM_ID {
+            problems.push(format!(
+                "room id {} is outside 1..={MAX_BOARD_ROOM_ID}",
+                room.id
+            ));
+        }
+        if !seen_room_ids.insert(room.id) {
+            problems.push(format!("room id {} is used more than once", room.id));
+        }
     }
-}
 
-fn strip_board_prefix(board_name: &str) -> &str {
-    let prefix_len = "board".len();
-    if board_name.len() >= prefix_len && board_name[..prefix_len].eq_ignore_ascii_case("board") {
-        &board_name[prefix_len..]
-    } else {
-        board_name
+    // split boards like LairSouth still list rooms from the other half, which is fine as long as
+    // the id fits in the matrices
+    let max_room_id = seen_room_ids
+        .iter()
+        .map(|room_id| room_id.0)
+        .filter(|room_id| *room_id <= MAX_BOARD_ROOM_ID)
+        .max()
+        .unwrap_or(0);
+    for room in &spec.rooms {
+        for (list_name, other_room_ids) in
+            [("adjacent", &room.adjacent), ("visible", &room.visible)]
+        {
+            for other_room_id in other_room_ids {
+                if other_room_id.0 > max_room_id {
+                    problems.push(format!(
+                        "room {} has {list_name} room {other_room_id} past the highest room id",
+                        room.id
+                    ));
+                }
+            }
+        }
     }
+
+    problems
 }
 
 fn embedded_board_path(board_name: &str) -> PathBuf {
@@ -529,8 +948,8 @@ fn embedded_board_path(board_name: &str) -> PathBuf {
 
 fn distance_to_stranger_loop_info(
     room_ids: &[RoomId],
-    dist: &[Vec<i32>],
-    sight: &[Vec<bool>],
+    dist: &RoomMatrix<i32>,
+    sight: &RoomMatrix<bool>,
 ) -> HashMap<RoomId, HashSet<RoomId>> {
     let mut enemy_rooms = HashSet::new();
     let mut ally_rooms = HashSet::new();
@@ -540,11 +959,11 @@ fn distance_to_stranger_loop_info(
         let plus2 = Board::next_room_id(*room_id, 2, room_ids);
         let plus3 = Board::next_room_id(*room_id, 3, room_ids);
 
-        if dist[room_id.0][plus2.0] <= 1 {
+        if dist[(*room_id, plus2)] <= 1 {
             enemy_rooms.insert(plus1);
         }
 
-        if dist[room_id.0][plus3.0] <= 1 && !sight[plus1.0][plus3.0] {
+        if dist[(*room_id, plus3)] <= 1 && !sight[(plus1, plus3)] {
             ally_rooms.insert(plus1);
         }
     }
@@ -557,8 +976,8 @@ fn distance_to_stranger_loop_info(
         let mut working_ally_rooms = HashSet::new();
 
         for ally_room in &ally_rooms {
-            if !sight[ally_room.0][enemy_room.0]
-                && !sight[ally_room.0][enemy_minus1.0]
+            if !sight[(*ally_room, enemy_room)]
+                && !sight[(*ally_room, enemy_minus1)]
                 && *ally_room != enemy_minus2
             {
                 working_ally_rooms.insert(*ally_room);
@@ -573,21 +992,52 @@ fn distance_to_stranger_loop_info(
     info
 }
 
-fn adjacency_to_distance(adjacency: &[Vec<bool>]) -> Vec<Vec<i32>> {
-    let dim = adjacency.len();
-    let mut distance = vec![vec![0; dim]; dim];
+// the automorphisms of the room graph that keep both adjacency and sight. the doctor always moves
+// on to the next room in visit order, so a relabeling must also carry each room's successor to the
+// new room's successor; that leaves only rotations of the visit order, and rules out reflections,
+// which would send the doctor backward
+fn room_symmetries(
+    matrix_dim: usize,
+    room_ids: &[RoomId],
+    adjacency: &RoomMatrix<bool>,
+    sight: &RoomMatrix<bool>,
+) -> Vec<RoomSymmetry> {
+    let num_rooms = room_ids.len();
+    (1..num_rooms)
+        .map(|shift| {
+            let mut room_map = (0..matrix_dim).map(RoomId).collect::<Vec<_>>();
+            for (idx, room_id) in room_ids.iter().enumerate() {
+                room_map[room_id.0] = room_ids[(idx + shift) % num_rooms];
+            }
+            RoomSymmetry { room_map }
+        })
+        .filter(|symmetry| {
+            room_ids.iter().all(|room_a| {
+                room_ids.iter().all(|room_b| {
+                    let (mapped_a, mapped_b) = (symmetry.apply(*room_a), symmetry.apply(*room_b));
+                    adjacency[(*room_a, *room_b)] == adjacency[(mapped_a, mapped_b)]
+                        && sight[(*room_a, *room_b)] == sight[(mapped_a, mapped_b)]
+                })
+            })
+        })
+        .collect::<Vec<_>>()
+}
+
+fn adjacency_to_distance(adjacency: &RoomMatrix<bool>) -> RoomMatrix<i32> {
+    let dim = adjacency.dim();
+    let mut distance = RoomMatrix::new(dim, 0);
 
-    for r in 0..dim {
-        for c in 0..dim {
+    for r in adjacency.room_ids() {
+        for c in adjacency.room_ids() {
             let initial_dist = if r == c {
                 0
-            } else if adjacency[r][c] {
+            } else if adjacency[(r, c)] {
                 1
             } else {
                 999
             };
 
-            distance[r][c] = initial_dist;
+            distance[(r, c)] = initial_dist;
         }
     }
 
@@ -595,18 +1045,18 @@ fn adjacency_to_distance(adjacency: &[Vec<bool>]) -> Vec<Vec<i32>> {
     while is_improving_distance {
         is_improving_distance = false;
 
-        for source in 1..dim {
-            for destination in 1..dim {
+        for source in (1..dim).map(RoomId) {
+            for destination in (1..dim).map(RoomId) {
                 if source == destination {
                     continue;
                 }
 
-                for intermediate in 1..dim {
+                for intermediate in (1..dim).map(RoomId) {
                     let distance_via_intermediate =
-                        distance[source][intermediate] + distance[intermediate][destination];
+                        distance[(source, intermediate)] + distance[(intermediate, destination)];
 
-                    if distance_via_intermediate < distance[source][destination] {
-                        distance[source][destination] = distance_via_intermediate;
+                    if distance_via_intermediate < distance[(source, destination)] {
+                        distance[(source, destination)] = distance_via_intermediate;
                         is_improving_distance = true;
                     }
                 }
@@ -627,27 +1077,30 @@ fn positive_remainder(x: i32, modulus: usize) -> usize {
     }
 }
 
+// ids between the board's rooms get the index of the next room up, wrapping, as if the doctor
+// passed through them
 fn room_visit_order_index(matrix_dim: usize, room_ids: &[RoomId]) -> Vec<usize> {
-    let mut indices = vec![usize::MAX; matrix_dim];
-    for (idx, room_id) in room_ids.iter().enumerate() {
-        indices[room_id.0] = idx;
-    }
-    indices
+    (0..matrix_dim)
+        .map(|id| {
+            room_ids
+                .binary_search_by_key(&id, |room_id| room_id.0)
+                .unwrap_or_else(|insert_idx| insert_idx % room_ids.len().max(1))
+        })
+        .collect::<Vec<_>>()
 }
 
 fn doctor_future_visit_distance(
     room_ids: &[RoomId],
     room_visit_order_index: &[usize],
-) -> Vec<Vec<i32>> {
-    let matrix_dim = room_visit_order_index.len();
-    let mut distances = vec![vec![0; matrix_dim]; matrix_dim];
+) -> RoomMatrix<i32> {
+    let mut distances = RoomMatrix::new(room_visit_order_index.len(), 0);
 
     for start_room_id in room_ids {
         let start_idx = room_visit_order_index[start_room_id.0];
         for target_room_id in room_ids {
             let target_idx = room_visit_order_index[target_room_id.0];
             let delta = positive_remainder(target_idx as i32 - start_idx as i32, room_ids.len());
-            distances[start_room_id.0][target_room_id.0] = delta as i32 + 1;
+            distances[(*start_room_id, *target_room_id)] = delta as i32 + 1;
         }
     }
 
@@ -656,23 +1109,22 @@ fn doctor_future_visit_distance(
 
 fn doctor_future_near_distance(
     room_ids: &[RoomId],
-    distance: &[Vec<i32>],
-    doctor_future_visit_distance: &[Vec<i32>],
-) -> Vec<Vec<i32>> {
-    let matrix_dim = distance.len();
-    let mut near_distances = vec![vec![0; matrix_dim]; matrix_dim];
+    distance: &RoomMatrix<i32>,
+    doctor_future_visit_distance: &RoomMatrix<i32>,
+) -> RoomMatrix<i32> {
+    let mut near_distances = RoomMatrix::new(distance.dim(), 0);
 
     for start_room_id in room_ids {
         for target_room_id in room_ids {
             let nearest_doctor_step = room_ids
                 .iter()
-                .filter(|doctor_room_id| distance[target_room_id.0][doctor_room_id.0] <= 1)
+                .filter(|doctor_room_id| distance[(*target_room_id, **doctor_room_id)] <= 1)
                 .map(|doctor_room_id| {
-                    doctor_future_visit_distance[start_room_id.0][doctor_room_id.0]
+                    doctor_future_visit_distance[(*start_room_id, *doctor_room_id)]
                 })
                 .min()
                 .expect("room should eventually be visited by doctor");
-            near_distances[start_room_id.0][target_room_id.0] = nearest_doctor_step;
+            near_distances[(*start_room_id, *target_room_id)] = nearest_doctor_step;
         }
     }
 
@@ -715,10 +1167,89 @@ mod tests {
             None,
         );
 
-        assert!(board.adjacency[1][2]);
-        assert!(board.adjacency[2][1]);
+        assert!(board.adjacency[(RoomId(1), RoomId(2))]);
+        assert!(board.adjacency[(RoomId(2), RoomId(1))]);
         assert_eq!(board.adjacency_count[2], 2);
-        assert_eq!(board.distance[1][4], 3);
+        assert_eq!(board.distance[(RoomId(1), RoomId(4))], 3);
+    }
+
+    #[test]
+    fn structure_hash_ignores_names_but_not_doors() {
+        let board = |name: &str, rooms: Vec<Room>| {
+            Board::new(
+                name,
+                rooms,
+                RoomId(1),
+                RoomId(1),
+                RoomId(1),
+                RoomId(1),
+                None,
+            )
+        };
+        let renamed_rooms = sample_rooms()
+            .iter()
+            .map(|room| {
+                Room::new(
+                    room.id,
+                    "renamed",
+                    room.adjacent.clone(),
+                    room.visible.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let original = board("test", sample_rooms());
+        assert_eq!(
+            original.structure_hash(),
+            board("other", renamed_rooms).structure_hash()
+        );
+
+        let rewired_rooms = sample_rooms()
+            .iter()
+            .map(|room| {
+                let extra_sight = match room.id.0 {
+                    1 => Some(RoomId(3)),
+                    3 => Some(RoomId(1)),
+                    _ => None,
+                };
+                Room::new(
+                    room.id,
+                    room.name.clone(),
+                    room.adjacent.clone(),
+                    room.visible.iter().copied().chain(extra_sight),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_ne!(
+            original.structure_hash(),
+            board("test", rewired_rooms).structure_hash()
+        );
+    }
+
+    #[test]
+    fn doctor_moves_between_wraps_and_places_missing_rooms_in_order() {
+        let board = Board::new(
+            "test",
+            sample_rooms(),
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            None,
+        );
+
+        assert_eq!(board.doctor_moves_between(RoomId(2), RoomId(4)), 2);
+        assert_eq!(board.doctor_moves_between(RoomId(4), RoomId(2)), 2);
+        assert_eq!(board.doctor_moves_between(RoomId(3), RoomId(3)), 0);
+        // room 0 sits where room 1 does, and ids past the board wrap to the first room
+        assert_eq!(board.doctor_moves_between(RoomId(2), RoomId(0)), 3);
+        assert_eq!(board.doctor_moves_between(RoomId(2), RoomId(99)), 3);
+        for room_id in &board.room_ids {
+            assert_eq!(
+                board.room_ids_in_doctor_visit_order(*room_id)
+                    [board.doctor_moves_between(*room_id, RoomId(1)) as usize],
+                RoomId(1)
+            );
+        }
     }
 
     #[test]
@@ -741,6 +1272,89 @@ mod tests {
         );
     }
 
+    #[test]
+    fn generated_boards_are_valid_and_connected() {
+        for board_name in Board::generated_board_names() {
+            let board = Board::from_embedded_json(board_name).expect("generated board should load");
+            assert_eq!(board.name, board_name);
+            assert_eq!(board.is_valid(), Ok(()));
+            assert!(
+                board
+                    .room_ids
+                    .iter()
+                    .cartesian_product(&board.room_ids)
+                    .all(|(from, to)| board.distance[(*from, *to)] < 999)
+            );
+        }
+
+        let ring = Board::ring(6);
+        assert_eq!(ring.distance[(RoomId(1), RoomId(4))], 3);
+        assert_eq!(ring.distance[(RoomId(1), RoomId(6))], 1);
+        assert!(!ring.sight[(RoomId(1), RoomId(3))]);
+
+        let grid = Board::grid(3, 2, GridSightRule::Adjacent);
+        assert_eq!(grid.distance[(RoomId(1), RoomId(6))], 3);
+        assert!(!grid.sight[(RoomId(1), RoomId(3))]);
+        let line_grid = Board::grid(3, 2, GridSightRule::Line);
+        assert!(line_grid.sight[(RoomId(1), RoomId(3))]);
+        assert!(!line_grid.sight[(RoomId(1), RoomId(5))]);
+    }
+
+    #[test]
+    fn generated_board_names_parse_sizes_and_reject_nonsense() {
+        let board =
+            Board::from_generated_name("BoardGrid5x2Line.json").expect("grid name should parse");
+        assert_eq!(board.name, "Grid5x2Line");
+        assert_eq!(board.json_name, "BoardGrid5x2Line");
+        assert_eq!(board.room_ids.len(), 10);
+
+        assert!(Board::from_generated_name("ring2").is_none());
+        assert!(Board::from_generated_name("grid0x9").is_none());
+        assert!(Board::from_generated_name("grid4294967297x4294967296").is_none());
+        assert!(Board::from_generated_name("grid101x1").is_none());
+        assert!(Board::from_generated_name("ring18446744073709551616").is_none());
+        assert!(Board::from_generated_name("grid50x50").is_none());
+        assert!(Board::from_generated_name("gridax3").is_none());
+        assert!(Board::from_embedded_json("Ring").is_err());
+    }
+
+    #[test]
+    fn room_labels_and_name_lookup_use_room_names() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let parlor_id = board
+            .room_id_by_name("  Parlor ")
+            .expect("parlor should be found ignoring case");
+
+        assert_eq!(
+            board.room_label(parlor_id),
+            format!("parlor (R{})", parlor_id.0)
+        );
+        assert_eq!(board.room_label(RoomId(99)), "R99");
+        assert_eq!(board.room_id_by_name("ballroom annex"), None);
+        assert_eq!(Board::ring(4).room_label(RoomId(2)), "R2");
+    }
+
+    #[test]
+    fn resolve_room_name_takes_unique_prefixes_and_reports_ambiguity() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let drawing_room_id = board
+            .room_id_by_name("drawing room")
+            .expect("drawing room should exist");
+
+        assert_eq!(board.resolve_room_name("Drawing_Room"), Ok(drawing_room_id));
+        assert_eq!(board.resolve_room_name("dr"), Ok(drawing_room_id));
+        assert_eq!(
+            board.resolve_room_name("zzz").map_err(|err| err.code()),
+            Err("unknownRoomName")
+        );
+        let Err(ValidationError::AmbiguousRoomName { candidates, .. }) =
+            board.resolve_room_name("d")
+        else {
+            panic!("'d' should match both drawing room and dining hall");
+        };
+        assert_eq!(candidates.len(), 2);
+    }
+
     #[test]
     fn next_room_id_handles_negative_delta() {
         let ids = vec![RoomId(1), RoomId(2), RoomId(3)];
@@ -792,6 +1406,49 @@ mod tests {
         assert_eq!(spec.rooms[0].id, RoomId(1));
     }
 
+    #[test]
+    fn wings_report_their_rooms_and_whether_they_are_closed() {
+        let json = r#"{
+            "Name": "winged",
+            "PlayerStartRoomIds": [1],
+            "DoctorStartRoomIds": [3, 1],
+            "CatStartRoomIds": [1],
+            "DogStartRoomIds": [1],
+            "Wings": [
+                { "Name": "west", "RoomIds": [2] },
+                { "Name": "east", "RoomIds": [3] }
+            ],
+            "Rooms": [
+                { "Id": 1, "Name": "hall", "Adjacent": [2, 3], "Visible": [] },
+                { "Id": 2, "Name": "study", "Adjacent": [1], "Visible": [] },
+                { "Id": 3, "Name": "attic", "Adjacent": [1], "Visible": [] }
+            ]
+        }"#;
+        let spec = BoardSpecification::from_json_str(json).unwrap();
+        let board = Board::from_spec(spec, ["EAST"], "", PathBuf::from("winged.json"), None)
+            .expect("board should build with a closed wing");
+
+        let wing_names = board
+            .wings()
+            .iter()
+            .map(|wing| wing.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(wing_names, ["west", "east"]);
+        assert!(!board.is_wing_closed(&board.wings()[0]));
+        assert!(board.is_wing_closed(&board.wings()[1]));
+        assert_eq!(
+            board.wing_of(RoomId(2)).map(|wing| wing.name.as_str()),
+            Some("west")
+        );
+        assert!(board.wing_of(RoomId(1)).is_none());
+        assert!(
+            Board::from_embedded_json("Tiny")
+                .unwrap()
+                .wings()
+                .is_empty()
+        );
+    }
+
     #[test]
     fn embedded_board_json_name_uses_source_board_key() {
         let board = Board::from_embedded_json("BoardAltDown").unwrap();
@@ -879,4 +1536,81 @@ mod tests {
         assert_eq!(board.doctor_start_room_id, RoomId(15));
         assert!(board.is_valid().is_ok());
     }
+
+    #[test]
+    fn board_file_parse_error_reports_line_and_column() {
+        let board_path =
+            std::env::temp_dir().join(format!("kdl_board_parse_error_{}.json", std::process::id()));
+        fs::write(&board_path, "{\n  \"Rooms\": [\n    oops\n  ]\n}\n")
+            .expect("temp board should be writable");
+
+        let err = Board::from_json_file(&board_path).expect_err("malformed board should fail");
+        let _ = fs::remove_file(&board_path);
+
+        assert_eq!(err.json_line_column(), Some((3, 5)));
+        assert_eq!(err.board_path(), Some(board_path.as_path()));
+    }
+
+    #[test]
+    fn board_json_with_unknown_or_huge_room_ids_is_a_load_error() {
+        let board_json = r#"{
+            "Name": "Broken",
+            "PlayerStartRoomIds": [1],
+            "DoctorStartRoomIds": [2],
+            "CatStartRoomIds": [1],
+            "DogStartRoomIds": [1],
+            "Rooms": [
+                {"Id": "1", "Name": "A", "Adjacent": [2, 7], "Visible": [2]},
+                {"Id": "2", "Name": "B", "Adjacent": [1], "Visible": [1]},
+                {"Id": "4294967296", "Name": "C", "Adjacent": [], "Visible": []},
+                {"Id": "2", "Name": "D", "Adjacent": [], "Visible": []}
+            ]
+        }"#;
+
+        let err = Board::from_json_str(board_json, "BoardBroken").expect_err("board should fail");
+        let BoardLoadError::InvalidSpec { problems, .. } = &err else {
+            panic!("expected InvalidSpec, got {err}");
+        };
+        assert_eq!(
+            problems,
+            &vec![
+                "room id 4294967296 is outside 1..=255".to_string(),
+                "room id 2 is used more than once".to_string(),
+                "room 1 has adjacent room 7 past the highest room id".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn symmetries_are_rotations_of_the_visit_order_that_keep_adjacency_and_sight() {
+        let ring = Board::ring(6);
+        assert_eq!(ring.symmetries.len(), 5);
+        let rotation = &ring.symmetries[0];
+        assert_eq!(rotation.apply(RoomId(1)), RoomId(2));
+        assert_eq!(rotation.apply(RoomId(6)), RoomId(1));
+        assert_eq!(rotation.apply(RoomId(0)), RoomId(0));
+        assert_eq!(rotation.inverse().apply(RoomId(1)), RoomId(6));
+
+        // a path isn't a cycle, so rotating it breaks adjacency
+        let path = Board::new(
+            "path",
+            sample_rooms(),
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            None,
+        );
+        assert!(path.symmetries.is_empty());
+        let alt_down = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        assert!(alt_down.symmetries.is_empty());
+    }
+
+    #[test]
+    fn next_room_id_steps_from_gap_for_missing_room() {
+        let ids = vec![RoomId(1), RoomId(3), RoomId(5)];
+        assert_eq!(Board::next_room_id(RoomId(2), 1, &ids), RoomId(3));
+        assert_eq!(Board::next_room_id(RoomId(2), -1, &ids), RoomId(1));
+        assert_eq!(Board::next_room_id(RoomId(6), 1, &ids), RoomId(1));
+    }
 }
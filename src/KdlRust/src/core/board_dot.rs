@@ -0,0 +1,143 @@
+use crate::core::{board::Board, room::RoomId};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DotOptions {
+    pub sight_edges: bool,      // dashed edges between rooms that see each other
+    pub wing_clusters: bool,    // a box around each open wing's rooms
+    pub start_highlights: bool, // fill the rooms pieces start in, labeled with who starts there
+}
+
+impl Default for DotOptions {
+    fn default() -> Self {
+        Self {
+            sight_edges: false,
+            wing_clusters: true,
+            start_highlights: true,
+        }
+    }
+}
+
+// an undirected graph of the open rooms; a pair is joined when either room lists the other
+pub fn board_to_dot(board: &Board, options: &DotOptions) -> String {
+    let mut lines = vec![
+        format!("graph {} {{", quoted(&board.name)),
+        "  node [shape=box];".to_string(),
+    ];
+
+    if options.wing_clusters {
+        for (wing_idx, wing) in board.wings().iter().enumerate() {
+            let open_room_ids = wing
+                .room_ids
+                .iter()
+                .filter(|room_id| board.rooms.contains_key(room_id))
+                .map(|room_id| room_id.to_string())
+                .collect::<Vec<_>>();
+            if open_room_ids.is_empty() {
+                continue;
+            }
+            lines.push(format!(
+                "  subgraph cluster_{wing_idx} {{ label={}; {}; }}",
+                quoted(&wing.name),
+                open_room_ids.join("; ")
+            ));
+        }
+    }
+
+    let starts = [
+        ("player start", board.player_start_room_id),
+        ("doctor start", board.doctor_start_room_id),
+        ("cat start", board.cat_start_room_id),
+        ("dog start", board.dog_start_room_id),
+    ];
+    for room_id in &board.room_ids {
+        let starters = starts
+            .iter()
+            .filter(|(_, start_room_id)| options.start_highlights && start_room_id == room_id)
+            .map(|(role, _)| role.to_string())
+            .collect::<Vec<_>>();
+        let label = std::iter::once(board.room_label(*room_id))
+            .chain(starters.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let style = if starters.is_empty() {
+            ""
+        } else {
+            ", style=filled, fillcolor=lightyellow"
+        };
+        lines.push(format!("  {room_id} [label={}{style}];", quoted(&label)));
+    }
+
+    for (room_a, room_b) in room_pairs(board) {
+        if board.adjacency[(room_a, room_b)] || board.adjacency[(room_b, room_a)] {
+            lines.push(format!("  {room_a} -- {room_b};"));
+        }
+    }
+    if options.sight_edges {
+        for (room_a, room_b) in room_pairs(board) {
+            if board.sight[(room_a, room_b)] || board.sight[(room_b, room_a)] {
+                lines.push(format!(
+                    "  {room_a} -- {room_b} [style=dashed, color=gray, constraint=false];"
+                ));
+            }
+        }
+    }
+
+    lines.push("}".to_string());
+    lines.join("\n") + "\n"
+}
+
+fn room_pairs(board: &Board) -> impl Iterator<Item = (RoomId, RoomId)> + '_ {
+    board.room_ids.iter().enumerate().flat_map(|(idx, room_a)| {
+        board.room_ids[idx + 1..]
+            .iter()
+            .map(move |room_b| (*room_a, *room_b))
+    })
+}
+
+fn quoted(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_lists_rooms_edges_and_starts() {
+        let board = Board::from_text_str(
+            "name\ttri\"angle\n\
+             player\t1\n\
+             doctor\t2\n\
+             cat\t2\n\
+             dog\t3\n\
+             room\t1\thall\t2\t2,3\n\
+             room\t2\tkitchen\t1,3\t1\teast\n\
+             room\t3\tcellar\t2\t-\teast\n",
+            "Triangle",
+        )
+        .expect("board should load");
+
+        let dot = board.to_dot(&DotOptions::default());
+        assert!(dot.starts_with("graph \"tri\\\"angle\" {\n"));
+        assert!(dot.contains("  subgraph cluster_0 { label=\"east\"; 2; 3; }\n"));
+        assert!(dot.contains(
+            "  2 [label=\"kitchen (R2)\\ndoctor start\\ncat start\", style=filled, fillcolor=lightyellow];\n"
+        ));
+        assert!(dot.contains("  1 -- 2;\n") && dot.contains("  2 -- 3;\n"));
+        assert!(!dot.contains("  1 -- 3;\n"));
+        assert!(!dot.contains("dashed"));
+
+        let sight_dot = board.to_dot(&DotOptions {
+            sight_edges: true,
+            wing_clusters: false,
+            start_highlights: false,
+        });
+        assert!(sight_dot.contains("  1 -- 3 [style=dashed"));
+        assert!(!sight_dot.contains("cluster") && !sight_dot.contains("start"));
+        assert!(sight_dot.ends_with("}\n"));
+    }
+}
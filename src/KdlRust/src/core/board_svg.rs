@@ -0,0 +1,195 @@
+use crate::core::{mutable_game_state::MutableGameState, player::PlayerId, room::RoomId};
+use crate::util::layout;
+use std::collections::HashMap;
+
+const SIZE: f64 = 640.0;
+const MARGIN: f64 = 48.0;
+const ROOM_RADIUS: f64 = 18.0;
+const PIECE_RADIUS: f64 = 7.0;
+const LAYOUT_ITERATIONS: usize = 300;
+// how many rooms of the doctor's upcoming visits the path shows, the current room included
+pub const DOCTOR_PATH_ROOMS: usize = 5;
+// by controlling normal player, so a player and its strangers share a color
+const PIECE_COLORS: [&str; 6] = [
+    "#1f77b4", "#ff7f0e", "#2ca02c", "#9467bd", "#8c564b", "#17becf",
+];
+const DOCTOR_COLOR: &str = "#d62728";
+
+// a picture of the position: rooms laid out by board_room_positions, adjacency lines, the
+// doctor's next few rooms as a dashed path, and every piece beside its room. strangers are hollow
+pub fn render_svg(state: &MutableGameState) -> String {
+    let board = &state.common.board;
+    let positions = board_room_positions(state);
+    let at = |room_id: &RoomId| positions.get(room_id).copied().unwrap_or((0.0, 0.0));
+    let mut parts = vec![
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{SIZE}\" height=\"{SIZE}\" viewBox=\"0 0 {SIZE} {SIZE}\" font-family=\"sans-serif\">"
+        ),
+        format!("<rect width=\"{SIZE}\" height=\"{SIZE}\" fill=\"white\"/>"),
+        format!(
+            "<text x=\"{MARGIN}\" y=\"24\" font-size=\"16\">{} - turn {}, {} to move</text>",
+            escaped(&board.name),
+            state.turn_id,
+            escaped(&state.player_text_for(state.current_player_id))
+        ),
+    ];
+
+    for (idx, room_a) in board.room_ids.iter().enumerate() {
+        for room_b in &board.room_ids[idx + 1..] {
+            if board.adjacency[(*room_a, *room_b)] || board.adjacency[(*room_b, *room_a)] {
+                let ((x1, y1), (x2, y2)) = (at(room_a), at(room_b));
+                parts.push(format!(
+                    "<line x1=\"{x1:.1}\" y1=\"{y1:.1}\" x2=\"{x2:.1}\" y2=\"{y2:.1}\" stroke=\"#bbbbbb\"/>"
+                ));
+            }
+        }
+    }
+
+    let doctor_path = board
+        .room_ids_in_doctor_visit_order(state.doctor_room_id)
+        .iter()
+        .take(DOCTOR_PATH_ROOMS)
+        .map(|room_id| {
+            let (x, y) = at(room_id);
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>();
+    parts.push(format!(
+        "<polyline points=\"{}\" fill=\"none\" stroke=\"{DOCTOR_COLOR}\" stroke-width=\"2\" stroke-dasharray=\"6,4\" opacity=\"0.7\"/>",
+        doctor_path.join(" ")
+    ));
+
+    for room_id in &board.room_ids {
+        let (x, y) = at(room_id);
+        let name = board
+            .rooms
+            .get(room_id)
+            .map(|room| room.name.as_str())
+            .unwrap_or_default();
+        parts.push(format!(
+            "<g><title>{}</title><circle cx=\"{x:.1}\" cy=\"{y:.1}\" r=\"{ROOM_RADIUS}\" fill=\"#f7f7f7\" stroke=\"#333333\"/><text x=\"{x:.1}\" y=\"{:.1}\" font-size=\"12\" text-anchor=\"middle\">{room_id}</text></g>",
+            escaped(name),
+            y + 4.0
+        ));
+    }
+
+    let seats = state.common.seat_map();
+    let mut occupants = HashMap::<RoomId, Vec<Option<PlayerId>>>::new();
+    occupants
+        .entry(state.doctor_room_id)
+        .or_default()
+        .push(None);
+    for (player_idx, room_id) in state.player_room_ids().iter().enumerate() {
+        occupants
+            .entry(*room_id)
+            .or_default()
+            .push(Some(PlayerId(player_idx)));
+    }
+    for room_id in &board.room_ids {
+        let Some(pieces) = occupants.get(room_id) else {
+            continue;
+        };
+        let (x, y) = at(room_id);
+        for (slot, piece) in pieces.iter().enumerate() {
+            let angle = std::f64::consts::TAU * slot as f64 / pieces.len() as f64
+                - std::f64::consts::FRAC_PI_2;
+            let distance = ROOM_RADIUS + PIECE_RADIUS + 2.0;
+            let (px, py) = (x + distance * angle.cos(), y + distance * angle.sin());
+            let (label, color, fill) = match piece {
+                None => ("Dr".to_string(), DOCTOR_COLOR, DOCTOR_COLOR),
+                Some(player_id) => {
+                    let color =
+                        PIECE_COLORS[seats.controller_of(*player_id).0 % PIECE_COLORS.len()];
+                    let fill = if seats.is_stranger(*player_id) {
+                        "white"
+                    } else {
+                        color
+                    };
+                    (state.player_text_for(*player_id), color, fill)
+                }
+            };
+            parts.push(format!(
+                "<g><title>{}</title><circle cx=\"{px:.1}\" cy=\"{py:.1}\" r=\"{PIECE_RADIUS}\" fill=\"{fill}\" stroke=\"{color}\" stroke-width=\"2\"/></g>",
+                escaped(&label)
+            ));
+        }
+    }
+
+    parts.push("</svg>".to_string());
+    parts.join("\n") + "\n"
+}
+
+// room centers in svg coordinates; the board has no coordinates of its own, so rooms are placed
+// by a spring layout of the adjacency graph
+pub fn board_room_positions(state: &MutableGameState) -> HashMap<RoomId, (f64, f64)> {
+    let board = &state.common.board;
+    let edges = board
+        .room_ids
+        .iter()
+        .enumerate()
+        .flat_map(|(idx_a, room_a)| {
+            board.room_ids[idx_a + 1..]
+                .iter()
+                .enumerate()
+                .filter(|(_, room_b)| board.adjacency[(*room_a, **room_b)])
+                .map(move |(offset, _)| (idx_a, idx_a + 1 + offset))
+        })
+        .collect::<Vec<_>>();
+    let span = SIZE - 2.0 * MARGIN;
+    layout::force_directed_layout(board.room_ids.len(), &edges, LAYOUT_ITERATIONS)
+        .into_iter()
+        .zip(&board.room_ids)
+        .map(|((x, y), room_id)| (*room_id, (MARGIN + x * span, MARGIN + y * span)))
+        .collect::<HashMap<_, _>>()
+}
+
+fn escaped(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+
+    #[test]
+    fn svg_draws_every_room_piece_and_the_doctor_path() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let state =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2));
+        let svg = render_svg(&state);
+
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg, render_svg(&state));
+        assert_eq!(
+            svg.matches(&format!("r=\"{ROOM_RADIUS}\"")).count(),
+            state.common.board.room_ids.len()
+        );
+        // every piece plus the doctor
+        assert_eq!(
+            svg.matches(&format!("r=\"{PIECE_RADIUS}\"")).count(),
+            state.player_room_ids().len() + 1
+        );
+        assert!(svg.contains("<title>Dr</title>"));
+        assert!(svg.contains("<title>p2</title>"));
+        let doctor_path = svg
+            .lines()
+            .find(|line| line.starts_with("<polyline"))
+            .expect("the doctor's path should be drawn");
+        assert_eq!(
+            doctor_path
+                .split('"')
+                .nth(1)
+                .map(|points| points.split(' ').count()),
+            Some(DOCTOR_PATH_ROOMS)
+        );
+
+        let positions = board_room_positions(&state);
+        assert!(positions.values().all(|(x, y)| {
+            (MARGIN..=SIZE - MARGIN).contains(x) && (MARGIN..=SIZE - MARGIN).contains(y)
+        }));
+    }
+}
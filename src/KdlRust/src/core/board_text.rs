@@ -0,0 +1,232 @@
+use crate::core::{
+    board::BoardSpecification,
+    room::{Room, RoomId},
+    wing::Wing,
+};
+
+// a tab-separated board format that's quicker to write by hand than board json:
+//   name    altdown
+//   player  6            (player, doctor, cat and dog list their start room choices)
+//   room    1   drawing room   2,6,5,7   2,5,6,10   west
+// a room line has its id, name, adjacent room ids, visible room ids (- for none) and optionally the
+// wings it belongs to; blank lines and lines starting with # are skipped
+const START_KEYS: [&str; 4] = ["player", "doctor", "cat", "dog"];
+const EMPTY_LIST: &str = "-";
+
+pub fn spec_from_text(text: &str) -> Result<BoardSpecification, String> {
+    let mut name = None;
+    let mut start_room_ids: [Option<Vec<RoomId>>; 4] = Default::default();
+    let mut rooms = Vec::new();
+    let mut wings = Vec::<Wing>::new();
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line_err = |message: String| format!("line {}: {message}", line_idx + 1);
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let fields = trimmed.split('\t').map(str::trim).collect::<Vec<_>>();
+        let key = fields[0].to_ascii_lowercase();
+        if key == "name" {
+            match fields.as_slice() {
+                [_, board_name] if !board_name.is_empty() => name = Some(board_name.to_string()),
+                _ => {
+                    return Err(line_err(
+                        "name needs one field, the board's name".to_string(),
+                    ));
+                }
+            }
+        } else if let Some(start_idx) = START_KEYS.iter().position(|start_key| *start_key == key) {
+            let [_, room_ids_text] = fields.as_slice() else {
+                return Err(line_err(format!("{key} needs one field of room ids")));
+            };
+            let room_ids = parse_room_ids(room_ids_text).map_err(line_err)?;
+            if room_ids.is_empty() {
+                return Err(line_err(format!("{key} needs at least one start room")));
+            }
+            start_room_ids[start_idx] = Some(room_ids);
+        } else if key == "room" {
+            let (id_text, room_name, adjacent_text, visible_text, wing_text) = match fields
+                .as_slice()
+            {
+                [_, id, room_name, adjacent, visible] => (*id, *room_name, *adjacent, *visible, ""),
+                [_, id, room_name, adjacent, visible, wing_names] => {
+                    (*id, *room_name, *adjacent, *visible, *wing_names)
+                }
+                _ => {
+                    return Err(line_err(
+                        "room needs id, name, adjacent ids, visible ids and optional wings"
+                            .to_string(),
+                    ));
+                }
+            };
+            let room_id = id_text
+                .parse::<usize>()
+                .map(RoomId)
+                .map_err(|_| line_err(format!("bad room id '{id_text}'")))?;
+            rooms.push(Room::new(
+                room_id,
+                room_name,
+                parse_room_ids(adjacent_text).map_err(line_err)?,
+                parse_room_ids(visible_text).map_err(line_err)?,
+            ));
+            for wing_name in wing_text.split(',').map(str::trim) {
+                if wing_name.is_empty() || wing_name == EMPTY_LIST {
+                    continue;
+                }
+                match wings.iter_mut().find(|wing| wing.name == wing_name) {
+                    Some(wing) => {
+                        *wing =
+                            Wing::new(wing_name, wing.room_ids.iter().copied().chain([room_id]));
+                    }
+                    None => wings.push(Wing::new(wing_name, [room_id])),
+                }
+            }
+        } else {
+            return Err(line_err(format!(
+                "unknown line kind '{}'; use name, {}, or room",
+                fields[0],
+                START_KEYS.join(", ")
+            )));
+        }
+    }
+
+    let name = name.ok_or("board text needs a name line")?;
+    let [player, doctor, cat, dog] = start_room_ids;
+    let missing = |start_key: &str| format!("board text needs a {start_key} start line");
+    Ok(BoardSpecification::new(
+        name,
+        player.ok_or_else(|| missing("player"))?,
+        doctor.ok_or_else(|| missing("doctor"))?,
+        cat.ok_or_else(|| missing("cat"))?,
+        dog.ok_or_else(|| missing("dog"))?,
+        wings,
+        rooms,
+    ))
+}
+
+// wings only come back for rooms the board lists, in the order those rooms first appear
+pub fn spec_to_text(spec: &BoardSpecification) -> String {
+    let mut lines = vec![format!("name\t{}", spec.name)];
+    for (start_key, room_ids) in START_KEYS.iter().zip([
+        &spec.player_start_room_ids,
+        &spec.doctor_start_room_ids,
+        &spec.cat_start_room_ids,
+        &spec.dog_start_room_ids,
+    ]) {
+        lines.push(format!("{start_key}\t{}", room_ids_text(room_ids)));
+    }
+    for room in &spec.rooms {
+        let wing_names = spec
+            .wings
+            .iter()
+            .filter(|wing| wing.room_ids.contains(&room.id))
+            .map(|wing| wing.name.as_str())
+            .collect::<Vec<_>>();
+        let mut line = format!(
+            "room\t{}\t{}\t{}\t{}",
+            room.id,
+            room.name,
+            room_ids_text(&room.adjacent),
+            room_ids_text(&room.visible)
+        );
+        if !wing_names.is_empty() {
+            line.push('\t');
+            line.push_str(&wing_names.join(","));
+        }
+        lines.push(line);
+    }
+    lines.join("\n") + "\n"
+}
+
+fn parse_room_ids(text: &str) -> Result<Vec<RoomId>, String> {
+    if text.is_empty() || text == EMPTY_LIST {
+        return Ok(Vec::new());
+    }
+    text.split(',')
+        .map(|id_text| {
+            let id_text = id_text.trim();
+            id_text
+                .parse::<usize>()
+                .map(RoomId)
+                .map_err(|_| format!("bad room id '{id_text}'"))
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
+fn room_ids_text(room_ids: &[RoomId]) -> String {
+    if room_ids.is_empty() {
+        return EMPTY_LIST.to_string();
+    }
+    room_ids
+        .iter()
+        .map(|room_id| room_id.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::board::Board;
+
+    const TRIANGLE_TEXT: &str = "# three rooms in a loop\n\
+        name\ttriangle\n\
+        player\t1\n\
+        doctor\t2\n\
+        cat\t3\n\
+        dog\t3,1\n\
+        room\t1\thall\t2,3\t2\n\
+        room\t2\tkitchen\t1,3\t1\teast\n\
+        room\t3\tcellar\t1,2\t-\teast,down\n";
+
+    #[test]
+    fn text_loads_as_a_board_and_writes_back_the_same() {
+        let spec = spec_from_text(TRIANGLE_TEXT).expect("text should parse");
+        assert_eq!(spec.dog_start_room_ids, vec![RoomId(3), RoomId(1)]);
+        assert_eq!(
+            spec.wings,
+            vec![
+                Wing::new("east", [RoomId(2), RoomId(3)]),
+                Wing::new("down", [RoomId(3)]),
+            ]
+        );
+        assert!(spec.rooms[2].visible.is_empty());
+        assert_eq!(
+            spec_to_text(&spec),
+            TRIANGLE_TEXT.trim_start_matches("# three rooms in a loop\n")
+        );
+
+        let board = Board::from_text_str(TRIANGLE_TEXT, "Triangle").expect("board should load");
+        assert_eq!(board.room_ids.len(), 3);
+        assert_eq!(board.distance[(RoomId(1), RoomId(3))], 1);
+    }
+
+    #[test]
+    fn embedded_boards_survive_a_round_trip_through_text() {
+        let specs = Board::embedded_board_names()
+            .filter_map(|board_name| Board::from_embedded_json(board_name).ok()?.spec.clone())
+            .collect::<Vec<_>>();
+        assert!(!specs.is_empty());
+        for spec in specs {
+            assert_eq!(spec_from_text(&spec_to_text(&spec)), Ok(spec));
+        }
+    }
+
+    #[test]
+    fn mistakes_name_their_line() {
+        let bad_id = TRIANGLE_TEXT.replace("room\t2\t", "room\ttwo\t");
+        assert_eq!(
+            spec_from_text(&bad_id),
+            Err("line 8: bad room id 'two'".to_string())
+        );
+        let short_room = TRIANGLE_TEXT.replace("\t2,3\t2\n", "\t2,3\n");
+        assert!(spec_from_text(&short_room).is_err_and(|err| err.starts_with("line 7: room")));
+        let no_cat = TRIANGLE_TEXT.replace("cat\t3\n", "");
+        assert_eq!(
+            spec_from_text(&no_cat),
+            Err("board text needs a cat start line".to_string())
+        );
+        assert!(spec_from_text("hallway\t1\n").is_err_and(|err| err.contains("unknown line kind")));
+    }
+}
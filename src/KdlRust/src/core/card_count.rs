@@ -0,0 +1,151 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+// fixed-point card quantity; loot adds 11/32 of a card and defense can halve a remainder a few
+// times, so 1/1024ths keep the usual arithmetic exact and identical on every platform
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CardCount(i64);
+
+impl CardCount {
+    pub const UNITS_PER_CARD: i64 = 1024;
+    pub const ZERO: Self = Self(0);
+
+    pub const fn from_units(units: i64) -> Self {
+        Self(units)
+    }
+
+    pub const fn whole(cards: i64) -> Self {
+        Self(cards * Self::UNITS_PER_CARD)
+    }
+
+    // rounds to the nearest unit; only for json and ui boundaries
+    pub fn from_f64(cards: f64) -> Self {
+        if cards.is_finite() {
+            Self((cards * Self::UNITS_PER_CARD as f64).round() as i64)
+        } else {
+            Self::ZERO
+        }
+    }
+
+    pub const fn units(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / Self::UNITS_PER_CARD as f64
+    }
+
+    pub fn whole_cards(self) -> i64 {
+        self.0.div_euclid(Self::UNITS_PER_CARD)
+    }
+
+    pub fn fraction(self) -> Self {
+        Self(self.0.rem_euclid(Self::UNITS_PER_CARD))
+    }
+
+    pub fn is_positive(self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn div_floor(self, divisor: i64) -> Self {
+        Self(self.0.div_euclid(divisor))
+    }
+}
+
+impl Add for CardCount {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self(self.0 + other.0)
+    }
+}
+
+impl AddAssign for CardCount {
+    fn add_assign(&mut self, other: Self) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for CardCount {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self(self.0 - other.0)
+    }
+}
+
+impl SubAssign for CardCount {
+    fn sub_assign(&mut self, other: Self) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for CardCount {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(-self.0)
+    }
+}
+
+impl Mul<i64> for CardCount {
+    type Output = Self;
+
+    fn mul(self, factor: i64) -> Self {
+        Self(self.0 * factor)
+    }
+}
+
+impl Sum for CardCount {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ZERO, Add::add)
+    }
+}
+
+impl fmt::Display for CardCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.to_f64(), f)
+    }
+}
+
+impl Serialize for CardCount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardCount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Self::from_f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loot_fractions_accumulate_exactly() {
+        let loot = CardCount::from_units(352);
+        let total = (0..32).map(|_| loot).sum::<CardCount>();
+
+        assert_eq!(total, CardCount::whole(11));
+        assert_eq!((CardCount::whole(1) + loot * 3).whole_cards(), 2);
+        assert_eq!(
+            (CardCount::whole(1) + loot * 3).fraction(),
+            CardCount::from_units(32)
+        );
+        assert_eq!(CardCount::from_f64(11.0 / 32.0), loot);
+    }
+
+    #[test]
+    fn serializes_as_plain_number() {
+        let json = serde_json::to_string(&CardCount::from_f64(2.5)).expect("should serialize");
+        assert_eq!(json, "2.5");
+        let parsed = serde_json::from_str::<CardCount>("0.34375").expect("should parse");
+        assert_eq!(parsed, CardCount::from_units(352));
+        assert_eq!(format!("{:.1}", CardCount::whole(6)), "6.0");
+    }
+}
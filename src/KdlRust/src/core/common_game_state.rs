@@ -1,18 +1,63 @@
 use crate::core::{
     board::Board,
     player::{PlayerId, PlayerType},
+    rule_config::RuleConfig,
     rule_helper,
+    rule_plugin::{self, RulePlugin},
+    seat_map::{SeatMap, StrangerCounts, Teams},
 };
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
+use std::sync::Arc;
+
+// how the players' pieces are shown; an empty name or color means the default
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PlayerLabel {
+    pub name: String,
+    pub color: String, // #rgb or #rrggbb
+}
+
+impl PlayerLabel {
+    pub const MAX_NAME_CHARS: usize = 32;
+
+    pub fn new(name: &str, color: &str) -> Result<Self, String> {
+        let name = name.trim();
+        if name.chars().count() > Self::MAX_NAME_CHARS {
+            return Err(format!(
+                "name '{name}' is longer than {} characters",
+                Self::MAX_NAME_CHARS
+            ));
+        }
+        if name.chars().any(char::is_control) {
+            return Err("names can't hold control characters".to_string());
+        }
+        let color = color.trim().to_ascii_lowercase();
+        let is_hex_color = color.strip_prefix('#').is_some_and(|hex| {
+            (hex.len() == 3 || hex.len() == 6) && hex.chars().all(|ch| ch.is_ascii_hexdigit())
+        });
+        if !color.is_empty() && !is_hex_color {
+            return Err(format!("color '{color}' is not #rgb or #rrggbb"));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            color,
+        })
+    }
+}
 
 #[derive(Clone, Debug)]
 #[readonly::make]
 pub struct CommonGameState {
     pub is_log_enabled: bool,
-    pub board: Rc<Board>,
+    pub board: Arc<Board>,
     pub num_normal_players: usize,
     pub num_all_players: usize,
+    pub stranger_counts: StrangerCounts,
+    pub teams: Option<Teams>,
+    pub rule_config: RuleConfig,
+    // the rule config's built-in plugins, then any registered with with_rule_plugin
+    pub rule_plugins: Arc<[Arc<dyn RulePlugin>]>,
+    // indexed by player id; only for display, so comparisons and hashes leave them out
+    pub player_labels: Arc<[PlayerLabel]>,
 }
 
 impl CommonGameState {
@@ -24,9 +69,14 @@ impl CommonGameState {
     ) -> Self {
         Self {
             is_log_enabled,
-            board: Rc::new(board),
+            board: Arc::new(board),
             num_normal_players,
             num_all_players,
+            stranger_counts: StrangerCounts::default_for(num_normal_players),
+            teams: None,
+            rule_config: RuleConfig::default(),
+            rule_plugins: Arc::new([]),
+            player_labels: Arc::new([]),
         }
     }
 
@@ -39,12 +89,90 @@ impl CommonGameState {
         Self::new(is_log_enabled, board, num_normal_players, num_all_players)
     }
 
+    pub fn with_rule_config(mut self, rule_config: RuleConfig) -> Self {
+        let registered = self
+            .rule_plugins
+            .iter()
+            .filter(|plugin| !rule_plugin::is_built_in(plugin.as_ref()))
+            .cloned();
+        self.rule_plugins = rule_plugin::built_in_plugins(&rule_config)
+            .into_iter()
+            .chain(registered)
+            .collect::<Vec<_>>()
+            .into();
+        self.rule_config = rule_config;
+        self
+    }
+
+    pub fn with_rule_plugin(mut self, plugin: Arc<dyn RulePlugin>) -> Self {
+        self.rule_plugins = self
+            .rule_plugins
+            .iter()
+            .cloned()
+            .chain([plugin])
+            .collect::<Vec<_>>()
+            .into();
+        self
+    }
+
+    pub fn rule_plugin_names(&self) -> impl Iterator<Item = &'static str> {
+        self.rule_plugins.iter().map(|plugin| plugin.name())
+    }
+
+    // replaces every label; players left out get none, and ids past the last player are ignored
+    pub fn with_player_labels(
+        mut self,
+        labels: impl IntoIterator<Item = (PlayerId, PlayerLabel)>,
+    ) -> Self {
+        let mut player_labels = vec![PlayerLabel::default(); self.num_all_players];
+        for (player_id, label) in labels {
+            if let Some(slot) = player_labels.get_mut(player_id.0) {
+                *slot = label;
+            }
+        }
+        self.player_labels = player_labels.into();
+        self
+    }
+
+    pub fn player_label(&self, player_id: PlayerId) -> Option<&PlayerLabel> {
+        self.player_labels.get(player_id.0)
+    }
+
+    // only two-player games have sides to hand strangers to
+    pub fn with_stranger_counts(mut self, stranger_counts: StrangerCounts) -> Result<Self, String> {
+        if self.num_normal_players != rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS
+            && stranger_counts.total() > 0
+        {
+            return Err(format!(
+                "strangers need {} normal players, not {}",
+                rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+                self.num_normal_players
+            ));
+        }
+        self.stranger_counts = stranger_counts;
+        self.num_all_players =
+            rule_helper::num_all_players_with_strangers(self.num_normal_players, stranger_counts);
+        Ok(self)
+    }
+
+    pub fn with_teams(mut self, teams: Teams) -> Result<Self, String> {
+        if self.num_normal_players != rule_helper::NUM_NORMAL_PLAYERS_FOR_TEAMS {
+            return Err(format!(
+                "teams need {} normal players, not {}",
+                rule_helper::NUM_NORMAL_PLAYERS_FOR_TEAMS,
+                self.num_normal_players
+            ));
+        }
+        self.teams = Some(teams);
+        Ok(self)
+    }
+
     pub fn has_strangers(&self) -> bool {
-        self.num_normal_players == rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS
+        self.seat_map().has_strangers()
     }
 
     pub fn get_player_type(&self, player_id: PlayerId) -> PlayerType {
-        if self.has_strangers() && player_id.0 % 2 == 1 {
+        if self.seat_map().is_stranger(player_id) {
             PlayerType::Stranger
         } else {
             PlayerType::Normal
@@ -69,12 +197,31 @@ impl CommonGameState {
         format!("{prefix}{}", Self::to_player_display_num(player_id))
     }
 
+    // player_text, after the player's name when there is one: "Alice (P1)"
+    pub fn player_display_text(&self, player_id: PlayerId) -> String {
+        match self.player_label(player_id) {
+            Some(label) if !label.name.is_empty() => {
+                format!("{} ({})", label.name, self.player_text(player_id))
+            }
+            _ => self.player_text(player_id),
+        }
+    }
+
     pub fn player_ids(&self) -> impl Iterator<Item = PlayerId> {
         (0..self.num_all_players).map(PlayerId)
     }
 
+    pub fn seat_map(&self) -> SeatMap {
+        let seats =
+            SeatMap::new(self.num_normal_players).with_stranger_counts(self.stranger_counts);
+        match self.teams {
+            Some(teams) => seats.with_teams(teams),
+            None => seats,
+        }
+    }
+
     pub fn to_normal_player_id(&self, player_id: PlayerId) -> PlayerId {
-        rule_helper::to_normal_player_id(player_id, self.num_normal_players)
+        self.seat_map().to_normal_player_id(player_id)
     }
 }
 
@@ -83,6 +230,10 @@ impl PartialEq for CommonGameState {
         self.board.name == other.board.name
             && self.num_normal_players == other.num_normal_players
             && self.num_all_players == other.num_all_players
+            && self.stranger_counts == other.stranger_counts
+            && self.teams == other.teams
+            && self.rule_config == other.rule_config
+            && self.rule_plugin_names().eq(other.rule_plugin_names())
     }
 }
 
@@ -93,6 +244,10 @@ impl Hash for CommonGameState {
         self.board.name.hash(state);
         self.num_normal_players.hash(state);
         self.num_all_players.hash(state);
+        self.stranger_counts.hash(state);
+        self.teams.hash(state);
+        self.rule_config.strangers_are_nosy.hash(state);
+        self.rule_plugin_names().for_each(|name| name.hash(state));
     }
 }
 
@@ -126,6 +281,26 @@ mod tests {
         assert_eq!(from_all_players, from_normal_players);
     }
 
+    #[test]
+    fn player_labels_name_players_without_changing_equality() {
+        let unlabeled = CommonGameState::from_num_normal_players(true, sample_board(), 2);
+        let alice = PlayerLabel::new("  Alice ", "#A0f").expect("label should be valid");
+        assert_eq!(alice.name, "Alice");
+        assert_eq!(alice.color, "#a0f");
+        let labeled = unlabeled
+            .clone()
+            .with_player_labels([(PlayerId(0), alice), (PlayerId(9), PlayerLabel::default())]);
+        assert_eq!(labeled.player_display_text(PlayerId(0)), "Alice (P1)");
+        assert_eq!(labeled.player_display_text(PlayerId(2)), "P3");
+        assert_eq!(labeled.player_labels.len(), 4);
+        assert_eq!(labeled, unlabeled);
+
+        assert!(PlayerLabel::new("Bob", "red").is_err());
+        assert!(PlayerLabel::new("Bob", "#12345").is_err());
+        assert!(PlayerLabel::new("Bo\nb", "").is_err());
+        assert!(PlayerLabel::new(&"x".repeat(PlayerLabel::MAX_NAME_CHARS + 1), "").is_err());
+    }
+
     #[test]
     fn has_strangers_and_player_type_match_rules() {
         let game_state = CommonGameState::from_num_normal_players(true, sample_board(), 2);
@@ -184,4 +359,50 @@ mod tests {
             PlayerId(1)
         );
     }
+
+    #[test]
+    fn stranger_counts_change_seats_and_need_two_players() {
+        let game_state = CommonGameState::from_num_normal_players(true, sample_board(), 2)
+            .with_stranger_counts(StrangerCounts::new(2, 2).unwrap())
+            .unwrap();
+        assert_eq!(game_state.num_all_players, 6);
+        assert_eq!(game_state.get_player_type(PlayerId(3)), PlayerType::Normal);
+        assert_eq!(
+            game_state.get_player_type(PlayerId(5)),
+            PlayerType::Stranger
+        );
+        assert_eq!(game_state.to_normal_player_id(PlayerId(2)), PlayerId(3));
+
+        let no_strangers_state = CommonGameState::from_num_normal_players(true, sample_board(), 2)
+            .with_stranger_counts(StrangerCounts::NONE)
+            .unwrap();
+        assert!(!no_strangers_state.has_strangers());
+        assert_eq!(no_strangers_state.num_all_players, 2);
+
+        assert!(
+            CommonGameState::from_num_normal_players(true, sample_board(), 3)
+                .with_stranger_counts(StrangerCounts::ONE_EACH)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn teams_map_teammates_to_one_normal_player_and_need_four_players() {
+        let game_state = CommonGameState::from_num_normal_players(true, sample_board(), 4)
+            .with_teams(Teams::ALTERNATING)
+            .unwrap();
+        assert_eq!(game_state.to_normal_player_id(PlayerId(2)), PlayerId(0));
+        assert_eq!(game_state.get_player_type(PlayerId(2)), PlayerType::Normal);
+        assert_eq!(game_state.player_text(PlayerId(2)), "P3");
+        assert_ne!(
+            game_state,
+            CommonGameState::from_num_normal_players(true, sample_board(), 4)
+        );
+
+        assert!(
+            CommonGameState::from_num_normal_players(true, sample_board(), 2)
+                .with_teams(Teams::ALTERNATING)
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,235 @@
+use crate::core::{
+    asset_registry::{self, DECK_ASSETS},
+    card_count::CardCount,
+    rule_helper,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+pub const STANDARD_DECK_NAME: &str = "Standard";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardType {
+    #[serde(alias = "Weapon")]
+    Weapon,
+    #[serde(alias = "Move")]
+    Move,
+    #[serde(alias = "Failure")]
+    Failure,
+}
+
+impl CardType {
+    pub const ALL: [CardType; 3] = [CardType::Move, CardType::Weapon, CardType::Failure];
+}
+
+// one card of the c# reference decks, like DeckStandard.json; move cards carry move points,
+// weapons an attack and a special attack for their own room, and every card some clovers
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct CardSpec {
+    #[serde(rename = "Type")]
+    pub card_type: CardType,
+    pub name: String,
+    #[serde(default)]
+    pub room_name: String,
+    #[serde(default)]
+    pub clover: i64,
+    #[serde(rename = "Move", default)]
+    pub move_points: i64,
+    #[serde(default)]
+    pub attack: i64,
+    #[serde(default)]
+    pub special_attack: i64,
+}
+
+// starting cards in the engine's units, where a move card is a move point, a weapon adds
+// STRENGTH_PER_WEAPON and a failure is worth CLOVERS_PER_FAILURE
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartingCards {
+    pub move_cards: CardCount,
+    pub weapons: CardCount,
+    pub failures: CardCount,
+}
+
+// the deck files are a bare array of cards, so the name comes from the file or asset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeckSpec {
+    pub name: String,
+    pub cards: Vec<CardSpec>,
+}
+
+impl DeckSpec {
+    pub fn from_json_str(json: &str, deck_name: &str) -> Result<Self, String> {
+        let cards = serde_json::from_str::<Vec<CardSpec>>(json)
+            .map_err(|err| format!("Invalid deck JSON for '{deck_name}': {err}"))?;
+        let deck = Self {
+            name: deck_name.to_string(),
+            cards,
+        };
+        deck.validate()?;
+        Ok(deck)
+    }
+
+    // names the deck after the file, without the Deck prefix the reference data uses
+    pub fn from_json_file(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read deck {}: {err}", path.display()))?;
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let deck_name = stem
+            .strip_prefix("Deck")
+            .filter(|name| !name.is_empty())
+            .unwrap_or(stem);
+        Self::from_json_str(&json, deck_name)
+    }
+
+    pub fn find_embedded(name: &str) -> Option<Self> {
+        let asset = asset_registry::find_embedded(DECK_ASSETS, name)?;
+        Some(
+            Self::from_json_str(asset.json, asset.metadata.name)
+                .expect("embedded decks should load"),
+        )
+    }
+
+    pub fn standard() -> Self {
+        Self::find_embedded(STANDARD_DECK_NAME).expect("standard deck should be embedded")
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = self
+            .cards
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, card)| {
+                card_problem(card)
+                    .map(|problem| format!("card {} '{}' {problem}", idx + 1, card.name))
+            })
+            .collect::<Vec<_>>();
+        problems.extend(
+            CardType::ALL
+                .into_iter()
+                .filter(|card_type| self.count(*card_type) == 0)
+                .map(|card_type| format!("has no {card_type:?} cards").to_lowercase()),
+        );
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Deck '{}': {}.", self.name, problems.join("; ")))
+        }
+    }
+
+    pub fn count(&self, card_type: CardType) -> usize {
+        self.cards
+            .iter()
+            .filter(|card| card.card_type == card_type)
+            .count()
+    }
+
+    // what a hand of num_cards drawn from the shuffled deck holds on average, so the fractional
+    // cards of the simple rules can stand in for a real hand
+    pub fn expected_hand(&self, num_cards: usize) -> StartingCards {
+        let total_of = |card_type: CardType, value: fn(&CardSpec) -> i64| {
+            self.cards
+                .iter()
+                .filter(|card| card.card_type == card_type)
+                .map(value)
+                .sum::<i64>()
+        };
+        let per_hand = |total: i64, per_card: i64| {
+            CardCount::from_units(
+                num_cards as i64 * total * CardCount::UNITS_PER_CARD
+                    / (self.cards.len().max(1) as i64 * per_card),
+            )
+        };
+        StartingCards {
+            move_cards: per_hand(total_of(CardType::Move, |card| card.move_points), 1),
+            weapons: per_hand(
+                total_of(CardType::Weapon, |card| card.attack),
+                rule_helper::simple::STRENGTH_PER_WEAPON,
+            ),
+            failures: per_hand(
+                total_of(CardType::Failure, |card| card.clover),
+                rule_helper::simple::CLOVERS_PER_FAILURE,
+            ),
+        }
+    }
+
+    pub fn starting_cards(&self) -> StartingCards {
+        self.expected_hand(rule_helper::NORMAL_PLAYER_NUM_STARTING_CARDS)
+    }
+}
+
+fn card_problem(card: &CardSpec) -> Option<&'static str> {
+    if card.clover < 0 || card.move_points < 0 || card.attack < 0 || card.special_attack < 0 {
+        return Some("has a negative value");
+    }
+    match card.card_type {
+        CardType::Move if card.move_points == 0 => Some("moves nowhere"),
+        CardType::Move if card.attack > 0 => Some("is a move card with an attack"),
+        CardType::Weapon if card.attack == 0 => Some("is a weapon without an attack"),
+        CardType::Weapon if card.special_attack < card.attack => {
+            Some("has a special attack below its attack")
+        }
+        CardType::Weapon if card.move_points > 0 => Some("is a weapon with move points"),
+        CardType::Failure if card.clover == 0 => Some("is a failure without clovers"),
+        CardType::Failure if card.move_points > 0 || card.attack > 0 => {
+            Some("is a failure that moves or attacks")
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_deck_loads_and_sets_an_average_hand() {
+        let deck = DeckSpec::standard();
+        assert_eq!(deck.name, STANDARD_DECK_NAME);
+        assert_eq!(deck.cards.len(), 72);
+        for card_type in CardType::ALL {
+            assert_eq!(deck.count(card_type), 24);
+        }
+        assert_eq!(
+            DeckSpec::find_embedded("deckstandard.json"),
+            Some(deck.clone())
+        );
+
+        // six cards are two of each type: 31 move points, 53 attack and 50 failure clovers over 24
+        let starting_cards = deck.starting_cards();
+        assert_eq!(
+            starting_cards.move_cards,
+            CardCount::from_units(6 * 31 * 1024 / 72)
+        );
+        assert_eq!(
+            starting_cards.weapons,
+            CardCount::from_units(6 * 53 * 1024 / (72 * 2))
+        );
+        assert_eq!(
+            starting_cards.failures,
+            CardCount::from_units(6 * 50 * 1024 / (72 * 2))
+        );
+        assert_eq!(deck.expected_hand(0).move_cards, CardCount::ZERO);
+    }
+
+    #[test]
+    fn rejects_malformed_cards_and_missing_types() {
+        let err = DeckSpec::from_json_str(
+            r#"[{"Type":"move","Name":"parlor","Move":0},
+                {"Type":"Weapon","Name":"spoon","Attack":3,"SpecialAttack":2}]"#,
+            "Broken",
+        )
+        .expect_err("deck should be refused");
+        assert!(err.contains("card 1 'parlor' moves nowhere"), "{err}");
+        assert!(err.contains("card 2 'spoon' has a special attack"), "{err}");
+        assert!(err.contains("has no failure cards"), "{err}");
+
+        assert!(DeckSpec::from_json_str(r#"[{"Type":"joker","Name":"x"}]"#, "Odd").is_err());
+        assert!(DeckSpec::from_json_str("[]", "Empty").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::core::{mutable_game_state::MutableGameState, player::PlayerId};
+use crate::util::cancellation::CancellationToken;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SolvedOutcome {
+    Win,
+    Loss,
+    Unknown,
+}
+
+impl SolvedOutcome {
+    pub fn flipped(self) -> Self {
+        match self {
+            SolvedOutcome::Win => SolvedOutcome::Loss,
+            SolvedOutcome::Loss => SolvedOutcome::Win,
+            SolvedOutcome::Unknown => SolvedOutcome::Unknown,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[readonly::make]
+pub struct EndgameSolver {
+    pub max_total_clovers: f64,
+    pub max_depth: i32,
+}
+
+impl EndgameSolver {
+    pub const DEFAULT_MAX_TOTAL_CLOVERS: f64 = 6.0;
+    pub const DEFAULT_MAX_DEPTH: i32 = 2;
+
+    pub fn new(max_total_clovers: f64, max_depth: i32) -> Self {
+        Self {
+            max_total_clovers,
+            max_depth,
+        }
+    }
+
+    // few clovers left means most unseen attacks end the game, so the tree to a terminal is shallow
+    pub fn is_bounded(&self, state: &MutableGameState) -> bool {
+        let total_clovers = (0..state.common.num_all_players)
+            .map(PlayerId)
+            .filter(|player_id| !state.common.seat_map().is_stranger(*player_id))
+            .map(|player_id| state.player_equivalent_clovers(player_id))
+            .sum::<f64>();
+        total_clovers <= self.max_total_clovers
+    }
+
+    // outcome is from the perspective of the player to move; Unknown if no proof within max_depth
+    pub fn solve(
+        &self,
+        state: &MutableGameState,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
+    ) -> SolvedOutcome {
+        for depth in 0..=self.max_depth {
+            let outcome =
+                Self::solve_to_depth(state, depth, cancellation_token, num_states_visited);
+            if outcome != SolvedOutcome::Unknown || cancellation_token.is_cancellation_requested() {
+                return outcome;
+            }
+        }
+
+        SolvedOutcome::Unknown
+    }
+
+    fn solve_to_depth(
+        state: &MutableGameState,
+        depth: i32,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
+    ) -> SolvedOutcome {
+        *num_states_visited += 1;
+
+        if let Some(winner) = state.result.winner() {
+            return if state.common.to_normal_player_id(winner)
+                == state.common.to_normal_player_id(state.current_player_id)
+            {
+                SolvedOutcome::Win
+            } else {
+                SolvedOutcome::Loss
+            };
+        }
+
+        if depth == 0 || state.is_game_over() {
+            return SolvedOutcome::Unknown;
+        }
+
+        let mover_id = state.current_player_id;
+        let mut every_turn_loses = true;
+        for turn in state.possible_turns() {
+            if cancellation_token.is_cancellation_requested() {
+                return SolvedOutcome::Unknown;
+            }
+
+            let child_state = state.after_turn_without_memory(turn);
+            let child_outcome = Self::solve_to_depth(
+                &child_state,
+                depth - 1,
+                cancellation_token,
+                num_states_visited,
+            );
+            let outcome = if child_state.current_player_id == mover_id {
+                child_outcome
+            } else {
+                child_outcome.flipped()
+            };
+
+            match outcome {
+                SolvedOutcome::Win => return SolvedOutcome::Win,
+                SolvedOutcome::Loss => {}
+                SolvedOutcome::Unknown => every_turn_loses = false,
+            }
+        }
+
+        if every_turn_loses {
+            SolvedOutcome::Loss
+        } else {
+            SolvedOutcome::Unknown
+        }
+    }
+}
+
+impl Default for EndgameSolver {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_TOTAL_CLOVERS, Self::DEFAULT_MAX_DEPTH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{
+        board::Board, card_count::CardCount, common_game_state::CommonGameState, room::RoomId,
+        rule_helper,
+    };
+    use crate::util::cancellation::NeverCancelToken;
+
+    fn tiny_endgame_state() -> MutableGameState {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let mut state = MutableGameState::at_start(common);
+        let side_a_idx = rule_helper::SIDE_A_NORMAL_PLAYER_ID.0;
+        let side_b_idx = rule_helper::SIDE_B_NORMAL_PLAYER_ID.0;
+        state.turn_id = 10;
+        state.doctor_room_id = RoomId(2);
+        state.set_player_room_ids(vec![RoomId(1), RoomId(4), RoomId(4), RoomId(4)]);
+        state.player_failures[side_a_idx] = CardCount::ZERO;
+        state.player_move_cards[side_b_idx] = CardCount::ZERO;
+        state.player_weapons[side_b_idx] = CardCount::ZERO;
+        state.player_failures[side_b_idx] = CardCount::ZERO;
+        state
+    }
+
+    #[test]
+    fn proves_immediate_winning_attack() {
+        let state = tiny_endgame_state();
+        let solver = EndgameSolver::default();
+        let mut num_states_visited = 0usize;
+
+        assert!(solver.is_bounded(&state));
+        assert_eq!(
+            solver.solve(&state, &NeverCancelToken, &mut num_states_visited),
+            SolvedOutcome::Win
+        );
+        assert!(num_states_visited > 1);
+    }
+
+    #[test]
+    fn start_position_is_not_bounded_or_proven() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let state = MutableGameState::at_start(common);
+        let solver = EndgameSolver::new(EndgameSolver::DEFAULT_MAX_TOTAL_CLOVERS, 1);
+        let mut num_states_visited = 0usize;
+
+        assert!(!solver.is_bounded(&state));
+        assert_eq!(
+            solver.solve(&state, &NeverCancelToken, &mut num_states_visited),
+            SolvedOutcome::Unknown
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use crate::core::{
+    mutable_game_state::MutableGameState,
+    player::{PlayerId, PlayerType},
+    rule_helper,
+};
+use serde::{Deserialize, Serialize};
+
+pub const GAME_FEATURES_VERSION: u32 = 1;
+pub const EVALUATION_FEATURE_PREFIX: &str = "eval.";
+
+pub const NUM_EVALUATION_FEATURES: usize = 6;
+
+pub const EVALUATION_FEATURE_NAMES: [&str; NUM_EVALUATION_FEATURES] = [
+    "strength",
+    "strengthMoveCards",
+    "strengthTurn",
+    "doctorAdvantage",
+    "weapons",
+    "failures",
+];
+
+// default weights reproduce MutableGameState::heuristic_score
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EvaluationWeights {
+    pub strength: f64,
+    pub strength_move_cards: f64,
+    pub strength_turn: f64,
+    pub doctor_advantage: f64,
+    pub weapons: f64,
+    pub failures: f64,
+}
+
+impl EvaluationWeights {
+    pub fn from_array(values: [f64; NUM_EVALUATION_FEATURES]) -> Self {
+        Self {
+            strength: values[0],
+            strength_move_cards: values[1],
+            strength_turn: values[2],
+            doctor_advantage: values[3],
+            weapons: values[4],
+            failures: values[5],
+        }
+    }
+
+    pub fn to_array(&self) -> [f64; NUM_EVALUATION_FEATURES] {
+        [
+            self.strength,
+            self.strength_move_cards,
+            self.strength_turn,
+            self.doctor_advantage,
+            self.weapons,
+            self.failures,
+        ]
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str::<Self>(json)
+            .map_err(|err| format!("failed to parse evaluation weights: {err}"))
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn score(&self, features: &[f64; NUM_EVALUATION_FEATURES]) -> f64 {
+        self.to_array()
+            .iter()
+            .zip(features)
+            .map(|(weight, feature)| weight * feature)
+            .sum()
+    }
+
+    pub fn evaluate(&self, state: &MutableGameState, analysis_player_id: PlayerId) -> f64 {
+        if state.is_game_over() {
+            return state.heuristic_score(analysis_player_id);
+        }
+        self.score(&evaluation_features(state, analysis_player_id))
+    }
+}
+
+impl Default for EvaluationWeights {
+    fn default() -> Self {
+        Self::from_array([1.0, 0.5, 0.475, 0.45, 0.5, 0.125])
+    }
+}
+
+// each feature is allied minus opposing, so features(opponent) == -features(self) with strangers
+pub fn evaluation_features(
+    state: &MutableGameState,
+    analysis_player_id: PlayerId,
+) -> [f64; NUM_EVALUATION_FEATURES] {
+    let mut features = [0.0; NUM_EVALUATION_FEATURES];
+    let common = &state.common;
+    let seats = common.seat_map();
+    let allied_doctor_advantage = if common.has_strangers() {
+        state.allied_doctor_advantage(analysis_player_id)
+    } else {
+        0.0
+    };
+    let analysis_side = common.to_normal_player_id(analysis_player_id);
+    let num_allies = seats.num_allied_normal_players(analysis_player_id);
+    let opponent_weight = -(num_allies as f64) / ((common.num_normal_players - num_allies) as f64);
+
+    for normal_player_id in common
+        .player_ids()
+        .filter(|player_id| !seats.is_stranger(*player_id))
+    {
+        let weight = if common.to_normal_player_id(normal_player_id) == analysis_side {
+            1.0
+        } else {
+            opponent_weight
+        };
+        let strength = common
+            .player_ids()
+            .filter(|player_id| seats.controller_of(*player_id) == normal_player_id)
+            .map(|player_id| state.player_strengths[player_id.0] as f64)
+            .sum::<f64>();
+        let is_turn = seats.controller_of(state.current_player_id) == normal_player_id;
+        let idx = normal_player_id.0;
+
+        features[0] += weight * strength;
+        features[1] += weight * strength * state.player_move_cards[idx].to_f64();
+        features[2] += weight * if is_turn { strength } else { 0.0 };
+        features[3] += weight.abs() * strength * allied_doctor_advantage;
+        features[4] += weight * state.player_weapons[idx].to_f64();
+        features[5] += weight * state.player_failures[idx].to_f64();
+    }
+
+    features
+}
+
+// Version 1 layout, in order (bump GAME_FEATURES_VERSION on any name, order or meaning change):
+//   turnParity                 turn_id % 2
+//   isAnalysisTurn             1 if the analysis player's side is to move, else 0
+//   <piece>.doctorDistance     doctor moves until the doctor reaches the piece's room, per piece
+//   <piece>.sightCount         number of other pieces that see the piece, per piece
+//   <piece>.strength           attack strength, per piece
+//   <piece>.moveCards          per normal player, likewise .weapons, .failures, .clovers
+//   eval.<name>                evaluation features from the analysis player's side
+// pieces are named by player text (P1, p2, ...) in player id order
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameFeatures {
+    pub version: u32,
+    pub analysis_player: String,
+    pub names: Vec<String>,
+    pub values: Vec<f64>,
+}
+
+impl GameFeatures {
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.names
+            .iter()
+            .position(|candidate| candidate == name)
+            .map(|idx| self.values[idx])
+    }
+
+    pub fn evaluation_features(&self) -> [f64; NUM_EVALUATION_FEATURES] {
+        let mut features = [0.0; NUM_EVALUATION_FEATURES];
+        let eval_start = self.values.len() - NUM_EVALUATION_FEATURES;
+        features.copy_from_slice(&self.values[eval_start..]);
+        features
+    }
+
+    fn push(&mut self, name: String, value: f64) {
+        self.names.push(name);
+        self.values.push(value);
+    }
+}
+
+pub fn game_features(state: &MutableGameState, analysis_player_id: PlayerId) -> GameFeatures {
+    let common = &state.common;
+    let mut features = GameFeatures {
+        version: GAME_FEATURES_VERSION,
+        analysis_player: common.player_text(analysis_player_id),
+        names: Vec::new(),
+        values: Vec::new(),
+    };
+
+    features.push("turnParity".to_string(), (state.turn_id % 2) as f64);
+    features.push(
+        "isAnalysisTurn".to_string(),
+        if common.to_normal_player_id(state.current_player_id)
+            == common.to_normal_player_id(analysis_player_id)
+        {
+            1.0
+        } else {
+            0.0
+        },
+    );
+
+    for player_id in common.player_ids() {
+        let piece = common.player_text(player_id);
+        let sight_count = common
+            .player_ids()
+            .filter(|other_id| {
+                *other_id != player_id && state.player_sees_player(*other_id, player_id)
+            })
+            .count();
+        features.push(
+            format!("{piece}.doctorDistance"),
+            state
+                .doctor_moves_until_player_room(player_id)
+                .map_or(0.0, f64::from),
+        );
+        features.push(format!("{piece}.sightCount"), sight_count as f64);
+        features.push(
+            format!("{piece}.strength"),
+            state.player_strengths[player_id.0] as f64,
+        );
+
+        if common.get_player_type(player_id) == PlayerType::Normal {
+            let idx = player_id.0;
+            features.push(
+                format!("{piece}.moveCards"),
+                state.player_move_cards[idx].to_f64(),
+            );
+            features.push(
+                format!("{piece}.weapons"),
+                state.player_weapons[idx].to_f64(),
+            );
+            features.push(
+                format!("{piece}.failures"),
+                state.player_failures[idx].to_f64(),
+            );
+            features.push(
+                format!("{piece}.clovers"),
+                state.player_equivalent_clovers(player_id),
+            );
+        }
+    }
+
+    for (name, value) in EVALUATION_FEATURE_NAMES
+        .iter()
+        .zip(evaluation_features(state, analysis_player_id))
+    {
+        features.push(format!("{EVALUATION_FEATURE_PREFIX}{name}"), value);
+    }
+
+    features
+}
+
+pub fn win_probability(score: f64) -> f64 {
+    if score == rule_helper::HEURISTIC_SCORE_WIN {
+        1.0
+    } else if score == rule_helper::HEURISTIC_SCORE_LOSS {
+        0.0
+    } else {
+        1.0 / (1.0 + (-score).exp())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState, seat_map::Teams};
+
+    fn start_state(board_name: &str, num_normal_players: usize) -> MutableGameState {
+        let board = Board::from_embedded_json(board_name).expect("board should load");
+        MutableGameState::at_start(CommonGameState::from_num_normal_players(
+            false,
+            board,
+            num_normal_players,
+        ))
+    }
+
+    #[test]
+    fn default_weights_match_heuristic_score() {
+        let board = Board::from_embedded_json("AltDown").expect("board should load");
+        let team_state = MutableGameState::at_start(
+            CommonGameState::from_num_normal_players(false, board, 4)
+                .with_teams(Teams::ALTERNATING)
+                .expect("four players can form teams"),
+        );
+        for mut state in [
+            start_state("AltDown", 2),
+            start_state("AltDown", 3),
+            team_state,
+        ] {
+            for _ in 0..6 {
+                for player_id in state.common.player_ids() {
+                    if state.common.seat_map().is_stranger(player_id) {
+                        continue;
+                    }
+                    let expected = state.heuristic_score(player_id);
+                    let actual = EvaluationWeights::default().evaluate(&state, player_id);
+                    assert!((expected - actual).abs() < 1e-9, "{expected} vs {actual}");
+                }
+                let turn = state.possible_turns()[0].clone();
+                state = state.after_turn_without_memory(turn);
+            }
+        }
+    }
+
+    #[test]
+    fn game_features_are_named_and_end_with_evaluation_features() {
+        let state = start_state("AltDown", 2);
+        let features = state.features_for(PlayerId(2));
+
+        assert_eq!(features.version, GAME_FEATURES_VERSION);
+        assert_eq!(features.analysis_player, "P3");
+        assert_eq!(features.names.len(), features.values.len());
+        assert_eq!(features.names[0], "turnParity");
+        assert_eq!(features.value("isAnalysisTurn"), Some(0.0));
+        assert_eq!(features.value("P1.strength"), Some(1.0));
+        assert!(features.value("p2.doctorDistance").is_some());
+        assert!(features.value("p2.moveCards").is_none());
+        assert_eq!(
+            features.evaluation_features(),
+            evaluation_features(&state, PlayerId(2))
+        );
+        assert_eq!(
+            features.value("eval.strength"),
+            Some(features.evaluation_features()[0])
+        );
+        assert_eq!(state.features().analysis_player, "P1");
+    }
+
+    #[test]
+    fn weights_round_trip_through_json() {
+        let weights = EvaluationWeights::from_array([1.5, 0.25, 0.5, 0.75, 0.5, 0.0]);
+        let parsed = EvaluationWeights::from_json(&weights.to_json()).expect("json should parse");
+        assert_eq!(parsed, weights);
+
+        let partial =
+            EvaluationWeights::from_json(r#"{"weapons":2.0}"#).expect("json should parse");
+        assert_eq!(partial.weapons, 2.0);
+        assert_eq!(partial.strength, EvaluationWeights::default().strength);
+    }
+}
@@ -0,0 +1,203 @@
+use crate::core::{
+    asset_registry::{AssetKind, AssetMetadata},
+    board::Board,
+    card_count::CardCount,
+    common_game_state::CommonGameState,
+    rule_config::RuleConfig,
+    rule_helper,
+    seat_map::{StrangerCounts, Teams},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GamePreset {
+    pub name: &'static str,
+    pub display_name: &'static str,
+    pub description: &'static str,
+    pub board_name: &'static str,
+    pub num_normal_players: usize,
+    pub stranger_counts: Option<StrangerCounts>, // None means the standard count for the players
+    pub teams: Option<Teams>,
+    pub rule_config: RuleConfig,
+}
+
+impl GamePreset {
+    pub fn metadata(&self) -> AssetMetadata {
+        AssetMetadata {
+            kind: AssetKind::Preset,
+            name: self.name,
+            display_name: self.display_name,
+            description: self.description,
+            min_players: self.num_normal_players,
+            max_players: self.num_normal_players,
+        }
+    }
+
+    pub fn common_game_state(&self, is_log_enabled: bool) -> Result<CommonGameState, String> {
+        let board = Board::from_embedded_json(self.board_name).map_err(|err| err.to_string())?;
+        let common = CommonGameState::from_num_normal_players(
+            is_log_enabled,
+            board,
+            self.num_normal_players,
+        )
+        .with_rule_config(self.rule_config.clone());
+        let common = match self.stranger_counts {
+            Some(stranger_counts) => common.with_stranger_counts(stranger_counts)?,
+            None => common,
+        };
+        match self.teams {
+            Some(teams) => common.with_teams(teams),
+            None => Ok(common),
+        }
+    }
+}
+
+pub fn game_presets() -> Vec<GamePreset> {
+    vec![
+        GamePreset {
+            name: "beginner",
+            display_name: "Beginner",
+            description: "small board with extra starting move and weapon cards",
+            board_name: "Tiny",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig {
+                player_starting_move_cards: CardCount::whole(2),
+                player_starting_weapons: CardCount::whole(3),
+                ..RuleConfig::default()
+            },
+        },
+        GamePreset {
+            name: "standard",
+            display_name: "Standard",
+            description: "AltDown board with the simple rules",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig::default(),
+        },
+        GamePreset {
+            name: "strangers-nosy",
+            display_name: "Nosy Strangers",
+            description: "standard, but moving a stranger that saw the doctor blocks attacks",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig {
+                strangers_are_nosy: true,
+                ..RuleConfig::default()
+            },
+        },
+        GamePreset {
+            name: "two-strikes",
+            display_name: "Two Strikes",
+            description: "standard, but a side needs two successful attacks and the doctor escapes after 120 turns",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig {
+                successful_attacks_to_win: 2,
+                doctor_escape_turns: 120,
+                ..RuleConfig::default()
+            },
+        },
+        GamePreset {
+            name: "duel",
+            display_name: "Duel",
+            description: "standard, but just the two players with no strangers",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: Some(StrangerCounts::NONE),
+            teams: None,
+            rule_config: RuleConfig::default(),
+        },
+        GamePreset {
+            name: "teams",
+            display_name: "Teams",
+            description: "four players in two teams (P1+P3 vs P2+P4) sharing attacks and wins",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_FOR_TEAMS,
+            stranger_counts: None,
+            teams: Some(Teams::ALTERNATING),
+            rule_config: RuleConfig::default(),
+        },
+        GamePreset {
+            name: "classic",
+            display_name: "Classic",
+            description: "standard, but a player the doctor activates has to move out of his room, moving a stranger always costs move cards, and starting cards are an average hand from the standard deck",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig::classic(),
+        },
+        GamePreset {
+            name: "custom",
+            display_name: "Custom",
+            description: "standard rules as a starting point for hand-entered setup",
+            board_name: "AltDown",
+            num_normal_players: rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+            stranger_counts: None,
+            teams: None,
+            rule_config: RuleConfig::default(),
+        },
+    ]
+}
+
+pub fn find_game_preset(name: &str) -> Option<GamePreset> {
+    game_presets()
+        .into_iter()
+        .find(|preset| preset.metadata().matches_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_preset_builds_a_common_state() {
+        for preset in game_presets() {
+            let common = preset
+                .common_game_state(false)
+                .unwrap_or_else(|err| panic!("preset {} failed: {err}", preset.name));
+            assert_eq!(common.num_normal_players, preset.num_normal_players);
+            assert_eq!(common.rule_config, preset.rule_config);
+            assert_eq!(common.teams, preset.teams);
+            assert_eq!(
+                common.stranger_counts,
+                preset
+                    .stranger_counts
+                    .unwrap_or(StrangerCounts::default_for(preset.num_normal_players))
+            );
+        }
+    }
+
+    #[test]
+    fn duel_preset_seats_two_players_who_only_move_themselves() {
+        let preset = find_game_preset("duel").expect("preset should exist");
+        let common = preset.common_game_state(false).expect("duel should build");
+        assert_eq!(common.num_all_players, 2);
+        assert!(!common.has_strangers());
+
+        let game = crate::core::mutable_game_state::MutableGameState::at_start(common);
+        let turns = game.possible_turns();
+        assert!(!turns.is_empty());
+        assert!(turns.iter().all(|turn| {
+            turn.moves
+                .iter()
+                .all(|mv| mv.player_id == game.current_player_id)
+        }));
+        assert!(game.heuristic_score(game.current_player_id).is_finite());
+    }
+
+    #[test]
+    fn find_game_preset_ignores_case() {
+        let preset = find_game_preset("Strangers-Nosy").expect("preset should exist");
+
+        assert!(preset.rule_config.strangers_are_nosy);
+        assert!(find_game_preset("expert").is_none());
+    }
+}
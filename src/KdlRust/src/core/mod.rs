@@ -1,9 +1,37 @@
+pub mod asset_registry;
 pub mod board;
+pub mod board_dot;
+pub mod board_svg;
+pub mod board_text;
+pub mod card_count;
 pub mod common_game_state;
+pub mod deck_spec;
+pub mod endgame_solver;
+pub mod evaluation;
+pub mod game_preset;
+pub mod move_selector;
 pub mod mutable_game_state;
 pub mod player;
+pub mod puzzle;
 pub mod room;
+pub mod room_matrix;
+pub mod room_occupancy;
+pub mod rule_config;
 pub mod rule_helper;
+pub mod rule_plugin;
+#[cfg(test)]
+mod scenario_tests;
+pub mod seat_map;
+pub mod self_play;
+pub mod session_stats;
+pub mod sight_cache;
 pub mod simple_turn;
+pub mod start_rooms;
+pub mod state_diff;
+pub mod stranger_placement;
+pub mod transposition_table;
 pub mod tree_search;
+pub mod tuning;
+pub mod validation_error;
+pub mod variation_tree;
 pub mod wing;
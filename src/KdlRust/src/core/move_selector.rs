@@ -0,0 +1,298 @@
+use crate::core::{
+    mutable_game_state::MutableGameState,
+    player::AppraisedPlayerTurn,
+    rule_helper,
+    tree_search::{RootTurns, SearchConfig, TreeSearch},
+};
+use crate::util::{cancellation::CancellationToken, rng::SplitMix64};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+// how a difficulty plays: it searches analysis_level deep, then picks among the best num_candidates
+// turns with softmax weights exp(-shortfall / temperature), where shortfall is how far a turn's
+// appraisal trails the best one. a temperature of zero always takes the best turn.
+// own_piece_only_chance is how often the search leaves strangers where they are
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DifficultyPreset {
+    pub analysis_level: i32,
+    pub num_candidates: usize,
+    pub temperature: f64,
+    pub own_piece_only_chance: f64,
+}
+
+impl Difficulty {
+    pub const NAMES: [&'static str; 3] = ["easy", "medium", "hard"];
+
+    pub fn preset(self) -> DifficultyPreset {
+        match self {
+            Difficulty::Easy => DifficultyPreset {
+                analysis_level: 2,
+                num_candidates: 6,
+                temperature: 1.0,
+                own_piece_only_chance: 0.5,
+            },
+            Difficulty::Medium => DifficultyPreset {
+                analysis_level: 3,
+                num_candidates: 3,
+                temperature: 0.35,
+                own_piece_only_chance: 0.2,
+            },
+            Difficulty::Hard => DifficultyPreset {
+                analysis_level: 4,
+                num_candidates: 1,
+                temperature: 0.0,
+                own_piece_only_chance: 0.0,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::NAMES[*self as usize])
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "easy" => Ok(Self::Easy),
+            "medium" => Ok(Self::Medium),
+            "hard" => Ok(Self::Hard),
+            _ => Err(format!(
+                "unknown difficulty '{text}'; use one of {}",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectedTurn {
+    pub appraised_turn: AppraisedPlayerTurn,
+    pub rank: usize, // 0 is the best turn the search found
+    pub num_candidates: usize,
+    pub own_piece_only: bool, // strangers were left out of this turn's search
+}
+
+// a weaker player on top of TreeSearch: a shallower search, then a weighted draw from its best
+// turns rather than always the best one. with a game seed, each turn's draws come from the seed and
+// the turn id alone, so replaying a save with the same seed makes the same mistakes
+#[derive(Clone, Debug)]
+pub struct MoveSelector {
+    pub difficulty: Difficulty,
+    pub preset: DifficultyPreset,
+    pub game_seed: Option<u64>,
+    rng: SplitMix64,
+}
+
+impl MoveSelector {
+    pub fn new(difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            difficulty,
+            preset: difficulty.preset(),
+            game_seed: None,
+            rng: SplitMix64::new(seed),
+        }
+    }
+
+    pub fn with_game_seed(mut self, game_seed: u64) -> Self {
+        self.game_seed = Some(game_seed);
+        self
+    }
+
+    // the preset's level, lowered to max_analysis_level when that's shallower
+    pub fn with_max_analysis_level(mut self, max_analysis_level: i32) -> Self {
+        self.preset.analysis_level = self.preset.analysis_level.min(max_analysis_level).max(1);
+        self
+    }
+
+    pub fn select_turn(
+        &mut self,
+        state: &MutableGameState,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
+    ) -> SelectedTurn {
+        if let Some(game_seed) = self.game_seed {
+            self.rng = SplitMix64::new(turn_seed(game_seed, state.turn_id));
+        }
+        let own_piece_root_turns = (self.rng.next_f64() < self.preset.own_piece_only_chance)
+            .then(|| own_piece_root_turns(state))
+            .flatten();
+        let own_piece_only = own_piece_root_turns.is_some();
+        let search_config = match own_piece_root_turns {
+            Some(root_turns) => SearchConfig {
+                root_turns: Some(root_turns),
+                ..search_config.clone()
+            },
+            None => search_config.clone(),
+        };
+
+        let mut candidates = TreeSearch::find_best_turns_with_config(
+            state,
+            self.preset.analysis_level,
+            self.preset.num_candidates.max(1),
+            &search_config,
+            cancellation_token,
+            num_states_visited,
+        );
+        if candidates.is_empty() {
+            candidates.push(AppraisedPlayerTurn::empty_minimum());
+        }
+        let rank = self.draw_rank(&candidates);
+        SelectedTurn {
+            num_candidates: candidates.len(),
+            appraised_turn: candidates.swap_remove(rank),
+            rank,
+            own_piece_only,
+        }
+    }
+
+    fn draw_rank(&mut self, candidates: &[AppraisedPlayerTurn]) -> usize {
+        let draw = self.rng.next_f64();
+        if self.preset.temperature <= 0.0 || candidates.len() < 2 {
+            return 0;
+        }
+        let best_appraisal = candidates[0].appraisal;
+        let weights = candidates
+            .iter()
+            .map(|candidate| {
+                if candidate.appraisal == best_appraisal {
+                    1.0
+                } else if best_appraisal == rule_helper::HEURISTIC_SCORE_WIN
+                    || candidate.appraisal == rule_helper::HEURISTIC_SCORE_LOSS
+                {
+                    0.0
+                } else {
+                    (-(best_appraisal - candidate.appraisal) / self.preset.temperature).exp()
+                }
+            })
+            .collect::<Vec<_>>();
+        let mut remaining = draw * weights.iter().sum::<f64>();
+        for (rank, weight) in weights.iter().enumerate() {
+            if remaining < *weight {
+                return rank;
+            }
+            remaining -= weight;
+        }
+        0
+    }
+}
+
+pub fn turn_seed(game_seed: u64, turn_id: i32) -> u64 {
+    SplitMix64::new(game_seed ^ (turn_id as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)).next_u64()
+}
+
+// the turns that move only the current player's own piece, or None when there are none or when
+// every turn already does
+fn own_piece_root_turns(state: &MutableGameState) -> Option<RootTurns> {
+    let turns = state.possible_turns();
+    let num_turns = turns.len();
+    let own_piece_turns = turns
+        .into_iter()
+        .filter(|turn| {
+            state
+                .effective_turn(turn)
+                .moves
+                .iter()
+                .all(|mv| mv.player_id == state.current_player_id)
+        })
+        .collect::<Vec<_>>();
+    if own_piece_turns.is_empty() || own_piece_turns.len() == num_turns {
+        return None;
+    }
+    RootTurns::new(state, own_piece_turns, []).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState, player::PlayerId};
+    use crate::util::cancellation::NeverCancelToken;
+
+    fn start_state() -> MutableGameState {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2))
+    }
+
+    #[test]
+    fn hard_takes_the_best_turn_and_easy_strays_from_it() {
+        let state = start_state();
+        let config = SearchConfig::default();
+        let mut num_states_visited = 0;
+        let best_turn = TreeSearch::find_best_turn_with_config(
+            &state,
+            Difficulty::Hard.preset().analysis_level,
+            &config,
+            &NeverCancelToken,
+            &mut num_states_visited,
+        );
+        let hard = MoveSelector::new(Difficulty::Hard, 1).select_turn(
+            &state,
+            &config,
+            &NeverCancelToken,
+            &mut num_states_visited,
+        );
+        assert_eq!(hard.rank, 0);
+        assert_eq!(hard.appraised_turn.turn, best_turn.turn);
+
+        let mut easy = MoveSelector::new(Difficulty::Easy, 3);
+        let picks = (0..8)
+            .map(|_| easy.select_turn(&state, &config, &NeverCancelToken, &mut num_states_visited))
+            .collect::<Vec<_>>();
+        assert!(picks.iter().any(|pick| pick.rank > 0));
+        assert!(picks.iter().any(|pick| pick.own_piece_only));
+        assert!(
+            picks
+                .iter()
+                .filter(|pick| pick.own_piece_only)
+                .flat_map(|pick| &pick.appraised_turn.turn.moves)
+                .all(|mv| mv.player_id == PlayerId(0)
+                    || mv.dest_room_id == state.player_room_ids()[mv.player_id.0])
+        );
+        let mut replay = MoveSelector::new(Difficulty::Easy, 3);
+        assert!(picks.iter().all(|pick| {
+            *pick == replay.select_turn(&state, &config, &NeverCancelToken, &mut num_states_visited)
+        }));
+    }
+
+    #[test]
+    fn game_seed_makes_each_turn_draw_independent_of_earlier_calls() {
+        let state = start_state();
+        let config = SearchConfig::default();
+        let mut num_states_visited = 0;
+        let mut select = |selector: &mut MoveSelector| {
+            selector.select_turn(&state, &config, &NeverCancelToken, &mut num_states_visited)
+        };
+
+        let mut warmed_up = MoveSelector::new(Difficulty::Easy, 0).with_game_seed(5);
+        let first = select(&mut warmed_up);
+        let again = (0..3).map(|_| select(&mut warmed_up)).collect::<Vec<_>>();
+        assert!(again.iter().all(|pick| *pick == first));
+        assert_eq!(
+            select(&mut MoveSelector::new(Difficulty::Easy, 99).with_game_seed(5)),
+            first
+        );
+        assert_ne!(turn_seed(5, 1), turn_seed(5, 2));
+        assert_ne!(turn_seed(5, 1), turn_seed(6, 1));
+    }
+
+    #[test]
+    fn difficulty_names_parse_and_level_caps_apply() {
+        assert_eq!(" Medium ".parse::<Difficulty>(), Ok(Difficulty::Medium));
+        assert!("impossible".parse::<Difficulty>().is_err());
+        assert_eq!(Difficulty::Easy.to_string(), "easy");
+        let capped = MoveSelector::new(Difficulty::Hard, 0).with_max_analysis_level(2);
+        assert_eq!(capped.preset.analysis_level, 2);
+    }
+}
@@ -1,14 +1,129 @@
 use crate::core::{
-    board::Board,
+    board::{Board, RoomSymmetry},
+    card_count::CardCount,
     common_game_state::CommonGameState,
+    evaluation::{self, GameFeatures},
     player::{PieceMove, PlayerAction, PlayerId, PlayerType},
     room::RoomId,
     rule_helper,
+    rule_plugin::TurnEvent,
+    seat_map::Side,
+    sight_cache::SightCache,
     simple_turn::SimpleTurn,
+    start_rooms::StartRooms,
+    validation_error::ValidationError,
 };
+use crate::util::log::{self, LogLevel};
+use crate::util::stable_hash::StableHasher;
 use std::fmt;
 use std::hash::{Hash, Hasher};
-use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DoctorActivation {
+    pub turn_id: i32,
+    pub doctor_room_id: RoomId,
+    pub activated_player_id: PlayerId,
+    pub rotation_player_id: PlayerId,
+}
+
+impl DoctorActivation {
+    pub fn skipped_player_ids(&self, num_all_players: usize) -> Vec<PlayerId> {
+        (0..num_all_players)
+            .map(|offset| PlayerId((self.rotation_player_id.0 + offset) % num_all_players))
+            .take_while(|player_id| *player_id != self.activated_player_id)
+            .collect::<Vec<_>>()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ActivationRestriction {
+    MustMove, // the player's own piece has to leave the doctor's room
+}
+
+impl ActivationRestriction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActivationRestriction::MustMove => "mustMove",
+        }
+    }
+}
+
+// how the current player came to have this turn; activation is None on an ordinary rotation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActivationContext {
+    pub player_id: PlayerId,
+    pub activation: Option<DoctorActivation>,
+    pub skipped_player_ids: Vec<PlayerId>,
+    pub restrictions: Vec<ActivationRestriction>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StrangerLoopThreat {
+    pub enemy_room_id: RoomId,
+    pub counter_room_ids: Vec<RoomId>,
+    pub opposing_stranger_is_here: bool,
+    pub is_countered: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DrawReason {
+    DoctorEscaped,
+    Repetition,
+}
+
+impl fmt::Display for DrawReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DrawReason::DoctorEscaped => "doctor escaped",
+            DrawReason::Repetition => "position repeated",
+        })
+    }
+}
+
+// a game that is not in progress accepts no more turns; an aborted game was stopped by the
+// players rather than by the rules
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum GameResult {
+    #[default]
+    InProgress,
+    Won {
+        player: PlayerId, // the winning side's normal player
+    },
+    Draw {
+        reason: DrawReason,
+    },
+    Aborted,
+}
+
+impl GameResult {
+    pub fn winner(self) -> Option<PlayerId> {
+        match self {
+            GameResult::Won { player } => Some(player),
+            _ => None,
+        }
+    }
+
+    pub fn draw_reason(self) -> Option<DrawReason> {
+        match self {
+            GameResult::Draw { reason } => Some(reason),
+            _ => None,
+        }
+    }
+
+    pub fn is_over(self) -> bool {
+        self != GameResult::InProgress
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            GameResult::InProgress => "inProgress",
+            GameResult::Won { .. } => "won",
+            GameResult::Draw { .. } => "draw",
+            GameResult::Aborted => "aborted",
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct MutableGameState {
@@ -16,15 +131,105 @@ pub struct MutableGameState {
     pub turn_id: i32,
     pub current_player_id: PlayerId,
     pub doctor_room_id: RoomId,
-    pub player_room_ids: Vec<RoomId>,
-    pub player_move_cards: Vec<f64>,
-    pub player_weapons: Vec<f64>,
-    pub player_failures: Vec<f64>,
+    player_room_ids: Vec<RoomId>, // set by move_piece or set_player_room_ids, which keep sight
+    pub sight_cache: SightCache,
+    pub player_move_cards: Vec<CardCount>,
+    pub player_weapons: Vec<CardCount>,
+    pub player_failures: Vec<CardCount>,
     pub player_strengths: Vec<i32>,
-    pub attacker_hist: Vec<PlayerId>,
-    pub winner: PlayerId,
+    pub attack_hist: Vec<AttackRecord>,
+    pub num_loots: usize,             // normal turns that ended with a loot
+    pub successful_attacks: Vec<i32>, // indexed by normal player id
+    pub result: GameResult,
+    pub position_hist: PositionHist, // keys before each turn, when repetition draws are on
     pub prev_turn: SimpleTurn,
-    pub prev_state: Option<Rc<MutableGameState>>,
+    pub doctor_activation: Option<DoctorActivation>,
+    pub prev_state: Option<Arc<MutableGameState>>,
+    pub compacted_history: Option<Arc<CompactedHistory>>,
+    pub staged_plan: Vec<PieceMove>, // moves staged one at a time for the current normal turn
+    pub appraisal: Option<f64>,      // side A's appraisal of this state, if a caller recorded one
+}
+
+// a stack of position keys, newest on top, that states share the older part of; a state copied at
+// every node of a search pushes onto its parent's keys instead of copying the whole game's history
+#[derive(Clone, Debug, Default)]
+pub struct PositionHist(Option<Arc<PositionHistEntry>>);
+
+#[derive(Debug)]
+struct PositionHistEntry {
+    position_key: u64,
+    len: usize,
+    below: PositionHist,
+}
+
+impl PositionHist {
+    pub fn push(&mut self, position_key: u64) {
+        let len = self.len() + 1;
+        let below = std::mem::take(self);
+        *self = Self(Some(Arc::new(PositionHistEntry {
+            position_key,
+            len,
+            below,
+        })));
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |entry| entry.len)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    // newest first
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::successors(self.0.as_deref(), |entry| entry.below.0.as_deref())
+            .map(|entry| entry.position_key)
+    }
+}
+
+// normal turns older than the oldest state in the prev_state chain, replayable from base
+#[derive(Clone, Debug)]
+pub struct CompactedHistory {
+    pub base: MutableGameState,
+    pub normal_turns: Vec<SimpleTurn>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DefenseCardType {
+    Failure,
+    Weapon,
+    MoveCard,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DefenseCardSpend {
+    pub defender: PlayerId,
+    pub card_type: DefenseCardType,
+    pub cards_spent: CardCount,
+    pub strength_remaining: CardCount,
+}
+
+// one attack in the order they happened; strength includes any weapon, and defense_spent is the
+// clovers defenders played against it, which can overshoot the strength by part of a card
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AttackRecord {
+    pub turn_id: i32,
+    pub attacker: PlayerId,
+    pub strength: CardCount,
+    pub defense_spent: CardCount,
+    pub succeeded: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttackSimulation {
+    pub attacker: PlayerId,
+    pub allowed_now: bool,
+    pub base_strength: i32,
+    pub weapon_used: bool,
+    pub attack_strength: CardCount,
+    pub card_spends: Vec<DefenseCardSpend>,
+    pub succeeded: bool,
 }
 
 impl MutableGameState {
@@ -33,28 +238,47 @@ impl MutableGameState {
         let player_start_room_id = common.board.player_start_room_id;
         let doctor_room_id = common.board.doctor_start_room_id;
         let player_room_ids = vec![player_start_room_id; num_players];
-        let player_move_cards = vec![rule_helper::simple::PLAYER_STARTING_MOVE_CARDS; num_players];
-        let player_weapons = vec![rule_helper::simple::PLAYER_STARTING_WEAPONS; num_players];
-        let player_failures = vec![rule_helper::simple::PLAYER_STARTING_FAILURES; num_players];
+        let rule_config = &common.rule_config;
+        let player_move_cards = vec![rule_config.player_starting_move_cards; num_players];
+        let player_weapons = vec![rule_config.player_starting_weapons; num_players];
+        let player_failures = vec![rule_config.player_starting_failures; num_players];
         let player_strengths = vec![rule_helper::PLAYER_STARTING_STRENGTH; num_players];
 
+        let sight_cache = SightCache::new(&common.board, &player_room_ids);
+
         MutableGameState {
             common,
             turn_id: 1,
             current_player_id: PlayerId(0),
             doctor_room_id,
             player_room_ids,
+            sight_cache,
             player_move_cards,
             player_weapons,
             player_failures,
             player_strengths,
-            attacker_hist: Vec::new(),
-            winner: PlayerId::INVALID,
+            attack_hist: Vec::new(),
+            num_loots: 0,
+            successful_attacks: vec![0; num_players],
+            result: GameResult::InProgress,
+            position_hist: PositionHist::default(),
             prev_turn: SimpleTurn::invalid_default(),
+            doctor_activation: None,
             prev_state: None,
+            compacted_history: None,
+            staged_plan: Vec::new(),
+            appraisal: None,
         }
     }
 
+    // every piece and the doctor moved to the given start rooms; only for a fresh game
+    pub fn with_start_rooms(mut self, start_rooms: StartRooms) -> Self {
+        self.player_room_ids.fill(start_rooms.player_room_id);
+        self.doctor_room_id = start_rooms.doctor_room_id;
+        self.refresh_sight_cache();
+        self
+    }
+
     pub fn copy_state(&self) -> Self {
         Self {
             common: self.common.clone(),
@@ -62,14 +286,22 @@ impl MutableGameState {
             current_player_id: self.current_player_id,
             doctor_room_id: self.doctor_room_id,
             player_room_ids: self.player_room_ids.clone(),
+            sight_cache: self.sight_cache.clone(),
             player_move_cards: self.player_move_cards.clone(),
             player_weapons: self.player_weapons.clone(),
             player_failures: self.player_failures.clone(),
             player_strengths: self.player_strengths.clone(),
-            attacker_hist: self.attacker_hist.clone(),
-            winner: self.winner,
+            attack_hist: self.attack_hist.clone(),
+            num_loots: self.num_loots,
+            successful_attacks: self.successful_attacks.clone(),
+            result: self.result,
+            position_hist: self.position_hist.clone(),
             prev_turn: self.prev_turn.clone(),
+            doctor_activation: self.doctor_activation,
             prev_state: self.prev_state.clone(),
+            compacted_history: self.compacted_history.clone(),
+            staged_plan: self.staged_plan.clone(),
+            appraisal: self.appraisal,
         }
     }
 
@@ -82,7 +314,142 @@ impl MutableGameState {
     }
 
     pub fn has_winner(&self) -> bool {
-        self.winner != PlayerId::INVALID
+        self.result.winner().is_some()
+    }
+
+    // the winning side's normal player, or PlayerId::INVALID
+    pub fn winner(&self) -> PlayerId {
+        self.result.winner().unwrap_or(PlayerId::INVALID)
+    }
+
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        self.result.draw_reason()
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.result == GameResult::Aborted
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.result.is_over()
+    }
+
+    // ends an unfinished game without a winner; a finished game keeps its result
+    pub fn abort(&mut self) -> bool {
+        if self.is_game_over() {
+            return false;
+        }
+        self.result = GameResult::Aborted;
+        true
+    }
+
+    // takes back an abort, so play goes on from where it stopped
+    pub fn resume(&mut self) -> bool {
+        if !self.is_aborted() {
+            return false;
+        }
+        self.result = GameResult::InProgress;
+        true
+    }
+
+    pub fn move_piece(&mut self, player_id: PlayerId, dest_room_id: RoomId) {
+        let src_room_id = self.player_room_ids[player_id.0];
+        self.sight_cache
+            .move_player(&self.common.board, player_id, src_room_id, dest_room_id);
+        self.player_room_ids[player_id.0] = dest_room_id;
+    }
+
+    pub fn player_room_ids(&self) -> &[RoomId] {
+        &self.player_room_ids
+    }
+
+    // places every piece at once, for setting up a position rather than playing to it
+    pub fn set_player_room_ids(&mut self, player_room_ids: Vec<RoomId>) {
+        self.player_room_ids = player_room_ids;
+        self.refresh_sight_cache();
+    }
+
+    fn refresh_sight_cache(&mut self) {
+        self.sight_cache = SightCache::new(&self.common.board, &self.player_room_ids);
+    }
+
+    // takes back the cards a loot just handed out, for rules that can spoil a loot
+    pub fn forfeit_loot(&mut self, player_id: PlayerId) {
+        self.num_loots = self.num_loots.saturating_sub(1);
+        self.player_move_cards[player_id.0] -= rule_helper::simple::MOVE_CARDS_PER_LOOT;
+        self.player_weapons[player_id.0] -= rule_helper::simple::WEAPONS_PER_LOOT;
+        self.player_failures[player_id.0] -= rule_helper::simple::FAILURES_PER_LOOT;
+    }
+
+    pub fn is_draw(&self) -> bool {
+        self.draw_reason().is_some()
+    }
+
+    // bump when position_key hashes other fields or hashes them differently
+    pub const POSITION_KEY_VERSION: u32 = 2;
+
+    // the same on every target and rust release, so saved and remote keys can be compared
+    pub fn position_key(&self) -> u64 {
+        let mut hasher = StableHasher::new();
+        self.hash(&mut hasher);
+        self.player_strengths.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // position_key for the state with its rooms relabeled by the board symmetry that gives the
+    // smallest key, and that symmetry (None when it is the identity). symmetric positions share the
+    // key, so searches can share their work. rule plugins may look at position_key itself, so with
+    // any switched on every position is its own representative
+    pub fn canonical_position_key(&self) -> (u64, Option<&RoomSymmetry>) {
+        let position_key = self.position_key();
+        if self.common.rule_plugin_names().next().is_some() {
+            return (position_key, None);
+        }
+        self.common
+            .board
+            .symmetries
+            .iter()
+            .map(|symmetry| (self.symmetric_position_key(symmetry), Some(symmetry)))
+            .fold((position_key, None), |best, candidate| {
+                if candidate.0 < best.0 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+    }
+
+    fn symmetric_position_key(&self, symmetry: &RoomSymmetry) -> u64 {
+        let player_room_ids = self
+            .player_room_ids
+            .iter()
+            .map(|room_id| symmetry.apply(*room_id))
+            .collect::<Vec<_>>();
+        let mut hasher = StableHasher::new();
+        self.hash_with_rooms(
+            &mut hasher,
+            symmetry.apply(self.doctor_room_id),
+            &player_room_ids,
+        );
+        self.player_strengths.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_with_rooms<H: Hasher>(
+        &self,
+        state: &mut H,
+        doctor_room_id: RoomId,
+        player_room_ids: &[RoomId],
+    ) {
+        self.common.hash(state);
+        self.current_player_id.hash(state);
+        (doctor_room_id.0 << 3).hash(state);
+        self.result.hash(state);
+        self.successful_attacks.hash(state);
+        player_room_ids.hash(state);
+        self.player_move_cards.hash(state);
+        self.player_weapons.hash(state);
+        self.player_failures.hash(state);
     }
 
     pub fn is_normal_turn(&self) -> bool {
@@ -101,7 +468,145 @@ impl MutableGameState {
             state = prev.prev_state.as_deref();
         }
 
-        ply
+        ply + self.num_compacted_plies() as i32
+    }
+
+    pub fn num_compacted_plies(&self) -> usize {
+        self.compacted_history
+            .as_ref()
+            .map_or(0, |history| history.normal_turns.len())
+    }
+
+    pub fn history_len(&self) -> usize {
+        let mut len = 1;
+        let mut state = self.prev_state.as_deref();
+        while let Some(prev) = state {
+            len += 1;
+            state = prev.prev_state.as_deref();
+        }
+        len
+    }
+
+    // normal turns from the very first state, including compacted ones
+    pub fn normal_turns(&self) -> Vec<SimpleTurn> {
+        let mut turns = self
+            .compacted_history
+            .as_ref()
+            .map(|history| history.normal_turns.clone())
+            .unwrap_or_default();
+        turns.extend(self.chain_normal_turns());
+        turns
+    }
+
+    fn chain_normal_turns(&self) -> Vec<SimpleTurn> {
+        let mut chain_turns = Vec::new();
+        let mut state = self;
+        while let Some(prev) = state.prev_state.as_deref() {
+            if prev.is_normal_turn() {
+                chain_turns.push(state.prev_turn.clone());
+            }
+            state = prev;
+        }
+        chain_turns.reverse();
+        chain_turns
+    }
+
+    // compact_history down to this state alone, appending to the compacted turns in place rather
+    // than copying them, so calling it after every turn of a long replay stays cheap
+    pub fn compact_all_history(&mut self) {
+        if self.prev_state.is_none() || !(self.is_normal_turn() || self.is_game_over()) {
+            return;
+        }
+
+        let chain_turns = self.chain_normal_turns();
+        let first_state = self.compacted_history.is_none().then(|| {
+            let mut state = self as &MutableGameState;
+            while let Some(prev) = state.prev_state.as_deref() {
+                state = prev;
+            }
+            state.copy_state()
+        });
+        // the chain shares the history, so it has to go before the history can be taken over
+        self.prev_state = None;
+        let mut history = match self.compacted_history.take() {
+            Some(history) => Arc::unwrap_or_clone(history),
+            None => CompactedHistory {
+                base: first_state.expect("first state is kept when nothing was compacted"),
+                normal_turns: Vec::new(),
+            },
+        };
+        history.normal_turns.extend(chain_turns);
+        self.compacted_history = Some(Arc::new(history));
+    }
+
+    // drops prev_state links older than keep_states, cutting at a normal turn so the dropped part
+    // is exactly a list of normal turns replayed from the first state
+    pub fn compact_history(&mut self, keep_states: usize) {
+        let mut chain = vec![self as &MutableGameState];
+        while let Some(prev) = chain[chain.len() - 1].prev_state.as_deref() {
+            chain.push(prev);
+        }
+
+        let Some(cut_idx) = (keep_states.max(1)..chain.len().saturating_sub(1))
+            .find(|idx| chain[*idx].is_normal_turn())
+        else {
+            return;
+        };
+
+        let base = match self.compacted_history.as_ref() {
+            Some(history) => history.base.clone(),
+            None => {
+                let mut base = chain[chain.len() - 1].copy_state();
+                base.compacted_history = None;
+                base
+            }
+        };
+        let history = Arc::new(CompactedHistory {
+            base,
+            normal_turns: chain[cut_idx].normal_turns(),
+        });
+
+        let mut rebuilt = chain[cut_idx].copy_state();
+        rebuilt.prev_state = None;
+        rebuilt.compacted_history = Some(history.clone());
+        for state in chain[..cut_idx].iter().rev() {
+            let mut newer = state.copy_state();
+            newer.prev_state = Some(Arc::new(rebuilt));
+            newer.compacted_history = Some(history.clone());
+            rebuilt = newer;
+        }
+        *self = rebuilt;
+    }
+
+    // replays compacted turns so every earlier state is reachable through prev_state again
+    pub fn rehydrated(&self) -> MutableGameState {
+        let Some(history) = self.compacted_history.as_ref() else {
+            return self.copy_state();
+        };
+
+        let mut replayed = history.base.copy_state();
+        for turn in &history.normal_turns {
+            replayed = replayed.after_turn(turn.clone());
+        }
+
+        let mut chain = vec![self as &MutableGameState];
+        while let Some(prev) = chain[chain.len() - 1].prev_state.as_deref() {
+            chain.push(prev);
+        }
+        let oldest = chain.pop().expect("chain includes self");
+        debug_assert_eq!(
+            replayed, *oldest,
+            "compacted turns should replay to the oldest state"
+        );
+        replayed.compacted_history = None;
+
+        for state in chain.into_iter().rev() {
+            let mut newer = state.copy_state();
+            newer.prev_state = Some(Arc::new(replayed));
+            newer.compacted_history = None;
+            replayed = newer;
+        }
+        replayed
     }
 
     pub fn current_player_type(&self) -> PlayerType {
@@ -116,34 +621,28 @@ impl MutableGameState {
         self.common.player_text(player_id)
     }
 
-    pub fn doctor_moves_until_room(&self, room_id: RoomId) -> i32 {
-        let room_ids = &self.common.board.room_ids;
-        let room_count = room_ids.len();
-        if room_count == 0 {
-            return 0;
-        }
-
-        let doctor_idx = room_ids
-            .binary_search_by_key(&self.doctor_room_id.0, |candidate| candidate.0)
-            .expect("doctor room id not found in board room ids");
-        let target_idx = room_ids
-            .binary_search_by_key(&room_id.0, |candidate| candidate.0)
-            .expect("target room id not found in board room ids");
-
-        let distance = if target_idx >= doctor_idx {
-            target_idx - doctor_idx
-        } else {
-            room_count - (doctor_idx - target_idx)
-        };
+    pub fn player_display_text_for(&self, player_id: PlayerId) -> String {
+        self.common.player_display_text(player_id)
+    }
 
-        distance as i32
+    // None for a room that isn't on the board, which the doctor never reaches
+    pub fn doctor_moves_until_room(&self, room_id: RoomId) -> Option<i32> {
+        let board = &self.common.board;
+        board
+            .rooms
+            .contains_key(&room_id)
+            .then(|| board.doctor_moves_between(self.doctor_room_id, room_id))
     }
 
-    pub fn doctor_moves_until_player_room(&self, player_id: PlayerId) -> i32 {
-        self.doctor_moves_until_room(self.player_room_ids[player_id.0])
+    pub fn doctor_moves_until_player_room(&self, player_id: PlayerId) -> Option<i32> {
+        self.doctor_moves_until_room(*self.player_room_ids.get(player_id.0)?)
     }
 
     pub fn player_equivalent_clovers(&self, player_id: PlayerId) -> f64 {
+        self.player_clovers(player_id).to_f64()
+    }
+
+    pub fn player_clovers(&self, player_id: PlayerId) -> CardCount {
         let idx = player_id.0;
         self.player_failures[idx] * rule_helper::simple::CLOVERS_PER_FAILURE
             + self.player_weapons[idx] * rule_helper::simple::CLOVERS_PER_WEAPON
@@ -174,29 +673,47 @@ impl MutableGameState {
         text
     }
 
+    pub fn features(&self) -> GameFeatures {
+        self.features_for(self.common.to_normal_player_id(self.current_player_id))
+    }
+
+    pub fn features_for(&self, analysis_player_id: PlayerId) -> GameFeatures {
+        evaluation::game_features(self, analysis_player_id)
+    }
+
+    // in player id order
+    pub fn player_ids_in_room(&self, room_id: RoomId) -> Vec<PlayerId> {
+        self.common
+            .player_ids()
+            .filter(|player_id| self.player_room_ids[player_id.0] == room_id)
+            .collect::<Vec<_>>()
+    }
+
+    // the other players who can see into this room
+    pub fn watcher_ids(&self, room_id: RoomId, player_id: PlayerId) -> Vec<PlayerId> {
+        let watchers = self.sight_cache.watchers(room_id);
+        self.common
+            .player_ids()
+            .filter(|watcher_id| *watcher_id != player_id && watchers & (1 << watcher_id.0) != 0)
+            .collect::<Vec<_>>()
+    }
+
     pub fn player_sees_player(&self, player_id1: PlayerId, player_id2: PlayerId) -> bool {
         let room1 = self.player_room_ids[player_id1.0];
         let room2 = self.player_room_ids[player_id2.0];
-        self.common.board.sight[room1.0][room2.0]
+        self.common.board.sight[(room1, room2)]
     }
 
-    pub fn num_defensive_clovers(&self) -> f64 {
-        let mut clovers = 0.0;
-        let attacking_side = rule_helper::to_normal_player_id(
-            self.current_player_id,
-            self.common.num_normal_players,
-        );
+    pub fn num_defensive_clovers(&self) -> CardCount {
+        let mut clovers = CardCount::ZERO;
+        let attacking_side = self.common.to_normal_player_id(self.current_player_id);
 
         for pid in 0..self.common.num_normal_players {
             let pid = PlayerId(pid as usize);
             if pid != self.current_player_id {
                 if self.common.get_player_type(pid) == PlayerType::Normal {
-                    if pid != attacking_side {
-                        clovers += self.player_failures[pid.0]
-                            * rule_helper::simple::CLOVERS_PER_FAILURE
-                            + self.player_weapons[pid.0] * rule_helper::simple::CLOVERS_PER_WEAPON
-                            + self.player_move_cards[pid.0]
-                                * rule_helper::simple::CLOVERS_PER_MOVE_CARD;
+                    if self.common.to_normal_player_id(pid) != attacking_side {
+                        clovers += self.player_clovers(pid);
                     }
                 }
             }
@@ -222,25 +739,25 @@ impl MutableGameState {
         sb.push_str(&format!(
             "{leading_text}Turn {}, {}, HeuScore={}",
             self.turn_id,
-            self.player_text(),
+            self.player_display_text_for(self.current_player_id),
             heuristic_score_text,
         ));
 
-        let attacker_hist = self
-            .attacker_hist
+        let attack_hist = self
+            .attack_hist
             .iter()
-            .map(|player_id| CommonGameState::to_player_display_num(*player_id))
+            .map(|attack| CommonGameState::to_player_display_num(attack.attacker))
             .map(|id| id.to_string())
             .collect::<Vec<_>>()
             .join(",");
-        sb.push_str(&format!("\n{leading_text}  AttackHist={{{attacker_hist}}}"));
+        sb.push_str(&format!("\n{leading_text}  AttackHist={{{attack_hist}}}"));
         sb.push_str(&format!("\n{leading_text}  Dr@R{}", self.doctor_room_id.0));
 
         let players_who_can_see_doctor = self
             .common
             .player_ids()
             .zip(self.player_room_ids.iter().copied())
-            .filter(|(_, room_id)| self.common.board.sight[room_id.0][self.doctor_room_id.0])
+            .filter(|(_, room_id)| self.common.board.sight[(*room_id, self.doctor_room_id)])
             .map(|(pid, _)| CommonGameState::to_player_display_num(pid))
             .collect::<Vec<_>>();
 
@@ -265,7 +782,7 @@ impl MutableGameState {
                 sb.push_str(" *");
             }
 
-            if self.player_room_ids[player_id.0 as usize] == self.doctor_room_id {
+            if self.player_room_ids[player_id.0] == self.doctor_room_id {
                 sb.push_str(" D");
             }
         }
@@ -273,136 +790,409 @@ impl MutableGameState {
         sb
     }
 
-    pub fn check_normal_turn(&self, turn: &SimpleTurn) -> Result<(), String> {
+    pub fn check_normal_turn(&self, turn: &SimpleTurn) -> Result<(), ValidationError> {
+        if self.is_game_over() {
+            return Err(ValidationError::GameOver);
+        }
+
         for mv in &turn.moves {
-            if mv.player_id.0 >= self.common.num_all_players {
-                return Err(format!(
-                    "invalid playerId {} (displayed {})",
-                    mv.player_id.0,
-                    self.player_text_for(mv.player_id)
-                ));
+            if mv.player_id.0 >= self.common.num_all_players
+                || mv.player_id.0 >= self.player_room_ids.len()
+            {
+                return Err(ValidationError::InvalidPlayer {
+                    player_id: mv.player_id.0,
+                    player: self.player_text_for(mv.player_id),
+                });
             } else if !self.common.board.room_ids.contains(&mv.dest_room_id) {
-                return Err(format!("invalid roomId {}", mv.dest_room_id.0));
+                return Err(ValidationError::InvalidRoom {
+                    room_id: mv.dest_room_id.0,
+                });
             }
         }
 
-        let total_dist: i32 = turn
-            .moves
-            .iter()
-            .map(|mv| {
-                self.common.board.distance[self.player_room_ids[mv.player_id.0 as usize].0]
-                    [mv.dest_room_id.0]
+        let is_strict = self.common.rule_config.strict_stranger_moves;
+        // distances are measured from where pieces stood before the turn, so a second move of the
+        // same piece would be priced from the wrong room
+        if is_strict
+            && let Some(mv) = turn.moves.iter().enumerate().find_map(|(idx, mv)| {
+                turn.moves[..idx]
+                    .iter()
+                    .any(|earlier| earlier.player_id == mv.player_id)
+                    .then_some(mv)
             })
-            .sum();
+        {
+            return Err(ValidationError::PieceMovedTwice {
+                player: self.player_text(),
+                piece: self.player_text_for(mv.player_id),
+            });
+        }
 
-        if self.player_move_cards[self.current_player_id.0 as usize]
-            < (total_dist - 1).max(0) as f64
+        let total_dist = self.turn_total_distance(turn);
+        let move_cards = self.player_move_cards[self.current_player_id.0];
+        let move_cards_needed = self.turn_move_cards_needed(turn);
+
+        if move_cards < move_cards_needed
+            && is_strict
+            && move_cards >= Self::move_cards_for_distance(total_dist)
+            && let Some(stranger_move) = turn
+                .moves
+                .iter()
+                .find(|mv| mv.player_id != self.current_player_id)
         {
-            return Err(format!(
-                "player {} used too many move points ({total_dist})",
-                self.player_text()
-            ));
+            return Err(ValidationError::StrangerMoveNeedsMoveCards {
+                player: self.player_text(),
+                piece: self.player_text_for(stranger_move.player_id),
+                stranger_distance: turn
+                    .moves
+                    .iter()
+                    .filter(|mv| mv.player_id != self.current_player_id)
+                    .map(|mv| self.move_distance(mv))
+                    .sum(),
+                cost: move_cards_needed.to_f64(),
+                budget: move_cards.to_f64(),
+            });
+        }
+        if move_cards < move_cards_needed {
+            return Err(ValidationError::TooManyMovePoints {
+                player: self.player_text(),
+                distance: total_dist,
+                cost: move_cards_needed.to_f64(),
+                budget: move_cards.to_f64(),
+            });
         }
 
         for mv in &turn.moves {
-            if mv.player_id.0 >= self.player_room_ids.len() {
-                return Err(format!(
-                    "invalid player ({}) in move",
-                    self.player_text_for(mv.player_id)
-                ));
-            }
-
             if mv.player_id != self.current_player_id
                 && self.common.get_player_type(mv.player_id) != PlayerType::Stranger
             {
-                return Err(format!(
-                    "player {} tried to move non-stranger {}",
-                    self.player_text(),
-                    self.player_text_for(mv.player_id)
-                ));
+                return Err(ValidationError::MovedNonStranger {
+                    player: self.player_text(),
+                    piece: self.player_text_for(mv.player_id),
+                });
             }
         }
 
+        if let Some((mv, occupant)) = self.blocked_move(turn) {
+            return Err(ValidationError::RoomOccupied {
+                player: self.player_text(),
+                piece: self.player_text_for(mv.player_id),
+                occupant: self.player_text_for(occupant),
+                room_id: mv.dest_room_id.0,
+                room_label: self.common.board.room_label(mv.dest_room_id),
+            });
+        }
+
+        if self.activated_player_must_move() && !self.moves_current_player(turn) {
+            return Err(ValidationError::ActivatedPlayerMustMove {
+                player: self.player_text(),
+                room_id: self.doctor_room_id.0,
+                room_label: self.common.board.room_label(self.doctor_room_id),
+            });
+        }
+
         Ok(())
     }
 
-    pub fn after_turn(&self, turn: SimpleTurn) -> MutableGameState {
-        let mut new_state = self.copy_state();
-        new_state.apply_normal_turn(turn, true, false);
-        new_state
-    }
+    pub fn activation_context(&self) -> ActivationContext {
+        let activation = self
+            .doctor_activation
+            .filter(|activation| activation.activated_player_id == self.current_player_id);
+        let mut restrictions = Vec::new();
+        if self.activated_player_must_move() {
+            restrictions.push(ActivationRestriction::MustMove);
+        }
 
-    pub fn after_turn_without_memory(&self, turn: SimpleTurn) -> MutableGameState {
-        let mut new_state = self.copy_state();
-        new_state.apply_normal_turn(turn, false, false);
-        new_state
+        ActivationContext {
+            player_id: self.current_player_id,
+            activation,
+            skipped_player_ids: activation
+                .map(|activation| activation.skipped_player_ids(self.common.num_all_players))
+                .unwrap_or_default(),
+            restrictions,
+        }
     }
 
-    pub fn apply_turn(&mut self, turn: SimpleTurn) -> &mut Self {
-        self.apply_normal_turn(turn, true, false)
+    // whether occupied_rooms_blocked keeps this player's piece from moving into the room as things
+    // stand, before anything else moves this turn
+    pub fn is_room_blocked_for(&self, player_id: PlayerId, room_id: RoomId) -> bool {
+        self.common.rule_config.occupied_rooms_blocked
+            && self.player_room_ids.get(player_id.0) != Some(&room_id)
+            && self
+                .player_ids_in_room(room_id)
+                .into_iter()
+                .any(|occupant| occupant != player_id)
     }
 
-    fn apply_normal_turn(
-        &mut self,
-        turn: SimpleTurn,
-        remember_prev_state: bool,
-        want_log: bool,
-    ) -> &mut Self {
-        if remember_prev_state {
-            self.prev_state = Some(Rc::new(self.copy_state()));
+    // the first move that occupied_rooms_blocked forbids, with a piece it would end up beside
+    fn blocked_move<'a>(&self, turn: &'a SimpleTurn) -> Option<(&'a PieceMove, PlayerId)> {
+        if !self.common.rule_config.occupied_rooms_blocked {
+            return None;
         }
+        let room_after_turn = |player_id: PlayerId| {
+            turn.moves
+                .iter()
+                .rev()
+                .find(|mv| mv.player_id == player_id)
+                .map(|mv| mv.dest_room_id)
+                .unwrap_or(self.player_room_ids[player_id.0])
+        };
 
-        let total_dist: i32 = turn
-            .moves
+        turn.moves
             .iter()
-            .map(|mv| {
-                self.common.board.distance[self.player_room_ids[mv.player_id.0 as usize].0]
-                    [mv.dest_room_id.0]
+            .filter(|mv| mv.dest_room_id != self.player_room_ids[mv.player_id.0])
+            .find_map(|mv| {
+                self.common
+                    .player_ids()
+                    .find(|other| {
+                        *other != mv.player_id && room_after_turn(*other) == mv.dest_room_id
+                    })
+                    .map(|occupant| (mv, occupant))
             })
-            .sum();
-        let move_cards_used = (total_dist - 1).max(0) as f64;
-        let current_idx = self.current_player_id.0 as usize;
-        self.player_move_cards[current_idx] -= move_cards_used;
+    }
 
-        let mut moved_stranger_that_saw_doctor = false;
+    fn activated_player_must_move(&self) -> bool {
+        self.common.rule_config.activated_player_must_move
+            && self.common.get_player_type(self.current_player_id) == PlayerType::Normal
+            && self
+                .doctor_activation
+                .is_some_and(|activation| activation.activated_player_id == self.current_player_id)
+    }
 
-        for mv in &turn.moves {
-            let player_idx = mv.player_id.0 as usize;
-            let room_id = self.player_room_ids[player_idx];
-            if mv.player_id != self.current_player_id
-                && self.common.board.sight[room_id.0][self.doctor_room_id.0]
-            {
-                moved_stranger_that_saw_doctor = true;
-            }
+    // the turn without moves that leave a piece where it is, so staying put written either way, or
+    // a turn padded with such moves, compares equal to the plain turn
+    pub fn effective_turn(&self, turn: &SimpleTurn) -> SimpleTurn {
+        SimpleTurn::new(
+            turn.moves
+                .iter()
+                .copied()
+                .filter(|mv| self.player_room_ids.get(mv.player_id.0) != Some(&mv.dest_room_id)),
+        )
+    }
+
+    fn moves_current_player(&self, turn: &SimpleTurn) -> bool {
+        turn.moves.iter().any(|mv| {
+            mv.player_id == self.current_player_id
+                && mv.dest_room_id != self.player_room_ids[self.current_player_id.0]
+        })
+    }
 
-            self.player_room_ids[player_idx] = mv.dest_room_id;
+    pub fn check_normal_turn_for_player(
+        &self,
+        player_id: PlayerId,
+        turn: &SimpleTurn,
+    ) -> Result<(), ValidationError> {
+        if player_id != self.current_player_id {
+            let activated_room_id = self
+                .doctor_activation
+                .filter(|activation| activation.activated_player_id == self.current_player_id)
+                .map(|activation| activation.doctor_room_id);
+            return Err(ValidationError::NotPlayersTurn {
+                player: self.player_text_for(player_id),
+                current_player: self.player_text(),
+                activated_room_id: activated_room_id.map(|room_id| room_id.0),
+                activated_room_label: activated_room_id
+                    .map(|room_id| self.common.board.room_label(room_id)),
+            });
         }
 
-        self.prev_turn = turn;
+        self.check_normal_turn(turn)
+    }
 
-        let action = self.best_action_allowed(moved_stranger_that_saw_doctor);
+    // replaces any staged move for the same piece; moving a piece back to its room unstages it
+    pub fn stage_move(&mut self, piece_move: PieceMove) -> Result<(), ValidationError> {
+        let mut staged_plan = self
+            .staged_plan
+            .iter()
+            .filter(|mv| mv.player_id != piece_move.player_id)
+            .copied()
+            .collect::<Vec<_>>();
+        if self.player_room_ids.get(piece_move.player_id.0) != Some(&piece_move.dest_room_id) {
+            staged_plan.push(piece_move);
+        }
 
-        if action == PlayerAction::Attack {
-            if self.process_attack() {
-                self.winner = self.current_player_id;
-            }
-        } else if action == PlayerAction::Loot {
+        self.check_normal_turn(&SimpleTurn::new(staged_plan.iter().copied()))?;
+        self.staged_plan = staged_plan;
+        Ok(())
+    }
+
+    pub fn staged_turn(&self) -> SimpleTurn {
+        SimpleTurn::new(self.staged_plan.iter().copied())
+    }
+
+    pub fn staged_room_id(&self, player_id: PlayerId) -> RoomId {
+        self.staged_plan
+            .iter()
+            .find(|mv| mv.player_id == player_id)
+            .map_or(self.player_room_ids[player_id.0], |mv| mv.dest_room_id)
+    }
+
+    pub fn commit_staged_moves(&mut self) -> Result<&mut Self, ValidationError> {
+        self.try_apply_turn(self.staged_turn())
+    }
+
+    pub fn rollback_staged_moves(&mut self) {
+        self.staged_plan.clear();
+    }
+
+    pub fn move_distance(&self, piece_move: &PieceMove) -> i32 {
+        self.common.board.distance[(
+            self.player_room_ids[piece_move.player_id.0],
+            piece_move.dest_room_id,
+        )]
+    }
+
+    pub fn turn_total_distance(&self, turn: &SimpleTurn) -> i32 {
+        turn.moves.iter().map(|mv| self.move_distance(mv)).sum()
+    }
+
+    pub fn move_cards_for_distance(total_dist: i32) -> CardCount {
+        CardCount::whole((total_dist - 1).max(0) as i64)
+    }
+
+    // the first room of a turn is free; with strict_stranger_moves only for the mover's own piece
+    pub fn turn_move_cards_needed(&self, turn: &SimpleTurn) -> CardCount {
+        if !self.common.rule_config.strict_stranger_moves {
+            return Self::move_cards_for_distance(self.turn_total_distance(turn));
+        }
+        let (own_moves, stranger_moves) = turn
+            .moves
+            .iter()
+            .partition::<Vec<_>, _>(|mv| mv.player_id == self.current_player_id);
+        let own_dist = own_moves
+            .iter()
+            .map(|mv| self.move_distance(mv))
+            .sum::<i32>();
+        let stranger_dist = stranger_moves
+            .iter()
+            .map(|mv| self.move_distance(mv))
+            .sum::<i32>();
+        Self::move_cards_for_distance(own_dist) + CardCount::whole(stranger_dist as i64)
+    }
+
+    // farthest the current player could move this piece in a turn that moves nothing else, capped
+    // at the board's farthest room from it; 0 for pieces the current player can't move
+    pub fn max_move_distance(&self, player_id: PlayerId) -> i32 {
+        let Some(room_id) = self.player_room_ids.get(player_id.0) else {
+            return 0;
+        };
+        let is_own_piece = player_id == self.current_player_id;
+        if self.is_game_over()
+            || !self.is_normal_turn()
+            || (!is_own_piece && self.common.get_player_type(player_id) != PlayerType::Stranger)
+        {
+            return 0;
+        }
+
+        let whole_cards = self.player_move_cards[self.current_player_id.0]
+            .whole_cards()
+            .max(0) as i32;
+        let budget = if is_own_piece || !self.common.rule_config.strict_stranger_moves {
+            whole_cards + 1
+        } else {
+            whole_cards
+        };
+        let board = &self.common.board;
+        let farthest = board
+            .room_ids
+            .iter()
+            .map(|dest_room_id| board.distance[(*room_id, *dest_room_id)])
+            .max()
+            .unwrap_or(0);
+        budget.min(farthest)
+    }
+
+    pub fn after_turn(&self, turn: SimpleTurn) -> MutableGameState {
+        let mut new_state = self.copy_state();
+        new_state.apply_normal_turn(turn, true, false);
+        new_state
+    }
+
+    pub fn after_turn_without_memory(&self, turn: SimpleTurn) -> MutableGameState {
+        let mut new_state = self.copy_state();
+        new_state.apply_normal_turn(turn, false, false);
+        new_state
+    }
+
+    pub fn apply_turn(&mut self, turn: SimpleTurn) -> &mut Self {
+        self.apply_normal_turn(turn, true, false)
+    }
+
+    // apply_turn indexes by the turn's player ids, so input from outside should come through here
+    pub fn try_apply_turn(&mut self, turn: SimpleTurn) -> Result<&mut Self, ValidationError> {
+        self.check_normal_turn(&turn)?;
+        Ok(self.apply_turn(turn))
+    }
+
+    fn apply_normal_turn(
+        &mut self,
+        turn: SimpleTurn,
+        remember_prev_state: bool,
+        want_log: bool,
+    ) -> &mut Self {
+        self.staged_plan.clear();
+        if remember_prev_state {
+            self.prev_state = Some(Arc::new(self.copy_state()));
+        }
+        self.appraisal = None;
+
+        if self.common.rule_config.repetitions_for_draw > 0 {
+            self.position_hist.push(self.position_key());
+        }
+
+        let move_cards_used = self.turn_move_cards_needed(&turn);
+        let current_idx = self.current_player_id.0;
+        self.player_move_cards[current_idx] -= move_cards_used;
+
+        let mut moved_stranger_that_saw_doctor = false;
+
+        for mv in &turn.moves {
+            let room_id = self.player_room_ids[mv.player_id.0];
+            if mv.player_id != self.current_player_id
+                && self.common.board.sight[(room_id, self.doctor_room_id)]
+            {
+                moved_stranger_that_saw_doctor = true;
+            }
+
+            self.move_piece(mv.player_id, mv.dest_room_id);
+        }
+
+        self.prev_turn = turn;
+
+        let action = self.best_action_allowed(moved_stranger_that_saw_doctor);
+
+        if action == PlayerAction::Attack {
+            if self.process_attack() {
+                self.record_successful_attack(self.current_player_id);
+            }
+        } else if action == PlayerAction::Loot {
+            self.num_loots += 1;
             self.player_move_cards[current_idx] += rule_helper::simple::MOVE_CARDS_PER_LOOT;
             self.player_weapons[current_idx] += rule_helper::simple::WEAPONS_PER_LOOT;
             self.player_failures[current_idx] += rule_helper::simple::FAILURES_PER_LOOT;
         }
 
+        if !self.common.rule_plugins.is_empty() {
+            let event = TurnEvent {
+                player_id: self.current_player_id,
+                action,
+            };
+            for plugin in Arc::clone(&self.common.rule_plugins).iter() {
+                plugin.after_normal_turn(self, event);
+            }
+        }
+
         if !self.has_winner() {
             self.do_doctor_phase();
         }
 
         self.turn_id += 1;
+        self.check_draw();
 
         if want_log {
-            println!("{}", self.prev_turn_summary(true));
+            log::log(LogLevel::Info, || self.prev_turn_summary(true));
         }
 
-        if !self.has_winner() && !self.is_normal_turn() {
+        if !self.is_game_over() && !self.is_normal_turn() {
             return self.apply_stranger_turn(remember_prev_state, want_log);
         }
 
@@ -411,19 +1201,23 @@ impl MutableGameState {
 
     fn apply_stranger_turn(&mut self, remember_prev_state: bool, want_log: bool) -> &mut Self {
         if remember_prev_state {
-            self.prev_state = Some(Rc::new(self.copy_state()));
+            self.prev_state = Some(Arc::new(self.copy_state()));
+        }
+
+        if self.common.rule_config.repetitions_for_draw > 0 {
+            self.position_hist.push(self.position_key());
         }
 
         let mut best_action = self.best_action_allowed(false);
 
-        let current_player_idx = self.current_player_id.0 as usize;
+        let current_player_idx = self.current_player_id.0;
         let current_room = self.player_room_ids[current_player_idx];
         let new_room_id = if best_action == PlayerAction::Attack {
             current_room
         } else {
             Board::next_room_id(current_room, -1, &self.common.board.room_ids)
         };
-        self.player_room_ids[current_player_idx] = new_room_id;
+        self.move_piece(self.current_player_id, new_room_id);
 
         if best_action != PlayerAction::Attack {
             best_action = self.best_action_allowed(false);
@@ -431,11 +1225,7 @@ impl MutableGameState {
 
         if best_action == PlayerAction::Attack {
             if self.process_attack() {
-                self.current_player_id = rule_helper::to_normal_player_id(
-                    self.current_player_id,
-                    self.common.num_normal_players,
-                );
-                self.winner = self.current_player_id;
+                self.record_successful_attack(self.current_player_id);
             }
         }
 
@@ -444,43 +1234,92 @@ impl MutableGameState {
         }
 
         self.turn_id += 1;
+        self.check_draw();
 
         if want_log {
-            println!("{}", self.prev_turn_summary(true));
+            log::log(LogLevel::Info, || self.prev_turn_summary(true));
         }
 
-        if !self.has_winner() && !self.is_normal_turn() {
+        if !self.is_game_over() && !self.is_normal_turn() {
             return self.apply_stranger_turn(remember_prev_state, want_log);
         }
 
         self
     }
 
-    pub fn best_action_allowed(&self, moved_stranger_that_saw_doctor: bool) -> PlayerAction {
-        let mut seen_by_other_players = false;
-        let current_room_id = self.player_room_ids[self.current_player_id.0 as usize];
+    // a stranger's win goes to its normal player, who also becomes the current player; in team
+    // games the win goes to the team, recorded under its first player
+    fn record_successful_attack(&mut self, attacker: PlayerId) {
+        let side = self.common.to_normal_player_id(attacker);
+        self.successful_attacks[side.0] += 1;
 
-        for player_id in self.common.player_ids() {
-            if player_id != self.current_player_id
-                && self.common.board.sight[current_room_id.0]
-                    [self.player_room_ids[player_id.0 as usize].0]
-            {
-                seen_by_other_players = true;
-                break;
+        if self.successful_attacks[side.0] >= self.common.rule_config.successful_attacks_to_win {
+            self.current_player_id = self.common.seat_map().controller_of(attacker);
+            self.result = GameResult::Won { player: side };
+        }
+    }
+
+    fn check_draw(&mut self) {
+        if self.has_winner() {
+            return;
+        }
+
+        let rule_config = &self.common.rule_config;
+        if rule_config.repetitions_for_draw > 0 {
+            let position_key = self.position_key();
+            let num_prev_occurrences = self
+                .position_hist
+                .iter()
+                .filter(|key| *key == position_key)
+                .count();
+            if num_prev_occurrences + 1 >= rule_config.repetitions_for_draw as usize {
+                self.result = GameResult::Draw {
+                    reason: DrawReason::Repetition,
+                };
+                return;
             }
         }
 
-        if seen_by_other_players {
+        if rule_config.doctor_escape_turns > 0 && self.turn_id > rule_config.doctor_escape_turns {
+            self.result = GameResult::Draw {
+                reason: DrawReason::DoctorEscaped,
+            };
+        }
+    }
+
+    pub fn best_action_allowed(&self, moved_stranger_that_saw_doctor: bool) -> PlayerAction {
+        debug_assert_eq!(
+            self.sight_cache,
+            SightCache::new(&self.common.board, &self.player_room_ids),
+            "sight_cache is out of step with player_room_ids"
+        );
+        self.action_allowed_in(
+            self.player_room_ids[self.current_player_id.0],
+            moved_stranger_that_saw_doctor,
+        )
+    }
+
+    // what the current player could do after moving their own piece to this room, with everyone
+    // else where they stand
+    pub fn action_allowed_in(
+        &self,
+        current_room_id: RoomId,
+        moved_stranger_that_saw_doctor: bool,
+    ) -> PlayerAction {
+        if self
+            .sight_cache
+            .is_seen_by_others(current_room_id, self.current_player_id)
+        {
             return PlayerAction::None;
         }
 
         if current_room_id == self.doctor_room_id
-            && (!rule_helper::simple::STRANGERS_ARE_NOSY || !moved_stranger_that_saw_doctor)
+            && (!self.common.rule_config.strangers_are_nosy || !moved_stranger_that_saw_doctor)
         {
             return PlayerAction::Attack;
         }
 
-        if self.common.board.sight[current_room_id.0][self.doctor_room_id.0] {
+        if self.common.board.sight[(current_room_id, self.doctor_room_id)] {
             PlayerAction::None
         } else {
             PlayerAction::Loot
@@ -514,14 +1353,18 @@ impl MutableGameState {
     }
 
     pub fn heuristic_score(&self, analysis_player_id: PlayerId) -> f64 {
-        if self.has_winner() {
-            return if analysis_player_id
-                == rule_helper::to_normal_player_id(self.winner, self.common.num_normal_players)
-            {
-                rule_helper::HEURISTIC_SCORE_WIN
-            } else {
-                rule_helper::HEURISTIC_SCORE_LOSS
-            };
+        match self.result {
+            GameResult::Draw { .. } | GameResult::Aborted => return 0.0,
+            GameResult::Won { player } => {
+                return if self.common.to_normal_player_id(analysis_player_id)
+                    == self.common.to_normal_player_id(player)
+                {
+                    rule_helper::HEURISTIC_SCORE_WIN
+                } else {
+                    rule_helper::HEURISTIC_SCORE_LOSS
+                };
+            }
+            GameResult::InProgress => {}
         }
 
         let misc_score = |player_id: PlayerId,
@@ -533,44 +1376,28 @@ impl MutableGameState {
             allied_strength
                 + 0.5
                     * allied_strength
-                    * (self.player_move_cards[player_id.0 as usize]
+                    * (self.player_move_cards[player_id.0].to_f64()
                         + if is_allied_turn { 0.95 } else { 0.0 }
                         + allied_doctor_advantage * 0.9)
-                + 0.5 * self.player_weapons[player_id.0 as usize]
-                + 0.125 * self.player_failures[player_id.0 as usize]
+                + 0.5 * self.player_weapons[player_id.0].to_f64()
+                + 0.125 * self.player_failures[player_id.0].to_f64()
         };
 
         if self.common.has_strangers() {
-            let stranger_ally = rule_helper::allied_stranger(analysis_player_id);
-            let normal_opponent = rule_helper::opposing_normal_player(analysis_player_id);
-            let stranger_opponent = rule_helper::allied_stranger(normal_opponent);
-            let allied_strength = self.player_strengths[analysis_player_id.0 as usize]
-                + self.player_strengths[stranger_ally.0 as usize];
-            let opponent_strength = self.player_strengths[normal_opponent.0 as usize]
-                + self.player_strengths[stranger_opponent.0 as usize];
+            let seats = self.common.seat_map();
+            let normal_opponent = seats.opposing_normal_player(analysis_player_id);
+            let side_strength = |normal_player_id: PlayerId| {
+                self.player_strengths[normal_player_id.0]
+                    + seats
+                        .allied_strangers(normal_player_id)
+                        .iter()
+                        .map(|stranger_id| self.player_strengths[stranger_id.0])
+                        .sum::<i32>()
+            };
+            let allied_strength = side_strength(analysis_player_id);
+            let opponent_strength = side_strength(normal_opponent);
             let is_my_turn = analysis_player_id == self.current_player_id;
-            let allied_doctor_advantage = self.doctor_score_with_rooms(
-                self.player_room_ids[if is_my_turn {
-                    analysis_player_id.0
-                } else {
-                    normal_opponent.0
-                } as usize],
-                self.player_room_ids[if is_my_turn {
-                    stranger_ally.0
-                } else {
-                    stranger_opponent.0
-                } as usize],
-                self.player_room_ids[if is_my_turn {
-                    normal_opponent.0
-                } else {
-                    analysis_player_id.0
-                } as usize],
-                self.player_room_ids[if is_my_turn {
-                    stranger_opponent.0
-                } else {
-                    stranger_ally.0
-                } as usize],
-            ) * if is_my_turn { 1.0 } else { -1.0 };
+            let allied_doctor_advantage = self.allied_doctor_advantage(analysis_player_id);
 
             misc_score(
                 analysis_player_id,
@@ -584,21 +1411,26 @@ impl MutableGameState {
                 -allied_doctor_advantage,
             )
         } else {
+            // teammates count fully; opponents are scaled so both sides weigh the same in total
+            let analysis_side = self.common.to_normal_player_id(analysis_player_id);
+            let num_allies = self
+                .common
+                .seat_map()
+                .num_allied_normal_players(analysis_player_id);
+            let opponent_weight =
+                -(num_allies as f64) / ((self.common.num_normal_players - num_allies) as f64);
             let mut score = 0.0;
             for pid in 0..self.common.num_all_players as usize {
                 let pid = PlayerId(pid);
-                let weight =
-                    if rule_helper::to_normal_player_id(pid, self.common.num_normal_players)
-                        == analysis_player_id
-                    {
-                        1.0
-                    } else {
-                        -1.0 / ((self.common.num_normal_players - 1) as f64)
-                    };
+                let weight = if self.common.to_normal_player_id(pid) == analysis_side {
+                    1.0
+                } else {
+                    opponent_weight
+                };
                 score += weight
                     * misc_score(
                         pid,
-                        self.player_strengths[pid.0 as usize],
+                        self.player_strengths[pid.0],
                         pid == self.current_player_id,
                         0.0,
                     );
@@ -607,22 +1439,46 @@ impl MutableGameState {
         }
     }
 
+    pub fn allied_doctor_advantage(&self, analysis_player_id: PlayerId) -> f64 {
+        let normal_opponent = self
+            .common
+            .seat_map()
+            .opposing_normal_player(analysis_player_id);
+        let is_my_turn = analysis_player_id == self.current_player_id;
+        if is_my_turn {
+            self.doctor_score_for(analysis_player_id)
+        } else {
+            -self.doctor_score_for(normal_opponent)
+        }
+    }
+
     pub fn doctor_score(&self) -> f64 {
+        self.doctor_score_for(self.current_player_id)
+    }
+
+    // doctor_score as if it were normal_player_id's turn
+    fn doctor_score_for(&self, normal_player_id: PlayerId) -> f64 {
+        let seats = self.common.seat_map();
+        let side = seats.side_of(normal_player_id);
+        let stranger_rooms_of = |side: Option<Side>| {
+            side.into_iter()
+                .flat_map(move |side| seats.stranger_ids_of(side))
+                .map(|player_id| self.player_room_ids[player_id.0])
+        };
         self.doctor_score_with_rooms(
-            self.player_room_ids[self.current_player_id.0 as usize],
-            self.player_room_ids[rule_helper::allied_stranger(self.current_player_id).0 as usize],
-            self.player_room_ids
-                [rule_helper::opposing_normal_player(self.current_player_id).0 as usize],
-            self.player_room_ids[rule_helper::opposing_stranger(self.current_player_id).0 as usize],
+            self.player_room_ids[normal_player_id.0],
+            stranger_rooms_of(side),
+            self.player_room_ids[seats.opposing_normal_player(normal_player_id).0],
+            stranger_rooms_of(side.map(Side::opponent)),
         )
     }
 
     pub fn doctor_score_with_rooms(
         &self,
         my_room: RoomId,
-        stranger_ally_room: RoomId,
+        stranger_ally_rooms: impl IntoIterator<Item = RoomId>,
         normal_enemy_room: RoomId,
-        stranger_enemy_room: RoomId,
+        stranger_enemy_rooms: impl IntoIterator<Item = RoomId>,
     ) -> f64 {
         const DECAY_FACTOR_NORMAL: f64 = 0.9;
         const DECAY_FACTOR_STRANGER: f64 = 0.5;
@@ -642,66 +1498,136 @@ impl MutableGameState {
                 .doctor_future_near_distance(next_doctor_room_id, my_room)
         };
 
-        let stranger_ally_doctor_dist = self
-            .common
-            .board
-            .doctor_future_visit_distance(next_doctor_room_id, stranger_ally_room)
-            as f64;
-        let normal_enemy_doctor_dist = self
-            .common
-            .board
-            .doctor_future_visit_distance(next_doctor_room_id, normal_enemy_room)
-            as f64;
-        let stranger_enemy_doctor_dist = self
-            .common
-            .board
-            .doctor_future_visit_distance(next_doctor_room_id, stranger_enemy_room)
-            as f64;
+        let visit_distance = |room_id: RoomId| {
+            self.common
+                .board
+                .doctor_future_visit_distance(next_doctor_room_id, room_id) as f64
+        };
+        let stranger_score = |room_id: RoomId| DECAY_FACTOR_STRANGER.powf(visit_distance(room_id));
 
         DECAY_FACTOR_NORMAL.powi(my_doctor_dist)
-            + DECAY_FACTOR_STRANGER.powf(stranger_ally_doctor_dist)
-            - DECAY_FACTOR_NORMAL.powf(normal_enemy_doctor_dist)
-            - DECAY_FACTOR_STRANGER.powf(stranger_enemy_doctor_dist)
+            + stranger_ally_rooms
+                .into_iter()
+                .map(stranger_score)
+                .sum::<f64>()
+            - DECAY_FACTOR_NORMAL.powf(visit_distance(normal_enemy_room))
+            - stranger_enemy_rooms
+                .into_iter()
+                .map(stranger_score)
+                .sum::<f64>()
     }
 
     pub fn possible_turns(&self) -> Vec<SimpleTurn> {
-        if self.has_winner() {
+        if self.is_game_over() {
             return Vec::new();
         }
-        let dist_allowed = self.player_move_cards[self.current_player_id.0 as usize] as i32 + 1;
+        let dist_allowed =
+            self.player_move_cards[self.current_player_id.0].whole_cards() as i32 + 1;
         let mut turns = vec![SimpleTurn::single(
             self.current_player_id,
-            self.player_room_ids[self.current_player_id.0 as usize],
+            self.player_room_ids[self.current_player_id.0],
         )];
         turns.extend(self.possible_turns_single(dist_allowed, self.current_player_id));
 
         if self.common.has_strangers() {
-            let allied_stranger = rule_helper::allied_stranger(self.current_player_id);
-            let opposing_stranger = rule_helper::opposing_stranger(self.current_player_id);
-
-            turns.extend(self.possible_turns_single(dist_allowed, allied_stranger));
-            turns.extend(self.possible_turns_single(dist_allowed, opposing_stranger));
+            // allied strangers first, then opposing ones
+            let seats = self.common.seat_map();
+            let strangers = seats
+                .allied_strangers(self.current_player_id)
+                .into_iter()
+                .chain(seats.opposing_strangers(self.current_player_id))
+                .collect::<Vec<_>>();
+
+            for stranger_id in &strangers {
+                turns.extend(self.possible_turns_single(dist_allowed, *stranger_id));
+            }
 
-            if self.player_move_cards[self.current_player_id.0 as usize] > 0.0 {
-                turns.extend(self.possible_turns_dual(
-                    dist_allowed,
-                    self.current_player_id,
-                    allied_stranger,
-                ));
-                turns.extend(self.possible_turns_dual(
-                    dist_allowed,
-                    self.current_player_id,
-                    opposing_stranger,
-                ));
-                turns.extend(self.possible_turns_dual(
-                    dist_allowed,
-                    allied_stranger,
-                    opposing_stranger,
-                ));
+            if self.player_move_cards[self.current_player_id.0].is_positive() {
+                for stranger_id in &strangers {
+                    turns.extend(self.possible_turns_dual(
+                        dist_allowed,
+                        self.current_player_id,
+                        *stranger_id,
+                    ));
+                }
+                for (idx, first_stranger_id) in strangers.iter().enumerate() {
+                    for second_stranger_id in &strangers[idx + 1..] {
+                        turns.extend(self.possible_turns_dual(
+                            dist_allowed,
+                            *first_stranger_id,
+                            *second_stranger_id,
+                        ));
+                    }
+                }
             }
         }
 
+        if self.activated_player_must_move() {
+            turns.retain(|turn| self.moves_current_player(turn));
+        }
+        if self.common.rule_config.strict_stranger_moves {
+            // the turns above share the free room between pieces, so some cost a card too many
+            let move_cards = self.player_move_cards[self.current_player_id.0];
+            turns.retain(|turn| self.turn_move_cards_needed(turn) <= move_cards);
+        }
+        if self.common.rule_config.occupied_rooms_blocked {
+            turns.retain(|turn| self.blocked_move(turn).is_none());
+        }
+
+        turns
+    }
+
+    pub fn pruned_possible_turns(&self) -> Vec<SimpleTurn> {
+        let turns = self.possible_turns();
+        if !self.common.has_strangers() {
+            return turns;
+        }
+
         turns
+            .into_iter()
+            .filter(|turn| !self.moves_opposing_stranger_pointlessly(turn))
+            .collect::<Vec<_>>()
+    }
+
+    // moving an opposing stranger closer to the doctor only helps the opponent unless it changes
+    // who can see the current player
+    fn moves_opposing_stranger_pointlessly(&self, turn: &SimpleTurn) -> bool {
+        self.common
+            .seat_map()
+            .opposing_strangers(self.current_player_id)
+            .into_iter()
+            .any(|opposing_stranger| {
+                turn.moves
+                    .iter()
+                    .find(|mv| mv.player_id == opposing_stranger)
+                    .is_some_and(|stranger_move| {
+                        self.moves_stranger_pointlessly(turn, opposing_stranger, stranger_move)
+                    })
+            })
+    }
+
+    fn moves_stranger_pointlessly(
+        &self,
+        turn: &SimpleTurn,
+        opposing_stranger: PlayerId,
+        stranger_move: &PieceMove,
+    ) -> bool {
+        let my_room_id = turn
+            .moves
+            .iter()
+            .find(|mv| mv.player_id == self.current_player_id)
+            .map(|mv| mv.dest_room_id)
+            .unwrap_or(self.player_room_ids[self.current_player_id.0]);
+        let src_room_id = self.player_room_ids[opposing_stranger.0];
+        let dest_room_id = stranger_move.dest_room_id;
+        let board = &self.common.board;
+        if board.sight[(src_room_id, my_room_id)] != board.sight[(dest_room_id, my_room_id)] {
+            return false;
+        }
+
+        let next_doctor_room_id = board.next_room_id_in_doctor_visit_order(self.doctor_room_id, 1);
+        board.doctor_future_visit_distance(next_doctor_room_id, dest_room_id)
+            <= board.doctor_future_visit_distance(next_doctor_room_id, src_room_id)
     }
 
     pub fn prev_player_heuristic_score(&self) -> f64 {
@@ -731,9 +1657,9 @@ impl MutableGameState {
         dist_allowed: i32,
         movable_player: PlayerId,
     ) -> Vec<SimpleTurn> {
-        let movable_room = self.player_room_ids[movable_player.0 as usize];
+        let movable_room = self.player_room_ids[movable_player.0];
         let room_ids = &self.common.board.room_ids;
-        let distance = &self.common.board.distance[movable_room.0];
+        let distance = &self.common.board.distance[movable_room];
         let mut turns = Vec::with_capacity(room_ids.len());
 
         for dest_room in room_ids {
@@ -751,14 +1677,14 @@ impl MutableGameState {
         movable_player_a: PlayerId,
         movable_player_b: PlayerId,
     ) -> Vec<SimpleTurn> {
-        let src_room_a = self.player_room_ids[movable_player_a.0 as usize];
-        let src_room_b = self.player_room_ids[movable_player_b.0 as usize];
+        let src_room_a = self.player_room_ids[movable_player_a.0];
+        let src_room_b = self.player_room_ids[movable_player_b.0];
         let room_ids = &self.common.board.room_ids;
         let distance = &self.common.board.distance;
         let mut turns = Vec::with_capacity(room_ids.len() * room_ids.len());
 
         for dst_room_a in room_ids {
-            let dist_remaining = dist_allowed - distance[src_room_a.0][dst_room_a.0];
+            let dist_remaining = dist_allowed - distance[(src_room_a, *dst_room_a)];
 
             if dist_remaining <= 0 || src_room_a == *dst_room_a {
                 continue;
@@ -767,8 +1693,7 @@ impl MutableGameState {
             let move_a = PieceMove::new(movable_player_a, *dst_room_a);
 
             for dst_room_b in room_ids {
-                if distance[src_room_b.0][dst_room_b.0] > dist_remaining
-                    || src_room_b == *dst_room_b
+                if distance[(src_room_b, *dst_room_b)] > dist_remaining || src_room_b == *dst_room_b
                 {
                     continue;
                 }
@@ -781,58 +1706,99 @@ impl MutableGameState {
         turns
     }
 
+    // plays out an attack by the given piece from this position on a scratch copy, recording each
+    // defender card spent in the order the rules spend them
+    pub fn simulate_attack(&self, attacker: PlayerId) -> AttackSimulation {
+        let mut state = self.clone();
+        state.current_player_id = attacker;
+        let attacker_idx = attacker.0;
+        let base_strength = state.player_strengths[attacker_idx];
+        let weapons_before = state.player_weapons[attacker_idx];
+        let allowed_now = state.best_action_allowed(false) == PlayerAction::Attack;
+        let mut card_spends = Some(Vec::new());
+        let succeeded = state.resolve_attack(&mut card_spends);
+        let weapon_used = state.player_weapons[attacker_idx] < weapons_before;
+
+        AttackSimulation {
+            attacker,
+            allowed_now,
+            base_strength,
+            weapon_used,
+            attack_strength: state
+                .attack_hist
+                .last()
+                .map(|attack| attack.strength)
+                .unwrap_or_default(),
+            card_spends: card_spends.unwrap_or_default(),
+            succeeded,
+        }
+    }
+
     fn process_attack(&mut self) -> bool {
-        let current_idx = self.current_player_id.0 as usize;
-        let mut attack_strength = self.player_strengths[current_idx] as f64;
+        self.resolve_attack(&mut None)
+    }
+
+    fn resolve_attack(&mut self, card_spends: &mut Option<Vec<DefenseCardSpend>>) -> bool {
+        let current_idx = self.current_player_id.0;
+        let base_strength = self.player_strengths[current_idx];
+        let weapons_before = self.player_weapons[current_idx];
         self.player_strengths[current_idx] += 1;
-        self.attacker_hist.push(self.current_player_id);
 
+        let mut attack_strength = CardCount::whole(base_strength as i64);
+        let succeeded = self.resolve_attack_strength(&mut attack_strength, card_spends);
+        let weapon_bonus = if self.player_weapons[current_idx] < weapons_before {
+            rule_helper::simple::STRENGTH_PER_WEAPON
+        } else {
+            0
+        };
+        let strength = CardCount::whole(base_strength as i64 + weapon_bonus);
+        self.attack_hist.push(AttackRecord {
+            turn_id: self.turn_id,
+            attacker: self.current_player_id,
+            strength,
+            defense_spent: strength - attack_strength,
+            succeeded,
+        });
+        succeeded
+    }
+
+    // attack_strength ends as what the defenders left of it
+    fn resolve_attack_strength(
+        &mut self,
+        attack_strength: &mut CardCount,
+        card_spends: &mut Option<Vec<DefenseCardSpend>>,
+    ) -> bool {
+        let current_idx = self.current_player_id.0;
         if self.common.has_strangers() {
-            if attack_strength < 0.0 {
+            if *attack_strength < CardCount::ZERO {
                 return false;
             }
 
             if self.is_normal_turn() {
-                use_weapon(&mut self.player_weapons, current_idx, &mut attack_strength);
+                use_weapon(&mut self.player_weapons, current_idx, attack_strength);
             }
 
-            let defender = rule_helper::opposing_normal_player(self.current_player_id);
-            let defender_idx = defender.0 as usize;
-
-            defend_with_card_type(
-                defender_idx,
-                &mut attack_strength,
-                &mut self.player_failures,
-                rule_helper::simple::CLOVERS_PER_FAILURE,
-            );
-            defend_with_card_type(
-                defender_idx,
-                &mut attack_strength,
-                &mut self.player_weapons,
-                rule_helper::simple::CLOVERS_PER_WEAPON,
-            );
-            defend_with_card_type(
-                defender_idx,
-                &mut attack_strength,
-                &mut self.player_move_cards,
-                rule_helper::simple::CLOVERS_PER_MOVE_CARD,
-            );
+            let defender = self
+                .common
+                .seat_map()
+                .opposing_normal_player(self.current_player_id);
+            self.defend(defender, attack_strength, card_spends);
 
-            attack_strength > 0.0
+            attack_strength.is_positive()
         } else {
             let num_defensive_clovers = self.num_defensive_clovers();
 
-            if num_defensive_clovers <= 2.0 * attack_strength {
-                use_weapon(&mut self.player_weapons, current_idx, &mut attack_strength);
+            if num_defensive_clovers <= *attack_strength * 2 {
+                use_weapon(&mut self.player_weapons, current_idx, attack_strength);
             }
 
-            if num_defensive_clovers < attack_strength {
+            if num_defensive_clovers < *attack_strength {
                 return true;
             }
 
             let mut defender = self.current_player_id;
 
-            while attack_strength > 0.0 {
+            while attack_strength.is_positive() {
                 defender = PlayerId(positive_remainder(
                     defender.0 as i32 - 1,
                     self.common.num_all_players as usize,
@@ -842,28 +1808,51 @@ impl MutableGameState {
                     return true;
                 }
 
-                let defender_idx = defender.0 as usize;
-                defend_with_card_type(
-                    defender_idx,
-                    &mut attack_strength,
+                self.defend(defender, attack_strength, card_spends);
+            }
+
+            false
+        }
+    }
+
+    fn defend(
+        &mut self,
+        defender: PlayerId,
+        attack_strength: &mut CardCount,
+        card_spends: &mut Option<Vec<DefenseCardSpend>>,
+    ) {
+        for card_type in [
+            DefenseCardType::Failure,
+            DefenseCardType::Weapon,
+            DefenseCardType::MoveCard,
+        ] {
+            let (player_cards, clovers_per_card) = match card_type {
+                DefenseCardType::Failure => (
                     &mut self.player_failures,
                     rule_helper::simple::CLOVERS_PER_FAILURE,
-                );
-                defend_with_card_type(
-                    defender_idx,
-                    &mut attack_strength,
+                ),
+                DefenseCardType::Weapon => (
                     &mut self.player_weapons,
                     rule_helper::simple::CLOVERS_PER_WEAPON,
-                );
-                defend_with_card_type(
-                    defender_idx,
-                    &mut attack_strength,
+                ),
+                DefenseCardType::MoveCard => (
                     &mut self.player_move_cards,
                     rule_helper::simple::CLOVERS_PER_MOVE_CARD,
-                );
-            }
+                ),
+            };
+            let cards_spent =
+                defend_with_card_type(defender.0, attack_strength, player_cards, clovers_per_card);
 
-            false
+            if let Some(card_spends) = card_spends.as_mut()
+                && cards_spent.is_positive()
+            {
+                card_spends.push(DefenseCardSpend {
+                    defender,
+                    card_type,
+                    cards_spent,
+                    strength_remaining: *attack_strength,
+                });
+            }
         }
     }
 
@@ -874,6 +1863,7 @@ impl MutableGameState {
         self.current_player_id = PlayerId(
             (self.current_player_id.0 + 1).rem_euclid(self.common.num_all_players as usize),
         );
+        self.doctor_activation = None;
 
         if self.turn_id >= self.common.num_all_players as i32 {
             for player_offset in 0..self.common.num_all_players {
@@ -881,7 +1871,13 @@ impl MutableGameState {
                     (self.current_player_id.0 + player_offset)
                         .rem_euclid(self.common.num_all_players as usize),
                 );
-                if self.player_room_ids[player_id.0 as usize] == self.doctor_room_id {
+                if self.player_room_ids[player_id.0] == self.doctor_room_id {
+                    self.doctor_activation = Some(DoctorActivation {
+                        turn_id: self.turn_id + 1,
+                        doctor_room_id: self.doctor_room_id,
+                        activated_player_id: player_id,
+                        rotation_player_id: self.current_player_id,
+                    });
                     self.current_player_id = player_id;
                     break;
                 }
@@ -889,6 +1885,23 @@ impl MutableGameState {
         }
     }
 
+    // what the mover did after moving on the turn that led here; None before any turn
+    pub fn prev_turn_action(&self) -> PlayerAction {
+        let Some(prev_state) = self.prev_state.as_deref() else {
+            return PlayerAction::None;
+        };
+        let prev_player = prev_state.current_player_id;
+        if prev_state.attack_hist.len() != self.attack_hist.len() {
+            PlayerAction::Attack
+        } else if prev_state.player_move_cards[prev_player.0].fraction()
+            != self.player_move_cards[prev_player.0].fraction()
+        {
+            PlayerAction::Loot
+        } else {
+            PlayerAction::None
+        }
+    }
+
     pub fn prev_turn_summary(&self, verbose: bool) -> String {
         let Some(prev_state) = self.prev_state.as_deref() else {
             return "PrevStateNull".to_string();
@@ -900,11 +1913,11 @@ impl MutableGameState {
         let mut total_dist = 0;
 
         for player_id in self.common.player_ids() {
-            let prev_room_id = prev_state.player_room_ids[player_id.0 as usize];
-            let room_id = self.player_room_ids[player_id.0 as usize];
+            let prev_room_id = prev_state.player_room_ids[player_id.0];
+            let room_id = self.player_room_ids[player_id.0];
 
             if prev_room_id != room_id {
-                let dist = self.common.board.distance[prev_room_id.0][room_id.0];
+                let dist = self.common.board.distance[(prev_room_id, room_id)];
                 let dist_text = if dist == 0 {
                     String::new()
                 } else {
@@ -919,17 +1932,17 @@ impl MutableGameState {
                     prev_room_id.0
                 ));
                 verbose_move_texts.push(format!(
-                    "    MOVE {}: R{} to R{}{}",
-                    self.player_text_for(player_id),
-                    prev_room_id.0,
-                    room_id.0,
+                    "    MOVE {}: {} to {}{}",
+                    self.player_display_text_for(player_id),
+                    self.common.board.room_label(prev_room_id),
+                    self.common.board.room_label(room_id),
                     dist_text
                 ));
             }
         }
 
         if short_move_texts.is_empty() {
-            let room_id = self.player_room_ids[prev_player.0 as usize];
+            let room_id = self.player_room_ids[prev_player.0];
             short_move_texts.push(format!(
                 "{}@{}({})",
                 CommonGameState::to_player_display_num(prev_player),
@@ -937,21 +1950,13 @@ impl MutableGameState {
                 room_id.0
             ));
             verbose_move_texts.push(format!(
-                "    MOVE {}: stayed at R{}",
-                self.player_text_for(prev_player),
-                room_id.0
+                "    MOVE {}: stayed at {}",
+                self.player_display_text_for(prev_player),
+                self.common.board.room_label(room_id)
             ));
         }
 
-        let action = if prev_state.attacker_hist.len() != self.attacker_hist.len() {
-            PlayerAction::Attack
-        } else if prev_state.player_move_cards[prev_player.0 as usize] % 1.0
-            != self.player_move_cards[prev_player.0 as usize] % 1.0
-        {
-            PlayerAction::Loot
-        } else {
-            PlayerAction::None
-        };
+        let action = self.prev_turn_action();
 
         let move_signifier = if prev_state.is_normal_turn() {
             "M".repeat((total_dist - 1).max(0) as usize)
@@ -963,10 +1968,10 @@ impl MutableGameState {
             PlayerAction::Loot => "L",
             PlayerAction::None => "",
         };
-        let win_text = if self.has_winner() {
-            format!("({} won)", self.player_text_for(self.winner))
-        } else {
-            String::new()
+        let win_text = match self.result {
+            GameResult::Won { player } => format!("({} won)", self.player_text_for(player)),
+            GameResult::Draw { reason } => format!("({reason})"),
+            GameResult::InProgress | GameResult::Aborted => String::new(),
         };
 
         let short_summary = format!(
@@ -1001,24 +2006,24 @@ impl MutableGameState {
                 sb.push('\n');
                 sb.push_str(&format!(
                     "    LOOT {}: now {}",
-                    self.player_text_for(prev_player),
+                    self.player_display_text_for(prev_player),
                     self.player_text_long(prev_player)
                 ));
             }
             PlayerAction::Attack => {
-                let weapon_bonus = if prev_state.player_weapons[prev_player.0 as usize]
-                    == self.player_weapons[prev_player.0 as usize]
+                let weapon_bonus = if prev_state.player_weapons[prev_player.0]
+                    == self.player_weapons[prev_player.0]
                 {
-                    0.0
+                    0
                 } else {
                     rule_helper::simple::STRENGTH_PER_WEAPON
                 };
                 let attack_strength =
-                    prev_state.player_strengths[prev_player.0 as usize] as f64 + weapon_bonus;
+                    prev_state.player_strengths[prev_player.0] as i64 + weapon_bonus;
                 let hist_text = self
-                    .attacker_hist
+                    .attack_hist
                     .iter()
-                    .map(|player_id| CommonGameState::to_player_display_num(*player_id))
+                    .map(|attack| CommonGameState::to_player_display_num(attack.attacker))
                     .map(|id| id.to_string())
                     .collect::<Vec<_>>()
                     .join(",");
@@ -1030,25 +2035,28 @@ impl MutableGameState {
             PlayerAction::None => {}
         }
 
-        if self.has_winner() {
+        if let Some(winner) = self.result.winner() {
             sb.push('\n');
             sb.push_str(&format!(
                 "    WINNER: {}",
-                self.player_text_for(self.winner)
+                self.player_display_text_for(winner)
             ));
+        } else if let Some(draw_reason) = self.draw_reason() {
+            sb.push('\n');
+            sb.push_str(&format!("    DRAW: {draw_reason}"));
         } else {
             sb.push('\n');
             sb.push_str(&format!(
-                "    DR MOVE: R{} to R{}",
-                prev_state.doctor_room_id.0, self.doctor_room_id.0
+                "    DR MOVE: {} to {}",
+                self.common.board.room_label(prev_state.doctor_room_id),
+                self.common.board.room_label(self.doctor_room_id)
             ));
 
-            if self.doctor_room_id == self.player_room_ids[self.current_player_id.0 as usize] {
+            if self.doctor_room_id == self.player_room_ids[self.current_player_id.0] {
                 let other_players_in_room = self
-                    .common
-                    .player_ids()
+                    .player_ids_in_room(self.doctor_room_id)
+                    .into_iter()
                     .filter(|pid| *pid != self.current_player_id)
-                    .filter(|pid| self.player_room_ids[(*pid).0 as usize] == self.doctor_room_id)
                     .map(CommonGameState::to_player_display_num)
                     .collect::<Vec<_>>();
 
@@ -1081,34 +2089,142 @@ impl MutableGameState {
         sb
     }
 
-    pub fn prev_turn_summaries_since_normal(&self, verbose: bool) -> String {
-        let mut summaries = Vec::new();
-        let mut state = self;
+    pub fn doctor_activation_hist(&self) -> Vec<DoctorActivation> {
+        let mut activations = Vec::new();
+        let mut state = Some(self);
 
-        while let Some(prev_state) = state.prev_state.as_deref() {
-            summaries.push(state.prev_turn_summary(verbose));
-            if prev_state.is_normal_turn() {
-                break;
+        while let Some(curr_state) = state {
+            if let Some(activation) = curr_state.doctor_activation {
+                activations.push(activation);
             }
-            state = prev_state;
+            state = curr_state.prev_state.as_deref();
         }
 
-        summaries.into_iter().rev().collect::<Vec<_>>().join("\n")
+        activations.reverse();
+        activations
     }
 
-    pub fn animation_frames_since_normal(&self) -> Vec<[RoomId; 5]> {
-        let mut states = Vec::new();
-        let mut state = self;
-
-        while let Some(prev_state) = state.prev_state.as_deref() {
-            states.push(state);
-            if prev_state.is_normal_turn() {
-                break;
-            }
-            state = prev_state;
-        }
-
-        if states.is_empty() {
+    pub fn stranger_loop_threats(&self, analysis_player_id: PlayerId) -> Vec<StrangerLoopThreat> {
+        if !self.common.has_strangers() {
+            return Vec::new();
+        }
+
+        let seats = self.common.seat_map();
+        let normal_player_id = seats.to_normal_player_id(analysis_player_id);
+        let rooms_of = |player_ids: Vec<PlayerId>| {
+            player_ids
+                .into_iter()
+                .map(|player_id| self.player_room_ids[player_id.0])
+                .collect::<Vec<_>>()
+        };
+        let stranger_ally_room_ids = rooms_of(seats.allied_strangers(normal_player_id));
+        let stranger_opponent_room_ids = rooms_of(seats.opposing_strangers(normal_player_id));
+
+        let mut threats = self
+            .common
+            .board
+            .stranger_loop_room_ids
+            .iter()
+            .map(|(enemy_room_id, ally_room_ids)| {
+                let mut counter_room_ids = ally_room_ids.iter().copied().collect::<Vec<_>>();
+                counter_room_ids.sort_by_key(|room_id| room_id.0);
+                StrangerLoopThreat {
+                    enemy_room_id: *enemy_room_id,
+                    opposing_stranger_is_here: stranger_opponent_room_ids.contains(enemy_room_id),
+                    is_countered: stranger_ally_room_ids
+                        .iter()
+                        .any(|room_id| ally_room_ids.contains(room_id)),
+                    counter_room_ids,
+                }
+            })
+            .collect::<Vec<_>>();
+        threats.sort_by_key(|threat| threat.enemy_room_id.0);
+        threats
+    }
+
+    // the enemy rooms of uncountered stranger loops that an opposing stranger already holds or can
+    // reach with one move, so the analysis player is in or about to be in the trap
+    pub fn stranger_loop_warning_room_ids(&self, analysis_player_id: PlayerId) -> Vec<RoomId> {
+        let seats = self.common.seat_map();
+        let normal_player_id = seats.to_normal_player_id(analysis_player_id);
+        let stranger_opponent_room_ids = seats
+            .opposing_strangers(normal_player_id)
+            .into_iter()
+            .map(|player_id| self.player_room_ids[player_id.0])
+            .collect::<Vec<_>>();
+        self.stranger_loop_threats(analysis_player_id)
+            .into_iter()
+            .filter(|threat| !threat.is_countered)
+            .filter(|threat| {
+                threat.opposing_stranger_is_here
+                    || stranger_opponent_room_ids.iter().any(|room_id| {
+                        self.common.board.distance[(*room_id, threat.enemy_room_id)] <= 1
+                    })
+            })
+            .map(|threat| threat.enemy_room_id)
+            .collect::<Vec<_>>()
+    }
+
+    pub fn prev_turn_summaries_since_normal(&self, verbose: bool) -> String {
+        let mut summaries = Vec::new();
+        let mut state = self;
+
+        while let Some(prev_state) = state.prev_state.as_deref() {
+            summaries.push(state.prev_turn_summary(verbose));
+            if prev_state.is_normal_turn() {
+                break;
+            }
+            state = prev_state;
+        }
+
+        summaries.into_iter().rev().collect::<Vec<_>>().join("\n")
+    }
+
+    // the rooms a piece (the doctor for None) stood in over the last num_plies normal turns, oldest
+    // first, each with the ply it got there by; stranger turns count, and staying put adds nothing
+    pub fn room_trail(
+        &self,
+        player_id: Option<PlayerId>,
+        num_plies: usize,
+    ) -> Vec<(usize, RoomId)> {
+        let room_of = |state: &MutableGameState| match player_id {
+            Some(player_id) => state.player_room_ids[player_id.0],
+            None => state.doctor_room_id,
+        };
+        let rehydrated = self.rehydrated();
+        let mut ply = self.ply().max(0) as usize;
+        let first_ply = ply.saturating_sub(num_plies);
+        let mut trail = Vec::new();
+        let mut cursor = Some(&rehydrated);
+        while let Some(state) = cursor {
+            trail.push((ply, room_of(state)));
+            if ply <= first_ply && state.is_normal_turn() {
+                break;
+            }
+            cursor = state.prev_state.as_deref();
+            if cursor.is_some_and(|prev_state| prev_state.is_normal_turn()) {
+                ply = ply.saturating_sub(1);
+            }
+        }
+        trail.reverse();
+        trail.dedup_by_key(|(_, room_id)| *room_id);
+        trail
+    }
+
+    // each frame is the doctor's room, then every player's room in seat map display order
+    pub fn animation_frames_since_normal(&self) -> Vec<Vec<RoomId>> {
+        let mut states = Vec::new();
+        let mut state = self;
+
+        while let Some(prev_state) = state.prev_state.as_deref() {
+            states.push(state);
+            if prev_state.is_normal_turn() {
+                break;
+            }
+            state = prev_state;
+        }
+
+        if states.is_empty() {
             return Vec::new();
         }
 
@@ -1126,61 +2242,61 @@ impl MutableGameState {
         }
 
         let mut frames = Vec::with_capacity(states.len() + 1);
-        frames.push(Self::frame_from_positions(
-            prev_state.doctor_room_id,
-            &baseline_player_rooms,
-        ));
+        frames.push(self.frame_from_positions(prev_state.doctor_room_id, &baseline_player_rooms));
 
         for state in states {
-            frames.push(Self::frame_from_positions(
-                state.doctor_room_id,
-                &state.player_room_ids,
-            ));
+            frames.push(self.frame_from_positions(state.doctor_room_id, &state.player_room_ids));
         }
 
         frames
     }
 
-    fn frame_from_positions(doctor_room_id: RoomId, player_room_ids: &[RoomId]) -> [RoomId; 5] {
-        [
-            doctor_room_id,
-            player_room_ids
-                .get(rule_helper::SIDE_A_NORMAL_PLAYER_ID.0)
-                .copied()
-                .unwrap_or(RoomId(0)),
-            player_room_ids
-                .get(rule_helper::SIDE_B_NORMAL_PLAYER_ID.0)
-                .copied()
-                .unwrap_or(RoomId(0)),
-            player_room_ids
-                .get(rule_helper::STRANGER_PLAYER_ID_FIRST.0)
-                .copied()
-                .unwrap_or(RoomId(0)),
-            player_room_ids
-                .get(rule_helper::STRANGER_PLAYER_ID_SECOND.0)
-                .copied()
-                .unwrap_or(RoomId(0)),
-        ]
+    fn frame_from_positions(
+        &self,
+        doctor_room_id: RoomId,
+        player_room_ids: &[RoomId],
+    ) -> Vec<RoomId> {
+        std::iter::once(doctor_room_id)
+            .chain(
+                self.common
+                    .seat_map()
+                    .display_order_player_ids()
+                    .into_iter()
+                    .map(|player_id| {
+                        player_room_ids
+                            .get(player_id.0)
+                            .copied()
+                            .unwrap_or(RoomId(0))
+                    }),
+            )
+            .collect::<Vec<_>>()
     }
 }
 
-fn use_weapon(player_weapons: &mut [f64], idx: usize, attack_strength: &mut f64) {
-    if player_weapons[idx] >= 1.0 {
-        *attack_strength += rule_helper::simple::STRENGTH_PER_WEAPON;
-        player_weapons[idx] -= 1.0;
+fn use_weapon(player_weapons: &mut [CardCount], idx: usize, attack_strength: &mut CardCount) {
+    if player_weapons[idx] >= CardCount::whole(1) {
+        *attack_strength += CardCount::whole(rule_helper::simple::STRENGTH_PER_WEAPON);
+        player_weapons[idx] -= CardCount::whole(1);
     }
 }
 
+// a remainder smaller than one unit can't be covered by a partial card, so it spends one more unit
 fn defend_with_card_type(
     idx: usize,
-    attack_strength: &mut f64,
-    player_cards: &mut [f64],
-    clovers_per_card: f64,
-) {
-    if *attack_strength > 0.0 && player_cards[idx] > 0.0 {
-        let num_used_cards = player_cards[idx].min(*attack_strength / clovers_per_card);
+    attack_strength: &mut CardCount,
+    player_cards: &mut [CardCount],
+    clovers_per_card: i64,
+) -> CardCount {
+    if attack_strength.is_positive() && player_cards[idx].is_positive() {
+        let cards_needed = CardCount::from_units(
+            (attack_strength.units() + clovers_per_card - 1).div_euclid(clovers_per_card),
+        );
+        let num_used_cards = player_cards[idx].min(cards_needed);
         player_cards[idx] -= num_used_cards;
         *attack_strength -= num_used_cards * clovers_per_card;
+        num_used_cards
+    } else {
+        CardCount::ZERO
     }
 }
 
@@ -1194,7 +2310,8 @@ impl PartialEq for MutableGameState {
             && self.player_weapons == other.player_weapons
             && self.player_failures == other.player_failures
             && self.player_strengths == other.player_strengths
-            && self.winner == other.winner
+            && self.successful_attacks == other.successful_attacks
+            && self.result == other.result
     }
 }
 
@@ -1222,11 +2339,7 @@ impl fmt::Display for MutableGameState {
 
 impl Hash for MutableGameState {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.common.hash(state);
-        self.current_player_id.hash(state);
-        (self.doctor_room_id.0 << 3).hash(state);
-        (self.winner.0 << 8).hash(state);
-        self.player_room_ids.hash(state);
+        self.hash_with_rooms(state, self.doctor_room_id, &self.player_room_ids);
     }
 }
 
@@ -1243,7 +2356,12 @@ fn positive_remainder(x: i32, modulus: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::room::Room;
+    use crate::core::{
+        room::Room,
+        rule_config::RuleConfig,
+        seat_map::{StrangerCounts, Teams},
+    };
+    use std::collections::HashSet;
 
     fn sample_board() -> Board {
         let rooms = vec![
@@ -1287,6 +2405,149 @@ mod tests {
             })
     }
 
+    #[test]
+    fn room_trail_follows_a_piece_back_through_recent_plies() {
+        let mut state = tiny_two_player_game_state();
+        let start_room_id = state.player_room_ids[0];
+        for _ in 0..4 {
+            let turn = state
+                .possible_turns()
+                .into_iter()
+                .last()
+                .expect("the game should be in progress");
+            state = state.after_turn(turn);
+        }
+
+        let trail = state.room_trail(Some(PlayerId(0)), 4);
+        assert_eq!(trail.first(), Some(&(0, start_room_id)));
+        assert_eq!(
+            trail.last().map(|(_, room_id)| *room_id),
+            Some(state.player_room_ids[0])
+        );
+        assert!(
+            trail
+                .windows(2)
+                .all(|pair| pair[0].0 <= pair[1].0 && pair[0].1 != pair[1].1)
+        );
+        assert_eq!(state.room_trail(Some(PlayerId(0)), 99), trail);
+
+        let doctor_trail = state.room_trail(None, 1);
+        assert!(doctor_trail.iter().all(|(ply, _)| *ply >= 3));
+        assert_eq!(
+            doctor_trail.last().map(|(_, room_id)| *room_id),
+            Some(state.doctor_room_id)
+        );
+
+        let mut compacted = state.clone();
+        compacted.compact_history(2);
+        assert_eq!(compacted.room_trail(Some(PlayerId(0)), 4), trail);
+    }
+
+    #[test]
+    fn compact_history_keeps_turns_and_rehydrates_to_same_chain() {
+        let mut state = tiny_two_player_game_state();
+        for _ in 0..6 {
+            let turn = state.possible_turns()[0].clone();
+            state = state.after_turn(turn);
+        }
+        let full_turns = state.normal_turns();
+        let full_ply = state.ply();
+        let full_len = state.history_len();
+        let uncompacted = state.clone();
+
+        state.compact_history(2);
+        assert!(state.history_len() < full_len);
+        assert!(state.compacted_history.is_some());
+        assert_eq!(state.normal_turns(), full_turns);
+        assert_eq!(state.ply(), full_ply);
+
+        let turn = state.possible_turns()[0].clone();
+        let mut continued = state.after_turn(turn.clone());
+        continued.compact_history(2);
+        assert_eq!(continued.normal_turns().len(), full_turns.len() + 1);
+
+        let rehydrated = continued.rehydrated();
+        assert!(rehydrated.compacted_history.is_none());
+        assert_eq!(
+            rehydrated.history_len(),
+            uncompacted.after_turn(turn).history_len()
+        );
+        assert_eq!(rehydrated.normal_turns(), continued.normal_turns());
+        assert_eq!(rehydrated, continued);
+    }
+
+    #[test]
+    fn staged_moves_validate_together_and_commit_as_one_turn() {
+        let common = CommonGameState::from_num_normal_players(true, sample_board(), 2);
+        let mut state = MutableGameState::at_start(common);
+        assert!(
+            state
+                .stage_move(PieceMove::new(PlayerId(0), RoomId(9)))
+                .is_err()
+        );
+        assert!(state.staged_plan.is_empty());
+
+        state
+            .stage_move(PieceMove::new(PlayerId(1), RoomId(2)))
+            .expect("stranger move should stage");
+        state
+            .stage_move(PieceMove::new(PlayerId(0), RoomId(2)))
+            .expect("adjacent move should stage");
+        assert_eq!(state.staged_room_id(PlayerId(0)), RoomId(2));
+        assert_eq!(state.player_room_ids[0], RoomId(1));
+        assert!(
+            state
+                .stage_move(PieceMove::new(PlayerId(2), RoomId(2)))
+                .is_err()
+        );
+
+        state
+            .stage_move(PieceMove::new(PlayerId(1), RoomId(1)))
+            .expect("moving back should unstage");
+        assert_eq!(
+            state.staged_plan,
+            vec![PieceMove::new(PlayerId(0), RoomId(2))]
+        );
+
+        state.rollback_staged_moves();
+        assert!(state.staged_plan.is_empty());
+
+        state
+            .stage_move(PieceMove::new(PlayerId(0), RoomId(2)))
+            .expect("adjacent move should stage");
+        state
+            .commit_staged_moves()
+            .expect("staged turn should apply");
+        assert!(state.staged_plan.is_empty());
+        assert_eq!(state.player_room_ids[0], RoomId(2));
+        assert_eq!(state.ply(), 1);
+    }
+
+    #[test]
+    fn player_ids_in_room_follows_moves() {
+        let mut state = tiny_two_player_game_state();
+        let start_room_id = state.player_room_ids[0];
+        let other_room_id = *state
+            .common
+            .board
+            .room_ids
+            .iter()
+            .find(|room_id| **room_id != start_room_id)
+            .expect("board has another room");
+        assert_eq!(
+            state.player_ids_in_room(start_room_id),
+            state.common.player_ids().collect::<Vec<_>>()
+        );
+
+        state.move_piece(PlayerId(1), other_room_id);
+        assert_eq!(state.player_ids_in_room(other_room_id), vec![PlayerId(1)]);
+        assert!(
+            !state
+                .player_ids_in_room(start_room_id)
+                .contains(&PlayerId(1))
+        );
+    }
+
     #[test]
     fn at_start_initializes_arrays() {
         let game = sample_game_state();
@@ -1296,8 +2557,8 @@ mod tests {
             game.player_room_ids.len(),
             game.common.num_all_players as usize
         );
-        assert!(game.attacker_hist.is_empty());
-        assert_eq!(game.winner, PlayerId::INVALID);
+        assert!(game.attack_hist.is_empty());
+        assert_eq!(game.result, GameResult::InProgress);
         assert_eq!(
             game.player_move_cards,
             vec![rule_helper::simple::PLAYER_STARTING_MOVE_CARDS; 3]
@@ -1314,11 +2575,32 @@ mod tests {
         assert!(game.check_normal_turn(&invalid_room_turn).is_err());
     }
 
+    #[test]
+    fn try_apply_turn_rejects_bad_turns_without_touching_state() {
+        let mut game = sample_game_state();
+        let turn_id_before = game.turn_id;
+
+        let err = game
+            .try_apply_turn(SimpleTurn::single(PlayerId(7), RoomId(2)))
+            .expect_err("unknown player should be rejected");
+        assert_eq!(err.code(), "invalidPlayer");
+        assert_eq!(game.turn_id, turn_id_before);
+
+        game.result = GameResult::Won {
+            player: PlayerId(0),
+        };
+        let err = game
+            .try_apply_turn(SimpleTurn::new(std::iter::empty()))
+            .expect_err("finished game should reject turns");
+        assert_eq!(err, ValidationError::GameOver);
+        assert_eq!(game.turn_id, turn_id_before);
+    }
+
     #[test]
     fn after_normal_turn_loots_when_doctor_unseen() {
         let mut game = sample_game_state();
         game.doctor_room_id = RoomId(3);
-        game.player_room_ids = vec![RoomId(1), RoomId(3), RoomId(3)];
+        game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
         let turn = SimpleTurn::single(PlayerId(0), RoomId(2));
         let starting_move_cards = game.player_move_cards[0];
         game.apply_normal_turn(turn.clone(), false, false);
@@ -1328,13 +2610,573 @@ mod tests {
             game.player_move_cards[0] > starting_move_cards,
             "player should have looted and gained move cards"
         );
+        assert_eq!(game.num_loots, 1);
         assert_eq!(game.prev_turn, turn);
     }
 
+    #[test]
+    fn creaky_floorboards_plugin_spoils_the_loot_after_the_turn() {
+        let mut game = sample_game_state();
+        game.common = game.common.clone().with_rule_config(RuleConfig {
+            creaky_floorboards_one_in: 1,
+            ..RuleConfig::default()
+        });
+        game.doctor_room_id = RoomId(3);
+        game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
+        let starting_move_cards = game.player_move_cards[0];
+        let move_cards_used =
+            game.turn_move_cards_needed(&SimpleTurn::single(PlayerId(0), RoomId(2)));
+        game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), false, false);
+
+        assert_eq!(game.num_loots, 0);
+        assert_eq!(
+            game.player_move_cards[0],
+            starting_move_cards - move_cards_used
+        );
+    }
+
+    #[test]
+    fn second_successful_attack_wins_when_rules_require_two() {
+        let mut game = sample_game_state();
+        game.common = game.common.clone().with_rule_config(RuleConfig {
+            successful_attacks_to_win: 2,
+            ..RuleConfig::default()
+        });
+        game.turn_id = 5;
+        game.doctor_room_id = RoomId(2);
+        game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
+        for pid in 1..3 {
+            game.player_failures[pid] = CardCount::ZERO;
+            game.player_weapons[pid] = CardCount::ZERO;
+            game.player_move_cards[pid] = CardCount::ZERO;
+        }
+        game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), false, false);
+
+        assert!(!game.has_winner());
+        assert_eq!(game.successful_attacks[0], 1);
+        assert_eq!(game.doctor_room_id, RoomId(3));
+
+        let mut game = game.copy_state();
+        game.current_player_id = PlayerId(0);
+        game.doctor_room_id = RoomId(2);
+        game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), false, false);
+
+        assert_eq!(game.winner(), PlayerId(0));
+        assert_eq!(game.successful_attacks[0], 2);
+    }
+
+    #[test]
+    fn attack_hist_records_strength_defense_and_outcome() {
+        let attacked_game = |defender_failures: i64| {
+            let mut game = sample_game_state();
+            game.turn_id = 5;
+            game.doctor_room_id = RoomId(2);
+            game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
+            for pid in 1..3 {
+                game.player_failures[pid] = CardCount::whole(defender_failures);
+                game.player_weapons[pid] = CardCount::ZERO;
+                game.player_move_cards[pid] = CardCount::ZERO;
+            }
+            game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), false, false);
+            game
+        };
+
+        let undefended = attacked_game(0);
+        let attack = undefended.attack_hist[0];
+        assert_eq!(attack.turn_id, 5);
+        assert_eq!(attack.attacker, PlayerId(0));
+        assert!(attack.succeeded);
+        assert_eq!(attack.defense_spent, CardCount::ZERO);
+
+        let defended = attacked_game(10);
+        let attack = defended.attack_hist[0];
+        assert!(!attack.succeeded);
+        assert!(attack.defense_spent >= attack.strength);
+        assert!(attack.strength >= CardCount::whole(1));
+    }
+
+    #[test]
+    fn doctor_escape_ends_game_in_draw() {
+        let mut game = sample_game_state();
+        game.common = game.common.clone().with_rule_config(RuleConfig {
+            doctor_escape_turns: 5,
+            ..RuleConfig::default()
+        });
+        game.turn_id = 5;
+        game.doctor_room_id = RoomId(3);
+        game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
+        game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), true, false);
+
+        assert_eq!(game.draw_reason(), Some(DrawReason::DoctorEscaped));
+        assert!(game.is_game_over());
+        assert!(!game.has_winner());
+        assert_eq!(game.heuristic_score(PlayerId(0)), 0.0);
+        assert!(game.prev_turn_summary(false).contains("doctor escaped"));
+    }
+
+    #[test]
+    fn third_repetition_of_a_position_is_a_draw() {
+        let play_standing_still = |repetitions_for_draw| {
+            let mut game = sample_game_state();
+            game.common = game.common.clone().with_rule_config(RuleConfig {
+                repetitions_for_draw,
+                ..RuleConfig::default()
+            });
+            game.set_player_room_ids(vec![RoomId(2), RoomId(2), RoomId(2)]);
+            let mut num_turns = 0;
+            while !game.is_game_over() && num_turns < 30 {
+                let turn = SimpleTurn::single(game.current_player_id, RoomId(2));
+                game.apply_normal_turn(turn, false, false);
+                num_turns += 1;
+            }
+            game
+        };
+
+        let game = play_standing_still(3);
+        assert_eq!(game.draw_reason(), Some(DrawReason::Repetition));
+        assert_eq!(game.position_hist.len(), game.turn_id as usize - 1);
+        assert!(game.possible_turns().is_empty());
+        assert!(!play_standing_still(0).is_game_over());
+    }
+
+    #[test]
+    fn defense_spends_exact_card_fractions() {
+        let mut game = sample_game_state();
+        game.turn_id = 5;
+        game.doctor_room_id = RoomId(2);
+        game.set_player_room_ids(vec![RoomId(1), RoomId(3), RoomId(3)]);
+        game.player_weapons[0] = CardCount::ZERO;
+        game.player_failures[2] = rule_helper::simple::JUST_OVER_ONE_THIRD;
+        game.player_weapons[2] = CardCount::ZERO;
+        game.player_move_cards[2] = CardCount::ZERO;
+        game.player_failures[1] = CardCount::ZERO;
+        game.player_weapons[1] = CardCount::ZERO;
+        game.player_move_cards[1] = CardCount::whole(3);
+        game.apply_normal_turn(SimpleTurn::single(PlayerId(0), RoomId(2)), false, false);
+
+        assert!(!game.has_winner());
+        assert_eq!(game.player_failures[2], CardCount::ZERO);
+        assert_eq!(
+            game.player_move_cards[1],
+            CardCount::whole(3)
+                - (CardCount::whole(1) - rule_helper::simple::JUST_OVER_ONE_THIRD * 2)
+        );
+    }
+
+    #[test]
+    fn simulate_attack_reports_spends_without_mutating() {
+        let mut game = sample_game_state();
+        game.turn_id = 5;
+        game.doctor_room_id = RoomId(2);
+        game.set_player_room_ids(vec![RoomId(2), RoomId(3), RoomId(3)]);
+        game.player_weapons[0] = CardCount::ZERO;
+        game.player_failures[2] = rule_helper::simple::JUST_OVER_ONE_THIRD;
+        game.player_weapons[2] = CardCount::ZERO;
+        game.player_move_cards[2] = CardCount::ZERO;
+        game.player_failures[1] = CardCount::ZERO;
+        game.player_weapons[1] = CardCount::ZERO;
+        game.player_move_cards[1] = CardCount::whole(3);
+        let before = game.clone();
+
+        let simulation = game.simulate_attack(PlayerId(0));
+
+        assert_eq!(game, before);
+        assert!(simulation.allowed_now);
+        assert!(!simulation.succeeded);
+        assert!(!simulation.weapon_used);
+        assert_eq!(simulation.attack_strength, CardCount::whole(1));
+        let spent = simulation
+            .card_spends
+            .iter()
+            .map(|spend| (spend.defender, spend.card_type, spend.cards_spent))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            spent,
+            vec![
+                (
+                    PlayerId(2),
+                    DefenseCardType::Failure,
+                    rule_helper::simple::JUST_OVER_ONE_THIRD
+                ),
+                (
+                    PlayerId(1),
+                    DefenseCardType::MoveCard,
+                    CardCount::whole(1) - rule_helper::simple::JUST_OVER_ONE_THIRD * 2
+                ),
+            ]
+        );
+        assert_eq!(
+            simulation
+                .card_spends
+                .last()
+                .map(|spend| spend.strength_remaining),
+            Some(CardCount::ZERO)
+        );
+    }
+
+    #[test]
+    fn doctor_activation_records_activated_and_skipped_players() {
+        let mut game = sample_game_state();
+        game.turn_id = 5;
+        game.doctor_room_id = RoomId(1);
+        game.set_player_room_ids(vec![RoomId(3), RoomId(3), RoomId(2)]);
+        game.apply_turn(SimpleTurn::single(PlayerId(0), RoomId(3)));
+
+        let activation = game
+            .doctor_activation
+            .expect("doctor should activate the player in R2");
+        assert_eq!(activation.turn_id, 6);
+        assert_eq!(activation.doctor_room_id, RoomId(2));
+        assert_eq!(activation.activated_player_id, PlayerId(2));
+        assert_eq!(activation.skipped_player_ids(3), vec![PlayerId(1)]);
+        assert_eq!(game.doctor_activation_hist(), vec![activation]);
+
+        let pass = SimpleTurn::single(PlayerId(1), RoomId(3));
+        let err = game
+            .check_normal_turn_for_player(PlayerId(1), &pass)
+            .expect_err("skipped player should not be able to submit");
+        assert!(err.to_string().contains("doctor activated"), "{err}");
+        assert!(
+            game.check_normal_turn_for_player(
+                PlayerId(2),
+                &SimpleTurn::single(PlayerId(2), RoomId(2))
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn classic_rules_make_an_activated_player_move() {
+        let activated_game = |rule_config: RuleConfig| {
+            let common = CommonGameState::from_num_normal_players(true, sample_board(), 3)
+                .with_rule_config(rule_config);
+            let mut game = MutableGameState::at_start(common);
+            game.turn_id = 5;
+            game.doctor_room_id = RoomId(1);
+            game.set_player_room_ids(vec![RoomId(3), RoomId(3), RoomId(2)]);
+            game.apply_turn(SimpleTurn::single(PlayerId(0), RoomId(3)));
+            game
+        };
+        let stay = SimpleTurn::single(PlayerId(2), RoomId(2));
+
+        let game = activated_game(RuleConfig::default());
+        let context = game.activation_context();
+        assert_eq!(context.player_id, PlayerId(2));
+        assert_eq!(context.skipped_player_ids, vec![PlayerId(1)]);
+        assert!(context.restrictions.is_empty());
+        assert!(game.check_normal_turn(&stay).is_ok());
+
+        let game = activated_game(RuleConfig::classic());
+        assert_eq!(
+            game.activation_context().restrictions,
+            vec![ActivationRestriction::MustMove]
+        );
+        let err = game
+            .check_normal_turn(&stay)
+            .expect_err("staying should be refused");
+        assert_eq!(err.code(), "activatedPlayerMustMove");
+        assert!(
+            game.check_normal_turn(&SimpleTurn::single(PlayerId(2), RoomId(1)))
+                .is_ok()
+        );
+        let turns = game.possible_turns();
+        assert!(!turns.is_empty());
+        assert!(turns.iter().all(|turn| game.moves_current_player(turn)));
+
+        // the doctor reaching the next player in rotation still activates them
+        let after = game.after_turn(SimpleTurn::single(PlayerId(2), RoomId(1)));
+        let context = after.activation_context();
+        assert_eq!(context.player_id, PlayerId(0));
+        assert!(context.skipped_player_ids.is_empty());
+        assert_eq!(context.restrictions, vec![ActivationRestriction::MustMove]);
+    }
+
+    #[test]
+    fn strict_stranger_moves_charge_every_stranger_room() {
+        let strict_game = |strict_stranger_moves: bool| {
+            let board = Board::from_embedded_json("Tiny").expect("Tiny board should be available");
+            let common = CommonGameState::from_num_normal_players(true, board, 2).with_rule_config(
+                RuleConfig {
+                    strict_stranger_moves,
+                    ..RuleConfig::default()
+                },
+            );
+            let mut game = MutableGameState::at_start(common);
+            game.player_move_cards[0] = CardCount::ZERO;
+            game
+        };
+        let game = strict_game(false);
+        let stranger_id = rule_helper::SIDE_A_STRANGER_PLAYER_ID;
+        let stranger_room_id = game.player_room_ids[stranger_id.0];
+        let next_room_id = *game
+            .common
+            .board
+            .room_ids
+            .iter()
+            .find(|room_id| game.common.board.distance[(stranger_room_id, **room_id)] == 1)
+            .expect("stranger should have a neighboring room");
+        let stranger_turn = SimpleTurn::single(stranger_id, next_room_id);
+        assert!(game.check_normal_turn(&stranger_turn).is_ok());
+
+        let mut game = strict_game(true);
+        let err = game
+            .check_normal_turn(&stranger_turn)
+            .expect_err("a stranger room should need a move card");
+        assert_eq!(err.code(), "strangerMoveNeedsMoveCards");
+        assert!(
+            err.to_string().contains("needs 1 move card(s) but has 0"),
+            "{err}"
+        );
+        assert!(game.possible_turns().iter().all(|turn| {
+            turn.moves
+                .iter()
+                .all(|mv| mv.player_id == game.current_player_id)
+        }));
+
+        game.player_move_cards[0] = CardCount::whole(1);
+        assert!(game.possible_turns().contains(&stranger_turn));
+        assert_eq!(
+            game.turn_move_cards_needed(&stranger_turn),
+            CardCount::whole(1)
+        );
+        let twice = SimpleTurn::new([
+            PieceMove::new(stranger_id, next_room_id),
+            PieceMove::new(stranger_id, stranger_room_id),
+        ]);
+        assert_eq!(
+            game.check_normal_turn(&twice)
+                .expect_err("a piece should only move once")
+                .code(),
+            "pieceMovedTwice"
+        );
+
+        // applying charges the card too; a loot afterwards adds back less than one
+        let after = game.after_turn(stranger_turn);
+        assert_eq!(after.player_move_cards[0].whole_cards(), 0);
+    }
+
+    #[test]
+    fn occupied_rooms_block_moves_that_end_beside_another_player() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should be available");
+        let common =
+            CommonGameState::from_num_normal_players(true, board, 2).with_rule_config(RuleConfig {
+                occupied_rooms_blocked: true,
+                ..RuleConfig::default()
+            });
+        let mut game = MutableGameState::at_start(common);
+        game.player_move_cards[0] = CardCount::whole(3);
+        let stranger_id = rule_helper::SIDE_A_STRANGER_PLAYER_ID;
+        let start_room_id = game.player_room_ids[0];
+        let mut other_room_ids = game
+            .common
+            .board
+            .room_ids
+            .iter()
+            .copied()
+            .filter(|room_id| *room_id != start_room_id)
+            .collect::<Vec<_>>();
+        other_room_ids.sort_by_key(|room_id| game.common.board.distance[(start_room_id, *room_id)]);
+        let (occupied_room_id, empty_room_id) = (other_room_ids[0], other_room_ids[1]);
+        game.move_piece(stranger_id, occupied_room_id);
+
+        let into_occupied = SimpleTurn::single(PlayerId(0), occupied_room_id);
+        let err = game
+            .check_normal_turn(&into_occupied)
+            .expect_err("the stranger should block the room");
+        assert_eq!(err.code(), "roomOccupied");
+        let occupant = game.player_text_for(stranger_id);
+        assert!(
+            err.to_string()
+                .contains(&format!("because {occupant} is there")),
+            "{err}"
+        );
+        assert!(game.is_room_blocked_for(PlayerId(0), occupied_room_id));
+        assert!(!game.is_room_blocked_for(PlayerId(0), empty_room_id));
+        assert!(!game.possible_turns().contains(&into_occupied));
+
+        // staying put beside the other players is fine, and so is a room vacated this turn
+        let stay = SimpleTurn::single(PlayerId(0), start_room_id);
+        assert!(game.check_normal_turn(&stay).is_ok());
+        let swap = SimpleTurn::new([
+            PieceMove::new(stranger_id, empty_room_id),
+            PieceMove::new(PlayerId(0), occupied_room_id),
+        ]);
+        assert!(game.check_normal_turn(&swap).is_ok());
+        let together = SimpleTurn::new([
+            PieceMove::new(stranger_id, empty_room_id),
+            PieceMove::new(PlayerId(0), empty_room_id),
+        ]);
+        assert_eq!(
+            game.check_normal_turn(&together)
+                .expect_err("two moved pieces can't share a room")
+                .code(),
+            "roomOccupied"
+        );
+        assert!(
+            game.possible_turns()
+                .iter()
+                .all(|turn| game.check_normal_turn(turn).is_ok())
+        );
+    }
+
+    #[test]
+    fn max_move_distance_counts_the_free_room_and_caps_at_the_board() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let mut game =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2));
+        let stranger_id = rule_helper::SIDE_A_STRANGER_PLAYER_ID;
+        game.player_move_cards[0] = CardCount::whole(1);
+        assert_eq!(game.max_move_distance(PlayerId(0)), 2);
+        assert_eq!(game.max_move_distance(stranger_id), 2);
+        assert_eq!(game.max_move_distance(PlayerId(2)), 0);
+
+        let mut strict = game.copy_state();
+        strict.common = strict.common.clone().with_rule_config(RuleConfig {
+            strict_stranger_moves: true,
+            ..RuleConfig::default()
+        });
+        assert_eq!(strict.max_move_distance(PlayerId(0)), 2);
+        assert_eq!(strict.max_move_distance(stranger_id), 1);
+
+        game.player_move_cards[0] = CardCount::whole(100);
+        let room_id = game.player_room_ids[0];
+        let farthest = game
+            .common
+            .board
+            .room_ids
+            .iter()
+            .map(|dest_room_id| game.common.board.distance[(room_id, *dest_room_id)])
+            .max()
+            .unwrap_or(0);
+        assert_eq!(game.max_move_distance(PlayerId(0)), farthest);
+    }
+
+    #[test]
+    fn stranger_loop_threats_mirror_board_info_from_each_side() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let mut game = MutableGameState::at_start(common);
+        let (enemy_room_id, ally_room_ids) = game
+            .common
+            .board
+            .stranger_loop_room_ids
+            .iter()
+            .map(|(enemy_room_id, ally_room_ids)| (*enemy_room_id, ally_room_ids.clone()))
+            .min_by_key(|(enemy_room_id, _)| enemy_room_id.0)
+            .expect("AltDown should have stranger loop rooms");
+        let ally_room_id = *ally_room_ids
+            .iter()
+            .next()
+            .expect("loop should be counterable");
+        game.move_piece(rule_helper::SIDE_B_STRANGER_PLAYER_ID, enemy_room_id);
+        game.move_piece(rule_helper::SIDE_A_STRANGER_PLAYER_ID, ally_room_id);
+
+        let threats = game.stranger_loop_threats(rule_helper::SIDE_A_NORMAL_PLAYER_ID);
+        assert_eq!(
+            threats.len(),
+            game.common.board.stranger_loop_room_ids.len()
+        );
+        assert_eq!(threats[0].enemy_room_id, enemy_room_id);
+        assert!(threats[0].opposing_stranger_is_here);
+        assert!(threats[0].is_countered);
+
+        assert_eq!(
+            game.stranger_loop_threats(rule_helper::SIDE_A_STRANGER_PLAYER_ID),
+            threats
+        );
+    }
+
+    #[test]
+    fn stranger_loop_warning_needs_an_uncountered_loop_within_a_move() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let mut game = MutableGameState::at_start(common);
+        let (enemy_room_id, ally_room_ids) = game
+            .common
+            .board
+            .stranger_loop_room_ids
+            .iter()
+            .map(|(enemy_room_id, ally_room_ids)| (*enemy_room_id, ally_room_ids.clone()))
+            .min_by_key(|(enemy_room_id, _)| enemy_room_id.0)
+            .expect("AltDown should have stranger loop rooms");
+        let far_room_id = game
+            .common
+            .board
+            .room_ids
+            .iter()
+            .copied()
+            .filter(|room_id| !ally_room_ids.contains(room_id))
+            .find(|room_id| game.common.board.distance[(*room_id, enemy_room_id)] > 1)
+            .expect("some room should be two moves from the loop room");
+        let near_room_id = game
+            .common
+            .board
+            .room_ids
+            .iter()
+            .copied()
+            .find(|room_id| game.common.board.distance[(*room_id, enemy_room_id)] == 1)
+            .expect("loop room should have a neighbor");
+        game.move_piece(rule_helper::SIDE_A_STRANGER_PLAYER_ID, far_room_id);
+        game.move_piece(rule_helper::SIDE_B_STRANGER_PLAYER_ID, far_room_id);
+        let analysis_player_id = rule_helper::SIDE_A_NORMAL_PLAYER_ID;
+        assert!(
+            !game
+                .stranger_loop_warning_room_ids(analysis_player_id)
+                .contains(&enemy_room_id)
+        );
+
+        game.move_piece(rule_helper::SIDE_B_STRANGER_PLAYER_ID, near_room_id);
+        assert!(
+            game.stranger_loop_warning_room_ids(analysis_player_id)
+                .contains(&enemy_room_id)
+        );
+        game.move_piece(rule_helper::SIDE_B_STRANGER_PLAYER_ID, enemy_room_id);
+        assert!(
+            game.stranger_loop_warning_room_ids(analysis_player_id)
+                .contains(&enemy_room_id)
+        );
+
+        game.move_piece(
+            rule_helper::SIDE_A_STRANGER_PLAYER_ID,
+            *ally_room_ids
+                .iter()
+                .next()
+                .expect("loop should be counterable"),
+        );
+        assert!(
+            !game
+                .stranger_loop_warning_room_ids(analysis_player_id)
+                .contains(&enemy_room_id)
+        );
+    }
+
+    #[test]
+    fn pruned_possible_turns_drop_only_pointless_opposing_stranger_moves() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let game = MutableGameState::at_start(common);
+        let all_turns = game.possible_turns();
+        let pruned_turns = game.pruned_possible_turns();
+        let opposing_strangers = game
+            .common
+            .seat_map()
+            .opposing_strangers(game.current_player_id);
+
+        assert!(pruned_turns.len() < all_turns.len());
+        assert_eq!(pruned_turns[0], all_turns[0]);
+        for turn in all_turns.iter().filter(|turn| !pruned_turns.contains(turn)) {
+            assert!(
+                turn.moves
+                    .iter()
+                    .any(|mv| opposing_strangers.contains(&mv.player_id))
+            );
+        }
+    }
+
     #[test]
     fn best_action_detects_being_seen() {
         let mut game = sample_game_state();
-        game.player_room_ids[1] = RoomId(2);
+        game.move_piece(PlayerId(1), RoomId(2));
         game.current_player_id = PlayerId(1);
         let action = game.best_action_allowed(false);
         assert_eq!(action, PlayerAction::None);
@@ -1344,9 +3186,18 @@ mod tests {
     fn doctor_moves_until_room_wraps_in_visit_order() {
         let mut game = sample_game_state();
         game.doctor_room_id = RoomId(2);
-        assert_eq!(game.doctor_moves_until_room(RoomId(2)), 0);
-        assert_eq!(game.doctor_moves_until_room(RoomId(3)), 1);
-        assert_eq!(game.doctor_moves_until_room(RoomId(1)), 2);
+        assert_eq!(game.doctor_moves_until_room(RoomId(2)), Some(0));
+        assert_eq!(game.doctor_moves_until_room(RoomId(3)), Some(1));
+        assert_eq!(game.doctor_moves_until_room(RoomId(1)), Some(2));
+    }
+
+    #[test]
+    fn doctor_moves_until_room_has_no_answer_for_rooms_missing_from_board() {
+        let mut game = sample_game_state();
+        game.doctor_room_id = RoomId(2);
+        assert_eq!(game.doctor_moves_until_room(RoomId(99)), None);
+        assert_eq!(game.doctor_moves_until_room(RoomId(0)), None);
+        assert_eq!(game.doctor_moves_until_player_room(PlayerId(99)), None);
     }
 
     #[test]
@@ -1372,11 +3223,82 @@ mod tests {
                 "2@3;\n",
                 "1@2 4@2;\n",
                 "1@2 2@2;\n",
-                "4@2 2@2;"
+                "2@2 4@2;"
             )
         );
     }
 
+    #[test]
+    fn possible_turns_move_every_stranger_with_two_per_side() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 2)
+            .with_stranger_counts(StrangerCounts::new(2, 2).unwrap())
+            .unwrap();
+        let game = MutableGameState::at_start(common);
+        let turns = game.possible_turns();
+
+        let moved_player_ids = |turn: &SimpleTurn| {
+            turn.moves
+                .iter()
+                .filter(|mv| game.player_room_ids[mv.player_id.0] != mv.dest_room_id)
+                .map(|mv| mv.player_id.0)
+                .collect::<Vec<_>>()
+        };
+        for stranger_idx in [1, 2, 4, 5] {
+            assert!(
+                turns
+                    .iter()
+                    .any(|turn| moved_player_ids(turn) == vec![stranger_idx])
+            );
+        }
+        // allied strangers are offered before opposing ones
+        let first_stranger_turn = turns
+            .iter()
+            .map(&moved_player_ids)
+            .find(|moved| !moved.is_empty() && !moved.contains(&0))
+            .expect("strangers should be movable");
+        assert_eq!(first_stranger_turn, vec![4]);
+        let stranger_pairs = turns
+            .iter()
+            .map(&moved_player_ids)
+            .filter(|moved| moved.len() == 2 && !moved.contains(&0))
+            .collect::<HashSet<_>>();
+        assert_eq!(stranger_pairs.len(), 6);
+    }
+
+    #[test]
+    fn team_attacks_count_for_the_side_and_teammates_win_together() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 4)
+            .with_teams(Teams::ALTERNATING)
+            .unwrap()
+            .with_rule_config(RuleConfig {
+                successful_attacks_to_win: 2,
+                ..RuleConfig::default()
+            });
+        let mut game = MutableGameState::at_start(common);
+
+        // teammates don't spend clovers against each other
+        assert_eq!(
+            game.num_defensive_clovers(),
+            game.player_clovers(PlayerId(1)) + game.player_clovers(PlayerId(3))
+        );
+
+        game.record_successful_attack(PlayerId(0));
+        assert!(!game.has_winner());
+        game.record_successful_attack(PlayerId(2));
+        assert_eq!(game.winner(), PlayerId(0));
+        assert_eq!(game.current_player_id, PlayerId(2));
+        assert_eq!(
+            game.heuristic_score(PlayerId(2)),
+            rule_helper::HEURISTIC_SCORE_WIN
+        );
+        assert_eq!(
+            game.heuristic_score(PlayerId(3)),
+            rule_helper::HEURISTIC_SCORE_LOSS
+        );
+    }
+
     #[test]
     fn possible_turns_include_only_one_no_move_turn() {
         let game = tiny_two_player_game_state();
@@ -1386,7 +3308,7 @@ mod tests {
             .filter(|turn| {
                 turn.moves
                     .iter()
-                    .all(|mv| game.player_room_ids[mv.player_id.0 as usize] == mv.dest_room_id)
+                    .all(|mv| game.player_room_ids[mv.player_id.0] == mv.dest_room_id)
             })
             .map(|turn| turn.to_string())
             .collect::<Vec<_>>();
@@ -1474,8 +3396,8 @@ mod tests {
             snapshot,
             concat!(
                 "count=10\n",
-                "head=3@1;|3@2;|3@3;|2@2;|2@3;|4@2;|4@3;|3@2 2@3;\n",
-                "tail=3@3;|2@2;|2@3;|4@2;|4@3;|3@2 2@3;|3@2 4@2;|2@3 4@2;"
+                "head=3@1;|3@2;|3@3;|2@2;|2@3;|4@2;|4@3;|2@3 3@2;\n",
+                "tail=3@3;|2@2;|2@3;|4@2;|4@3;|2@3 3@2;|3@2 4@2;|2@3 4@2;"
             )
         );
     }
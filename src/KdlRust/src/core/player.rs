@@ -2,7 +2,7 @@ use crate::core::{mutable_game_state::MutableGameState, room::RoomId, simple_tur
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(transparent)]
 pub struct PlayerId(pub usize);
 
@@ -29,7 +29,7 @@ pub enum PlayerType {
     Stranger,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 #[readonly::make]
 pub struct PieceMove {
@@ -61,6 +61,7 @@ pub fn player_moves_to_nice_string(moves: impl IntoIterator<Item = PieceMove>) -
     format!("{joined};")
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct AppraisedPlayerTurn {
     pub appraisal: f64,
     pub turn: SimpleTurn,
@@ -101,7 +102,9 @@ impl fmt::Display for AppraisedPlayerTurn {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::{board::Board, common_game_state::CommonGameState, room::Room};
+    use crate::core::{
+        board::Board, card_count::CardCount, common_game_state::CommonGameState, room::Room,
+    };
 
     #[test]
     fn player_move_display_matches_csharp() {
@@ -138,7 +141,7 @@ mod tests {
     #[test]
     fn appraised_player_turn_from_state_uses_state_data() {
         let mut state = sample_state();
-        state.player_move_cards[0] = 1.5;
+        state.player_move_cards[0] = CardCount::from_f64(1.5);
         state.prev_turn = SimpleTurn::from_move(PieceMove::new(PlayerId(1), RoomId(2)));
         let expected_appraisal = state.heuristic_score(PlayerId(2));
 
@@ -0,0 +1,361 @@
+use crate::core::{
+    asset_registry::{self, PUZZLE_ASSETS},
+    board::Board,
+    card_count::CardCount,
+    common_game_state::CommonGameState,
+    endgame_solver::{EndgameSolver, SolvedOutcome},
+    mutable_game_state::MutableGameState,
+    player::PlayerId,
+    room::RoomId,
+    rule_config::RuleConfig,
+    rule_helper,
+    seat_map::PieceId,
+    simple_turn::SimpleTurn,
+};
+use crate::util::cancellation::NeverCancelToken;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, path::Path};
+
+// the solver proves a goal by searching every reply, so goals stay short
+pub const MAX_GOAL_TURNS: i32 = 2;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum PuzzleGoal {
+    // counts the side to move's own turns, the first one included
+    WinWithin { turns: i32 },
+}
+
+impl PuzzleGoal {
+    // normal turns of both sides, ending with the solver's last one
+    pub fn num_plies(self) -> i32 {
+        match self {
+            PuzzleGoal::WinWithin { turns } => 2 * turns - 1,
+        }
+    }
+}
+
+// pieces left out of a puzzle keep their starting room, cards and strength
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PuzzlePiece {
+    pub piece_id: PieceId,
+    pub room_id: usize,
+    #[serde(default)]
+    pub move_cards: CardCount,
+    #[serde(default)]
+    pub weapons: CardCount,
+    #[serde(default)]
+    pub failures: CardCount,
+    #[serde(default = "default_strength")]
+    pub strength: i32,
+}
+
+fn default_strength() -> i32 {
+    rule_helper::PLAYER_STARTING_STRENGTH
+}
+
+fn default_turn_id() -> i32 {
+    1
+}
+
+// a two-player position with strangers where the side to move has to reach the goal against any
+// defense; the cards of a stranger piece are ignored, as they are in play
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Puzzle {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub board_name: String,
+    #[serde(default)]
+    pub rule_config: RuleConfig,
+    #[serde(default = "default_turn_id")]
+    pub turn_id: i32,
+    pub side_to_move: PieceId,
+    pub doctor_room_id: usize,
+    pub pieces: Vec<PuzzlePiece>,
+    pub goal: PuzzleGoal,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PuzzleVerdict {
+    Solved,
+    OnTrack,                             // no mistake so far, and the goal is still ahead
+    Failed { ply: i32, reason: String }, // ply of the turn that lost the forced win, from 1
+}
+
+impl Puzzle {
+    pub fn from_json_str(json: &str) -> Result<Self, String> {
+        let puzzle = serde_json::from_str::<Self>(json)
+            .map_err(|err| format!("Invalid puzzle JSON: {err}"))?;
+        puzzle.start_state(false)?;
+        Ok(puzzle)
+    }
+
+    pub fn from_json_file(path: &Path) -> Result<Self, String> {
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("Could not read puzzle {}: {err}", path.display()))?;
+        Self::from_json_str(&json)
+    }
+
+    pub fn embedded() -> Vec<Self> {
+        PUZZLE_ASSETS
+            .iter()
+            .map(|asset| Self::from_json_str(asset.json).expect("embedded puzzles should load"))
+            .collect::<Vec<_>>()
+    }
+
+    pub fn find_embedded(name: &str) -> Option<Self> {
+        let asset = asset_registry::find_embedded(PUZZLE_ASSETS, name)?;
+        Some(Self::from_json_str(asset.json).expect("embedded puzzles should load"))
+    }
+
+    pub fn start_state(&self, is_log_enabled: bool) -> Result<MutableGameState, String> {
+        let PuzzleGoal::WinWithin { turns } = self.goal;
+        if !(1..=MAX_GOAL_TURNS).contains(&turns) {
+            return Err(format!(
+                "Puzzle '{}' goal must be 1 to {MAX_GOAL_TURNS} turns, not {turns}.",
+                self.name
+            ));
+        }
+        if !matches!(self.side_to_move, PieceId::Player1 | PieceId::Player2) {
+            return Err(format!(
+                "Puzzle '{}' side to move must be player1 or player2.",
+                self.name
+            ));
+        }
+
+        let board = Board::from_embedded_json(&self.board_name).map_err(|err| err.to_string())?;
+        let common = CommonGameState::from_num_normal_players(
+            is_log_enabled,
+            board,
+            rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+        )
+        .with_rule_config(self.rule_config.clone());
+        let seats = common.seat_map();
+        let mut state = MutableGameState::at_start(common);
+        state.turn_id = self.turn_id;
+        state.doctor_room_id = self.room_id(&state, self.doctor_room_id)?;
+
+        let mut seen_piece_ids = HashSet::new();
+        for piece in &self.pieces {
+            let Some(player_id) = seats.player_id(piece.piece_id) else {
+                return Err(format!(
+                    "Puzzle '{}' has no piece {}.",
+                    self.name,
+                    piece.piece_id.as_str()
+                ));
+            };
+            if !seen_piece_ids.insert(piece.piece_id) {
+                return Err(format!(
+                    "Puzzle '{}' lists {} more than once.",
+                    self.name,
+                    piece.piece_id.as_str()
+                ));
+            }
+            let room_id = self.room_id(&state, piece.room_id)?;
+            state.move_piece(player_id, room_id);
+            state.player_move_cards[player_id.0] = piece.move_cards;
+            state.player_weapons[player_id.0] = piece.weapons;
+            state.player_failures[player_id.0] = piece.failures;
+            state.player_strengths[player_id.0] = piece.strength;
+        }
+        state.current_player_id = seats
+            .player_id(self.side_to_move)
+            .unwrap_or(rule_helper::SIDE_A_NORMAL_PLAYER_ID);
+        Ok(state)
+    }
+
+    fn room_id(&self, state: &MutableGameState, room_id: usize) -> Result<RoomId, String> {
+        if state.common.board.room_ids.contains(&RoomId(room_id)) {
+            Ok(RoomId(room_id))
+        } else {
+            Err(format!(
+                "Puzzle '{}' uses room {room_id}, which {} doesn't have.",
+                self.name, self.board_name
+            ))
+        }
+    }
+
+    // replays a line of normal turns, both sides', from the puzzle's start; every turn the solver
+    // plays has to keep the win forced within the plies that are left
+    pub fn check_line(
+        &self,
+        turns: impl IntoIterator<Item = SimpleTurn>,
+    ) -> Result<PuzzleVerdict, String> {
+        let mut state = self.start_state(false)?;
+        let solver_id = state.current_player_id;
+        let num_plies = self.goal.num_plies();
+        let mut ply = 0;
+
+        for turn in turns {
+            ply += 1;
+            if ply > num_plies {
+                return Ok(self.out_of_turns(ply));
+            }
+            state
+                .check_normal_turn(&turn)
+                .map_err(|err| format!("Turn {ply} ({turn}) is not legal: {err}"))?;
+            let is_solver_turn = state.common.to_normal_player_id(state.current_player_id)
+                == state.common.to_normal_player_id(solver_id);
+            state = state.after_turn(turn);
+
+            if let Some(winner) = state.result.winner() {
+                return Ok(
+                    if state.common.to_normal_player_id(winner)
+                        == state.common.to_normal_player_id(solver_id)
+                    {
+                        PuzzleVerdict::Solved
+                    } else {
+                        PuzzleVerdict::Failed {
+                            ply,
+                            reason: "The other side won.".to_string(),
+                        }
+                    },
+                );
+            }
+            if state.is_game_over() {
+                return Ok(PuzzleVerdict::Failed {
+                    ply,
+                    reason: "The game ended without a win.".to_string(),
+                });
+            }
+            if is_solver_turn && !Self::is_forced_win(&state, solver_id, num_plies - ply) {
+                return Ok(PuzzleVerdict::Failed {
+                    ply,
+                    reason: "That turn lets the other side escape.".to_string(),
+                });
+            }
+        }
+
+        Ok(if ply >= num_plies {
+            self.out_of_turns(ply)
+        } else {
+            PuzzleVerdict::OnTrack
+        })
+    }
+
+    fn out_of_turns(&self, ply: i32) -> PuzzleVerdict {
+        let PuzzleGoal::WinWithin { turns } = self.goal;
+        PuzzleVerdict::Failed {
+            ply,
+            reason: format!("The goal was to win within {turns} turns."),
+        }
+    }
+
+    // a turn for the solver that keeps the win forced, from a position reached by check_line
+    pub fn hint(&self, state: &MutableGameState, plies_played: i32) -> Option<SimpleTurn> {
+        let solver_id = state.current_player_id;
+        let plies_left = self.goal.num_plies() - plies_played - 1;
+        let seats = state.common.seat_map();
+        if seats.piece_id(state.common.to_normal_player_id(solver_id)) != Some(self.side_to_move)
+            || plies_left < 0
+            || state.is_game_over()
+        {
+            return None;
+        }
+        state.possible_turns().into_iter().find(|turn| {
+            let child_state = state.after_turn_without_memory(turn.clone());
+            Self::is_forced_win(&child_state, solver_id, plies_left)
+        })
+    }
+
+    fn is_forced_win(state: &MutableGameState, solver_id: PlayerId, plies_left: i32) -> bool {
+        let mover_is_solver = state.common.to_normal_player_id(state.current_player_id)
+            == state.common.to_normal_player_id(solver_id);
+        if let Some(winner) = state.result.winner() {
+            return state.common.to_normal_player_id(winner)
+                == state.common.to_normal_player_id(solver_id);
+        }
+        let outcome =
+            EndgameSolver::new(f64::INFINITY, plies_left).solve(state, &NeverCancelToken, &mut 0);
+        outcome
+            == if mover_is_solver {
+                SolvedOutcome::Win
+            } else {
+                SolvedOutcome::Loss
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solved_line(puzzle: &Puzzle) -> Vec<SimpleTurn> {
+        let mut state = puzzle.start_state(false).expect("puzzle should start");
+        let solver_id = state.current_player_id;
+        let mut line = Vec::new();
+        while !state.is_game_over() {
+            let turn = if state.current_player_id == solver_id {
+                puzzle
+                    .hint(&state, line.len() as i32)
+                    .unwrap_or_else(|| panic!("{}: no hint after {line:?}", puzzle.name))
+            } else {
+                state.possible_turns()[0].clone()
+            };
+            state = state.after_turn(turn.clone());
+            line.push(turn);
+        }
+        line
+    }
+
+    #[test]
+    fn embedded_puzzles_are_forced_wins_that_need_every_turn() {
+        let puzzles = Puzzle::embedded();
+        assert_eq!(puzzles.len(), PUZZLE_ASSETS.len());
+        for puzzle in &puzzles {
+            let state = puzzle.start_state(false).expect("puzzle should start");
+            let solver_id = state.current_player_id;
+            let num_plies = puzzle.goal.num_plies();
+            assert!(
+                Puzzle::is_forced_win(&state, solver_id, num_plies),
+                "{}",
+                puzzle.name
+            );
+            assert!(
+                !Puzzle::is_forced_win(&state, solver_id, num_plies - 2),
+                "{} is solvable in fewer turns",
+                puzzle.name
+            );
+
+            let line = solved_line(puzzle);
+            assert_eq!(puzzle.check_line(line.clone()), Ok(PuzzleVerdict::Solved));
+            assert_eq!(
+                puzzle.check_line(line[..line.len() - 1].iter().cloned()),
+                Ok(PuzzleVerdict::OnTrack),
+                "{}",
+                puzzle.name
+            );
+        }
+        assert!(Puzzle::find_embedded("FIRST BLOOD").is_some());
+    }
+
+    #[test]
+    fn check_line_rejects_mistakes_and_bad_puzzles() {
+        let puzzle = Puzzle::find_embedded("first blood").expect("puzzle should exist");
+        let state = puzzle.start_state(false).expect("puzzle should start");
+        let wrong_turn = state
+            .possible_turns()
+            .into_iter()
+            .find(|turn| puzzle.check_line([turn.clone()]) != Ok(PuzzleVerdict::Solved))
+            .expect("some turn should miss the win");
+        assert!(matches!(
+            puzzle.check_line([wrong_turn]),
+            Ok(PuzzleVerdict::Failed { ply: 1, .. })
+        ));
+        assert_eq!(puzzle.check_line([]), Ok(PuzzleVerdict::OnTrack));
+
+        let illegal_turn = "1@9".parse::<SimpleTurn>().expect("turn text should parse");
+        assert!(puzzle.check_line([illegal_turn]).is_err());
+
+        let mut bad_puzzle = puzzle.clone();
+        bad_puzzle.pieces[0].room_id = 99;
+        assert!(bad_puzzle.start_state(false).is_err());
+        bad_puzzle = puzzle.clone();
+        bad_puzzle.goal = PuzzleGoal::WinWithin { turns: 9 };
+        assert!(bad_puzzle.start_state(false).is_err());
+        assert!(Puzzle::from_json_str("{}").is_err());
+    }
+}
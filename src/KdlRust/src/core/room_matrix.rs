@@ -0,0 +1,100 @@
+use crate::core::room::RoomId;
+use crate::util::matrix::Matrix;
+use serde::{Serialize, Serializer};
+use std::ops::{Index, IndexMut};
+
+// square table indexed by (from room, to room); room ids are used directly, so row and column
+// zero go unused on boards that number rooms from one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoomMatrix<T> {
+    cells: Matrix<T>,
+}
+
+impl<T: Clone> RoomMatrix<T> {
+    pub fn new(dim: usize, value: T) -> Self {
+        Self {
+            cells: Matrix::new(dim, dim, value),
+        }
+    }
+}
+
+impl<T> RoomMatrix<T> {
+    pub fn dim(&self) -> usize {
+        self.cells.num_rows()
+    }
+
+    pub fn room_ids(&self) -> impl Iterator<Item = RoomId> + use<T> {
+        (0..self.dim()).map(RoomId)
+    }
+
+    pub fn row(&self, from: RoomId) -> &[T] {
+        self.cells.row(from.0)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.rows()
+    }
+}
+
+// nested arrays, so js can index matrix[fromRoomId][toRoomId] like the engine does
+impl<T: Serialize> Serialize for RoomMatrix<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.rows())
+    }
+}
+
+impl<T> Index<(RoomId, RoomId)> for RoomMatrix<T> {
+    type Output = T;
+
+    fn index(&self, (from, to): (RoomId, RoomId)) -> &T {
+        assert!(to.0 < self.dim(), "room id {to} out of range");
+        &self.cells[(from.0, to.0)]
+    }
+}
+
+impl<T> IndexMut<(RoomId, RoomId)> for RoomMatrix<T> {
+    fn index_mut(&mut self, (from, to): (RoomId, RoomId)) -> &mut T {
+        assert!(to.0 < self.dim(), "room id {to} out of range");
+        &mut self.cells[(from.0, to.0)]
+    }
+}
+
+impl<T> Index<RoomId> for RoomMatrix<T> {
+    type Output = [T];
+
+    fn index(&self, from: RoomId) -> &[T] {
+        self.row(from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_by_room_pair_and_row() {
+        let mut matrix = RoomMatrix::new(4, 0);
+        matrix[(RoomId(1), RoomId(3))] = 7;
+        matrix[(RoomId(3), RoomId(1))] = 2;
+
+        assert_eq!(matrix[(RoomId(1), RoomId(3))], 7);
+        assert_eq!(matrix[RoomId(3)], [0, 2, 0, 0]);
+        assert_eq!(matrix.room_ids().collect::<Vec<_>>().len(), 4);
+    }
+
+    #[test]
+    fn serializes_as_nested_rows() {
+        let mut matrix = RoomMatrix::new(2, 0);
+        matrix[(RoomId(1), RoomId(0))] = 3;
+
+        let json = serde_json::to_string(&matrix).expect("matrix should serialize");
+        assert_eq!(json, "[[0,0],[3,0]]");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn column_past_dim_panics_instead_of_wrapping_rows() {
+        let matrix = RoomMatrix::new(3, false);
+        let _ = matrix[(RoomId(0), RoomId(3))];
+    }
+}
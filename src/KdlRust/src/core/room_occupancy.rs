@@ -0,0 +1,141 @@
+use crate::core::{mutable_game_state::MutableGameState, player::PlayerId, room::RoomId};
+
+// how many plies each piece started in each room over a game, for post-game heatmaps; a ply is
+// counted at the position its normal turn was taken from, so the totals add up to the game's ply
+pub struct RoomOccupancy {
+    pub room_ids: Vec<RoomId>, // the board's, sorted
+    pub num_plies: usize,
+    pub doctor_plies: Vec<usize>,      // aligned with room_ids
+    pub player_plies: Vec<Vec<usize>>, // by player id, each aligned with room_ids
+}
+
+impl RoomOccupancy {
+    pub fn from_state(state: &MutableGameState) -> Self {
+        let room_ids = state.common.board.room_ids.clone();
+        let mut occupancy = Self {
+            num_plies: 0,
+            doctor_plies: vec![0; room_ids.len()],
+            player_plies: vec![vec![0; room_ids.len()]; state.player_room_ids().len()],
+            room_ids,
+        };
+
+        let rehydrated = state
+            .compacted_history
+            .is_some()
+            .then(|| state.rehydrated());
+        let mut prev_state = rehydrated.as_ref().unwrap_or(state).prev_state.as_deref();
+        while let Some(prev) = prev_state {
+            if prev.is_normal_turn() {
+                occupancy.num_plies += 1;
+                if let Some(room_idx) = occupancy.room_idx(prev.doctor_room_id) {
+                    occupancy.doctor_plies[room_idx] += 1;
+                }
+                for (player_idx, room_id) in prev.player_room_ids().iter().enumerate() {
+                    if let Some(room_idx) = occupancy.room_idx(*room_id)
+                        && let Some(plies) = occupancy.player_plies.get_mut(player_idx)
+                    {
+                        plies[room_idx] += 1;
+                    }
+                }
+            }
+            prev_state = prev.prev_state.as_deref();
+        }
+        occupancy
+    }
+
+    pub fn room_idx(&self, room_id: RoomId) -> Option<usize> {
+        self.room_ids
+            .binary_search_by_key(&room_id.0, |id| id.0)
+            .ok()
+    }
+
+    pub fn plies_for(&self, player_id: PlayerId, room_id: RoomId) -> usize {
+        self.room_idx(room_id)
+            .and_then(|room_idx| Some(self.player_plies.get(player_id.0)?[room_idx]))
+            .unwrap_or(0)
+    }
+
+    // one row per room any piece started a ply in; the doctor, then players in display order
+    pub fn table_text(&self, state: &MutableGameState) -> String {
+        let player_ids = state.common.seat_map().display_order_player_ids();
+        let board = &state.common.board;
+        let labels = self
+            .room_ids
+            .iter()
+            .map(|room_id| board.room_label(*room_id))
+            .collect::<Vec<_>>();
+        let label_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("Room".len());
+
+        let mut lines = vec![format!(
+            "{:label_width$} {:>4}{}",
+            "Room",
+            "Dr",
+            player_ids
+                .iter()
+                .map(|player_id| format!(" {:>4}", state.player_text_for(*player_id)))
+                .collect::<String>()
+        )];
+        for (room_idx, label) in labels.iter().enumerate() {
+            let player_counts = player_ids
+                .iter()
+                .map(|player_id| self.player_plies[player_id.0][room_idx])
+                .collect::<Vec<_>>();
+            let doctor_count = self.doctor_plies[room_idx];
+            if doctor_count == 0 && player_counts.iter().all(|count| *count == 0) {
+                continue;
+            }
+            lines.push(format!(
+                "{label:label_width$} {doctor_count:>4}{}",
+                player_counts
+                    .iter()
+                    .map(|count| format!(" {count:>4}"))
+                    .collect::<String>()
+            ));
+        }
+        lines.push(format!("({} plies)", self.num_plies));
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+
+    #[test]
+    fn counts_the_room_each_piece_started_every_ply_in() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny should be available");
+        let mut state =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(false, board, 2));
+        let start_room_id = state.common.board.player_start_room_id;
+        for _ in 0..4 {
+            let turn = state.possible_turns()[0].clone();
+            state = state.after_turn(turn);
+        }
+
+        let occupancy = RoomOccupancy::from_state(&state);
+        assert_eq!(occupancy.num_plies, 4);
+        assert_eq!(occupancy.doctor_plies.iter().sum::<usize>(), 4);
+        for plies in &occupancy.player_plies {
+            assert_eq!(plies.iter().sum::<usize>(), 4);
+        }
+        assert!(occupancy.plies_for(PlayerId(0), start_room_id) >= 1);
+
+        let mut compacted = state.copy_state();
+        compacted.compact_history(2);
+        assert!(compacted.compacted_history.is_some());
+        assert_eq!(
+            RoomOccupancy::from_state(&compacted).player_plies,
+            occupancy.player_plies
+        );
+
+        let table = occupancy.table_text(&state);
+        assert!(table.starts_with("Room"));
+        assert!(table.ends_with("(4 plies)"));
+    }
+}
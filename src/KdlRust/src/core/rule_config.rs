@@ -0,0 +1,126 @@
+use crate::core::{
+    card_count::CardCount,
+    deck_spec::{DeckSpec, StartingCards},
+    rule_helper,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RuleConfig {
+    pub strangers_are_nosy: bool,
+    pub player_starting_move_cards: CardCount,
+    pub player_starting_weapons: CardCount,
+    pub player_starting_failures: CardCount,
+    // successful attacks a side needs to win; each one credits the attacker's normal player
+    pub successful_attacks_to_win: i32,
+    // once this many turns have been played without a winner the doctor escapes and the game is a
+    // draw; zero means the doctor never escapes
+    pub doctor_escape_turns: i32,
+    // a position reached this many times, counting the mover and every card, is a draw; zero, the
+    // default, turns repetition draws off
+    pub repetitions_for_draw: i32,
+    // a normal player the doctor activates has to move out of the doctor's room, so they can't
+    // attack on that turn; part of the classic rules
+    pub activated_player_must_move: bool,
+    // the board game's stranger rules: the free room only moves the mover's own piece, so every
+    // room a stranger moves costs a move card, and no piece moves twice in one turn
+    pub strict_stranger_moves: bool,
+    // a moved piece can't end the turn in a room with another player's piece; the doctor's piece
+    // doesn't block, and pieces that stay put don't have to leave
+    pub occupied_rooms_blocked: bool,
+    // creaky floorboards: a loot comes to nothing one time in this many, picked by the looter's
+    // position so replays agree; zero turns it off
+    pub creaky_floorboards_one_in: i32,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            strangers_are_nosy: rule_helper::simple::STRANGERS_ARE_NOSY,
+            player_starting_move_cards: rule_helper::simple::PLAYER_STARTING_MOVE_CARDS,
+            player_starting_weapons: rule_helper::simple::PLAYER_STARTING_WEAPONS,
+            player_starting_failures: rule_helper::simple::PLAYER_STARTING_FAILURES,
+            successful_attacks_to_win: 1,
+            doctor_escape_turns: 0,
+            repetitions_for_draw: 0,
+            activated_player_must_move: false,
+            strict_stranger_moves: false,
+            occupied_rooms_blocked: false,
+            creaky_floorboards_one_in: 0,
+        }
+    }
+}
+
+impl RuleConfig {
+    // the board game's restrictions on doctor-activated players and strangers, with starting cards
+    // from its standard deck
+    pub fn classic() -> Self {
+        Self {
+            activated_player_must_move: true,
+            strict_stranger_moves: true,
+            ..Self::default()
+        }
+        .with_deck(&DeckSpec::standard())
+    }
+
+    pub fn with_deck(self, deck: &DeckSpec) -> Self {
+        self.with_starting_cards(deck.starting_cards())
+    }
+
+    pub fn with_starting_cards(self, starting_cards: StartingCards) -> Self {
+        Self {
+            player_starting_move_cards: starting_cards.move_cards,
+            player_starting_weapons: starting_cards.weapons,
+            player_starting_failures: starting_cards.failures,
+            ..self
+        }
+    }
+
+    pub fn starting_cards(&self) -> StartingCards {
+        StartingCards {
+            move_cards: self.player_starting_move_cards,
+            weapons: self.player_starting_weapons,
+            failures: self.player_starting_failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_fall_back_to_simple_rules() {
+        let rule_config = serde_json::from_str::<RuleConfig>(r#"{"strangersAreNosy":true}"#)
+            .expect("partial rule config should parse");
+
+        assert!(rule_config.strangers_are_nosy);
+        assert_eq!(
+            rule_config.player_starting_failures,
+            rule_helper::simple::PLAYER_STARTING_FAILURES
+        );
+        assert_eq!(rule_config.successful_attacks_to_win, 1);
+        assert_eq!(rule_config.doctor_escape_turns, 0);
+        assert_eq!(rule_config.repetitions_for_draw, 0);
+        assert!(!rule_config.activated_player_must_move);
+        assert!(!rule_config.strict_stranger_moves);
+        assert!(!rule_config.occupied_rooms_blocked);
+        assert_eq!(rule_config.creaky_floorboards_one_in, 0);
+    }
+
+    #[test]
+    fn classic_rules_start_with_the_standard_deck_hand() {
+        let classic = RuleConfig::classic();
+        assert_eq!(
+            classic.starting_cards(),
+            DeckSpec::standard().starting_cards()
+        );
+        assert!(classic.activated_player_must_move && classic.strict_stranger_moves);
+
+        let simple = RuleConfig::default();
+        let restored = classic.with_starting_cards(simple.starting_cards());
+        assert_eq!(restored.starting_cards(), simple.starting_cards());
+        assert_ne!(restored, simple);
+    }
+}
@@ -1,20 +1,23 @@
-use crate::core::player::PlayerId;
+use crate::core::{player::PlayerId, seat_map::StrangerCounts};
 
 pub mod simple {
-    pub const JUST_OVER_ONE_THIRD: f64 = 11.0 / 32.0;
+    use crate::core::card_count::CardCount;
 
-    pub const PLAYER_STARTING_MOVE_CARDS: f64 = 1.0;
-    pub const MOVE_CARDS_PER_LOOT: f64 = JUST_OVER_ONE_THIRD;
-    pub const CLOVERS_PER_MOVE_CARD: f64 = 1.0;
+    pub const JUST_OVER_ONE_THIRD: CardCount =
+        CardCount::from_units(11 * CardCount::UNITS_PER_CARD / 32);
 
-    pub const PLAYER_STARTING_WEAPONS: f64 = 2.0;
-    pub const WEAPONS_PER_LOOT: f64 = JUST_OVER_ONE_THIRD;
-    pub const STRENGTH_PER_WEAPON: f64 = 2.0; //53.0 / 24.0;
-    pub const CLOVERS_PER_WEAPON: f64 = 1.0;
+    pub const PLAYER_STARTING_MOVE_CARDS: CardCount = CardCount::whole(1);
+    pub const MOVE_CARDS_PER_LOOT: CardCount = JUST_OVER_ONE_THIRD;
+    pub const CLOVERS_PER_MOVE_CARD: i64 = 1;
 
-    pub const PLAYER_STARTING_FAILURES: f64 = 6.0;
-    pub const FAILURES_PER_LOOT: f64 = JUST_OVER_ONE_THIRD;
-    pub const CLOVERS_PER_FAILURE: f64 = 2.0; //50.0 / 24.0;
+    pub const PLAYER_STARTING_WEAPONS: CardCount = CardCount::whole(2);
+    pub const WEAPONS_PER_LOOT: CardCount = JUST_OVER_ONE_THIRD;
+    pub const STRENGTH_PER_WEAPON: i64 = 2; //53.0 / 24.0;
+    pub const CLOVERS_PER_WEAPON: i64 = 1;
+
+    pub const PLAYER_STARTING_FAILURES: CardCount = CardCount::whole(6);
+    pub const FAILURES_PER_LOOT: CardCount = JUST_OVER_ONE_THIRD;
+    pub const CLOVERS_PER_FAILURE: i64 = 2; //50.0 / 24.0;
 
     pub const CLOVERS_CONTRIBUTED_PER_STRANGER: f64 = 0.0; // used to be 1 or 0.5
 
@@ -25,7 +28,9 @@ pub const PLAYER_STARTING_STRENGTH: i32 = 1;
 pub const NORMAL_PLAYER_NUM_STARTING_CARDS: usize = 6;
 pub const NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS: usize = 2;
 pub const NUM_ALL_PLAYERS_WHEN_HAVE_STRANGERS: usize = 4;
+pub const NUM_NORMAL_PLAYERS_FOR_TEAMS: usize = 4;
 
+// seats for the standard one stranger per side; other stranger counts go through SeatMap
 pub const NORMAL_PLAYER_ID_FIRST: PlayerId = PlayerId(0);
 pub const STRANGER_PLAYER_ID_FIRST: PlayerId = PlayerId(1);
 pub const NORMAL_PLAYER_ID_SECOND: PlayerId = PlayerId(2);
@@ -40,98 +45,101 @@ pub const HEURISTIC_SCORE_WIN: f64 = f64::MAX;
 pub const HEURISTIC_SCORE_LOSS: f64 = f64::MIN;
 
 pub fn num_all_players(num_normal_players: usize) -> usize {
-    if num_normal_players == NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS {
-        NUM_ALL_PLAYERS_WHEN_HAVE_STRANGERS
-    } else {
-        num_normal_players
-    }
+    num_all_players_with_strangers(
+        num_normal_players,
+        StrangerCounts::default_for(num_normal_players),
+    )
 }
 
-pub fn to_normal_player_id(player_id: PlayerId, num_normal_players: usize) -> PlayerId {
-    if num_normal_players != NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS {
-        return player_id;
-    }
-
-    if player_id == SIDE_A_NORMAL_PLAYER_ID || player_id == SIDE_A_STRANGER_PLAYER_ID {
-        SIDE_A_NORMAL_PLAYER_ID
+pub fn num_all_players_with_strangers(
+    num_normal_players: usize,
+    stranger_counts: StrangerCounts,
+) -> usize {
+    if num_normal_players == NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS {
+        num_normal_players + stranger_counts.total()
     } else {
-        SIDE_B_NORMAL_PLAYER_ID
-    }
-}
-
-pub fn allied_stranger(player_id: PlayerId) -> PlayerId {
-    match player_id {
-        SIDE_A_NORMAL_PLAYER_ID | SIDE_A_STRANGER_PLAYER_ID => SIDE_A_STRANGER_PLAYER_ID,
-        SIDE_B_NORMAL_PLAYER_ID | SIDE_B_STRANGER_PLAYER_ID => SIDE_B_STRANGER_PLAYER_ID,
-        _ => PlayerId::INVALID,
+        num_normal_players
     }
 }
 
-pub fn opposing_normal_player(player_id: PlayerId) -> PlayerId {
-    if player_id == SIDE_A_NORMAL_PLAYER_ID || player_id == SIDE_A_STRANGER_PLAYER_ID {
-        SIDE_B_NORMAL_PLAYER_ID
+pub fn heuristic_score_text(score: f64) -> String {
+    if score == HEURISTIC_SCORE_WIN {
+        "WIN".to_string()
+    } else if score == HEURISTIC_SCORE_LOSS {
+        "LOSE".to_string()
     } else {
-        SIDE_A_NORMAL_PLAYER_ID
+        format!("{score:+0.4}")
     }
 }
 
-pub fn opposing_stranger(player_id: PlayerId) -> PlayerId {
-    allied_stranger(opposing_normal_player(player_id))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::seat_map::SeatMap;
 
     #[test]
     fn num_all_players_adds_strangers_when_needed() {
-        assert_eq!(num_all_players(2), 4);
+        assert_eq!(num_all_players(2), NUM_ALL_PLAYERS_WHEN_HAVE_STRANGERS);
         assert_eq!(num_all_players(3), 3);
+        assert_eq!(
+            num_all_players_with_strangers(2, StrangerCounts::new(2, 1).unwrap()),
+            5
+        );
+        assert_eq!(num_all_players_with_strangers(2, StrangerCounts::NONE), 2);
+        assert_eq!(
+            num_all_players_with_strangers(3, StrangerCounts::ONE_EACH),
+            3
+        );
+    }
+
+    #[test]
+    fn heuristic_score_text_names_terminal_scores() {
+        assert_eq!(heuristic_score_text(HEURISTIC_SCORE_WIN), "WIN");
+        assert_eq!(heuristic_score_text(HEURISTIC_SCORE_LOSS), "LOSE");
+        assert_eq!(heuristic_score_text(1.5), "+1.5000");
+        assert_eq!(heuristic_score_text(-0.25), "-0.2500");
     }
 
     #[test]
     fn to_normal_player_id_maps_strangers_to_allies() {
         assert_eq!(
-            to_normal_player_id(
-                STRANGER_PLAYER_ID_FIRST,
-                NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS
-            ),
+            SeatMap::new(NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS)
+                .to_normal_player_id(STRANGER_PLAYER_ID_FIRST),
             SIDE_B_NORMAL_PLAYER_ID
         );
         assert_eq!(
-            to_normal_player_id(
-                STRANGER_PLAYER_ID_SECOND,
-                NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS
-            ),
+            SeatMap::new(NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS)
+                .to_normal_player_id(STRANGER_PLAYER_ID_SECOND),
             SIDE_A_NORMAL_PLAYER_ID
         );
         assert_eq!(
-            to_normal_player_id(STRANGER_PLAYER_ID_FIRST, 3),
+            SeatMap::new(3).to_normal_player_id(STRANGER_PLAYER_ID_FIRST),
             STRANGER_PLAYER_ID_FIRST
         );
     }
 
     #[test]
     fn allied_and_opposing_player_helpers_match_switch_logic() {
+        let seats = SeatMap::with_strangers();
         assert_eq!(
-            allied_stranger(SIDE_A_NORMAL_PLAYER_ID),
-            SIDE_A_STRANGER_PLAYER_ID
+            seats.allied_strangers(SIDE_A_NORMAL_PLAYER_ID),
+            vec![SIDE_A_STRANGER_PLAYER_ID]
         );
         assert_eq!(
-            allied_stranger(SIDE_B_NORMAL_PLAYER_ID),
-            SIDE_B_STRANGER_PLAYER_ID
+            seats.allied_strangers(SIDE_B_NORMAL_PLAYER_ID),
+            vec![SIDE_B_STRANGER_PLAYER_ID]
         );
         assert_eq!(
-            opposing_normal_player(SIDE_A_NORMAL_PLAYER_ID),
+            seats.opposing_normal_player(SIDE_A_NORMAL_PLAYER_ID),
             SIDE_B_NORMAL_PLAYER_ID
         );
         assert_eq!(
-            opposing_normal_player(SIDE_B_STRANGER_PLAYER_ID),
+            seats.opposing_normal_player(SIDE_B_STRANGER_PLAYER_ID),
             SIDE_A_NORMAL_PLAYER_ID
         );
         assert_eq!(
-            opposing_stranger(SIDE_B_NORMAL_PLAYER_ID),
-            SIDE_A_STRANGER_PLAYER_ID
+            seats.opposing_strangers(SIDE_B_NORMAL_PLAYER_ID),
+            vec![SIDE_A_STRANGER_PLAYER_ID]
         );
     }
 }
@@ -0,0 +1,135 @@
+use crate::core::{
+    mutable_game_state::MutableGameState,
+    player::{PlayerAction, PlayerId},
+    rule_config::RuleConfig,
+};
+use std::fmt;
+use std::sync::Arc;
+
+// what a normal turn did, handed to plugins once its action is resolved and before the doctor
+// moves
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TurnEvent {
+    pub player_id: PlayerId,
+    pub action: PlayerAction,
+}
+
+// an optional rule that adjusts a normal turn's outcome. anything "random" has to come from the
+// state itself, so replays, undo and search all see the same effect
+pub trait RulePlugin: fmt::Debug + Send + Sync {
+    // names the plugin in comparisons and hashes of the rules
+    fn name(&self) -> &'static str;
+
+    fn after_normal_turn(&self, state: &mut MutableGameState, event: TurnEvent);
+}
+
+// the plugins a rule config switches on, in the order they run
+pub fn built_in_plugins(rule_config: &RuleConfig) -> Vec<Arc<dyn RulePlugin>> {
+    let mut plugins = Vec::<Arc<dyn RulePlugin>>::new();
+    if rule_config.creaky_floorboards_one_in > 0 {
+        plugins.push(Arc::new(CreakyFloorboards {
+            one_in: rule_config.creaky_floorboards_one_in as u64,
+        }));
+    }
+    plugins
+}
+
+pub fn is_built_in(plugin: &dyn RulePlugin) -> bool {
+    plugin.name() == CreakyFloorboards::NAME
+}
+
+// a loot is overheard and comes to nothing one time in one_in, picked by the position the looter
+// is in after moving
+#[derive(Debug)]
+pub struct CreakyFloorboards {
+    pub one_in: u64,
+}
+
+impl CreakyFloorboards {
+    pub const NAME: &'static str = "creakyFloorboards";
+
+    // position_key is a stable hash, so a replay, a saved game and the other player's device all
+    // hear the same creaks
+    pub fn creaks(&self, state: &MutableGameState) -> bool {
+        state.position_key().is_multiple_of(self.one_in.max(1))
+    }
+}
+
+impl RulePlugin for CreakyFloorboards {
+    fn name(&self) -> &'static str {
+        Self::NAME
+    }
+
+    fn after_normal_turn(&self, state: &mut MutableGameState, event: TurnEvent) {
+        if event.action == PlayerAction::Loot && self.creaks(state) {
+            state.forfeit_loot(event.player_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+
+    #[test]
+    fn creaky_floorboards_only_spoil_loots_where_they_creak() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let game = MutableGameState::at_start(common.clone());
+        let always = CreakyFloorboards { one_in: 1 };
+        assert!(built_in_plugins(&RuleConfig::default()).is_empty());
+
+        let mut looted = game.clone();
+        looted.num_loots = 1;
+        looted.player_move_cards[0] += crate::core::rule_helper::simple::MOVE_CARDS_PER_LOOT;
+        let mut spoiled = looted.clone();
+        always.after_normal_turn(
+            &mut spoiled,
+            TurnEvent {
+                player_id: PlayerId(0),
+                action: PlayerAction::Loot,
+            },
+        );
+        assert_eq!(spoiled.num_loots, 0);
+        assert_eq!(spoiled.player_move_cards[0], game.player_move_cards[0]);
+
+        let mut attacked = looted.clone();
+        always.after_normal_turn(
+            &mut attacked,
+            TurnEvent {
+                player_id: PlayerId(0),
+                action: PlayerAction::Attack,
+            },
+        );
+        assert_eq!(attacked.num_loots, 1);
+
+        let creaky = common.with_rule_config(RuleConfig {
+            creaky_floorboards_one_in: 1,
+            ..RuleConfig::default()
+        });
+        assert_eq!(
+            creaky
+                .rule_plugins
+                .iter()
+                .map(|plugin| plugin.name())
+                .collect::<Vec<_>>(),
+            vec![CreakyFloorboards::NAME]
+        );
+    }
+
+    #[test]
+    fn creaks_are_the_same_on_every_build() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let game =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2));
+        let creaks = (2..=9)
+            .map(|one_in| CreakyFloorboards { one_in }.creaks(&game))
+            .collect::<Vec<_>>();
+        assert_eq!(format!("{:016x}", game.position_key()), "96952a601c47ce47");
+        assert_eq!(
+            creaks,
+            vec![false, true, false, false, false, true, false, false]
+        );
+    }
+}
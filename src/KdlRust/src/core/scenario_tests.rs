@@ -0,0 +1,178 @@
+// scripted games whose verbose summaries, animation frames and doctor activations are compared
+// with the text in scenario_snapshots; the ui shows this text as-is, so a change to any of it
+// should be deliberate. after an intended change, rewrite the snapshots with
+//   KDL_REGENERATE_GOLDEN=1 cargo test --lib scenario_tests
+use crate::core::{
+    board::Board, common_game_state::CommonGameState, mutable_game_state::MutableGameState,
+    seat_map::StrangerCounts,
+};
+use std::fs;
+use std::path::PathBuf;
+
+const REGENERATE_ENV_VAR: &str = "KDL_REGENERATE_GOLDEN";
+const MAX_TURNS: usize = 24;
+
+struct Scenario {
+    name: &'static str,
+    board_name: &'static str,
+    num_normal_players: usize,
+    stranger_counts: Option<StrangerCounts>, // None keeps the standard count
+    turn_stride: usize, // picks possible_turns()[(turn_idx * turn_stride) % count], so play is fixed
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "tiny_2p_strangers",
+        board_name: "Tiny",
+        num_normal_players: 2,
+        stranger_counts: None,
+        turn_stride: 3,
+    },
+    Scenario {
+        name: "alt_down_2p_strangers",
+        board_name: "AltDown",
+        num_normal_players: 2,
+        stranger_counts: None,
+        turn_stride: 7,
+    },
+    Scenario {
+        name: "alt_down_2p_uneven_strangers",
+        board_name: "AltDown",
+        num_normal_players: 2,
+        stranger_counts: Some(StrangerCounts {
+            side_a: 2,
+            side_b: 1,
+        }),
+        turn_stride: 5,
+    },
+    Scenario {
+        name: "alt_up_3p_no_strangers",
+        board_name: "AltUp",
+        num_normal_players: 3,
+        stranger_counts: None,
+        turn_stride: 5,
+    },
+    Scenario {
+        name: "lair_north_4p_no_strangers",
+        board_name: "LairNorth",
+        num_normal_players: 4,
+        stranger_counts: None,
+        turn_stride: 11,
+    },
+];
+
+fn snapshot_path(scenario: &Scenario) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/core/scenario_snapshots")
+        .join(format!("{}.txt", scenario.name))
+}
+
+fn play_scenario(scenario: &Scenario) -> String {
+    let board = Board::from_embedded_json(scenario.board_name)
+        .unwrap_or_else(|err| panic!("{}: {err}", scenario.name));
+    let mut common =
+        CommonGameState::from_num_normal_players(false, board, scenario.num_normal_players);
+    if let Some(stranger_counts) = scenario.stranger_counts {
+        common = common
+            .with_stranger_counts(stranger_counts)
+            .unwrap_or_else(|err| panic!("{}: {err}", scenario.name));
+    }
+    let mut state = MutableGameState::at_start(common);
+
+    let mut lines = vec![
+        format!(
+            "scenario {} ({} with {} normal players, strangers {})",
+            scenario.name,
+            scenario.board_name,
+            scenario.num_normal_players,
+            state.common.stranger_counts
+        ),
+        state.summary(0),
+    ];
+    for turn_idx in 0..MAX_TURNS {
+        if state.is_game_over() {
+            break;
+        }
+        let turns = state.possible_turns();
+        let turn = turns[(turn_idx * scenario.turn_stride) % turns.len()].clone();
+        lines.push(format!("== turn {}: {turn}", turn_idx + 1));
+        state = state.after_turn(turn);
+        lines.push(state.prev_turn_summaries_since_normal(true));
+        let frames = state
+            .animation_frames_since_normal()
+            .iter()
+            .map(|frame| {
+                frame
+                    .iter()
+                    .map(|room_id| room_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>();
+        lines.push(format!("frames: {}", frames.join(" | ")));
+    }
+
+    let activations = state
+        .doctor_activation_hist()
+        .iter()
+        .map(|activation| {
+            format!(
+                "T{} doctor in {} activated {}",
+                activation.turn_id,
+                state.common.board.room_label(activation.doctor_room_id),
+                state.player_text_for(activation.activated_player_id)
+            )
+        })
+        .collect::<Vec<_>>();
+    lines.push("== doctor activations".to_string());
+    lines.extend(activations);
+    lines.push("== final".to_string());
+    lines.push(state.summary(0));
+    lines.push(format!("turns: {}", state.normal_turn_hist()));
+
+    lines.join("\n") + "\n"
+}
+
+#[test]
+fn scenario_text_matches_snapshots() {
+    let regenerate = std::env::var_os(REGENERATE_ENV_VAR).is_some();
+    let mut failures = Vec::new();
+
+    for scenario in SCENARIOS {
+        let actual = play_scenario(scenario);
+        let path = snapshot_path(scenario);
+        if regenerate {
+            fs::write(&path, &actual).expect("snapshot should be writable");
+            continue;
+        }
+
+        let Ok(expected) = fs::read_to_string(&path) else {
+            failures.push(format!(
+                "{}: no snapshot yet; run with {REGENERATE_ENV_VAR}=1",
+                scenario.name
+            ));
+            continue;
+        };
+        if let Some((line_idx, (want, got))) = expected
+            .lines()
+            .zip(actual.lines())
+            .enumerate()
+            .find(|(_, (want, got))| want != got)
+        {
+            failures.push(format!(
+                "{} line {}: got '{got}', expected '{want}'",
+                scenario.name,
+                line_idx + 1
+            ));
+        } else if expected != actual {
+            failures.push(format!(
+                "{}: {} lines, expected {}",
+                scenario.name,
+                actual.lines().count(),
+                expected.lines().count()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,511 @@
+use crate::core::{
+    player::PlayerId,
+    rule_helper::{
+        NUM_NORMAL_PLAYERS_FOR_TEAMS, NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+        SIDE_A_NORMAL_PLAYER_ID,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    pub fn opponent(self) -> Side {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+}
+
+// piece names used by the web ui; strangers are numbered in turn order, so with one stranger per
+// side stranger1 is side B's stranger because it moves right after P1
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PieceId {
+    Doctor,
+    Player1,
+    Player2,
+    Stranger1,
+    Stranger2,
+    Stranger3,
+    Stranger4,
+}
+
+impl PieceId {
+    const STRANGERS: [PieceId; 4] = [
+        PieceId::Stranger1,
+        PieceId::Stranger2,
+        PieceId::Stranger3,
+        PieceId::Stranger4,
+    ];
+
+    pub fn display_label(self) -> &'static str {
+        match self {
+            PieceId::Doctor => "Doctor",
+            PieceId::Player1 => "P1",
+            PieceId::Player2 => "P2",
+            PieceId::Stranger1 => "S1",
+            PieceId::Stranger2 => "S2",
+            PieceId::Stranger3 => "S3",
+            PieceId::Stranger4 => "S4",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PieceId::Doctor => "doctor",
+            PieceId::Player1 => "player1",
+            PieceId::Player2 => "player2",
+            PieceId::Stranger1 => "stranger1",
+            PieceId::Stranger2 => "stranger2",
+            PieceId::Stranger3 => "stranger3",
+            PieceId::Stranger4 => "stranger4",
+        }
+    }
+
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_ascii_lowercase().as_str() {
+            "doctor" => Some(PieceId::Doctor),
+            "player1" => Some(PieceId::Player1),
+            "player2" => Some(PieceId::Player2),
+            "stranger1" => Some(PieceId::Stranger1),
+            "stranger2" => Some(PieceId::Stranger2),
+            "stranger3" => Some(PieceId::Stranger3),
+            "stranger4" => Some(PieceId::Stranger4),
+            _ => None,
+        }
+    }
+
+    fn stranger_idx(self) -> Option<usize> {
+        Self::STRANGERS
+            .iter()
+            .position(|piece_id| *piece_id == self)
+    }
+}
+
+impl fmt::Display for PieceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.display_label())
+    }
+}
+
+// how many strangers each side of a two-player game controls
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StrangerCounts {
+    pub side_a: usize,
+    pub side_b: usize,
+}
+
+impl StrangerCounts {
+    pub const MAX_PER_SIDE: usize = 2;
+    pub const NONE: StrangerCounts = StrangerCounts {
+        side_a: 0,
+        side_b: 0,
+    };
+    pub const ONE_EACH: StrangerCounts = StrangerCounts {
+        side_a: 1,
+        side_b: 1,
+    };
+
+    pub fn new(side_a: usize, side_b: usize) -> Result<Self, String> {
+        if side_a > Self::MAX_PER_SIDE || side_b > Self::MAX_PER_SIDE {
+            return Err(format!(
+                "each side can have 0 to {} strangers, not {side_a} and {side_b}",
+                Self::MAX_PER_SIDE
+            ));
+        }
+        Ok(Self { side_a, side_b })
+    }
+
+    // the standard rules: one stranger per side in two-player games, none otherwise
+    pub fn default_for(num_normal_players: usize) -> Self {
+        if num_normal_players == NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS {
+            Self::ONE_EACH
+        } else {
+            Self::NONE
+        }
+    }
+
+    pub fn total(self) -> usize {
+        self.side_a + self.side_b
+    }
+
+    pub fn of(self, side: Side) -> usize {
+        match side {
+            Side::A => self.side_a,
+            Side::B => self.side_b,
+        }
+    }
+}
+
+impl fmt::Display for StrangerCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.side_a, self.side_b)
+    }
+}
+
+// the side each normal player of a four-player team game is on, two players per side; a side's
+// attacks count toward one shared total and its players win together
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Teams {
+    sides: [Side; NUM_NORMAL_PLAYERS_FOR_TEAMS],
+}
+
+impl Teams {
+    // P1 and P3 against P2 and P4, so teammates never move back to back
+    pub const ALTERNATING: Teams = Teams {
+        sides: [Side::A, Side::B, Side::A, Side::B],
+    };
+
+    pub fn with_side_a(side_a_player_ids: [PlayerId; 2]) -> Result<Self, String> {
+        let [first, second] = side_a_player_ids;
+        if first == second
+            || first.0 >= NUM_NORMAL_PLAYERS_FOR_TEAMS
+            || second.0 >= NUM_NORMAL_PLAYERS_FOR_TEAMS
+        {
+            return Err(format!(
+                "side A needs two different players from 1 to {NUM_NORMAL_PLAYERS_FOR_TEAMS}"
+            ));
+        }
+        let mut sides = [Side::B; NUM_NORMAL_PLAYERS_FOR_TEAMS];
+        sides[first.0] = Side::A;
+        sides[second.0] = Side::A;
+        Ok(Self { sides })
+    }
+
+    pub fn side_of(self, player_id: PlayerId) -> Option<Side> {
+        self.sides.get(player_id.0).copied()
+    }
+
+    // in turn order
+    pub fn players_of(self, side: Side) -> Vec<PlayerId> {
+        (0..NUM_NORMAL_PLAYERS_FOR_TEAMS)
+            .map(PlayerId)
+            .filter(|player_id| self.sides[player_id.0] == side)
+            .collect::<Vec<_>>()
+    }
+}
+
+impl fmt::Display for Teams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side_text = |side: Side| {
+            self.players_of(side)
+                .iter()
+                .map(|player_id| format!("P{}", player_id.0 + 1))
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+        write!(f, "{} vs {}", side_text(Side::A), side_text(Side::B))
+    }
+}
+
+// owns the mapping between player ids, sides and piece ids; two-player games with strangers and
+// four-player team games have sides, every other setup is one seat per player. with strangers,
+// seats go in turn order: side A's normal player, side B's strangers, side B's normal player, then
+// side A's strangers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SeatMap {
+    num_normal_players: usize,
+    stranger_counts: StrangerCounts,
+    teams: Option<Teams>,
+}
+
+impl SeatMap {
+    pub fn new(num_normal_players: usize) -> Self {
+        Self {
+            num_normal_players,
+            stranger_counts: StrangerCounts::default_for(num_normal_players),
+            teams: None,
+        }
+    }
+
+    pub fn with_strangers() -> Self {
+        Self::new(NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS)
+    }
+
+    // ignored unless there are two normal players
+    pub fn with_stranger_counts(mut self, stranger_counts: StrangerCounts) -> Self {
+        if self.num_normal_players == NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS {
+            self.stranger_counts = stranger_counts;
+        }
+        self
+    }
+
+    // ignored unless there are four normal players
+    pub fn with_teams(mut self, teams: Teams) -> Self {
+        if self.num_normal_players == NUM_NORMAL_PLAYERS_FOR_TEAMS {
+            self.teams = Some(teams);
+        }
+        self
+    }
+
+    pub fn stranger_counts(self) -> StrangerCounts {
+        self.stranger_counts
+    }
+
+    pub fn teams(self) -> Option<Teams> {
+        self.teams
+    }
+
+    pub fn num_all_players(self) -> usize {
+        self.num_normal_players + self.stranger_counts.total()
+    }
+
+    pub fn has_strangers(self) -> bool {
+        self.stranger_counts.total() > 0
+    }
+
+    pub fn side_of(self, player_id: PlayerId) -> Option<Side> {
+        if let Some(teams) = self.teams {
+            return teams.side_of(player_id);
+        }
+        if !self.has_strangers() {
+            return None;
+        }
+        let side_b_normal_idx = self.normal_of(Side::B).0;
+        match player_id.0 {
+            0 => Some(Side::A),
+            idx if idx <= side_b_normal_idx => Some(Side::B),
+            idx if idx < self.num_all_players() => Some(Side::A),
+            _ => None,
+        }
+    }
+
+    pub fn is_stranger(self, player_id: PlayerId) -> bool {
+        self.has_strangers()
+            && self
+                .side_of(player_id)
+                .is_some_and(|side| self.normal_of(side) != player_id)
+    }
+
+    // the side's first normal player, which stands for the whole side in team games
+    pub fn normal_of(self, side: Side) -> PlayerId {
+        if let Some(teams) = self.teams {
+            return teams.players_of(side)[0];
+        }
+        match side {
+            Side::A => SIDE_A_NORMAL_PLAYER_ID,
+            Side::B => PlayerId(self.stranger_counts.side_b + 1),
+        }
+    }
+
+    // in turn order; a side's strangers move right after the opposing normal player
+    pub fn strangers_of(self, side: Side) -> Vec<PlayerId> {
+        self.stranger_ids_of(side).collect::<Vec<_>>()
+    }
+
+    // strangers_of without the allocation, for evaluation
+    pub fn stranger_ids_of(self, side: Side) -> impl Iterator<Item = PlayerId> + use<> {
+        let first_idx = self.normal_of(side.opponent()).0 + 1;
+        (first_idx..first_idx + self.stranger_counts.of(side)).map(PlayerId)
+    }
+
+    // the normal player whose attack total and win this player's attacks count toward
+    pub fn to_normal_player_id(self, player_id: PlayerId) -> PlayerId {
+        self.side_of(player_id)
+            .map_or(player_id, |side| self.normal_of(side))
+    }
+
+    // the normal player who moves this piece; differs from to_normal_player_id only in team games
+    pub fn controller_of(self, player_id: PlayerId) -> PlayerId {
+        if self.is_stranger(player_id) {
+            self.to_normal_player_id(player_id)
+        } else {
+            player_id
+        }
+    }
+
+    // normal players sharing this player's wins, itself included
+    pub fn num_allied_normal_players(self, player_id: PlayerId) -> usize {
+        let normal_player_id = self.to_normal_player_id(player_id);
+        (0..self.num_all_players())
+            .map(PlayerId)
+            .filter(|other_id| {
+                !self.is_stranger(*other_id)
+                    && self.to_normal_player_id(*other_id) == normal_player_id
+            })
+            .count()
+    }
+
+    pub fn allied_strangers(self, player_id: PlayerId) -> Vec<PlayerId> {
+        self.side_of(player_id)
+            .map_or_else(Vec::new, |side| self.strangers_of(side))
+    }
+
+    pub fn opposing_normal_player(self, player_id: PlayerId) -> PlayerId {
+        self.side_of(player_id)
+            .map_or(PlayerId::INVALID, |side| self.normal_of(side.opponent()))
+    }
+
+    pub fn opposing_strangers(self, player_id: PlayerId) -> Vec<PlayerId> {
+        self.side_of(player_id)
+            .map_or_else(Vec::new, |side| self.strangers_of(side.opponent()))
+    }
+
+    // every stranger, in turn order
+    fn stranger_ids(self) -> Vec<PlayerId> {
+        (0..self.num_all_players())
+            .map(PlayerId)
+            .filter(|player_id| self.is_stranger(*player_id))
+            .collect::<Vec<_>>()
+    }
+
+    pub fn piece_id(self, player_id: PlayerId) -> Option<PieceId> {
+        if self.has_strangers() {
+            if player_id == self.normal_of(Side::A) {
+                return Some(PieceId::Player1);
+            }
+            if player_id == self.normal_of(Side::B) {
+                return Some(PieceId::Player2);
+            }
+            let stranger_idx = self
+                .stranger_ids()
+                .iter()
+                .position(|stranger_id| *stranger_id == player_id)?;
+            PieceId::STRANGERS.get(stranger_idx).copied()
+        } else {
+            match player_id.0 {
+                0 => Some(PieceId::Player1),
+                1 => Some(PieceId::Player2),
+                _ => None,
+            }
+        }
+    }
+
+    pub fn player_id(self, piece_id: PieceId) -> Option<PlayerId> {
+        match (piece_id, self.has_strangers()) {
+            (PieceId::Doctor, _) => None,
+            (PieceId::Player1, _) => Some(PlayerId(0)),
+            (PieceId::Player2, true) => Some(self.normal_of(Side::B)),
+            (PieceId::Player2, false) => Some(PlayerId(1)),
+            (_, true) => self.stranger_ids().get(piece_id.stranger_idx()?).copied(),
+            (_, false) => None,
+        }
+    }
+
+    // pieces in the order the ui lists them: the normal players, then the strangers
+    pub fn piece_ids(self) -> Vec<PieceId> {
+        self.display_order_player_ids()
+            .into_iter()
+            .filter_map(|player_id| self.piece_id(player_id))
+            .collect::<Vec<_>>()
+    }
+
+    // player ids in piece_ids order; games without strangers list every player in id order
+    pub fn display_order_player_ids(self) -> Vec<PlayerId> {
+        if !self.has_strangers() {
+            return (0..self.num_all_players())
+                .map(PlayerId)
+                .collect::<Vec<_>>();
+        }
+        [self.normal_of(Side::A), self.normal_of(Side::B)]
+            .into_iter()
+            .chain(self.stranger_ids())
+            .collect::<Vec<_>>()
+    }
+
+    pub fn piece_label(self, player_id: PlayerId) -> &'static str {
+        self.piece_id(player_id)
+            .map_or("?", |piece_id| piece_id.display_label())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sides_and_pieces_round_trip_with_strangers() {
+        let seats = SeatMap::with_strangers();
+        for player_id in (0..4).map(PlayerId) {
+            let piece_id = seats.piece_id(player_id).expect("every seat has a piece");
+            assert_eq!(seats.player_id(piece_id), Some(player_id));
+        }
+
+        assert_eq!(seats.side_of(PlayerId(1)), Some(Side::B));
+        assert_eq!(seats.side_of(PlayerId(3)), Some(Side::A));
+        assert_eq!(seats.to_normal_player_id(PlayerId(3)), PlayerId(0));
+        assert_eq!(seats.allied_strangers(PlayerId(0)), vec![PlayerId(3)]);
+        assert_eq!(seats.opposing_normal_player(PlayerId(1)), PlayerId(0));
+        assert_eq!(seats.opposing_strangers(PlayerId(0)), vec![PlayerId(1)]);
+        assert_eq!(seats.piece_label(PlayerId(1)), "S1");
+    }
+
+    #[test]
+    fn games_without_strangers_have_no_sides() {
+        let seats = SeatMap::new(3);
+        assert_eq!(seats.side_of(PlayerId(1)), None);
+        assert_eq!(seats.to_normal_player_id(PlayerId(1)), PlayerId(1));
+        assert_eq!(seats.piece_id(PlayerId(1)), Some(PieceId::Player2));
+        assert_eq!(seats.player_id(PieceId::Player2), Some(PlayerId(1)));
+        assert_eq!(seats.player_id(PieceId::Stranger1), None);
+    }
+
+    #[test]
+    fn uneven_stranger_counts_seat_in_turn_order() {
+        let seats =
+            SeatMap::with_strangers().with_stranger_counts(StrangerCounts::new(2, 1).unwrap());
+        assert_eq!(seats.num_all_players(), 5);
+        // P1, side B's stranger, P2, then side A's two strangers
+        assert_eq!(seats.normal_of(Side::B), PlayerId(2));
+        assert_eq!(seats.strangers_of(Side::A), vec![PlayerId(3), PlayerId(4)]);
+        assert_eq!(
+            seats.opposing_strangers(PlayerId(2)),
+            vec![PlayerId(3), PlayerId(4)]
+        );
+        assert_eq!(seats.to_normal_player_id(PlayerId(4)), PlayerId(0));
+        assert!(seats.is_stranger(PlayerId(1)) && !seats.is_stranger(PlayerId(2)));
+        for player_id in (0..5).map(PlayerId) {
+            let piece_id = seats.piece_id(player_id).expect("every seat has a piece");
+            assert_eq!(seats.player_id(piece_id), Some(player_id));
+        }
+        assert_eq!(seats.player_id(PieceId::Stranger4), None);
+        assert_eq!(
+            seats.piece_ids(),
+            vec![
+                PieceId::Player1,
+                PieceId::Player2,
+                PieceId::Stranger1,
+                PieceId::Stranger2,
+                PieceId::Stranger3,
+            ]
+        );
+    }
+
+    #[test]
+    fn stranger_counts_are_bounded_and_only_used_with_two_players() {
+        assert!(StrangerCounts::new(3, 0).is_err());
+        let seats = SeatMap::new(3).with_stranger_counts(StrangerCounts::ONE_EACH);
+        assert!(!seats.has_strangers());
+        let seats = SeatMap::with_strangers().with_stranger_counts(StrangerCounts::NONE);
+        assert_eq!(seats.side_of(PlayerId(1)), None);
+        assert_eq!(seats.player_id(PieceId::Player2), Some(PlayerId(1)));
+    }
+
+    #[test]
+    fn team_games_share_a_side_without_strangers() {
+        let seats = SeatMap::new(4).with_teams(Teams::ALTERNATING);
+        assert!(!seats.has_strangers());
+        assert_eq!(seats.side_of(PlayerId(2)), Some(Side::A));
+        assert_eq!(seats.to_normal_player_id(PlayerId(2)), PlayerId(0));
+        assert_eq!(seats.to_normal_player_id(PlayerId(3)), PlayerId(1));
+        assert_eq!(seats.controller_of(PlayerId(3)), PlayerId(3));
+        assert_eq!(seats.num_allied_normal_players(PlayerId(3)), 2);
+        assert!(!seats.is_stranger(PlayerId(2)));
+        assert!(seats.allied_strangers(PlayerId(0)).is_empty());
+
+        let teams = Teams::with_side_a([PlayerId(1), PlayerId(0)]).unwrap();
+        assert_eq!(teams.to_string(), "P1+P2 vs P3+P4");
+        assert!(Teams::with_side_a([PlayerId(1), PlayerId(1)]).is_err());
+        assert!(Teams::with_side_a([PlayerId(0), PlayerId(4)]).is_err());
+        assert_eq!(SeatMap::new(3).with_teams(teams).side_of(PlayerId(1)), None);
+    }
+}
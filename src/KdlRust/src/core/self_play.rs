@@ -0,0 +1,273 @@
+use crate::core::{
+    board::Board,
+    common_game_state::CommonGameState,
+    move_selector::{Difficulty, MoveSelector},
+    mutable_game_state::{DrawReason, GameResult, MutableGameState},
+    rule_helper,
+    tree_search::{SearchConfig, TreeSearch},
+};
+use crate::util::cancellation::NeverCancelToken;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[readonly::make]
+pub struct EngineConfig {
+    pub name: String,
+    pub analysis_level: i32,
+    pub difficulty: Option<Difficulty>, // plays through a MoveSelector rather than the best turn
+}
+
+impl EngineConfig {
+    pub fn new(name: impl Into<String>, analysis_level: i32) -> Self {
+        Self {
+            name: name.into(),
+            analysis_level,
+            difficulty: None,
+        }
+    }
+
+    pub fn from_analysis_level(analysis_level: i32) -> Self {
+        Self::new(format!("L{analysis_level}"), analysis_level)
+    }
+
+    pub fn from_difficulty(difficulty: Difficulty) -> Self {
+        Self {
+            difficulty: Some(difficulty),
+            ..Self::new(difficulty.to_string(), difficulty.preset().analysis_level)
+        }
+    }
+}
+
+impl fmt::Display for EngineConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.difficulty {
+            Some(difficulty) => write!(f, "{}(difficulty={difficulty})", self.name),
+            None => write!(f, "{}(level={})", self.name, self.analysis_level),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SelfPlayOutcome {
+    FirstEngineWon,
+    SecondEngineWon,
+    TurnLimitReached,
+    Draw(DrawReason),
+}
+
+impl SelfPlayOutcome {
+    pub fn first_engine_score(self) -> f64 {
+        match self {
+            SelfPlayOutcome::FirstEngineWon => 1.0,
+            SelfPlayOutcome::SecondEngineWon => 0.0,
+            SelfPlayOutcome::TurnLimitReached | SelfPlayOutcome::Draw(_) => 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SelfPlayGame {
+    pub outcome: SelfPlayOutcome,
+    pub num_normal_turns: usize,
+    pub num_states_visited: usize,
+    pub final_state: MutableGameState,
+    pub positions: Vec<MutableGameState>,
+}
+
+pub struct SelfPlayRunner {
+    pub max_normal_turns: usize,
+    pub record_positions: bool,
+    pub game_seed: u64, // what difficulty engines draw from, so a game can be played again exactly
+}
+
+impl SelfPlayRunner {
+    pub const DEFAULT_MAX_NORMAL_TURNS: usize = 200;
+
+    pub fn new(max_normal_turns: usize) -> Self {
+        Self {
+            max_normal_turns,
+            record_positions: false,
+            game_seed: 0,
+        }
+    }
+
+    pub fn with_recorded_positions(mut self) -> Self {
+        self.record_positions = true;
+        self
+    }
+
+    pub fn with_game_seed(mut self, game_seed: u64) -> Self {
+        self.game_seed = game_seed;
+        self
+    }
+
+    pub fn play_on_board(
+        &self,
+        board: Board,
+        first_engine: &EngineConfig,
+        second_engine: &EngineConfig,
+        first_engine_moves_first: bool,
+    ) -> SelfPlayGame {
+        let common = CommonGameState::from_num_normal_players(
+            false,
+            board,
+            rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS,
+        );
+        self.play(
+            MutableGameState::at_start(common),
+            first_engine,
+            second_engine,
+            first_engine_moves_first,
+        )
+    }
+
+    pub fn play(
+        &self,
+        start_state: MutableGameState,
+        first_engine: &EngineConfig,
+        second_engine: &EngineConfig,
+        first_engine_moves_first: bool,
+    ) -> SelfPlayGame {
+        let first_engine_player_id = if first_engine_moves_first {
+            rule_helper::SIDE_A_NORMAL_PLAYER_ID
+        } else {
+            rule_helper::SIDE_B_NORMAL_PLAYER_ID
+        };
+        let token = NeverCancelToken;
+        let mut state = start_state;
+        let mut num_normal_turns = 0;
+        let mut total_states_visited = 0;
+        let mut positions = Vec::new();
+        let mut selectors = [first_engine, second_engine].map(|engine| {
+            engine
+                .difficulty
+                .map(|difficulty| MoveSelector::new(difficulty, 0).with_game_seed(self.game_seed))
+        });
+        let search_config = SearchConfig::default();
+
+        while !state.is_game_over() && num_normal_turns < self.max_normal_turns {
+            let engine_idx = if state.common.to_normal_player_id(state.current_player_id)
+                == first_engine_player_id
+            {
+                0
+            } else {
+                1
+            };
+            let engine = [first_engine, second_engine][engine_idx];
+            let mut num_states_visited = 0;
+            let appraised_turn = match selectors[engine_idx].as_mut() {
+                Some(selector) => {
+                    selector
+                        .select_turn(&state, &search_config, &token, &mut num_states_visited)
+                        .appraised_turn
+                }
+                None => TreeSearch::find_best_turn(
+                    &state,
+                    engine.analysis_level.max(1),
+                    &token,
+                    &mut num_states_visited,
+                ),
+            };
+            total_states_visited += num_states_visited;
+            if self.record_positions {
+                positions.push(state.clone());
+            }
+            state = state.after_turn_without_memory(appraised_turn.turn);
+            num_normal_turns += 1;
+        }
+
+        let outcome = match state.result {
+            GameResult::Draw { reason } => SelfPlayOutcome::Draw(reason),
+            GameResult::InProgress | GameResult::Aborted => SelfPlayOutcome::TurnLimitReached,
+            GameResult::Won { player }
+                if state.common.to_normal_player_id(player) == first_engine_player_id =>
+            {
+                SelfPlayOutcome::FirstEngineWon
+            }
+            GameResult::Won { .. } => SelfPlayOutcome::SecondEngineWon,
+        };
+
+        SelfPlayGame {
+            outcome,
+            num_normal_turns,
+            num_states_visited: total_states_visited,
+            final_state: state,
+            positions,
+        }
+    }
+}
+
+impl Default for SelfPlayRunner {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_NORMAL_TURNS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_play_on_tiny_board_finishes_with_winner() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let runner = SelfPlayRunner::default();
+        let game = runner.play_on_board(
+            board,
+            &EngineConfig::from_analysis_level(2),
+            &EngineConfig::from_analysis_level(1),
+            true,
+        );
+
+        assert!(game.final_state.has_winner());
+        assert_ne!(game.outcome, SelfPlayOutcome::TurnLimitReached);
+        assert!(game.num_normal_turns > 0);
+        assert!(game.num_states_visited > 0);
+    }
+
+    #[test]
+    fn self_play_turn_limit_is_a_draw() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let runner = SelfPlayRunner::new(0);
+        let game = runner.play_on_board(
+            board,
+            &EngineConfig::from_analysis_level(1),
+            &EngineConfig::from_analysis_level(1),
+            false,
+        );
+
+        assert_eq!(game.outcome, SelfPlayOutcome::TurnLimitReached);
+        assert_eq!(game.outcome.first_engine_score(), 0.5);
+        assert!(game.positions.is_empty());
+    }
+
+    #[test]
+    fn seeded_difficulty_games_replay_exactly() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let easy = EngineConfig::from_difficulty(Difficulty::Easy);
+        let play = |game_seed: u64| {
+            SelfPlayRunner::new(12)
+                .with_game_seed(game_seed)
+                .play_on_board(board.clone(), &easy, &easy, true)
+                .final_state
+                .normal_turns()
+        };
+
+        assert_eq!(play(4), play(4));
+        assert_eq!(easy.to_string(), "easy(difficulty=easy)");
+    }
+
+    #[test]
+    fn recorded_positions_cover_each_normal_turn() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let runner = SelfPlayRunner::default().with_recorded_positions();
+        let game = runner.play_on_board(
+            board,
+            &EngineConfig::from_analysis_level(1),
+            &EngineConfig::from_analysis_level(1),
+            true,
+        );
+
+        assert_eq!(game.positions.len(), game.num_normal_turns);
+        assert!(game.positions.iter().all(|state| !state.has_winner()));
+    }
+}
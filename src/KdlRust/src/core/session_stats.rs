@@ -0,0 +1,146 @@
+use crate::core::{mutable_game_state::MutableGameState, player::PlayerId};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// totals over the finished games of a session; a game is recorded once it ends, and forgotten
+// again if its last turn gets undone. aborted games are left out
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionStats {
+    pub games_played: usize,
+    // indexed by the winner's normal player id, the way successful_attacks is; a stranger's or a
+    // team's win is counted for the side's normal player
+    pub wins_by_side: Vec<usize>,
+    pub draws: usize,
+    pub normal_turns: usize,
+    pub attacks_attempted: usize,
+    pub attacks_succeeded: usize,
+    pub loots_taken: usize,
+}
+
+impl SessionStats {
+    pub fn record_game(&mut self, state: &MutableGameState) {
+        self.tally_game(state, true);
+    }
+
+    pub fn forget_game(&mut self, state: &MutableGameState) {
+        self.tally_game(state, false);
+    }
+
+    fn tally_game(&mut self, state: &MutableGameState, is_adding: bool) {
+        if !state.is_game_over() || state.is_aborted() {
+            return;
+        }
+        let tally = |total: &mut usize, amount: usize| {
+            *total = if is_adding {
+                *total + amount
+            } else {
+                total.saturating_sub(amount)
+            };
+        };
+
+        tally(&mut self.games_played, 1);
+        if let Some(winner) = state.result.winner() {
+            if self.wins_by_side.len() <= winner.0 {
+                self.wins_by_side.resize(winner.0 + 1, 0);
+            }
+            tally(&mut self.wins_by_side[winner.0], 1);
+        } else {
+            tally(&mut self.draws, 1);
+        }
+        tally(&mut self.normal_turns, state.ply().max(0) as usize);
+        tally(&mut self.attacks_attempted, state.attack_hist.len());
+        tally(
+            &mut self.attacks_succeeded,
+            state
+                .successful_attacks
+                .iter()
+                .map(|count| (*count).max(0) as usize)
+                .sum(),
+        );
+        tally(&mut self.loots_taken, state.num_loots);
+    }
+
+    pub fn wins_for(&self, player_id: PlayerId) -> usize {
+        self.wins_by_side.get(player_id.0).copied().unwrap_or(0)
+    }
+
+    pub fn average_turns(&self) -> f64 {
+        if self.games_played == 0 {
+            0.0
+        } else {
+            self.normal_turns as f64 / self.games_played as f64
+        }
+    }
+}
+
+impl fmt::Display for SessionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wins_text = self
+            .wins_by_side
+            .iter()
+            .enumerate()
+            .filter(|(_, wins)| **wins > 0)
+            .map(|(idx, wins)| format!("P{} {wins}", idx + 1))
+            .chain((self.draws > 0).then(|| format!("draws {}", self.draws)))
+            .collect::<Vec<_>>();
+        write!(
+            f,
+            "games {}{}, avg turns {:.1}, attacks {} ({} succeeded), loots {}",
+            self.games_played,
+            if wins_text.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", wins_text.join(", "))
+            },
+            self.average_turns(),
+            self.attacks_attempted,
+            self.attacks_succeeded,
+            self.loots_taken
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+
+    fn finished_game() -> MutableGameState {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny should be available");
+        let mut state =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(false, board, 2));
+        while !state.is_game_over() {
+            let turn = state.possible_turns()[0].clone();
+            state = state.after_turn(turn);
+        }
+        state
+    }
+
+    #[test]
+    fn records_and_forgets_finished_games() {
+        let game = finished_game();
+        let mut stats = SessionStats::default();
+        let mut aborted = MutableGameState::at_start(game.common.clone());
+        stats.record_game(&aborted);
+        assert!(aborted.abort());
+        stats.record_game(&aborted);
+        assert_eq!(stats, SessionStats::default());
+
+        stats.record_game(&game);
+        stats.record_game(&game);
+        assert_eq!(stats.games_played, 2);
+        assert_eq!(stats.wins_for(game.winner()) + stats.draws, 2);
+        assert_eq!(stats.normal_turns, 2 * game.ply() as usize);
+        assert_eq!(stats.average_turns(), game.ply() as f64);
+        assert_eq!(stats.attacks_attempted, 2 * game.attack_hist.len());
+        assert_eq!(stats.loots_taken, 2 * game.num_loots);
+        assert!(stats.to_string().starts_with("games 2 ("));
+
+        stats.forget_game(&game);
+        stats.forget_game(&game);
+        assert_eq!(stats.games_played, 0);
+        assert_eq!(stats.average_turns(), 0.0);
+        assert_eq!(stats.loots_taken, 0);
+    }
+}
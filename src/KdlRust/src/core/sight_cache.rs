@@ -0,0 +1,100 @@
+use crate::core::{board::Board, player::PlayerId, room::RoomId};
+
+// bit p of watchers[room] is set when that room has player p's room in sight; moves update only
+// the rooms that see the old and new rooms, so asking whether a room is watched is one lookup
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SightCache {
+    watchers: Vec<u32>,
+}
+
+impl SightCache {
+    pub fn new(board: &Board, player_room_ids: &[RoomId]) -> Self {
+        assert!(
+            player_room_ids.len() <= u32::BITS as usize,
+            "sight cache supports at most {} players",
+            u32::BITS
+        );
+        let mut cache = Self {
+            watchers: vec![0; board.sight.dim()],
+        };
+        for (player_idx, room_id) in player_room_ids.iter().enumerate() {
+            cache.set_watcher(board, PlayerId(player_idx), *room_id, true);
+        }
+        cache
+    }
+
+    pub fn move_player(&mut self, board: &Board, player_id: PlayerId, from: RoomId, to: RoomId) {
+        if from != to {
+            self.set_watcher(board, player_id, from, false);
+            self.set_watcher(board, player_id, to, true);
+        }
+    }
+
+    pub fn watchers(&self, room_id: RoomId) -> u32 {
+        self.watchers[room_id.0]
+    }
+
+    pub fn is_seen_by_others(&self, room_id: RoomId, player_id: PlayerId) -> bool {
+        self.watchers(room_id) & !(1 << player_id.0) != 0
+    }
+
+    pub fn num_other_watchers(&self, room_id: RoomId, player_id: PlayerId) -> u32 {
+        (self.watchers(room_id) & !(1 << player_id.0)).count_ones()
+    }
+
+    fn set_watcher(&mut self, board: &Board, player_id: PlayerId, room_id: RoomId, is_set: bool) {
+        let bit = 1 << player_id.0;
+        for watching_room_id in &board.seen_from_room_ids[room_id.0] {
+            if is_set {
+                self.watchers[watching_room_id.0] |= bit;
+            } else {
+                self.watchers[watching_room_id.0] &= !bit;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::room::Room;
+
+    fn line_board() -> Board {
+        Board::new(
+            "line",
+            [
+                Room::new(RoomId(1), "A", [RoomId(2)], [RoomId(2)]),
+                Room::new(
+                    RoomId(2),
+                    "B",
+                    [RoomId(1), RoomId(3)],
+                    [RoomId(1), RoomId(3)],
+                ),
+                Room::new(RoomId(3), "C", [RoomId(2)], [RoomId(2)]),
+            ],
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            RoomId(1),
+            None,
+        )
+    }
+
+    #[test]
+    fn incremental_moves_match_rebuild() {
+        let board = line_board();
+        let mut player_room_ids = vec![RoomId(1), RoomId(3)];
+        let mut cache = SightCache::new(&board, &player_room_ids);
+
+        assert!(cache.is_seen_by_others(RoomId(2), PlayerId(0)));
+        assert!(!cache.is_seen_by_others(RoomId(1), PlayerId(0)));
+        assert_eq!(cache.num_other_watchers(RoomId(2), PlayerId(2)), 2);
+
+        cache.move_player(&board, PlayerId(1), RoomId(3), RoomId(2));
+        player_room_ids[1] = RoomId(2);
+
+        assert_eq!(cache, SightCache::new(&board, &player_room_ids));
+        assert!(cache.is_seen_by_others(RoomId(1), PlayerId(0)));
+        assert_eq!(cache.watchers(RoomId(3)), 0b10);
+    }
+}
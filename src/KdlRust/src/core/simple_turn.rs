@@ -1,11 +1,14 @@
 use crate::core::{
+    board::RoomSymmetry,
     player::{PieceMove, PlayerId, player_moves_to_nice_string},
     room::RoomId,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+// moves are kept in player id order, so the same moves listed in another order are the same turn
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 #[serde(rename_all = "PascalCase")]
 #[readonly::make]
 pub struct SimpleTurn {
@@ -14,9 +17,9 @@ pub struct SimpleTurn {
 
 impl SimpleTurn {
     pub fn new(moves: impl IntoIterator<Item = PieceMove>) -> Self {
-        Self {
-            moves: moves.into_iter().collect(),
-        }
+        let mut moves = moves.into_iter().collect::<Vec<_>>();
+        moves.sort_by_key(|mv| mv.player_id);
+        Self { moves }
     }
 
     pub fn single(player_id: PlayerId, dest_room_id: RoomId) -> Self {
@@ -27,6 +30,14 @@ impl SimpleTurn {
         Self::new([piece_move])
     }
 
+    pub fn relabeled(&self, symmetry: &RoomSymmetry) -> Self {
+        Self::new(
+            self.moves
+                .iter()
+                .map(|mv| PieceMove::new(mv.player_id, symmetry.apply(mv.dest_room_id))),
+        )
+    }
+
     pub fn invalid_default() -> Self {
         Self::new([PieceMove::new(PlayerId::INVALID, RoomId(0))])
     }
@@ -44,6 +55,18 @@ impl From<SimpleTurn> for Vec<PieceMove> {
     }
 }
 
+impl<'de> Deserialize<'de> for SimpleTurn {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct SimpleTurnFields {
+            moves: Vec<PieceMove>,
+        }
+
+        SimpleTurnFields::deserialize(deserializer).map(|fields| Self::new(fields.moves))
+    }
+}
+
 impl fmt::Display for SimpleTurn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -54,6 +77,34 @@ impl fmt::Display for SimpleTurn {
     }
 }
 
+// inverse of Display, so recorded turn text like "1@2 2@7;" can be replayed
+impl FromStr for SimpleTurn {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let moves = text
+            .trim()
+            .trim_end_matches(';')
+            .split_whitespace()
+            .map(|move_text| {
+                let (player_text, room_text) = move_text
+                    .split_once('@')
+                    .ok_or_else(|| format!("move '{move_text}' needs playerNum@roomId"))?;
+                let player_num = player_text
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|player_num| *player_num > 0)
+                    .ok_or_else(|| format!("bad player number in move '{move_text}'"))?;
+                let room_id = room_text
+                    .parse::<usize>()
+                    .map_err(|_| format!("bad room id in move '{move_text}'"))?;
+                Ok(PieceMove::new(PlayerId(player_num - 1), RoomId(room_id)))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self::new(moves))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -89,6 +140,42 @@ mod tests {
         assert_eq!(turn.to_string(), "1@2 2@7;");
     }
 
+    #[test]
+    fn parses_its_own_display_text() {
+        let turn = SimpleTurn::new([
+            PieceMove::new(PlayerId(0), RoomId(2)),
+            PieceMove::new(PlayerId(1), RoomId(7)),
+        ]);
+        assert_eq!(turn.to_string().parse::<SimpleTurn>(), Ok(turn));
+        assert_eq!(";".parse::<SimpleTurn>(), Ok(SimpleTurn::new([])));
+        assert!("0@2;".parse::<SimpleTurn>().is_err());
+        assert!("1-2;".parse::<SimpleTurn>().is_err());
+    }
+
+    #[test]
+    fn moves_in_any_order_make_the_same_turn() {
+        let forward = SimpleTurn::new([
+            PieceMove::new(PlayerId(1), RoomId(7)),
+            PieceMove::new(PlayerId(2), RoomId(3)),
+        ]);
+        let backward = "3@3 2@7;"
+            .parse::<SimpleTurn>()
+            .expect("turn text should parse");
+        assert_eq!(forward, backward);
+        assert_eq!(backward.to_string(), "2@7 3@3;");
+        assert_eq!(forward.cmp(&backward), std::cmp::Ordering::Equal);
+        assert_eq!(
+            [forward.clone(), backward]
+                .into_iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            1
+        );
+
+        let json = r#"{"Moves":[{"PlayerId":2,"DestRoomId":3},{"PlayerId":1,"DestRoomId":7}]}"#;
+        assert_eq!(serde_json::from_str::<SimpleTurn>(json).ok(), Some(forward));
+    }
+
     #[test]
     fn into_vec_matches_implicit_conversion() {
         let turn = SimpleTurn::new([
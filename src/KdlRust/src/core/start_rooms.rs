@@ -0,0 +1,242 @@
+use crate::core::{board::Board, room::RoomId};
+use crate::util::rng::SplitMix64;
+use std::fmt;
+use std::str::FromStr;
+
+// where a new game's start rooms come from: the board's own starts, a random pick from the
+// board spec's candidate lists, or a random pick from every open room
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StartRoomPool {
+    #[default]
+    Board,
+    Candidates,
+    Any,
+}
+
+impl StartRoomPool {
+    pub const NAMES: [&'static str; 3] = ["board", "candidates", "any"];
+}
+
+impl fmt::Display for StartRoomPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::NAMES[*self as usize])
+    }
+}
+
+impl FromStr for StartRoomPool {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "board" => Ok(Self::Board),
+            "candidates" => Ok(Self::Candidates),
+            "any" => Ok(Self::Any),
+            _ => Err(format!(
+                "unknown start room pool '{text}'; use one of {}",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+// every piece starts in player_room_id, as in the physical game
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StartRooms {
+    pub player_room_id: RoomId,
+    pub doctor_room_id: RoomId,
+}
+
+// with a doctor offset, the doctor starts that many rooms after the players' room in visit
+// order instead of being picked from the pool
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct StartOptions {
+    pub pool: StartRoomPool,
+    pub doctor_offset: Option<i32>,
+}
+
+impl StartOptions {
+    pub fn is_board_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn choose(&self, board: &Board, rng: &mut SplitMix64) -> Result<StartRooms, String> {
+        let (player_pool, doctor_pool) = match self.pool {
+            StartRoomPool::Board => (
+                vec![board.player_start_room_id],
+                vec![board.doctor_start_room_id],
+            ),
+            StartRoomPool::Candidates => {
+                let open_candidates = |candidates: Option<&Vec<RoomId>>, fallback: RoomId| {
+                    let open_room_ids = candidates
+                        .into_iter()
+                        .flatten()
+                        .copied()
+                        .filter(|room_id| board.rooms.contains_key(room_id))
+                        .collect::<Vec<_>>();
+                    if open_room_ids.is_empty() {
+                        vec![fallback]
+                    } else {
+                        open_room_ids
+                    }
+                };
+                let spec = board.spec.as_ref();
+                (
+                    open_candidates(
+                        spec.map(|spec| &spec.player_start_room_ids),
+                        board.player_start_room_id,
+                    ),
+                    open_candidates(
+                        spec.map(|spec| &spec.doctor_start_room_ids),
+                        board.doctor_start_room_id,
+                    ),
+                )
+            }
+            StartRoomPool::Any => (board.room_ids.clone(), board.room_ids.clone()),
+        };
+
+        let player_room_id = pick(&player_pool, rng);
+        let doctor_room_id = match self.doctor_offset {
+            Some(offset) => board.next_room_id_in_doctor_visit_order(player_room_id, offset),
+            None => {
+                // the doctor shares the players' room only when the pool leaves no other choice
+                let apart = doctor_pool
+                    .iter()
+                    .copied()
+                    .filter(|room_id| *room_id != player_room_id)
+                    .collect::<Vec<_>>();
+                if apart.is_empty() {
+                    pick(&doctor_pool, rng)
+                } else {
+                    pick(&apart, rng)
+                }
+            }
+        };
+        if self.doctor_offset.is_some() && doctor_room_id == player_room_id {
+            return Err(format!(
+                "A doctor offset of {} puts the doctor in the players' room.",
+                self.doctor_offset.unwrap_or_default()
+            ));
+        }
+        Ok(StartRooms {
+            player_room_id,
+            doctor_room_id,
+        })
+    }
+}
+
+impl fmt::Display for StartOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pool)?;
+        if let Some(offset) = self.doctor_offset {
+            write!(f, " offset={offset}")?;
+        }
+        Ok(())
+    }
+}
+
+fn pick(room_ids: &[RoomId], rng: &mut SplitMix64) -> RoomId {
+    if room_ids.len() < 2 {
+        return room_ids.first().copied().unwrap_or(RoomId(0));
+    }
+    room_ids[(rng.next_u64() % room_ids.len() as u64) as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pools_and_offsets_pick_rooms_the_same_way_for_a_seed() {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let mut rng = SplitMix64::new(3);
+        let board_default = StartOptions::default()
+            .choose(&board, &mut rng)
+            .expect("the board's starts are fine");
+        assert_eq!(board_default.player_room_id, board.player_start_room_id);
+        assert_eq!(board_default.doctor_room_id, board.doctor_start_room_id);
+
+        let any = StartOptions {
+            pool: StartRoomPool::Any,
+            doctor_offset: None,
+        };
+        let draws = (0..20)
+            .map(|_| any.choose(&board, &mut rng).expect("any room will do"))
+            .collect::<Vec<_>>();
+        assert!(
+            draws
+                .iter()
+                .all(|rooms| rooms.player_room_id != rooms.doctor_room_id)
+        );
+        assert!(draws.iter().any(|rooms| *rooms != draws[0]));
+        let mut replay = SplitMix64::new(3);
+        StartOptions::default()
+            .choose(&board, &mut replay)
+            .expect("the board's starts are fine");
+        assert!(
+            draws
+                .iter()
+                .all(|rooms| any.choose(&board, &mut replay).as_ref() == Ok(rooms))
+        );
+
+        let offset = StartOptions {
+            pool: StartRoomPool::Board,
+            doctor_offset: Some(2),
+        };
+        assert_eq!(
+            offset
+                .choose(&board, &mut rng)
+                .map(|rooms| rooms.doctor_room_id),
+            Ok(board.next_room_id_in_doctor_visit_order(board.player_start_room_id, 2))
+        );
+        let onto_players = StartOptions {
+            doctor_offset: Some(board.room_ids.len() as i32),
+            ..offset
+        };
+        assert!(onto_players.choose(&board, &mut rng).is_err());
+    }
+
+    #[test]
+    fn candidates_come_from_the_spec_and_pools_parse() {
+        let board = Board::from_text_str(
+            "name\tsquare\n\
+             player\t1,2\n\
+             doctor\t3,4\n\
+             cat\t1\n\
+             dog\t1\n\
+             room\t1\ta\t2,4\t2,4\n\
+             room\t2\tb\t1,3\t1,3\n\
+             room\t3\tc\t2,4\t2,4\n\
+             room\t4\td\t1,3\t1,3\n",
+            "Square",
+        )
+        .expect("board should load");
+        let candidates = StartOptions {
+            pool: StartRoomPool::Candidates,
+            doctor_offset: None,
+        };
+        let mut rng = SplitMix64::new(11);
+        let draws = (0..12)
+            .map(|_| {
+                candidates
+                    .choose(&board, &mut rng)
+                    .expect("candidates will do")
+            })
+            .collect::<Vec<_>>();
+        assert!(draws.iter().all(|rooms| {
+            [RoomId(1), RoomId(2)].contains(&rooms.player_room_id)
+                && [RoomId(3), RoomId(4)].contains(&rooms.doctor_room_id)
+        }));
+        assert!(draws.iter().any(|rooms| *rooms != draws[0]));
+
+        assert_eq!(" Any ".parse::<StartRoomPool>(), Ok(StartRoomPool::Any));
+        assert!("somewhere".parse::<StartRoomPool>().is_err());
+        assert_eq!(
+            StartOptions {
+                pool: StartRoomPool::Candidates,
+                doctor_offset: Some(-1),
+            }
+            .to_string(),
+            "candidates offset=-1"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::core::{
+    card_count::CardCount,
+    mutable_game_state::{AttackRecord, MutableGameState},
+    player::PlayerId,
+    room::RoomId,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct PieceDiff {
+    pub player_id: PlayerId,
+    pub from_room_id: RoomId,
+    pub to_room_id: RoomId,
+}
+
+// what the later state holds minus what the earlier one did
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CardDelta {
+    pub player_id: PlayerId,
+    pub move_cards: CardCount,
+    pub weapons: CardCount,
+    pub failures: CardCount,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct StrengthDelta {
+    pub player_id: PlayerId,
+    pub from_strength: i32,
+    pub to_strength: i32,
+}
+
+// how one state differs from another; only pieces and players that changed are listed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateDiff {
+    pub from_ply: i32,
+    pub to_ply: i32,
+    pub doctor_from_room_id: RoomId,
+    pub doctor_to_room_id: RoomId,
+    pub piece_moves: Vec<PieceDiff>,
+    pub card_deltas: Vec<CardDelta>,
+    pub strength_deltas: Vec<StrengthDelta>,
+    pub attacks: Vec<AttackRecord>, // made after the histories part ways, in to's history only
+    pub undone_attacks: Vec<AttackRecord>, // in from's history only, as when diffing backward
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.doctor_from_room_id == self.doctor_to_room_id
+            && self.piece_moves.is_empty()
+            && self.card_deltas.is_empty()
+            && self.strength_deltas.is_empty()
+            && self.attacks.is_empty()
+            && self.undone_attacks.is_empty()
+    }
+}
+
+pub fn diff_states(from: &MutableGameState, to: &MutableGameState) -> Result<StateDiff, String> {
+    let num_all_players = from.player_room_ids().len();
+    if to.player_room_ids().len() != num_all_players {
+        return Err(format!(
+            "States have different pieces ({num_all_players} and {}).",
+            to.player_room_ids().len()
+        ));
+    }
+
+    let player_ids = (0..num_all_players).map(PlayerId).collect::<Vec<_>>();
+    let piece_moves = player_ids
+        .iter()
+        .filter(|player_id| {
+            from.player_room_ids()[player_id.0] != to.player_room_ids()[player_id.0]
+        })
+        .map(|player_id| PieceDiff {
+            player_id: *player_id,
+            from_room_id: from.player_room_ids()[player_id.0],
+            to_room_id: to.player_room_ids()[player_id.0],
+        })
+        .collect::<Vec<_>>();
+    let card_deltas = player_ids
+        .iter()
+        .map(|player_id| CardDelta {
+            player_id: *player_id,
+            move_cards: to.player_move_cards[player_id.0] - from.player_move_cards[player_id.0],
+            weapons: to.player_weapons[player_id.0] - from.player_weapons[player_id.0],
+            failures: to.player_failures[player_id.0] - from.player_failures[player_id.0],
+        })
+        .filter(|delta| {
+            [delta.move_cards, delta.weapons, delta.failures]
+                .iter()
+                .any(|cards| *cards != CardCount::ZERO)
+        })
+        .collect::<Vec<_>>();
+    let strength_deltas = player_ids
+        .iter()
+        .filter(|player_id| from.player_strengths[player_id.0] != to.player_strengths[player_id.0])
+        .map(|player_id| StrengthDelta {
+            player_id: *player_id,
+            from_strength: from.player_strengths[player_id.0],
+            to_strength: to.player_strengths[player_id.0],
+        })
+        .collect::<Vec<_>>();
+
+    let num_shared_attacks = from
+        .attack_hist
+        .iter()
+        .zip(&to.attack_hist)
+        .take_while(|(from_attack, to_attack)| from_attack == to_attack)
+        .count();
+    Ok(StateDiff {
+        from_ply: from.ply(),
+        to_ply: to.ply(),
+        doctor_from_room_id: from.doctor_room_id,
+        doctor_to_room_id: to.doctor_room_id,
+        piece_moves,
+        card_deltas,
+        strength_deltas,
+        attacks: to.attack_hist[num_shared_attacks..].to_vec(),
+        undone_attacks: from.attack_hist[num_shared_attacks..].to_vec(),
+    })
+}
+
+// the state a normal player faced after ply normal turns, or the finished game after the last
+pub fn state_at_ply(state: &MutableGameState, ply: usize) -> Result<MutableGameState, String> {
+    let current_ply = state.ply().max(0) as usize;
+    if ply > current_ply {
+        return Err(format!(
+            "Ply {ply} has not been played yet (current ply is {current_ply})."
+        ));
+    }
+
+    let rehydrated = state.rehydrated();
+    let mut cursor_ply = current_ply;
+    let mut cursor = Some(&rehydrated);
+    while let Some(cursor_state) = cursor {
+        if cursor_ply == ply && (cursor_state.is_normal_turn() || cursor_state.is_game_over()) {
+            let mut found = cursor_state.copy_state();
+            found.prev_state = None;
+            found.compacted_history = None;
+            return Ok(found);
+        }
+        cursor = cursor_state.prev_state.as_deref();
+        if cursor.is_some_and(|prev_state| prev_state.is_normal_turn()) {
+            cursor_ply = cursor_ply.saturating_sub(1);
+        }
+    }
+    Err(format!("Ply {ply} is no longer in the game's history."))
+}
+
+pub fn diff_plies(
+    state: &MutableGameState,
+    from_ply: usize,
+    to_ply: usize,
+) -> Result<StateDiff, String> {
+    let from = state_at_ply(state, from_ply)?;
+    let to = state_at_ply(state, to_ply)?;
+    let mut diff = diff_states(&from, &to)?;
+    // the found states were cut from their history, so ply() no longer counts it
+    diff.from_ply = from_ply as i32;
+    diff.to_ply = to_ply as i32;
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+
+    fn played_game(num_turns: usize) -> MutableGameState {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        let mut state =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2));
+        for _ in 0..num_turns {
+            let turn = state
+                .possible_turns()
+                .into_iter()
+                .last()
+                .expect("a game in progress has turns");
+            state.apply_turn(turn);
+        }
+        state
+    }
+
+    #[test]
+    fn plies_diff_both_ways_and_through_compaction() {
+        let state = played_game(6);
+        let forward = diff_plies(&state, 0, 6).expect("both plies were played");
+        assert_eq!((forward.from_ply, forward.to_ply), (0, 6));
+        assert!(!forward.piece_moves.is_empty());
+        assert_eq!(forward.attacks.len(), state.attack_hist.len());
+        assert!(forward.undone_attacks.is_empty());
+
+        let backward = diff_plies(&state, 6, 0).expect("both plies were played");
+        assert_eq!(backward.attacks, forward.undone_attacks);
+        assert_eq!(backward.undone_attacks, forward.attacks);
+        assert_eq!(
+            backward
+                .piece_moves
+                .iter()
+                .map(|mv| (mv.player_id, mv.to_room_id, mv.from_room_id))
+                .collect::<Vec<_>>(),
+            forward
+                .piece_moves
+                .iter()
+                .map(|mv| (mv.player_id, mv.from_room_id, mv.to_room_id))
+                .collect::<Vec<_>>()
+        );
+        assert!(diff_plies(&state, 3, 3).is_ok_and(|diff| diff.is_empty()));
+
+        let mut compacted = state.copy_state();
+        compacted.compact_history(2);
+        assert_eq!(diff_plies(&compacted, 1, 5), diff_plies(&state, 1, 5));
+        assert!(diff_plies(&state, 0, 7).is_err());
+    }
+}
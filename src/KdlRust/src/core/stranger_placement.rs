@@ -0,0 +1,196 @@
+use crate::core::{
+    mutable_game_state::MutableGameState,
+    player::PlayerId,
+    room::RoomId,
+    seat_map::Side,
+    simple_turn::SimpleTurn,
+    tree_search::{SearchConfig, TreeSearch},
+};
+use crate::util::cancellation::CancellationToken;
+use serde::Serialize;
+use std::collections::HashSet;
+
+// every candidate gets an exact appraisal, so the search stays shallow
+pub const PLACEMENT_ANALYSIS_LEVEL: i32 = 2;
+pub const MAX_PLACEMENTS: usize = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PlacementReason {
+    // the stranger sees the doctor's room after the turn, so an attack there would be witnessed
+    BlocksSight,
+    // the stranger now counters a stranger loop of its side that it didn't before
+    SetsUpLoop,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrangerPlacement {
+    pub stranger_id: PlayerId,
+    pub room_id: RoomId,
+    pub turn: SimpleTurn, // the best turn that puts the stranger there
+    pub appraisal: f64,   // for the player to move
+    pub reasons: Vec<PlacementReason>,
+}
+
+// the best rooms for the current player to move one of a side's strangers to, best first; each
+// room is appraised by the best turn that takes a stranger of the side there
+pub fn suggest_stranger_placements(
+    state: &MutableGameState,
+    side: Side,
+    analysis_level: i32,
+    max_placements: usize,
+    search_config: &SearchConfig,
+    cancellation_token: &impl CancellationToken,
+    num_states_visited: &mut usize,
+) -> Result<Vec<StrangerPlacement>, String> {
+    if state.is_game_over() {
+        return Err("The game is over.".to_string());
+    }
+    let seats = state.common.seat_map();
+    let stranger_ids = if seats.has_strangers() {
+        seats.strangers_of(side)
+    } else {
+        Vec::new()
+    };
+    if stranger_ids.is_empty() {
+        return Err(format!("Side {side:?} has no strangers to place."));
+    }
+
+    let num_turns = state.possible_turns().len();
+    let ranked_turns = TreeSearch::find_best_turns_with_config(
+        state,
+        analysis_level.max(1),
+        num_turns,
+        search_config,
+        cancellation_token,
+        num_states_visited,
+    );
+
+    let side_normal_id = seats.normal_of(side);
+    let countered_before = countered_loop_rooms(state, side_normal_id);
+    let mut seen_placements = HashSet::new();
+    let mut placements = Vec::new();
+    for ranked in ranked_turns {
+        for mv in &ranked.turn.moves {
+            let is_move = mv.dest_room_id != state.player_room_ids()[mv.player_id.0];
+            if !is_move
+                || !stranger_ids.contains(&mv.player_id)
+                || !seen_placements.insert((mv.player_id, mv.dest_room_id))
+            {
+                continue;
+            }
+
+            let child_state = state.after_turn_without_memory(ranked.turn.clone());
+            let mut reasons = Vec::new();
+            if state.common.board.sight[(mv.dest_room_id, child_state.doctor_room_id)] {
+                reasons.push(PlacementReason::BlocksSight);
+            }
+            if countered_loop_rooms(&child_state, side_normal_id)
+                .difference(&countered_before)
+                .next()
+                .is_some()
+            {
+                reasons.push(PlacementReason::SetsUpLoop);
+            }
+            placements.push(StrangerPlacement {
+                stranger_id: mv.player_id,
+                room_id: mv.dest_room_id,
+                turn: ranked.turn.clone(),
+                appraisal: ranked.appraisal,
+                reasons,
+            });
+        }
+    }
+    placements.truncate(max_placements);
+    Ok(placements)
+}
+
+fn countered_loop_rooms(state: &MutableGameState, normal_player_id: PlayerId) -> HashSet<RoomId> {
+    state
+        .stranger_loop_threats(normal_player_id)
+        .into_iter()
+        .filter(|threat| threat.is_countered)
+        .map(|threat| threat.enemy_room_id)
+        .collect::<HashSet<_>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{board::Board, common_game_state::CommonGameState};
+    use crate::util::cancellation::NeverCancelToken;
+
+    fn alt_down_state() -> MutableGameState {
+        let board = Board::from_embedded_json("AltDown").expect("AltDown should be available");
+        MutableGameState::at_start(CommonGameState::from_num_normal_players(false, board, 2))
+    }
+
+    #[test]
+    fn ranks_distinct_rooms_for_the_sides_strangers() {
+        let state = alt_down_state();
+        let stranger_ids = state.common.seat_map().strangers_of(Side::B);
+        let mut num_states_visited = 0;
+        let placements = suggest_stranger_placements(
+            &state,
+            Side::B,
+            1,
+            MAX_PLACEMENTS,
+            &SearchConfig::default(),
+            &NeverCancelToken,
+            &mut num_states_visited,
+        )
+        .expect("side B has a stranger");
+
+        assert!(!placements.is_empty() && placements.len() <= MAX_PLACEMENTS);
+        assert!(num_states_visited > 0);
+        assert!(
+            placements
+                .windows(2)
+                .all(|pair| pair[0].appraisal >= pair[1].appraisal)
+        );
+        for placement in &placements {
+            assert!(stranger_ids.contains(&placement.stranger_id));
+            assert_ne!(
+                placement.room_id,
+                state.player_room_ids()[placement.stranger_id.0]
+            );
+            assert!(state.check_normal_turn(&placement.turn).is_ok());
+            let blocks_sight = state.common.board.sight[(
+                placement.room_id,
+                state.after_turn(placement.turn.clone()).doctor_room_id,
+            )];
+            assert_eq!(
+                placement.reasons.contains(&PlacementReason::BlocksSight),
+                blocks_sight
+            );
+        }
+        let rooms = placements
+            .iter()
+            .map(|placement| (placement.stranger_id, placement.room_id))
+            .collect::<HashSet<_>>();
+        assert_eq!(rooms.len(), placements.len());
+    }
+
+    #[test]
+    fn refuses_sides_without_strangers() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny should be available");
+        let state = MutableGameState::at_start(
+            CommonGameState::from_num_normal_players(false, board, 2)
+                .with_stranger_counts(
+                    crate::core::seat_map::StrangerCounts::new(1, 0).expect("counts are in range"),
+                )
+                .expect("stranger counts should apply"),
+        );
+        let err = suggest_stranger_placements(
+            &state,
+            Side::B,
+            1,
+            MAX_PLACEMENTS,
+            &SearchConfig::default(),
+            &NeverCancelToken,
+            &mut 0,
+        )
+        .expect_err("side B has no strangers");
+        assert!(err.contains("no strangers"));
+    }
+}
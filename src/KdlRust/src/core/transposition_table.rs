@@ -0,0 +1,218 @@
+use crate::core::simple_turn::SimpleTurn;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+
+// how an appraisal relates to the position's real value; searches with a narrowed window only
+// learn a bound
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Debug)]
+pub struct TableEntry {
+    pub key: u64,
+    pub analysis_level: i32,
+    pub appraisal: f64,
+    pub bound: Bound,
+    pub best_turn: SimpleTurn,
+    generation: u32,
+}
+
+impl TableEntry {
+    pub fn new(
+        key: u64,
+        analysis_level: i32,
+        appraisal: f64,
+        bound: Bound,
+        best_turn: SimpleTurn,
+    ) -> Self {
+        Self {
+            key,
+            analysis_level,
+            appraisal,
+            bound,
+            best_turn,
+            generation: 0,
+        }
+    }
+
+    // whether the appraisal settles a search of this window without looking at the children
+    pub fn settles(&self, alpha: f64, beta: f64) -> bool {
+        match self.bound {
+            Bound::Exact => true,
+            Bound::Lower => self.appraisal >= beta,
+            Bound::Upper => self.appraisal <= alpha,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStats {
+    pub num_entries: usize,
+    pub max_entries: usize,
+    pub num_probes: usize,
+    pub num_hits: usize,
+}
+
+// search results by position that outlive a single search, so analysis after the opponent's reply
+// starts from what the previous turn's search already learned. max_entries slots are indexed by
+// key; within one search a deeper result keeps its slot, and anything from an earlier search can be
+// overwritten. the slots are allocated on the first store
+#[derive(Debug)]
+pub struct TranspositionTable {
+    slots: Vec<Option<TableEntry>>,
+    max_entries: usize,
+    generation: u32,
+    stats: TableStats,
+}
+
+pub type SharedTranspositionTable = Arc<Mutex<TranspositionTable>>;
+
+impl TranspositionTable {
+    // about 4 MB of slots with typical turns
+    pub const DEFAULT_MAX_ENTRIES: usize = 1 << 16;
+
+    pub fn new(max_entries: usize) -> Self {
+        let max_entries = max_entries.max(1);
+        Self {
+            slots: Vec::new(),
+            max_entries,
+            generation: 0,
+            stats: TableStats {
+                max_entries,
+                ..TableStats::default()
+            },
+        }
+    }
+
+    pub fn shared(max_entries: usize) -> SharedTranspositionTable {
+        Arc::new(Mutex::new(Self::new(max_entries)))
+    }
+
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    pub fn stats(&self) -> TableStats {
+        self.stats
+    }
+
+    pub fn start_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    pub fn probe(&mut self, key: u64) -> Option<&TableEntry> {
+        self.stats.num_probes += 1;
+        let slot_idx = self.slot_idx(key);
+        let entry = self.slots.get(slot_idx)?.as_ref()?;
+        if entry.key != key {
+            return None;
+        }
+        self.stats.num_hits += 1;
+        Some(entry)
+    }
+
+    pub fn store(&mut self, mut entry: TableEntry) {
+        if self.slots.is_empty() {
+            self.slots = vec![None; self.max_entries];
+        }
+        entry.generation = self.generation;
+        let slot_idx = self.slot_idx(entry.key);
+        let slot = &mut self.slots[slot_idx];
+        match slot {
+            Some(old)
+                if old.generation == entry.generation
+                    && old.key != entry.key
+                    && old.analysis_level > entry.analysis_level => {}
+            Some(_) => *slot = Some(entry),
+            None => {
+                *slot = Some(entry);
+                self.stats.num_entries += 1;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots = Vec::new();
+        self.stats = TableStats {
+            max_entries: self.max_entries,
+            ..TableStats::default()
+        };
+    }
+
+    fn slot_idx(&self, key: u64) -> usize {
+        (key % self.max_entries as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, analysis_level: i32, appraisal: f64) -> TableEntry {
+        TableEntry::new(
+            key,
+            analysis_level,
+            appraisal,
+            Bound::Exact,
+            SimpleTurn::default(),
+        )
+    }
+
+    #[test]
+    fn keeps_deeper_entries_within_a_search_and_stays_bounded() {
+        let mut table = TranspositionTable::new(4);
+        assert!(table.probe(1).is_none());
+
+        table.start_search();
+        table.store(entry(1, 3, 0.5));
+        table.store(entry(5, 1, -0.5)); // same slot, shallower
+        assert_eq!(table.probe(1).map(|found| found.appraisal), Some(0.5));
+        assert!(table.probe(5).is_none());
+
+        table.store(entry(1, 1, 0.25)); // same key always refreshes
+        assert_eq!(table.probe(1).map(|found| found.analysis_level), Some(1));
+
+        table.start_search();
+        table.store(entry(1, 4, 1.0));
+        table.start_search();
+        table.store(entry(5, 1, -0.5)); // an earlier search's entry gives way
+        assert!(table.probe(1).is_none());
+        assert!(table.probe(5).is_some());
+
+        for key in 0..100 {
+            table.store(entry(key, 1, 0.0));
+        }
+        let stats = table.stats();
+        assert_eq!(stats.num_entries, 4);
+        assert_eq!(stats.max_entries, 4);
+        assert!(stats.num_hits <= stats.num_probes);
+
+        table.clear();
+        assert!(table.probe(5).is_none());
+        assert_eq!(table.stats().num_entries, 0);
+        assert_eq!(table.stats().num_probes, 1);
+    }
+
+    #[test]
+    fn bounds_settle_only_the_windows_they_cover() {
+        let lower = TableEntry {
+            bound: Bound::Lower,
+            ..entry(1, 1, 0.5)
+        };
+        assert!(lower.settles(-1.0, 0.5));
+        assert!(!lower.settles(-1.0, 1.0));
+
+        let upper = TableEntry {
+            bound: Bound::Upper,
+            ..entry(1, 1, -0.5)
+        };
+        assert!(upper.settles(-0.5, 1.0));
+        assert!(!upper.settles(-1.0, 1.0));
+        assert!(entry(1, 1, 0.0).settles(-1.0, 1.0));
+    }
+}
@@ -1,28 +1,295 @@
+use crate::core::board::{Board, RoomSymmetry};
+use crate::core::endgame_solver::{EndgameSolver, SolvedOutcome};
+use crate::core::evaluation::EvaluationWeights;
 use crate::core::mutable_game_state::MutableGameState;
-use crate::core::player::AppraisedPlayerTurn;
+use crate::core::player::{AppraisedPlayerTurn, PlayerId};
 use crate::core::rule_helper;
+use crate::core::simple_turn::SimpleTurn;
+use crate::core::transposition_table::{
+    Bound, SharedTranspositionTable, TableEntry, TranspositionTable,
+};
 use crate::util::cancellation::CancellationToken;
+use serde::Serialize;
 use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, MutexGuard};
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchConfig {
+    pub endgame_solver: Option<EndgameSolver>,
+    pub prune_turns: bool,
+    pub evaluation_weights: Option<EvaluationWeights>,
+    pub aspiration_window: Option<AspirationWindow>, // only used when deepening one level at a time
+    pub pass_move_pruning: bool,
+    pub late_move_reductions: bool,
+    // shared with clones of the config, so it outlives the search; None searches without one
+    pub transposition_table: Option<SharedTranspositionTable>,
+    pub root_turns: Option<RootTurns>,
+    pub root_children: Option<Arc<RootChildren>>,
+}
+
+// searchmoves: which turns the root may choose from, for one position. excluded turns are compared
+// by their effective turns, so a piece "moving" to its own room doesn't hide a match
+#[derive(Clone, Debug, PartialEq)]
+pub struct RootTurns {
+    root_turn_id: i32,
+    root_key: u64,
+    only: Vec<SimpleTurn>, // empty means every turn not excluded
+    excluded: HashSet<SimpleTurn>,
+}
+
+impl RootTurns {
+    pub fn new(
+        state: &MutableGameState,
+        only: impl IntoIterator<Item = SimpleTurn>,
+        excluded: impl IntoIterator<Item = SimpleTurn>,
+    ) -> Result<Self, String> {
+        let only = only.into_iter().collect::<Vec<_>>();
+        let excluded = excluded.into_iter().collect::<Vec<_>>();
+        for turn in only.iter().chain(&excluded) {
+            state
+                .check_normal_turn(turn)
+                .map_err(|err| format!("turn '{turn}' is not legal here: {err}"))?;
+        }
+        let root_turns = Self {
+            root_turn_id: state.turn_id,
+            root_key: table_key(state),
+            only,
+            excluded: excluded
+                .iter()
+                .map(|turn| state.effective_turn(turn))
+                .collect::<HashSet<_>>(),
+        };
+        if root_turns
+            .restrict(state, state.possible_turns())
+            .is_empty()
+        {
+            return Err("every turn is excluded, so there is nothing to search".to_string());
+        }
+        Ok(root_turns)
+    }
+
+    fn applies_to(&self, state: &MutableGameState) -> bool {
+        state.turn_id == self.root_turn_id && table_key(state) == self.root_key
+    }
+
+    // only's turns are searched even when turn pruning would skip them
+    fn restrict(&self, state: &MutableGameState, turns: Vec<SimpleTurn>) -> Vec<SimpleTurn> {
+        let turns = if self.only.is_empty() {
+            turns
+        } else {
+            self.only.clone()
+        };
+        turns
+            .into_iter()
+            .filter(|turn| !self.excluded.contains(&state.effective_turn(turn)))
+            .collect::<Vec<_>>()
+    }
+}
+
+// children of one position already computed elsewhere, like a ui's previews, which the search
+// takes in place of applying the same turns again at that root. keyed by effective turn
+#[derive(Clone, Debug)]
+pub struct RootChildren {
+    root_turn_id: i32,
+    root_key: u64,
+    children: HashMap<SimpleTurn, MutableGameState>,
+}
+
+impl RootChildren {
+    pub fn new(
+        state: &MutableGameState,
+        children: impl IntoIterator<Item = (SimpleTurn, MutableGameState)>,
+    ) -> Self {
+        Self {
+            root_turn_id: state.turn_id,
+            root_key: table_key(state),
+            children: children
+                .into_iter()
+                .map(|(turn, child_state)| (state.effective_turn(&turn), child_state))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn applies_to(&self, state: &MutableGameState) -> bool {
+        state.turn_id == self.root_turn_id && table_key(state) == self.root_key
+    }
+}
+
+// when deepening, each level is first searched with a root window of initial_width on either side
+// of the previous level's appraisal; a result on or past a bound is re-searched with that bound
+// pushed out by a width that grows by growth_factor, and with the full window once the width
+// passes max_width
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AspirationWindow {
+    pub initial_width: f64,
+    pub growth_factor: f64,
+    pub max_width: f64,
+}
+
+impl Default for AspirationWindow {
+    // levels alternate by about one point at the start of a game, so start a bit narrower
+    fn default() -> Self {
+        Self {
+            initial_width: 0.25,
+            growth_factor: 4.0,
+            max_width: 4.0,
+        }
+    }
+}
+
+impl AspirationWindow {
+    pub fn new(initial_width: f64, growth_factor: f64) -> Result<Self, String> {
+        if !initial_width.is_finite() || initial_width <= 0.0 {
+            return Err(format!(
+                "aspiration width must be a positive number, not {initial_width}"
+            ));
+        }
+        if !growth_factor.is_finite() || growth_factor <= 1.0 {
+            return Err(format!(
+                "aspiration growth factor must be above 1, not {growth_factor}"
+            ));
+        }
+        Ok(Self {
+            initial_width,
+            growth_factor,
+            max_width: initial_width.max(Self::default().max_width),
+        })
+    }
+}
+
+// what one deepening step cost, so a narrower window can be compared against the full one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchStats {
+    pub num_states_visited: usize,
+    pub used_aspiration_window: bool,
+    pub fail_lows: usize,
+    pub fail_highs: usize,
+    pub re_search_states_visited: usize, // included in num_states_visited
+}
+
+impl SearchStats {
+    pub fn num_re_searches(&self) -> usize {
+        self.fail_lows + self.fail_highs
+    }
+}
+
+impl SearchConfig {
+    pub fn appraise(&self, state: &MutableGameState, analysis_player_id: PlayerId) -> f64 {
+        match self.evaluation_weights.as_ref() {
+            Some(evaluation_weights) => evaluation_weights.evaluate(state, analysis_player_id),
+            None => state.heuristic_score(analysis_player_id),
+        }
+    }
+
+    pub fn with_transposition_table(mut self, max_entries: usize) -> Self {
+        self.transposition_table = Some(TranspositionTable::shared(max_entries));
+        self
+    }
+
+    fn is_restricted_root(&self, state: &MutableGameState) -> bool {
+        self.root_turns
+            .as_ref()
+            .is_some_and(|root_turns| root_turns.applies_to(state))
+    }
+
+    fn searched_turns(&self, state: &MutableGameState) -> Vec<SimpleTurn> {
+        let turns = if self.prune_turns {
+            state.pruned_possible_turns()
+        } else {
+            state.possible_turns()
+        };
+        match self.root_turns.as_ref() {
+            Some(root_turns) if root_turns.applies_to(state) => root_turns.restrict(state, turns),
+            _ => turns,
+        }
+    }
+
+    fn child_state(&self, state: &MutableGameState, turn: SimpleTurn) -> MutableGameState {
+        let known_child = self
+            .root_children
+            .as_ref()
+            .filter(|root_children| root_children.applies_to(state))
+            .and_then(|root_children| root_children.children.get(&state.effective_turn(&turn)));
+        match known_child {
+            Some(child_state) => child_state.clone(),
+            None => state.after_turn_without_memory(turn),
+        }
+    }
+
+    fn start_table_search(&self) {
+        if let Some(mut table) = self.locked_table() {
+            table.start_search();
+        }
+    }
+
+    fn table_entry(&self, key: u64) -> Option<TableEntry> {
+        self.locked_table()?.probe(key).cloned()
+    }
+
+    fn store_table_entry(&self, entry: TableEntry) {
+        if let Some(mut table) = self.locked_table() {
+            table.store(entry);
+        }
+    }
+
+    fn locked_table(&self) -> Option<MutexGuard<'_, TranspositionTable>> {
+        self.transposition_table.as_ref()?.lock().ok()
+    }
+}
 
 pub struct TreeSearch;
 
 impl TreeSearch {
     pub const ALPHA_INITIAL: f64 = rule_helper::HEURISTIC_SCORE_LOSS;
     pub const BETA_INITIAL: f64 = rule_helper::HEURISTIC_SCORE_WIN;
+    pub const PASS_MOVE_REDUCTION: i32 = 2;
+    // with late_move_reductions, children after the first few that also trail the best child's
+    // static appraisal by the margin get searched a level shallower, and again at full depth only
+    // if that still raises alpha
+    pub const LATE_MOVE_MIN_LEVEL: i32 = 3;
+    pub const LATE_MOVE_FULL_DEPTH_CHILDREN: usize = 3;
+    pub const LATE_MOVE_STATIC_MARGIN: f64 = 0.5;
 
     pub fn find_best_turn(
         state: &MutableGameState,
         analysis_level: i32,
         cancellation_token: &impl CancellationToken,
         num_states_visited: &mut usize,
+    ) -> AppraisedPlayerTurn {
+        Self::find_best_turn_with_config(
+            state,
+            analysis_level,
+            &SearchConfig::default(),
+            cancellation_token,
+            num_states_visited,
+        )
+    }
+
+    pub fn find_best_turn_with_config(
+        state: &MutableGameState,
+        analysis_level: i32,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
     ) -> AppraisedPlayerTurn {
         *num_states_visited = 0;
+        search_config.start_table_search();
 
         if state.num_players() == 2 {
             Self::find_best_turn_two_players(
                 state,
                 analysis_level,
+                search_config,
                 cancellation_token,
                 num_states_visited,
                 Self::ALPHA_INITIAL,
@@ -33,9 +300,179 @@ impl TreeSearch {
         }
     }
 
+    // one deepening step: with an aspiration window configured and a finite appraisal from the
+    // previous level, the root starts with a narrow window around it
+    pub fn find_best_turn_deepening(
+        state: &MutableGameState,
+        analysis_level: i32,
+        prev_appraisal: Option<f64>,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+        search_stats: &mut SearchStats,
+    ) -> AppraisedPlayerTurn {
+        *search_stats = SearchStats::default();
+        let window = search_config
+            .aspiration_window
+            .zip(prev_appraisal.filter(|appraisal| {
+                appraisal.is_finite() && appraisal.abs() < rule_helper::HEURISTIC_SCORE_WIN
+            }));
+        let Some((window, center)) = window.filter(|_| state.num_players() == 2) else {
+            return Self::find_best_turn_with_config(
+                state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                &mut search_stats.num_states_visited,
+            );
+        };
+
+        search_stats.used_aspiration_window = true;
+        search_config.start_table_search();
+        let mut low_width = window.initial_width;
+        let mut high_width = window.initial_width;
+        loop {
+            let alpha = if low_width > window.max_width {
+                Self::ALPHA_INITIAL
+            } else {
+                center - low_width
+            };
+            let beta = if high_width > window.max_width {
+                Self::BETA_INITIAL
+            } else {
+                center + high_width
+            };
+            let mut num_states_visited = 0usize;
+            let best_turn = Self::find_best_turn_two_players(
+                state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                &mut num_states_visited,
+                alpha,
+                beta,
+            );
+            if search_stats.num_re_searches() > 0 {
+                search_stats.re_search_states_visited += num_states_visited;
+            }
+            search_stats.num_states_visited += num_states_visited;
+
+            if cancellation_token.is_cancellation_requested() {
+                return best_turn;
+            }
+            if best_turn.appraisal <= alpha && alpha > Self::ALPHA_INITIAL {
+                search_stats.fail_lows += 1;
+                low_width *= window.growth_factor;
+            } else if best_turn.appraisal >= beta && beta < Self::BETA_INITIAL {
+                search_stats.fail_highs += 1;
+                high_width *= window.growth_factor;
+            } else {
+                return best_turn;
+            }
+        }
+    }
+
+    // top num_turns root turns, best first; alpha at the root is the current num_turns-th score, so
+    // a turn only gets an exact appraisal when it could still make the list
+    pub fn find_best_turns_with_config(
+        state: &MutableGameState,
+        analysis_level: i32,
+        num_turns: usize,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
+    ) -> Vec<AppraisedPlayerTurn> {
+        if state.is_game_over() || analysis_level == 0 || num_turns <= 1 {
+            let best_turn = Self::find_best_turn_with_config(
+                state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                num_states_visited,
+            );
+            return if num_turns == 0 {
+                Vec::new()
+            } else {
+                vec![best_turn]
+            };
+        }
+        if state.num_players() != 2 {
+            panic!("TreeSearch only supports 2 player games");
+        }
+
+        *num_states_visited = 1;
+        search_config.start_table_search();
+        let curr_player_id = state.current_player_id;
+        let possible_turns = search_config.searched_turns(state);
+        let mut scored_states = possible_turns
+            .into_iter()
+            .map(|turn| {
+                let child_state = search_config.child_state(state, turn);
+                (
+                    search_config.appraise(&child_state, curr_player_id),
+                    child_state,
+                )
+            })
+            .collect::<Vec<_>>();
+        scored_states
+            .sort_by(|(score_a, _), (score_b, _)| compare_scores(*score_a, *score_b, false));
+
+        // num_turns comes from callers like the web ui, so it can't size the list unchecked
+        let num_turns = num_turns.min(scored_states.len());
+        let mut best_turns = Vec::<AppraisedPlayerTurn>::with_capacity(num_turns + 1);
+        for (static_score, child_state) in scored_states {
+            if cancellation_token.is_cancellation_requested() {
+                break;
+            }
+            let alpha = if best_turns.len() < num_turns {
+                Self::ALPHA_INITIAL
+            } else {
+                best_turns[num_turns - 1].appraisal
+            };
+            let beta = Self::BETA_INITIAL;
+            let child_is_us = curr_player_id == child_state.current_player_id;
+            let child_alpha = if child_is_us { alpha } else { -beta };
+            let child_beta = if child_is_us { beta } else { -alpha };
+            let mut hypo_turn = Self::cached_leaf_turn(
+                &child_state,
+                analysis_level - 1,
+                curr_player_id,
+                static_score,
+                search_config,
+                num_states_visited,
+            )
+            .unwrap_or_else(|| {
+                let mut hypo_turn = Self::find_best_turn_two_players(
+                    &child_state,
+                    analysis_level - 1,
+                    search_config,
+                    cancellation_token,
+                    num_states_visited,
+                    child_alpha,
+                    child_beta,
+                );
+                if !child_is_us {
+                    hypo_turn.appraisal *= -1.0;
+                }
+                hypo_turn
+            });
+
+            if best_turns.len() < num_turns || hypo_turn.appraisal > alpha {
+                hypo_turn.turn = child_state.prev_turn;
+                let insert_idx = best_turns
+                    .iter()
+                    .position(|ranked| ranked.appraisal < hypo_turn.appraisal)
+                    .unwrap_or(best_turns.len());
+                best_turns.insert(insert_idx, hypo_turn);
+                best_turns.truncate(num_turns);
+            }
+        }
+        best_turns
+    }
+
     fn find_best_turn_two_players(
         curr_state: &MutableGameState,
         analysis_level: i32,
+        search_config: &SearchConfig,
         cancellation_token: &impl CancellationToken,
         num_states_visited: &mut usize,
         alpha: f64,
@@ -43,44 +480,130 @@ impl TreeSearch {
     ) -> AppraisedPlayerTurn {
         *num_states_visited += 1;
 
-        if curr_state.has_winner() || analysis_level == 0 {
-            return AppraisedPlayerTurn::from_state(curr_state, curr_state.current_player_id);
+        if curr_state.is_game_over() || analysis_level == 0 {
+            if let Some(endgame_solver) = search_config.endgame_solver.as_ref()
+                && !curr_state.is_game_over()
+                && endgame_solver.is_bounded(curr_state)
+            {
+                let appraisal = match endgame_solver.solve(
+                    curr_state,
+                    cancellation_token,
+                    num_states_visited,
+                ) {
+                    SolvedOutcome::Win => Some(rule_helper::HEURISTIC_SCORE_WIN),
+                    SolvedOutcome::Loss => Some(rule_helper::HEURISTIC_SCORE_LOSS),
+                    SolvedOutcome::Unknown => None,
+                };
+                if let Some(appraisal) = appraisal {
+                    return AppraisedPlayerTurn::new(appraisal, curr_state.prev_turn.clone());
+                }
+            }
+            return AppraisedPlayerTurn::new(
+                search_config.appraise(curr_state, curr_state.current_player_id),
+                curr_state.prev_turn.clone(),
+            );
+        }
+
+        // a restricted root's result isn't the position's, so it stays out of the table
+        let is_restricted_root = search_config.is_restricted_root(curr_state);
+        let table_key = search_config
+            .transposition_table
+            .as_ref()
+            .filter(|_| !is_restricted_root)
+            .map(|_| canonical_table_key(curr_state));
+        // only for cutoffs: ordering children by a stored turn visits more states, since a
+        // shallower search's favorite is often wrong one level deeper
+        if let Some((key, symmetry)) = table_key
+            && let Some(entry) = search_config.table_entry(key)
+            && entry.analysis_level >= analysis_level
+            && entry.settles(alpha, beta)
+        {
+            let best_turn = match symmetry {
+                Some(symmetry) => entry.best_turn.relabeled(&symmetry.inverse()),
+                None => entry.best_turn,
+            };
+            return AppraisedPlayerTurn::new(entry.appraisal, best_turn);
         }
 
         let curr_player_id = curr_state.current_player_id;
-        let possible_turns = curr_state.possible_turns();
+        if search_config.pass_move_pruning
+            && !is_restricted_root
+            && analysis_level > Self::PASS_MOVE_REDUCTION
+            && beta < Self::BETA_INITIAL
+            && Self::is_pass_move_safe(curr_state)
+            && let Some(pass_turn) = Self::pass_move_cutoff(
+                curr_state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                num_states_visited,
+                alpha,
+                beta,
+            )
+        {
+            return pass_turn;
+        }
+        let possible_turns = search_config.searched_turns(curr_state);
 
         let mut best_turn = AppraisedPlayerTurn::empty_minimum();
+        let window_alpha = alpha;
         let mut alpha = alpha;
         let beta = beta;
 
         if analysis_level > 1 {
             let mut scored_states = Vec::with_capacity(possible_turns.len());
             for turn in possible_turns {
-                let child_state = curr_state.after_turn_without_memory(turn);
-                let score = child_state.heuristic_score(curr_player_id);
+                let child_state = search_config.child_state(curr_state, turn);
+                let score = search_config.appraise(&child_state, curr_player_id);
                 scored_states.push((score, child_state));
             }
             scored_states
                 .sort_by(|(score_a, _), (score_b, _)| compare_scores(*score_a, *score_b, false));
-            for (_, child_state) in scored_states {
+            let best_static_score = scored_states.first().map_or(0.0, |(score, _)| *score);
+            for (child_idx, (static_score, child_state)) in scored_states.into_iter().enumerate() {
                 if cancellation_token.is_cancellation_requested() {
                     break;
                 }
                 let child_is_us = curr_player_id == child_state.current_player_id;
                 let child_alpha = if child_is_us { alpha } else { -beta };
                 let child_beta = if child_is_us { beta } else { -alpha };
-                let mut hypo_turn = Self::find_best_turn_two_players(
-                    &child_state,
-                    analysis_level - 1,
-                    cancellation_token,
-                    num_states_visited,
-                    child_alpha,
-                    child_beta,
-                );
+                let search_child = |child_level: i32, num_states_visited: &mut usize| {
+                    if let Some(leaf_turn) = Self::cached_leaf_turn(
+                        &child_state,
+                        child_level,
+                        curr_player_id,
+                        static_score,
+                        search_config,
+                        num_states_visited,
+                    ) {
+                        return leaf_turn;
+                    }
+                    let mut hypo_turn = Self::find_best_turn_two_players(
+                        &child_state,
+                        child_level,
+                        search_config,
+                        cancellation_token,
+                        num_states_visited,
+                        child_alpha,
+                        child_beta,
+                    );
+                    if !child_is_us {
+                        hypo_turn.appraisal *= -1.0;
+                    }
+                    hypo_turn
+                };
 
-                if !child_is_us {
-                    hypo_turn.appraisal *= -1.0;
+                let is_late_move = search_config.late_move_reductions
+                    && analysis_level >= Self::LATE_MOVE_MIN_LEVEL
+                    && child_idx >= Self::LATE_MOVE_FULL_DEPTH_CHILDREN
+                    && static_score < best_static_score - Self::LATE_MOVE_STATIC_MARGIN;
+                let mut hypo_turn = if is_late_move {
+                    search_child(analysis_level - 2, num_states_visited)
+                } else {
+                    search_child(analysis_level - 1, num_states_visited)
+                };
+                if is_late_move && hypo_turn.appraisal > alpha {
+                    hypo_turn = search_child(analysis_level - 1, num_states_visited);
                 }
 
                 if best_turn.appraisal < hypo_turn.appraisal {
@@ -101,13 +624,14 @@ impl TreeSearch {
                 if cancellation_token.is_cancellation_requested() {
                     break;
                 }
-                let child_state = curr_state.after_turn_without_memory(turn);
+                let child_state = search_config.child_state(curr_state, turn);
                 let child_is_us = curr_player_id == child_state.current_player_id;
                 let child_alpha = if child_is_us { alpha } else { -beta };
                 let child_beta = if child_is_us { beta } else { -alpha };
                 let mut hypo_turn = Self::find_best_turn_two_players(
                     &child_state,
                     analysis_level - 1,
+                    search_config,
                     cancellation_token,
                     num_states_visited,
                     child_alpha,
@@ -133,15 +657,329 @@ impl TreeSearch {
             }
         }
 
+        if let Some((key, symmetry)) = table_key
+            && best_turn.appraisal.is_finite()
+            && !cancellation_token.is_cancellation_requested()
+        {
+            let bound = if best_turn.appraisal <= window_alpha {
+                Bound::Upper
+            } else if best_turn.appraisal >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            // stored in the representative's rooms, so a symmetric position can map it back
+            let stored_turn = match symmetry {
+                Some(symmetry) => best_turn.turn.relabeled(symmetry),
+                None => best_turn.turn.clone(),
+            };
+            search_config.store_table_entry(TableEntry::new(
+                key,
+                analysis_level,
+                best_turn.appraisal,
+                bound,
+                stored_turn,
+            ));
+        }
         best_turn
     }
 
+    // a child searched at child_level that would be scored as a leaf already has that score from
+    // move ordering, so it is counted as visited but not appraised again. the heuristic scores the
+    // opponent's view as the exact negation of ours, but tuned weights might not, so then only
+    // children where we are still to move qualify; the endgame solver may replace a leaf's score,
+    // so with it on only finished games do
+    fn cached_leaf_turn(
+        child_state: &MutableGameState,
+        child_level: i32,
+        parent_player_id: PlayerId,
+        static_score: f64,
+        search_config: &SearchConfig,
+        num_states_visited: &mut usize,
+    ) -> Option<AppraisedPlayerTurn> {
+        let is_leaf = child_state.is_game_over()
+            || (child_level == 0 && search_config.endgame_solver.is_none());
+        let child_player_id = child_state.current_player_id;
+        let same_score = child_player_id == parent_player_id
+            || (search_config.evaluation_weights.is_none()
+                && child_player_id
+                    == child_state
+                        .common
+                        .seat_map()
+                        .opposing_normal_player(parent_player_id));
+        if !is_leaf || !same_score {
+            return None;
+        }
+        *num_states_visited += 1;
+        Some(AppraisedPlayerTurn::new(
+            static_score,
+            child_state.prev_turn.clone(),
+        ))
+    }
+
+    // kdl's stand-in for a null move: staying put is always legal and rarely the best turn, so if
+    // it already reaches beta when searched PASS_MOVE_REDUCTION levels shallower, the node is cut
+    // with that score. like a null move this is a guess, wrong when every real turn is worse than
+    // staying; see is_pass_move_safe for the positions it's kept out of
+    fn pass_move_cutoff(
+        curr_state: &MutableGameState,
+        analysis_level: i32,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+        num_states_visited: &mut usize,
+        alpha: f64,
+        beta: f64,
+    ) -> Option<AppraisedPlayerTurn> {
+        let curr_player_id = curr_state.current_player_id;
+        let pass_turn = SimpleTurn::single(
+            curr_player_id,
+            curr_state.player_room_ids()[curr_player_id.0],
+        );
+        let child_state = curr_state.after_turn_without_memory(pass_turn);
+        let child_is_us = curr_player_id == child_state.current_player_id;
+        let child_alpha = if child_is_us { alpha } else { -beta };
+        let child_beta = if child_is_us { beta } else { -alpha };
+        let mut hypo_turn = Self::find_best_turn_two_players(
+            &child_state,
+            analysis_level - 1 - Self::PASS_MOVE_REDUCTION,
+            search_config,
+            cancellation_token,
+            num_states_visited,
+            child_alpha,
+            child_beta,
+        );
+        if !child_is_us {
+            hypo_turn.appraisal *= -1.0;
+        }
+
+        (hypo_turn.appraisal >= beta).then(|| {
+            hypo_turn.turn = child_state.prev_turn;
+            hypo_turn
+        })
+    }
+
+    // staying put is only a fair lower bound when the mover has nothing urgent: they can't reach
+    // the doctor this turn (an attack is a threat a pass would give away), no piece is in the
+    // doctor's next room (where to stand decides who the doctor activates, so passing can be the
+    // zugzwang-like worst choice), and no rule makes the mover leave their room
+    fn is_pass_move_safe(state: &MutableGameState) -> bool {
+        let player_id = state.current_player_id;
+        let board = &state.common.board;
+        let player_room_id = state.player_room_ids()[player_id.0];
+        let dist_allowed = state.player_move_cards[player_id.0].whole_cards() as i32 + 1;
+        if board.distance[(player_room_id, state.doctor_room_id)] <= dist_allowed {
+            return false;
+        }
+
+        let doctor_next_room_id = Board::next_room_id(state.doctor_room_id, 1, &board.room_ids);
+        if state.player_room_ids().contains(&doctor_next_room_id) {
+            return false;
+        }
+
+        state.activation_context().restrictions.is_empty()
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub fn find_best_turn_parallel(
+        state: &MutableGameState,
+        analysis_level: i32,
+        cancellation_token: &(impl CancellationToken + Sync),
+        num_states_visited: &mut usize,
+        parallelization: usize,
+    ) -> AppraisedPlayerTurn {
+        Self::find_best_turn_parallel_with_config(
+            state,
+            analysis_level,
+            &SearchConfig::default(),
+            cancellation_token,
+            num_states_visited,
+            parallelization,
+        )
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    pub fn find_best_turn_parallel_with_config(
+        state: &MutableGameState,
+        analysis_level: i32,
+        search_config: &SearchConfig,
+        cancellation_token: &(impl CancellationToken + Sync),
+        num_states_visited: &mut usize,
+        parallelization: usize,
+    ) -> AppraisedPlayerTurn {
+        if parallelization <= 1 || analysis_level <= 1 || state.is_game_over() {
+            return Self::find_best_turn_with_config(
+                state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                num_states_visited,
+            );
+        }
+
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(parallelization)
+            .build()
+        {
+            Ok(pool) => pool.install(|| {
+                Self::find_best_turn_two_players_parallel_prioritized(
+                    state,
+                    analysis_level,
+                    search_config,
+                    cancellation_token,
+                    num_states_visited,
+                )
+            }),
+            Err(_) => Self::find_best_turn_with_config(
+                state,
+                analysis_level,
+                search_config,
+                cancellation_token,
+                num_states_visited,
+            ),
+        }
+    }
+
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn find_best_turn_two_players_parallel_prioritized(
+        curr_state: &MutableGameState,
+        analysis_level: i32,
+        search_config: &SearchConfig,
+        cancellation_token: &(impl CancellationToken + Sync),
+        num_states_visited: &mut usize,
+    ) -> AppraisedPlayerTurn {
+        use rayon::prelude::*;
+
+        *num_states_visited = 1;
+        search_config.start_table_search();
+        let curr_player_id = curr_state.current_player_id;
+        let possible_turns = search_config.searched_turns(curr_state);
+        let mut scored_states = possible_turns
+            .into_iter()
+            .map(|turn| {
+                let child_state = search_config.child_state(curr_state, turn);
+                (
+                    search_config.appraise(&child_state, curr_player_id),
+                    child_state,
+                )
+            })
+            .collect::<Vec<_>>();
+        scored_states
+            .sort_by(|(score_a, _), (score_b, _)| compare_scores(*score_a, *score_b, false));
+
+        let appraise_child = |(static_score, child_state): &(f64, MutableGameState),
+                              alpha: f64,
+                              visited: &mut usize| {
+            if let Some(leaf_turn) = Self::cached_leaf_turn(
+                child_state,
+                analysis_level - 1,
+                curr_player_id,
+                *static_score,
+                search_config,
+                visited,
+            ) {
+                return leaf_turn.appraisal;
+            }
+            let child_is_us = curr_player_id == child_state.current_player_id;
+            let child_alpha = if child_is_us {
+                alpha
+            } else {
+                -Self::BETA_INITIAL
+            };
+            let child_beta = if child_is_us {
+                Self::BETA_INITIAL
+            } else {
+                -alpha
+            };
+            let hypo_turn = Self::find_best_turn_two_players(
+                child_state,
+                analysis_level - 1,
+                search_config,
+                cancellation_token,
+                visited,
+                child_alpha,
+                child_beta,
+            );
+            if child_is_us {
+                hypo_turn.appraisal
+            } else {
+                -hypo_turn.appraisal
+            }
+        };
+
+        let mut best_turn = AppraisedPlayerTurn::empty_minimum();
+        let Some((first_child, other_children)) = scored_states.split_first() else {
+            return best_turn;
+        };
+
+        // the most promising child sets alpha before its siblings fan out across threads
+        let first_appraisal = appraise_child(first_child, Self::ALPHA_INITIAL, num_states_visited);
+        if cancellation_token.is_cancellation_requested() {
+            return best_turn;
+        }
+        if best_turn.appraisal < first_appraisal {
+            best_turn = AppraisedPlayerTurn::new(first_appraisal, first_child.1.prev_turn.clone());
+        }
+        let alpha = best_turn.appraisal.max(Self::ALPHA_INITIAL);
+
+        let other_appraisals = other_children
+            .par_iter()
+            .map(|scored_state| {
+                if cancellation_token.is_cancellation_requested() {
+                    return (f64::NEG_INFINITY, 0);
+                }
+                let mut visited = 0;
+                let appraisal = appraise_child(scored_state, alpha, &mut visited);
+                (appraisal, visited)
+            })
+            .collect::<Vec<_>>();
+
+        for ((_, child_state), (appraisal, visited)) in other_children.iter().zip(other_appraisals)
+        {
+            *num_states_visited += visited;
+            if best_turn.appraisal < appraisal {
+                best_turn = AppraisedPlayerTurn::new(appraisal, child_state.prev_turn.clone());
+            }
+        }
+
+        best_turn
+    }
+
+    // the search doesn't record its line, so this replays it one level shallower from each position
+    // after first_turn; the result starts with first_turn and has at most analysis_level turns
+    pub fn principal_variation(
+        state: &MutableGameState,
+        first_turn: SimpleTurn,
+        analysis_level: i32,
+        search_config: &SearchConfig,
+        cancellation_token: &impl CancellationToken,
+    ) -> Vec<SimpleTurn> {
+        let mut curr_state = state.after_turn_without_memory(first_turn.clone());
+        let mut line = vec![first_turn];
+        let mut num_states_visited = 0usize;
+        for level in (1..analysis_level).rev() {
+            if curr_state.is_game_over() || cancellation_token.is_cancellation_requested() {
+                break;
+            }
+            let next_turn = Self::find_best_turn_with_config(
+                &curr_state,
+                level,
+                search_config,
+                cancellation_token,
+                &mut num_states_visited,
+            )
+            .turn;
+            curr_state = curr_state.after_turn_without_memory(next_turn.clone());
+            line.push(next_turn);
+        }
+        line
+    }
+
     pub fn find_full_control_cycles(
         begin_state: &MutableGameState,
         cancellation_token: &impl CancellationToken,
     ) -> Vec<AppraisedPlayerTurn> {
         let mut cycles = Vec::new();
-        if begin_state.has_winner() {
+        if begin_state.is_game_over() {
             return cycles;
         }
 
@@ -196,13 +1034,18 @@ impl TreeSearch {
 
             // if doctor has seemingly moved "less" than the number of turns, we have completed a loop but skipped
             // over the exact beginning dr room
-            let num_rooms_doctor_moved =
-                begin_state.doctor_moves_until_room(child_state.doctor_room_id);
+            let num_rooms_doctor_moved = begin_state
+                .doctor_moves_until_room(child_state.doctor_room_id)
+                .unwrap_or(0);
             let num_turns = child_state.turn_id - begin_state.turn_id;
 
             if child_state.doctor_room_id == begin_doctor_room_id
                 || num_rooms_doctor_moved < num_turns
-                || child_state.winner == begin_player_id
+                || child_state.result.winner().is_some_and(|winner| {
+                    child_state.common.to_normal_player_id(winner)
+                        == child_state.common.to_normal_player_id(begin_player_id)
+                })
+                || child_state.is_draw()
             {
                 end_states.insert(child_state);
                 continue;
@@ -218,6 +1061,21 @@ impl TreeSearch {
     }
 }
 
+// position_key leaves out the turn id, which the doctor escape rule looks at
+fn table_key(state: &MutableGameState) -> u64 {
+    with_turn_id(state.position_key(), state)
+}
+
+// table_key of the position's symmetry class, with the symmetry that takes the state there
+fn canonical_table_key(state: &MutableGameState) -> (u64, Option<&RoomSymmetry>) {
+    let (position_key, symmetry) = state.canonical_position_key();
+    (with_turn_id(position_key, state), symmetry)
+}
+
+fn with_turn_id(position_key: u64, state: &MutableGameState) -> u64 {
+    position_key ^ (state.turn_id as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
 fn compare_scores(a: f64, b: f64, sort_ascending: bool) -> Ordering {
     if sort_ascending {
         compare_f64(a, b)
@@ -234,8 +1092,8 @@ fn compare_f64(a: f64, b: f64) -> Ordering {
 mod tests {
     use super::*;
     use crate::core::{
-        board::Board, common_game_state::CommonGameState, mutable_game_state::MutableGameState,
-        simple_turn::SimpleTurn,
+        board::Board, card_count::CardCount, common_game_state::CommonGameState,
+        mutable_game_state::MutableGameState, room::RoomId,
     };
     use crate::util::cancellation::{AtomicCancellationToken, CancellationToken, NeverCancelToken};
 
@@ -265,6 +1123,381 @@ mod tests {
         )
     }
 
+    #[test]
+    fn transposition_table_keeps_appraisals_and_starts_the_next_turn_warm() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig::default()
+            .with_transposition_table(TranspositionTable::DEFAULT_MAX_ENTRIES);
+
+        let mut full_states = 0usize;
+        let full = TreeSearch::find_best_turn(&state, 4, &token, &mut full_states);
+        let mut cached_states = 0usize;
+        let cached = TreeSearch::find_best_turn_with_config(
+            &state,
+            4,
+            &search_config,
+            &token,
+            &mut cached_states,
+        );
+        assert_eq!(cached.appraisal, full.appraisal);
+        assert!(cached_states < full_states);
+
+        let mut repeat_states = 0usize;
+        let repeat = TreeSearch::find_best_turn_with_config(
+            &state,
+            4,
+            &search_config,
+            &token,
+            &mut repeat_states,
+        );
+        assert_eq!(repeat.appraisal, cached.appraisal);
+        assert_eq!(repeat.turn, cached.turn);
+        assert_eq!(repeat_states, 1);
+
+        // the reply position was searched two levels shallower, which settles some of its nodes
+        let line =
+            TreeSearch::principal_variation(&state, cached.turn.clone(), 4, &search_config, &token);
+        let reply_state = state
+            .after_turn_without_memory(line[0].clone())
+            .after_turn_without_memory(line[1].clone());
+        let mut warm_states = 0usize;
+        TreeSearch::find_best_turn_with_config(
+            &reply_state,
+            5,
+            &search_config,
+            &token,
+            &mut warm_states,
+        );
+        let mut cold_states = 0usize;
+        TreeSearch::find_best_turn(&reply_state, 5, &token, &mut cold_states);
+        assert!(warm_states < cold_states);
+    }
+
+    #[test]
+    fn symmetric_positions_share_table_entries() {
+        let board = Board::ring(8);
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let state = MutableGameState::at_start(common);
+        let rotation = state.common.board.symmetries[2].clone();
+        let mut rotated = state.copy_state();
+        rotated.doctor_room_id = rotation.apply(state.doctor_room_id);
+        rotated.set_player_room_ids(
+            state
+                .player_room_ids()
+                .iter()
+                .map(|room_id| rotation.apply(*room_id))
+                .collect::<Vec<_>>(),
+        );
+        assert_ne!(table_key(&state), table_key(&rotated));
+        assert_eq!(
+            canonical_table_key(&state).0,
+            canonical_table_key(&rotated).0
+        );
+
+        let config = SearchConfig::default()
+            .with_transposition_table(TranspositionTable::DEFAULT_MAX_ENTRIES);
+        let token = NeverCancelToken;
+        let mut num_states_visited = 0usize;
+        let best = TreeSearch::find_best_turn_with_config(
+            &state,
+            3,
+            &config,
+            &token,
+            &mut num_states_visited,
+        );
+        let num_states_unrotated = num_states_visited;
+        let rotated_best = TreeSearch::find_best_turn_with_config(
+            &rotated,
+            3,
+            &config,
+            &token,
+            &mut num_states_visited,
+        );
+        // the rotated root is answered from the table, in its own rooms
+        assert!(num_states_visited < num_states_unrotated);
+        assert_eq!(rotated_best.appraisal, best.appraisal);
+        assert_eq!(rotated_best.turn, best.turn.relabeled(&rotation));
+        assert!(rotated.check_normal_turn(&rotated_best.turn).is_ok());
+    }
+
+    #[test]
+    fn root_children_stand_in_for_applying_their_turns() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let mut num_states_visited = 0usize;
+        let best = TreeSearch::find_best_turn(&state, 2, &token, &mut num_states_visited);
+
+        let known_config = SearchConfig {
+            root_children: Some(Arc::new(RootChildren::new(
+                &state,
+                state
+                    .possible_turns()
+                    .into_iter()
+                    .map(|turn| (turn.clone(), state.after_turn(turn))),
+            ))),
+            ..SearchConfig::default()
+        };
+        let known = TreeSearch::find_best_turn_with_config(
+            &state,
+            2,
+            &known_config,
+            &token,
+            &mut num_states_visited,
+        );
+        assert_eq!(known.appraisal, best.appraisal);
+        assert_eq!(
+            state.effective_turn(&known.turn),
+            state.effective_turn(&best.turn)
+        );
+
+        // every child standing in for one turn's shows the search really takes them
+        let other_turn = state
+            .possible_turns()
+            .into_iter()
+            .find(|turn| state.effective_turn(turn) != state.effective_turn(&best.turn))
+            .expect("the start should have more than one turn");
+        let other_child = state.after_turn(other_turn.clone());
+        let swapped_config = SearchConfig {
+            root_children: Some(Arc::new(RootChildren::new(
+                &state,
+                state
+                    .possible_turns()
+                    .into_iter()
+                    .map(|turn| (turn, other_child.clone())),
+            ))),
+            ..SearchConfig::default()
+        };
+        let swapped = TreeSearch::find_best_turn_with_config(
+            &state,
+            2,
+            &swapped_config,
+            &token,
+            &mut num_states_visited,
+        );
+        assert_eq!(swapped.turn, other_turn);
+        let later = state.after_turn(best.turn.clone());
+        assert!(
+            !swapped_config
+                .root_children
+                .as_ref()
+                .is_some_and(|root_children| root_children.applies_to(&later))
+        );
+    }
+
+    #[test]
+    fn root_turns_limit_which_turns_the_root_can_pick() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let mut num_states_visited = 0usize;
+        let best = TreeSearch::find_best_turn(&state, 3, &token, &mut num_states_visited);
+
+        let worst_turn = state
+            .possible_turns()
+            .into_iter()
+            .find(|turn| *turn != best.turn)
+            .expect("the start should have more than one turn");
+        let only_config = SearchConfig {
+            root_turns: Some(
+                RootTurns::new(&state, [worst_turn.clone()], []).expect("turn should be legal"),
+            ),
+            ..SearchConfig::default()
+        }
+        .with_transposition_table(TranspositionTable::DEFAULT_MAX_ENTRIES);
+        let only = TreeSearch::find_best_turn_with_config(
+            &state,
+            3,
+            &only_config,
+            &token,
+            &mut num_states_visited,
+        );
+        assert_eq!(only.turn, worst_turn);
+        // the restricted root's appraisal would mislead an unrestricted search of the same position
+        assert!(
+            only_config
+                .locked_table()
+                .expect("table should be set")
+                .probe(canonical_table_key(&state).0)
+                .is_none()
+        );
+
+        let skip_config = SearchConfig {
+            root_turns: Some(
+                RootTurns::new(&state, [], [best.turn.clone()]).expect("turn should be legal"),
+            ),
+            ..SearchConfig::default()
+        };
+        let skipped = TreeSearch::find_best_turns_with_config(
+            &state,
+            3,
+            1000,
+            &skip_config,
+            &token,
+            &mut num_states_visited,
+        );
+        assert_eq!(skipped.len(), state.possible_turns().len() - 1);
+        assert!(skipped.iter().all(|turn| turn.turn != best.turn));
+
+        // a move to the piece's own room is the same turn written differently
+        let still_id = (0..state.num_players())
+            .find(|id| best.turn.moves.iter().all(|mv| mv.player_id.0 != *id))
+            .expect("a one-card turn leaves some piece still");
+        let padded = format!(
+            "{}@{} {}",
+            still_id + 1,
+            state.player_room_ids()[still_id].0,
+            best.turn
+        )
+        .parse::<SimpleTurn>()
+        .expect("padded turn should parse");
+        let root_turns = RootTurns::new(&state, [], [padded]).expect("turn should be legal");
+        assert!(
+            root_turns
+                .restrict(&state, vec![best.turn.clone()])
+                .is_empty()
+        );
+
+        let everything = state.possible_turns();
+        assert!(RootTurns::new(&state, [], everything).is_err());
+    }
+
+    #[test]
+    fn late_move_reductions_visit_fewer_states() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            late_move_reductions: true,
+            ..SearchConfig::default()
+        };
+
+        let mut full_states = 0usize;
+        let full = TreeSearch::find_best_turn(&state, 4, &token, &mut full_states);
+        let mut reduced_states = 0usize;
+        let reduced = TreeSearch::find_best_turn_with_config(
+            &state,
+            4,
+            &search_config,
+            &token,
+            &mut reduced_states,
+        );
+        assert!(reduced_states < full_states);
+        assert_eq!(reduced.appraisal, full.appraisal);
+        assert_eq!(reduced.turn, full.turn);
+    }
+
+    #[test]
+    fn pass_move_pruning_visits_fewer_states() {
+        // everyone starts out of the doctor's reach on LairFull, so passes get tried just below the root
+        let board = Board::from_embedded_json("LairFull").expect("LairFull should be available");
+        let state =
+            MutableGameState::at_start(CommonGameState::from_num_normal_players(true, board, 2));
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            pass_move_pruning: true,
+            ..SearchConfig::default()
+        };
+
+        let mut full_states = 0usize;
+        let full = TreeSearch::find_best_turn(&state, 4, &token, &mut full_states);
+        let mut pruned_states = 0usize;
+        let pruned = TreeSearch::find_best_turn_with_config(
+            &state,
+            4,
+            &search_config,
+            &token,
+            &mut pruned_states,
+        );
+        assert!(pruned_states < full_states);
+        assert_eq!(pruned.appraisal, full.appraisal);
+        assert!(state.check_normal_turn(&pruned.turn).is_ok());
+    }
+
+    #[test]
+    fn pass_moves_are_kept_out_of_attack_and_activation_positions() {
+        let mut state = alt_down_two_player_start();
+        let room_ids = state.common.board.room_ids.clone();
+        let doctor_next_room_id = Board::next_room_id(state.doctor_room_id, 1, &room_ids);
+        let far_room_id = *room_ids
+            .iter()
+            .filter(|room_id| **room_id != doctor_next_room_id)
+            .max_by_key(|room_id| state.common.board.distance[(**room_id, state.doctor_room_id)])
+            .expect("board should have rooms");
+        assert_ne!(far_room_id, doctor_next_room_id);
+        assert_ne!(far_room_id, state.doctor_room_id);
+        let quiet_room_id = *room_ids
+            .iter()
+            .find(|room_id| **room_id != doctor_next_room_id && **room_id != far_room_id)
+            .expect("board should have a third room");
+        state.set_player_room_ids(vec![
+            far_room_id,
+            quiet_room_id,
+            quiet_room_id,
+            quiet_room_id,
+        ]);
+        state.player_move_cards[0] = CardCount::ZERO;
+        assert!(TreeSearch::is_pass_move_safe(&state));
+
+        let mut attacking = state.copy_state();
+        attacking.move_piece(PlayerId(0), attacking.doctor_room_id);
+        assert!(!TreeSearch::is_pass_move_safe(&attacking));
+
+        let mut activating = state.copy_state();
+        activating.move_piece(PlayerId(1), doctor_next_room_id);
+        assert!(!TreeSearch::is_pass_move_safe(&activating));
+    }
+
+    #[test]
+    fn aspiration_windows_keep_each_level_appraisal() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            aspiration_window: Some(AspirationWindow::default()),
+            ..SearchConfig::default()
+        };
+
+        let mut prev_appraisal = None;
+        for analysis_level in 1..=3 {
+            let mut full_states = 0usize;
+            let full = TreeSearch::find_best_turn(&state, analysis_level, &token, &mut full_states);
+            let mut stats = SearchStats::default();
+            let aspirated = TreeSearch::find_best_turn_deepening(
+                &state,
+                analysis_level,
+                prev_appraisal,
+                &search_config,
+                &token,
+                &mut stats,
+            );
+            assert_eq!(
+                aspirated.appraisal, full.appraisal,
+                "level {analysis_level}"
+            );
+            assert_eq!(stats.used_aspiration_window, prev_appraisal.is_some());
+            prev_appraisal = Some(aspirated.appraisal);
+        }
+
+        // a center far from the real appraisal fails low until the window covers it
+        let mut stats = SearchStats::default();
+        let aspirated = TreeSearch::find_best_turn_deepening(
+            &state,
+            2,
+            Some(50.0),
+            &search_config,
+            &token,
+            &mut stats,
+        );
+        let mut full_states = 0usize;
+        let full = TreeSearch::find_best_turn(&state, 2, &token, &mut full_states);
+        assert_eq!(aspirated.appraisal, full.appraisal);
+        assert!(stats.fail_lows > 0);
+        assert_eq!(stats.fail_highs, 0);
+        assert!(stats.re_search_states_visited > 0);
+        assert!(stats.num_states_visited > stats.re_search_states_visited);
+
+        assert!(AspirationWindow::new(0.0, 2.0).is_err());
+        assert!(AspirationWindow::new(0.5, 1.0).is_err());
+    }
+
     #[test]
     fn tree_search_snapshot_alt_down_start_levels_0_to_3() {
         let state = alt_down_two_player_start();
@@ -306,11 +1539,199 @@ mod tests {
             concat!(
                 "L1|turn=2@14;|appraisal=-0.647815|states=189\n",
                 "L2|turn=2@14 4@5;|appraisal=+0.844624|states=858\n",
-                "L3|turn=3@1 2@14;|appraisal=+2.213941|states=1114"
+                "L3|turn=2@14 3@1;|appraisal=+2.213941|states=1114"
             )
         );
     }
 
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    #[test]
+    fn parallel_search_matches_sequential_search() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+
+        for analysis_level in 2..=3 {
+            let mut sequential_states = 0usize;
+            let sequential =
+                TreeSearch::find_best_turn(&state, analysis_level, &token, &mut sequential_states);
+            let mut parallel_states = 0usize;
+            let parallel = TreeSearch::find_best_turn_parallel(
+                &state,
+                analysis_level,
+                &token,
+                &mut parallel_states,
+                4,
+            );
+
+            assert_eq!(parallel.turn, sequential.turn);
+            assert_eq!(parallel.appraisal, sequential.appraisal);
+            assert!(parallel_states > 0);
+        }
+    }
+
+    #[test]
+    fn endgame_solver_replaces_heuristic_at_proven_leaf() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let common = CommonGameState::from_num_normal_players(true, board, 2);
+        let mut state = MutableGameState::at_start(common);
+        state.turn_id = 10;
+        state.doctor_room_id = RoomId(2);
+        state.set_player_room_ids(vec![RoomId(1), RoomId(4), RoomId(4), RoomId(4)]);
+        state.player_failures = vec![CardCount::ZERO; 4];
+        state.player_move_cards[2] = CardCount::ZERO;
+        state.player_weapons[2] = CardCount::ZERO;
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            endgame_solver: Some(EndgameSolver::default()),
+            ..SearchConfig::default()
+        };
+
+        let mut heuristic_states = 0usize;
+        let heuristic = TreeSearch::find_best_turn(&state, 0, &token, &mut heuristic_states);
+        let mut solved_states = 0usize;
+        let solved = TreeSearch::find_best_turn_with_config(
+            &state,
+            0,
+            &search_config,
+            &token,
+            &mut solved_states,
+        );
+
+        assert_ne!(heuristic.appraisal, rule_helper::HEURISTIC_SCORE_WIN);
+        assert_eq!(solved.appraisal, rule_helper::HEURISTIC_SCORE_WIN);
+        assert!(solved_states > heuristic_states);
+    }
+
+    #[test]
+    fn endgame_solver_leaves_unbounded_search_unchanged() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            endgame_solver: Some(EndgameSolver::default()),
+            ..SearchConfig::default()
+        };
+
+        let mut plain_states = 0usize;
+        let plain = TreeSearch::find_best_turn(&state, 2, &token, &mut plain_states);
+        let mut solver_states = 0usize;
+        let with_solver = TreeSearch::find_best_turn_with_config(
+            &state,
+            2,
+            &search_config,
+            &token,
+            &mut solver_states,
+        );
+
+        assert_eq!(with_solver.turn, plain.turn);
+        assert_eq!(with_solver.appraisal, plain.appraisal);
+        assert_eq!(solver_states, plain_states);
+    }
+
+    #[test]
+    fn pruned_search_visits_fewer_states() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            prune_turns: true,
+            ..SearchConfig::default()
+        };
+
+        let mut full_states = 0usize;
+        TreeSearch::find_best_turn(&state, 2, &token, &mut full_states);
+        let mut pruned_states = 0usize;
+        let pruned = TreeSearch::find_best_turn_with_config(
+            &state,
+            2,
+            &search_config,
+            &token,
+            &mut pruned_states,
+        );
+
+        assert!(pruned_states < full_states);
+        assert!(state.pruned_possible_turns().contains(&pruned.turn));
+    }
+
+    #[test]
+    fn default_evaluation_weights_match_heuristic_search() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let search_config = SearchConfig {
+            evaluation_weights: Some(EvaluationWeights::default()),
+            ..SearchConfig::default()
+        };
+
+        let mut heuristic_states = 0usize;
+        let heuristic = TreeSearch::find_best_turn(&state, 2, &token, &mut heuristic_states);
+        let mut weighted_states = 0usize;
+        let weighted = TreeSearch::find_best_turn_with_config(
+            &state,
+            2,
+            &search_config,
+            &token,
+            &mut weighted_states,
+        );
+
+        assert_eq!(weighted.turn, heuristic.turn);
+        assert!((weighted.appraisal - heuristic.appraisal).abs() < 1e-9);
+        assert_eq!(weighted_states, heuristic_states);
+    }
+
+    #[test]
+    fn cached_leaf_scores_match_a_fresh_leaf_search() {
+        let state = alt_down_two_player_start();
+        let config = SearchConfig::default();
+        let parent_player_id = state.current_player_id;
+        for turn in state.possible_turns() {
+            let child_state = state.after_turn_without_memory(turn);
+            let static_score = config.appraise(&child_state, parent_player_id);
+            let mut cached_states = 0usize;
+            let cached = TreeSearch::cached_leaf_turn(
+                &child_state,
+                0,
+                parent_player_id,
+                static_score,
+                &config,
+                &mut cached_states,
+            )
+            .expect("a level 0 child is a leaf");
+
+            let mut searched_states = 0usize;
+            let mut searched = TreeSearch::find_best_turn_two_players(
+                &child_state,
+                0,
+                &config,
+                &NeverCancelToken,
+                &mut searched_states,
+                TreeSearch::ALPHA_INITIAL,
+                TreeSearch::BETA_INITIAL,
+            );
+            if child_state.current_player_id != parent_player_id {
+                searched.appraisal *= -1.0;
+            }
+            assert_eq!(cached.appraisal, searched.appraisal);
+            assert_eq!(cached.turn, searched.turn);
+            assert_eq!(cached_states, searched_states);
+        }
+
+        let weighted_config = SearchConfig {
+            evaluation_weights: Some(EvaluationWeights::default()),
+            ..SearchConfig::default()
+        };
+        let child_state = state.after_turn_without_memory(state.possible_turns()[0].clone());
+        assert_ne!(child_state.current_player_id, parent_player_id);
+        assert!(
+            TreeSearch::cached_leaf_turn(
+                &child_state,
+                0,
+                parent_player_id,
+                0.0,
+                &weighted_config,
+                &mut 0,
+            )
+            .is_none()
+        );
+    }
+
     #[test]
     fn find_full_control_cycles_honors_cancellation() {
         let begin = alt_down_two_player_start();
@@ -321,6 +1742,89 @@ mod tests {
         assert!(cycles.is_empty());
     }
 
+    #[test]
+    fn best_turns_are_ranked_and_led_by_the_best_turn() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let config = SearchConfig::default();
+        for analysis_level in 1..=3 {
+            let mut num_states_visited = 0usize;
+            let best_turn =
+                TreeSearch::find_best_turn(&state, analysis_level, &token, &mut num_states_visited);
+            let best_turns = TreeSearch::find_best_turns_with_config(
+                &state,
+                analysis_level,
+                3,
+                &config,
+                &token,
+                &mut num_states_visited,
+            );
+
+            assert_eq!(best_turns.len(), 3);
+            assert_eq!(best_turns[0].turn, best_turn.turn);
+            assert_eq!(best_turns[0].appraisal, best_turn.appraisal);
+            assert!(
+                best_turns
+                    .windows(2)
+                    .all(|pair| pair[0].appraisal >= pair[1].appraisal)
+            );
+
+            let mut exact_appraisals = state
+                .possible_turns()
+                .into_iter()
+                .map(|turn| {
+                    let child_state = state.after_turn(turn);
+                    let child_best = TreeSearch::find_best_turn(
+                        &child_state,
+                        analysis_level - 1,
+                        &token,
+                        &mut num_states_visited,
+                    );
+                    if child_state.current_player_id == state.current_player_id {
+                        child_best.appraisal
+                    } else {
+                        -child_best.appraisal
+                    }
+                })
+                .collect::<Vec<_>>();
+            exact_appraisals.sort_by(|a, b| b.total_cmp(a));
+            assert_eq!(
+                best_turns
+                    .iter()
+                    .map(|ranked| ranked.appraisal)
+                    .collect::<Vec<_>>(),
+                exact_appraisals[..3]
+            );
+        }
+    }
+
+    #[test]
+    fn principal_variation_follows_shallower_searches() {
+        let state = alt_down_two_player_start();
+        let token = NeverCancelToken;
+        let mut num_states_visited = 0usize;
+        let best_turn = TreeSearch::find_best_turn(&state, 3, &token, &mut num_states_visited).turn;
+
+        let line = TreeSearch::principal_variation(
+            &state,
+            best_turn.clone(),
+            3,
+            &SearchConfig::default(),
+            &token,
+        );
+        let reply = TreeSearch::find_best_turn(
+            &state.after_turn(best_turn.clone()),
+            2,
+            &token,
+            &mut num_states_visited,
+        )
+        .turn;
+
+        assert_eq!(line.len(), 3);
+        assert_eq!(line[0], best_turn);
+        assert_eq!(line[1], reply);
+    }
+
     #[test]
     fn tree_search_cancelled_token_returns_empty_minimum() {
         let state = alt_down_two_player_start();
@@ -0,0 +1,175 @@
+use crate::core::{
+    board::Board,
+    evaluation::{self, EvaluationWeights, NUM_EVALUATION_FEATURES},
+    rule_helper,
+    seat_map::SeatMap,
+    self_play::{EngineConfig, SelfPlayRunner},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningSample {
+    pub features: [f64; NUM_EVALUATION_FEATURES],
+    pub outcome: f64,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[readonly::make]
+pub struct FitOptions {
+    pub iterations: usize,
+    pub learning_rate: f64,
+    pub l2_penalty: f64,
+}
+
+impl FitOptions {
+    pub fn new(iterations: usize, learning_rate: f64, l2_penalty: f64) -> Self {
+        Self {
+            iterations,
+            learning_rate,
+            l2_penalty,
+        }
+    }
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        Self::new(2000, 0.05, 1e-4)
+    }
+}
+
+// every recorded position yields a sample for both sides; drawn games score 0.5
+pub fn collect_samples(
+    runner: &SelfPlayRunner,
+    boards: impl IntoIterator<Item = Board>,
+    engines: &[EngineConfig],
+) -> Vec<TuningSample> {
+    let mut samples = Vec::new();
+    for board in boards {
+        for first_idx in 0..engines.len() {
+            for second_idx in first_idx..engines.len() {
+                for first_engine_moves_first in [true, false] {
+                    let game = runner.play_on_board(
+                        board.clone(),
+                        &engines[first_idx],
+                        &engines[second_idx],
+                        first_engine_moves_first,
+                    );
+                    let first_engine_player_id = if first_engine_moves_first {
+                        rule_helper::SIDE_A_NORMAL_PLAYER_ID
+                    } else {
+                        rule_helper::SIDE_B_NORMAL_PLAYER_ID
+                    };
+                    let second_engine_player_id =
+                        SeatMap::with_strangers().opposing_normal_player(first_engine_player_id);
+                    let first_score = game.outcome.first_engine_score();
+
+                    for state in &game.positions {
+                        samples.push(TuningSample {
+                            features: state
+                                .features_for(first_engine_player_id)
+                                .evaluation_features(),
+                            outcome: first_score,
+                        });
+                        samples.push(TuningSample {
+                            features: state
+                                .features_for(second_engine_player_id)
+                                .evaluation_features(),
+                            outcome: 1.0 - first_score,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    samples
+}
+
+// logistic regression by full-batch gradient descent; features are allied minus opposing, so no
+// bias term is needed
+pub fn fit_weights(
+    samples: &[TuningSample],
+    initial_weights: &EvaluationWeights,
+    fit_options: &FitOptions,
+) -> EvaluationWeights {
+    let mut weights = initial_weights.to_array();
+    if samples.is_empty() {
+        return EvaluationWeights::from_array(weights);
+    }
+
+    for _ in 0..fit_options.iterations {
+        let current_weights = EvaluationWeights::from_array(weights);
+        let mut gradient = [0.0; NUM_EVALUATION_FEATURES];
+        for sample in samples {
+            let prediction = evaluation::win_probability(current_weights.score(&sample.features));
+            let error = prediction - sample.outcome;
+            for (grad, feature) in gradient.iter_mut().zip(&sample.features) {
+                *grad += error * feature;
+            }
+        }
+        for (weight, grad) in weights.iter_mut().zip(&gradient) {
+            *weight -= fit_options.learning_rate
+                * (grad / samples.len() as f64 + fit_options.l2_penalty * *weight);
+        }
+    }
+
+    EvaluationWeights::from_array(weights)
+}
+
+pub fn log_loss(samples: &[TuningSample], weights: &EvaluationWeights) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    samples
+        .iter()
+        .map(|sample| {
+            let prediction = evaluation::win_probability(weights.score(&sample.features))
+                .clamp(1e-12, 1.0 - 1e-12);
+            -(sample.outcome * prediction.ln() + (1.0 - sample.outcome) * (1.0 - prediction).ln())
+        })
+        .sum::<f64>()
+        / samples.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_recovers_direction_of_separating_feature() {
+        let samples = (0..40)
+            .map(|idx| {
+                let weapons = (idx % 5) as f64 - 2.0;
+                let mut features = [0.0; NUM_EVALUATION_FEATURES];
+                features[4] = weapons;
+                TuningSample {
+                    features,
+                    outcome: if weapons > 0.0 {
+                        1.0
+                    } else if weapons < 0.0 {
+                        0.0
+                    } else {
+                        0.5
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+        let initial = EvaluationWeights::from_array([0.0; NUM_EVALUATION_FEATURES]);
+        let fitted = fit_weights(&samples, &initial, &FitOptions::new(500, 0.5, 0.0));
+
+        assert!(fitted.weapons > 1.0);
+        assert_eq!(fitted.strength, 0.0);
+        assert!(log_loss(&samples, &fitted) < log_loss(&samples, &initial));
+    }
+
+    #[test]
+    fn self_play_samples_come_in_mirrored_pairs() {
+        let board = Board::from_embedded_json("Tiny").expect("Tiny board should load");
+        let runner = SelfPlayRunner::new(20).with_recorded_positions();
+        let samples = collect_samples(&runner, [board], &[EngineConfig::from_analysis_level(1)]);
+
+        assert!(!samples.is_empty());
+        for pair in samples.chunks(2) {
+            assert_eq!(pair[0].outcome + pair[1].outcome, 1.0);
+            assert_eq!(pair[0].features[0], -pair[1].features[0]);
+        }
+    }
+}
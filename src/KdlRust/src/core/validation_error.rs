@@ -0,0 +1,311 @@
+use serde::Serialize;
+use std::fmt;
+
+// players are carried as display text (like "P1"), so a localized message can show them as-is
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "code", content = "params", rename_all = "camelCase")]
+#[serde(rename_all_fields = "camelCase")]
+pub enum ValidationError {
+    MalformedPlan {
+        detail: String,
+    },
+    PlanTooLong {
+        length: usize,
+        limit: usize,
+    },
+    TooManyMoves {
+        count: usize,
+        limit: usize,
+    },
+    AnalysisTooDeep {
+        analysis_level: i32,
+        limit: i32,
+    },
+    InvalidPlayer {
+        player_id: usize,
+        player: String,
+    },
+    InvalidRoom {
+        room_id: usize,
+    },
+    UnknownRoomName {
+        name: String,
+    },
+    AmbiguousRoomName {
+        name: String,
+        candidates: Vec<String>, // room labels that match
+    },
+    TooManyMovePoints {
+        player: String,
+        distance: i32,
+        cost: f64,   // move cards the plan needs
+        budget: f64, // move cards the player holds
+    },
+    MovedNonStranger {
+        player: String,
+        piece: String,
+    },
+    PieceMovedTwice {
+        player: String,
+        piece: String,
+    },
+    StrangerMoveNeedsMoveCards {
+        player: String,
+        piece: String, // the first stranger the plan moves
+        stranger_distance: i32,
+        cost: f64,
+        budget: f64,
+    },
+    NotPlayersTurn {
+        player: String,
+        current_player: String,
+        activated_room_id: Option<usize>,
+        activated_room_label: Option<String>,
+    },
+    ActivatedPlayerMustMove {
+        player: String,
+        room_id: usize,
+        room_label: String,
+    },
+    RoomOccupied {
+        player: String,
+        piece: String,
+        occupant: String, // a piece already in the room, or moving there too
+        room_id: usize,
+        room_label: String,
+    },
+    GameOver,
+}
+
+impl ValidationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::MalformedPlan { .. } => "malformedPlan",
+            ValidationError::PlanTooLong { .. } => "planTooLong",
+            ValidationError::TooManyMoves { .. } => "tooManyMoves",
+            ValidationError::AnalysisTooDeep { .. } => "analysisTooDeep",
+            ValidationError::InvalidPlayer { .. } => "invalidPlayer",
+            ValidationError::InvalidRoom { .. } => "invalidRoom",
+            ValidationError::UnknownRoomName { .. } => "unknownRoomName",
+            ValidationError::AmbiguousRoomName { .. } => "ambiguousRoomName",
+            ValidationError::TooManyMovePoints { .. } => "tooManyMovePoints",
+            ValidationError::MovedNonStranger { .. } => "movedNonStranger",
+            ValidationError::PieceMovedTwice { .. } => "pieceMovedTwice",
+            ValidationError::StrangerMoveNeedsMoveCards { .. } => "strangerMoveNeedsMoveCards",
+            ValidationError::NotPlayersTurn { .. } => "notPlayersTurn",
+            ValidationError::ActivatedPlayerMustMove { .. } => "activatedPlayerMustMove",
+            ValidationError::RoomOccupied { .. } => "roomOccupied",
+            ValidationError::GameOver => "gameOver",
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MalformedPlan { detail } => write!(f, "{detail}"),
+            ValidationError::PlanTooLong { length, limit } => {
+                write!(f, "turn plan is {length} characters; the limit is {limit}")
+            }
+            ValidationError::TooManyMoves { count, limit } => {
+                write!(f, "turn plan has {count} moves; the limit is {limit}")
+            }
+            ValidationError::AnalysisTooDeep {
+                analysis_level,
+                limit,
+            } => write!(
+                f,
+                "analysis level {analysis_level} is above the limit of {limit}"
+            ),
+            ValidationError::InvalidPlayer { player_id, player } => {
+                write!(f, "invalid playerId {player_id} (displayed {player})")
+            }
+            ValidationError::InvalidRoom { room_id } => write!(f, "invalid roomId {room_id}"),
+            ValidationError::UnknownRoomName { name } => write!(f, "no room named '{name}'"),
+            ValidationError::AmbiguousRoomName { name, candidates } => {
+                write!(f, "room name '{name}' could mean {}", candidates.join(", "))
+            }
+            ValidationError::TooManyMovePoints {
+                player, distance, ..
+            } => {
+                write!(f, "player {player} used too many move points ({distance})")
+            }
+            ValidationError::MovedNonStranger { player, piece } => {
+                write!(f, "player {player} tried to move non-stranger {piece}")
+            }
+            ValidationError::PieceMovedTwice { player, piece } => {
+                write!(
+                    f,
+                    "player {player} moved {piece} more than once in one turn"
+                )
+            }
+            ValidationError::StrangerMoveNeedsMoveCards {
+                player,
+                piece,
+                stranger_distance,
+                cost,
+                budget,
+            } => write!(
+                f,
+                "moving strangers like {piece} {stranger_distance} room(s) costs a move card per room, so player {player} needs {cost} move card(s) but has {budget}"
+            ),
+            ValidationError::NotPlayersTurn {
+                player,
+                current_player,
+                activated_room_id,
+                activated_room_label,
+            } => {
+                write!(
+                    f,
+                    "turn plan is for {player} but it is {current_player}'s turn"
+                )?;
+                match (activated_room_label, activated_room_id) {
+                    (Some(room_label), _) => {
+                        write!(f, " (doctor activated {current_player} in {room_label})")
+                    }
+                    (None, Some(room_id)) => {
+                        write!(f, " (doctor activated {current_player} in R{room_id})")
+                    }
+                    (None, None) => Ok(()),
+                }
+            }
+            ValidationError::ActivatedPlayerMustMove {
+                player, room_label, ..
+            } => write!(
+                f,
+                "doctor activated {player} in {room_label}, so {player} has to move out of it"
+            ),
+            ValidationError::RoomOccupied {
+                player,
+                piece,
+                occupant,
+                room_label,
+                ..
+            } => write!(
+                f,
+                "player {player} can't move {piece} into {room_label} because {occupant} is there"
+            ),
+            ValidationError::GameOver => write!(f, "the game is over"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_with_camel_case_params() {
+        let error = ValidationError::TooManyMovePoints {
+            player: "P1".to_string(),
+            distance: 3,
+            cost: 2.0,
+            budget: 1.5,
+        };
+
+        assert_eq!(error.code(), "tooManyMovePoints");
+        assert_eq!(error.to_string(), "player P1 used too many move points (3)");
+        assert_eq!(
+            serde_json::to_string(&error).expect("error should serialize"),
+            r#"{"code":"tooManyMovePoints","params":{"player":"P1","distance":3,"cost":2.0,"budget":1.5}}"#
+        );
+    }
+
+    #[test]
+    fn code_matches_the_serialized_tag_of_every_variant() {
+        let text = || "P1".to_string();
+        let errors = vec![
+            ValidationError::MalformedPlan { detail: text() },
+            ValidationError::PlanTooLong {
+                length: 1,
+                limit: 0,
+            },
+            ValidationError::TooManyMoves { count: 1, limit: 0 },
+            ValidationError::AnalysisTooDeep {
+                analysis_level: 1,
+                limit: 0,
+            },
+            ValidationError::InvalidPlayer {
+                player_id: 0,
+                player: text(),
+            },
+            ValidationError::InvalidRoom { room_id: 0 },
+            ValidationError::UnknownRoomName { name: text() },
+            ValidationError::AmbiguousRoomName {
+                name: text(),
+                candidates: Vec::new(),
+            },
+            ValidationError::TooManyMovePoints {
+                player: text(),
+                distance: 1,
+                cost: 1.0,
+                budget: 0.0,
+            },
+            ValidationError::MovedNonStranger {
+                player: text(),
+                piece: text(),
+            },
+            ValidationError::PieceMovedTwice {
+                player: text(),
+                piece: text(),
+            },
+            ValidationError::StrangerMoveNeedsMoveCards {
+                player: text(),
+                piece: text(),
+                stranger_distance: 1,
+                cost: 1.0,
+                budget: 0.0,
+            },
+            ValidationError::NotPlayersTurn {
+                player: text(),
+                current_player: text(),
+                activated_room_id: None,
+                activated_room_label: None,
+            },
+            ValidationError::ActivatedPlayerMustMove {
+                player: text(),
+                room_id: 0,
+                room_label: text(),
+            },
+            ValidationError::RoomOccupied {
+                player: text(),
+                piece: text(),
+                occupant: text(),
+                room_id: 0,
+                room_label: text(),
+            },
+            ValidationError::GameOver,
+        ];
+        // no wildcard, so a new variant doesn't compile until it's listed above
+        for error in &errors {
+            match error {
+                ValidationError::MalformedPlan { .. }
+                | ValidationError::PlanTooLong { .. }
+                | ValidationError::TooManyMoves { .. }
+                | ValidationError::AnalysisTooDeep { .. }
+                | ValidationError::InvalidPlayer { .. }
+                | ValidationError::InvalidRoom { .. }
+                | ValidationError::UnknownRoomName { .. }
+                | ValidationError::AmbiguousRoomName { .. }
+                | ValidationError::TooManyMovePoints { .. }
+                | ValidationError::MovedNonStranger { .. }
+                | ValidationError::PieceMovedTwice { .. }
+                | ValidationError::StrangerMoveNeedsMoveCards { .. }
+                | ValidationError::NotPlayersTurn { .. }
+                | ValidationError::ActivatedPlayerMustMove { .. }
+                | ValidationError::RoomOccupied { .. }
+                | ValidationError::GameOver => {}
+            }
+        }
+
+        let codes = errors
+            .iter()
+            .map(|error| {
+                let serialized = serde_json::to_value(error).expect("error should serialize");
+                assert_eq!(serialized["code"], error.code(), "{error:?}");
+                error.code()
+            })
+            .collect::<std::collections::HashSet<_>>();
+        assert_eq!(codes.len(), errors.len());
+    }
+}
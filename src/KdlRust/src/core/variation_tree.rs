@@ -0,0 +1,263 @@
+use crate::core::simple_turn::SimpleTurn;
+use serde::{Deserialize, Serialize};
+
+pub const ROOT_NODE_ID: usize = 0;
+
+// a normal turn played from its parent node; the root is the start position and has no node
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationNode {
+    pub parent_id: usize,
+    pub turn: SimpleTurn,
+}
+
+// every line of normal turns played since the start position; undoing and then playing a
+// different turn starts a variation instead of discarding the undone line. node ids are 1-based
+// indexes into nodes, so a parent always comes before its children and the first child of a
+// node is its main line
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VariationTree {
+    nodes: Vec<VariationNode>,
+    current_id: usize,
+    // the end of the line last played, which undo leaves in place for redo to head back toward
+    #[serde(default)]
+    tip_id: usize,
+}
+
+impl VariationTree {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some((idx, _)) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .find(|(idx, node)| node.parent_id > *idx)
+        {
+            return Err(format!(
+                "Variation node {} comes before its parent.",
+                idx + 1
+            ));
+        }
+        if self.current_id > self.nodes.len() || self.tip_id > self.nodes.len() {
+            return Err("Variation tree points at a node it doesn't have.".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn current_id(&self) -> usize {
+        self.current_id
+    }
+
+    pub fn node(&self, node_id: usize) -> Option<&VariationNode> {
+        node_id.checked_sub(1).and_then(|idx| self.nodes.get(idx))
+    }
+
+    pub fn parent_id(&self, node_id: usize) -> Option<usize> {
+        self.node(node_id).map(|node| node.parent_id)
+    }
+
+    pub fn child_ids(&self, node_id: usize) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.parent_id == node_id)
+            .map(|(idx, _)| idx + 1)
+            .collect::<Vec<_>>()
+    }
+
+    // the nodes from the start position to node_id, excluding the root
+    pub fn line_node_ids(&self, node_id: usize) -> Vec<usize> {
+        let mut node_ids = Vec::new();
+        let mut node_id = node_id;
+        while let Some(node) = self.node(node_id) {
+            node_ids.push(node_id);
+            node_id = node.parent_id;
+        }
+        node_ids.reverse();
+        node_ids
+    }
+
+    pub fn line(&self, node_id: usize) -> Vec<SimpleTurn> {
+        self.line_node_ids(node_id)
+            .into_iter()
+            .filter_map(|node_id| self.node(node_id))
+            .map(|node| node.turn.clone())
+            .collect::<Vec<_>>()
+    }
+
+    pub fn is_ancestor(&self, ancestor_id: usize, node_id: usize) -> bool {
+        ancestor_id == ROOT_NODE_ID || self.line_node_ids(node_id).contains(&ancestor_id)
+    }
+
+    // follows turns from the root, adding the nodes that don't exist yet, and makes the last one
+    // current; stepping back along the current line keeps the line it came from as the tip
+    pub fn sync_to_line(&mut self, turns: impl IntoIterator<Item = SimpleTurn>) {
+        let mut node_id = ROOT_NODE_ID;
+        for turn in turns {
+            node_id = match self
+                .child_ids(node_id)
+                .into_iter()
+                .find(|child_id| self.nodes[child_id - 1].turn == turn)
+            {
+                Some(child_id) => child_id,
+                None => {
+                    self.nodes.push(VariationNode {
+                        parent_id: node_id,
+                        turn,
+                    });
+                    self.nodes.len()
+                }
+            };
+        }
+        self.go_to(node_id);
+    }
+
+    pub fn go_to(&mut self, node_id: usize) {
+        debug_assert!(node_id <= self.nodes.len());
+        if !self.is_ancestor(node_id, self.tip_id) {
+            self.tip_id = node_id;
+        }
+        self.current_id = node_id;
+    }
+
+    // drops a node and every line through it; the line being played has to be left first
+    pub fn delete(&mut self, node_id: usize) -> Result<(), String> {
+        if self.node(node_id).is_none() {
+            return Err(format!("Unknown variation node {node_id}."));
+        }
+        if self.is_ancestor(node_id, self.current_id) {
+            return Err(format!(
+                "Variation node {node_id} is on the current line; switch to another line first."
+            ));
+        }
+
+        // parents come before children, so one pass knows whether each parent survived
+        let mut new_ids = vec![Some(ROOT_NODE_ID)];
+        let mut nodes = Vec::new();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let new_parent_id = new_ids[node.parent_id].filter(|_| idx + 1 != node_id);
+            new_ids.push(new_parent_id.map(|parent_id| {
+                nodes.push(VariationNode {
+                    parent_id,
+                    turn: node.turn.clone(),
+                });
+                nodes.len()
+            }));
+        }
+        self.nodes = nodes;
+        self.current_id = new_ids[self.current_id].unwrap_or(ROOT_NODE_ID);
+        self.tip_id = new_ids[self.tip_id].unwrap_or(self.current_id);
+        Ok(())
+    }
+
+    // every node with its depth below the root, each followed by its subtree, main lines first
+    pub fn depth_first_ids(&self) -> Vec<(usize, usize)> {
+        let mut ids = Vec::new();
+        let mut pending = self
+            .child_ids(ROOT_NODE_ID)
+            .into_iter()
+            .rev()
+            .map(|node_id| (node_id, 0))
+            .collect::<Vec<_>>();
+        while let Some((node_id, depth)) = pending.pop() {
+            ids.push((node_id, depth));
+            pending.extend(
+                self.child_ids(node_id)
+                    .into_iter()
+                    .rev()
+                    .map(|child_id| (child_id, depth + 1)),
+            );
+        }
+        ids
+    }
+
+    // the child redo would play: toward the tip if it is further down the current line, else
+    // the main line
+    pub fn redo_id(&self) -> Option<usize> {
+        if self.tip_id != self.current_id && self.is_ancestor(self.current_id, self.tip_id) {
+            let line = self.line_node_ids(self.tip_id);
+            let depth = self.line_node_ids(self.current_id).len();
+            return line.get(depth).copied();
+        }
+        self.child_ids(self.current_id).first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{player::PlayerId, room::RoomId};
+
+    fn turn(room_id: usize) -> SimpleTurn {
+        SimpleTurn::single(PlayerId(1), RoomId(room_id))
+    }
+
+    #[test]
+    fn undo_then_another_turn_starts_a_variation() {
+        let mut tree = VariationTree::default();
+        tree.sync_to_line([turn(1), turn(2), turn(3)]);
+        assert_eq!(tree.num_nodes(), 3);
+        assert_eq!(tree.current_id(), 3);
+
+        tree.sync_to_line([turn(1)]);
+        assert_eq!(tree.current_id(), 1);
+        assert_eq!(tree.redo_id(), Some(2));
+
+        tree.sync_to_line([turn(1), turn(5)]);
+        assert_eq!(tree.num_nodes(), 4);
+        assert_eq!(tree.child_ids(1), vec![2, 4]);
+        assert_eq!(tree.line(4), vec![turn(1), turn(5)]);
+        assert_eq!(tree.redo_id(), None);
+
+        // replaying a known line reuses its nodes
+        tree.sync_to_line([turn(1), turn(2)]);
+        assert_eq!(tree.num_nodes(), 4);
+        assert_eq!(tree.current_id(), 2);
+        assert_eq!(tree.redo_id(), Some(3));
+        tree.sync_to_line(Vec::new());
+        assert_eq!(tree.redo_id(), Some(1));
+    }
+
+    #[test]
+    fn deleting_a_variation_drops_its_subtree_and_renumbers() {
+        let mut tree = VariationTree::default();
+        tree.sync_to_line([turn(1), turn(2)]);
+        tree.sync_to_line([turn(3), turn(4)]);
+        tree.sync_to_line([turn(1), turn(5)]);
+        assert_eq!(
+            tree.depth_first_ids(),
+            vec![(1, 0), (2, 1), (5, 1), (3, 0), (4, 1)]
+        );
+
+        assert!(tree.delete(1).is_err());
+        assert!(tree.delete(9).is_err());
+        assert_eq!(tree.delete(3), Ok(()));
+        assert_eq!(tree.num_nodes(), 3);
+        assert_eq!(tree.current_id(), 3);
+        assert_eq!(tree.line(3), vec![turn(1), turn(5)]);
+        assert_eq!(tree.child_ids(ROOT_NODE_ID), vec![1]);
+        assert!(tree.validate().is_ok());
+    }
+
+    #[test]
+    fn round_trips_through_json_and_rejects_bad_links() {
+        let mut tree = VariationTree::default();
+        tree.sync_to_line([turn(1), turn(2)]);
+        tree.sync_to_line([turn(3)]);
+        let json = serde_json::to_string(&tree).expect("tree should serialize");
+        let restored =
+            serde_json::from_str::<VariationTree>(&json).expect("tree should deserialize");
+        assert_eq!(restored, tree);
+        assert!(restored.validate().is_ok());
+
+        let bad = serde_json::from_str::<VariationTree>(
+            r#"{"nodes":[{"parentId":2,"turn":{"Moves":[]}}],"currentId":1}"#,
+        )
+        .expect("shape is fine");
+        assert!(bad.validate().is_err());
+    }
+}
@@ -1,9 +1,16 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::fmt;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use wasm_bindgen::prelude::*;
 
+use crate::core::seat_map::{PieceId, SeatMap, StrangerCounts};
+use crate::core::session_stats::SessionStats;
+use crate::core::validation_error::ValidationError;
+use crate::core::variation_tree::VariationTree;
+
 pub mod core;
+#[cfg(all(feature = "server", not(target_arch = "wasm32")))]
+pub mod server;
 pub mod util;
 
 #[wasm_bindgen]
@@ -11,6 +18,51 @@ extern "C" {
     fn alert(s: &str);
 }
 
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = log)]
+    fn console_log(message: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(message: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(message: &str);
+}
+
+#[cfg(target_arch = "wasm32")]
+struct BrowserConsoleLogSink;
+
+#[cfg(target_arch = "wasm32")]
+impl util::log::LogSink for BrowserConsoleLogSink {
+    fn log(&self, level: util::log::LogLevel, message: &str) {
+        match level {
+            util::log::LogLevel::Error => console_error(message),
+            util::log::LogLevel::Warn => console_warn(message),
+            _ => console_log(message),
+        }
+    }
+}
+
+// engine messages go to the browser console from error up to level (error, warn, info or debug);
+// off, the default, silences them. returns an error message or ""
+#[wasm_bindgen(js_name = "setEngineLogLevel")]
+pub fn set_engine_log_level(level: &str) -> String {
+    if level.trim().eq_ignore_ascii_case("off") {
+        util::log::clear_log_sink();
+        return String::new();
+    }
+    match level.parse::<util::log::LogLevel>() {
+        Ok(level) => {
+            #[cfg(target_arch = "wasm32")]
+            util::log::set_log_sink(level, BrowserConsoleLogSink);
+            #[cfg(not(target_arch = "wasm32"))]
+            util::log::set_log_sink(level, util::log::ConsoleLogSink);
+            String::new()
+        }
+        Err(err) => err,
+    }
+}
+
 #[wasm_bindgen]
 pub fn greet() {
     alert("Hello from KdlRust.");
@@ -22,90 +74,42 @@ pub fn get_a_number() -> i32 {
     7
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-enum PieceId {
-    Doctor,
-    Player1,
-    Player2,
-    Stranger1,
-    Stranger2,
-}
-
-impl PieceId {
-    fn display_label(self) -> &'static str {
-        match self {
-            PieceId::Doctor => "Doctor",
-            PieceId::Player1 => "P1",
-            PieceId::Player2 => "P2",
-            PieceId::Stranger1 => "S1",
-            PieceId::Stranger2 => "S2",
-        }
-    }
-
-    fn as_str(self) -> &'static str {
-        match self {
-            PieceId::Doctor => "doctor",
-            PieceId::Player1 => "player1",
-            PieceId::Player2 => "player2",
-            PieceId::Stranger1 => "stranger1",
-            PieceId::Stranger2 => "stranger2",
-        }
-    }
-
-    fn parse(input: &str) -> Option<Self> {
-        match input.trim().to_ascii_lowercase().as_str() {
-            "doctor" => Some(PieceId::Doctor),
-            "player1" => Some(PieceId::Player1),
-            "player2" => Some(PieceId::Player2),
-            "stranger1" => Some(PieceId::Stranger1),
-            "stranger2" => Some(PieceId::Stranger2),
-            _ => None,
-        }
-    }
-
-    fn from_player_id(player_id: core::player::PlayerId, has_strangers: bool) -> Option<Self> {
-        if has_strangers {
-            match player_id.0 {
-                0 => Some(PieceId::Player1),
-                1 => Some(PieceId::Stranger1),
-                2 => Some(PieceId::Player2),
-                3 => Some(PieceId::Stranger2),
-                _ => None,
-            }
-        } else {
-            match player_id.0 {
-                0 => Some(PieceId::Player1),
-                1 => Some(PieceId::Player2),
-                _ => None,
-            }
-        }
-    }
-
-    fn to_player_id(self) -> Option<core::player::PlayerId> {
-        use core::rule_helper;
+struct TurnPlanEntry {
+    piece_id: PieceId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room_id: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    room_name: Option<String>, // used when roomId is absent
+}
 
-        match self {
-            PieceId::Doctor => None,
-            PieceId::Player1 => Some(rule_helper::SIDE_A_NORMAL_PLAYER_ID),
-            PieceId::Player2 => Some(rule_helper::SIDE_B_NORMAL_PLAYER_ID),
-            PieceId::Stranger1 => Some(rule_helper::STRANGER_PLAYER_ID_FIRST),
-            PieceId::Stranger2 => Some(rule_helper::STRANGER_PLAYER_ID_SECOND),
-        }
-    }
+// one turn of remote play; both ends apply the same packet. sequence counts the game's normal
+// turns from 1 and positionKey is the hex key of the position the plan was made from, so a
+// stale, repeated or out-of-sync packet is turned away the same way on every end
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnPacket {
+    session_id: String,
+    sequence: usize,
+    position_key: String,
+    plan: Vec<TurnPlanEntry>,
 }
 
-impl fmt::Display for PieceId {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(self.display_label())
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnPacketResponse {
+    is_valid: bool,
+    validation_message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    packet: Option<TurnPacket>,
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-struct TurnPlanEntry {
-    piece_id: PieceId,
-    room_id: usize,
+struct RemoteSessionJson {
+    session_id: String,
+    next_sequence: usize,
 }
 
 #[derive(Serialize)]
@@ -117,6 +121,14 @@ struct BoardRoomInfo {
     visible: Vec<usize>,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WingInfo {
+    name: String,
+    room_ids: Vec<usize>, // from the board spec, so a closed wing still lists its rooms
+    is_closed: bool,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct TurnPlanPreview {
@@ -128,7 +140,11 @@ struct TurnPlanPreview {
     attackers: Vec<String>,
     current_player_loots: bool,
     doctor_room_id: usize,
+    doctor_room_label: String,
     moved_strangers: Vec<PreviewPieceRoom>,
+    // the mover walks into an uncountered stranger loop the opponent holds or is a move from
+    stranger_loop_warning: bool,
+    stranger_loop_room_ids: Vec<usize>,
 }
 
 #[derive(Serialize)]
@@ -136,6 +152,7 @@ struct TurnPlanPreview {
 struct PreviewPieceRoom {
     piece_id: String,
     room_id: usize,
+    room_label: String,
 }
 
 #[derive(Serialize)]
@@ -145,6 +162,27 @@ struct SuggestedTurnEntry {
     room_id: usize,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StrangerPlacementEntry {
+    piece_id: String,
+    room_id: usize,
+    room_label: String,
+    appraisal: f64,
+    reasons: Vec<core::stranger_placement::PlacementReason>,
+    suggested_turn_text: String,
+    suggested_turn: Vec<SuggestedTurnEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StrangerPlacementResponse {
+    is_valid: bool,
+    validation_message: String,
+    placements: Vec<StrangerPlacementEntry>,
+    num_states_visited: usize,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct BestTurnAnalysisResponse {
@@ -154,13 +192,64 @@ struct BestTurnAnalysisResponse {
     suggested_turn: Vec<SuggestedTurnEntry>,
     heuristic_score: f64,
     num_states_visited: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    search_stats: Option<core::tree_search::SearchStats>, // when searched from an aspiration window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completed_level: Option<i32>, // deepest level a timed search finished
+    elapsed_ms: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RankedTurn {
+    suggested_turn_text: String,
+    suggested_turn: Vec<SuggestedTurnEntry>,
+    heuristic_score: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BestTurnsAnalysisResponse {
+    is_valid: bool,
+    validation_message: String,
+    turns: Vec<RankedTurn>, // best first
+    num_states_visited: usize,
     elapsed_ms: f64,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnPlanValidation {
+    is_valid: bool,
+    validation_message: String,
+    error: Option<core::validation_error::ValidationError>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnPlanCost {
+    is_valid: bool,
+    validation_message: String,
+    total_distance: i32,
+    move_cards_spent: f64,
+    move_cards_remaining: f64,
+    moves: Vec<TurnPlanMoveCost>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnPlanMoveCost {
+    piece_id: String,
+    from_room_id: usize,
+    room_id: usize,
+    distance: i32,
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct PlayerStats {
     piece_id: String,
+    room_id: usize,
     doctor_distance: i32,
     strength: i32,
     move_cards: f64,
@@ -169,6 +258,94 @@ struct PlayerStats {
     equivalent_clovers: f64,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StrangerLoopEntry {
+    enemy_room_id: usize,
+    counter_room_ids: Vec<usize>,
+    opposing_stranger_is_here: bool,
+    is_countered: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorActivationEvent {
+    turn_id: i32,
+    doctor_room_id: usize,
+    activated_piece_id: String,
+    skipped_piece_ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActivationContextSummary {
+    piece_id: String,
+    is_activated: bool,
+    doctor_room_id: Option<usize>,
+    skipped_piece_ids: Vec<String>,
+    restrictions: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoomOccupancyJson {
+    num_plies: usize,
+    rooms: Vec<RoomOccupancyRoom>,
+    pieces: Vec<RoomOccupancyPiece>,
+}
+
+// where the current player stands and which rooms their own piece could reach this turn and then
+// attack or loot from, with the other pieces left where they are
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionOutlook {
+    is_valid: bool,
+    validation_message: String,
+    piece_id: String,
+    room_id: usize,
+    sees_doctor: bool,
+    seen_by_piece_ids: Vec<String>,
+    action_if_staying: String,   // attack, loot or none
+    attack_room_ids: Vec<usize>, // the staying room counts when staying allows it
+    loot_room_ids: Vec<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoomOccupants {
+    is_valid: bool,
+    validation_message: String,
+    room_id: usize,
+    piece_ids: Vec<String>, // in turn order
+    has_doctor: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoomOccupancyRoom {
+    room_id: usize,
+    room_label: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoomOccupancyPiece {
+    piece_id: String,
+    plies: Vec<usize>, // aligned with rooms
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionStatsSummary {
+    games_played: usize,
+    wins: BTreeMap<String, usize>, // by the winning side's piece id
+    draws: usize,
+    average_turns: f64,
+    attacks_attempted: usize,
+    attacks_succeeded: usize,
+    loots_taken: usize,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct NormalSetup {
@@ -208,6 +385,29 @@ struct NormalSetup {
     turn_id: i32,
     #[serde(default = "default_current_player_piece_id")]
     current_player_piece_id: PieceId,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    piece_labels: Vec<PieceLabelJson>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PieceLabelJson {
+    piece_id: PieceId,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    color: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PieceInfoJson {
+    piece_id: &'static str,
+    piece_text: &'static str, // P1, S1 and so on
+    name: String,
+    color: String,
+    display_text: String, // as summaries show the player: "Alice (P1)", "P3" without a name
+    is_stranger: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -218,1257 +418,7498 @@ struct PersistedGameState {
     #[serde(default = "default_normal_setup")]
     normal_setup: NormalSetup,
     normal_turns: Vec<core::simple_turn::SimpleTurn>,
+    #[serde(default)]
+    rule_config: core::rule_config::RuleConfig,
+    #[serde(default)]
+    annotations: Vec<PlyAnnotation>,
+    #[serde(default = "default_stranger_counts")]
+    stranger_counts: StrangerCounts,
+    // the only result a replay of normal_turns can't reproduce
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    aborted: bool,
+    // for readers of the save; a replay rebuilds it, and a mismatch means the save was edited
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attack_history: Vec<AttackHistoryEntry>,
+    // Board::structure_hash in hex, so the save can be mapped onto a renamed copy of its board
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    board_structure_hash: String,
+    // every line played since the start, from exportStateJsonWithVariations; normal_turns is its
+    // current line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    variations: Option<VariationTree>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    teams: Option<core::seat_map::Teams>,
 }
 
-const PERSISTED_GAME_STATE_VERSION: u32 = 1;
+// what importStateJsonWithOptions may relax; the defaults are importStateJson's strict checks
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct ImportOptions {
+    // accept a save from a board with another name if its structure hash matches the current board
+    allow_matching_board_structure: bool,
+    // replay up to the first invalid turn instead of refusing the whole save
+    truncate_at_invalid_turn: bool,
+    // keep only the restored state and its turn list, not a state per turn; undo replays instead
+    compact_history: bool,
+}
 
-fn default_move_cards() -> f64 {
-    core::rule_helper::simple::PLAYER_STARTING_MOVE_CARDS
+// findBestTurnsAmong's turn plans: only, when given, are the turns searched; excluded never are
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct RootTurnsOptions {
+    only: Vec<serde_json::Value>,
+    excluded: Vec<serde_json::Value>,
 }
 
-fn default_weapon_cards() -> f64 {
-    core::rule_helper::simple::PLAYER_STARTING_WEAPONS
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportReport {
+    is_valid: bool,
+    validation_message: String,
+    board_mapped_from: String, // the saved board's name when it differs from the current board's
+    num_saved_turns: usize,
+    num_restored_turns: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invalid_turn: Option<usize>, // from 1, the saved turn the replay stopped at
+    invalid_reason: String,
 }
 
-fn default_failure_cards() -> f64 {
-    core::rule_helper::simple::PLAYER_STARTING_FAILURES
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VariationNodeJson {
+    node_id: usize,
+    parent_id: usize,
+    ply: usize,
+    turn_text: String,
+    child_ids: Vec<usize>, // the first is the main line
+    is_on_current_line: bool,
 }
 
-fn default_current_player_piece_id() -> PieceId {
-    PieceId::Player1
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VariationTreeJson {
+    current_node_id: usize, // 0 is the start position
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redo_node_id: Option<usize>,
+    root_child_ids: Vec<usize>,
+    nodes: Vec<VariationNodeJson>,
 }
 
-fn default_player_strength() -> i32 {
-    core::rule_helper::PLAYER_STARTING_STRENGTH
+struct RestoredGame {
+    state: core::mutable_game_state::MutableGameState,
+    normal_setup: NormalSetup,
+    board_mapped_from: Option<String>,
+    num_saved_turns: usize,
+    invalid_turn: Option<(usize, String)>,
 }
 
-fn default_turn_id() -> i32 {
-    1
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AttackHistoryEntry {
+    turn_id: i32,
+    piece_id: String,
+    strength: f64,
+    defense_spent: f64, // clovers
+    succeeded: bool,
 }
 
-fn default_unset_card_quantity() -> f64 {
-    -1.0
+fn default_stranger_counts() -> StrangerCounts {
+    StrangerCounts::ONE_EACH
 }
 
-fn default_normal_setup() -> NormalSetup {
-    NormalSetup {
-        board_name: String::new(),
-        move_cards: default_move_cards(),
-        weapon_cards: default_weapon_cards(),
-        failure_cards: default_failure_cards(),
-        player2_move_cards: default_move_cards(),
-        player2_weapon_cards: default_weapon_cards(),
-        player2_failure_cards: default_failure_cards(),
-        doctor_room_id: 0,
-        player1_room_id: 0,
-        stranger1_room_id: 0,
-        player2_room_id: 0,
-        stranger2_room_id: 0,
-        player1_strength: default_player_strength(),
-        stranger1_strength: default_player_strength(),
-        player2_strength: default_player_strength(),
-        stranger2_strength: default_player_strength(),
-        turn_id: default_turn_id(),
-        current_player_piece_id: default_current_player_piece_id(),
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppraisalHistoryEntry {
+    ply: usize,
+    appraisal: f64,
+    appraisal_text: String,
 }
 
-fn normalize_normal_setup(
-    setup: &NormalSetup,
-    common: &core::common_game_state::CommonGameState,
-) -> NormalSetup {
-    let mut normalized = setup.clone();
-    normalized.board_name = common.board.json_name.clone();
+// caps on caller-supplied work, so an embedding page can't wedge the engine with huge inputs
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct InputLimits {
+    max_plan_json_len: usize,
+    max_plan_moves: usize,
+    max_analysis_level: i32,
+}
 
-    if normalized.doctor_room_id == 0 {
-        normalized.doctor_room_id = common.board.doctor_start_room_id.0;
-    }
-    if normalized.player1_room_id == 0 {
-        normalized.player1_room_id = common.board.player_start_room_id.0;
-    }
-    if normalized.stranger1_room_id == 0 {
-        normalized.stranger1_room_id = common.board.player_start_room_id.0;
-    }
-    if normalized.player2_room_id == 0 {
-        normalized.player2_room_id = common.board.player_start_room_id.0;
-    }
-    if normalized.stranger2_room_id == 0 {
-        normalized.stranger2_room_id = common.board.player_start_room_id.0;
-    }
-    if normalized.player2_move_cards < 0.0 {
-        normalized.player2_move_cards = normalized.move_cards;
-    }
-    if normalized.player2_weapon_cards < 0.0 {
-        normalized.player2_weapon_cards = normalized.weapon_cards;
-    }
-    if normalized.player2_failure_cards < 0.0 {
-        normalized.player2_failure_cards = normalized.failure_cards;
+impl Default for InputLimits {
+    fn default() -> Self {
+        Self {
+            max_plan_json_len: 4096,
+            max_plan_moves: 8,
+            max_analysis_level: Self::DEFAULT_MAX_ANALYSIS_LEVEL,
+        }
     }
-
-    normalized
 }
 
-fn validate_normal_setup(
-    setup: &NormalSetup,
-    common: &core::common_game_state::CommonGameState,
-) -> Result<(), String> {
-    let checks = [
-        ("moveCards", setup.move_cards),
-        ("weaponCards", setup.weapon_cards),
-        ("failureCards", setup.failure_cards),
-        ("player2MoveCards", setup.player2_move_cards),
-        ("player2WeaponCards", setup.player2_weapon_cards),
-        ("player2FailureCards", setup.player2_failure_cards),
-    ];
-    for (label, value) in checks {
-        if !value.is_finite() {
-            return Err(format!("{label} must be a finite number."));
-        }
-        if value < 0.0 {
-            return Err(format!("{label} must be >= 0."));
+impl InputLimits {
+    // a few seconds of search on a phone; the server's suggest route stops at the same level
+    const DEFAULT_MAX_ANALYSIS_LEVEL: i32 = 6;
+
+    fn check_analysis_level(&self, analysis_level: i32) -> Result<(), ValidationError> {
+        if analysis_level > self.max_analysis_level {
+            return Err(ValidationError::AnalysisTooDeep {
+                analysis_level,
+                limit: self.max_analysis_level,
+            });
         }
+        Ok(())
     }
+}
 
-    let room_checks = [
-        ("doctorRoomId", setup.doctor_room_id),
-        ("player1RoomId", setup.player1_room_id),
-        ("stranger1RoomId", setup.stranger1_room_id),
-        ("player2RoomId", setup.player2_room_id),
-        ("stranger2RoomId", setup.stranger2_room_id),
-    ];
-    for (label, room_id) in room_checks {
-        if !common
-            .board
-            .rooms
-            .contains_key(&core::room::RoomId(room_id))
-        {
-            return Err(format!("{label} must be a valid room id."));
-        }
-    }
+// deepening that js drives one level per step, since a running wasm search can't be interrupted;
+// cancelSearch takes effect before the next step
+#[derive(Clone, Default)]
+struct ContinuousAnalysis {
+    is_running: bool,
+    completed_level: i32,
+    position_key: u64,
+    latest_json: String,
+    latest_appraisal: Option<f64>,
+}
 
-    let strength_checks = [
-        ("player1Strength", setup.player1_strength),
-        ("stranger1Strength", setup.stranger1_strength),
-        ("player2Strength", setup.player2_strength),
-        ("stranger2Strength", setup.stranger2_strength),
-    ];
-    for (label, strength) in strength_checks {
-        if strength < 0 {
-            return Err(format!("{label} must be >= 0."));
-        }
-    }
+#[derive(Copy, Clone)]
+struct BlunderCheck {
+    analysis_level: i32,
+    blunder_threshold: f64,
+}
 
-    if setup.turn_id < 1 {
-        return Err("turnId must be >= 1.".to_string());
-    }
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnSwing {
+    piece_id: String,
+    analysis_level: i32,
+    appraisal_before: f64,
+    appraisal_after: f64,
+    appraisal_before_text: String,
+    appraisal_after_text: String,
+    swing: f64,
+    is_blunder: bool,
+}
 
-    if setup.current_player_piece_id != PieceId::Player1
-        && setup.current_player_piece_id != PieceId::Player2
-    {
-        return Err("currentPlayerPieceId must be P1 or P3.".to_string());
-    }
+// what a setOnStateChanged callback gets; kind is turn, undo, reset, import, abort or labels
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateChangeJson {
+    kind: &'static str,
+    ply: i32,
+    turn_id: i32,
+    current_player_piece_id: String,
+    result: &'static str,
+}
 
-    Ok(())
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnBroadcast {
+    ply: usize,
+    piece_id: String,
+    text: String,
+    summary: String,
+    attackers: Vec<String>,
+    doctor_room_id_before: usize,
+    doctor_room_id_after: usize,
+    appraisal: f64,
+    appraisal_text: String,
+    winner_piece_id: String,
+    stranger_loop_warning: bool,
+    stranger_loop_room_ids: Vec<usize>,
 }
 
-fn apply_normal_setup_to_state(
-    state: &mut core::mutable_game_state::MutableGameState,
-    normal_setup: &NormalSetup,
-) {
-    state.doctor_room_id = core::room::RoomId(normal_setup.doctor_room_id);
-    state.player_room_ids[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0] =
-        core::room::RoomId(normal_setup.player1_room_id);
-    state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0] =
-        core::room::RoomId(normal_setup.stranger1_room_id);
-    state.player_room_ids[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0] =
-        core::room::RoomId(normal_setup.player2_room_id);
-    state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0] =
-        core::room::RoomId(normal_setup.stranger2_room_id);
-    state.player_strengths[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0] =
-        normal_setup.player1_strength;
-    state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0] =
-        normal_setup.stranger1_strength;
-    state.player_strengths[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0] =
-        normal_setup.player2_strength;
-    state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0] =
-        normal_setup.stranger2_strength;
-    state.turn_id = normal_setup.turn_id;
-    state.current_player_id = normal_setup
-        .current_player_piece_id
-        .to_player_id()
-        .unwrap_or(core::rule_helper::SIDE_A_NORMAL_PLAYER_ID);
+// result is inProgress, won, draw or aborted; winnerPieceId and drawReason are empty unless they
+// apply
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameResultJson {
+    result: String,
+    winner_piece_id: String,
+    draw_reason: String,
+}
 
-    let side_a_idx = core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0;
-    state.player_move_cards[side_a_idx] = normal_setup.move_cards;
-    state.player_weapons[side_a_idx] = normal_setup.weapon_cards;
-    state.player_failures[side_a_idx] = normal_setup.failure_cards;
-    let side_b_idx = core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0;
-    state.player_move_cards[side_b_idx] = normal_setup.player2_move_cards;
-    state.player_weapons[side_b_idx] = normal_setup.player2_weapon_cards;
-    state.player_failures[side_b_idx] = normal_setup.player2_failure_cards;
+// the last normal turn as data, so a replay doesn't have to parse prevTurnSummaryVerbose
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LastTurnJson {
+    is_valid: bool,
+    validation_message: String,
+    ply: usize,
+    piece_id: String,
+    turn_text: String,
+    moves: Vec<LastTurnMoveJson>,
+    total_distance: i32,
+    action: String, // attack, loot or none
+    attack_succeeded: bool,
+    doctor_room_id_before: usize,
+    doctor_room_id_after: usize,
+    doctor_room_ids: Vec<usize>, // before the turn, then after it and each stranger turn since
+    actions: Vec<LastTurnActionJson>, // the turn, then each stranger turn, like animationFrames
 }
 
-fn new_state_with_normal_setup(
-    common: core::common_game_state::CommonGameState,
-    normal_setup: &NormalSetup,
-) -> core::mutable_game_state::MutableGameState {
-    let normalized_setup = normalize_normal_setup(normal_setup, &common);
-    let mut state = core::mutable_game_state::MutableGameState::at_start(common);
-    apply_normal_setup_to_state(&mut state, &normalized_setup);
-    state
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LastTurnActionJson {
+    piece_id: String,
+    action: String,
 }
 
-fn snap_card_quantity_to_thirty_seconds(value: f64) -> f64 {
-    (value * 32.0).round() / 32.0
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LastTurnMoveJson {
+    piece_id: String,
+    from_room_id: usize,
+    to_room_id: usize,
+    distance: i32,
 }
 
-fn snapped_normal_setup(setup: &NormalSetup) -> NormalSetup {
-    NormalSetup {
-        board_name: setup.board_name.clone(),
-        move_cards: snap_card_quantity_to_thirty_seconds(setup.move_cards),
-        weapon_cards: snap_card_quantity_to_thirty_seconds(setup.weapon_cards),
-        failure_cards: snap_card_quantity_to_thirty_seconds(setup.failure_cards),
-        player2_move_cards: snap_card_quantity_to_thirty_seconds(setup.player2_move_cards),
-        player2_weapon_cards: snap_card_quantity_to_thirty_seconds(setup.player2_weapon_cards),
-        player2_failure_cards: snap_card_quantity_to_thirty_seconds(setup.player2_failure_cards),
-        doctor_room_id: setup.doctor_room_id,
-        player1_room_id: setup.player1_room_id,
-        stranger1_room_id: setup.stranger1_room_id,
-        player2_room_id: setup.player2_room_id,
-        stranger2_room_id: setup.stranger2_room_id,
-        player1_strength: setup.player1_strength,
-        stranger1_strength: setup.stranger1_strength,
-        player2_strength: setup.player2_strength,
-        stranger2_strength: setup.stranger2_strength,
-        turn_id: setup.turn_id,
-        current_player_piece_id: setup.current_player_piece_id,
-    }
+// how the position changed between two plies or saves, for stepping a replay without redrawing
+// everything; undoneAttacks is only filled when the diff runs backward or across diverging games
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateDiffJson {
+    is_valid: bool,
+    validation_message: String,
+    from_ply: i32,
+    to_ply: i32,
+    doctor_from_room_id: usize,
+    doctor_to_room_id: usize,
+    piece_moves: Vec<StateDiffMoveJson>,
+    card_deltas: Vec<StateDiffCardsJson>,
+    strength_deltas: Vec<StateDiffStrengthJson>,
+    attacks: Vec<AttackHistoryEntry>,
+    undone_attacks: Vec<AttackHistoryEntry>,
 }
 
-fn is_matching_board_name(
-    saved_board_name: &str,
-    current_board_name: &str,
-    current_board_json_name: &str,
-) -> bool {
-    saved_board_name == current_board_name || saved_board_name == current_board_json_name
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateDiffMoveJson {
+    piece_id: String,
+    from_room_id: usize,
+    to_room_id: usize,
 }
 
-fn is_legacy_or_matching_board_name(
-    saved_board_name: &str,
-    current_board_name: &str,
-    current_board_json_name: &str,
-) -> bool {
-    saved_board_name.is_empty()
-        || is_matching_board_name(
-            saved_board_name,
-            current_board_name,
-            current_board_json_name,
-        )
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateDiffCardsJson {
+    piece_id: String,
+    move_cards: f64,
+    weapons: f64,
+    failures: f64,
 }
 
-fn normal_piece_id_for_state(state: &core::mutable_game_state::MutableGameState) -> PieceId {
-    let normal_id = core::rule_helper::to_normal_player_id(
-        state.current_player_id,
-        state.common.num_normal_players,
-    );
-    if normal_id == core::rule_helper::SIDE_A_NORMAL_PLAYER_ID {
-        PieceId::Player1
-    } else {
-        PieceId::Player2
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StateDiffStrengthJson {
+    piece_id: String,
+    from_strength: i32,
+    to_strength: i32,
 }
 
-fn winner_piece_id_for_state(
-    state: &core::mutable_game_state::MutableGameState,
-) -> Option<PieceId> {
-    if !state.has_winner() {
-        return None;
-    }
+// oldest first; ply is the normal turn count when the piece reached the room
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PieceTrailJson {
+    is_valid: bool,
+    validation_message: String,
+    piece_id: String,
+    rooms: Vec<PieceTrailRoomJson>,
+}
 
-    let normal_id =
-        core::rule_helper::to_normal_player_id(state.winner, state.common.num_normal_players);
-    Some(if normal_id == core::rule_helper::SIDE_A_NORMAL_PLAYER_ID {
-        PieceId::Player1
-    } else {
-        PieceId::Player2
-    })
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PieceTrailRoomJson {
+    ply: usize,
+    room_id: usize,
 }
 
-fn player_id_for_piece_id(piece_id: PieceId) -> Option<core::player::PlayerId> {
-    piece_id.to_player_id()
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttackSimulationJson {
+    is_valid: bool,
+    validation_message: String,
+    attacker_piece_id: String,
+    allowed_now: bool,
+    base_strength: i32,
+    weapon_used: bool,
+    attack_strength: f64,
+    defender_cards: Vec<DefenderCardJson>,
+    outcome: String,
 }
 
-fn player_id_for_piece_id_str(piece_id: &str) -> Option<core::player::PlayerId> {
-    let piece_id = PieceId::parse(piece_id)?;
-    player_id_for_piece_id(piece_id)
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DefenderCardJson {
+    defender_piece_id: String,
+    card_type: String,
+    cards_spent: f64,
+    strength_remaining: f64,
 }
 
-fn piece_attack_strength_for_state(
-    state: &core::mutable_game_state::MutableGameState,
-    player_id: core::player::PlayerId,
-) -> i32 {
-    let idx = player_id.0;
-    let weapon_bonus = if state.common.get_player_type(player_id)
-        == core::player::PlayerType::Normal
-        && state.player_weapons[idx] >= 1.0
-    {
-        2
-    } else {
-        0
-    };
-    state.player_strengths[idx] + weapon_bonus
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlyAnnotation {
+    #[serde(default)]
+    ply: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    appraisal: Option<f64>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    comment: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    markers: Vec<String>,
 }
 
-fn display_turn_id_for_state(state: &core::mutable_game_state::MutableGameState) -> i32 {
-    if state.is_normal_turn() {
-        state.ply() + 1
-    } else {
-        state.ply()
+impl PlyAnnotation {
+    fn is_empty(&self) -> bool {
+        self.appraisal.is_none() && self.comment.is_empty() && self.markers.is_empty()
     }
 }
 
-fn attack_history_text_for_state(state: &core::mutable_game_state::MutableGameState) -> String {
-    let mut attacks = Vec::new();
-    let mut current_state = state;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GamePresetSummary {
+    name: String,
+    description: String,
+    board_name: String,
+    num_normal_players: usize,
+    stranger_counts: StrangerCounts,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    teams: Option<core::seat_map::Teams>,
+    rule_config: core::rule_config::RuleConfig,
+}
 
-    while let Some(prev_state) = current_state.prev_state.as_deref() {
-        if current_state.attacker_hist.len() > prev_state.attacker_hist.len() {
-            let attacker = prev_state.current_player_id;
-            let player_num = core::common_game_state::CommonGameState::to_player_display_num(attacker);
-            let room_id = current_state.player_room_ids[attacker.0].0;
-            attacks.push(format!("{player_num}@{room_id},T{}", display_turn_id_for_state(prev_state)));
-        }
+// boardJsonFromText and boardTextFromJson; each fills in the format it converts to
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BoardConversionResponse {
+    is_valid: bool,
+    validation_message: String,
+    board_json: String,
+    board_text: String,
+}
 
-        current_state = prev_state;
-    }
+// lets a client tell whether a cached wasm bundle can read its saved games and cached keys
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EngineInfo {
+    engine_version: String,
+    rule_config_keys: Vec<String>, // the optional rules this engine knows, as ruleConfigJson names them
+    preset_names: Vec<String>,
+    persisted_state_version: u32,
+    persisted_state_versions_supported: Vec<u32>,
+    position_key_version: u32,
+    game_features_version: u32,
+}
 
-    attacks.reverse();
-    attacks.join("; ")
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleSummary {
+    name: String,
+    description: String,
+    board_name: String,
+    goal: core::puzzle::PuzzleGoal,
 }
 
-fn attack_history_label_for_state(state: &core::mutable_game_state::MutableGameState) -> String {
-    let mut side_a_attacks = 0;
-    let mut side_b_attacks = 0;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleCheckResponse {
+    is_valid: bool,
+    validation_message: String,
+    verdict: String, // solved, onTrack or failed; empty when invalid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failed_ply: Option<i32>,
+    reason: String,
+}
 
-    for attacker in &state.attacker_hist {
-        if *attacker == core::rule_helper::SIDE_A_NORMAL_PLAYER_ID
-            || *attacker == core::rule_helper::STRANGER_PLAYER_ID_SECOND
-        {
-            side_a_attacks += 1;
-        } else if *attacker == core::rule_helper::SIDE_B_NORMAL_PLAYER_ID
-            || *attacker == core::rule_helper::STRANGER_PLAYER_ID_FIRST
-        {
-            side_b_attacks += 1;
-        }
-    }
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleHintResponse {
+    is_valid: bool,
+    validation_message: String,
+    suggested_turn_text: String,
+    suggested_turn: Vec<SuggestedTurnEntry>,
+}
 
-    format!("Atks{side_a_attacks},{side_b_attacks}")
+const PERSISTED_GAME_STATE_VERSION: u32 = 2;
+const PERSISTED_GAME_STATE_VERSION_WITHOUT_ANNOTATIONS: u32 = 1;
+// previewTurnPlans' batch size; each plan still answers to the input limits
+const MAX_PREVIEW_PLANS: usize = 64;
+
+// a new id for each game any handle starts, so a match can tell a game it already recorded
+static NEXT_GAME_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+fn next_game_id() -> u64 {
+    NEXT_GAME_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
-fn to_preview_json(preview: &TurnPlanPreview) -> String {
-    serde_json::to_string(preview).unwrap_or_else(|_| {
-        "{\"isValid\":false,\"validationMessage\":\"Preview serialization failed.\",\"nextPlayerPieceId\":\"\",\"hasWinner\":false,\"winnerPieceId\":\"\",\"attackers\":[],\"currentPlayerLoots\":false,\"doctorRoomId\":0,\"movedStrangers\":[]}".to_string()
-    })
+fn default_move_cards() -> f64 {
+    core::rule_helper::simple::PLAYER_STARTING_MOVE_CARDS.to_f64()
 }
 
-fn invalid_preview_json(message: String) -> String {
-    to_preview_json(&TurnPlanPreview {
-        is_valid: false,
-        validation_message: message,
-        next_player_piece_id: String::new(),
-        has_winner: false,
-        winner_piece_id: String::new(),
-        attackers: Vec::new(),
-        current_player_loots: false,
-        doctor_room_id: 0,
-        moved_strangers: Vec::new(),
-    })
+fn default_weapon_cards() -> f64 {
+    core::rule_helper::simple::PLAYER_STARTING_WEAPONS.to_f64()
 }
 
-fn to_best_turn_analysis_json(response: &BestTurnAnalysisResponse) -> String {
-    serde_json::to_string(response).unwrap_or_else(|_| {
-        "{\"isValid\":false,\"validationMessage\":\"Best turn serialization failed.\",\"suggestedTurnText\":\"\",\"suggestedTurn\":[],\"heuristicScore\":0,\"numStatesVisited\":0,\"elapsedMs\":0}".to_string()
-    })
+fn default_failure_cards() -> f64 {
+    core::rule_helper::simple::PLAYER_STARTING_FAILURES.to_f64()
 }
 
-fn invalid_best_turn_analysis_json(
-    message: String,
-    num_states_visited: usize,
-    elapsed_ms: f64,
-) -> String {
-    to_best_turn_analysis_json(&BestTurnAnalysisResponse {
-        is_valid: false,
-        validation_message: message,
-        suggested_turn_text: String::new(),
-        suggested_turn: Vec::new(),
-        heuristic_score: 0.0,
-        num_states_visited,
-        elapsed_ms,
-    })
+fn default_current_player_piece_id() -> PieceId {
+    PieceId::Player1
 }
 
-#[cfg(target_arch = "wasm32")]
-fn now_ms() -> f64 {
-    js_sys::Date::now()
+fn default_player_strength() -> i32 {
+    core::rule_helper::PLAYER_STARTING_STRENGTH
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-fn now_ms() -> f64 {
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|duration| duration.as_secs_f64() * 1000.0)
-        .unwrap_or(0.0)
+fn default_turn_id() -> i32 {
+    1
 }
 
-fn current_player_loots_after_turn(
-    state: &core::mutable_game_state::MutableGameState,
-    turn: &core::simple_turn::SimpleTurn,
-) -> bool {
-    let mut preview_state = state.copy_state();
-    let current_player_id = preview_state.current_player_id;
-    let mut moved_stranger_that_saw_doctor = false;
+fn default_unset_card_quantity() -> f64 {
+    -1.0
+}
 
-    for mv in &turn.moves {
-        let player_idx = mv.player_id.0;
-        let current_room_id = preview_state.player_room_ids[player_idx];
-        if mv.player_id != current_player_id
-            && preview_state.common.board.sight[current_room_id.0][preview_state.doctor_room_id.0]
-        {
-            moved_stranger_that_saw_doctor = true;
-        }
-        preview_state.player_room_ids[player_idx] = mv.dest_room_id;
+fn default_normal_setup() -> NormalSetup {
+    NormalSetup {
+        board_name: String::new(),
+        move_cards: default_move_cards(),
+        weapon_cards: default_weapon_cards(),
+        failure_cards: default_failure_cards(),
+        player2_move_cards: default_move_cards(),
+        player2_weapon_cards: default_weapon_cards(),
+        player2_failure_cards: default_failure_cards(),
+        doctor_room_id: 0,
+        player1_room_id: 0,
+        stranger1_room_id: 0,
+        player2_room_id: 0,
+        stranger2_room_id: 0,
+        player1_strength: default_player_strength(),
+        stranger1_strength: default_player_strength(),
+        player2_strength: default_player_strength(),
+        stranger2_strength: default_player_strength(),
+        turn_id: default_turn_id(),
+        current_player_piece_id: default_current_player_piece_id(),
+        piece_labels: Vec::new(),
     }
-
-    preview_state.best_action_allowed(moved_stranger_that_saw_doctor)
-        == core::player::PlayerAction::Loot
 }
 
-fn collect_normal_turns(
-    state: &core::mutable_game_state::MutableGameState,
-) -> Vec<core::simple_turn::SimpleTurn> {
-    let mut states = Vec::new();
-    let mut cursor = Some(state);
-
-    while let Some(current) = cursor {
-        states.push(current);
-        cursor = current.prev_state.as_deref();
+fn default_normal_setup_for_rules(rule_config: &core::rule_config::RuleConfig) -> NormalSetup {
+    NormalSetup {
+        move_cards: rule_config.player_starting_move_cards.to_f64(),
+        weapon_cards: rule_config.player_starting_weapons.to_f64(),
+        failure_cards: rule_config.player_starting_failures.to_f64(),
+        player2_move_cards: rule_config.player_starting_move_cards.to_f64(),
+        player2_weapon_cards: rule_config.player_starting_weapons.to_f64(),
+        player2_failure_cards: rule_config.player_starting_failures.to_f64(),
+        ..default_normal_setup()
     }
-
-    states.reverse();
-    states
-        .into_iter()
-        .skip(1)
-        .filter_map(|current| {
-            let prev_state = current.prev_state.as_deref()?;
-            if prev_state.is_normal_turn() {
-                Some(current.prev_turn.clone())
-            } else {
-                None
-            }
-        })
-        .collect::<Vec<_>>()
 }
 
-#[wasm_bindgen]
-pub struct GameStateHandle {
-    state: core::mutable_game_state::MutableGameState,
-    normal_setup: NormalSetup,
-}
+fn normalize_normal_setup(
+    setup: &NormalSetup,
+    common: &core::common_game_state::CommonGameState,
+) -> NormalSetup {
+    let mut normalized = setup.clone();
+    normalized.board_name = common.board.json_name.clone();
 
-#[wasm_bindgen]
-impl GameStateHandle {
-    pub fn summary(&self, indentation_level: usize) -> String {
-        self.state.summary(indentation_level)
+    if normalized.doctor_room_id == 0 {
+        normalized.doctor_room_id = common.board.doctor_start_room_id.0;
     }
-
-    #[wasm_bindgen(js_name = "currentPlayerPieceId")]
-    pub fn current_player_piece_id(&self) -> String {
-        normal_piece_id_for_state(&self.state).as_str().to_string()
+    if normalized.player1_room_id == 0 {
+        normalized.player1_room_id = common.board.player_start_room_id.0;
     }
-
-    #[wasm_bindgen(js_name = "hasWinner")]
-    pub fn has_winner(&self) -> bool {
-        self.state.has_winner()
+    if normalized.stranger1_room_id == 0 {
+        normalized.stranger1_room_id = common.board.player_start_room_id.0;
     }
-
-    #[wasm_bindgen(js_name = "winnerPieceId")]
-    pub fn winner_piece_id(&self) -> String {
-        winner_piece_id_for_state(&self.state)
-            .map(|piece_id| piece_id.as_str().to_string())
-            .unwrap_or_default()
+    if normalized.player2_room_id == 0 {
+        normalized.player2_room_id = common.board.player_start_room_id.0;
     }
-
-    #[wasm_bindgen(js_name = "piecePositions")]
-    pub fn piece_positions(&self) -> Vec<u32> {
-        vec![
-            self.state.doctor_room_id.0 as u32,
-            self.state.player_room_ids[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0].0 as u32,
-            self.state.player_room_ids[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].0 as u32,
-            self.state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0].0 as u32,
-            self.state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0].0 as u32,
-        ]
+    if normalized.stranger2_room_id == 0 {
+        normalized.stranger2_room_id = common.board.player_start_room_id.0;
     }
-
-    #[wasm_bindgen(js_name = "pieceDoctorDistance")]
-    pub fn piece_doctor_distance(&self, piece_id: &str) -> i32 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0;
-        };
-        self.state.doctor_moves_until_player_room(player_id)
+    if normalized.player2_move_cards < 0.0 {
+        normalized.player2_move_cards = normalized.move_cards;
     }
-
-    #[wasm_bindgen(js_name = "pieceStrength")]
-    pub fn piece_strength(&self, piece_id: &str) -> i32 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0;
-        };
-        self.state.player_strengths[player_id.0]
+    if normalized.player2_weapon_cards < 0.0 {
+        normalized.player2_weapon_cards = normalized.weapon_cards;
     }
-
-    #[wasm_bindgen(js_name = "pieceMoveCards")]
-    pub fn piece_move_cards(&self, piece_id: &str) -> f64 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0.0;
-        };
-        self.state.player_move_cards[player_id.0]
+    if normalized.player2_failure_cards < 0.0 {
+        normalized.player2_failure_cards = normalized.failure_cards;
     }
 
-    #[wasm_bindgen(js_name = "pieceWeaponCards")]
-    pub fn piece_weapon_cards(&self, piece_id: &str) -> f64 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0.0;
-        };
-        self.state.player_weapons[player_id.0]
+    normalized
+}
+
+fn validate_normal_setup(
+    setup: &NormalSetup,
+    common: &core::common_game_state::CommonGameState,
+) -> Result<(), String> {
+    let checks = [
+        ("moveCards", setup.move_cards),
+        ("weaponCards", setup.weapon_cards),
+        ("failureCards", setup.failure_cards),
+        ("player2MoveCards", setup.player2_move_cards),
+        ("player2WeaponCards", setup.player2_weapon_cards),
+        ("player2FailureCards", setup.player2_failure_cards),
+    ];
+    for (label, value) in checks {
+        if !value.is_finite() {
+            return Err(format!("{label} must be a finite number."));
+        }
+        if value < 0.0 {
+            return Err(format!("{label} must be >= 0."));
+        }
     }
 
-    #[wasm_bindgen(js_name = "pieceFailureCards")]
-    pub fn piece_failure_cards(&self, piece_id: &str) -> f64 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0.0;
-        };
-        self.state.player_failures[player_id.0]
+    let room_checks = [
+        ("doctorRoomId", setup.doctor_room_id),
+        ("player1RoomId", setup.player1_room_id),
+        ("stranger1RoomId", setup.stranger1_room_id),
+        ("player2RoomId", setup.player2_room_id),
+        ("stranger2RoomId", setup.stranger2_room_id),
+    ];
+    for (label, room_id) in room_checks {
+        if !common
+            .board
+            .rooms
+            .contains_key(&core::room::RoomId(room_id))
+        {
+            return Err(format!("{label} must be a valid room id."));
+        }
     }
 
-    #[wasm_bindgen(js_name = "pieceEquivalentClovers")]
-    pub fn piece_equivalent_clovers(&self, piece_id: &str) -> f64 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0.0;
-        };
-        self.state.player_equivalent_clovers(player_id)
+    let strength_checks = [
+        ("player1Strength", setup.player1_strength),
+        ("stranger1Strength", setup.stranger1_strength),
+        ("player2Strength", setup.player2_strength),
+        ("stranger2Strength", setup.stranger2_strength),
+    ];
+    for (label, strength) in strength_checks {
+        if strength < 0 {
+            return Err(format!("{label} must be >= 0."));
+        }
     }
 
-    #[wasm_bindgen(js_name = "pieceAttackStrength")]
-    pub fn piece_attack_strength(&self, piece_id: &str) -> i32 {
-        let Some(player_id) = player_id_for_piece_id_str(piece_id) else {
-            return 0;
-        };
-        piece_attack_strength_for_state(&self.state, player_id)
+    if setup.turn_id < 1 {
+        return Err("turnId must be >= 1.".to_string());
     }
 
-    #[wasm_bindgen(js_name = "attackHistoryText")]
-    pub fn attack_history_text(&self) -> String {
-        attack_history_text_for_state(&self.state)
+    if setup.current_player_piece_id != PieceId::Player1
+        && setup.current_player_piece_id != PieceId::Player2
+    {
+        return Err("currentPlayerPieceId must be P1 or P3.".to_string());
     }
 
-    #[wasm_bindgen(js_name = "attackHistoryLabel")]
-    pub fn attack_history_label(&self) -> String {
-        attack_history_label_for_state(&self.state)
+    let seats = common.seat_map();
+    let mut labeled_piece_ids = HashSet::new();
+    for piece_label in &setup.piece_labels {
+        let piece_id = piece_label.piece_id;
+        if seats
+            .player_id(piece_id)
+            .is_none_or(|player_id| player_id.0 >= common.num_all_players)
+        {
+            return Err(format!(
+                "pieceLabels names {piece_id}, which isn't in this game."
+            ));
+        }
+        if !labeled_piece_ids.insert(piece_id) {
+            return Err(format!("pieceLabels names {piece_id} more than once."));
+        }
+        core::common_game_state::PlayerLabel::new(&piece_label.name, &piece_label.color)
+            .map_err(|message| format!("pieceLabels for {piece_id}: {message}"))?;
     }
 
-    #[wasm_bindgen(js_name = "playerStatsJson")]
-    pub fn player_stats_json(&self) -> String {
-        let has_strangers = self.state.common.has_strangers();
-        let stats = self
-            .state
-            .common
-            .player_ids()
-            .filter_map(|player_id| {
-                let piece_id = PieceId::from_player_id(player_id, has_strangers)?;
-                let idx = player_id.0;
-                Some(PlayerStats {
-                    piece_id: piece_id.as_str().to_string(),
-                    doctor_distance: self.state.doctor_moves_until_player_room(player_id),
-                    strength: self.state.player_strengths[idx],
-                    move_cards: self.state.player_move_cards[idx],
-                    weapon_cards: self.state.player_weapons[idx],
-                    failure_cards: self.state.player_failures[idx],
-                    equivalent_clovers: self.state.player_equivalent_clovers(player_id),
-                })
+    Ok(())
+}
+
+// labels that fail validation or name unseated pieces are left out
+fn player_labels_from_setup(
+    setup: &NormalSetup,
+    common: &core::common_game_state::CommonGameState,
+) -> Vec<(core::player::PlayerId, core::common_game_state::PlayerLabel)> {
+    let seats = common.seat_map();
+    setup
+        .piece_labels
+        .iter()
+        .filter_map(|piece_label| {
+            let player_id = seats.player_id(piece_label.piece_id)?;
+            let label =
+                core::common_game_state::PlayerLabel::new(&piece_label.name, &piece_label.color)
+                    .ok()?;
+            Some((player_id, label))
+        })
+        .collect::<Vec<_>>()
+}
+
+fn piece_labels_from_common(
+    common: &core::common_game_state::CommonGameState,
+) -> Vec<PieceLabelJson> {
+    let seats = common.seat_map();
+    seats
+        .display_order_player_ids()
+        .into_iter()
+        .filter_map(|player_id| {
+            let label = common.player_label(player_id)?;
+            if label.name.is_empty() && label.color.is_empty() {
+                return None;
+            }
+            Some(PieceLabelJson {
+                piece_id: seats.piece_id(player_id)?,
+                name: label.name.clone(),
+                color: label.color.clone(),
             })
-            .collect::<Vec<_>>();
+        })
+        .collect::<Vec<_>>()
+}
 
-        serde_json::to_string(&stats).unwrap_or_else(|_| "[]".to_string())
+fn apply_normal_setup_to_state(
+    state: &mut core::mutable_game_state::MutableGameState,
+    normal_setup: &NormalSetup,
+) {
+    state.doctor_room_id = core::room::RoomId(normal_setup.doctor_room_id);
+    // the setup only names the first two strangers; any others keep their starting values
+    let seats = state.common.seat_map();
+    let piece_setups = [
+        (
+            PieceId::Player1,
+            normal_setup.player1_room_id,
+            normal_setup.player1_strength,
+        ),
+        (
+            PieceId::Stranger1,
+            normal_setup.stranger1_room_id,
+            normal_setup.stranger1_strength,
+        ),
+        (
+            PieceId::Player2,
+            normal_setup.player2_room_id,
+            normal_setup.player2_strength,
+        ),
+        (
+            PieceId::Stranger2,
+            normal_setup.stranger2_room_id,
+            normal_setup.stranger2_strength,
+        ),
+    ];
+    for (piece_id, room_id, strength) in piece_setups {
+        if let Some(player_id) = seats.player_id(piece_id)
+            && let Some(player_strength) = state.player_strengths.get_mut(player_id.0)
+        {
+            *player_strength = strength;
+            state.move_piece(player_id, core::room::RoomId(room_id));
+        }
     }
+    state.turn_id = normal_setup.turn_id;
+    state.current_player_id = state
+        .common
+        .seat_map()
+        .player_id(normal_setup.current_player_piece_id)
+        .unwrap_or(core::rule_helper::SIDE_A_NORMAL_PLAYER_ID);
 
-    #[wasm_bindgen(js_name = "boardRoomsJson")]
-    pub fn board_rooms_json(&self) -> String {
-        let rooms = self
-            .state
-            .common
-            .board
-            .room_ids
-            .iter()
-            .filter_map(|room_id| self.state.common.board.rooms.get(room_id))
-            .map(|room| BoardRoomInfo {
-                id: room.id.0,
-                name: room.name.clone(),
-                adjacent: room.adjacent.iter().map(|id| id.0).collect::<Vec<_>>(),
-                visible: room.visible.iter().map(|id| id.0).collect::<Vec<_>>(),
+    let side_a_idx = core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0;
+    state.player_move_cards[side_a_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.move_cards);
+    state.player_weapons[side_a_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.weapon_cards);
+    state.player_failures[side_a_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.failure_cards);
+    let side_b_idx = seats.normal_of(core::seat_map::Side::B).0;
+    state.player_move_cards[side_b_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.player2_move_cards);
+    state.player_weapons[side_b_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.player2_weapon_cards);
+    state.player_failures[side_b_idx] =
+        core::card_count::CardCount::from_f64(normal_setup.player2_failure_cards);
+}
+
+// the setup that rebuilds a position that didn't come from one, like a puzzle's; cards of
+// strangers aren't part of a setup
+fn normal_setup_from_state(state: &core::mutable_game_state::MutableGameState) -> NormalSetup {
+    let seats = state.common.seat_map();
+    let room_and_strength = |piece_id| {
+        seats
+            .player_id(piece_id)
+            .map(|player_id| {
+                (
+                    state.player_room_ids()[player_id.0].0,
+                    state.player_strengths[player_id.0],
+                )
             })
-            .collect::<Vec<_>>();
+            .unwrap_or((0, default_player_strength()))
+    };
+    let (player1_room_id, player1_strength) = room_and_strength(PieceId::Player1);
+    let (stranger1_room_id, stranger1_strength) = room_and_strength(PieceId::Stranger1);
+    let (player2_room_id, player2_strength) = room_and_strength(PieceId::Player2);
+    let (stranger2_room_id, stranger2_strength) = room_and_strength(PieceId::Stranger2);
+    let side_a_idx = core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0;
+    let side_b_idx = seats.normal_of(core::seat_map::Side::B).0;
 
-        serde_json::to_string(&rooms).unwrap_or_else(|_| "[]".to_string())
+    NormalSetup {
+        board_name: state.common.board.json_name.clone(),
+        move_cards: state.player_move_cards[side_a_idx].to_f64(),
+        weapon_cards: state.player_weapons[side_a_idx].to_f64(),
+        failure_cards: state.player_failures[side_a_idx].to_f64(),
+        player2_move_cards: state.player_move_cards[side_b_idx].to_f64(),
+        player2_weapon_cards: state.player_weapons[side_b_idx].to_f64(),
+        player2_failure_cards: state.player_failures[side_b_idx].to_f64(),
+        doctor_room_id: state.doctor_room_id.0,
+        player1_room_id,
+        stranger1_room_id,
+        player2_room_id,
+        stranger2_room_id,
+        player1_strength,
+        stranger1_strength,
+        player2_strength,
+        stranger2_strength,
+        turn_id: state.turn_id,
+        current_player_piece_id: seats
+            .piece_id(state.current_player_id)
+            .unwrap_or_else(default_current_player_piece_id),
+        piece_labels: piece_labels_from_common(&state.common),
     }
+}
 
-    #[wasm_bindgen(js_name = "reachableRooms")]
-    pub fn reachable_rooms(&self, piece_id: &str, steps: i32) -> Vec<u32> {
-        let Some(piece_id) = PieceId::parse(piece_id) else {
-            return Vec::new();
-        };
-        let steps = steps.max(0);
-        let room_id = match piece_id {
-            PieceId::Doctor => self.state.doctor_room_id,
-            _ => {
-                let Some(player_id) = piece_id.to_player_id() else {
-                    return Vec::new();
-                };
-                let Some(room_id) = self.state.player_room_ids.get(player_id.0) else {
-                    return Vec::new();
-                };
-                *room_id
-            }
-        };
+fn new_state_with_normal_setup(
+    common: core::common_game_state::CommonGameState,
+    normal_setup: &NormalSetup,
+) -> core::mutable_game_state::MutableGameState {
+    let normalized_setup = normalize_normal_setup(normal_setup, &common);
+    let player_labels = player_labels_from_setup(&normalized_setup, &common);
+    let common = common.with_player_labels(player_labels);
+    let mut state = core::mutable_game_state::MutableGameState::at_start(common);
+    apply_normal_setup_to_state(&mut state, &normalized_setup);
+    state
+}
 
-        self.state
-            .common
-            .board
-            .room_ids
-            .iter()
-            .filter(|dest_room_id| {
-                self.state.common.board.distance[room_id.0][dest_room_id.0] <= steps
-            })
-            .map(|dest_room_id| dest_room_id.0 as u32)
-            .collect::<Vec<_>>()
+fn snap_card_quantity_to_thirty_seconds(value: f64) -> f64 {
+    (value * 32.0).round() / 32.0
+}
+
+fn snapped_normal_setup(setup: &NormalSetup) -> NormalSetup {
+    NormalSetup {
+        board_name: setup.board_name.clone(),
+        move_cards: snap_card_quantity_to_thirty_seconds(setup.move_cards),
+        weapon_cards: snap_card_quantity_to_thirty_seconds(setup.weapon_cards),
+        failure_cards: snap_card_quantity_to_thirty_seconds(setup.failure_cards),
+        player2_move_cards: snap_card_quantity_to_thirty_seconds(setup.player2_move_cards),
+        player2_weapon_cards: snap_card_quantity_to_thirty_seconds(setup.player2_weapon_cards),
+        player2_failure_cards: snap_card_quantity_to_thirty_seconds(setup.player2_failure_cards),
+        doctor_room_id: setup.doctor_room_id,
+        player1_room_id: setup.player1_room_id,
+        stranger1_room_id: setup.stranger1_room_id,
+        player2_room_id: setup.player2_room_id,
+        stranger2_room_id: setup.stranger2_room_id,
+        player1_strength: setup.player1_strength,
+        stranger1_strength: setup.stranger1_strength,
+        player2_strength: setup.player2_strength,
+        stranger2_strength: setup.stranger2_strength,
+        turn_id: setup.turn_id,
+        current_player_piece_id: setup.current_player_piece_id,
+        piece_labels: setup.piece_labels.clone(),
     }
+}
 
-    #[wasm_bindgen(js_name = "undoLastTurn")]
-    pub fn undo_last_turn(&mut self) -> bool {
-        loop {
-            let prev_state = self
-                .state
-                .prev_state
-                .as_ref()
-                .map(|state| state.as_ref().clone());
+fn is_matching_board_name(
+    saved_board_name: &str,
+    current_board_name: &str,
+    current_board_json_name: &str,
+) -> bool {
+    saved_board_name == current_board_name || saved_board_name == current_board_json_name
+}
 
-            let Some(prev_state) = prev_state else {
-                return false;
-            };
+fn is_legacy_or_matching_board_name(
+    saved_board_name: &str,
+    current_board_name: &str,
+    current_board_json_name: &str,
+) -> bool {
+    saved_board_name.is_empty()
+        || is_matching_board_name(
+            saved_board_name,
+            current_board_name,
+            current_board_json_name,
+        )
+}
 
-            self.state = prev_state;
+fn normal_piece_id_for_state(state: &core::mutable_game_state::MutableGameState) -> PieceId {
+    let seats = state.common.seat_map();
+    seats
+        .piece_id(seats.to_normal_player_id(state.current_player_id))
+        .unwrap_or(PieceId::Player2)
+}
 
-            if self.state.is_normal_turn() {
-                return true;
-            }
-        }
-    }
+fn winner_piece_id_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+) -> Option<PieceId> {
+    let winner = state.result.winner()?;
+    let seats = state.common.seat_map();
+    seats.piece_id(seats.to_normal_player_id(winner))
+}
 
-    #[wasm_bindgen(js_name = "resetGame")]
-    pub fn reset_game(&mut self) {
-        let common = self.state.common.clone();
-        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+fn invalid_attack_simulation_json(message: String) -> String {
+    serde_json::to_string(&AttackSimulationJson {
+        is_valid: false,
+        validation_message: message,
+        attacker_piece_id: String::new(),
+        allowed_now: false,
+        base_strength: 0,
+        weapon_used: false,
+        attack_strength: 0.0,
+        defender_cards: Vec::new(),
+        outcome: String::new(),
+    })
+    .unwrap_or_default()
+}
+
+fn attack_simulation_json_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    attacker_piece_id: &str,
+) -> String {
+    use core::mutable_game_state::DefenseCardType;
+
+    let Some(piece_id) = PieceId::parse(attacker_piece_id) else {
+        return invalid_attack_simulation_json(format!(
+            "Unknown attacker piece id '{attacker_piece_id}'."
+        ));
+    };
+    let Some(attacker) = state
+        .common
+        .seat_map()
+        .player_id(piece_id)
+        .filter(|player_id| player_id.0 < state.common.num_all_players)
+    else {
+        return invalid_attack_simulation_json(format!(
+            "Piece '{attacker_piece_id}' is not in this game."
+        ));
+    };
+    if let Some(message) = game_over_message(state) {
+        return invalid_attack_simulation_json(message);
     }
 
-    #[wasm_bindgen(js_name = "loadBoard")]
-    pub fn load_board(&mut self, board_name: &str) -> String {
-        match new_game_state_for_board(board_name) {
-            Ok(next) => {
-                self.state = next.state;
-                self.normal_setup = next.normal_setup;
-                String::new()
+    let seats = state.common.seat_map();
+    let piece_id_text = |player_id| {
+        seats
+            .piece_id(player_id)
+            .map(|piece_id| piece_id.as_str().to_string())
+            .unwrap_or_default()
+    };
+    let simulation = state.simulate_attack(attacker);
+    let defender_cards = simulation
+        .card_spends
+        .iter()
+        .map(|spend| DefenderCardJson {
+            defender_piece_id: piece_id_text(spend.defender),
+            card_type: match spend.card_type {
+                DefenseCardType::Failure => "failure",
+                DefenseCardType::Weapon => "weapon",
+                DefenseCardType::MoveCard => "moveCard",
             }
-            Err(err) => err
-                .as_string()
-                .unwrap_or_else(|| "Failed to load board.".to_string()),
+            .to_string(),
+            cards_spent: spend.cards_spent.to_f64(),
+            strength_remaining: spend.strength_remaining.to_f64(),
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&AttackSimulationJson {
+        is_valid: true,
+        validation_message: String::new(),
+        attacker_piece_id: piece_id_text(attacker),
+        allowed_now: simulation.allowed_now,
+        base_strength: simulation.base_strength,
+        weapon_used: simulation.weapon_used,
+        attack_strength: simulation.attack_strength.to_f64(),
+        defender_cards,
+        outcome: if simulation.succeeded {
+            "success"
+        } else {
+            "failure"
         }
-    }
+        .to_string(),
+    })
+    .unwrap_or_default()
+}
 
-    #[wasm_bindgen(js_name = "normalTurnHistory")]
-    pub fn normal_turn_history(&self) -> String {
-        self.state.normal_turn_hist()
+fn state_change_json(
+    state: &core::mutable_game_state::MutableGameState,
+    kind: &'static str,
+) -> String {
+    serde_json::to_string(&StateChangeJson {
+        kind,
+        ply: state.ply(),
+        turn_id: state.turn_id,
+        current_player_piece_id: normal_piece_id_for_state(state).as_str().to_string(),
+        result: state.result.code(),
+    })
+    .unwrap_or_default()
+}
+
+// the error for a plan submitted while another piece holds the seat lock
+fn seat_lock_error(
+    state: &core::mutable_game_state::MutableGameState,
+    locked_piece_id: Option<PieceId>,
+) -> Option<String> {
+    let locked_piece_id = locked_piece_id?;
+    let current_piece_id = normal_piece_id_for_state(state);
+    (current_piece_id != locked_piece_id).then(|| {
+        let seats = state.common.seat_map();
+        let display_text = |piece_id| {
+            seats
+                .player_id(piece_id)
+                .map(|player_id| state.common.player_display_text(player_id))
+                .unwrap_or_else(|| piece_id.to_string())
+        };
+        format!(
+            "It is {}'s turn, but this device is locked to {}.",
+            display_text(current_piece_id),
+            display_text(locked_piece_id)
+        )
+    })
+}
+
+// set when the move now belongs to a piece other than the locked one and the game goes on
+fn seat_handoff_json(
+    state: &core::mutable_game_state::MutableGameState,
+    locked_piece_id: Option<PieceId>,
+) -> Option<String> {
+    let locked_piece_id = locked_piece_id?;
+    let next_piece_id = normal_piece_id_for_state(state);
+    if state.is_game_over() || next_piece_id == locked_piece_id {
+        return None;
     }
+    serde_json::to_string(&SeatHandoffJson {
+        locked_piece_id: locked_piece_id.as_str(),
+        next_piece_id: next_piece_id.as_str(),
+        next_display_text: state
+            .common
+            .player_display_text(state.common.to_normal_player_id(state.current_player_id)),
+        turn_id: state.turn_id,
+    })
+    .ok()
+}
 
-    #[wasm_bindgen(js_name = "prevTurnSummaryVerbose")]
-    pub fn prev_turn_summary_verbose(&self) -> String {
-        self.state.prev_turn_summaries_since_normal(true)
+// no warning once the game is over, since no loop can matter then
+fn stranger_loop_warning_room_ids(
+    state: &core::mutable_game_state::MutableGameState,
+    analysis_player_id: core::player::PlayerId,
+) -> Vec<usize> {
+    if state.is_game_over() {
+        return Vec::new();
     }
+    state
+        .stranger_loop_warning_room_ids(analysis_player_id)
+        .into_iter()
+        .map(|room_id| room_id.0)
+        .collect::<Vec<_>>()
+}
 
-    #[wasm_bindgen(js_name = "animationFrames")]
-    pub fn animation_frames(&self) -> Vec<u32> {
-        let frames = self.state.animation_frames_since_normal();
-        let mut flat = Vec::with_capacity(frames.len() * 5);
+fn game_over_message(state: &core::mutable_game_state::MutableGameState) -> Option<String> {
+    if state.has_winner() {
+        return Some("Game already has a winner.".to_string());
+    }
+    if state.is_aborted() {
+        return Some("Game was aborted.".to_string());
+    }
+    state
+        .draw_reason()
+        .map(|draw_reason| format!("Game already ended in a draw ({draw_reason})."))
+}
 
-        for frame in frames {
-            flat.push(frame[0].0 as u32);
-            flat.push(frame[1].0 as u32);
-            flat.push(frame[2].0 as u32);
-            flat.push(frame[3].0 as u32);
-            flat.push(frame[4].0 as u32);
-        }
+fn piece_attack_strength_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    player_id: core::player::PlayerId,
+) -> i32 {
+    let idx = player_id.0;
+    let weapon_bonus = if state.common.get_player_type(player_id)
+        == core::player::PlayerType::Normal
+        && state.player_weapons[idx] >= core::card_count::CardCount::whole(1)
+    {
+        2
+    } else {
+        0
+    };
+    state.player_strengths[idx] + weapon_bonus
+}
 
-        flat
+fn display_turn_id_for_state(state: &core::mutable_game_state::MutableGameState) -> i32 {
+    if state.is_normal_turn() {
+        state.ply() + 1
+    } else {
+        state.ply()
     }
+}
 
-    #[wasm_bindgen(js_name = "validateTurnPlan")]
-    pub fn validate_turn_plan(&self, turn_plan_json: &str) -> String {
-        let turn = match parse_turn_plan(turn_plan_json) {
-            Ok(turn) => turn,
-            Err(message) => return message,
-        };
-
-        match self.state.check_normal_turn(&turn) {
-            Ok(()) => String::new(),
-            Err(message) => message,
+fn last_turn_json_for_state(state: &core::mutable_game_state::MutableGameState) -> String {
+    // states back to the one the last normal turn was taken from, latest first
+    let mut states = vec![state];
+    while let Some(prev_state) = states[states.len() - 1].prev_state.as_deref() {
+        states.push(prev_state);
+        if prev_state.is_normal_turn() {
+            break;
         }
     }
-
-    #[wasm_bindgen(js_name = "applyTurnPlan")]
-    pub fn apply_turn_plan(&mut self, turn_plan_json: &str) -> String {
-        let turn = match parse_turn_plan(turn_plan_json) {
-            Ok(turn) => turn,
-            Err(message) => return message,
-        };
-
-        if let Err(message) = self.state.check_normal_turn(&turn) {
-            return message;
+    let last_turn = match states.as_slice() {
+        [.., after_turn, before_turn] if before_turn.is_normal_turn() => {
+            let seats = state.common.seat_map();
+            let board = &state.common.board;
+            let moves = after_turn
+                .prev_turn
+                .moves
+                .iter()
+                .map(|mv| {
+                    let from_room_id = before_turn.player_room_ids()[mv.player_id.0];
+                    LastTurnMoveJson {
+                        piece_id: piece_id_text(&seats, mv.player_id),
+                        from_room_id: from_room_id.0,
+                        to_room_id: mv.dest_room_id.0,
+                        distance: board.distance[(from_room_id, mv.dest_room_id)],
+                    }
+                })
+                .collect::<Vec<_>>();
+            let action = after_turn.prev_turn_action();
+            let actions = states
+                .iter()
+                .rev()
+                .skip(1)
+                .filter_map(|state| {
+                    let prev_state = state.prev_state.as_deref()?;
+                    Some(LastTurnActionJson {
+                        piece_id: piece_id_text(&seats, prev_state.current_player_id),
+                        action: player_action_text(state.prev_turn_action()).to_string(),
+                    })
+                })
+                .collect::<Vec<_>>();
+            LastTurnJson {
+                is_valid: true,
+                validation_message: String::new(),
+                ply: before_turn.ply() as usize + 1,
+                piece_id: piece_id_text(&seats, before_turn.current_player_id),
+                turn_text: after_turn.prev_turn.to_string(),
+                total_distance: moves.iter().map(|mv| mv.distance).sum(),
+                moves,
+                action: player_action_text(action).to_string(),
+                attack_succeeded: action == core::player::PlayerAction::Attack
+                    && after_turn.successful_attacks != before_turn.successful_attacks,
+                doctor_room_id_before: before_turn.doctor_room_id.0,
+                doctor_room_id_after: state.doctor_room_id.0,
+                doctor_room_ids: states
+                    .iter()
+                    .rev()
+                    .map(|state| state.doctor_room_id.0)
+                    .collect::<Vec<_>>(),
+                actions,
+            }
         }
+        _ => LastTurnJson {
+            validation_message: "No turn has been played yet.".to_string(),
+            ..LastTurnJson::default()
+        },
+    };
+    serde_json::to_string(&last_turn).unwrap_or_default()
+}
 
-        self.state.apply_turn(turn);
-        String::new()
+fn piece_id_text(seats: &SeatMap, player_id: core::player::PlayerId) -> String {
+    seats
+        .piece_id(player_id)
+        .map(|piece_id| piece_id.as_str().to_string())
+        .unwrap_or_default()
+}
+
+fn player_action_text(action: core::player::PlayerAction) -> &'static str {
+    match action {
+        core::player::PlayerAction::Attack => "attack",
+        core::player::PlayerAction::Loot => "loot",
+        core::player::PlayerAction::None => "none",
     }
+}
 
-    #[wasm_bindgen(js_name = "previewTurnPlan")]
-    pub fn preview_turn_plan(&self, turn_plan_json: &str) -> String {
-        let turn = match parse_turn_plan(turn_plan_json) {
-            Ok(turn) => turn,
-            Err(message) => return invalid_preview_json(message),
+fn new_attacker_piece_ids(
+    state: &core::mutable_game_state::MutableGameState,
+    prior_attack_count: usize,
+) -> Vec<String> {
+    let seats = state.common.seat_map();
+    let mut seen_attackers = HashSet::new();
+    let mut attackers = Vec::new();
+    for attack in state.attack_hist.iter().skip(prior_attack_count) {
+        let Some(piece_id) = seats.piece_id(attack.attacker) else {
+            continue;
         };
-
-        if let Err(message) = self.state.check_normal_turn(&turn) {
-            return invalid_preview_json(message);
+        let piece_id = piece_id.as_str().to_string();
+        if seen_attackers.insert(piece_id.clone()) {
+            attackers.push(piece_id);
         }
+    }
+    attackers
+}
 
-        let current_player_loots = current_player_loots_after_turn(&self.state, &turn);
-        let prior_attack_count = self.state.attacker_hist.len();
-        let preview_state = self.state.after_turn(turn);
-
-        let mut seen_attackers = HashSet::new();
-        let mut attackers = Vec::new();
-        let has_strangers = preview_state.common.has_strangers();
-        for player_id in preview_state.attacker_hist.iter().skip(prior_attack_count) {
-            let Some(piece_id) = PieceId::from_player_id(*player_id, has_strangers) else {
-                continue;
-            };
-            let piece_id = piece_id.as_str().to_string();
-            if seen_attackers.insert(piece_id.clone()) {
-                attackers.push(piece_id);
-            }
-        }
+fn attack_history_text_for_state(state: &core::mutable_game_state::MutableGameState) -> String {
+    let mut attacks = Vec::new();
+    let mut current_state = state;
 
-        let mut moved_strangers = Vec::new();
-        if preview_state.common.has_strangers() {
-            let stranger_rooms = [
-                (
-                    core::rule_helper::STRANGER_PLAYER_ID_FIRST,
-                    PieceId::Stranger1,
-                ),
-                (
-                    core::rule_helper::STRANGER_PLAYER_ID_SECOND,
-                    PieceId::Stranger2,
-                ),
-            ];
-            for (player_id, piece_id) in stranger_rooms {
-                let current_room_id = self.state.player_room_ids[player_id.0].0;
-                let preview_room_id = preview_state.player_room_ids[player_id.0].0;
-                if current_room_id != preview_room_id {
-                    moved_strangers.push(PreviewPieceRoom {
-                        piece_id: piece_id.as_str().to_string(),
-                        room_id: preview_room_id,
-                    });
-                }
-            }
+    while let Some(prev_state) = current_state.prev_state.as_deref() {
+        if current_state.attack_hist.len() > prev_state.attack_hist.len() {
+            let attacker = prev_state.current_player_id;
+            let player_num =
+                core::common_game_state::CommonGameState::to_player_display_num(attacker);
+            let room_id = current_state.player_room_ids()[attacker.0].0;
+            attacks.push(format!(
+                "{player_num}@{room_id},T{}",
+                display_turn_id_for_state(prev_state)
+            ));
         }
-        let winner_piece_id = winner_piece_id_for_state(&preview_state);
 
-        to_preview_json(&TurnPlanPreview {
-            is_valid: true,
-            validation_message: String::new(),
-            next_player_piece_id: normal_piece_id_for_state(&preview_state)
-                .as_str()
-                .to_string(),
-            has_winner: winner_piece_id.is_some(),
-            winner_piece_id: winner_piece_id
-                .map(|piece_id| piece_id.as_str().to_string())
-                .unwrap_or_default(),
-            attackers,
-            current_player_loots,
-            doctor_room_id: preview_state.doctor_room_id.0,
-            moved_strangers,
-        })
+        current_state = prev_state;
     }
 
-    #[wasm_bindgen(js_name = "findBestTurn")]
+    attacks.reverse();
+    attacks.join("; ")
+}
+
+fn attack_history_entries(
+    state: &core::mutable_game_state::MutableGameState,
+) -> Vec<AttackHistoryEntry> {
+    let seats = state.common.seat_map();
+    state
+        .attack_hist
+        .iter()
+        .map(|attack| attack_history_entry(&seats, attack))
+        .collect::<Vec<_>>()
+}
+
+fn attack_history_entry(
+    seats: &SeatMap,
+    attack: &core::mutable_game_state::AttackRecord,
+) -> AttackHistoryEntry {
+    AttackHistoryEntry {
+        turn_id: attack.turn_id,
+        piece_id: piece_id_text(seats, attack.attacker),
+        strength: attack.strength.to_f64(),
+        defense_spent: attack.defense_spent.to_f64(),
+        succeeded: attack.succeeded,
+    }
+}
+
+fn state_diff_json(
+    diff: Result<core::state_diff::StateDiff, String>,
+    common: &core::common_game_state::CommonGameState,
+) -> String {
+    let seats = common.seat_map();
+    let json = match diff {
+        Ok(diff) => StateDiffJson {
+            is_valid: true,
+            validation_message: String::new(),
+            from_ply: diff.from_ply,
+            to_ply: diff.to_ply,
+            doctor_from_room_id: diff.doctor_from_room_id.0,
+            doctor_to_room_id: diff.doctor_to_room_id.0,
+            piece_moves: diff
+                .piece_moves
+                .iter()
+                .map(|mv| StateDiffMoveJson {
+                    piece_id: piece_id_text(&seats, mv.player_id),
+                    from_room_id: mv.from_room_id.0,
+                    to_room_id: mv.to_room_id.0,
+                })
+                .collect::<Vec<_>>(),
+            card_deltas: diff
+                .card_deltas
+                .iter()
+                .map(|delta| StateDiffCardsJson {
+                    piece_id: piece_id_text(&seats, delta.player_id),
+                    move_cards: delta.move_cards.to_f64(),
+                    weapons: delta.weapons.to_f64(),
+                    failures: delta.failures.to_f64(),
+                })
+                .collect::<Vec<_>>(),
+            strength_deltas: diff
+                .strength_deltas
+                .iter()
+                .map(|delta| StateDiffStrengthJson {
+                    piece_id: piece_id_text(&seats, delta.player_id),
+                    from_strength: delta.from_strength,
+                    to_strength: delta.to_strength,
+                })
+                .collect::<Vec<_>>(),
+            attacks: diff
+                .attacks
+                .iter()
+                .map(|attack| attack_history_entry(&seats, attack))
+                .collect::<Vec<_>>(),
+            undone_attacks: diff
+                .undone_attacks
+                .iter()
+                .map(|attack| attack_history_entry(&seats, attack))
+                .collect::<Vec<_>>(),
+        },
+        Err(message) => return invalid_state_diff_json(message),
+    };
+    serde_json::to_string(&json).unwrap_or_default()
+}
+
+fn invalid_state_diff_json(message: String) -> String {
+    serde_json::to_string(&StateDiffJson {
+        validation_message: message,
+        ..StateDiffJson::default()
+    })
+    .unwrap_or_default()
+}
+
+fn attack_history_label_for_state(state: &core::mutable_game_state::MutableGameState) -> String {
+    let mut side_a_attacks = 0;
+    let mut side_b_attacks = 0;
+
+    let seats = state.common.seat_map();
+    for attack in &state.attack_hist {
+        match seats.side_of(attack.attacker) {
+            Some(core::seat_map::Side::A) => side_a_attacks += 1,
+            Some(core::seat_map::Side::B) => side_b_attacks += 1,
+            None => {}
+        }
+    }
+
+    format!("Atks{side_a_attacks},{side_b_attacks}")
+}
+
+// a panic that reaches js takes the wasm instance, and the user's game, down with it. this catches
+// it on targets that unwind; wasm32 builds abort on panic, so there the checked lookups in front of
+// every index are what keep the game alive
+fn catch_panic<T>(body: impl FnOnce() -> T) -> Result<T, String> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(body)).map_err(|payload| {
+        payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    })
+}
+
+fn internal_error_text(message: &str) -> String {
+    format!("Internal error ({message}); the game was left as it was before this call.")
+}
+
+fn to_preview_json(preview: &TurnPlanPreview) -> String {
+    serde_json::to_string(preview).unwrap_or_else(|_| {
+        "{\"isValid\":false,\"validationMessage\":\"Preview serialization failed.\",\"nextPlayerPieceId\":\"\",\"hasWinner\":false,\"winnerPieceId\":\"\",\"attackers\":[],\"currentPlayerLoots\":false,\"doctorRoomId\":0,\"movedStrangers\":[]}".to_string()
+    })
+}
+
+fn invalid_turn_packet_json(message: String) -> String {
+    serde_json::to_string(&TurnPacketResponse {
+        is_valid: false,
+        validation_message: message,
+        packet: None,
+    })
+    .unwrap_or_default()
+}
+
+// position_key hashes stably, so a wasm build and a native one agree on the same position
+fn position_key_text(state: &core::mutable_game_state::MutableGameState) -> String {
+    format!("{:016x}", state.position_key())
+}
+
+fn next_turn_sequence(state: &core::mutable_game_state::MutableGameState) -> usize {
+    state.ply() as usize + 1
+}
+
+// everything about a packet but its plan, which is checked like any other
+fn check_turn_packet(
+    state: &core::mutable_game_state::MutableGameState,
+    session_id: &str,
+    packet: &TurnPacket,
+) -> Result<(), String> {
+    if session_id.is_empty() {
+        return Err("No remote session is set.".to_string());
+    }
+    if packet.session_id != session_id {
+        return Err(format!(
+            "Turn packet is for session '{}', not '{session_id}'.",
+            packet.session_id
+        ));
+    }
+    let next_sequence = next_turn_sequence(state);
+    if packet.sequence < next_sequence {
+        return Err(format!(
+            "Turn packet {} is stale; the game is waiting for packet {next_sequence}.",
+            packet.sequence
+        ));
+    }
+    if packet.sequence > next_sequence {
+        return Err(format!(
+            "Turn packet {} skips ahead; the game is waiting for packet {next_sequence}.",
+            packet.sequence
+        ));
+    }
+    if packet.position_key != position_key_text(state) {
+        return Err(format!(
+            "Turn packet {} was made from a different position; the two ends are out of sync.",
+            packet.sequence
+        ));
+    }
+    Ok(())
+}
+
+fn invalid_preview(message: String) -> TurnPlanPreview {
+    TurnPlanPreview {
+        is_valid: false,
+        validation_message: message,
+        next_player_piece_id: String::new(),
+        has_winner: false,
+        winner_piece_id: String::new(),
+        attackers: Vec::new(),
+        current_player_loots: false,
+        doctor_room_id: 0,
+        doctor_room_label: String::new(),
+        moved_strangers: Vec::new(),
+        stranger_loop_warning: false,
+        stranger_loop_room_ids: Vec::new(),
+    }
+}
+
+fn to_best_turn_analysis_json(response: &BestTurnAnalysisResponse) -> String {
+    serde_json::to_string(response).unwrap_or_else(|_| {
+        "{\"isValid\":false,\"validationMessage\":\"Best turn serialization failed.\",\"suggestedTurnText\":\"\",\"suggestedTurn\":[],\"heuristicScore\":0,\"numStatesVisited\":0,\"elapsedMs\":0}".to_string()
+    })
+}
+
+fn invalid_best_turn_analysis_json(
+    message: String,
+    num_states_visited: usize,
+    elapsed_ms: f64,
+) -> String {
+    to_best_turn_analysis_json(&BestTurnAnalysisResponse {
+        is_valid: false,
+        validation_message: message,
+        suggested_turn_text: String::new(),
+        suggested_turn: Vec::new(),
+        heuristic_score: 0.0,
+        num_states_visited,
+        search_stats: None,
+        completed_level: None,
+        elapsed_ms,
+    })
+}
+
+fn invalid_best_turns_analysis_json(
+    message: String,
+    num_states_visited: usize,
+    elapsed_ms: f64,
+) -> String {
+    serde_json::to_string(&BestTurnsAnalysisResponse {
+        is_valid: false,
+        validation_message: message,
+        turns: Vec::new(),
+        num_states_visited,
+        elapsed_ms,
+    })
+    .unwrap_or_else(|_| {
+        "{\"isValid\":false,\"validationMessage\":\"Best turns serialization failed.\",\"turns\":[],\"numStatesVisited\":0,\"elapsedMs\":0}".to_string()
+    })
+}
+
+fn to_turn_plan_cost_json(cost: &TurnPlanCost) -> String {
+    serde_json::to_string(cost).unwrap_or_else(|_| {
+        "{\"isValid\":false,\"validationMessage\":\"Turn plan cost serialization failed.\",\"totalDistance\":0,\"moveCardsSpent\":0,\"moveCardsRemaining\":0,\"moves\":[]}".to_string()
+    })
+}
+
+fn invalid_turn_plan_cost_json(message: String) -> String {
+    to_turn_plan_cost_json(&TurnPlanCost {
+        is_valid: false,
+        validation_message: message,
+        total_distance: 0,
+        move_cards_spent: 0.0,
+        move_cards_remaining: 0.0,
+        moves: Vec::new(),
+    })
+}
+
+fn turn_plan_cost_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    turn: &core::simple_turn::SimpleTurn,
+) -> Result<TurnPlanCost, String> {
+    let seats = state.common.seat_map();
+    let mut moves = Vec::with_capacity(turn.moves.len());
+    for mv in &turn.moves {
+        let (Some(piece_id), Some(from_room_id)) = (
+            seats.piece_id(mv.player_id),
+            state.player_room_ids().get(mv.player_id.0),
+        ) else {
+            return Err(format!("{} is not in this game.", mv.player_id));
+        };
+        if !state.common.board.room_ids.contains(&mv.dest_room_id) {
+            return Err(format!("invalid roomId {}", mv.dest_room_id.0));
+        }
+
+        moves.push(TurnPlanMoveCost {
+            piece_id: piece_id.as_str().to_string(),
+            from_room_id: from_room_id.0,
+            room_id: mv.dest_room_id.0,
+            distance: state.move_distance(mv),
+        });
+    }
+
+    let total_distance = state.turn_total_distance(turn);
+    let move_cards_spent = state.turn_move_cards_needed(turn);
+    let move_cards_remaining =
+        (state.player_move_cards[state.current_player_id.0] - move_cards_spent).to_f64();
+    let move_cards_spent = move_cards_spent.to_f64();
+    let validation_message = state
+        .check_normal_turn(turn)
+        .err()
+        .map(|err| err.to_string())
+        .unwrap_or_default();
+
+    Ok(TurnPlanCost {
+        is_valid: validation_message.is_empty(),
+        validation_message,
+        total_distance,
+        move_cards_spent,
+        move_cards_remaining,
+        moves,
+    })
+}
+
+fn side_appraisal_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    player_id: core::player::PlayerId,
+    analysis_level: i32,
+    search_config: &core::tree_search::SearchConfig,
+) -> f64 {
+    if state.is_game_over() {
+        return state.heuristic_score(player_id);
+    }
+
+    let token = util::cancellation::NeverCancelToken;
+    let mut num_states_visited = 0usize;
+    let appraised_turn = core::tree_search::TreeSearch::find_best_turn_with_config(
+        state,
+        analysis_level,
+        search_config,
+        &token,
+        &mut num_states_visited,
+    );
+    if state.common.to_normal_player_id(state.current_player_id) == player_id {
+        appraised_turn.appraisal
+    } else {
+        -appraised_turn.appraisal
+    }
+}
+
+fn best_turn_analysis_json_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    analysis_level: i32,
+    search_config: &core::tree_search::SearchConfig,
+) -> String {
+    deepening_analysis_json_for_state(state, analysis_level, None, search_config).0
+}
+
+// the json and, when the search finished, its appraisal for the next level to start from
+fn deepening_analysis_json_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    analysis_level: i32,
+    prev_appraisal: Option<f64>,
+    search_config: &core::tree_search::SearchConfig,
+) -> (String, Option<f64>) {
+    if let Some(message) = game_over_message(state) {
+        return (invalid_best_turn_analysis_json(message, 0, 0.0), None);
+    }
+
+    let analysis_level = analysis_level.max(0);
+    let token = util::cancellation::NeverCancelToken;
+    let mut search_stats = core::tree_search::SearchStats::default();
+    let started_ms = now_ms();
+    let appraised_turn = core::tree_search::TreeSearch::find_best_turn_deepening(
+        state,
+        analysis_level,
+        prev_appraisal,
+        search_config,
+        &token,
+        &mut search_stats,
+    );
+    let elapsed_ms = (now_ms() - started_ms).max(0.0);
+    let num_states_visited = search_stats.num_states_visited;
+
+    let suggested_turn = match suggested_turn_entries(state, &appraised_turn.turn) {
+        Ok(suggested_turn) => suggested_turn,
+        Err(message) => {
+            return (
+                invalid_best_turn_analysis_json(message, num_states_visited, elapsed_ms),
+                None,
+            );
+        }
+    };
+
+    let json = to_best_turn_analysis_json(&BestTurnAnalysisResponse {
+        is_valid: true,
+        validation_message: String::new(),
+        suggested_turn_text: appraised_turn.turn.to_string(),
+        suggested_turn,
+        heuristic_score: appraised_turn.appraisal,
+        num_states_visited,
+        search_stats: search_stats.used_aspiration_window.then_some(search_stats),
+        completed_level: None,
+        elapsed_ms,
+    });
+    (json, Some(appraised_turn.appraisal))
+}
+
+// deepens until budget_ms runs out or max_level is done. a level the deadline cuts short is thrown
+// away, so the turn comes from the deepest finished level; level 1 ignores the deadline so there
+// is always a turn
+fn timed_analysis_json_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    budget_ms: f64,
+    max_level: i32,
+    search_config: &core::tree_search::SearchConfig,
+) -> String {
+    if let Some(message) = game_over_message(state) {
+        return invalid_best_turn_analysis_json(message, 0, 0.0);
+    }
+
+    let started_ms = now_ms();
+    let deadline =
+        util::cancellation::DeadlineCancellationToken::new(started_ms + budget_ms, now_ms);
+    let mut num_states_visited = 0usize;
+    let mut completed = None::<(core::player::AppraisedPlayerTurn, i32)>;
+    for level in 1..=max_level.max(1) {
+        let prev_appraisal = completed
+            .as_ref()
+            .map(|(appraised_turn, _)| appraised_turn.appraisal);
+        let mut search_stats = core::tree_search::SearchStats::default();
+        let appraised_turn = if level == 1 {
+            core::tree_search::TreeSearch::find_best_turn_deepening(
+                state,
+                level,
+                prev_appraisal,
+                search_config,
+                &util::cancellation::NeverCancelToken,
+                &mut search_stats,
+            )
+        } else {
+            core::tree_search::TreeSearch::find_best_turn_deepening(
+                state,
+                level,
+                prev_appraisal,
+                search_config,
+                &deadline,
+                &mut search_stats,
+            )
+        };
+        num_states_visited += search_stats.num_states_visited;
+        if deadline.has_cancelled() && level > 1 {
+            break;
+        }
+
+        // a forced win or loss won't change with depth
+        let is_decided = appraised_turn.appraisal.abs() >= core::rule_helper::HEURISTIC_SCORE_WIN;
+        completed = Some((appraised_turn, level));
+        if is_decided || deadline.is_past_deadline() {
+            break;
+        }
+    }
+    let elapsed_ms = (now_ms() - started_ms).max(0.0);
+
+    let Some((appraised_turn, completed_level)) = completed else {
+        return invalid_best_turn_analysis_json(
+            "Timed search finished no level.".to_string(),
+            num_states_visited,
+            elapsed_ms,
+        );
+    };
+    match suggested_turn_entries(state, &appraised_turn.turn) {
+        Ok(suggested_turn) => to_best_turn_analysis_json(&BestTurnAnalysisResponse {
+            is_valid: true,
+            validation_message: String::new(),
+            suggested_turn_text: appraised_turn.turn.to_string(),
+            suggested_turn,
+            heuristic_score: appraised_turn.appraisal,
+            num_states_visited,
+            search_stats: None,
+            completed_level: Some(completed_level),
+            elapsed_ms,
+        }),
+        Err(message) => invalid_best_turn_analysis_json(message, num_states_visited, elapsed_ms),
+    }
+}
+
+fn best_turns_analysis_json_for_state(
+    state: &core::mutable_game_state::MutableGameState,
+    analysis_level: i32,
+    num_turns: usize,
+    search_config: &core::tree_search::SearchConfig,
+) -> String {
+    if let Some(message) = game_over_message(state) {
+        return invalid_best_turns_analysis_json(message, 0, 0.0);
+    }
+
+    let token = util::cancellation::NeverCancelToken;
+    let mut num_states_visited = 0usize;
+    let started_ms = now_ms();
+    let best_turns = core::tree_search::TreeSearch::find_best_turns_with_config(
+        state,
+        analysis_level.max(0),
+        num_turns,
+        search_config,
+        &token,
+        &mut num_states_visited,
+    );
+    let elapsed_ms = (now_ms() - started_ms).max(0.0);
+
+    let turns = best_turns
+        .iter()
+        .map(|ranked| {
+            Ok(RankedTurn {
+                suggested_turn_text: ranked.turn.to_string(),
+                suggested_turn: suggested_turn_entries(state, &ranked.turn)?,
+                heuristic_score: ranked.appraisal,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>();
+    match turns {
+        Ok(turns) => serde_json::to_string(&BestTurnsAnalysisResponse {
+            is_valid: true,
+            validation_message: String::new(),
+            turns,
+            num_states_visited,
+            elapsed_ms,
+        })
+        .unwrap_or_default(),
+        Err(message) => invalid_best_turns_analysis_json(message, num_states_visited, elapsed_ms),
+    }
+}
+
+fn suggested_turn_entries(
+    state: &core::mutable_game_state::MutableGameState,
+    turn: &core::simple_turn::SimpleTurn,
+) -> Result<Vec<SuggestedTurnEntry>, String> {
+    let seats = state.common.seat_map();
+    turn.moves
+        .iter()
+        .map(|player_move| {
+            let piece_id = seats.piece_id(player_move.player_id).ok_or_else(|| {
+                format!(
+                    "Could not map player {} to a piece id.",
+                    player_move.player_id.0
+                )
+            })?;
+            Ok(SuggestedTurnEntry {
+                piece_id: piece_id.as_str().to_string(),
+                room_id: player_move.dest_room_id.0,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now_ms() -> f64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64() * 1000.0)
+        .unwrap_or(0.0)
+}
+
+fn current_player_loots_after_turn(
+    state: &core::mutable_game_state::MutableGameState,
+    turn: &core::simple_turn::SimpleTurn,
+) -> bool {
+    let mut preview_state = state.copy_state();
+    let current_player_id = preview_state.current_player_id;
+    let mut moved_stranger_that_saw_doctor = false;
+
+    for mv in &turn.moves {
+        let Some(&current_room_id) = preview_state.player_room_ids().get(mv.player_id.0) else {
+            return false;
+        };
+        if mv.player_id != current_player_id
+            && preview_state.common.board.sight[(current_room_id, preview_state.doctor_room_id)]
+        {
+            moved_stranger_that_saw_doctor = true;
+        }
+        preview_state.move_piece(mv.player_id, mv.dest_room_id);
+    }
+
+    preview_state.best_action_allowed(moved_stranger_that_saw_doctor)
+        == core::player::PlayerAction::Loot
+}
+
+fn collect_normal_turns(
+    state: &core::mutable_game_state::MutableGameState,
+) -> Vec<core::simple_turn::SimpleTurn> {
+    state.normal_turns()
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct GameStateHandle {
+    state: core::mutable_game_state::MutableGameState,
+    normal_setup: NormalSetup,
+    annotations: BTreeMap<usize, PlyAnnotation>,
+    blunder_check: Option<BlunderCheck>,
+    appraisal_history_level: i32,
+    input_limits: InputLimits,
+    last_turn_swing: Option<TurnSwing>,
+    last_turn_broadcast: Option<TurnBroadcast>,
+    search_config: core::tree_search::SearchConfig,
+    history_limit: Option<usize>,
+    continuous_analysis: ContinuousAnalysis,
+    session_stats: SessionStats,
+    on_state_changed: Option<js_sys::Function>,
+    puzzle: Option<core::puzzle::Puzzle>, // while playing one; turns are checked from its start
+    variations: VariationTree,
+    autosave: Autosave,
+    random_start: RandomStart,
+    seat_lock: SeatLock,
+    remote_session_id: String, // empty outside remote play; a new game ends the session
+    preview_cache: RefCell<PreviewCache>, // previewTurnPlan takes &self
+    game_id: u64,              // changes on every reset, import or new game
+}
+
+// with setPreviewCache on, previewTurnPlan keeps the states it computes for the current position,
+// keyed by effective turn, so applyTurnPlan of a previewed plan takes its state as is and
+// suggestTurn starts from those children; any change to the game empties it
+#[derive(Clone, Default)]
+struct PreviewCache {
+    is_enabled: bool,
+    position: (i32, u64, i32), // turn id, position key and ply the children are for
+    children: HashMap<core::simple_turn::SimpleTurn, core::mutable_game_state::MutableGameState>,
+}
+
+impl PreviewCache {
+    const MAX_CHILDREN: usize = 32;
+
+    fn position_of(state: &core::mutable_game_state::MutableGameState) -> (i32, u64, i32) {
+        (state.turn_id, state.position_key(), state.ply())
+    }
+
+    fn store(
+        &mut self,
+        state: &core::mutable_game_state::MutableGameState,
+        turn: &core::simple_turn::SimpleTurn,
+        child_state: &core::mutable_game_state::MutableGameState,
+    ) {
+        if !self.is_enabled {
+            return;
+        }
+        let position = Self::position_of(state);
+        if self.position != position {
+            self.children.clear();
+            self.position = position;
+        }
+        if self.children.len() < Self::MAX_CHILDREN {
+            self.children
+                .insert(state.effective_turn(turn), child_state.clone());
+        }
+    }
+
+    fn take_child(
+        &mut self,
+        state: &core::mutable_game_state::MutableGameState,
+        turn: &core::simple_turn::SimpleTurn,
+    ) -> Option<core::mutable_game_state::MutableGameState> {
+        if self.children.is_empty() || self.position != Self::position_of(state) {
+            return None;
+        }
+        self.children.remove(&state.effective_turn(turn))
+    }
+
+    fn root_children(
+        &self,
+        state: &core::mutable_game_state::MutableGameState,
+    ) -> Option<std::sync::Arc<core::tree_search::RootChildren>> {
+        if self.children.is_empty() || self.position != Self::position_of(state) {
+            return None;
+        }
+        Some(std::sync::Arc::new(core::tree_search::RootChildren::new(
+            state,
+            self.children
+                .iter()
+                .map(|(turn, child_state)| (turn.clone(), child_state.clone())),
+        )))
+    }
+}
+
+// for pass-and-play on one device: only the locked piece's plans are accepted, and the handoff
+// callback hears when a turn passes the move to the other piece, so the ui can ask for the
+// device to be handed over before calling setSeatLock again
+#[derive(Clone, Default)]
+struct SeatLock {
+    piece_id: Option<PieceId>, // player1 or player2
+    on_handoff: Option<js_sys::Function>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SeatLockJson {
+    locked_piece_id: String, // empty when unlocked
+    current_player_piece_id: &'static str,
+    can_submit: bool,
+}
+
+// what a setOnSeatHandoff callback gets
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SeatHandoffJson {
+    locked_piece_id: &'static str,
+    next_piece_id: &'static str,
+    next_display_text: String,
+    turn_id: i32,
+}
+
+// how startNewGameWithSetup fills room ids left at 0; the rng carries on from game to game, so
+// each new game starts somewhere else while a seed still replays the same run of starts
+#[derive(Clone, Default)]
+struct RandomStart {
+    options: core::start_rooms::StartOptions,
+    seed: u64,
+    rng: util::rng::SplitMix64,
+}
+
+// pool is board, candidates or any
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct StartOptionsJson {
+    pool: String,
+    doctor_offset: Option<i32>,
+    seed: u64,
+}
+
+// exportStateJson's string after each state change, pushed to a callback and, when kept, held for
+// takeAutosaveJson, so a page that never saves still has the game after a refresh
+#[derive(Clone, Default)]
+struct Autosave {
+    callback: Option<js_sys::Function>,
+    keeps_latest: bool,
+    latest: Option<String>,
+}
+
+impl Autosave {
+    fn is_active(&self) -> bool {
+        self.callback.is_some() || self.keeps_latest
+    }
+}
+
+#[wasm_bindgen]
+impl GameStateHandle {
+    pub fn summary(&self, indentation_level: usize) -> String {
+        self.state.summary(indentation_level)
+    }
+
+    #[wasm_bindgen(js_name = "currentPlayerPieceId")]
+    pub fn current_player_piece_id(&self) -> String {
+        normal_piece_id_for_state(&self.state).as_str().to_string()
+    }
+
+    #[wasm_bindgen(js_name = "hasWinner")]
+    pub fn has_winner(&self) -> bool {
+        self.state.has_winner()
+    }
+
+    #[wasm_bindgen(js_name = "isDraw")]
+    pub fn is_draw(&self) -> bool {
+        self.state.is_draw()
+    }
+
+    #[wasm_bindgen(js_name = "drawReason")]
+    pub fn draw_reason(&self) -> String {
+        self.state
+            .draw_reason()
+            .map(|draw_reason| draw_reason.to_string())
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "winnerPieceId")]
+    pub fn winner_piece_id(&self) -> String {
+        winner_piece_id_for_state(&self.state)
+            .map(|piece_id| piece_id.as_str().to_string())
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "resultJson")]
+    pub fn result_json(&self) -> String {
+        serde_json::to_string(&GameResultJson {
+            result: self.state.result.code().to_string(),
+            winner_piece_id: self.winner_piece_id(),
+            draw_reason: self.draw_reason(),
+        })
+        .unwrap_or_default()
+    }
+
+    // ends the game without a winner; undoLastTurn takes back the abort itself, not a turn
+    #[wasm_bindgen(js_name = "abortGame")]
+    pub fn abort_game(&mut self) -> String {
+        if let Some(message) = game_over_message(&self.state) {
+            return message;
+        }
+        self.state.abort();
+        self.notify_state_changed("abort");
+        String::new()
+    }
+
+    // rooms in pieceIdsJson order
+    #[wasm_bindgen(js_name = "piecePositions")]
+    pub fn piece_positions(&self) -> Vec<u32> {
+        let room_id_of = |player_id: core::player::PlayerId| {
+            self.state
+                .player_room_ids()
+                .get(player_id.0)
+                .map_or(0, |room_id| room_id.0 as u32)
+        };
+        std::iter::once(self.state.doctor_room_id.0 as u32)
+            .chain(
+                self.state
+                    .common
+                    .seat_map()
+                    .display_order_player_ids()
+                    .into_iter()
+                    .map(room_id_of),
+            )
+            .collect::<Vec<_>>()
+    }
+
+    // the doctor, then every seated piece; piecePositions and animationFrames use this order
+    #[wasm_bindgen(js_name = "pieceIdsJson")]
+    pub fn piece_ids_json(&self) -> String {
+        let piece_ids = std::iter::once(PieceId::Doctor)
+            .chain(self.state.common.seat_map().piece_ids())
+            .map(|piece_id| piece_id.as_str())
+            .collect::<Vec<_>>();
+        serde_json::to_string(&piece_ids).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    // one entry per seated piece, in pieceIdsJson order, with its name and color from the setup
+    #[wasm_bindgen(js_name = "pieceInfoJson")]
+    pub fn piece_info_json(&self) -> String {
+        let common = &self.state.common;
+        let seats = common.seat_map();
+        let piece_infos = seats
+            .piece_ids()
+            .into_iter()
+            .filter_map(|piece_id| {
+                let player_id = seats.player_id(piece_id)?;
+                let label = common.player_label(player_id).cloned().unwrap_or_default();
+                Some(PieceInfoJson {
+                    piece_id: piece_id.as_str(),
+                    piece_text: piece_id.display_label(),
+                    name: label.name,
+                    color: label.color,
+                    display_text: common.player_display_text(player_id),
+                    is_stranger: seats.is_stranger(player_id),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&piece_infos).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    // replaces every piece's name and color, keeping the game's turns; takes a pieceLabels
+    // array like the setup's and returns an error message or ""
+    #[wasm_bindgen(js_name = "setPieceLabelsJson")]
+    pub fn set_piece_labels_json(&mut self, labels_json: &str) -> String {
+        let piece_labels = match serde_json::from_str::<Vec<PieceLabelJson>>(labels_json) {
+            Ok(piece_labels) => piece_labels,
+            Err(err) => return format!("Invalid piece labels JSON: {err}"),
+        };
+        let setup = NormalSetup {
+            piece_labels,
+            ..normalize_normal_setup(&self.normal_setup, &self.state.common)
+        };
+        if let Err(message) = validate_normal_setup(&setup, &self.state.common) {
+            return message;
+        }
+
+        let mut state = new_state_with_normal_setup(self.state.common.clone(), &setup);
+        for turn in self.state.normal_turns() {
+            state.apply_turn(turn);
+        }
+        if self.state.is_aborted() {
+            state.abort();
+        }
+        self.state = state;
+        self.normal_setup.piece_labels = setup.piece_labels;
+        self.last_turn_broadcast = None;
+        self.notify_state_changed("labels");
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "strangerCountsJson")]
+    pub fn stranger_counts_json(&self) -> String {
+        serde_json::to_string(&self.state.common.stranger_counts).unwrap_or_default()
+    }
+
+    // restarts from the current setup with the given number of strangers on each side
+    #[wasm_bindgen(js_name = "setStrangerCounts")]
+    pub fn set_stranger_counts(&mut self, side_a: usize, side_b: usize) -> String {
+        let common = match StrangerCounts::new(side_a, side_b)
+            .and_then(|counts| self.state.common.clone().with_stranger_counts(counts))
+        {
+            Ok(common) => common,
+            Err(message) => return message,
+        };
+        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+        self.annotations.clear();
+        self.reset_transient_state();
+        self.notify_state_changed("reset");
+        String::new()
+    }
+
+    // 0, like the other piece getters, for a piece that isn't seated
+    #[wasm_bindgen(js_name = "pieceDoctorDistance")]
+    pub fn piece_doctor_distance(&self, piece_id: &str) -> i32 {
+        self.seated_player_id(piece_id)
+            .and_then(|player_id| self.state.doctor_moves_until_player_room(player_id))
+            .unwrap_or(0)
+    }
+
+    #[wasm_bindgen(js_name = "pieceStrength")]
+    pub fn piece_strength(&self, piece_id: &str) -> i32 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0;
+        };
+        self.state.player_strengths[player_id.0]
+    }
+
+    #[wasm_bindgen(js_name = "pieceMoveCards")]
+    pub fn piece_move_cards(&self, piece_id: &str) -> f64 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0.0;
+        };
+        self.state.player_move_cards[player_id.0].to_f64()
+    }
+
+    #[wasm_bindgen(js_name = "pieceWeaponCards")]
+    pub fn piece_weapon_cards(&self, piece_id: &str) -> f64 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0.0;
+        };
+        self.state.player_weapons[player_id.0].to_f64()
+    }
+
+    #[wasm_bindgen(js_name = "pieceFailureCards")]
+    pub fn piece_failure_cards(&self, piece_id: &str) -> f64 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0.0;
+        };
+        self.state.player_failures[player_id.0].to_f64()
+    }
+
+    #[wasm_bindgen(js_name = "pieceEquivalentClovers")]
+    pub fn piece_equivalent_clovers(&self, piece_id: &str) -> f64 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0.0;
+        };
+        self.state.player_equivalent_clovers(player_id)
+    }
+
+    #[wasm_bindgen(js_name = "pieceAttackStrength")]
+    pub fn piece_attack_strength(&self, piece_id: &str) -> i32 {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return 0;
+        };
+        piece_attack_strength_for_state(&self.state, player_id)
+    }
+
+    #[wasm_bindgen(js_name = "simulateAttackJson")]
+    pub fn simulate_attack_json(&self, attacker_piece_id: &str) -> String {
+        attack_simulation_json_for_state(&self.state, attacker_piece_id)
+    }
+
+    #[wasm_bindgen(js_name = "currentAppraisal")]
+    pub fn current_appraisal(&self, piece_id: &str) -> String {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return String::new();
+        };
+        let analysis_player_id = self.state.common.to_normal_player_id(player_id);
+        core::rule_helper::heuristic_score_text(
+            self.search_config.appraise(&self.state, analysis_player_id),
+        )
+    }
+
+    #[wasm_bindgen(js_name = "featuresJson")]
+    pub fn features_json(&self, piece_id: &str) -> String {
+        let features = match self.seated_player_id(piece_id) {
+            Some(player_id) => self
+                .state
+                .features_for(self.state.common.to_normal_player_id(player_id)),
+            None => self.state.features(),
+        };
+        serde_json::to_string(&features).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "attackHistoryText")]
+    pub fn attack_history_text(&self) -> String {
+        attack_history_text_for_state(&self.state)
+    }
+
+    // every attack so far, oldest first, with turnId, pieceId, strength, defenseSpent and succeeded
+    #[wasm_bindgen(js_name = "attackHistoryJson")]
+    pub fn attack_history_json(&self) -> String {
+        serde_json::to_string(&attack_history_entries(&self.state))
+            .unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "attackHistoryLabel")]
+    pub fn attack_history_label(&self) -> String {
+        attack_history_label_for_state(&self.state)
+    }
+
+    #[wasm_bindgen(js_name = "playerStatsJson")]
+    pub fn player_stats_json(&self) -> String {
+        let seats = self.state.common.seat_map();
+        let stats = self
+            .state
+            .common
+            .player_ids()
+            .filter_map(|player_id| {
+                let piece_id = seats.piece_id(player_id)?;
+                let idx = player_id.0;
+                Some(PlayerStats {
+                    piece_id: piece_id.as_str().to_string(),
+                    room_id: self.state.player_room_ids()[idx].0,
+                    doctor_distance: self
+                        .state
+                        .doctor_moves_until_player_room(player_id)
+                        .unwrap_or(0),
+                    strength: self.state.player_strengths[idx],
+                    move_cards: self.state.player_move_cards[idx].to_f64(),
+                    weapon_cards: self.state.player_weapons[idx].to_f64(),
+                    failure_cards: self.state.player_failures[idx].to_f64(),
+                    equivalent_clovers: self.state.player_equivalent_clovers(player_id),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&stats).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "strangerLoopsJson")]
+    pub fn stranger_loops_json(&self, piece_id: &str) -> String {
+        let Some(player_id) = self.seated_player_id(piece_id) else {
+            return "[]".to_string();
+        };
+        let entries = self
+            .state
+            .stranger_loop_threats(player_id)
+            .into_iter()
+            .map(|threat| StrangerLoopEntry {
+                enemy_room_id: threat.enemy_room_id.0,
+                counter_room_ids: threat
+                    .counter_room_ids
+                    .iter()
+                    .map(|room_id| room_id.0)
+                    .collect::<Vec<_>>(),
+                opposing_stranger_is_here: threat.opposing_stranger_is_here,
+                is_countered: threat.is_countered,
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "doctorActivationsJson")]
+    pub fn doctor_activations_json(&self) -> String {
+        let seats = self.state.common.seat_map();
+        let piece_id_text = |player_id| {
+            seats
+                .piece_id(player_id)
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default()
+        };
+        let events = self
+            .state
+            .doctor_activation_hist()
+            .into_iter()
+            .map(|activation| DoctorActivationEvent {
+                turn_id: activation.turn_id,
+                doctor_room_id: activation.doctor_room_id.0,
+                activated_piece_id: piece_id_text(activation.activated_player_id),
+                skipped_piece_ids: activation
+                    .skipped_player_ids(self.state.common.num_all_players)
+                    .into_iter()
+                    .map(piece_id_text)
+                    .collect::<Vec<_>>(),
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&events).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "activationContextJson")]
+    pub fn activation_context_json(&self) -> String {
+        let seats = self.state.common.seat_map();
+        let piece_id_text = |player_id| {
+            seats
+                .piece_id(player_id)
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default()
+        };
+        let context = self.state.activation_context();
+        let summary = ActivationContextSummary {
+            piece_id: piece_id_text(context.player_id),
+            is_activated: context.activation.is_some(),
+            doctor_room_id: context
+                .activation
+                .map(|activation| activation.doctor_room_id.0),
+            skipped_piece_ids: context
+                .skipped_player_ids
+                .into_iter()
+                .map(piece_id_text)
+                .collect::<Vec<_>>(),
+            restrictions: context
+                .restrictions
+                .into_iter()
+                .map(|restriction| restriction.as_str())
+                .collect::<Vec<_>>(),
+        };
+
+        serde_json::to_string(&summary).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "roomOccupancyJson")]
+    pub fn room_occupancy_json(&self) -> String {
+        let occupancy = core::room_occupancy::RoomOccupancy::from_state(&self.state);
+        let seats = self.state.common.seat_map();
+        let board = &self.state.common.board;
+        let pieces = std::iter::once(RoomOccupancyPiece {
+            piece_id: PieceId::Doctor.as_str().to_string(),
+            plies: occupancy.doctor_plies.clone(),
+        })
+        .chain(
+            seats
+                .display_order_player_ids()
+                .into_iter()
+                .filter_map(|player_id| {
+                    Some(RoomOccupancyPiece {
+                        piece_id: seats.piece_id(player_id)?.as_str().to_string(),
+                        plies: occupancy.player_plies.get(player_id.0)?.clone(),
+                    })
+                }),
+        )
+        .collect::<Vec<_>>();
+        let json = RoomOccupancyJson {
+            num_plies: occupancy.num_plies,
+            rooms: occupancy
+                .room_ids
+                .iter()
+                .map(|room_id| RoomOccupancyRoom {
+                    room_id: room_id.0,
+                    room_label: board.room_label(*room_id),
+                })
+                .collect::<Vec<_>>(),
+            pieces,
+        };
+
+        serde_json::to_string(&json).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "sessionStatsJson")]
+    pub fn session_stats_json(&self) -> String {
+        session_stats_json_for(&self.session_stats, &self.state.common.seat_map())
+    }
+
+    #[wasm_bindgen(js_name = "resetSessionStats")]
+    pub fn reset_session_stats(&mut self) {
+        self.session_stats = SessionStats::default();
+    }
+
+    #[wasm_bindgen(js_name = "roomIdByName")]
+    pub fn room_id_by_name(&self, name: &str) -> Option<usize> {
+        self.state
+            .common
+            .board
+            .room_id_by_name(name)
+            .map(|room_id| room_id.0)
+    }
+
+    #[wasm_bindgen(js_name = "currentActionOutlookJson")]
+    pub fn current_action_outlook_json(&self) -> String {
+        let state = &self.state;
+        let outlook = if let Some(message) = game_over_message(state) {
+            ActionOutlook {
+                validation_message: message,
+                ..ActionOutlook::default()
+            }
+        } else {
+            let seats = state.common.seat_map();
+            let player_id = state.current_player_id;
+            let room_id = state.player_room_ids()[player_id.0];
+            let board = &state.common.board;
+            let budget = state.max_move_distance(player_id);
+            let reachable_room_ids = board
+                .room_ids
+                .iter()
+                .copied()
+                .filter(|dest_room_id| {
+                    board.distance[(room_id, *dest_room_id)] <= budget
+                        && !state.is_room_blocked_for(player_id, *dest_room_id)
+                })
+                .collect::<Vec<_>>();
+            let room_ids_allowing = |action: core::player::PlayerAction| {
+                reachable_room_ids
+                    .iter()
+                    .filter(|dest_room_id| state.action_allowed_in(**dest_room_id, false) == action)
+                    .map(|dest_room_id| dest_room_id.0)
+                    .collect::<Vec<_>>()
+            };
+            ActionOutlook {
+                is_valid: true,
+                validation_message: String::new(),
+                piece_id: piece_id_text(&seats, player_id),
+                room_id: room_id.0,
+                sees_doctor: board.sight[(room_id, state.doctor_room_id)],
+                seen_by_piece_ids: state
+                    .watcher_ids(room_id, player_id)
+                    .into_iter()
+                    .map(|watcher_id| piece_id_text(&seats, watcher_id))
+                    .collect::<Vec<_>>(),
+                action_if_staying: player_action_text(state.action_allowed_in(room_id, false))
+                    .to_string(),
+                attack_room_ids: room_ids_allowing(core::player::PlayerAction::Attack),
+                loot_room_ids: room_ids_allowing(core::player::PlayerAction::Loot),
+            }
+        };
+        serde_json::to_string(&outlook).unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "occupantsOfRoom")]
+    pub fn occupants_of_room(&self, room_id: usize) -> String {
+        let room_id = core::room::RoomId(room_id);
+        let occupants = if self.state.common.board.room_ids.contains(&room_id) {
+            let seats = self.state.common.seat_map();
+            RoomOccupants {
+                is_valid: true,
+                validation_message: String::new(),
+                room_id: room_id.0,
+                piece_ids: self
+                    .state
+                    .player_ids_in_room(room_id)
+                    .into_iter()
+                    .filter_map(|player_id| seats.piece_id(player_id))
+                    .map(|piece_id| piece_id.as_str().to_string())
+                    .collect::<Vec<_>>(),
+                has_doctor: self.state.doctor_room_id == room_id,
+            }
+        } else {
+            RoomOccupants {
+                is_valid: false,
+                validation_message: format!("Unknown room id {}.", room_id.0),
+                room_id: room_id.0,
+                piece_ids: Vec::new(),
+                has_doctor: false,
+            }
+        };
+        serde_json::to_string(&occupants).unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "roomLabel")]
+    pub fn room_label(&self, room_id: usize) -> String {
+        self.state
+            .common
+            .board
+            .room_label(core::room::RoomId(room_id))
+    }
+
+    // a standalone svg picture of the board and pieces, for sharing a position as an image
+    #[wasm_bindgen(js_name = "renderSvg")]
+    pub fn render_svg(&self) -> String {
+        core::board_svg::render_svg(&self.state)
+    }
+
+    #[wasm_bindgen(js_name = "boardRoomsJson")]
+    pub fn board_rooms_json(&self) -> String {
+        let rooms = self
+            .state
+            .common
+            .board
+            .room_ids
+            .iter()
+            .filter_map(|room_id| self.state.common.board.rooms.get(room_id))
+            .map(|room| BoardRoomInfo {
+                id: room.id.0,
+                name: room.name.clone(),
+                adjacent: room.adjacent.iter().map(|id| id.0).collect::<Vec<_>>(),
+                visible: room.visible.iter().map(|id| id.0).collect::<Vec<_>>(),
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&rooms).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "wingsJson")]
+    pub fn wings_json(&self) -> String {
+        let board = &self.state.common.board;
+        let wings = board
+            .wings()
+            .iter()
+            .map(|wing| WingInfo {
+                name: wing.name.clone(),
+                room_ids: wing.room_ids.iter().map(|id| id.0).collect::<Vec<_>>(),
+                is_closed: board.is_wing_closed(wing),
+            })
+            .collect::<Vec<_>>();
+
+        serde_json::to_string(&wings).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    // farthest the current player can drag this piece with nothing else moved this turn, counting
+    // the free first room; 0 for pieces it can't move
+    #[wasm_bindgen(js_name = "pieceMoveBudget")]
+    pub fn piece_move_budget(&self, piece_id: &str) -> i32 {
+        self.seated_player_id(piece_id)
+            .map(|player_id| self.state.max_move_distance(player_id))
+            .unwrap_or(0)
+    }
+
+    #[wasm_bindgen(js_name = "reachableRooms")]
+    pub fn reachable_rooms(&self, piece_id: &str, steps: i32) -> Vec<u32> {
+        let Some(piece_id) = PieceId::parse(piece_id) else {
+            return Vec::new();
+        };
+        let steps = steps.max(0);
+        let (room_id, player_id) = match piece_id {
+            PieceId::Doctor => (self.state.doctor_room_id, None),
+            _ => {
+                let Some(player_id) = self.state.common.seat_map().player_id(piece_id) else {
+                    return Vec::new();
+                };
+                let Some(room_id) = self.state.player_room_ids().get(player_id.0) else {
+                    return Vec::new();
+                };
+                (*room_id, Some(player_id))
+            }
+        };
+
+        // rooms another player's piece blocks are left out, so the ui offers what the engine allows
+        self.state
+            .common
+            .board
+            .room_ids
+            .iter()
+            .filter(|dest_room_id| {
+                self.state.common.board.distance[(room_id, **dest_room_id)] <= steps
+            })
+            .filter(|dest_room_id| {
+                player_id.is_none_or(|player_id| {
+                    !self.state.is_room_blocked_for(player_id, **dest_room_id)
+                })
+            })
+            .map(|dest_room_id| dest_room_id.0 as u32)
+            .collect::<Vec<_>>()
+    }
+
+    // where a piece (or the doctor) has been over the last plies normal turns, for fading trails
+    #[wasm_bindgen(js_name = "pieceTrailJson")]
+    pub fn piece_trail_json(&self, piece_id: &str, plies: usize) -> String {
+        let player_id = match PieceId::parse(piece_id) {
+            Some(PieceId::Doctor) => Ok(None),
+            Some(_) => self
+                .seated_player_id(piece_id)
+                .map(Some)
+                .ok_or_else(|| format!("Piece '{piece_id}' is not in this game.")),
+            None => Err(format!("Unknown piece id '{piece_id}'.")),
+        };
+        let trail = match player_id {
+            Ok(player_id) => PieceTrailJson {
+                is_valid: true,
+                validation_message: String::new(),
+                piece_id: piece_id.trim().to_ascii_lowercase(),
+                rooms: self
+                    .state
+                    .room_trail(player_id, plies)
+                    .into_iter()
+                    .map(|(ply, room_id)| PieceTrailRoomJson {
+                        ply,
+                        room_id: room_id.0,
+                    })
+                    .collect::<Vec<_>>(),
+            },
+            Err(message) => PieceTrailJson {
+                validation_message: message,
+                ..PieceTrailJson::default()
+            },
+        };
+        serde_json::to_string(&trail).unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "undoLastTurn")]
+    pub fn undo_last_turn(&mut self) -> bool {
+        if self.state.resume() {
+            self.notify_state_changed("undo");
+            return true;
+        }
+        let finished_game = self.state.is_game_over().then(|| self.state.copy_state());
+        loop {
+            let prev_state = self
+                .state
+                .prev_state
+                .as_ref()
+                .map(|state| state.as_ref().clone());
+
+            let Some(prev_state) = prev_state else {
+                if self.state.compacted_history.is_some() {
+                    self.state = self.state.rehydrated();
+                    continue;
+                }
+                return false;
+            };
+
+            self.state = prev_state;
+
+            if self.state.is_normal_turn() {
+                if let Some(finished_game) = finished_game.as_ref() {
+                    self.session_stats.forget_game(finished_game);
+                }
+                self.last_turn_swing = None;
+                self.last_turn_broadcast = None;
+                let ply = self.state.ply() as usize;
+                self.annotations
+                    .retain(|annotation_ply, _| *annotation_ply <= ply);
+                self.notify_state_changed("undo");
+                return true;
+            }
+        }
+    }
+
+    #[wasm_bindgen(js_name = "variationTreeJson")]
+    pub fn variation_tree_json(&self) -> String {
+        let tree = &self.variations;
+        let current_line = tree.line_node_ids(tree.current_id());
+        let nodes = (1..=tree.num_nodes())
+            .filter_map(|node_id| tree.node(node_id).map(|node| (node_id, node)))
+            .map(|(node_id, node)| VariationNodeJson {
+                node_id,
+                parent_id: node.parent_id,
+                ply: tree.line_node_ids(node_id).len(),
+                turn_text: node.turn.to_string(),
+                child_ids: tree.child_ids(node_id),
+                is_on_current_line: current_line.contains(&node_id),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&VariationTreeJson {
+            current_node_id: tree.current_id(),
+            redo_node_id: tree.redo_id(),
+            root_child_ids: tree.child_ids(core::variation_tree::ROOT_NODE_ID),
+            nodes,
+        })
+        .unwrap_or_default()
+    }
+
+    // replays the line to a node of variationTreeJson from the start position; annotations past
+    // the part of the line shared with the current one are dropped
+    #[wasm_bindgen(js_name = "goToVariation")]
+    pub fn go_to_variation(&mut self, node_id: usize) -> String {
+        if node_id != core::variation_tree::ROOT_NODE_ID && self.variations.node(node_id).is_none()
+        {
+            return format!("Unknown variation node {node_id}.");
+        }
+        let line = self.variations.line(node_id);
+        let mut state = new_state_with_normal_setup(self.state.common.clone(), &self.normal_setup);
+        for (turn_idx, turn) in line.iter().enumerate() {
+            if let Err(err) = state.check_normal_turn(turn) {
+                return format!("Variation turn {} is invalid: {err}", turn_idx + 1);
+            }
+            state.apply_turn(turn.clone());
+        }
+        if let Some(history_limit) = self.history_limit {
+            state.compact_history(history_limit);
+        }
+
+        let num_shared_turns = self
+            .state
+            .normal_turns()
+            .iter()
+            .zip(&line)
+            .take_while(|(current, other)| current == other)
+            .count();
+        self.annotations
+            .retain(|annotation_ply, _| *annotation_ply <= num_shared_turns);
+        self.state = state;
+        self.last_turn_swing = None;
+        self.last_turn_broadcast = None;
+        self.notify_state_changed("variation");
+        String::new()
+    }
+
+    // plays a turn plan from a node, starting a new variation unless the node already has that
+    // turn; the game stays where it was if the plan doesn't apply
+    #[wasm_bindgen(js_name = "createVariation")]
+    pub fn create_variation(&mut self, node_id: usize, turn_plan_json: &str) -> String {
+        let prior_node_id = self.variations.current_id();
+        let message = self.go_to_variation(node_id);
+        if !message.is_empty() {
+            return message;
+        }
+        let message = self.apply_turn_plan(turn_plan_json);
+        if !message.is_empty() {
+            self.go_to_variation(prior_node_id);
+        }
+        message
+    }
+
+    // drops a node and every line through it, first backing up to its parent if it is on the
+    // current line
+    #[wasm_bindgen(js_name = "deleteVariation")]
+    pub fn delete_variation(&mut self, node_id: usize) -> String {
+        let Some(parent_id) = self.variations.parent_id(node_id) else {
+            return format!("Unknown variation node {node_id}.");
+        };
+        if self
+            .variations
+            .is_ancestor(node_id, self.variations.current_id())
+        {
+            let message = self.go_to_variation(parent_id);
+            if !message.is_empty() {
+                return message;
+            }
+        }
+        match self.variations.delete(node_id) {
+            Ok(()) => {
+                self.notify_state_changed("variation");
+                String::new()
+            }
+            Err(message) => message,
+        }
+    }
+
+    // replays the turn undoLastTurn took back, or the main line when there is none
+    #[wasm_bindgen(js_name = "redoTurn")]
+    pub fn redo_turn(&mut self) -> bool {
+        self.variations
+            .redo_id()
+            .is_some_and(|node_id| self.go_to_variation(node_id).is_empty())
+    }
+
+    #[wasm_bindgen(js_name = "resetGame")]
+    pub fn reset_game(&mut self) {
+        let common = self.state.common.clone();
+        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+        self.annotations.clear();
+        let puzzle = self.puzzle.take(); // a puzzle restarts from its own start
+        self.reset_transient_state();
+        self.puzzle = puzzle;
+        self.notify_state_changed("reset");
+    }
+
+    #[wasm_bindgen(js_name = "loadBoard")]
+    pub fn load_board(&mut self, board_name: &str) -> String {
+        let current = &self.state.common;
+        let (num_normal_players, rule_config) =
+            (current.num_normal_players, current.rule_config.clone());
+        let (stranger_counts, teams) = (current.stranger_counts, current.teams);
+        let next = core::board::Board::from_embedded_json(board_name)
+            .map_err(|err| err.to_string())
+            .and_then(|board| {
+                core::common_game_state::CommonGameState::from_num_normal_players(
+                    true,
+                    board,
+                    num_normal_players,
+                )
+                .with_rule_config(rule_config)
+                .with_stranger_counts(stranger_counts)
+            })
+            .and_then(|common| match teams {
+                Some(teams) => common.with_teams(teams),
+                None => Ok(common),
+            })
+            .map(new_game_state_handle);
+        match next {
+            Ok(next) => {
+                self.state = next.state;
+                self.normal_setup = next.normal_setup;
+                self.annotations.clear();
+                self.reset_transient_state();
+                self.notify_state_changed("reset");
+                String::new()
+            }
+            Err(message) => message,
+        }
+    }
+
+    #[wasm_bindgen(js_name = "loadPuzzle")]
+    pub fn load_puzzle(&mut self, puzzle_name: &str) -> String {
+        match core::puzzle::Puzzle::find_embedded(puzzle_name) {
+            Some(puzzle) => self.start_puzzle(puzzle),
+            None => format!("Unknown puzzle '{puzzle_name}'."),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "loadPuzzleJson")]
+    pub fn load_puzzle_json(&mut self, puzzle_json: &str) -> String {
+        match core::puzzle::Puzzle::from_json_str(puzzle_json) {
+            Ok(puzzle) => self.start_puzzle(puzzle),
+            Err(message) => message,
+        }
+    }
+
+    fn start_puzzle(&mut self, puzzle: core::puzzle::Puzzle) -> String {
+        let state = match puzzle.start_state(true) {
+            Ok(state) => state,
+            Err(message) => return message,
+        };
+        self.normal_setup = normal_setup_from_state(&state);
+        self.state = state;
+        self.annotations.clear();
+        self.reset_transient_state();
+        self.puzzle = Some(puzzle);
+        self.notify_state_changed("reset");
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "puzzleJson")]
+    pub fn puzzle_json(&self) -> String {
+        serde_json::to_string(&self.puzzle).unwrap_or_else(|_| "null".to_string())
+    }
+
+    // a line is a json array of turn plans, both sides' turns from the puzzle's start
+    #[wasm_bindgen(js_name = "checkPuzzleLine")]
+    pub fn check_puzzle_line(&self, line_json: &str) -> String {
+        let turns = serde_json::from_str::<Vec<serde_json::Value>>(line_json)
+            .map_err(|err| format!("Invalid puzzle line JSON: {err}"))
+            .and_then(|plans| {
+                plans
+                    .iter()
+                    .map(|plan| {
+                        parse_turn_plan(&plan.to_string(), &self.state.common, &self.input_limits)
+                            .map_err(|err| err.to_string())
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            });
+        self.puzzle_check_json(turns)
+    }
+
+    // checks the turns played on this handle since the puzzle was loaded
+    #[wasm_bindgen(js_name = "checkPuzzleProgress")]
+    pub fn check_puzzle_progress(&self) -> String {
+        self.puzzle_check_json(Ok(collect_normal_turns(&self.state)))
+    }
+
+    fn puzzle_check_json(
+        &self,
+        turns: Result<Vec<core::simple_turn::SimpleTurn>, String>,
+    ) -> String {
+        let result = self
+            .puzzle
+            .as_ref()
+            .ok_or_else(|| "No puzzle is loaded.".to_string())
+            .and_then(|puzzle| puzzle.check_line(turns?));
+        let response = match result {
+            Ok(verdict) => {
+                let (verdict, failed_ply, reason) = match verdict {
+                    core::puzzle::PuzzleVerdict::Solved => ("solved", None, String::new()),
+                    core::puzzle::PuzzleVerdict::OnTrack => ("onTrack", None, String::new()),
+                    core::puzzle::PuzzleVerdict::Failed { ply, reason } => {
+                        ("failed", Some(ply), reason)
+                    }
+                };
+                PuzzleCheckResponse {
+                    is_valid: true,
+                    validation_message: String::new(),
+                    verdict: verdict.to_string(),
+                    failed_ply,
+                    reason,
+                }
+            }
+            Err(message) => PuzzleCheckResponse {
+                is_valid: false,
+                validation_message: message,
+                verdict: String::new(),
+                failed_ply: None,
+                reason: String::new(),
+            },
+        };
+        serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "puzzleHintJson")]
+    pub fn puzzle_hint_json(&self) -> String {
+        let hint = self
+            .puzzle
+            .as_ref()
+            .ok_or_else(|| "No puzzle is loaded.".to_string())
+            .and_then(|puzzle| {
+                let plies_played = collect_normal_turns(&self.state).len() as i32;
+                puzzle
+                    .hint(&self.state, plies_played)
+                    .ok_or_else(|| "No turn keeps the win forced from here.".to_string())
+            })
+            .and_then(|turn| {
+                Ok(PuzzleHintResponse {
+                    is_valid: true,
+                    validation_message: String::new(),
+                    suggested_turn: suggested_turn_entries(&self.state, &turn)?,
+                    suggested_turn_text: turn.to_string(),
+                })
+            });
+        let response = hint.unwrap_or_else(|message| PuzzleHintResponse {
+            is_valid: false,
+            validation_message: message,
+            suggested_turn_text: String::new(),
+            suggested_turn: Vec::new(),
+        });
+        serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    // keeps about this many recent states linked for undo; older turns are kept only as a
+    // replayable list and rehydrated on demand; zero keeps everything
+    // frees every earlier state now, keeping the turns so export and undo still work
+    #[wasm_bindgen(js_name = "compactHistory")]
+    pub fn compact_history(&mut self) {
+        self.state.compact_all_history();
+    }
+
+    #[wasm_bindgen(js_name = "setHistoryLimit")]
+    pub fn set_history_limit(&mut self, max_states: usize) {
+        self.history_limit = (max_states > 0).then_some(max_states);
+        if let Some(history_limit) = self.history_limit {
+            self.state.compact_history(history_limit);
+        }
+    }
+
+    #[wasm_bindgen(js_name = "rehydrateHistory")]
+    pub fn rehydrate_history(&mut self) {
+        self.state = self.state.rehydrated();
+    }
+
+    #[wasm_bindgen(js_name = "normalTurnHistory")]
+    pub fn normal_turn_history(&self) -> String {
+        self.state.normal_turn_hist()
+    }
+
+    #[wasm_bindgen(js_name = "prevTurnSummaryVerbose")]
+    pub fn prev_turn_summary_verbose(&self) -> String {
+        self.state.prev_turn_summaries_since_normal(true)
+    }
+
+    #[wasm_bindgen(js_name = "lastTurnJson")]
+    pub fn last_turn_json(&self) -> String {
+        last_turn_json_for_state(&self.state)
+    }
+
+    // what changed from one ply to another, either way round; ply 0 is the start position
+    #[wasm_bindgen(js_name = "plyDiffJson")]
+    pub fn ply_diff_json(&self, from_ply: usize, to_ply: usize) -> String {
+        state_diff_json(
+            core::state_diff::diff_plies(&self.state, from_ply, to_ply),
+            &self.state.common,
+        )
+    }
+
+    #[wasm_bindgen(js_name = "animationFrames")]
+    pub fn animation_frames(&self) -> Vec<u32> {
+        // frames are flattened; each one has an entry per pieceIdsJson piece
+        self.state
+            .animation_frames_since_normal()
+            .into_iter()
+            .flatten()
+            .map(|room_id| room_id.0 as u32)
+            .collect::<Vec<_>>()
+    }
+
+    #[wasm_bindgen(js_name = "validateTurnPlan")]
+    pub fn validate_turn_plan(&self, turn_plan_json: &str) -> String {
+        catch_panic(|| {
+            let turn = match parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits)
+            {
+                Ok(turn) => turn,
+                Err(err) => return err.to_string(),
+            };
+
+            match self.state.check_normal_turn(&turn) {
+                Ok(()) => String::new(),
+                Err(err) => err.to_string(),
+            }
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    // same check as validateTurnPlan, but with an error code and parameters the ui can localize
+    #[wasm_bindgen(js_name = "validateTurnPlanJson")]
+    pub fn validate_turn_plan_json(&self, turn_plan_json: &str) -> String {
+        catch_panic(|| {
+        let result = parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits)
+            .and_then(|turn| self.state.check_normal_turn(&turn));
+        let error = result.err();
+        serde_json::to_string(&TurnPlanValidation {
+            is_valid: error.is_none(),
+            validation_message: error
+                .as_ref()
+                .map(|err| err.to_string())
+                .unwrap_or_default(),
+            error,
+        })
+        .unwrap_or_else(|_| {
+            "{\"isValid\":false,\"validationMessage\":\"Turn plan validation serialization failed.\",\"error\":null}".to_string()
+        })
+        })
+        .unwrap_or_else(|message| {
+            serde_json::to_string(&TurnPlanValidation {
+                is_valid: false,
+                validation_message: internal_error_text(&message),
+                error: None,
+            })
+            .unwrap_or_default()
+        })
+    }
+
+    // whether two turn plans move the same pieces to the same rooms here, whatever their order and
+    // ignoring pieces "moved" to the room they are in; false if either plan doesn't parse
+    #[wasm_bindgen(js_name = "turnPlansEquivalent")]
+    pub fn turn_plans_equivalent(&self, turn_plan_json_a: &str, turn_plan_json_b: &str) -> bool {
+        let parse = |turn_plan_json| {
+            parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits)
+                .map(|turn| self.state.effective_turn(&turn))
+        };
+        match (parse(turn_plan_json_a), parse(turn_plan_json_b)) {
+            (Ok(turn_a), Ok(turn_b)) => turn_a == turn_b,
+            _ => false,
+        }
+    }
+
+    #[wasm_bindgen(js_name = "applyTurnPlan")]
+    pub fn apply_turn_plan(&mut self, turn_plan_json: &str) -> String {
+        self.guard_state(|handle| {
+            let turn =
+                match parse_turn_plan(turn_plan_json, &handle.state.common, &handle.input_limits) {
+                    Ok(turn) => turn,
+                    Err(err) => return err.to_string(),
+                };
+
+            if let Some(message) = seat_lock_error(&handle.state, handle.seat_lock.piece_id) {
+                return message;
+            }
+            if let Err(err) = handle.state.check_normal_turn(&turn) {
+                return err.to_string();
+            }
+
+            handle.apply_checked_turn(turn);
+            String::new()
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    // packets need a session id both ends agree on; an empty id ends remote play
+    #[wasm_bindgen(js_name = "setRemoteSession")]
+    pub fn set_remote_session(&mut self, session_id: &str) -> String {
+        let session_id = session_id.trim();
+        if session_id.len() > 64 || session_id.chars().any(char::is_control) {
+            return "Session ids are at most 64 characters, with no control characters."
+                .to_string();
+        }
+        self.remote_session_id = session_id.to_string();
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "remoteSessionJson")]
+    pub fn remote_session_json(&self) -> String {
+        serde_json::to_string(&RemoteSessionJson {
+            session_id: self.remote_session_id.clone(),
+            next_sequence: next_turn_sequence(&self.state),
+        })
+        .unwrap_or_default()
+    }
+
+    // checks a plan for the current position and wraps it as the next packet; the sender applies
+    // the packet with applyTurnPacketJson just as the other end does
+    #[wasm_bindgen(js_name = "turnPacketJson")]
+    pub fn turn_packet_json(&self, turn_plan_json: &str) -> String {
+        if self.remote_session_id.is_empty() {
+            return invalid_turn_packet_json("No remote session is set.".to_string());
+        }
+        let turn = match parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits) {
+            Ok(turn) => turn,
+            Err(err) => return invalid_turn_packet_json(err.to_string()),
+        };
+        if let Err(err) = self.state.check_normal_turn(&turn) {
+            return invalid_turn_packet_json(err.to_string());
+        }
+
+        let seats = self.state.common.seat_map();
+        let plan = turn
+            .moves
+            .iter()
+            .filter_map(|mv| {
+                Some(TurnPlanEntry {
+                    piece_id: seats.piece_id(mv.player_id)?,
+                    room_id: Some(mv.dest_room_id.0),
+                    room_name: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&TurnPacketResponse {
+            is_valid: true,
+            validation_message: String::new(),
+            packet: Some(TurnPacket {
+                session_id: self.remote_session_id.clone(),
+                sequence: next_turn_sequence(&self.state),
+                position_key: position_key_text(&self.state),
+                plan,
+            }),
+        })
+        .unwrap_or_default()
+    }
+
+    // returns an error message or ""; a rejected packet leaves the game as it was
+    #[wasm_bindgen(js_name = "applyTurnPacketJson")]
+    pub fn apply_turn_packet_json(&mut self, packet_json: &str) -> String {
+        self.guard_state(|handle| {
+            let packet = match serde_json::from_str::<TurnPacket>(packet_json) {
+                Ok(packet) => packet,
+                Err(err) => return format!("Invalid turn packet JSON: {err}"),
+            };
+            if let Err(message) =
+                check_turn_packet(&handle.state, &handle.remote_session_id, &packet)
+            {
+                return message;
+            }
+            let turn = match serde_json::to_string(&packet.plan)
+                .map_err(|err| err.to_string())
+                .and_then(|plan_json| {
+                    parse_turn_plan(&plan_json, &handle.state.common, &handle.input_limits)
+                        .map_err(|err| err.to_string())
+                }) {
+                Ok(turn) => turn,
+                Err(message) => return message,
+            };
+            if let Err(err) = handle.state.check_normal_turn(&turn) {
+                return err.to_string();
+            }
+
+            handle.apply_checked_turn(turn);
+            String::new()
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    #[wasm_bindgen(js_name = "applyTurnPlanForPiece")]
+    pub fn apply_turn_plan_for_piece(&mut self, piece_id: &str, turn_plan_json: &str) -> String {
+        self.guard_state(|handle| {
+            let Some(player_id) = handle.seated_player_id(piece_id) else {
+                return format!("Unknown piece id '{piece_id}'.");
+            };
+            let turn =
+                match parse_turn_plan(turn_plan_json, &handle.state.common, &handle.input_limits) {
+                    Ok(turn) => turn,
+                    Err(err) => return err.to_string(),
+                };
+
+            if let Some(message) = seat_lock_error(&handle.state, handle.seat_lock.piece_id) {
+                return message;
+            }
+            if let Err(err) = handle.state.check_normal_turn_for_player(player_id, &turn) {
+                return err.to_string();
+            }
+
+            handle.apply_checked_turn(turn);
+            String::new()
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    #[wasm_bindgen(js_name = "stageMove")]
+    pub fn stage_move(&mut self, piece_id: &str, room_id: usize) -> String {
+        self.guard_state(|handle| {
+            let Some(player_id) = handle.seated_player_id(piece_id) else {
+                return format!("Unknown piece id '{piece_id}'.");
+            };
+            if let Some(message) = seat_lock_error(&handle.state, handle.seat_lock.piece_id) {
+                return message;
+            }
+
+            match handle.state.stage_move(core::player::PieceMove::new(
+                player_id,
+                core::room::RoomId(room_id),
+            )) {
+                Ok(()) => String::new(),
+                Err(err) => err.to_string(),
+            }
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    #[wasm_bindgen(js_name = "stagedPlanJson")]
+    pub fn staged_plan_json(&self) -> String {
+        let seats = self.state.common.seat_map();
+        let entries = self
+            .state
+            .staged_plan
+            .iter()
+            .filter_map(|mv| {
+                Some(TurnPlanEntry {
+                    piece_id: seats.piece_id(mv.player_id)?,
+                    room_id: Some(mv.dest_room_id.0),
+                    room_name: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "commitStagedMoves")]
+    pub fn commit_staged_moves(&mut self) -> String {
+        self.guard_state(|handle| {
+            if let Some(message) = seat_lock_error(&handle.state, handle.seat_lock.piece_id) {
+                return message;
+            }
+            let turn = handle.state.staged_turn();
+            if let Err(err) = handle.state.check_normal_turn(&turn) {
+                return err.to_string();
+            }
+
+            handle.apply_checked_turn(turn);
+            String::new()
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    #[wasm_bindgen(js_name = "rollbackStagedMoves")]
+    pub fn rollback_staged_moves(&mut self) {
+        self.state.rollback_staged_moves();
+    }
+
+    // the piece's player id, if that piece is seated in this game
+    fn seated_player_id(&self, piece_id: &str) -> Option<core::player::PlayerId> {
+        let player_id = self
+            .state
+            .common
+            .seat_map()
+            .player_id(PieceId::parse(piece_id)?)?;
+        (player_id.0 < self.state.player_room_ids().len()).then_some(player_id)
+    }
+
+    // the callback gets a StateChangeJson string after each turn, undo, reset, import, abort or
+    // relabel. it runs while this handle is still borrowed, so it should queue any calls back into
+    // the handle (queueMicrotask, a framework's state setter) rather than make them directly. null
+    // removes it
+    #[wasm_bindgen(js_name = "setOnStateChanged")]
+    pub fn set_on_state_changed(&mut self, callback: Option<js_sys::Function>) {
+        self.on_state_changed = callback;
+    }
+
+    // keeps the variation tree on the current line before telling the ui
+    // what no new game should inherit from the one before it: its id, the last turn's swing and
+    // broadcast, the puzzle being played, and positions cached for search and preview
+    fn reset_transient_state(&mut self) {
+        self.game_id = next_game_id();
+        self.last_turn_swing = None;
+        self.last_turn_broadcast = None;
+        self.puzzle = None;
+        self.clear_search_cache();
+        self.preview_cache.get_mut().children.clear();
+    }
+
+    fn notify_state_changed(&mut self, kind: &'static str) {
+        self.preview_cache.get_mut().children.clear();
+        match kind {
+            "turn" | "undo" | "variation" => {
+                self.variations.sync_to_line(self.state.normal_turns())
+            }
+            "reset" => {
+                self.variations = VariationTree::default();
+                self.remote_session_id.clear();
+            }
+            _ => {}
+        }
+        if self.autosave.is_active() {
+            let saved_json = self.export_snapshot(false);
+            if let Some(callback) = self.autosave.callback.as_ref() {
+                let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&saved_json));
+            }
+            if self.autosave.keeps_latest {
+                self.autosave.latest = Some(saved_json);
+            }
+        }
+        if let Some(callback) = self.on_state_changed.as_ref() {
+            let change_json = state_change_json(&self.state, kind);
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&change_json));
+        }
+    }
+
+    // the callback gets exportStateJson's string after every change setOnStateChanged reports,
+    // before that callback runs, and under the same rules about calling back in. null removes it
+    #[wasm_bindgen(js_name = "setOnAutosave")]
+    pub fn set_on_autosave(&mut self, callback: Option<js_sys::Function>) {
+        self.autosave.callback = callback;
+    }
+
+    // locks plan submission to player1 or player2; an empty piece id unlocks
+    #[wasm_bindgen(js_name = "setSeatLock")]
+    pub fn set_seat_lock(&mut self, piece_id: &str) -> String {
+        if piece_id.trim().is_empty() {
+            self.seat_lock.piece_id = None;
+            return String::new();
+        }
+        match PieceId::parse(piece_id) {
+            Some(piece_id @ (PieceId::Player1 | PieceId::Player2)) => {
+                self.seat_lock.piece_id = Some(piece_id);
+                String::new()
+            }
+            _ => format!("Only player1 or player2 can hold the seat lock, not '{piece_id}'."),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "seatLockJson")]
+    pub fn seat_lock_json(&self) -> String {
+        serde_json::to_string(&SeatLockJson {
+            locked_piece_id: self
+                .seat_lock
+                .piece_id
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default(),
+            current_player_piece_id: normal_piece_id_for_state(&self.state).as_str(),
+            can_submit: seat_lock_error(&self.state, self.seat_lock.piece_id).is_none(),
+        })
+        .unwrap_or_default()
+    }
+
+    // the callback gets a SeatHandoffJson string, after the turn's setOnStateChanged callback,
+    // whenever a turn leaves the move with a piece other than the locked one. null removes it
+    #[wasm_bindgen(js_name = "setOnSeatHandoff")]
+    pub fn set_on_seat_handoff(&mut self, callback: Option<js_sys::Function>) {
+        self.seat_lock.on_handoff = callback;
+    }
+
+    // keeps previewTurnPlan's states for applyTurnPlan and suggestTurn; off empties the cache
+    #[wasm_bindgen(js_name = "setPreviewCache")]
+    pub fn set_preview_cache(&mut self, is_enabled: bool) {
+        let preview_cache = self.preview_cache.get_mut();
+        preview_cache.is_enabled = is_enabled;
+        if !is_enabled {
+            preview_cache.children.clear();
+        }
+    }
+
+    // for embeds without callbacks: keep the latest autosave for takeAutosaveJson to hand over
+    #[wasm_bindgen(js_name = "setAutosave")]
+    pub fn set_autosave(&mut self, keeps_latest: bool) {
+        self.autosave.keeps_latest = keeps_latest;
+        if !keeps_latest {
+            self.autosave.latest = None;
+        }
+    }
+
+    // the save from the latest change since the last take, or empty if nothing changed
+    #[wasm_bindgen(js_name = "takeAutosaveJson")]
+    pub fn take_autosave_json(&mut self) -> String {
+        self.autosave.latest.take().unwrap_or_default()
+    }
+
+    // runs body and, if it panics, puts the whole handle back the way it was before the call; a
+    // body may have set its setup, annotations or puzzle before the panic as well as the state
+    #[cfg(not(target_arch = "wasm32"))]
+    fn guard_state<T>(&mut self, body: impl FnOnce(&mut Self) -> T) -> Result<T, String> {
+        let handle_before = self.clone();
+        let result = catch_panic(|| body(self));
+        if result.is_err() {
+            *self = handle_before;
+        }
+        result
+    }
+
+    // a wasm32 panic aborts before any restore could run, so the snapshot would only cost time
+    #[cfg(target_arch = "wasm32")]
+    fn guard_state<T>(&mut self, body: impl FnOnce(&mut Self) -> T) -> Result<T, String> {
+        Ok(body(self))
+    }
+
+    fn apply_checked_turn(&mut self, turn: core::simple_turn::SimpleTurn) {
+        let prior_attack_count = self.state.attack_hist.len();
+        let doctor_room_id_before = self.state.doctor_room_id.0;
+        let mover_id = self
+            .state
+            .common
+            .to_normal_player_id(self.state.current_player_id);
+
+        self.apply_turn_with_blunder_check(turn);
+        self.record_appraisal();
+        if self.state.is_game_over() {
+            self.session_stats.record_game(&self.state);
+        }
+
+        self.last_turn_broadcast = self.state.common.is_log_enabled.then(|| {
+            let seats = self.state.common.seat_map();
+            let appraisal = self.search_config.appraise(&self.state, mover_id);
+            let attackers = new_attacker_piece_ids(&self.state, prior_attack_count);
+            let winner_piece_id = winner_piece_id_for_state(&self.state)
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default();
+            let summary = self.state.prev_turn_summaries_since_normal(false);
+            let mut text = summary.lines().next().unwrap_or_default().to_string();
+            if !attackers.is_empty() {
+                text.push_str(&format!(" | attacks: {}", attackers.join(",")));
+            }
+            let board = &self.state.common.board;
+            text.push_str(&format!(
+                " | doctor {}->{}",
+                board.room_label(core::room::RoomId(doctor_room_id_before)),
+                board.room_label(self.state.doctor_room_id)
+            ));
+            text.push_str(&format!(
+                " | eval {}",
+                core::rule_helper::heuristic_score_text(appraisal)
+            ));
+            if !winner_piece_id.is_empty() {
+                text.push_str(&format!(" | {winner_piece_id} wins"));
+            }
+            let stranger_loop_room_ids = stranger_loop_warning_room_ids(&self.state, mover_id);
+            if !stranger_loop_room_ids.is_empty() {
+                text.push_str(" | stranger loop warning");
+            }
+
+            TurnBroadcast {
+                ply: self.state.ply() as usize,
+                piece_id: seats
+                    .piece_id(mover_id)
+                    .map(|piece_id| piece_id.as_str().to_string())
+                    .unwrap_or_default(),
+                text,
+                summary,
+                attackers,
+                doctor_room_id_before,
+                doctor_room_id_after: self.state.doctor_room_id.0,
+                appraisal,
+                appraisal_text: core::rule_helper::heuristic_score_text(appraisal),
+                winner_piece_id,
+                stranger_loop_warning: !stranger_loop_room_ids.is_empty(),
+                stranger_loop_room_ids,
+            }
+        });
+
+        // compacting back down to the limit only once the chain doubles keeps the rebuild amortized
+        if let Some(history_limit) = self.history_limit
+            && self.state.history_len() > history_limit * 2
+        {
+            self.state.compact_history(history_limit);
+        }
+        self.notify_state_changed("turn");
+        if let Some(callback) = self.seat_lock.on_handoff.as_ref()
+            && let Some(handoff_json) = seat_handoff_json(&self.state, self.seat_lock.piece_id)
+        {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&handoff_json));
+        }
+    }
+
+    fn apply_turn_with_blunder_check(&mut self, turn: core::simple_turn::SimpleTurn) {
+        let Some(blunder_check) = self.blunder_check else {
+            self.apply_turn_reusing_preview(turn);
+            return;
+        };
+
+        let mover_id = self
+            .state
+            .common
+            .to_normal_player_id(self.state.current_player_id);
+        let appraisal_before = side_appraisal_for_state(
+            &self.state,
+            mover_id,
+            blunder_check.analysis_level,
+            &self.search_config,
+        );
+        self.apply_turn_reusing_preview(turn);
+        let appraisal_after = side_appraisal_for_state(
+            &self.state,
+            mover_id,
+            blunder_check.analysis_level,
+            &self.search_config,
+        );
+        let swing = (appraisal_after - appraisal_before).clamp(f64::MIN, f64::MAX);
+
+        self.last_turn_swing = Some(TurnSwing {
+            piece_id: self
+                .state
+                .common
+                .seat_map()
+                .piece_id(mover_id)
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default(),
+            analysis_level: blunder_check.analysis_level,
+            appraisal_before,
+            appraisal_after,
+            appraisal_before_text: core::rule_helper::heuristic_score_text(appraisal_before),
+            appraisal_after_text: core::rule_helper::heuristic_score_text(appraisal_after),
+            swing,
+            is_blunder: swing < -blunder_check.blunder_threshold,
+        });
+    }
+
+    fn apply_turn_reusing_preview(&mut self, turn: core::simple_turn::SimpleTurn) {
+        match self.preview_cache.get_mut().take_child(&self.state, &turn) {
+            Some(child_state) => self.state = child_state,
+            None => {
+                self.state.apply_turn(turn);
+            }
+        }
+    }
+
+    fn record_appraisal(&mut self) {
+        if self.appraisal_history_level <= 0 || self.state.appraisal.is_some() {
+            return;
+        }
+        let side_a = self
+            .state
+            .common
+            .seat_map()
+            .normal_of(core::seat_map::Side::A);
+        self.state.appraisal = Some(side_appraisal_for_state(
+            &self.state,
+            side_a,
+            self.appraisal_history_level,
+            &self.search_config,
+        ));
+    }
+
+    // zero or less stops recording; states already appraised keep their values
+    #[wasm_bindgen(js_name = "setAppraisalHistory")]
+    pub fn set_appraisal_history(&mut self, analysis_level: i32) {
+        self.appraisal_history_level = analysis_level.min(self.input_limits.max_analysis_level);
+        self.record_appraisal();
+    }
+
+    // side A's appraisal after each recorded ply, oldest first, for plotting over the game
+    #[wasm_bindgen(js_name = "appraisalHistoryJson")]
+    pub fn appraisal_history_json(&self) -> String {
+        let mut entries = Vec::new();
+        let mut ply = self.state.ply() as usize;
+        let mut cursor = Some(&self.state);
+        while let Some(state) = cursor {
+            if let Some(appraisal) = state.appraisal {
+                entries.push(AppraisalHistoryEntry {
+                    ply,
+                    appraisal,
+                    appraisal_text: core::rule_helper::heuristic_score_text(appraisal),
+                });
+            }
+            cursor = state.prev_state.as_deref();
+            if cursor.is_some_and(|prev_state| prev_state.is_normal_turn()) {
+                ply = ply.saturating_sub(1);
+            }
+        }
+        entries.reverse();
+        serde_json::to_string(&entries).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "setBlunderCheck")]
+    pub fn set_blunder_check(&mut self, analysis_level: i32, blunder_threshold: f64) {
+        let analysis_level = analysis_level.min(self.input_limits.max_analysis_level);
+        self.blunder_check = (analysis_level > 0).then_some(BlunderCheck {
+            analysis_level,
+            blunder_threshold: blunder_threshold.abs(),
+        });
+        self.last_turn_swing = None;
+    }
+
+    #[wasm_bindgen(js_name = "lastTurnSwing")]
+    pub fn last_turn_swing(&self) -> String {
+        self.last_turn_swing
+            .as_ref()
+            .and_then(|swing| serde_json::to_string(swing).ok())
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "lastTurnBroadcastJson")]
+    pub fn last_turn_broadcast_json(&self) -> String {
+        self.last_turn_broadcast
+            .as_ref()
+            .and_then(|broadcast| serde_json::to_string(broadcast).ok())
+            .unwrap_or_default()
+    }
+
+    #[wasm_bindgen(js_name = "turnPlanCostJson")]
+    pub fn turn_plan_cost_json(&self, turn_plan_json: &str) -> String {
+        catch_panic(|| {
+            let turn = match parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits)
+            {
+                Ok(turn) => turn,
+                Err(err) => return invalid_turn_plan_cost_json(err.to_string()),
+            };
+
+            match turn_plan_cost_for_state(&self.state, &turn) {
+                Ok(cost) => to_turn_plan_cost_json(&cost),
+                Err(message) => invalid_turn_plan_cost_json(message),
+            }
+        })
+        .unwrap_or_else(|message| invalid_turn_plan_cost_json(internal_error_text(&message)))
+    }
+
+    #[wasm_bindgen(js_name = "previewTurnPlan")]
+    pub fn preview_turn_plan(&self, turn_plan_json: &str) -> String {
+        to_preview_json(&self.turn_plan_preview(turn_plan_json))
+    }
+
+    // previewTurnPlan for each plan of a json array, answered as an array in the same order. a
+    // batch that isn't an array of plans, or holds more than MAX_PREVIEW_PLANS, comes back as a
+    // single invalid preview saying why
+    #[wasm_bindgen(js_name = "previewTurnPlans")]
+    pub fn preview_turn_plans(&self, turn_plans_json: &str) -> String {
+        let max_batch_len = self
+            .input_limits
+            .max_plan_json_len
+            .saturating_mul(MAX_PREVIEW_PLANS);
+        let plans = if turn_plans_json.len() > max_batch_len {
+            Err(format!(
+                "Turn plan batch is {} characters; the limit is {max_batch_len}.",
+                turn_plans_json.len()
+            ))
+        } else {
+            serde_json::from_str::<Vec<serde_json::Value>>(turn_plans_json)
+                .map_err(|err| format!("Invalid turn plan batch JSON: {err}"))
+        };
+        let previews = match plans {
+            Ok(plans) if plans.len() > MAX_PREVIEW_PLANS => vec![invalid_preview(format!(
+                "Turn plan batch has {} plans; the limit is {}.",
+                plans.len(),
+                MAX_PREVIEW_PLANS
+            ))],
+            Ok(plans) => plans
+                .iter()
+                .map(|plan| self.turn_plan_preview(&plan.to_string()))
+                .collect::<Vec<_>>(),
+            Err(message) => vec![invalid_preview(message)],
+        };
+        serde_json::to_string(&previews).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    fn turn_plan_preview(&self, turn_plan_json: &str) -> TurnPlanPreview {
+        catch_panic(|| {
+            let turn = match parse_turn_plan(turn_plan_json, &self.state.common, &self.input_limits)
+            {
+                Ok(turn) => turn,
+                Err(err) => return invalid_preview(err.to_string()),
+            };
+
+            if let Err(err) = self.state.check_normal_turn(&turn) {
+                return invalid_preview(err.to_string());
+            }
+
+            let current_player_loots = current_player_loots_after_turn(&self.state, &turn);
+            let mover_id = self
+                .state
+                .common
+                .to_normal_player_id(self.state.current_player_id);
+            let prior_attack_count = self.state.attack_hist.len();
+            let preview_state = self.state.after_turn(turn.clone());
+            self.preview_cache
+                .borrow_mut()
+                .store(&self.state, &turn, &preview_state);
+
+            let attackers = new_attacker_piece_ids(&preview_state, prior_attack_count);
+
+            let mut moved_strangers = Vec::new();
+            if preview_state.common.has_strangers() {
+                let seats = preview_state.common.seat_map();
+                let stranger_rooms = seats
+                    .display_order_player_ids()
+                    .into_iter()
+                    .filter(|player_id| seats.is_stranger(*player_id))
+                    .filter_map(|player_id| {
+                        Some((
+                            seats.piece_id(player_id)?,
+                            self.state.player_room_ids().get(player_id.0)?.0,
+                            preview_state.player_room_ids().get(player_id.0)?.0,
+                        ))
+                    })
+                    .collect::<Vec<_>>();
+                for (piece_id, current_room_id, preview_room_id) in stranger_rooms {
+                    if current_room_id != preview_room_id {
+                        moved_strangers.push(PreviewPieceRoom {
+                            piece_id: piece_id.as_str().to_string(),
+                            room_id: preview_room_id,
+                            room_label: preview_state
+                                .common
+                                .board
+                                .room_label(core::room::RoomId(preview_room_id)),
+                        });
+                    }
+                }
+            }
+            let winner_piece_id = winner_piece_id_for_state(&preview_state);
+            let stranger_loop_room_ids = stranger_loop_warning_room_ids(&preview_state, mover_id);
+
+            TurnPlanPreview {
+                is_valid: true,
+                validation_message: String::new(),
+                next_player_piece_id: normal_piece_id_for_state(&preview_state)
+                    .as_str()
+                    .to_string(),
+                has_winner: winner_piece_id.is_some(),
+                winner_piece_id: winner_piece_id
+                    .map(|piece_id| piece_id.as_str().to_string())
+                    .unwrap_or_default(),
+                attackers,
+                current_player_loots,
+                doctor_room_id: preview_state.doctor_room_id.0,
+                doctor_room_label: preview_state
+                    .common
+                    .board
+                    .room_label(preview_state.doctor_room_id),
+                moved_strangers,
+                stranger_loop_warning: !stranger_loop_room_ids.is_empty(),
+                stranger_loop_room_ids,
+            }
+        })
+        .unwrap_or_else(|message| invalid_preview(internal_error_text(&message)))
+    }
+
+    #[wasm_bindgen(js_name = "inputLimitsJson")]
+    pub fn input_limits_json(&self) -> String {
+        serde_json::to_string(&self.input_limits).unwrap_or_default()
+    }
+
+    // missing fields fall back to the defaults
+    #[wasm_bindgen(js_name = "setInputLimitsJson")]
+    pub fn set_input_limits_json(&mut self, limits_json: &str) -> String {
+        match serde_json::from_str::<InputLimits>(limits_json) {
+            Ok(input_limits) => {
+                self.input_limits = input_limits;
+                String::new()
+            }
+            Err(err) => format!("Invalid input limits JSON: {err}"),
+        }
+    }
+
+    #[wasm_bindgen(js_name = "startContinuousAnalysis")]
+    pub fn start_continuous_analysis(&mut self) {
+        self.continuous_analysis = ContinuousAnalysis {
+            is_running: game_over_message(&self.state).is_none(),
+            position_key: self.state.position_key(),
+            ..ContinuousAnalysis::default()
+        };
+    }
+
+    // searches one level deeper than the last step and returns that level's findBestTurn json;
+    // stops by itself at the analysis level limit or when the position changes underneath it
+    #[wasm_bindgen(js_name = "stepContinuousAnalysis")]
+    pub fn step_continuous_analysis(&mut self) -> String {
+        if !self.continuous_analysis.is_running {
+            return invalid_best_turn_analysis_json(
+                "Continuous analysis is not running.".to_string(),
+                0,
+                0.0,
+            );
+        }
+        if self.continuous_analysis.position_key != self.state.position_key() {
+            self.continuous_analysis.is_running = false;
+            return invalid_best_turn_analysis_json(
+                "Position changed during continuous analysis.".to_string(),
+                0,
+                0.0,
+            );
+        }
+
+        let analysis_level = self.continuous_analysis.completed_level + 1;
+        let (analysis_json, appraisal) = deepening_analysis_json_for_state(
+            &self.state,
+            analysis_level,
+            self.continuous_analysis.latest_appraisal,
+            &self.search_config,
+        );
+        self.continuous_analysis.completed_level = analysis_level;
+        self.continuous_analysis.latest_json = analysis_json.clone();
+        self.continuous_analysis.latest_appraisal = appraisal;
+        if analysis_level >= self.input_limits.max_analysis_level {
+            self.continuous_analysis.is_running = false;
+        }
+        analysis_json
+    }
+
+    #[wasm_bindgen(js_name = "cancelSearch")]
+    pub fn cancel_search(&mut self) {
+        self.continuous_analysis.is_running = false;
+    }
+
+    #[wasm_bindgen(js_name = "isContinuousAnalysisRunning")]
+    pub fn is_continuous_analysis_running(&self) -> bool {
+        self.continuous_analysis.is_running
+    }
+
+    #[wasm_bindgen(js_name = "continuousAnalysisLevel")]
+    pub fn continuous_analysis_level(&self) -> i32 {
+        self.continuous_analysis.completed_level
+    }
+
+    // latest completed level's result, or "" before the first step
+    #[wasm_bindgen(js_name = "continuousAnalysisJson")]
+    pub fn continuous_analysis_json(&self) -> String {
+        self.continuous_analysis.latest_json.clone()
+    }
+
+    #[wasm_bindgen(js_name = "findBestTurn")]
     pub fn find_best_turn(&self, analysis_level: i32) -> String {
-        if self.state.has_winner() {
+        if let Err(err) = self.input_limits.check_analysis_level(analysis_level) {
+            return invalid_best_turn_analysis_json(err.to_string(), 0, 0.0);
+        }
+        best_turn_analysis_json_for_state(&self.state, analysis_level, &self.search_config)
+    }
+
+    // findBestTurn at the deepest level that finishes within milliseconds, reported as
+    // completedLevel; the level that runs out of time can overshoot the budget by a few polls
+    #[wasm_bindgen(js_name = "suggestTurnTimed")]
+    pub fn suggest_turn_timed(&self, milliseconds: f64) -> String {
+        if !milliseconds.is_finite() || milliseconds <= 0.0 {
             return invalid_best_turn_analysis_json(
-                "Game already has a winner.".to_string(),
+                "milliseconds must be a positive number.".to_string(),
                 0,
                 0.0,
             );
         }
+        timed_analysis_json_for_state(
+            &self.state,
+            milliseconds,
+            self.input_limits.max_analysis_level,
+            &self.search_config,
+        )
+    }
+
+    // a turn for an engine opponent at easy, medium or hard: a shallower search and a seeded draw
+    // from its best turns, answered like findBestTurn. seed is the game's (MatchHandle.gameSeed);
+    // mixed with the turn id it picks the same turn whenever a replay reaches the same turn
+    #[wasm_bindgen(js_name = "suggestTurn")]
+    pub fn suggest_turn(&self, difficulty: &str, seed: u32) -> String {
+        let difficulty = match difficulty.parse::<core::move_selector::Difficulty>() {
+            Ok(difficulty) => difficulty,
+            Err(message) => return invalid_best_turn_analysis_json(message, 0, 0.0),
+        };
+        if let Some(message) = game_over_message(&self.state) {
+            return invalid_best_turn_analysis_json(message, 0, 0.0);
+        }
+
+        let mut selector = core::move_selector::MoveSelector::new(difficulty, 0)
+            .with_game_seed(seed as u64)
+            .with_max_analysis_level(self.input_limits.max_analysis_level);
+        let search_config = core::tree_search::SearchConfig {
+            root_children: self.preview_cache.borrow().root_children(&self.state),
+            ..self.search_config.clone()
+        };
+        let mut num_states_visited = 0usize;
+        let started_ms = now_ms();
+        let selected = selector.select_turn(
+            &self.state,
+            &search_config,
+            &util::cancellation::NeverCancelToken,
+            &mut num_states_visited,
+        );
+        let elapsed_ms = (now_ms() - started_ms).max(0.0);
+        let turn = selected.appraised_turn.turn;
+        match suggested_turn_entries(&self.state, &turn) {
+            Ok(suggested_turn) => to_best_turn_analysis_json(&BestTurnAnalysisResponse {
+                is_valid: true,
+                validation_message: String::new(),
+                suggested_turn_text: turn.to_string(),
+                suggested_turn,
+                heuristic_score: selected.appraised_turn.appraisal,
+                num_states_visited,
+                search_stats: None,
+                completed_level: None,
+                elapsed_ms,
+            }),
+            Err(message) => {
+                invalid_best_turn_analysis_json(message, num_states_visited, elapsed_ms)
+            }
+        }
+    }
+
+    // like findBestTurn, but the top numTurns turns with their appraisals under "turns"
+    #[wasm_bindgen(js_name = "findBestTurns")]
+    pub fn find_best_turns(&self, analysis_level: i32, num_turns: usize) -> String {
+        if let Err(err) = self.input_limits.check_analysis_level(analysis_level) {
+            return invalid_best_turns_analysis_json(err.to_string(), 0, 0.0);
+        }
+        best_turns_analysis_json_for_state(
+            &self.state,
+            analysis_level,
+            num_turns,
+            &self.search_config,
+        )
+    }
+
+    // like findBestTurns, with rootTurnsJson {"only":[plan...],"excluded":[plan...]} limiting the
+    // current player's choices, for questions like "what if I leave the stranger alone?"
+    #[wasm_bindgen(js_name = "findBestTurnsAmong")]
+    pub fn find_best_turns_among(
+        &self,
+        analysis_level: i32,
+        num_turns: usize,
+        root_turns_json: &str,
+    ) -> String {
+        if let Err(err) = self.input_limits.check_analysis_level(analysis_level) {
+            return invalid_best_turns_analysis_json(err.to_string(), 0, 0.0);
+        }
+        let root_turns = self.parse_root_turns(root_turns_json);
+        match root_turns {
+            Ok(root_turns) => best_turns_analysis_json_for_state(
+                &self.state,
+                analysis_level,
+                num_turns,
+                &core::tree_search::SearchConfig {
+                    root_turns: Some(root_turns),
+                    ..self.search_config.clone()
+                },
+            ),
+            Err(message) => invalid_best_turns_analysis_json(message, 0, 0.0),
+        }
+    }
+
+    fn parse_root_turns(
+        &self,
+        root_turns_json: &str,
+    ) -> Result<core::tree_search::RootTurns, String> {
+        let options = serde_json::from_str::<RootTurnsOptions>(root_turns_json)
+            .map_err(|err| format!("Invalid root turns JSON: {err}"))?;
+        let parse_plans = |plans: &[serde_json::Value]| {
+            plans
+                .iter()
+                .map(|plan| {
+                    parse_turn_plan(&plan.to_string(), &self.state.common, &self.input_limits)
+                        .map_err(|err| err.to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        core::tree_search::RootTurns::new(
+            &self.state,
+            parse_plans(&options.only)?,
+            parse_plans(&options.excluded)?,
+        )
+    }
+
+    // where the current player could best move a stranger of a side, found with a shallow search:
+    // side is a or b, or any piece id on that side. each placement gives reasons like blocksSight
+    #[wasm_bindgen(js_name = "suggestStrangerPlacement")]
+    pub fn suggest_stranger_placement(&self, side: &str) -> String {
+        let invalid_json = |message: String| {
+            serde_json::to_string(&StrangerPlacementResponse {
+                is_valid: false,
+                validation_message: message,
+                placements: Vec::new(),
+                num_states_visited: 0,
+            })
+            .unwrap_or_default()
+        };
+        let seats = self.state.common.seat_map();
+        let parsed_side = match side.trim().to_ascii_lowercase().as_str() {
+            "a" => Some(core::seat_map::Side::A),
+            "b" => Some(core::seat_map::Side::B),
+            _ => self
+                .seated_player_id(side)
+                .and_then(|player_id| seats.side_of(player_id)),
+        };
+        let Some(parsed_side) = parsed_side else {
+            return invalid_json(format!("Unknown side '{side}'; use a, b or a piece id."));
+        };
+
+        catch_panic(|| -> Result<String, String> {
+            let mut num_states_visited = 0usize;
+            let placements = core::stranger_placement::suggest_stranger_placements(
+                &self.state,
+                parsed_side,
+                core::stranger_placement::PLACEMENT_ANALYSIS_LEVEL,
+                core::stranger_placement::MAX_PLACEMENTS,
+                &self.search_config,
+                &util::cancellation::NeverCancelToken,
+                &mut num_states_visited,
+            )?
+            .into_iter()
+            .map(|placement| {
+                Ok(StrangerPlacementEntry {
+                    piece_id: seats
+                        .piece_id(placement.stranger_id)
+                        .map(|piece_id| piece_id.as_str().to_string())
+                        .unwrap_or_default(),
+                    room_id: placement.room_id.0,
+                    room_label: self.state.common.board.room_label(placement.room_id),
+                    appraisal: placement.appraisal,
+                    reasons: placement.reasons,
+                    suggested_turn_text: placement.turn.to_string(),
+                    suggested_turn: suggested_turn_entries(&self.state, &placement.turn)?,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+            Ok(serde_json::to_string(&StrangerPlacementResponse {
+                is_valid: true,
+                validation_message: String::new(),
+                placements,
+                num_states_visited,
+            })
+            .unwrap_or_default())
+        })
+        .map_err(|message| internal_error_text(&message))
+        .and_then(|result| result)
+        .unwrap_or_else(invalid_json)
+    }
+
+    #[wasm_bindgen(js_name = "defaultNormalSetupJson")]
+    pub fn default_normal_setup_json(&self) -> String {
+        let setup = normalize_normal_setup(
+            &default_normal_setup_for_rules(&self.state.common.rule_config),
+            &self.state.common,
+        );
+        serde_json::to_string(&setup).unwrap_or_else(|_| {
+            "{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"}".to_string()
+        })
+    }
+
+    #[wasm_bindgen(js_name = "currentNormalSetupJson")]
+    pub fn current_normal_setup_json(&self) -> String {
+        let setup = normalize_normal_setup(&self.normal_setup, &self.state.common);
+        serde_json::to_string(&setup).unwrap_or_else(|_| {
+            "{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"}".to_string()
+        })
+    }
+
+    #[wasm_bindgen(js_name = "startNewGameWithSetup")]
+    pub fn start_new_game_with_setup(
+        &mut self,
+        move_cards: f64,
+        weapon_cards: f64,
+        failure_cards: f64,
+        player2_move_cards: f64,
+        player2_weapon_cards: f64,
+        player2_failure_cards: f64,
+        doctor_room_id: usize,
+        player1_room_id: usize,
+        stranger1_room_id: usize,
+        player2_room_id: usize,
+        stranger2_room_id: usize,
+        player1_strength: i32,
+        stranger1_strength: i32,
+        player2_strength: i32,
+        stranger2_strength: i32,
+        turn_id: i32,
+        current_player_piece_id: &str,
+    ) -> String {
+        let Some(current_player_piece_id) = PieceId::parse(current_player_piece_id) else {
+            return "currentPlayerPieceId must be P1 or P3.".to_string();
+        };
+
+        let mut setup = snapped_normal_setup(&NormalSetup {
+            board_name: String::new(),
+            move_cards,
+            weapon_cards,
+            failure_cards,
+            player2_move_cards,
+            player2_weapon_cards,
+            player2_failure_cards,
+            doctor_room_id,
+            player1_room_id,
+            stranger1_room_id,
+            player2_room_id,
+            stranger2_room_id,
+            player1_strength,
+            stranger1_strength,
+            player2_strength,
+            stranger2_strength,
+            turn_id,
+            current_player_piece_id,
+            piece_labels: self.normal_setup.piece_labels.clone(),
+        });
+        let common = self.state.common.clone();
+        if !self.random_start.options.is_board_default() {
+            let start_rooms = match self
+                .random_start
+                .options
+                .choose(&common.board, &mut self.random_start.rng)
+            {
+                Ok(start_rooms) => start_rooms,
+                Err(message) => return message,
+            };
+            if setup.doctor_room_id == 0 {
+                setup.doctor_room_id = start_rooms.doctor_room_id.0;
+            }
+            for room_id in [
+                &mut setup.player1_room_id,
+                &mut setup.stranger1_room_id,
+                &mut setup.player2_room_id,
+                &mut setup.stranger2_room_id,
+            ] {
+                if *room_id == 0 {
+                    *room_id = start_rooms.player_room_id.0;
+                }
+            }
+        }
+        let normalized_setup = normalize_normal_setup(&setup, &common);
+        if let Err(message) = validate_normal_setup(&normalized_setup, &common) {
+            return message;
+        }
+
+        self.normal_setup = normalized_setup;
+        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+        self.annotations.clear();
+        self.reset_transient_state();
+        self.notify_state_changed("reset");
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "startOptionsJson")]
+    pub fn start_options_json(&self) -> String {
+        let options = &self.random_start.options;
+        serde_json::to_string(&StartOptionsJson {
+            pool: options.pool.to_string(),
+            doctor_offset: options.doctor_offset,
+            seed: self.random_start.seed,
+        })
+        .unwrap_or_default()
+    }
+
+    // applies to later startNewGameWithSetup calls; setting it restarts the seed's run of starts.
+    // a missing pool means board, which with no doctorOffset turns random starts off
+    #[wasm_bindgen(js_name = "setStartOptionsJson")]
+    pub fn set_start_options_json(&mut self, options_json: &str) -> String {
+        let options = match serde_json::from_str::<StartOptionsJson>(options_json) {
+            Ok(options) => options,
+            Err(err) => return format!("Invalid start options JSON: {err}"),
+        };
+        let pool = if options.pool.is_empty() {
+            core::start_rooms::StartRoomPool::Board
+        } else {
+            match options.pool.parse::<core::start_rooms::StartRoomPool>() {
+                Ok(pool) => pool,
+                Err(message) => return message,
+            }
+        };
+        self.random_start = RandomStart {
+            options: core::start_rooms::StartOptions {
+                pool,
+                doctor_offset: options.doctor_offset,
+            },
+            seed: options.seed,
+            rng: util::rng::SplitMix64::new(options.seed),
+        };
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "distanceMatrixJson")]
+    pub fn distance_matrix_json(&self) -> String {
+        serde_json::to_string(&self.state.common.board.distance)
+            .unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "sightMatrixJson")]
+    pub fn sight_matrix_json(&self) -> String {
+        serde_json::to_string(&self.state.common.board.sight).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "ruleConfigJson")]
+    pub fn rule_config_json(&self) -> String {
+        serde_json::to_string(&self.state.common.rule_config).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    // restarts from the current setup, since earlier turns may have been decided under other rules
+    #[wasm_bindgen(js_name = "setWinConditions")]
+    pub fn set_win_conditions(
+        &mut self,
+        successful_attacks_to_win: i32,
+        doctor_escape_turns: i32,
+    ) -> String {
+        if successful_attacks_to_win < 1 {
+            return "successfulAttacksToWin must be at least 1.".to_string();
+        }
+        if doctor_escape_turns < 0 {
+            return "doctorEscapeTurns must be 0 (never) or positive.".to_string();
+        }
+
+        let rule_config = core::rule_config::RuleConfig {
+            successful_attacks_to_win,
+            doctor_escape_turns,
+            ..self.state.common.rule_config.clone()
+        };
+        let common = self.state.common.clone().with_rule_config(rule_config);
+        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+        self.annotations.clear();
+        self.reset_transient_state(); // positions hash the same under the old rules
+        self.notify_state_changed("reset");
+        String::new()
+    }
+
+    // restarts with every player holding the deck's average hand, keeping the rest of the setup
+    #[wasm_bindgen(js_name = "setDeck")]
+    pub fn set_deck(&mut self, deck_name: &str) -> String {
+        match core::deck_spec::DeckSpec::find_embedded(deck_name) {
+            Some(deck) => {
+                self.start_with_deck(&deck);
+                String::new()
+            }
+            None => format!("Unknown deck '{deck_name}'."),
+        }
+    }
+
+    // deck json in the reference data's format, a bare array of cards
+    #[wasm_bindgen(js_name = "setDeckJson")]
+    pub fn set_deck_json(&mut self, deck_json: &str, deck_name: &str) -> String {
+        match core::deck_spec::DeckSpec::from_json_str(deck_json, deck_name) {
+            Ok(deck) => {
+                self.start_with_deck(&deck);
+                String::new()
+            }
+            Err(message) => message,
+        }
+    }
+
+    fn start_with_deck(&mut self, deck: &core::deck_spec::DeckSpec) {
+        let rule_config = self.state.common.rule_config.clone().with_deck(deck);
+        let deck_setup = default_normal_setup_for_rules(&rule_config);
+        self.normal_setup = NormalSetup {
+            move_cards: deck_setup.move_cards,
+            weapon_cards: deck_setup.weapon_cards,
+            failure_cards: deck_setup.failure_cards,
+            player2_move_cards: deck_setup.player2_move_cards,
+            player2_weapon_cards: deck_setup.player2_weapon_cards,
+            player2_failure_cards: deck_setup.player2_failure_cards,
+            ..self.normal_setup.clone()
+        };
+        let common = self.state.common.clone().with_rule_config(rule_config);
+        self.state = new_state_with_normal_setup(common, &self.normal_setup);
+        self.annotations.clear();
+        self.reset_transient_state();
+        self.notify_state_changed("reset");
+    }
+
+    #[wasm_bindgen(js_name = "exportStateJson")]
+    pub fn export_state_json(&self) -> String {
+        self.export_snapshot(false)
+    }
+
+    // exportStateJson plus every variation undo left behind, for importStateJson to restore
+    #[wasm_bindgen(js_name = "exportStateJsonWithVariations")]
+    pub fn export_state_json_with_variations(&self) -> String {
+        self.export_snapshot(true)
+    }
+
+    fn export_snapshot(&self, include_variations: bool) -> String {
+        let snapshot = PersistedGameState {
+            version: PERSISTED_GAME_STATE_VERSION,
+            board_name: self.state.common.board.json_name.clone(),
+            normal_setup: normalize_normal_setup(&self.normal_setup, &self.state.common),
+            normal_turns: collect_normal_turns(&self.state),
+            rule_config: self.state.common.rule_config.clone(),
+            annotations: self.annotations.values().cloned().collect::<Vec<_>>(),
+            stranger_counts: self.state.common.stranger_counts,
+            aborted: self.state.is_aborted(),
+            attack_history: attack_history_entries(&self.state),
+            board_structure_hash: board_structure_hash_text(&self.state.common.board),
+            variations: include_variations.then(|| self.variations.clone()),
+            teams: self.state.common.teams,
+        };
+
+        serde_json::to_string(&snapshot).unwrap_or_else(|_| {
+            "{\"version\":2,\"boardName\":\"BoardAltDown\",\"normalSetup\":{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"},\"normalTurns\":[]}".to_string()
+        })
+    }
+
+    #[wasm_bindgen(js_name = "importStateJson")]
+    pub fn import_state_json(&mut self, state_json: &str) -> String {
+        self.guard_state(|handle| {
+            match handle.import_snapshot(state_json, &ImportOptions::default()) {
+                Ok(_) => String::new(),
+                Err(message) => message,
+            }
+        })
+        .unwrap_or_else(|message| internal_error_text(&message))
+    }
+
+    // importStateJson with ImportOptions json, answering with an ImportReport json that says which
+    // board the save was mapped from and which turn, if any, the replay stopped at
+    #[wasm_bindgen(js_name = "importStateJsonWithOptions")]
+    pub fn import_state_json_with_options(
+        &mut self,
+        state_json: &str,
+        options_json: &str,
+    ) -> String {
+        let invalid_report = |message: String| ImportReport {
+            is_valid: false,
+            validation_message: message,
+            board_mapped_from: String::new(),
+            num_saved_turns: 0,
+            num_restored_turns: 0,
+            invalid_turn: None,
+            invalid_reason: String::new(),
+        };
+        let options = if options_json.trim().is_empty() {
+            Ok(ImportOptions::default())
+        } else {
+            serde_json::from_str::<ImportOptions>(options_json)
+                .map_err(|err| format!("Invalid import options JSON: {err}"))
+        };
+        let report = match options {
+            Ok(options) => self
+                .guard_state(|handle| handle.import_snapshot(state_json, &options))
+                .map_err(|message| internal_error_text(&message))
+                .and_then(|result| result)
+                .unwrap_or_else(invalid_report),
+            Err(message) => invalid_report(message),
+        };
+        serde_json::to_string(&report).unwrap_or_default()
+    }
+
+    fn import_snapshot(
+        &mut self,
+        state_json: &str,
+        options: &ImportOptions,
+    ) -> Result<ImportReport, String> {
+        let mut snapshot = parse_persisted_game_state(state_json)?;
+        let variations = snapshot.variations.take().unwrap_or_default();
+        variations
+            .validate()
+            .map_err(|message| format!("Saved game has invalid variations: {message}"))?;
+
+        let num_normal_turns = snapshot.normal_turns.len();
+        let mut annotations = BTreeMap::new();
+        for annotation in std::mem::take(&mut snapshot.annotations) {
+            if annotation.ply > num_normal_turns {
+                return Err(format!(
+                    "Saved annotation for ply {} is beyond the {num_normal_turns} saved turns.",
+                    annotation.ply
+                ));
+            }
+            annotations.insert(annotation.ply, annotation);
+        }
+
+        let restored = restore_persisted_game_state_with_options(
+            snapshot,
+            self.state.common.clone(),
+            options,
+        )?;
+        let num_restored_turns = restored.state.ply().max(0) as usize;
+        annotations.retain(|ply, _| *ply <= num_restored_turns);
+        self.normal_setup = restored.normal_setup;
+        self.state = restored.state;
+        self.annotations = annotations;
+        self.variations = variations;
+        self.variations.sync_to_line(self.state.normal_turns());
+        self.reset_transient_state();
+        self.notify_state_changed("import");
+
+        let (invalid_turn, invalid_reason) = restored
+            .invalid_turn
+            .map_or((None, String::new()), |(turn, reason)| (Some(turn), reason));
+        Ok(ImportReport {
+            is_valid: true,
+            validation_message: String::new(),
+            board_mapped_from: restored.board_mapped_from.unwrap_or_default(),
+            num_saved_turns: restored.num_saved_turns,
+            num_restored_turns,
+            invalid_turn,
+            invalid_reason,
+        })
+    }
+
+    #[wasm_bindgen(js_name = "setPlyAnnotation")]
+    pub fn set_ply_annotation(&mut self, ply: usize, annotation_json: &str) -> String {
+        let current_ply = self.state.ply() as usize;
+        if ply > current_ply {
+            return format!("Ply {ply} has not been played yet (current ply is {current_ply}).");
+        }
+
+        let mut annotation = match serde_json::from_str::<PlyAnnotation>(annotation_json) {
+            Ok(annotation) => annotation,
+            Err(err) => return format!("Invalid annotation JSON: {err}"),
+        };
+        annotation.ply = ply;
+        if annotation.is_empty() {
+            self.annotations.remove(&ply);
+        } else {
+            self.annotations.insert(ply, annotation);
+        }
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "setEvaluationWeightsJson")]
+    pub fn set_evaluation_weights_json(&mut self, weights_json: &str) -> String {
+        if weights_json.trim().is_empty() {
+            self.search_config.evaluation_weights = None;
+            self.clear_search_cache();
+            return String::new();
+        }
+
+        match core::evaluation::EvaluationWeights::from_json(weights_json) {
+            Ok(evaluation_weights) => {
+                self.search_config.evaluation_weights = Some(evaluation_weights);
+                self.clear_search_cache();
+                String::new()
+            }
+            Err(message) => message,
+        }
+    }
+
+    // continuous analysis starts each level in a window this wide around the last level's
+    // appraisal; a width of 0 turns that off
+    #[wasm_bindgen(js_name = "setAspirationWindow")]
+    pub fn set_aspiration_window(&mut self, initial_width: f64, growth_factor: f64) -> String {
+        if initial_width == 0.0 {
+            self.search_config.aspiration_window = None;
+            return String::new();
+        }
+        match core::tree_search::AspirationWindow::new(initial_width, growth_factor) {
+            Ok(window) => {
+                self.search_config.aspiration_window = Some(window);
+                String::new()
+            }
+            Err(message) => message,
+        }
+    }
+
+    // search results are kept across turns so analysis after a reply starts warm; the cache holds
+    // at most maxEntries positions, and 0 turns it off
+    #[wasm_bindgen(js_name = "setSearchCacheSize")]
+    pub fn set_search_cache_size(&mut self, max_entries: usize) {
+        self.search_config.transposition_table = (max_entries > 0)
+            .then(|| core::transposition_table::TranspositionTable::shared(max_entries));
+    }
+
+    #[wasm_bindgen(js_name = "clearSearchCache")]
+    pub fn clear_search_cache(&self) {
+        if let Some(table) = self.search_config.transposition_table.as_ref()
+            && let Ok(mut table) = table.lock()
+        {
+            table.clear();
+        }
+    }
+
+    // numEntries, maxEntries, numProbes and numHits; all 0 with the cache off
+    #[wasm_bindgen(js_name = "searchCacheJson")]
+    pub fn search_cache_json(&self) -> String {
+        let stats = self
+            .search_config
+            .transposition_table
+            .as_ref()
+            .and_then(|table| Some(table.lock().ok()?.stats()))
+            .unwrap_or_default();
+        serde_json::to_string(&stats).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "setLateMoveReductions")]
+    pub fn set_late_move_reductions(&mut self, enabled: bool) {
+        self.search_config.late_move_reductions = enabled;
+    }
+
+    #[wasm_bindgen(js_name = "setPassMovePruning")]
+    pub fn set_pass_move_pruning(&mut self, enabled: bool) {
+        self.search_config.pass_move_pruning = enabled;
+    }
+
+    #[wasm_bindgen(js_name = "evaluationWeightsJson")]
+    pub fn evaluation_weights_json(&self) -> String {
+        self.search_config
+            .evaluation_weights
+            .clone()
+            .unwrap_or_default()
+            .to_json()
+    }
+
+    #[wasm_bindgen(js_name = "plyAnnotationJson")]
+    pub fn ply_annotation_json(&self, ply: usize) -> String {
+        let annotation = self
+            .annotations
+            .get(&ply)
+            .cloned()
+            .unwrap_or(PlyAnnotation {
+                ply,
+                ..PlyAnnotation::default()
+            });
+        serde_json::to_string(&annotation).unwrap_or_else(|_| "{}".to_string())
+    }
+
+    #[wasm_bindgen(js_name = "annotationsJson")]
+    pub fn annotations_json(&self) -> String {
+        let annotations = self.annotations.values().collect::<Vec<_>>();
+        serde_json::to_string(&annotations).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchGameRecord {
+    board_name: String,
+    starting_piece_id: PieceId,
+    winner_piece_id: Option<PieceId>, // None for a draw
+    num_normal_turns: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MatchResult {
+    best_of: u32,
+    games_played: usize,
+    player1_wins: usize,
+    player2_wins: usize,
+    is_decided: bool,
+    match_winner_piece_id: String,
+    next_starting_piece_id: String,
+    games: Vec<MatchGameRecord>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_stats: Option<serde_json::Value>,
+}
+
+#[wasm_bindgen]
+pub struct MatchHandle {
+    best_of: u32,
+    first_starting_piece_id: PieceId,
+    games: Vec<MatchGameRecord>,
+    session_stats: Option<(SessionStats, SeatMap)>, // kept across the match's games once tracked
+    seed: u32,
+    last_recorded_game_id: Option<u64>,
+}
+
+impl MatchHandle {
+    fn wins_for(&self, piece_id: PieceId) -> usize {
+        self.games
+            .iter()
+            .filter(|game| game.winner_piece_id == Some(piece_id))
+            .count()
+    }
+
+    fn wins_needed(&self) -> usize {
+        self.best_of as usize / 2 + 1
+    }
+
+    fn match_winner(&self) -> Option<PieceId> {
+        [PieceId::Player1, PieceId::Player2]
+            .into_iter()
+            .find(|piece_id| self.wins_for(*piece_id) >= self.wins_needed())
+    }
+
+    fn next_starting_piece_id(&self) -> PieceId {
+        let other_piece_id = if self.first_starting_piece_id == PieceId::Player1 {
+            PieceId::Player2
+        } else {
+            PieceId::Player1
+        };
+        if self.games.len().is_multiple_of(2) {
+            self.first_starting_piece_id
+        } else {
+            other_piece_id
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl MatchHandle {
+    #[wasm_bindgen(js_name = "isDecided")]
+    pub fn is_decided(&self) -> bool {
+        self.match_winner().is_some() || self.games.len() >= self.best_of as usize
+    }
+
+    // the match's seed picks every game's seed, so a whole match of difficulty engine turns can
+    // be played again
+    #[wasm_bindgen(js_name = "setSeed")]
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+    }
+
+    // the seed for suggestTurn in the game about to be played (the next one recorded)
+    #[wasm_bindgen(js_name = "gameSeed")]
+    pub fn game_seed(&self) -> u32 {
+        util::rng::SplitMix64::new(((self.seed as u64) << 32) | self.games.len() as u64).next_u64()
+            as u32
+    }
+
+    #[wasm_bindgen(js_name = "nextStartingPieceId")]
+    pub fn next_starting_piece_id_str(&self) -> String {
+        self.next_starting_piece_id().as_str().to_string()
+    }
+
+    #[wasm_bindgen(js_name = "recordFinishedGame")]
+    pub fn record_finished_game(&mut self, game: &GameStateHandle) -> String {
+        if self.is_decided() {
+            return "Match is already decided.".to_string();
+        }
+        // a draw gives neither side a point but still counts toward best-of and the alternation
+        let winner_piece_id = match game.state.result {
+            core::mutable_game_state::GameResult::InProgress => {
+                return "Game is not over yet.".to_string();
+            }
+            core::mutable_game_state::GameResult::Aborted => {
+                return "Game was aborted; only finished games are recorded.".to_string();
+            }
+            core::mutable_game_state::GameResult::Won { .. }
+            | core::mutable_game_state::GameResult::Draw { .. } => {
+                winner_piece_id_for_state(&game.state)
+            }
+        };
+        if self.last_recorded_game_id == Some(game.game_id) {
+            return "Game is already recorded; start the next game first.".to_string();
+        }
+        let board_name = game.state.common.board.json_name.clone();
+        if let Some(first_game) = self.games.first()
+            && first_game.board_name != board_name
+        {
+            return format!(
+                "Game board '{board_name}' does not match match board '{}'.",
+                first_game.board_name
+            );
+        }
+
+        self.games.push(MatchGameRecord {
+            board_name,
+            starting_piece_id: game.normal_setup.current_player_piece_id,
+            winner_piece_id,
+            num_normal_turns: collect_normal_turns(&game.state).len(),
+        });
+        self.last_recorded_game_id = Some(game.game_id);
+        if let Some((session_stats, seats)) = self.session_stats.as_mut() {
+            session_stats.record_game(&game.state);
+            *seats = game.state.common.seat_map();
+        }
+        String::new()
+    }
+
+    // off by default; turning it off drops what was tracked
+    #[wasm_bindgen(js_name = "trackSessionStats")]
+    pub fn track_session_stats(&mut self, enabled: bool) {
+        if !enabled {
+            self.session_stats = None;
+        } else if self.session_stats.is_none() {
+            self.session_stats = Some((SessionStats::default(), SeatMap::with_strangers()));
+        }
+    }
+
+    #[wasm_bindgen(js_name = "startNextGame")]
+    pub fn start_next_game(&self, game: &mut GameStateHandle) -> String {
+        if self.is_decided() {
+            return "Match is already decided.".to_string();
+        }
+
+        game.normal_setup.current_player_piece_id = self.next_starting_piece_id();
+        game.reset_game();
+        String::new()
+    }
+
+    #[wasm_bindgen(js_name = "matchResultJson")]
+    pub fn match_result_json(&self) -> String {
+        let result = MatchResult {
+            best_of: self.best_of,
+            games_played: self.games.len(),
+            player1_wins: self.wins_for(PieceId::Player1),
+            player2_wins: self.wins_for(PieceId::Player2),
+            is_decided: self.is_decided(),
+            match_winner_piece_id: self
+                .match_winner()
+                .map(|piece_id| piece_id.as_str().to_string())
+                .unwrap_or_default(),
+            next_starting_piece_id: self.next_starting_piece_id().as_str().to_string(),
+            games: self.games.clone(),
+            session_stats: self
+                .session_stats
+                .as_ref()
+                .and_then(|(session_stats, seats)| {
+                    serde_json::from_str(&session_stats_json_for(session_stats, seats)).ok()
+                }),
+        };
+
+        serde_json::to_string(&result).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+#[wasm_bindgen(js_name = "newMatch")]
+pub fn new_match(best_of: u32, first_starting_piece_id: &str) -> Result<MatchHandle, JsValue> {
+    if best_of == 0 {
+        return Err(JsValue::from_str("bestOf must be >= 1."));
+    }
+    let first_starting_piece_id = match PieceId::parse(first_starting_piece_id) {
+        Some(piece_id @ (PieceId::Player1 | PieceId::Player2)) => piece_id,
+        _ => return Err(JsValue::from_str("firstStartingPieceId must be P1 or P3.")),
+    };
+
+    Ok(MatchHandle {
+        best_of,
+        first_starting_piece_id,
+        games: Vec::new(),
+        session_stats: None,
+        seed: 0,
+        last_recorded_game_id: None,
+    })
+}
+
+fn session_stats_json_for(stats: &SessionStats, seats: &SeatMap) -> String {
+    let summary = SessionStatsSummary {
+        games_played: stats.games_played,
+        wins: stats
+            .wins_by_side
+            .iter()
+            .enumerate()
+            .filter(|(_, wins)| **wins > 0)
+            .map(|(idx, wins)| {
+                let piece_id = seats
+                    .piece_id(core::player::PlayerId(idx))
+                    .map(|piece_id| piece_id.as_str().to_string())
+                    .unwrap_or_else(|| format!("P{}", idx + 1));
+                (piece_id, *wins)
+            })
+            .collect::<BTreeMap<_, _>>(),
+        draws: stats.draws,
+        average_turns: stats.average_turns(),
+        attacks_attempted: stats.attacks_attempted,
+        attacks_succeeded: stats.attacks_succeeded,
+        loots_taken: stats.loots_taken,
+    };
+
+    serde_json::to_string(&summary).unwrap_or_else(|_| "{}".to_string())
+}
+
+#[wasm_bindgen(js_name = "newDefaultGameState")]
+pub fn new_default_game_state() -> Result<GameStateHandle, JsValue> {
+    new_game_state_for_board("BoardAltDown")
+}
+
+#[wasm_bindgen(js_name = "newGameStateForBoard")]
+pub fn new_game_state_for_board(board_name: &str) -> Result<GameStateHandle, JsValue> {
+    game_state_handle_for_board(board_name).map_err(|message| JsValue::from_str(&message))
+}
+
+#[wasm_bindgen(js_name = "engineInfoJson")]
+pub fn engine_info_json() -> String {
+    let rule_config_keys = serde_json::to_value(core::rule_config::RuleConfig::default())
+        .ok()
+        .and_then(|value| {
+            value
+                .as_object()
+                .map(|fields| fields.keys().cloned().collect::<Vec<_>>())
+        })
+        .unwrap_or_default();
+    let info = EngineInfo {
+        engine_version: env!("CARGO_PKG_VERSION").to_string(),
+        rule_config_keys,
+        preset_names: core::game_preset::game_presets()
+            .into_iter()
+            .filter(is_web_playable)
+            .map(|preset| preset.name.to_string())
+            .collect::<Vec<_>>(),
+        persisted_state_version: PERSISTED_GAME_STATE_VERSION,
+        persisted_state_versions_supported: vec![
+            PERSISTED_GAME_STATE_VERSION_WITHOUT_ANNOTATIONS,
+            PERSISTED_GAME_STATE_VERSION,
+        ],
+        position_key_version: core::mutable_game_state::MutableGameState::POSITION_KEY_VERSION,
+        game_features_version: core::evaluation::GAME_FEATURES_VERSION,
+    };
+    serde_json::to_string(&info).unwrap_or_else(|_| "{}".to_string())
+}
+
+#[wasm_bindgen(js_name = "listPresetsJson")]
+pub fn list_presets_json() -> String {
+    let presets = core::game_preset::game_presets()
+        .into_iter()
+        .filter(is_web_playable)
+        .map(|preset| GamePresetSummary {
+            name: preset.name.to_string(),
+            description: preset.description.to_string(),
+            board_name: preset.board_name.to_string(),
+            num_normal_players: preset.num_normal_players,
+            stranger_counts: preset
+                .stranger_counts
+                .unwrap_or(StrangerCounts::default_for(preset.num_normal_players)),
+            teams: preset.teams,
+            rule_config: preset.rule_config,
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&presets).unwrap_or_else(|_| "[]".to_string())
+}
+
+// board json for the board editor from the tab-separated board text format (see board_text); the
+// board is built too, so a text that parses but can't make a board is still invalid
+#[wasm_bindgen(js_name = "boardJsonFromText")]
+pub fn board_json_from_text(board_text: &str) -> String {
+    let board_json = core::board::Board::from_text_str(board_text, "BoardText")
+        .map_err(|err| match err {
+            core::board::BoardLoadError::Text { message, .. } => message,
+            err => err.to_string(),
+        })
+        .and_then(|board| serde_json::to_string_pretty(&board.spec).map_err(|err| err.to_string()));
+    board_conversion_json(board_json.map(|board_json| (board_json, String::new())))
+}
+
+#[wasm_bindgen(js_name = "boardTextFromJson")]
+pub fn board_text_from_json(board_json: &str) -> String {
+    let board_text = core::board::BoardSpecification::from_json_str(board_json)
+        .map(|spec| (String::new(), spec.to_text()))
+        .map_err(|err| format!("Invalid board JSON: {err}"));
+    board_conversion_json(board_text)
+}
+
+fn board_conversion_json(converted: Result<(String, String), String>) -> String {
+    let response = match converted {
+        Ok((board_json, board_text)) => BoardConversionResponse {
+            is_valid: true,
+            validation_message: String::new(),
+            board_json,
+            board_text,
+        },
+        Err(message) => BoardConversionResponse {
+            is_valid: false,
+            validation_message: message,
+            board_json: String::new(),
+            board_text: String::new(),
+        },
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+// kind is board, puzzle, preset or deck; empty lists every kind, and an unknown kind lists nothing
+#[wasm_bindgen(js_name = "listAssetsJson")]
+pub fn list_assets_json(kind: &str) -> String {
+    let assets = if kind.trim().is_empty() {
+        core::asset_registry::list_assets(None)
+    } else {
+        core::asset_registry::AssetKind::parse(kind)
+            .map(|kind| core::asset_registry::list_assets(Some(kind)))
+            .unwrap_or_default()
+    };
+    serde_json::to_string(&assets).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen(js_name = "listPuzzlesJson")]
+pub fn list_puzzles_json() -> String {
+    let puzzles = core::puzzle::Puzzle::embedded()
+        .into_iter()
+        .map(|puzzle| PuzzleSummary {
+            name: puzzle.name,
+            description: puzzle.description,
+            board_name: puzzle.board_name,
+            goal: puzzle.goal,
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_string(&puzzles).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen(js_name = "listBoardNamesJson")]
+pub fn list_board_names_json() -> String {
+    let board_names = core::board::Board::embedded_board_names()
+        .chain(core::board::Board::generated_board_names())
+        .collect::<Vec<_>>();
+    serde_json::to_string(&board_names).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen(js_name = "newGameFromPreset")]
+pub fn new_game_from_preset(preset_name: &str) -> Result<GameStateHandle, JsValue> {
+    game_state_handle_for_preset(preset_name).map_err(|message| JsValue::from_str(&message))
+}
+
+fn game_state_handle_for_board(board_name: &str) -> Result<GameStateHandle, String> {
+    let board =
+        core::board::Board::from_embedded_json(board_name).map_err(|err| err.to_string())?;
+    let common = core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
+    Ok(new_game_state_handle(common))
+}
+
+fn game_state_handle_for_preset(preset_name: &str) -> Result<GameStateHandle, String> {
+    let Some(preset) = core::game_preset::find_game_preset(preset_name) else {
+        return Err(format!("Unknown preset '{preset_name}'."));
+    };
+    if !is_web_playable(&preset) {
+        return Err(format!(
+            "Preset '{}' needs {} players, but the web game supports only 2.",
+            preset.name, preset.num_normal_players
+        ));
+    }
+
+    let common = preset
+        .common_game_state(true)
+        .map_err(|err| err.to_string())?;
+    Ok(new_game_state_handle(common))
+}
+
+// the handle's setup and seats are for two players, so four-player presets such as teams aren't
+// listed or started here
+fn is_web_playable(preset: &core::game_preset::GamePreset) -> bool {
+    preset.num_normal_players == core::rule_helper::NUM_NORMAL_PLAYERS_WHEN_HAVE_STRANGERS
+}
+
+fn new_game_state_handle(common: core::common_game_state::CommonGameState) -> GameStateHandle {
+    let normal_setup = normalize_normal_setup(
+        &default_normal_setup_for_rules(&common.rule_config),
+        &common,
+    );
+    let state = new_state_with_normal_setup(common, &normal_setup);
+    GameStateHandle {
+        state,
+        normal_setup,
+        annotations: BTreeMap::new(),
+        blunder_check: None,
+        appraisal_history_level: 0,
+        input_limits: InputLimits::default(),
+        last_turn_swing: None,
+        last_turn_broadcast: None,
+        search_config: core::tree_search::SearchConfig::default().with_transposition_table(
+            core::transposition_table::TranspositionTable::DEFAULT_MAX_ENTRIES,
+        ),
+        history_limit: None,
+        continuous_analysis: ContinuousAnalysis::default(),
+        session_stats: SessionStats::default(),
+        on_state_changed: None,
+        puzzle: None,
+        variations: VariationTree::default(),
+        autosave: Autosave::default(),
+        random_start: RandomStart::default(),
+        seat_lock: SeatLock::default(),
+        remote_session_id: String::new(),
+        preview_cache: RefCell::default(),
+        game_id: next_game_id(),
+    }
+}
+
+#[wasm_bindgen(js_name = "searchFromStateJson")]
+pub fn search_from_state_json(persisted_state_json: &str, analysis_level: i32) -> String {
+    if let Err(err) = InputLimits::default().check_analysis_level(analysis_level) {
+        return invalid_best_turn_analysis_json(err.to_string(), 0, 0.0);
+    }
+    let snapshot = match parse_persisted_game_state(persisted_state_json) {
+        Ok(snapshot) => snapshot,
+        Err(message) => return invalid_best_turn_analysis_json(message, 0, 0.0),
+    };
+    let board = match core::board::Board::from_embedded_json(&snapshot.board_name) {
+        Ok(board) => board,
+        Err(err) => return invalid_best_turn_analysis_json(err.to_string(), 0, 0.0),
+    };
+    let common = core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
+
+    match restore_persisted_game_state(snapshot, common) {
+        Ok((state, _)) => best_turn_analysis_json_for_state(
+            &state,
+            analysis_level,
+            &core::tree_search::SearchConfig::default().with_transposition_table(
+                core::transposition_table::TranspositionTable::DEFAULT_MAX_ENTRIES,
+            ),
+        ),
+        Err(message) => invalid_best_turn_analysis_json(message, 0, 0.0),
+    }
+}
+
+// plyDiffJson between the final positions of two saves of the same board
+#[wasm_bindgen(js_name = "snapshotDiffJson")]
+pub fn snapshot_diff_json(from_state_json: &str, to_state_json: &str) -> String {
+    match (
+        state_from_persisted_json(from_state_json),
+        state_from_persisted_json(to_state_json),
+    ) {
+        (Ok(from), Ok(to)) => {
+            state_diff_json(core::state_diff::diff_states(&from, &to), &from.common)
+        }
+        (Err(message), _) | (_, Err(message)) => invalid_state_diff_json(message),
+    }
+}
+
+fn state_from_persisted_json(
+    persisted_state_json: &str,
+) -> Result<core::mutable_game_state::MutableGameState, String> {
+    let snapshot = parse_persisted_game_state(persisted_state_json)?;
+    let board = core::board::Board::from_embedded_json(&snapshot.board_name)
+        .map_err(|err| err.to_string())?;
+    let common = core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
+    restore_persisted_game_state(snapshot, common).map(|(state, _)| state)
+}
+
+fn parse_persisted_game_state(state_json: &str) -> Result<PersistedGameState, String> {
+    let snapshot = serde_json::from_str::<PersistedGameState>(state_json)
+        .map_err(|err| format!("Invalid saved game JSON: {err}"))?;
+
+    if snapshot.version != PERSISTED_GAME_STATE_VERSION
+        && snapshot.version != PERSISTED_GAME_STATE_VERSION_WITHOUT_ANNOTATIONS
+    {
+        return Err(format!(
+            "Unsupported saved game version {}.",
+            snapshot.version
+        ));
+    }
+
+    Ok(snapshot)
+}
+
+fn restore_persisted_game_state(
+    snapshot: PersistedGameState,
+    common: core::common_game_state::CommonGameState,
+) -> Result<(core::mutable_game_state::MutableGameState, NormalSetup), String> {
+    restore_persisted_game_state_with_options(snapshot, common, &ImportOptions::default())
+        .map(|restored| (restored.state, restored.normal_setup))
+}
+
+fn board_structure_hash_text(board: &core::board::Board) -> String {
+    format!("{:016x}", board.structure_hash())
+}
+
+fn restore_persisted_game_state_with_options(
+    snapshot: PersistedGameState,
+    common: core::common_game_state::CommonGameState,
+    options: &ImportOptions,
+) -> Result<RestoredGame, String> {
+    let board_mapped_from = if is_matching_board_name(
+        &snapshot.board_name,
+        &common.board.name,
+        &common.board.json_name,
+    ) {
+        None
+    } else if !options.allow_matching_board_structure {
+        return Err(format!(
+            "Saved game board '{}' does not match current board '{}'.",
+            snapshot.board_name, common.board.json_name
+        ));
+    } else if snapshot.board_structure_hash.is_empty() {
+        return Err(format!(
+            "Saved game board '{}' does not match current board '{}', and the save has no board structure hash to compare.",
+            snapshot.board_name, common.board.json_name
+        ));
+    } else if snapshot.board_structure_hash != board_structure_hash_text(&common.board) {
+        return Err(format!(
+            "Saved game board '{}' has a different room structure than current board '{}'.",
+            snapshot.board_name, common.board.json_name
+        ));
+    } else {
+        Some(snapshot.board_name.clone())
+    };
+    let setup_board_matches = match board_mapped_from.as_deref() {
+        Some(saved_board_name) => {
+            snapshot.normal_setup.board_name.is_empty()
+                || snapshot.normal_setup.board_name == saved_board_name
+        }
+        None => is_legacy_or_matching_board_name(
+            &snapshot.normal_setup.board_name,
+            &common.board.name,
+            &common.board.json_name,
+        ),
+    };
+    if !setup_board_matches {
+        return Err(format!(
+            "Saved game setup board '{}' does not match current board '{}'.",
+            snapshot.normal_setup.board_name, common.board.json_name
+        ));
+    }
+
+    let common = common
+        .with_rule_config(snapshot.rule_config)
+        .with_stranger_counts(snapshot.stranger_counts)
+        .map_err(|message| format!("Saved game has invalid stranger counts: {message}"))?;
+    let common = match snapshot.teams {
+        Some(teams) => common
+            .with_teams(teams)
+            .map_err(|message| format!("Saved game has invalid teams: {message}"))?,
+        None => common,
+    };
+    let normalized_setup = normalize_normal_setup(&snapshot.normal_setup, &common);
+    validate_normal_setup(&normalized_setup, &common)
+        .map_err(|message| format!("Saved game has invalid setup: {message}"))?;
+
+    let aborted = snapshot.aborted;
+    let saved_attack_history = snapshot.attack_history;
+    let num_saved_turns = snapshot.normal_turns.len();
+    let mut restored = new_state_with_normal_setup(common, &normalized_setup);
+    let mut invalid_turn = None;
+    for (turn_idx, turn) in snapshot.normal_turns.into_iter().enumerate() {
+        if let Err(err) = restored.check_normal_turn(&turn) {
+            if !options.truncate_at_invalid_turn {
+                return Err(format!("Saved turn {} is invalid: {err}", turn_idx + 1));
+            }
+            invalid_turn = Some((turn_idx + 1, err.to_string()));
+            break;
+        }
+        restored.apply_turn(turn);
+        if options.compact_history {
+            restored.compact_all_history();
+        }
+    }
+    // a replay cut short never reached the end the save's result and attack history describe
+    if invalid_turn.is_none() {
+        if aborted && !restored.abort() {
+            return Err(
+                "Saved game is marked aborted but its turns already finish it.".to_string(),
+            );
+        }
+        if !saved_attack_history.is_empty()
+            && saved_attack_history != attack_history_entries(&restored)
+        {
+            return Err("Saved attack history does not match its turns.".to_string());
+        }
+    }
+
+    Ok(RestoredGame {
+        state: restored,
+        normal_setup: normalized_setup,
+        board_mapped_from,
+        num_saved_turns,
+        invalid_turn,
+    })
+}
+
+// pieces are looked up in the game's own seats, so stranger counts decide who player2 is
+fn parse_turn_plan(
+    turn_plan_json: &str,
+    common: &core::common_game_state::CommonGameState,
+    input_limits: &InputLimits,
+) -> Result<core::simple_turn::SimpleTurn, ValidationError> {
+    let board = &common.board;
+    let seats = common.seat_map();
+    let trimmed = turn_plan_json.trim();
+    if trimmed.len() > input_limits.max_plan_json_len {
+        return Err(ValidationError::PlanTooLong {
+            length: trimmed.len(),
+            limit: input_limits.max_plan_json_len,
+        });
+    }
+    let entries = if trimmed.is_empty() {
+        Vec::new()
+    } else {
+        serde_json::from_str::<Vec<TurnPlanEntry>>(trimmed).map_err(|err| {
+            ValidationError::MalformedPlan {
+                detail: format!("Invalid turn plan JSON: {err}"),
+            }
+        })?
+    };
+    if entries.len() > input_limits.max_plan_moves {
+        return Err(ValidationError::TooManyMoves {
+            count: entries.len(),
+            limit: input_limits.max_plan_moves,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    let moves = entries
+        .into_iter()
+        .map(|entry| {
+            if !seen.insert(entry.piece_id) {
+                return Err(ValidationError::MalformedPlan {
+                    detail: format!(
+                        "{} appears more than once in the turn plan.",
+                        entry.piece_id
+                    ),
+                });
+            }
+
+            if entry.piece_id == PieceId::Doctor {
+                return Err(ValidationError::MalformedPlan {
+                    detail: "Doctor cannot be moved.".to_string(),
+                });
+            }
+            let Some(player_id) = seats
+                .player_id(entry.piece_id)
+                .filter(|player_id| player_id.0 < common.num_all_players)
+            else {
+                return Err(ValidationError::MalformedPlan {
+                    detail: format!("{} is not in this game.", entry.piece_id),
+                });
+            };
+
+            let room_id = match (entry.room_id, entry.room_name.as_deref()) {
+                (Some(room_id), _) => core::room::RoomId(room_id),
+                (None, Some(room_name)) => board.resolve_room_name(room_name)?,
+                (None, None) => {
+                    return Err(ValidationError::MalformedPlan {
+                        detail: format!("{} needs a roomId or roomName.", entry.piece_id),
+                    });
+                }
+            };
+
+            Ok(core::player::PieceMove::new(player_id, room_id))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(core::simple_turn::SimpleTurn::new(moves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_common() -> core::common_game_state::CommonGameState {
+        let board = core::board::Board::from_embedded_json("Tiny").expect("tiny board should load");
+        core::common_game_state::CommonGameState::from_num_normal_players(false, board, 2)
+    }
+
+    #[test]
+    fn normalize_normal_setup_fills_missing_advanced_values_from_board_defaults() {
+        let common = sample_common();
+        let normalized = normalize_normal_setup(&default_normal_setup(), &common);
+
+        assert_eq!(
+            normalized.doctor_room_id,
+            common.board.doctor_start_room_id.0
+        );
+        assert_eq!(
+            normalized.player1_room_id,
+            common.board.player_start_room_id.0
+        );
+        assert_eq!(
+            normalized.stranger1_room_id,
+            common.board.player_start_room_id.0
+        );
+        assert_eq!(
+            normalized.player2_room_id,
+            common.board.player_start_room_id.0
+        );
+        assert_eq!(
+            normalized.stranger2_room_id,
+            common.board.player_start_room_id.0
+        );
+        assert_eq!(
+            normalized.player1_strength,
+            core::rule_helper::PLAYER_STARTING_STRENGTH
+        );
+        assert_eq!(
+            normalized.stranger1_strength,
+            core::rule_helper::PLAYER_STARTING_STRENGTH
+        );
+        assert_eq!(
+            normalized.player2_strength,
+            core::rule_helper::PLAYER_STARTING_STRENGTH
+        );
+        assert_eq!(
+            normalized.stranger2_strength,
+            core::rule_helper::PLAYER_STARTING_STRENGTH
+        );
+        assert_eq!(normalized.turn_id, 1);
+        assert_eq!(normalized.current_player_piece_id, PieceId::Player1);
+    }
+
+    #[test]
+    fn new_state_with_normal_setup_applies_rooms_and_current_player() {
+        let common = sample_common();
+        let setup = NormalSetup {
+            board_name: String::new(),
+            move_cards: 0.5,
+            weapon_cards: 1.5,
+            failure_cards: 2.5,
+            player2_move_cards: 3.5,
+            player2_weapon_cards: 4.5,
+            player2_failure_cards: 5.5,
+            doctor_room_id: 1,
+            player1_room_id: 2,
+            stranger1_room_id: 1,
+            player2_room_id: 1,
+            stranger2_room_id: 2,
+            player1_strength: 3,
+            stranger1_strength: 4,
+            player2_strength: 5,
+            stranger2_strength: 6,
+            turn_id: 7,
+            current_player_piece_id: PieceId::Player2,
+            piece_labels: Vec::new(),
+        };
+
+        let state = new_state_with_normal_setup(common, &setup);
+
+        assert_eq!(state.doctor_room_id.0, 1);
+        assert_eq!(
+            state.player_room_ids()[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0].0,
+            2
+        );
+        assert_eq!(
+            state.player_room_ids()[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0].0,
+            1
+        );
+        assert_eq!(
+            state.player_room_ids()[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].0,
+            1
+        );
+        assert_eq!(
+            state.player_room_ids()[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0].0,
+            2
+        );
+        assert_eq!(
+            state.current_player_id,
+            core::rule_helper::SIDE_B_NORMAL_PLAYER_ID
+        );
+        assert_eq!(
+            state.player_move_cards[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0].to_f64(),
+            0.5
+        );
+        assert_eq!(
+            state.player_strengths[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0],
+            3
+        );
+        assert_eq!(
+            state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0],
+            4
+        );
+        assert_eq!(
+            state.player_weapons[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].to_f64(),
+            4.5
+        );
+        assert_eq!(
+            state.player_strengths[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0],
+            5
+        );
+        assert_eq!(
+            state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0],
+            6
+        );
+        assert_eq!(
+            state.player_failures[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0].to_f64(),
+            2.5
+        );
+        assert_eq!(
+            state.player_move_cards[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].to_f64(),
+            3.5
+        );
+        assert_eq!(
+            state.player_failures[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].to_f64(),
+            5.5
+        );
+        assert_eq!(state.turn_id, 7);
+    }
+
+    #[test]
+    fn validate_normal_setup_rejects_non_normal_current_player() {
+        let common = sample_common();
+        let setup = NormalSetup {
+            current_player_piece_id: PieceId::Doctor,
+            ..normalize_normal_setup(&default_normal_setup(), &common)
+        };
+
+        assert_eq!(
+            validate_normal_setup(&setup, &common),
+            Err("currentPlayerPieceId must be P1 or P3.".to_string())
+        );
+    }
+
+    #[test]
+    fn attack_history_text_includes_attacker_room_and_turn_in_order() {
+        let attack_by = |attacker| core::mutable_game_state::AttackRecord {
+            turn_id: 1,
+            attacker,
+            strength: core::card_count::CardCount::whole(1),
+            defense_spent: core::card_count::CardCount::ZERO,
+            succeeded: false,
+        };
+        let common = sample_common();
+        let mut turn_8 = core::mutable_game_state::MutableGameState::at_start(common);
+        turn_8.turn_id = 8;
+        turn_8.current_player_id = core::player::PlayerId(0);
+        turn_8.move_piece(core::player::PlayerId(0), core::room::RoomId(3));
+
+        let mut turn_9 = turn_8.copy_state();
+        turn_9.prev_state = Some(std::sync::Arc::new(turn_8));
+        turn_9.turn_id = 9;
+        turn_9.current_player_id = core::player::PlayerId(1);
+        turn_9
+            .attack_hist
+            .push(attack_by(core::player::PlayerId(0)));
+
+        let mut turn_10 = turn_9.copy_state();
+        turn_10.prev_state = Some(std::sync::Arc::new(turn_9));
+        turn_10.turn_id = 10;
+        turn_10
+            .attack_hist
+            .push(attack_by(core::player::PlayerId(1)));
+        turn_10.move_piece(core::player::PlayerId(1), core::room::RoomId(1));
+
+        assert_eq!(attack_history_text_for_state(&turn_10), "1@3,T1; 2@1,T1");
+        assert_eq!(attack_history_label_for_state(&turn_10), "Atks1,1");
+    }
+
+    #[test]
+    fn stranger_counts_change_piece_ids_and_survive_export() {
+        let mut handle = new_default_game_state().expect("default game should load");
+        assert_eq!(
+            handle.piece_ids_json(),
+            r#"["doctor","player1","player2","stranger1","stranger2"]"#
+        );
+        assert!(!handle.set_stranger_counts(3, 0).is_empty());
+
+        assert_eq!(handle.set_stranger_counts(2, 0), "");
+        assert_eq!(
+            handle.piece_ids_json(),
+            r#"["doctor","player1","player2","stranger1","stranger2"]"#
+        );
+        assert_eq!(handle.piece_positions().len(), 5);
+        assert_eq!(handle.piece_strength("stranger2"), 1);
+        assert_eq!(handle.piece_strength("stranger3"), 0);
+
+        let exported = handle.export_state_json();
+        let mut restored = new_default_game_state().expect("default game should load");
+        assert_eq!(restored.import_state_json(&exported), "");
+        assert_eq!(restored.stranger_counts_json(), r#"{"sideA":2,"sideB":0}"#);
+        assert_eq!(restored.load_board("BoardAltUp"), "");
+        assert_eq!(restored.stranger_counts_json(), r#"{"sideA":2,"sideB":0}"#);
+    }
+
+    #[test]
+    fn action_outlook_matches_the_actions_turns_would_get() {
+        let handle = new_default_game_state().expect("default game should load");
+        let outlook =
+            serde_json::from_str::<serde_json::Value>(&handle.current_action_outlook_json())
+                .expect("outlook should be json");
+        assert_eq!(outlook["isValid"], true);
+        assert_eq!(outlook["pieceId"], "player1");
+        assert_eq!(
+            outlook["seenByPieceIds"],
+            serde_json::json!(["stranger1", "player2", "stranger2"])
+        );
+        assert_eq!(outlook["actionIfStaying"], "none");
+
+        let loot_room_ids = outlook["lootRoomIds"]
+            .as_array()
+            .expect("loot rooms should be an array");
+        assert!(!loot_room_ids.is_empty());
+        for room_id in loot_room_ids {
+            let turn = core::simple_turn::SimpleTurn::single(
+                core::player::PlayerId(0),
+                core::room::RoomId(room_id.as_u64().expect("room id") as usize),
+            );
+            assert!(handle.state.check_normal_turn(&turn).is_ok());
+            assert!(current_player_loots_after_turn(&handle.state, &turn));
+        }
+    }
 
-        let analysis_level = analysis_level.max(0);
-        let token = util::cancellation::NeverCancelToken;
-        let mut num_states_visited = 0usize;
-        let started_ms = now_ms();
-        let appraised_turn = core::tree_search::TreeSearch::find_best_turn(
-            &self.state,
-            analysis_level,
-            &token,
-            &mut num_states_visited,
+    #[test]
+    fn occupants_of_room_lists_the_pieces_and_doctor_there() {
+        let handle = new_default_game_state().expect("default game should load");
+        let start_room_id = handle.state.player_room_ids()[0].0;
+        let occupants =
+            serde_json::from_str::<serde_json::Value>(&handle.occupants_of_room(start_room_id))
+                .expect("occupants should be json");
+        assert_eq!(occupants["isValid"], true);
+        assert_eq!(
+            occupants["pieceIds"],
+            serde_json::json!(["player1", "stranger1", "player2", "stranger2"])
+        );
+        assert_eq!(
+            occupants["hasDoctor"],
+            handle.state.doctor_room_id.0 == start_room_id
         );
-        let elapsed_ms = (now_ms() - started_ms).max(0.0);
 
-        let has_strangers = self.state.common.has_strangers();
-        let mut suggested_turn = Vec::with_capacity(appraised_turn.turn.moves.len());
-        for player_move in &appraised_turn.turn.moves {
-            let Some(piece_id) = PieceId::from_player_id(player_move.player_id, has_strangers)
-            else {
-                return invalid_best_turn_analysis_json(
-                    format!(
-                        "Could not map player {} to a piece id.",
-                        player_move.player_id.0
-                    ),
-                    num_states_visited,
-                    elapsed_ms,
-                );
-            };
+        let occupants = serde_json::from_str::<serde_json::Value>(&handle.occupants_of_room(999))
+            .expect("occupants should be json");
+        assert_eq!(occupants["isValid"], false);
+        assert_eq!(occupants["pieceIds"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn room_occupancy_json_lines_up_plies_with_rooms() {
+        let mut handle = new_default_game_state().expect("default game should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        let occupancy = serde_json::from_str::<serde_json::Value>(&handle.room_occupancy_json())
+            .expect("room occupancy should be json");
+        let num_rooms = occupancy["rooms"].as_array().map(Vec::len).unwrap_or(0);
+        assert!(num_rooms > 0);
+        assert_eq!(occupancy["pieces"][0]["pieceId"], "doctor");
+        assert_eq!(occupancy["pieces"][1]["pieceId"], "player1");
+        for piece in occupancy["pieces"]
+            .as_array()
+            .expect("pieces should be a list")
+        {
+            let plies = piece["plies"].as_array().expect("plies should be a list");
+            assert_eq!(plies.len(), num_rooms);
+            assert_eq!(
+                plies.iter().filter_map(|count| count.as_u64()).sum::<u64>(),
+                occupancy["numPlies"].as_u64().unwrap_or(0)
+            );
+        }
+    }
+
+    #[test]
+    fn activation_context_json_reports_the_activated_piece() {
+        let handle = new_default_game_state().expect("default game should load");
+        let context = serde_json::from_str::<serde_json::Value>(&handle.activation_context_json())
+            .expect("activation context should be json");
+        assert_eq!(context["pieceId"], "player1");
+        assert_eq!(context["isActivated"], false);
+        assert_eq!(context["doctorRoomId"], serde_json::Value::Null);
+        assert_eq!(context["restrictions"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn export_state_json_uses_board_json_name() {
+        let board = core::board::Board::from_embedded_json("BoardAltDown")
+            .expect("AltDown board should load");
+        let common =
+            core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
+        let normal_setup = normalize_normal_setup(&default_normal_setup(), &common);
+        let state = new_state_with_normal_setup(common, &normal_setup);
+        let handle = GameStateHandle {
+            state,
+            normal_setup,
+            annotations: BTreeMap::new(),
+            blunder_check: None,
+            appraisal_history_level: 0,
+            input_limits: InputLimits::default(),
+            last_turn_swing: None,
+            last_turn_broadcast: None,
+            search_config: core::tree_search::SearchConfig::default(),
+            history_limit: None,
+            continuous_analysis: ContinuousAnalysis::default(),
+            session_stats: SessionStats::default(),
+            on_state_changed: None,
+            puzzle: None,
+            variations: VariationTree::default(),
+            autosave: Autosave::default(),
+            random_start: RandomStart::default(),
+            seat_lock: SeatLock::default(),
+            remote_session_id: String::new(),
+            preview_cache: RefCell::default(),
+            game_id: next_game_id(),
+        };
+
+        let snapshot = serde_json::from_str::<PersistedGameState>(&handle.export_state_json())
+            .expect("export should be valid persisted game json");
+
+        assert_eq!(snapshot.board_name, "BoardAltDown");
+        assert_eq!(snapshot.normal_setup.board_name, "BoardAltDown");
+    }
+
+    #[test]
+    fn turn_plan_cost_sums_distances_across_pieces() {
+        let handle = new_game_state_for_board("Tiny").expect("tiny board should load");
+        let cost = serde_json::from_str::<serde_json::Value>(&handle.turn_plan_cost_json(
+            r#"[{"pieceId":"player1","roomId":2},{"pieceId":"stranger2","roomId":2}]"#,
+        ))
+        .expect("cost should be valid json");
+
+        assert_eq!(cost["isValid"], true);
+        assert_eq!(cost["totalDistance"], 2);
+        assert_eq!(cost["moveCardsSpent"], 1.0);
+        assert_eq!(cost["moveCardsRemaining"], 0.0);
+        assert_eq!(cost["moves"][0]["distance"], 1);
+        assert_eq!(cost["moves"][1]["pieceId"], "stranger2");
+        assert_eq!(cost["moves"][1]["distance"], 1);
+    }
+
+    fn finish_game_with_winner(handle: &mut GameStateHandle, winner_piece_id: PieceId) {
+        let winner = handle
+            .state
+            .common
+            .seat_map()
+            .player_id(winner_piece_id)
+            .expect("winner should be a player");
+        let mut finished = handle.state.copy_state();
+        finished.prev_state = Some(std::sync::Arc::new(handle.state.copy_state()));
+        finished.result = core::mutable_game_state::GameResult::Won { player: winner };
+        handle.state = finished;
+    }
+
+    #[test]
+    fn match_handle_alternates_starting_player_and_decides_best_of_three() {
+        let mut handle = new_game_state_for_board("Tiny").expect("tiny board should load");
+        let mut game_match = new_match(3, "player1").expect("match should start");
+
+        assert_eq!(game_match.start_next_game(&mut handle), "");
+        assert_eq!(handle.current_player_piece_id(), "player1");
+        finish_game_with_winner(&mut handle, PieceId::Player2);
+        assert_eq!(game_match.record_finished_game(&handle), "");
+
+        assert_eq!(game_match.start_next_game(&mut handle), "");
+        assert_eq!(handle.current_player_piece_id(), "player2");
+        assert_eq!(
+            game_match.record_finished_game(&handle),
+            "Game is not over yet."
+        );
+        finish_game_with_winner(&mut handle, PieceId::Player2);
+        assert_eq!(game_match.record_finished_game(&handle), "");
+
+        let result = serde_json::from_str::<serde_json::Value>(&game_match.match_result_json())
+            .expect("match result should be valid json");
+        assert_eq!(result["gamesPlayed"], 2);
+        assert_eq!(result["player2Wins"], 2);
+        assert_eq!(result["isDecided"], true);
+        assert_eq!(result["matchWinnerPieceId"], "player2");
+        assert_eq!(
+            game_match.start_next_game(&mut handle),
+            "Match is already decided."
+        );
+    }
+
+    #[test]
+    fn match_handle_records_draws_and_turns_away_aborted_games() {
+        let mut handle = new_game_state_for_board("Tiny").expect("tiny board should load");
+        let mut game_match = new_match(3, "player1").expect("match should start");
+
+        assert_eq!(game_match.start_next_game(&mut handle), "");
+        assert_eq!(handle.abort_game(), "");
+        assert_eq!(
+            game_match.record_finished_game(&handle),
+            "Game was aborted; only finished games are recorded."
+        );
+
+        assert_eq!(game_match.start_next_game(&mut handle), "");
+        handle.state.result = core::mutable_game_state::GameResult::Draw {
+            reason: core::mutable_game_state::DrawReason::Repetition,
+        };
+        assert_eq!(game_match.record_finished_game(&handle), "");
+
+        let result = serde_json::from_str::<serde_json::Value>(&game_match.match_result_json())
+            .expect("match result should be valid json");
+        assert_eq!(result["gamesPlayed"], 1);
+        assert_eq!(result["player1Wins"], 0);
+        assert_eq!(result["player2Wins"], 0);
+        assert_eq!(result["games"][0]["winnerPieceId"], serde_json::Value::Null);
+        assert_eq!(result["isDecided"], false);
+        assert_eq!(result["nextStartingPieceId"], "player2");
+    }
+
+    #[test]
+    fn match_seed_replays_suggested_turns_from_a_save() {
+        let mut game_match = new_match(3, "player1").expect("match should start");
+        game_match.set_seed(42);
+        let game_seed = game_match.game_seed();
+        let mut same_match = new_match(3, "player1").expect("match should start");
+        same_match.set_seed(42);
+        assert_eq!(same_match.game_seed(), game_seed);
+        same_match.set_seed(43);
+        assert_ne!(same_match.game_seed(), game_seed);
+
+        let suggested_text = |handle: &GameStateHandle| {
+            serde_json::from_str::<serde_json::Value>(&handle.suggest_turn("easy", game_seed))
+                .expect("suggestion should be json")["suggestedTurnText"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string()
+        };
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let mut saves = Vec::new();
+        let mut suggestions = Vec::new();
+        for _ in 0..3 {
+            saves.push(handle.export_state_json());
+            let turn_text = suggested_text(&handle);
+            let turn = handle
+                .state
+                .possible_turns()
+                .into_iter()
+                .find(|turn| turn.to_string() == turn_text)
+                .expect("the suggestion should be a legal turn");
+            suggestions.push(turn_text);
+            handle.apply_checked_turn(turn);
+        }
+
+        for (save, suggestion) in saves.iter().zip(&suggestions) {
+            let mut replay = new_default_game_state().expect("default game state should load");
+            assert_eq!(replay.import_state_json(save), "");
+            assert_eq!(suggested_text(&replay), *suggestion);
+        }
+    }
+
+    #[test]
+    fn session_stats_count_finished_games_and_survive_in_the_match() {
+        let mut handle = new_game_state_for_board("Tiny").expect("tiny board should load");
+        let mut game_match = new_match(3, "player1").expect("match should start");
+        game_match.track_session_stats(true);
+
+        while !handle.state.is_game_over() {
+            let turn = handle.state.possible_turns()[0].clone();
+            handle.apply_checked_turn(turn);
+        }
+        let stats = serde_json::from_str::<serde_json::Value>(&handle.session_stats_json())
+            .expect("session stats should be json");
+        assert_eq!(stats["gamesPlayed"], 1);
+        assert!(stats["lootsTaken"].is_u64());
+        assert!(handle.undo_last_turn());
+        assert!(handle.session_stats_json().contains(r#""gamesPlayed":0"#));
+
+        finish_game_with_winner(&mut handle, PieceId::Player2);
+        assert_eq!(game_match.record_finished_game(&handle), "");
+        assert_eq!(
+            game_match.record_finished_game(&handle),
+            "Game is already recorded; start the next game first."
+        );
+        let result = serde_json::from_str::<serde_json::Value>(&game_match.match_result_json())
+            .expect("match result should be valid json");
+        assert_eq!(result["gamesPlayed"], 1);
+        assert_eq!(result["sessionStats"]["gamesPlayed"], 1);
+        assert_eq!(result["sessionStats"]["wins"]["player2"], 1);
+
+        game_match.track_session_stats(false);
+        assert!(!game_match.match_result_json().contains("sessionStats"));
+    }
+
+    #[test]
+    fn saved_game_board_match_accepts_legacy_display_name() {
+        assert!(is_matching_board_name("Tiny", "Tiny", "BoardTiny"));
+        assert!(is_matching_board_name("BoardTiny", "Tiny", "BoardTiny"));
+        assert!(!is_matching_board_name("BoardMain", "Tiny", "BoardTiny"));
+    }
+
+    #[test]
+    fn search_from_state_json_matches_handle_search() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        let state_json = handle.export_state_json();
+
+        let expected = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(2))
+            .expect("handle analysis should be json");
+        let actual =
+            serde_json::from_str::<serde_json::Value>(&search_from_state_json(&state_json, 2))
+                .expect("stateless analysis should be json");
+
+        assert_eq!(actual["isValid"], true);
+        assert_eq!(actual["suggestedTurn"], expected["suggestedTurn"]);
+        assert_eq!(actual["heuristicScore"], expected["heuristicScore"]);
+        assert_eq!(actual["numStatesVisited"], expected["numStatesVisited"]);
+    }
+
+    #[test]
+    fn search_from_state_json_reports_bad_snapshot() {
+        let response = serde_json::from_str::<serde_json::Value>(&search_from_state_json(
+            "{\"version\":99,\"boardName\":\"BoardAltDown\"}",
+            1,
+        ))
+        .expect("stateless analysis should be json");
+
+        assert_eq!(response["isValid"], false);
+        assert!(
+            response["validationMessage"]
+                .as_str()
+                .unwrap_or_default()
+                .contains("Invalid saved game JSON")
+        );
+    }
+
+    #[test]
+    fn apply_turn_plan_for_piece_rejects_wrong_seat() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+
+        assert_eq!(handle.doctor_activations_json(), "[]");
+        assert!(
+            handle
+                .apply_turn_plan_for_piece("player2", "")
+                .contains("it is P1's turn")
+        );
+        assert_eq!(handle.apply_turn_plan_for_piece("player1", ""), "");
+    }
+
+    #[test]
+    fn panic_inside_guarded_call_leaves_game_as_it_was() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let summary_before = handle.summary(0);
+        let export_before = handle.export_state_json();
+
+        let result = handle.guard_state(|handle| {
+            handle.normal_setup.turn_id += 5;
+            handle.history_limit = Some(1);
+            handle.state.turn_id += 5;
+            panic!("corrupted state");
+        });
+
+        assert_eq!(result, Err::<(), _>("corrupted state".to_string()));
+        assert_eq!(handle.summary(0), summary_before);
+        assert_eq!(handle.export_state_json(), export_before);
+        assert_eq!(handle.history_limit, None);
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+    }
+
+    #[test]
+    fn finished_game_rejects_turns_and_unknown_pieces_read_as_zero() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(handle.piece_move_budget("player1") > 0);
+        assert_eq!(handle.piece_move_budget("player2"), 0);
+        handle.state.result = core::mutable_game_state::GameResult::Won {
+            player: core::player::PlayerId(0),
+        };
+
+        assert_eq!(handle.apply_turn_plan("[]"), "the game is over");
+        assert_eq!(handle.piece_move_budget("player1"), 0);
+        assert_eq!(handle.piece_move_budget("doctor"), 0);
+        assert_eq!(handle.stage_move("player1", 1), "the game is over");
+        assert_eq!(handle.piece_strength("stranger3"), 0);
+        assert_eq!(handle.piece_move_cards("doctor"), 0.0);
+    }
+
+    #[test]
+    fn piece_labels_reach_summaries_piece_info_and_saves() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        assert_eq!(
+            handle.set_piece_labels_json(
+                r##"[{"pieceId":"player1","name":"Alice","color":"#f00"},{"pieceId":"player2","name":"Bob"}]"##
+            ),
+            ""
+        );
+        assert_eq!(handle.state.ply(), 1);
+        assert!(handle.summary(0).contains("Bob (P3)"));
+        let piece_info = serde_json::from_str::<serde_json::Value>(&handle.piece_info_json())
+            .expect("piece info should be json");
+        assert_eq!(piece_info[0]["pieceId"], "player1");
+        assert_eq!(piece_info[0]["displayText"], "Alice (P1)");
+        assert_eq!(piece_info[0]["color"], "#f00");
+        assert_eq!(piece_info[1]["displayText"], "Bob (P3)");
+        let stranger1 = piece_info
+            .as_array()
+            .and_then(|infos| infos.iter().find(|info| info["pieceId"] == "stranger1"))
+            .expect("stranger1 should be seated");
+        assert_eq!(stranger1["name"], "");
+        assert_eq!(stranger1["isStranger"], true);
+
+        let saved = handle.export_state_json();
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&saved), "");
+        assert_eq!(restored.piece_info_json(), handle.piece_info_json());
+        assert!(
+            restored
+                .current_normal_setup_json()
+                .contains(r#""name":"Bob""#)
+        );
+
+        assert!(
+            handle
+                .set_piece_labels_json(
+                    r#"[{"pieceId":"player1","name":"A"},{"pieceId":"player1"}]"#
+                )
+                .contains("more than once")
+        );
+        assert!(
+            handle
+                .set_piece_labels_json(r#"[{"pieceId":"stranger3","name":"C"}]"#)
+                .contains("isn't in this game")
+        );
+        assert!(
+            handle
+                .set_piece_labels_json(r#"[{"pieceId":"player2","color":"blue"}]"#)
+                .contains("#rgb")
+        );
+        assert_eq!(handle.set_piece_labels_json("[]"), "");
+        assert!(!handle.export_state_json().contains("pieceLabels"));
+    }
+
+    // moves the piece one room over for whoever's turn it is, and checks the move reached its seat
+    fn apply_one_room_move(handle: &mut GameStateHandle, piece_id: PieceId) {
+        let seats = handle.state.common.seat_map();
+        let player_id = seats.player_id(piece_id).expect("piece should be seated");
+        let from_room_id = handle.state.player_room_ids()[player_id.0];
+        let to_room_id = handle.state.common.board.rooms[&from_room_id].adjacent[0];
+        let plan = format!(
+            r#"[{{"pieceId":"{}","roomId":{}}}]"#,
+            piece_id.as_str(),
+            to_room_id.0
+        );
+        assert_eq!(handle.apply_turn_plan(&plan), "", "{piece_id} plan {plan}");
+        let last_turn = handle
+            .state
+            .normal_turns()
+            .pop()
+            .expect("a turn was applied");
+        assert_eq!(
+            last_turn.moves,
+            vec![core::player::PieceMove::new(player_id, to_room_id)]
+        );
+    }
+
+    #[test]
+    fn plans_name_pieces_by_the_games_own_seats() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.set_stranger_counts(0, 0), "");
+        apply_one_room_move(&mut handle, PieceId::Player1);
+        apply_one_room_move(&mut handle, PieceId::Player2);
+        assert!(
+            handle
+                .apply_turn_plan(r#"[{"pieceId":"stranger1","roomId":1}]"#)
+                .contains("not in this game")
+        );
+
+        assert_eq!(handle.set_stranger_counts(2, 1), "");
+        let seats = handle.state.common.seat_map();
+        assert_eq!(
+            seats.player_id(PieceId::Player2),
+            Some(core::player::PlayerId(2))
+        );
+        apply_one_room_move(&mut handle, PieceId::Player1);
+        apply_one_room_move(&mut handle, PieceId::Player2);
+        for piece_id in [PieceId::Stranger1, PieceId::Stranger2, PieceId::Stranger3] {
+            apply_one_room_move(&mut handle, piece_id);
+        }
+        assert!(
+            handle
+                .apply_turn_plan(r#"[{"pieceId":"stranger4","roomId":1}]"#)
+                .contains("not in this game")
+        );
+        let simulation =
+            serde_json::from_str::<serde_json::Value>(&handle.simulate_attack_json("stranger3"))
+                .expect("simulation should be json");
+        assert_eq!(simulation["attackerPieceId"], "stranger3");
+    }
+
+    #[test]
+    fn result_json_reports_aborted_games_and_saves_keep_them_aborted() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let result = serde_json::from_str::<serde_json::Value>(&handle.result_json())
+            .expect("result should be json");
+        assert_eq!(result["result"], "inProgress");
+        assert_eq!(result["winnerPieceId"], "");
+        assert!(!handle.export_state_json().contains("aborted"));
+
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        assert_eq!(handle.abort_game(), "");
+        assert_eq!(handle.abort_game(), "Game was aborted.");
+        assert_eq!(handle.apply_turn_plan("[]"), "the game is over");
+        let result = serde_json::from_str::<serde_json::Value>(&handle.result_json())
+            .expect("result should be json");
+        assert_eq!(result["result"], "aborted");
+        assert_eq!(result["drawReason"], "");
+
+        let saved = handle.export_state_json();
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&saved), "");
+        assert!(restored.state.is_aborted());
+        assert_eq!(restored.state.ply(), 1);
+
+        assert!(handle.undo_last_turn());
+        assert!(!handle.state.is_aborted());
+        assert_eq!(handle.state.ply(), 1);
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        assert_eq!(handle.state.ply(), 2);
+
+        finish_game_with_winner(&mut handle, PieceId::Player2);
+        let result = serde_json::from_str::<serde_json::Value>(&handle.result_json())
+            .expect("result should be json");
+        assert_eq!(result["result"], "won");
+        assert_eq!(result["winnerPieceId"], "player2");
+    }
+
+    #[test]
+    fn reachable_rooms_leave_out_rooms_blocked_by_other_players() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let destination = handle.reachable_rooms("player1", 1)[1];
+        let turn_plan = format!("[{{\"pieceId\":\"player1\",\"roomId\":{destination}}}]");
+        assert_eq!(handle.apply_turn_plan(&turn_plan), "");
+        assert!(handle.reachable_rooms("player2", 1).contains(&destination));
+
+        let rule_config = core::rule_config::RuleConfig {
+            occupied_rooms_blocked: true,
+            ..handle.state.common.rule_config.clone()
+        };
+        handle.state.common = handle.state.common.clone().with_rule_config(rule_config);
+        let reachable = handle.reachable_rooms("player2", 1);
+        assert!(!reachable.contains(&destination));
+        assert!(!reachable.is_empty());
+
+        let blocked_plan = format!("[{{\"pieceId\":\"player2\",\"roomId\":{destination}}}]");
+        let validation = serde_json::from_str::<serde_json::Value>(
+            &handle.validate_turn_plan_json(&blocked_plan),
+        )
+        .expect("validation should be json");
+        assert_eq!(validation["isValid"], false);
+        assert_eq!(validation["error"]["code"], "roomOccupied");
+    }
+
+    #[test]
+    fn player_stats_json_tracks_rooms_and_cards_after_turns() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let destination = handle.reachable_rooms("player1", 1)[0];
+        let turn_plan = format!("[{{\"pieceId\":\"player1\",\"roomId\":{destination}}}]");
+        assert_eq!(handle.apply_turn_plan(&turn_plan), "");
+
+        let stats = serde_json::from_str::<serde_json::Value>(&handle.player_stats_json())
+            .expect("player stats should be json");
+        let player1 = stats
+            .as_array()
+            .and_then(|entries| entries.iter().find(|entry| entry["pieceId"] == "player1"))
+            .expect("player1 stats should be present");
+
+        assert_eq!(stats.as_array().map(Vec::len), Some(4));
+        assert_eq!(player1["roomId"], destination);
+        assert_eq!(
+            player1["moveCards"].as_f64(),
+            Some(handle.piece_move_cards("player1"))
+        );
+        assert_eq!(
+            player1["equivalentClovers"].as_f64(),
+            Some(handle.piece_equivalent_clovers("player1"))
+        );
+    }
+
+    #[test]
+    fn current_appraisal_is_from_requested_side() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let player1_score = handle.state.heuristic_score(core::player::PlayerId(0));
+
+        assert_eq!(
+            handle.current_appraisal("player1"),
+            core::rule_helper::heuristic_score_text(player1_score)
+        );
+        assert_eq!(
+            handle.current_appraisal("stranger2"),
+            handle.current_appraisal("player1")
+        );
+        assert_eq!(handle.current_appraisal("doctor"), "");
+
+        finish_game_with_winner(&mut handle, PieceId::Player2);
+        assert_eq!(handle.current_appraisal("player2"), "WIN");
+        assert_eq!(handle.current_appraisal("player1"), "LOSE");
+    }
+
+    #[test]
+    fn simulate_attack_json_lists_defender_cards_in_order() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        handle.state.doctor_room_id = handle.state.player_room_ids()[0];
+        let before = handle.state.clone();
+
+        let simulation =
+            serde_json::from_str::<serde_json::Value>(&handle.simulate_attack_json("player1"))
+                .expect("simulation should be json");
+        assert_eq!(handle.state, before);
+        assert_eq!(simulation["isValid"], true);
+        assert_eq!(simulation["attackerPieceId"], "player1");
+        assert_eq!(simulation["outcome"], "failure");
+        let defender_cards = simulation["defenderCards"]
+            .as_array()
+            .expect("defender cards");
+        assert!(!defender_cards.is_empty());
+        assert!(
+            defender_cards
+                .iter()
+                .all(|card| card["defenderPieceId"] == "player2")
+        );
+        assert_eq!(defender_cards[0]["cardType"], "failure");
+
+        let unknown =
+            serde_json::from_str::<serde_json::Value>(&handle.simulate_attack_json("butler"))
+                .expect("simulation should be json");
+        assert_eq!(unknown["isValid"], false);
+    }
+
+    #[test]
+    fn set_win_conditions_restarts_with_new_rules() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(
+            handle
+                .set_win_conditions(0, 0)
+                .contains("successfulAttacksToWin")
+        );
+        assert_eq!(handle.set_win_conditions(2, 1), "");
+
+        let rule_config = serde_json::from_str::<serde_json::Value>(&handle.rule_config_json())
+            .expect("rule config should be json");
+        assert_eq!(rule_config["successfulAttacksToWin"], 2);
+        assert_eq!(rule_config["doctorEscapeTurns"], 1);
+
+        let suggestion = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(1))
+            .expect("analysis should be json");
+        let turn_plan = serde_json::to_string(&suggestion["suggestedTurn"]).expect("turn plan");
+        assert_eq!(handle.apply_turn_plan(&turn_plan), "");
+        assert!(handle.is_draw());
+        assert!(!handle.has_winner());
+
+        let analysis = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(1))
+            .expect("analysis should be json");
+        assert_eq!(analysis["isValid"], false);
+        let restored = game_state_handle_for_board("BoardAltDown")
+            .and_then(|mut restored| {
+                let message = restored.import_state_json(&handle.export_state_json());
+                if message.is_empty() {
+                    Ok(restored)
+                } else {
+                    Err(message)
+                }
+            })
+            .expect("export should round trip");
+        assert!(restored.is_draw());
+    }
+
+    #[test]
+    fn suggest_stranger_placement_lists_rooms_for_a_sides_stranger() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let response = serde_json::from_str::<serde_json::Value>(
+            &handle.suggest_stranger_placement("player2"),
+        )
+        .expect("placements should be json");
+        assert_eq!(response["isValid"], true, "{response}");
+        let placements = response["placements"].as_array().expect("placements array");
+        assert!(!placements.is_empty());
+        assert!(placements.iter().all(
+            |placement| placement["pieceId"] == "stranger1" && placement["reasons"].is_array()
+        ));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&handle.suggest_stranger_placement("B"))
+                .expect("placements should be json")["placements"],
+            response["placements"]
+        );
+
+        let unknown =
+            serde_json::from_str::<serde_json::Value>(&handle.suggest_stranger_placement("c"))
+                .expect("error should be json");
+        assert_eq!(unknown["isValid"], false);
+    }
+
+    #[test]
+    fn set_deck_restarts_with_the_deck_hand() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(handle.set_deck("pinochle").contains("Unknown deck"));
+        assert!(handle.set_deck_json("[]", "Empty").contains("Deck 'Empty'"));
+
+        assert_eq!(handle.set_deck("DeckStandard"), "");
+        let starting_cards = core::deck_spec::DeckSpec::standard().starting_cards();
+        assert_eq!(handle.state.player_move_cards[0], starting_cards.move_cards);
+        assert_eq!(handle.state.player_weapons[2], starting_cards.weapons);
+        assert_eq!(handle.state.player_failures[2], starting_cards.failures);
+        assert_eq!(
+            handle.state.common.rule_config.starting_cards(),
+            starting_cards
+        );
+    }
+
+    #[test]
+    fn room_matrices_match_board_tables() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let board = &handle.state.common.board;
+        let distance = serde_json::from_str::<Vec<Vec<i32>>>(&handle.distance_matrix_json())
+            .expect("distance matrix should be json");
+        let sight = serde_json::from_str::<Vec<Vec<bool>>>(&handle.sight_matrix_json())
+            .expect("sight matrix should be json");
+
+        assert_eq!(distance.len(), board.distance.dim());
+        for from in &board.room_ids {
+            for to in &board.room_ids {
+                assert_eq!(distance[from.0][to.0], board.distance[(*from, *to)]);
+                assert_eq!(sight[from.0][to.0], board.sight[(*from, *to)]);
+            }
+        }
+    }
+
+    #[test]
+    fn attack_history_json_lists_attacks_and_saves_must_match_their_turns() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.attack_history_json(), "[]");
+        while handle.state.attack_hist.is_empty() && !handle.state.is_game_over() {
+            let turn = handle.state.possible_turns()[0].clone();
+            handle.apply_checked_turn(turn);
+        }
+        let history = serde_json::from_str::<serde_json::Value>(&handle.attack_history_json())
+            .expect("attack history should be json");
+        let first = &history[0];
+        assert_eq!(first["turnId"], handle.state.attack_hist[0].turn_id);
+        assert!(
+            first["pieceId"]
+                .as_str()
+                .is_some_and(|piece_id| !piece_id.is_empty())
+        );
+        assert!(first["strength"].as_f64() >= Some(1.0));
+
+        let mut saved = serde_json::from_str::<serde_json::Value>(&handle.export_state_json())
+            .expect("save should be json");
+        assert_eq!(saved["attackHistory"], history);
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&saved.to_string()), "");
+        assert_eq!(restored.attack_history_json(), handle.attack_history_json());
 
-            suggested_turn.push(SuggestedTurnEntry {
-                piece_id: piece_id.as_str().to_string(),
-                room_id: player_move.dest_room_id.0,
-            });
+        saved["attackHistory"][0]["succeeded"] =
+            serde_json::Value::Bool(!handle.state.attack_hist[0].succeeded);
+        assert_eq!(
+            restored.import_state_json(&saved.to_string()),
+            "Saved attack history does not match its turns."
+        );
+    }
+
+    #[test]
+    fn import_with_options_maps_matching_boards_and_truncates_at_a_bad_turn() {
+        let mut handle = new_game_state_handle(sample_common());
+        for _ in 0..2 {
+            let turn = handle.state.possible_turns()[0].clone();
+            handle.apply_checked_turn(turn);
         }
+        let saved = handle.export_state_json();
+        let import_report = |handle: &mut GameStateHandle, saved: &str, options_json: &str| {
+            serde_json::from_str::<serde_json::Value>(
+                &handle.import_state_json_with_options(saved, options_json),
+            )
+            .expect("import report should be json")
+        };
 
-        to_best_turn_analysis_json(&BestTurnAnalysisResponse {
-            is_valid: true,
-            validation_message: String::new(),
-            suggested_turn_text: appraised_turn.turn.to_string(),
-            suggested_turn,
-            heuristic_score: appraised_turn.appraisal,
-            num_states_visited,
-            elapsed_ms,
-        })
+        let tiny_json =
+            core::asset_registry::find_embedded(core::asset_registry::BOARD_ASSETS, "Tiny")
+                .expect("Tiny should be embedded")
+                .json;
+        let copy_board =
+            core::board::Board::from_json_str(tiny_json, "TinyCopy").expect("copy should load");
+        let mut copy = new_game_state_handle(
+            core::common_game_state::CommonGameState::from_num_normal_players(false, copy_board, 2),
+        );
+        assert!(copy.import_state_json(&saved).contains("does not match"));
+        let report = import_report(&mut copy, &saved, "");
+        assert_eq!(report["isValid"], false);
+        let report = import_report(&mut copy, &saved, r#"{"allowMatchingBoardStructure":true}"#);
+        assert_eq!(report["isValid"], true, "{report}");
+        assert_eq!(
+            report["boardMappedFrom"],
+            handle.state.common.board.json_name
+        );
+        assert_eq!(report["numRestoredTurns"], 2);
+        assert!(report.get("invalidTurn").is_none());
+        assert_eq!(copy.state.player_room_ids(), handle.state.player_room_ids());
+
+        let mut alt_down = new_default_game_state().expect("default game state should load");
+        let report = import_report(
+            &mut alt_down,
+            &saved,
+            r#"{"allowMatchingBoardStructure":true}"#,
+        );
+        assert!(
+            report["validationMessage"]
+                .as_str()
+                .is_some_and(|message| message.contains("different room structure"))
+        );
+
+        let mut edited = serde_json::from_str::<serde_json::Value>(&saved).expect("save json");
+        edited["normalTurns"][1] = edited["normalTurns"][0].clone();
+        edited["attackHistory"] = serde_json::Value::Array(Vec::new());
+        let edited = edited.to_string();
+        let mut restored = new_game_state_handle(sample_common());
+        assert!(
+            restored
+                .import_state_json(&edited)
+                .starts_with("Saved turn 2 is invalid")
+        );
+        let report = import_report(&mut restored, &edited, r#"{"truncateAtInvalidTurn":true}"#);
+        assert_eq!(report["isValid"], true, "{report}");
+        assert_eq!(report["invalidTurn"], 2);
+        assert_eq!(report["numSavedTurns"], 2);
+        assert_eq!(report["numRestoredTurns"], 1);
+        assert!(
+            !report["invalidReason"]
+                .as_str()
+                .unwrap_or_default()
+                .is_empty()
+        );
+        assert_eq!(restored.state.ply(), 1);
     }
 
-    #[wasm_bindgen(js_name = "defaultNormalSetupJson")]
-    pub fn default_normal_setup_json(&self) -> String {
-        let setup = normalize_normal_setup(&default_normal_setup(), &self.state.common);
-        serde_json::to_string(&setup).unwrap_or_else(|_| {
-            "{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"}".to_string()
-        })
+    #[test]
+    fn autosave_keeps_the_save_from_the_latest_change() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_eq!(handle.take_autosave_json(), "");
+
+        handle.set_autosave(true);
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        let saved = handle.take_autosave_json();
+        assert_eq!(saved, handle.export_state_json());
+        assert_eq!(handle.take_autosave_json(), "");
+
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&saved), "");
+        assert_eq!(restored.state.ply(), 3);
+
+        assert!(handle.undo_last_turn());
+        handle.set_autosave(false);
+        assert_eq!(handle.take_autosave_json(), "");
     }
 
-    #[wasm_bindgen(js_name = "currentNormalSetupJson")]
-    pub fn current_normal_setup_json(&self) -> String {
-        let setup = normalize_normal_setup(&self.normal_setup, &self.state.common);
-        serde_json::to_string(&setup).unwrap_or_else(|_| {
-            "{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"}".to_string()
-        })
+    #[test]
+    fn seat_lock_turns_away_the_other_seat_and_reports_handoffs() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(
+            handle
+                .set_seat_lock("stranger1")
+                .contains("player1 or player2")
+        );
+        assert_eq!(
+            handle.set_piece_labels_json(r#"[{"pieceId":"player2","name":"Bob"}]"#),
+            ""
+        );
+        assert_eq!(handle.set_seat_lock("player2"), "");
+        assert_eq!(
+            handle.apply_turn_plan("[]"),
+            "It is P1's turn, but this device is locked to Bob (P3)."
+        );
+        assert!(handle.stage_move("player1", 1).contains("locked to"));
+        assert!(handle.commit_staged_moves().contains("locked to"));
+        let lock = serde_json::from_str::<serde_json::Value>(&handle.seat_lock_json())
+            .expect("seat lock should be json");
+        assert_eq!(lock["lockedPieceId"], "player2");
+        assert_eq!(lock["currentPlayerPieceId"], "player1");
+        assert_eq!(lock["canSubmit"], false);
+        assert_eq!(handle.state.ply(), 0);
+
+        assert_eq!(handle.set_seat_lock("player1"), "");
+        assert_eq!(
+            seat_handoff_json(&handle.state, handle.seat_lock.piece_id),
+            None
+        );
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        let handoff = serde_json::from_str::<serde_json::Value>(
+            &seat_handoff_json(&handle.state, handle.seat_lock.piece_id)
+                .expect("the move passed to player2"),
+        )
+        .expect("handoff should be json");
+        assert_eq!(handoff["lockedPieceId"], "player1");
+        assert_eq!(handoff["nextPieceId"], "player2");
+        assert_eq!(handoff["nextDisplayText"], "Bob (P3)");
+        assert!(handle.apply_turn_plan("[]").contains("locked to P1"));
+
+        assert_eq!(handle.set_seat_lock(""), "");
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        assert_eq!(handle.state.ply(), 2);
     }
 
-    #[wasm_bindgen(js_name = "startNewGameWithSetup")]
-    pub fn start_new_game_with_setup(
-        &mut self,
-        move_cards: f64,
-        weapon_cards: f64,
-        failure_cards: f64,
-        player2_move_cards: f64,
-        player2_weapon_cards: f64,
-        player2_failure_cards: f64,
-        doctor_room_id: usize,
-        player1_room_id: usize,
-        stranger1_room_id: usize,
-        player2_room_id: usize,
-        stranger2_room_id: usize,
-        player1_strength: i32,
-        stranger1_strength: i32,
-        player2_strength: i32,
-        stranger2_strength: i32,
-        turn_id: i32,
-        current_player_piece_id: &str,
-    ) -> String {
-        let Some(current_player_piece_id) = PieceId::parse(current_player_piece_id) else {
-            return "currentPlayerPieceId must be P1 or P3.".to_string();
-        };
+    #[test]
+    fn turn_packets_reject_stale_repeated_and_foreign_packets() {
+        let mut sender = new_default_game_state().expect("default game state should load");
+        let mut receiver = new_default_game_state().expect("default game state should load");
+        let unset = serde_json::from_str::<serde_json::Value>(&sender.turn_packet_json("[]"))
+            .expect("packet response should be json");
+        assert_eq!(unset["isValid"], false);
+        assert_eq!(sender.set_remote_session(" game-7 "), "");
+        assert_eq!(receiver.set_remote_session("game-7"), "");
+
+        let response = serde_json::from_str::<serde_json::Value>(&sender.turn_packet_json("[]"))
+            .expect("packet response should be json");
+        assert_eq!(response["isValid"], true);
+        assert_eq!(response["packet"]["sessionId"], "game-7");
+        assert_eq!(response["packet"]["sequence"], 1);
+        // the other end may be another build on another target; the key must not depend on either
+        assert_eq!(response["packet"]["positionKey"], "96952a601c47ce47");
+        let packet = response["packet"].to_string();
+        assert_eq!(sender.apply_turn_packet_json(&packet), "");
+        assert_eq!(receiver.apply_turn_packet_json(&packet), "");
+        assert_eq!(receiver.state.normal_turns(), sender.state.normal_turns());
+        assert!(
+            receiver
+                .apply_turn_packet_json(&packet)
+                .contains("is stale")
+        );
 
-        let setup = snapped_normal_setup(&NormalSetup {
-            board_name: String::new(),
-            move_cards,
-            weapon_cards,
-            failure_cards,
-            player2_move_cards,
-            player2_weapon_cards,
-            player2_failure_cards,
-            doctor_room_id,
-            player1_room_id,
-            stranger1_room_id,
-            player2_room_id,
-            stranger2_room_id,
-            player1_strength,
-            stranger1_strength,
-            player2_strength,
-            stranger2_strength,
-            turn_id,
-            current_player_piece_id,
-        });
-        let common = self.state.common.clone();
-        let normalized_setup = normalize_normal_setup(&setup, &common);
-        if let Err(message) = validate_normal_setup(&normalized_setup, &common) {
-            return message;
+        let mut skipping = serde_json::from_str::<TurnPacket>(&packet).expect("packet parses");
+        skipping.sequence = 3;
+        let skipping = serde_json::to_string(&skipping).expect("packet serializes");
+        assert!(
+            receiver
+                .apply_turn_packet_json(&skipping)
+                .contains("skips ahead")
+        );
+
+        let next = serde_json::from_str::<serde_json::Value>(&sender.turn_packet_json("[]"))
+            .expect("packet response should be json")["packet"]
+            .to_string();
+        assert_eq!(receiver.set_remote_session("game-8"), "");
+        assert!(
+            receiver
+                .apply_turn_packet_json(&next)
+                .contains("not 'game-8'")
+        );
+        assert_eq!(receiver.set_remote_session("game-7"), "");
+        let mut out_of_sync = serde_json::from_str::<TurnPacket>(&next).expect("packet parses");
+        out_of_sync.position_key = "0".repeat(16);
+        let out_of_sync = serde_json::to_string(&out_of_sync).expect("packet serializes");
+        assert!(
+            receiver
+                .apply_turn_packet_json(&out_of_sync)
+                .contains("out of sync")
+        );
+        assert_eq!(receiver.apply_turn_packet_json(&next), "");
+        let session = serde_json::from_str::<serde_json::Value>(&receiver.remote_session_json())
+            .expect("session should be json");
+        assert_eq!(session["nextSequence"], 3);
+    }
+
+    #[test]
+    fn previewed_states_are_reused_by_apply_and_suggest() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let preview = serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plan("[]"))
+            .expect("preview should be json");
+        assert_eq!(preview["isValid"], true);
+        assert!(handle.preview_cache.borrow().children.is_empty());
+
+        handle.set_preview_cache(true);
+        let suggestion = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn("hard", 3))
+            .expect("suggestion should be json");
+        let suggested_plan = suggestion["suggestedTurn"].to_string();
+        handle.preview_turn_plan("[]");
+        handle.preview_turn_plan(&suggested_plan);
+        handle.preview_turn_plan("oops");
+        assert_eq!(handle.preview_cache.borrow().children.len(), 2);
+        let reused = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn("hard", 3))
+            .expect("suggestion should be json");
+        assert_eq!(reused["suggestedTurn"], suggestion["suggestedTurn"]);
+        assert_eq!(reused["heuristicScore"], suggestion["heuristicScore"]);
+
+        let previewed_prev_state = handle
+            .preview_cache
+            .borrow()
+            .children
+            .get(&core::simple_turn::SimpleTurn::new([]))
+            .and_then(|child_state| child_state.prev_state.clone())
+            .expect("a previewed state remembers its parent");
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        assert!(
+            handle
+                .state
+                .prev_state
+                .as_ref()
+                .is_some_and(|prev_state| std::sync::Arc::ptr_eq(
+                    prev_state,
+                    &previewed_prev_state
+                ))
+        );
+        assert!(handle.preview_cache.borrow().children.is_empty());
+        let mut replayed = new_default_game_state().expect("default game state should load");
+        assert_eq!(replayed.apply_turn_plan("[]"), "");
+        assert_eq!(handle.state.position_key(), replayed.state.position_key());
+
+        handle.preview_turn_plan("[]");
+        handle.set_preview_cache(false);
+        assert!(handle.preview_cache.borrow().children.is_empty());
+    }
+
+    #[test]
+    fn preview_turn_plans_answers_each_plan_in_order() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let previews = serde_json::from_str::<serde_json::Value>(
+            &handle.preview_turn_plans(r#"[[], "oops", [{"pieceId":"player1","roomId":999}]]"#),
+        )
+        .expect("previews should be json");
+        let previews = previews.as_array().expect("previews should be an array");
+        assert_eq!(previews.len(), 3);
+        assert_eq!(
+            previews[0],
+            serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plan("[]"))
+                .expect("preview should be json")
+        );
+        assert_eq!(previews[1]["isValid"], false);
+        assert_eq!(previews[2]["isValid"], false);
+        assert_eq!(handle.preview_turn_plans("[]"), "[]");
+
+        for bad_batch in [
+            "{}".to_string(),
+            format!("[{}]", vec!["[]"; MAX_PREVIEW_PLANS + 1].join(",")),
+        ] {
+            let previews =
+                serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plans(&bad_batch))
+                    .expect("previews should be json");
+            assert_eq!(previews[0]["isValid"], false);
+            assert_eq!(previews.as_array().map(Vec::len), Some(1));
         }
+    }
 
-        self.normal_setup = normalized_setup;
-        self.state = new_state_with_normal_setup(common, &self.normal_setup);
-        String::new()
+    #[test]
+    fn state_change_json_describes_the_state_after_the_change() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        handle.set_on_state_changed(None);
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+
+        let change =
+            serde_json::from_str::<serde_json::Value>(&state_change_json(&handle.state, "turn"))
+                .expect("state change should be json");
+        assert_eq!(change["kind"], "turn");
+        assert_eq!(change["ply"], 1);
+        assert_eq!(change["currentPlayerPieceId"], "player2");
+        assert_eq!(change["result"], "inProgress");
+
+        assert_eq!(handle.abort_game(), "");
+        let change =
+            serde_json::from_str::<serde_json::Value>(&state_change_json(&handle.state, "abort"))
+                .expect("state change should be json");
+        assert_eq!(change["result"], "aborted");
     }
 
-    #[wasm_bindgen(js_name = "exportStateJson")]
-    pub fn export_state_json(&self) -> String {
-        let snapshot = PersistedGameState {
-            version: PERSISTED_GAME_STATE_VERSION,
-            board_name: self.state.common.board.json_name.clone(),
-            normal_setup: normalize_normal_setup(&self.normal_setup, &self.state.common),
-            normal_turns: collect_normal_turns(&self.state),
-        };
+    #[test]
+    fn last_turn_broadcast_describes_applied_turn() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.last_turn_broadcast_json(), "");
+
+        let suggestion = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(1))
+            .expect("analysis should be json");
+        let turn_plan = serde_json::to_string(&suggestion["suggestedTurn"]).expect("turn plan");
+        assert_eq!(handle.apply_turn_plan(&turn_plan), "");
+
+        let broadcast =
+            serde_json::from_str::<serde_json::Value>(&handle.last_turn_broadcast_json())
+                .expect("broadcast should be json");
+        assert_eq!(broadcast["ply"], 1);
+        assert_eq!(broadcast["pieceId"], "player1");
+        assert_eq!(
+            broadcast["doctorRoomIdAfter"],
+            handle.state.doctor_room_id.0
+        );
+        assert!(
+            broadcast["text"]
+                .as_str()
+                .is_some_and(|text| text.contains(&format!(
+                    "->{}",
+                    handle
+                        .state
+                        .common
+                        .board
+                        .room_label(handle.state.doctor_room_id)
+                )))
+        );
 
-        serde_json::to_string(&snapshot).unwrap_or_else(|_| {
-            "{\"version\":1,\"boardName\":\"BoardAltDown\",\"normalSetup\":{\"boardName\":\"BoardAltDown\",\"moveCards\":1,\"weaponCards\":2,\"failureCards\":6,\"player2MoveCards\":1,\"player2WeaponCards\":2,\"player2FailureCards\":6,\"doctorRoomId\":0,\"player1RoomId\":0,\"stranger1RoomId\":0,\"player2RoomId\":0,\"stranger2RoomId\":0,\"player1Strength\":1,\"stranger1Strength\":1,\"player2Strength\":1,\"stranger2Strength\":1,\"turnId\":1,\"currentPlayerPieceId\":\"player1\"},\"normalTurns\":[]}".to_string()
-        })
+        handle.undo_last_turn();
+        assert_eq!(handle.last_turn_broadcast_json(), "");
     }
 
-    #[wasm_bindgen(js_name = "importStateJson")]
-    pub fn import_state_json(&mut self, state_json: &str) -> String {
-        let snapshot = match serde_json::from_str::<PersistedGameState>(state_json) {
-            Ok(snapshot) => snapshot,
-            Err(err) => return format!("Invalid saved game JSON: {err}"),
+    #[test]
+    fn start_options_randomize_unset_setup_rooms() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let start = |handle: &mut GameStateHandle, doctor_room_id: usize| {
+            let message = handle.start_new_game_with_setup(
+                1.0,
+                2.0,
+                6.0,
+                -1.0,
+                -1.0,
+                -1.0,
+                doctor_room_id,
+                0,
+                0,
+                0,
+                0,
+                1,
+                1,
+                1,
+                1,
+                1,
+                "player1",
+            );
+            assert_eq!(message, "");
+            (
+                handle.state.doctor_room_id,
+                handle.state.player_room_ids().to_vec(),
+            )
         };
+        let board_start = start(&mut handle, 0);
+        assert!(handle.start_options_json().contains("\"pool\":\"board\""));
 
-        if snapshot.version != PERSISTED_GAME_STATE_VERSION {
-            return format!("Unsupported saved game version {}.", snapshot.version);
-        }
+        assert_eq!(
+            handle.set_start_options_json(r#"{"pool":"any","seed":9}"#),
+            ""
+        );
+        let starts = (0..6).map(|_| start(&mut handle, 0)).collect::<Vec<_>>();
+        assert!(starts.iter().any(|rooms| *rooms != board_start));
+        assert!(starts.iter().all(|(_, player_room_ids)| {
+            player_room_ids
+                .iter()
+                .all(|room_id| *room_id == player_room_ids[0])
+        }));
+        assert_eq!(
+            handle.set_start_options_json(r#"{"pool":"any","seed":9}"#),
+            ""
+        );
+        assert!(starts.iter().all(|rooms| start(&mut handle, 0) == *rooms));
 
-        if !is_matching_board_name(
-            &snapshot.board_name,
-            &self.state.common.board.name,
-            &self.state.common.board.json_name,
-        ) {
-            return format!(
-                "Saved game board '{}' does not match current board '{}'.",
-                snapshot.board_name, self.state.common.board.json_name
-            );
+        // a room the caller names wins over the draw
+        let named_doctor_room_id = handle.state.common.board.room_ids[0];
+        assert_eq!(
+            start(&mut handle, named_doctor_room_id.0).0,
+            named_doctor_room_id
+        );
+
+        assert_eq!(handle.set_start_options_json(r#"{"doctorOffset":1}"#), "");
+        let (doctor_room_id, player_room_ids) = start(&mut handle, 0);
+        assert_eq!(
+            doctor_room_id,
+            handle
+                .state
+                .common
+                .board
+                .next_room_id_in_doctor_visit_order(player_room_ids[0], 1)
+        );
+        assert!(
+            handle
+                .set_start_options_json(r#"{"pool":"nowhere"}"#)
+                .contains("unknown start room pool")
+        );
+    }
+
+    #[test]
+    fn stranger_loop_warning_shows_in_preview_and_broadcast() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let preview = serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plan("oops"))
+            .expect("preview should be json");
+        assert_eq!(preview["strangerLoopWarning"], false);
+
+        let board = &handle.state.common.board;
+        let (enemy_room_id, counter_room_ids) = board
+            .stranger_loop_room_ids
+            .iter()
+            .min_by_key(|(room_id, _)| room_id.0)
+            .expect("default board should have stranger loop rooms");
+        let (enemy_room_id, counter_room_ids) = (*enemy_room_id, counter_room_ids.clone());
+        let uncountering_room_id = board
+            .room_ids
+            .iter()
+            .copied()
+            .find(|room_id| !counter_room_ids.contains(room_id))
+            .expect("some room should not counter the loop");
+        let seats = handle.state.common.seat_map();
+        for stranger_id in seats.allied_strangers(core::player::PlayerId(0)) {
+            handle.state.move_piece(stranger_id, uncountering_room_id);
         }
-        if !is_legacy_or_matching_board_name(
-            &snapshot.normal_setup.board_name,
-            &self.state.common.board.name,
-            &self.state.common.board.json_name,
-        ) {
-            return format!(
-                "Saved game setup board '{}' does not match current board '{}'.",
-                snapshot.normal_setup.board_name, self.state.common.board.json_name
-            );
+        for stranger_id in seats.opposing_strangers(core::player::PlayerId(0)) {
+            handle.state.move_piece(stranger_id, enemy_room_id);
         }
 
-        let normalized_setup = normalize_normal_setup(&snapshot.normal_setup, &self.state.common);
-        if let Err(message) = validate_normal_setup(&normalized_setup, &self.state.common) {
-            return format!("Saved game has invalid setup: {message}");
-        }
+        let preview = serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plan("[]"))
+            .expect("preview should be json");
+        assert_eq!(preview["strangerLoopWarning"], true);
+        assert!(
+            preview["strangerLoopRoomIds"]
+                .as_array()
+                .is_some_and(|room_ids| room_ids.contains(&enemy_room_id.0.into()))
+        );
 
-        let common = self.state.common.clone();
-        let mut restored = new_state_with_normal_setup(common, &normalized_setup);
+        assert_eq!(handle.apply_turn_plan("[]"), "");
+        let broadcast =
+            serde_json::from_str::<serde_json::Value>(&handle.last_turn_broadcast_json())
+                .expect("broadcast should be json");
+        assert_eq!(broadcast["strangerLoopWarning"], true);
+        assert!(
+            broadcast["text"]
+                .as_str()
+                .is_some_and(|text| text.contains("stranger loop warning"))
+        );
+    }
 
-        for (turn_idx, turn) in snapshot.normal_turns.into_iter().enumerate() {
-            if let Err(message) = restored.check_normal_turn(&turn) {
-                return format!("Saved turn {} is invalid: {message}", turn_idx + 1);
-            }
-            restored.apply_turn(turn);
-        }
+    #[test]
+    fn last_turn_json_lists_moves_action_and_doctor_path() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let before = serde_json::from_str::<serde_json::Value>(&handle.last_turn_json())
+            .expect("last turn should be json");
+        assert_eq!(before["isValid"], false);
 
-        self.normal_setup = normalized_setup;
-        self.state = restored;
-        String::new()
+        let doctor_room_id_before = handle.state.doctor_room_id.0;
+        let player_room_id_before = handle.state.player_room_ids()[0];
+        let turn = handle
+            .state
+            .possible_turns()
+            .into_iter()
+            .find(|turn| {
+                turn.moves
+                    .iter()
+                    .any(|mv| mv.dest_room_id != player_room_id_before)
+            })
+            .expect("player 1 should be able to move");
+        handle.apply_checked_turn(turn.clone());
+
+        let last_turn = serde_json::from_str::<serde_json::Value>(&handle.last_turn_json())
+            .expect("last turn should be json");
+        assert_eq!(last_turn["isValid"], true);
+        assert_eq!(last_turn["ply"], 1);
+        assert_eq!(last_turn["pieceId"], "player1");
+        assert_eq!(last_turn["turnText"], turn.to_string());
+        assert_eq!(last_turn["moves"][0]["pieceId"], "player1");
+        assert_eq!(last_turn["moves"][0]["fromRoomId"], player_room_id_before.0);
+        assert!(last_turn["totalDistance"].as_i64().unwrap_or_default() > 0);
+        assert_eq!(last_turn["action"], "none");
+        assert_eq!(last_turn["doctorRoomIdBefore"], doctor_room_id_before);
+        assert_eq!(
+            last_turn["doctorRoomIdAfter"],
+            handle.state.doctor_room_id.0
+        );
+        let doctor_room_ids = last_turn["doctorRoomIds"]
+            .as_array()
+            .expect("doctor path should be a list");
+        assert!(doctor_room_ids.len() >= 2);
+        assert_eq!(doctor_room_ids[0], doctor_room_id_before);
+        assert_eq!(
+            doctor_room_ids[doctor_room_ids.len() - 1],
+            handle.state.doctor_room_id.0
+        );
+        let actions = last_turn["actions"]
+            .as_array()
+            .expect("actions should be a list");
+        assert_eq!(actions.len(), doctor_room_ids.len() - 1);
+        assert_eq!(actions[0]["pieceId"], "player1");
+        assert_eq!(actions[0]["action"], "none");
+
+        handle.undo_last_turn();
+        assert!(handle.last_turn_json().contains("\"isValid\":false"));
     }
-}
 
-#[wasm_bindgen(js_name = "newDefaultGameState")]
-pub fn new_default_game_state() -> Result<GameStateHandle, JsValue> {
-    new_game_state_for_board("BoardAltDown")
-}
+    #[test]
+    fn features_json_is_versioned_and_follows_requested_side() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let current = serde_json::from_str::<serde_json::Value>(&handle.features_json(""))
+            .expect("features should be json");
+        let stranger2 =
+            serde_json::from_str::<serde_json::Value>(&handle.features_json("stranger2"))
+                .expect("features should be json");
+        let player2 = serde_json::from_str::<serde_json::Value>(&handle.features_json("player2"))
+            .expect("features should be json");
+
+        assert_eq!(current["version"], core::evaluation::GAME_FEATURES_VERSION);
+        assert_eq!(current, stranger2);
+        assert_eq!(player2["analysisPlayer"], "P3");
+        assert_eq!(
+            current["names"].as_array().map(Vec::len),
+            current["values"].as_array().map(Vec::len)
+        );
+    }
 
-#[wasm_bindgen(js_name = "newGameStateForBoard")]
-pub fn new_game_state_for_board(board_name: &str) -> Result<GameStateHandle, JsValue> {
-    let board = core::board::Board::from_embedded_json(board_name)
-        .map_err(|err| JsValue::from_str(&err.to_string()))?;
-    let common = core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
-    let normal_setup = normalize_normal_setup(&default_normal_setup(), &common);
-    let state = new_state_with_normal_setup(common, &normal_setup);
-    Ok(GameStateHandle {
-        state,
-        normal_setup,
-    })
-}
+    #[test]
+    fn evaluation_weights_change_appraisal_until_cleared() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let heuristic_text = handle.current_appraisal("player1");
 
-fn parse_turn_plan(turn_plan_json: &str) -> Result<core::simple_turn::SimpleTurn, String> {
-    let trimmed = turn_plan_json.trim();
-    let entries = if trimmed.is_empty() {
-        Vec::new()
-    } else {
-        serde_json::from_str::<Vec<TurnPlanEntry>>(trimmed)
-            .map_err(|err| format!("Invalid turn plan JSON: {err}"))?
-    };
+        assert!(
+            handle
+                .set_evaluation_weights_json("{not json")
+                .starts_with("failed to parse evaluation weights")
+        );
+        assert_eq!(
+            handle.set_evaluation_weights_json(
+                r#"{"strength":0,"strengthMoveCards":0,"strengthTurn":0,"doctorAdvantage":0,"weapons":0,"failures":0}"#
+            ),
+            ""
+        );
+        assert_eq!(handle.current_appraisal("player1"), "+0.0000");
+        let weights = serde_json::from_str::<serde_json::Value>(&handle.evaluation_weights_json())
+            .expect("weights should be json");
+        assert_eq!(weights["strength"], 0.0);
 
-    let mut seen = HashSet::new();
-    let moves = entries
-        .into_iter()
-        .map(|entry| {
-            if !seen.insert(entry.piece_id) {
-                return Err(format!(
-                    "{} appears more than once in the turn plan.",
-                    entry.piece_id
-                ));
-            }
+        assert_eq!(handle.set_evaluation_weights_json(""), "");
+        assert_eq!(handle.current_appraisal("player1"), heuristic_text);
+    }
 
-            let Some(player_id) = entry.piece_id.to_player_id() else {
-                return Err("Doctor cannot be moved.".to_string());
-            };
+    #[test]
+    fn new_game_from_preset_applies_rules_and_round_trips_them() {
+        let presets = serde_json::from_str::<serde_json::Value>(&list_presets_json())
+            .expect("presets should be json");
+        assert!(
+            presets
+                .as_array()
+                .is_some_and(|presets| presets.iter().any(|preset| preset["name"] == "beginner"))
+        );
+        let listed_names = presets
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|preset| preset["name"].as_str())
+            .collect::<Vec<_>>();
+        for preset_name in listed_names {
+            assert!(
+                game_state_handle_for_preset(preset_name).is_ok(),
+                "{preset_name}"
+            );
+        }
+        assert!(!list_presets_json().contains(r#""name":"teams""#));
 
-            Ok(core::player::PieceMove::new(
-                player_id,
-                core::room::RoomId(entry.room_id),
-            ))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+        // a save from a team game keeps its teams rather than quietly becoming a 1v1 game
+        let mut team_save = serde_json::from_str::<serde_json::Value>(
+            &new_default_game_state()
+                .expect("default game state should load")
+                .export_state_json(),
+        )
+        .expect("save should be json");
+        team_save["teams"] = serde_json::to_value(core::seat_map::Teams::ALTERNATING)
+            .expect("teams should serialize");
+        assert!(
+            new_default_game_state()
+                .expect("default game state should load")
+                .import_state_json(&team_save.to_string())
+                .contains("Saved game has invalid teams")
+        );
+
+        let beginner = new_game_from_preset("beginner").expect("beginner preset should load");
+        assert_eq!(beginner.piece_move_cards("player1"), 2.0);
+        assert_eq!(beginner.piece_weapon_cards("player2"), 3.0);
+
+        let nosy = new_game_from_preset("strangers-nosy").expect("nosy preset should load");
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&nosy.export_state_json()), "");
+        assert!(restored.state.common.rule_config.strangers_are_nosy);
+
+        let mut duel = new_game_from_preset("duel").expect("duel preset should load");
+        assert_eq!(duel.piece_ids_json(), r#"["doctor","player1","player2"]"#);
+        assert_eq!(duel.stranger_counts_json(), r#"{"sideA":0,"sideB":0}"#);
+        assert_eq!(restored.import_state_json(&duel.export_state_json()), "");
+        assert_eq!(restored.state.common.num_all_players, 2);
+
+        apply_one_room_move(&mut duel, PieceId::Player1);
+        assert_eq!(duel.current_player_piece_id(), "player2");
+        apply_one_room_move(&mut duel, PieceId::Player2);
+        assert_eq!(duel.state.ply(), 2);
+    }
+
+    #[test]
+    fn ply_annotations_round_trip_through_saved_game_and_undo() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_eq!(
+            handle.set_ply_annotation(0, r#"{"appraisal":1.25,"markers":["start"]}"#),
+            ""
+        );
+        assert_eq!(
+            handle.set_ply_annotation(1, r#"{"comment":"passed instead of moving"}"#),
+            ""
+        );
+        assert!(
+            handle
+                .set_ply_annotation(5, "{}")
+                .contains("not been played")
+        );
+
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&handle.export_state_json()), "");
+        assert_eq!(restored.annotations_json(), handle.annotations_json());
+        assert_eq!(
+            restored.ply_annotation_json(1),
+            r#"{"ply":1,"comment":"passed instead of moving"}"#
+        );
+
+        assert!(restored.undo_last_turn());
+        assert_eq!(
+            restored.annotations_json(),
+            r#"[{"ply":0,"appraisal":1.25,"markers":["start"]}]"#
+        );
+    }
 
-    Ok(core::simple_turn::SimpleTurn::new(moves))
-}
+    #[test]
+    fn history_limit_compacts_without_losing_export_or_undo() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        handle.set_history_limit(2);
+        for _ in 0..5 {
+            assert_eq!(handle.apply_turn_plan(""), "");
+        }
+        assert!(handle.state.history_len() <= 4);
+        assert_eq!(handle.state.ply(), 5);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.import_state_json(&handle.export_state_json()), "");
+        assert_eq!(restored.state.normal_turns(), handle.state.normal_turns());
 
-    fn sample_common() -> core::common_game_state::CommonGameState {
-        let board = core::board::Board::from_embedded_json("Tiny").expect("tiny board should load");
-        core::common_game_state::CommonGameState::from_num_normal_players(false, board, 2)
+        for ply in (0..5).rev() {
+            assert!(handle.undo_last_turn());
+            assert_eq!(handle.state.ply(), ply);
+        }
+        assert!(!handle.undo_last_turn());
     }
 
     #[test]
-    fn normalize_normal_setup_fills_missing_advanced_values_from_board_defaults() {
-        let common = sample_common();
-        let normalized = normalize_normal_setup(&default_normal_setup(), &common);
+    fn compacted_imports_keep_one_state_and_still_undo() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        for _ in 0..5 {
+            assert_eq!(handle.apply_turn_plan(""), "");
+        }
+        let saved = handle.export_state_json();
+
+        let mut restored = new_default_game_state().expect("default game state should load");
+        let report = serde_json::from_str::<serde_json::Value>(
+            &restored.import_state_json_with_options(&saved, r#"{"compactHistory":true}"#),
+        )
+        .expect("report should be json");
+        assert_eq!(report["isValid"], true);
+        assert_eq!(restored.state.history_len(), 1);
+        assert_eq!(restored.state.ply(), 5);
+        assert_eq!(restored.state, handle.state);
+        assert_eq!(restored.export_state_json(), saved);
+
+        assert!(restored.undo_last_turn());
+        assert_eq!(restored.state.ply(), 4);
+        assert_eq!(restored.apply_turn_plan(""), "");
+        restored.compact_history();
+        assert_eq!(restored.state.history_len(), 1);
+        assert_eq!(restored.state.normal_turns(), handle.state.normal_turns());
+    }
 
+    #[test]
+    fn undone_lines_survive_as_variations_through_export() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let room_id = handle.state.player_room_ids()[0].0;
+        let neighbor_room_id =
+            handle.state.common.board.rooms[&core::room::RoomId(room_id)].adjacent[0].0;
+        let move_plan = format!(r#"[{{"pieceId":"player1","roomId":{neighbor_room_id}}}]"#);
+
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert!(handle.undo_last_turn());
+        assert!(handle.undo_last_turn());
+        assert_eq!(handle.apply_turn_plan(&move_plan), "");
+        let moved_turns = handle.state.normal_turns();
+
+        let tree = serde_json::from_str::<serde_json::Value>(&handle.variation_tree_json())
+            .expect("variation tree should be json");
+        assert_eq!(tree["rootChildIds"], serde_json::json!([1, 3]));
+        assert_eq!(tree["currentNodeId"], 3);
+        assert_eq!(tree["nodes"][1]["parentId"], 1);
+        assert_eq!(tree["nodes"][2]["isOnCurrentLine"], true);
+
+        let mut restored = new_default_game_state().expect("default game state should load");
         assert_eq!(
-            normalized.doctor_room_id,
-            common.board.doctor_start_room_id.0
+            restored.import_state_json(&handle.export_state_json_with_variations()),
+            ""
         );
-        assert_eq!(
-            normalized.player1_room_id,
-            common.board.player_start_room_id.0
+        assert_eq!(restored.variation_tree_json(), handle.variation_tree_json());
+        assert_eq!(restored.state.normal_turns(), moved_turns);
+
+        assert_eq!(restored.go_to_variation(2), "");
+        assert_eq!(restored.state.ply(), 2);
+        assert!(restored.undo_last_turn());
+        assert!(restored.redo_turn());
+        assert_eq!(restored.state.ply(), 2);
+        assert_eq!(restored.go_to_variation(3), "");
+        assert_eq!(restored.state.normal_turns(), moved_turns);
+        assert!(restored.go_to_variation(9).contains("Unknown"));
+
+        assert!(!restored.create_variation(0, "not json").is_empty());
+        assert_eq!(restored.state.normal_turns(), moved_turns);
+        assert_eq!(restored.create_variation(3, ""), "");
+        assert_eq!(restored.state.ply(), 2);
+        assert_eq!(restored.variations.num_nodes(), 4);
+        assert_eq!(restored.delete_variation(3), "");
+        assert_eq!(restored.state.ply(), 0);
+        assert_eq!(restored.variations.num_nodes(), 2);
+        assert!(restored.go_to_variation(3).contains("Unknown"));
+
+        let mut plain = new_default_game_state().expect("default game state should load");
+        assert_eq!(plain.import_state_json(&handle.export_state_json()), "");
+        assert!(!plain.redo_turn());
+        handle.reset_game();
+        assert!(!handle.redo_turn());
+    }
+
+    #[test]
+    fn staged_moves_commit_through_handle_like_a_turn_plan() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let room_id = handle.state.player_room_ids()[0].0;
+        let neighbor_room_id =
+            handle.state.common.board.rooms[&core::room::RoomId(room_id)].adjacent[0].0;
+
+        assert!(
+            handle
+                .stage_move("doctor", neighbor_room_id)
+                .contains("Unknown")
         );
+        assert!(!handle.stage_move("player2", neighbor_room_id).is_empty());
+        assert_eq!(handle.stage_move("player1", neighbor_room_id), "");
         assert_eq!(
-            normalized.stranger1_room_id,
-            common.board.player_start_room_id.0
+            handle.staged_plan_json(),
+            format!(r#"[{{"pieceId":"player1","roomId":{neighbor_room_id}}}]"#)
         );
+
+        handle.rollback_staged_moves();
+        assert_eq!(handle.staged_plan_json(), "[]");
+
+        assert_eq!(handle.stage_move("player1", neighbor_room_id), "");
+        assert_eq!(handle.commit_staged_moves(), "");
+        assert_eq!(handle.state.player_room_ids()[0].0, neighbor_room_id);
+        assert_eq!(handle.staged_plan_json(), "[]");
+        assert!(handle.last_turn_broadcast.is_some());
+    }
+
+    #[test]
+    fn validate_turn_plan_json_reports_code_and_params() {
+        let handle = new_default_game_state().expect("default game state should load");
+
+        let valid = serde_json::from_str::<serde_json::Value>(&handle.validate_turn_plan_json(""))
+            .expect("validation should be json");
+        assert_eq!(valid["isValid"], true);
+        assert!(valid["error"].is_null());
+
+        let wrong_piece = serde_json::from_str::<serde_json::Value>(
+            &handle.validate_turn_plan_json(r#"[{"pieceId":"player2","roomId":1}]"#),
+        )
+        .expect("validation should be json");
+        assert_eq!(wrong_piece["isValid"], false);
+        assert_eq!(wrong_piece["error"]["code"], "movedNonStranger");
+        assert_eq!(wrong_piece["error"]["params"]["player"], "P1");
         assert_eq!(
-            normalized.player2_room_id,
-            common.board.player_start_room_id.0
+            wrong_piece["validationMessage"],
+            handle.validate_turn_plan(r#"[{"pieceId":"player2","roomId":1}]"#)
         );
+
+        let malformed =
+            serde_json::from_str::<serde_json::Value>(&handle.validate_turn_plan_json("not json"))
+                .expect("validation should be json");
+        assert_eq!(malformed["error"]["code"], "malformedPlan");
+    }
+
+    #[test]
+    fn generated_boards_load_and_round_trip_by_name() {
+        assert!(list_board_names_json().contains(r#""Grid4x4Line""#));
+
+        let mut handle = new_game_state_for_board("Ring10").expect("ring board should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+
+        let mut restored = new_default_game_state().expect("default game state should load");
+        assert_eq!(restored.load_board("BoardRing10"), "");
+        assert_eq!(restored.import_state_json(&handle.export_state_json()), "");
+        assert_eq!(restored.state.common.board.name, "Ring10");
+        assert_eq!(restored.state.ply(), 1);
+    }
+
+    #[test]
+    fn room_names_resolve_and_label_previews() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let doctor_room_id = handle.state.doctor_room_id;
+        let doctor_room_name = handle.state.common.board.rooms[&doctor_room_id]
+            .name
+            .clone();
+
         assert_eq!(
-            normalized.stranger2_room_id,
-            common.board.player_start_room_id.0
+            handle.room_id_by_name(&doctor_room_name.to_uppercase()),
+            Some(doctor_room_id.0)
         );
+        assert_eq!(handle.room_id_by_name("no such room"), None);
         assert_eq!(
-            normalized.player1_strength,
-            core::rule_helper::PLAYER_STARTING_STRENGTH
+            handle.room_label(doctor_room_id.0),
+            format!("{doctor_room_name} (R{})", doctor_room_id.0)
         );
+
+        let preview = serde_json::from_str::<serde_json::Value>(&handle.preview_turn_plan(""))
+            .expect("preview should be json");
         assert_eq!(
-            normalized.stranger1_strength,
-            core::rule_helper::PLAYER_STARTING_STRENGTH
+            preview["doctorRoomLabel"],
+            handle.room_label(preview["doctorRoomId"].as_u64().expect("room id") as usize)
         );
-        assert_eq!(
-            normalized.player2_strength,
-            core::rule_helper::PLAYER_STARTING_STRENGTH
+    }
+
+    #[test]
+    fn turn_plan_entries_accept_room_names() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let ambiguous = serde_json::from_str::<serde_json::Value>(
+            &handle.validate_turn_plan_json(r#"[{"pieceId":"player1","roomName":"d"}]"#),
+        )
+        .expect("validation should be json");
+        assert_eq!(ambiguous["error"]["code"], "ambiguousRoomName");
+        assert!(
+            handle
+                .apply_turn_plan(r#"[{"pieceId":"player1"}]"#)
+                .contains("roomName")
         );
+
+        let parlor_id = handle
+            .room_id_by_name("parlor")
+            .expect("parlor should exist");
         assert_eq!(
-            normalized.stranger2_strength,
-            core::rule_helper::PLAYER_STARTING_STRENGTH
+            handle.apply_turn_plan(r#"[{"pieceId":"player1","roomName":"Parlor"}]"#),
+            ""
         );
-        assert_eq!(normalized.turn_id, 1);
-        assert_eq!(normalized.current_player_piece_id, PieceId::Player1);
+        assert_eq!(handle.state.player_room_ids()[0].0, parlor_id);
     }
 
     #[test]
-    fn new_state_with_normal_setup_applies_rooms_and_current_player() {
-        let common = sample_common();
-        let setup = NormalSetup {
-            board_name: String::new(),
-            move_cards: 0.5,
-            weapon_cards: 1.5,
-            failure_cards: 2.5,
-            player2_move_cards: 3.5,
-            player2_weapon_cards: 4.5,
-            player2_failure_cards: 5.5,
-            doctor_room_id: 1,
-            player1_room_id: 2,
-            stranger1_room_id: 1,
-            player2_room_id: 1,
-            stranger2_room_id: 2,
-            player1_strength: 3,
-            stranger1_strength: 4,
-            player2_strength: 5,
-            stranger2_strength: 6,
-            turn_id: 7,
-            current_player_piece_id: PieceId::Player2,
+    fn appraisal_history_records_each_ply_and_follows_undo() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.appraisal_history_json(), "[]");
+
+        handle.set_appraisal_history(1);
+        for _ in 0..3 {
+            assert_eq!(handle.apply_turn_plan(""), "");
+        }
+        let history = serde_json::from_str::<serde_json::Value>(&handle.appraisal_history_json())
+            .expect("history should be json");
+        let plies = history
+            .as_array()
+            .expect("history should be an array")
+            .iter()
+            .map(|entry| entry["ply"].as_u64().expect("ply"))
+            .collect::<Vec<_>>();
+        assert_eq!(plies, vec![0, 1, 2, 3]);
+        assert!(history[3]["appraisalText"].is_string());
+
+        assert!(handle.undo_last_turn());
+        let history = serde_json::from_str::<serde_json::Value>(&handle.appraisal_history_json())
+            .expect("history should be json");
+        assert_eq!(history.as_array().map(Vec::len), Some(3));
+
+        handle.set_appraisal_history(0);
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert!(handle.state.appraisal.is_none());
+    }
+
+    #[test]
+    fn find_best_turns_ranks_the_best_turn_first() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let best = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(2))
+            .expect("analysis should be json");
+        let ranked = serde_json::from_str::<serde_json::Value>(&handle.find_best_turns(2, 3))
+            .expect("ranked analysis should be json");
+
+        assert_eq!(ranked["isValid"], true);
+        let turns = ranked["turns"]
+            .as_array()
+            .expect("turns should be an array");
+        assert_eq!(turns.len(), 3);
+        assert_eq!(turns[0]["suggestedTurnText"], best["suggestedTurnText"]);
+        assert_eq!(turns[0]["suggestedTurn"], best["suggestedTurn"]);
+        assert!(turns[1]["heuristicScore"].as_f64() <= turns[0]["heuristicScore"].as_f64());
+
+        let every_turn =
+            serde_json::from_str::<serde_json::Value>(&handle.find_best_turns(1, usize::MAX / 2))
+                .expect("ranked analysis should be json");
+        let num_ranked = every_turn["turns"].as_array().map_or(0, Vec::len);
+        assert!(num_ranked > 3);
+        assert!(num_ranked <= handle.state.possible_turns().len());
+    }
+
+    #[test]
+    fn render_svg_follows_the_game() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let start_svg = handle.render_svg();
+        assert!(start_svg.starts_with("<svg "));
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_ne!(handle.render_svg(), start_svg);
+    }
+
+    #[test]
+    fn turn_plans_equivalent_ignores_move_order_and_moves_that_stay() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let room_of = |piece_id: &str| {
+            let player_id = handle
+                .seated_player_id(piece_id)
+                .expect("piece should be seated");
+            handle.state.player_room_ids()[player_id.0].0
         };
+        let stranger_room = room_of("stranger1");
+        let player_plan = serde_json::json!({ "pieceId": "player1", "roomId": 1 });
+        let stranger_plan = serde_json::json!({ "pieceId": "stranger1", "roomId": 2 });
+        let stay_plan =
+            serde_json::json!({ "pieceId": "stranger2", "roomId": room_of("stranger2") });
+
+        let forward = serde_json::json!([player_plan, stranger_plan]).to_string();
+        let backward = serde_json::json!([stranger_plan, player_plan]).to_string();
+        let padded = serde_json::json!([stranger_plan, stay_plan, player_plan]).to_string();
+        assert!(handle.turn_plans_equivalent(&forward, &backward));
+        assert!(handle.turn_plans_equivalent(&forward, &padded));
+        assert!(
+            !handle.turn_plans_equivalent(&forward, &serde_json::json!([player_plan]).to_string())
+        );
+        assert!(!handle.turn_plans_equivalent(&forward, "not json"));
+        assert!(handle.turn_plans_equivalent(
+            "[]",
+            &serde_json::json!([{ "pieceId": "stranger1", "roomId": stranger_room }]).to_string()
+        ));
+    }
 
-        let state = new_state_with_normal_setup(common, &setup);
+    #[test]
+    fn find_best_turns_among_keeps_to_the_given_turns() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let ranked = serde_json::from_str::<serde_json::Value>(&handle.find_best_turns(2, 2))
+            .expect("ranked analysis should be json");
+        let best_plan = ranked["turns"][0]["suggestedTurn"].clone();
+        let second_text = ranked["turns"][1]["suggestedTurnText"].clone();
+
+        let excluded = serde_json::from_str::<serde_json::Value>(&handle.find_best_turns_among(
+            2,
+            1,
+            &serde_json::json!({ "excluded": [best_plan] }).to_string(),
+        ))
+        .expect("analysis should be json");
+        assert_eq!(excluded["isValid"], true);
+        assert_eq!(excluded["turns"][0]["suggestedTurnText"], second_text);
+
+        let only = serde_json::from_str::<serde_json::Value>(&handle.find_best_turns_among(
+            2,
+            3,
+            &serde_json::json!({ "only": [ranked["turns"][1]["suggestedTurn"]] }).to_string(),
+        ))
+        .expect("analysis should be json");
+        assert_eq!(only["turns"].as_array().map(Vec::len), Some(1));
+        assert_eq!(only["turns"][0]["suggestedTurnText"], second_text);
+
+        let invalid = serde_json::from_str::<serde_json::Value>(&handle.find_best_turns_among(
+            2,
+            1,
+            "{\"only\":[[{\"pieceId\":\"nobody\"}]]}",
+        ))
+        .expect("analysis should be json");
+        assert_eq!(invalid["isValid"], false);
+    }
 
-        assert_eq!(state.doctor_room_id.0, 1);
-        assert_eq!(
-            state.player_room_ids[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0].0,
-            2
+    #[test]
+    fn suggest_turn_timed_returns_the_deepest_level_finished_in_time() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let invalid = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn_timed(0.0))
+            .expect("analysis should be json");
+        assert_eq!(invalid["isValid"], false);
+
+        let rushed = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn_timed(1e-6))
+            .expect("analysis should be json");
+        assert_eq!(rushed["isValid"], true);
+        assert_eq!(rushed["completedLevel"], 1);
+        assert!(
+            !rushed["suggestedTurnText"]
+                .as_str()
+                .unwrap_or_default()
+                .is_empty()
         );
+
         assert_eq!(
-            state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0].0,
-            1
+            handle.set_input_limits_json(r#"{"maxAnalysisLevel":3}"#),
+            ""
         );
+        handle.clear_search_cache();
+        let relaxed =
+            serde_json::from_str::<serde_json::Value>(&handle.suggest_turn_timed(60_000.0))
+                .expect("analysis should be json");
+        handle.clear_search_cache();
+        let level_3 = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(3))
+            .expect("analysis should be json");
+        assert_eq!(relaxed["completedLevel"], 3);
+        assert_eq!(relaxed["heuristicScore"], level_3["heuristicScore"]);
+    }
+
+    #[test]
+    fn search_cache_survives_turns_until_cleared() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let first = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(3))
+            .expect("analysis should be json");
+        let again = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(3))
+            .expect("analysis should be json");
+        assert_eq!(again["heuristicScore"], first["heuristicScore"]);
+        assert_eq!(again["numStatesVisited"], 1);
+
+        let turn_text = first["suggestedTurnText"].as_str().unwrap_or_default();
+        let turn = turn_text
+            .parse::<core::simple_turn::SimpleTurn>()
+            .expect("suggested turn should parse");
+        handle.apply_checked_turn(turn);
+        let stats = serde_json::from_str::<serde_json::Value>(&handle.search_cache_json())
+            .expect("cache stats should be json");
+        assert!(stats["numEntries"].as_u64().unwrap_or_default() > 0);
         assert_eq!(
-            state.player_room_ids[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0].0,
-            1
+            stats["maxEntries"],
+            core::transposition_table::TranspositionTable::DEFAULT_MAX_ENTRIES
         );
-        assert_eq!(
-            state.player_room_ids[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0].0,
-            2
+
+        handle.clear_search_cache();
+        let cleared = serde_json::from_str::<serde_json::Value>(&handle.search_cache_json())
+            .expect("cache stats should be json");
+        assert_eq!(cleared["numEntries"], 0);
+        handle.set_search_cache_size(0);
+        assert!(handle.search_cache_json().contains("\"maxEntries\":0"));
+        let uncached = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(3))
+            .expect("analysis should be json");
+        assert!(uncached["numStatesVisited"].as_u64().unwrap_or_default() > 1);
+    }
+
+    #[test]
+    fn continuous_analysis_reports_aspiration_window_stats() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(!handle.set_aspiration_window(-1.0, 4.0).is_empty());
+        assert_eq!(handle.set_aspiration_window(0.25, 4.0), "");
+
+        handle.start_continuous_analysis();
+        let level_one =
+            serde_json::from_str::<serde_json::Value>(&handle.step_continuous_analysis())
+                .expect("analysis should be json");
+        assert!(level_one.get("searchStats").is_none());
+        let level_two =
+            serde_json::from_str::<serde_json::Value>(&handle.step_continuous_analysis())
+                .expect("analysis should be json");
+        assert_eq!(level_two["searchStats"]["usedAspirationWindow"], true);
+        let full = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(2))
+            .expect("analysis should be json");
+        assert_eq!(level_two["heuristicScore"], full["heuristicScore"]);
+
+        assert_eq!(handle.set_aspiration_window(0.0, 0.0), "");
+        handle.start_continuous_analysis();
+        handle.step_continuous_analysis();
+        assert!(!handle.step_continuous_analysis().contains("searchStats"));
+    }
+
+    #[test]
+    fn continuous_analysis_deepens_per_step_until_cancelled() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert!(!handle.is_continuous_analysis_running());
+
+        handle.start_continuous_analysis();
+        for expected_level in 1..=2 {
+            let analysis =
+                serde_json::from_str::<serde_json::Value>(&handle.step_continuous_analysis())
+                    .expect("analysis should be json");
+            assert_eq!(analysis["isValid"], true);
+            assert_eq!(handle.continuous_analysis_level(), expected_level);
+        }
+        let latest = serde_json::from_str::<serde_json::Value>(&handle.continuous_analysis_json())
+            .expect("latest analysis should be json");
+        handle.clear_search_cache(); // or the cache answers level 2 straight away
+        let level_two = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(2))
+            .expect("analysis should be json");
+        assert_eq!(latest["suggestedTurnText"], level_two["suggestedTurnText"]);
+        assert_eq!(latest["numStatesVisited"], level_two["numStatesVisited"]);
+
+        handle.cancel_search();
+        assert!(!handle.is_continuous_analysis_running());
+        assert!(handle.step_continuous_analysis().contains("not running"));
+
+        handle.start_continuous_analysis();
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert!(
+            handle
+                .step_continuous_analysis()
+                .contains("Position changed")
         );
-        assert_eq!(
-            state.current_player_id,
-            core::rule_helper::SIDE_B_NORMAL_PLAYER_ID
+        assert!(!handle.is_continuous_analysis_running());
+    }
+
+    #[test]
+    fn input_limits_reject_oversized_plans_and_deep_analysis() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let long_plan = format!(
+            "[{}]",
+            vec![r#"{"pieceId":"player1","roomId":1}"#; 200].join(",")
         );
+
+        let too_long =
+            serde_json::from_str::<serde_json::Value>(&handle.validate_turn_plan_json(&long_plan))
+                .expect("validation should be json");
+        assert_eq!(too_long["error"]["code"], "planTooLong");
+        assert!(handle.apply_turn_plan(&long_plan).contains("limit"));
         assert_eq!(
-            state.player_move_cards[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0],
-            0.5
+            handle.input_limits_json(),
+            r#"{"maxPlanJsonLen":4096,"maxPlanMoves":8,"maxAnalysisLevel":6}"#
         );
+
         assert_eq!(
-            state.player_strengths[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0],
-            3
+            handle.set_input_limits_json(r#"{"maxPlanMoves":1,"maxAnalysisLevel":2}"#),
+            ""
         );
+        let too_many = serde_json::from_str::<serde_json::Value>(&handle.validate_turn_plan_json(
+            r#"[{"pieceId":"player1","roomId":1},{"pieceId":"stranger1","roomId":1}]"#,
+        ))
+        .expect("validation should be json");
+        assert_eq!(too_many["error"]["code"], "tooManyMoves");
+        assert_eq!(too_many["error"]["params"]["limit"], 1);
+
+        let analysis = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(3))
+            .expect("analysis should be json");
+        assert_eq!(analysis["isValid"], false);
         assert_eq!(
-            state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_FIRST.0],
-            4
+            handle.input_limits_json(),
+            r#"{"maxPlanJsonLen":4096,"maxPlanMoves":1,"maxAnalysisLevel":2}"#
         );
+        assert!(!handle.set_input_limits_json("nope").is_empty());
+    }
+
+    #[test]
+    fn board_text_converts_to_board_json_and_back() {
+        let tiny = core::board::Board::from_embedded_json("Tiny").expect("tiny board should load");
+        let tiny_spec = tiny.spec.clone().expect("tiny keeps its spec");
+        let text = serde_json::from_str::<serde_json::Value>(&board_text_from_json(
+            &serde_json::to_string(&tiny_spec).expect("spec should serialize"),
+        ))
+        .expect("conversion should be json");
+        assert_eq!(text["isValid"], true);
+        let board_text = text["boardText"].as_str().expect("text should be a string");
+        assert!(board_text.starts_with("name\t"));
+
+        let json = serde_json::from_str::<serde_json::Value>(&board_json_from_text(board_text))
+            .expect("conversion should be json");
+        assert_eq!(json["isValid"], true);
+        let board_json = json["boardJson"].as_str().expect("json should be a string");
         assert_eq!(
-            state.player_weapons[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0],
-            4.5
+            core::board::BoardSpecification::from_json_str(board_json).ok(),
+            Some(tiny_spec)
         );
+
+        let no_start = board_text.replace("doctor\t", "# doctor\t");
+        let invalid = serde_json::from_str::<serde_json::Value>(&board_json_from_text(&no_start))
+            .expect("conversion should be json");
+        assert_eq!(invalid["isValid"], false);
         assert_eq!(
-            state.player_strengths[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0],
-            5
+            invalid["validationMessage"],
+            "board text needs a doctor start line"
         );
-        assert_eq!(
-            state.player_strengths[core::rule_helper::STRANGER_PLAYER_ID_SECOND.0],
-            6
+    }
+
+    #[test]
+    fn ply_and_snapshot_diffs_agree() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let start_json = handle.export_state_json();
+        for _ in 0..2 {
+            let turn = handle
+                .state
+                .possible_turns()
+                .into_iter()
+                .last()
+                .expect("the game should be in progress");
+            handle.apply_checked_turn(turn);
+        }
+
+        let ply_diff = serde_json::from_str::<serde_json::Value>(&handle.ply_diff_json(0, 2))
+            .expect("diff should be json");
+        assert_eq!(ply_diff["isValid"], true);
+        assert_eq!(ply_diff["toPly"], 2);
+        assert_eq!(ply_diff["doctorToRoomId"], handle.state.doctor_room_id.0);
+        assert!(
+            ply_diff["pieceMoves"]
+                .as_array()
+                .is_some_and(|moves| !moves.is_empty())
         );
+
+        let snapshot_diff = serde_json::from_str::<serde_json::Value>(&snapshot_diff_json(
+            &start_json,
+            &handle.export_state_json(),
+        ))
+        .expect("diff should be json");
+        assert_eq!(snapshot_diff["pieceMoves"], ply_diff["pieceMoves"]);
+        assert_eq!(snapshot_diff["cardDeltas"], ply_diff["cardDeltas"]);
+
+        let too_far = serde_json::from_str::<serde_json::Value>(&handle.ply_diff_json(0, 3))
+            .expect("diff should be json");
+        assert_eq!(too_far["isValid"], false);
         assert_eq!(
-            state.player_failures[core::rule_helper::SIDE_A_NORMAL_PLAYER_ID.0],
-            2.5
+            too_far["validationMessage"],
+            "Ply 3 has not been played yet (current ply is 2)."
         );
+    }
+
+    #[test]
+    fn piece_trail_lists_rooms_oldest_first() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let start_room_id = handle.state.player_room_ids()[0].0;
+        let turn = handle
+            .state
+            .possible_turns()
+            .into_iter()
+            .find(|turn| {
+                turn.moves
+                    .iter()
+                    .any(|mv| mv.player_id.0 == 0 && mv.dest_room_id.0 != start_room_id)
+            })
+            .expect("player 1 should be able to move");
+        handle.apply_checked_turn(turn);
+
+        let trail =
+            serde_json::from_str::<serde_json::Value>(&handle.piece_trail_json("Player1", 1))
+                .expect("trail should be json");
+        assert_eq!(trail["isValid"], true);
+        assert_eq!(trail["pieceId"], "player1");
+        assert_eq!(trail["rooms"][0]["ply"], 0);
+        assert_eq!(trail["rooms"][0]["roomId"], start_room_id);
+        assert_eq!(trail["rooms"][1]["ply"], 1);
         assert_eq!(
-            state.player_move_cards[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0],
-            3.5
+            trail["rooms"][1]["roomId"],
+            handle.state.player_room_ids()[0].0
         );
-        assert_eq!(
-            state.player_failures[core::rule_helper::SIDE_B_NORMAL_PLAYER_ID.0],
-            5.5
+
+        let doctor =
+            serde_json::from_str::<serde_json::Value>(&handle.piece_trail_json("doctor", 1))
+                .expect("trail should be json");
+        assert!(
+            doctor["rooms"]
+                .as_array()
+                .is_some_and(|rooms| rooms.len() >= 2)
         );
-        assert_eq!(state.turn_id, 7);
+        let unknown = serde_json::from_str::<serde_json::Value>(&handle.piece_trail_json("cat", 1))
+            .expect("trail should be json");
+        assert_eq!(unknown["isValid"], false);
     }
 
     #[test]
-    fn validate_normal_setup_rejects_non_normal_current_player() {
-        let common = sample_common();
-        let setup = NormalSetup {
-            current_player_piece_id: PieceId::Doctor,
-            ..normalize_normal_setup(&default_normal_setup(), &common)
-        };
+    fn suggest_turn_plays_a_legal_turn_for_each_difficulty() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        for difficulty in core::move_selector::Difficulty::NAMES {
+            let suggest = || {
+                serde_json::from_str::<serde_json::Value>(&handle.suggest_turn(difficulty, 11))
+                    .expect("suggestion should be json")
+            };
+            let suggestion = suggest();
+            assert_eq!(suggestion["isValid"], true, "{difficulty}");
+            assert_eq!(
+                suggestion["suggestedTurnText"],
+                suggest()["suggestedTurnText"]
+            );
+            let turn_text = suggestion["suggestedTurnText"]
+                .as_str()
+                .expect("turn text should be a string");
+            assert!(
+                handle
+                    .state
+                    .possible_turns()
+                    .iter()
+                    .any(|turn| turn.to_string() == turn_text)
+            );
+        }
+        let unknown = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn("brutal", 0))
+            .expect("suggestion should be json");
+        assert_eq!(unknown["isValid"], false);
+
+        handle.state.abort();
+        let finished = serde_json::from_str::<serde_json::Value>(&handle.suggest_turn("easy", 0))
+            .expect("suggestion should be json");
+        assert_eq!(finished["isValid"], false);
+    }
+
+    #[test]
+    fn set_engine_log_level_rejects_unknown_levels() {
+        let message = set_engine_log_level("loud");
+        assert!(message.contains("unknown log level 'loud'"), "{message}");
+    }
 
+    #[test]
+    fn engine_info_json_lists_versions_and_known_rules() {
+        let info = serde_json::from_str::<serde_json::Value>(&engine_info_json())
+            .expect("engine info should be json");
+        assert_eq!(info["engineVersion"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(info["persistedStateVersion"], PERSISTED_GAME_STATE_VERSION);
         assert_eq!(
-            validate_normal_setup(&setup, &common),
-            Err("currentPlayerPieceId must be P1 or P3.".to_string())
+            info["persistedStateVersionsSupported"],
+            serde_json::json!([1, 2])
+        );
+        assert_eq!(info["positionKeyVersion"], 2);
+        let rule_config_keys = info["ruleConfigKeys"]
+            .as_array()
+            .expect("rule config keys should be an array");
+        assert!(rule_config_keys.contains(&"strictStrangerMoves".into()));
+        assert!(rule_config_keys.contains(&"occupiedRoomsBlocked".into()));
+        assert!(
+            info["presetNames"]
+                .as_array()
+                .is_some_and(|names| !names.is_empty())
         );
     }
 
     #[test]
-    fn attack_history_text_includes_attacker_room_and_turn_in_order() {
-        let common = sample_common();
-        let mut turn_8 = core::mutable_game_state::MutableGameState::at_start(common);
-        turn_8.turn_id = 8;
-        turn_8.current_player_id = core::player::PlayerId(0);
-        turn_8.player_room_ids[0] = core::room::RoomId(15);
-
-        let mut turn_9 = turn_8.copy_state();
-        turn_9.prev_state = Some(std::rc::Rc::new(turn_8));
-        turn_9.turn_id = 9;
-        turn_9.current_player_id = core::player::PlayerId(1);
-        turn_9.attacker_hist.push(core::player::PlayerId(0));
-
-        let mut turn_10 = turn_9.copy_state();
-        turn_10.prev_state = Some(std::rc::Rc::new(turn_9));
-        turn_10.turn_id = 10;
-        turn_10.attacker_hist.push(core::player::PlayerId(1));
-        turn_10.player_room_ids[1] = core::room::RoomId(1);
+    fn import_state_json_accepts_version_one_without_annotations() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        let mut snapshot = serde_json::from_str::<serde_json::Value>(&handle.export_state_json())
+            .expect("export should be json");
+        snapshot["version"] = 1.into();
+        snapshot
+            .as_object_mut()
+            .expect("snapshot should be an object")
+            .remove("annotations");
+
+        assert_eq!(handle.import_state_json(&snapshot.to_string()), "");
+        assert_eq!(handle.annotations_json(), "[]");
+    }
 
-        assert_eq!(
-            attack_history_text_for_state(&turn_10),
-            "1@15,T1; 2@1,T1"
-        );
-        assert_eq!(attack_history_label_for_state(&turn_10), "Atks1,1");
+    #[test]
+    fn blunder_check_records_swing_for_mover() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_eq!(handle.last_turn_swing(), "");
+
+        handle.set_blunder_check(1, 0.0);
+        let best_turn = serde_json::from_str::<serde_json::Value>(&handle.find_best_turn(1))
+            .expect("analysis should be json");
+        let best_turn_plan = best_turn["suggestedTurn"].to_string();
+        assert_eq!(handle.apply_turn_plan(&best_turn_plan), "");
+
+        let swing = serde_json::from_str::<serde_json::Value>(&handle.last_turn_swing())
+            .expect("swing should be json");
+        assert_eq!(swing["pieceId"], "player2");
+        assert_eq!(swing["analysisLevel"], 1);
+        assert!(swing["swing"].as_f64().is_some());
+
+        assert!(handle.undo_last_turn());
+        assert_eq!(handle.last_turn_swing(), "");
     }
 
     #[test]
-    fn export_state_json_uses_board_json_name() {
-        let board = core::board::Board::from_embedded_json("BoardAltDown")
-            .expect("AltDown board should load");
-        let common =
-            core::common_game_state::CommonGameState::from_num_normal_players(true, board, 2);
-        let normal_setup = normalize_normal_setup(&default_normal_setup(), &common);
-        let state = new_state_with_normal_setup(common, &normal_setup);
-        let handle = GameStateHandle {
-            state,
-            normal_setup,
-        };
+    fn loading_a_board_or_importing_drops_the_last_turns_swing() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        handle.set_blunder_check(1, 0.0);
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_ne!(handle.last_turn_swing(), "");
+        let snapshot = handle.export_state_json();
+
+        assert_eq!(handle.load_board("BoardAltDown"), "");
+        assert_eq!(handle.last_turn_swing(), "");
+
+        assert_eq!(handle.apply_turn_plan(""), "");
+        assert_ne!(handle.last_turn_swing(), "");
+        assert_eq!(handle.import_state_json(&snapshot), "");
+        assert_eq!(handle.last_turn_swing(), "");
+        assert_eq!(handle.last_turn_broadcast_json(), "");
+    }
 
-        let snapshot = serde_json::from_str::<PersistedGameState>(&handle.export_state_json())
-            .expect("export should be valid persisted game json");
+    #[test]
+    fn stranger_loops_json_lists_board_loop_rooms() {
+        let handle = new_default_game_state().expect("default game state should load");
+        let loops =
+            serde_json::from_str::<serde_json::Value>(&handle.stranger_loops_json("player1"))
+                .expect("stranger loops should be json");
 
-        assert_eq!(snapshot.board_name, "BoardAltDown");
-        assert_eq!(snapshot.normal_setup.board_name, "BoardAltDown");
+        assert_eq!(
+            loops.as_array().map(Vec::len),
+            Some(handle.state.common.board.stranger_loop_room_ids.len())
+        );
+        assert!(loops[0]["counterRoomIds"].is_array());
+        assert_eq!(handle.stranger_loops_json("doctor"), "[]");
     }
 
     #[test]
-    fn saved_game_board_match_accepts_legacy_display_name() {
-        assert!(is_matching_board_name("Tiny", "Tiny", "BoardTiny"));
-        assert!(is_matching_board_name("BoardTiny", "Tiny", "BoardTiny"));
-        assert!(!is_matching_board_name("BoardMain", "Tiny", "BoardTiny"));
+    fn wings_json_is_empty_for_boards_without_wings() {
+        let handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.wings_json(), "[]");
     }
 
     #[test]
@@ -1484,4 +7925,51 @@ mod tests {
             "Saved game setup board 'BoardMain' does not match current board 'BoardAltDown'."
         );
     }
+
+    #[test]
+    fn puzzles_load_check_lines_and_give_hints() {
+        let mut handle = new_default_game_state().expect("default game state should load");
+        assert_eq!(handle.load_puzzle("nope"), "Unknown puzzle 'nope'.");
+        assert!(!handle.load_puzzle_json("{}").is_empty());
+        let listed = serde_json::from_str::<serde_json::Value>(&list_puzzles_json())
+            .expect("puzzle list should be json");
+        assert_eq!(listed[0]["goal"]["kind"], "winWithin");
+        let assets = serde_json::from_str::<serde_json::Value>(&list_assets_json("puzzles"))
+            .expect("asset list should be json");
+        assert_eq!(assets[0]["kind"], "puzzle");
+        assert_eq!(assets[0]["name"], listed[0]["name"]);
+        assert_eq!(list_assets_json("maps"), "[]");
+
+        assert_eq!(handle.load_puzzle("first blood"), "");
+        let start_room_ids = handle.state.player_room_ids().to_vec();
+        let progress = serde_json::from_str::<serde_json::Value>(&handle.check_puzzle_progress())
+            .expect("progress should be json");
+        assert_eq!(progress["verdict"], "onTrack");
+        let missed = serde_json::from_str::<serde_json::Value>(&handle.check_puzzle_line("[[]]"))
+            .expect("line check should be json");
+        assert_eq!(missed["verdict"], "failed");
+        assert_eq!(missed["failedPly"], 1);
+
+        let hint = serde_json::from_str::<serde_json::Value>(&handle.puzzle_hint_json())
+            .expect("hint should be json");
+        assert_eq!(hint["isValid"], true);
+        assert_eq!(
+            handle.apply_turn_plan(&hint["suggestedTurn"].to_string()),
+            ""
+        );
+        let progress = serde_json::from_str::<serde_json::Value>(&handle.check_puzzle_progress())
+            .expect("progress should be json");
+        assert_eq!(progress["verdict"], "solved");
+
+        handle.reset_game();
+        assert_eq!(handle.state.player_room_ids(), start_room_ids);
+        assert!(handle.puzzle.is_some());
+        assert_eq!(handle.load_board("Tiny"), "");
+        assert_eq!(handle.puzzle_json(), "null");
+        assert!(
+            handle
+                .check_puzzle_progress()
+                .contains("No puzzle is loaded.")
+        );
+    }
 }
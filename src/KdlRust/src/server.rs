@@ -0,0 +1,301 @@
+use crate::util::log::{self, LogLevel};
+use crate::{GameStateHandle, game_state_handle_for_board, game_state_handle_for_preset};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8137";
+const DEFAULT_SUGGEST_LEVEL: i32 = 2;
+const MAX_SUGGEST_LEVEL: i32 = crate::InputLimits::DEFAULT_MAX_ANALYSIS_LEVEL;
+const MAX_BODY_BYTES: u64 = 64 * 1024;
+const MAX_GAMES: usize = 256; // past this, a new game drops the oldest
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct NewGameRequest {
+    board_name: Option<String>,
+    preset: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NewGameResponse {
+    is_valid: bool,
+    validation_message: String,
+    game_id: u64,
+    state: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationResponse {
+    is_valid: bool,
+    validation_message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl ServerResponse {
+    fn ok(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn invalid(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: validation_json(message.into()),
+        }
+    }
+}
+
+// routes mirror the wasm GameStateHandle methods:
+//   GET    /presets                      listPresetsJson
+//   POST   /games                        {"boardName"} or {"preset"}; returns gameId and exportStateJson
+//   GET    /games/{id}                   exportStateJson
+//   DELETE /games/{id}
+//   POST   /games/{id}/turn              applyTurnPlan
+//   POST   /games/{id}/preview           previewTurnPlan
+//   GET    /games/{id}/suggest?level=n   findBestTurn
+#[derive(Default)]
+pub struct EngineServer {
+    games: BTreeMap<u64, GameStateHandle>,
+    next_game_id: u64,
+}
+
+impl EngineServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle(&mut self, method: &str, url: &str, body: &str) -> ServerResponse {
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let segments = path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+
+        match (method, segments.as_slice()) {
+            ("GET", ["presets"]) => ServerResponse::ok(crate::list_presets_json()),
+            ("POST", ["games"]) => self.new_game(body),
+            (_, ["games", game_id, rest @ ..]) => {
+                let Ok(game_id) = game_id.parse::<u64>() else {
+                    return ServerResponse::invalid(400, format!("Invalid game id '{game_id}'."));
+                };
+                if method == "DELETE" && rest.is_empty() {
+                    return match self.games.remove(&game_id) {
+                        Some(_) => ServerResponse::ok(validation_json(String::new())),
+                        None => unknown_game(game_id),
+                    };
+                }
+                let Some(game) = self.games.get_mut(&game_id) else {
+                    return unknown_game(game_id);
+                };
+                match (method, rest) {
+                    ("GET", []) => ServerResponse::ok(game.export_state_json()),
+                    ("POST", ["turn"]) => {
+                        let message = game.apply_turn_plan(body);
+                        if message.is_empty() {
+                            ServerResponse::ok(game.export_state_json())
+                        } else {
+                            ServerResponse::invalid(400, message)
+                        }
+                    }
+                    ("POST", ["preview"]) => ServerResponse::ok(game.preview_turn_plan(body)),
+                    ("GET", ["suggest"]) => match suggest_level(query) {
+                        Ok(level) => ServerResponse::ok(game.find_best_turn(level)),
+                        Err(message) => ServerResponse::invalid(400, message),
+                    },
+                    _ => not_found(method, path),
+                }
+            }
+            _ => not_found(method, path),
+        }
+    }
+
+    fn new_game(&mut self, body: &str) -> ServerResponse {
+        let request = if body.trim().is_empty() {
+            NewGameRequest::default()
+        } else {
+            match serde_json::from_str::<NewGameRequest>(body) {
+                Ok(request) => request,
+                Err(err) => {
+                    return ServerResponse::invalid(400, format!("Invalid new game JSON: {err}"));
+                }
+            }
+        };
+        let game = match (request.preset.as_ref(), request.board_name.as_ref()) {
+            (Some(_), Some(_)) => Err("Give either boardName or preset, not both.".to_string()),
+            (Some(preset), None) => game_state_handle_for_preset(preset),
+            (None, Some(board_name)) => game_state_handle_for_board(board_name),
+            (None, None) => game_state_handle_for_board("BoardAltDown"),
+        };
+        let game = match game {
+            Ok(game) => game,
+            Err(message) => return ServerResponse::invalid(400, message),
+        };
+
+        while self.games.len() >= MAX_GAMES {
+            self.games.pop_first();
+        }
+        self.next_game_id += 1;
+        let game_id = self.next_game_id;
+        let state = serde_json::from_str::<serde_json::Value>(&game.export_state_json())
+            .unwrap_or_default();
+        self.games.insert(game_id, game);
+        ServerResponse::ok(
+            serde_json::to_string(&NewGameResponse {
+                is_valid: true,
+                validation_message: String::new(),
+                game_id,
+                state,
+            })
+            .unwrap_or_default(),
+        )
+    }
+}
+
+pub fn serve(address: &str) -> Result<(), String> {
+    let server = tiny_http::Server::http(address)
+        .map_err(|err| format!("failed to listen on {address}: {err}"))?;
+    let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json")
+        .map_err(|_| "failed to build content type header".to_string())?;
+    let mut engine_server = EngineServer::new();
+    log::log(LogLevel::Info, || {
+        format!("serving engine on http://{address}")
+    });
+
+    for mut request in server.incoming_requests() {
+        let response = match read_body(request.as_reader()) {
+            Ok(body) => engine_server.handle(request.method().as_str(), request.url(), &body),
+            Err(response) => response,
+        };
+        let http_response = tiny_http::Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(http_response) {
+            log::log(LogLevel::Error, || format!("failed to respond: {err}"));
+        }
+    }
+
+    Ok(())
+}
+
+fn read_body(reader: impl Read) -> Result<String, ServerResponse> {
+    let mut body = String::new();
+    reader
+        .take(MAX_BODY_BYTES + 1)
+        .read_to_string(&mut body)
+        .map_err(|err| ServerResponse::invalid(400, format!("Failed to read body: {err}")))?;
+    if body.len() as u64 > MAX_BODY_BYTES {
+        return Err(ServerResponse::invalid(
+            413,
+            format!("Request body is over the {MAX_BODY_BYTES} byte limit."),
+        ));
+    }
+    Ok(body)
+}
+
+fn suggest_level(query: &str) -> Result<i32, String> {
+    let Some(level_text) = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "level")
+        .map(|(_, value)| value)
+    else {
+        return Ok(DEFAULT_SUGGEST_LEVEL);
+    };
+
+    match level_text.parse::<i32>() {
+        Ok(level) if (1..=MAX_SUGGEST_LEVEL).contains(&level) => Ok(level),
+        _ => Err(format!(
+            "level must be an integer from 1 to {MAX_SUGGEST_LEVEL}, not '{level_text}'."
+        )),
+    }
+}
+
+fn validation_json(validation_message: String) -> String {
+    serde_json::to_string(&ValidationResponse {
+        is_valid: validation_message.is_empty(),
+        validation_message,
+    })
+    .unwrap_or_default()
+}
+
+fn unknown_game(game_id: u64) -> ServerResponse {
+    ServerResponse::invalid(404, format!("Unknown game id {game_id}."))
+}
+
+fn not_found(method: &str, path: &str) -> ServerResponse {
+    ServerResponse::invalid(404, format!("No route for {method} {path}."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(response: &ServerResponse) -> serde_json::Value {
+        serde_json::from_str::<serde_json::Value>(&response.body).expect("body should be json")
+    }
+
+    #[test]
+    fn new_game_then_suggest_and_apply_turn() {
+        let mut server = EngineServer::new();
+        let created = server.handle("POST", "/games", r#"{"boardName":"BoardAltDown"}"#);
+        assert_eq!(created.status, 200);
+        let game_id = json(&created)["gameId"].as_u64().expect("game id");
+
+        let suggestion = server.handle("GET", &format!("/games/{game_id}/suggest?level=1"), "");
+        let suggestion = json(&suggestion);
+        assert_eq!(suggestion["isValid"], true);
+        let turn_plan = serde_json::to_string(&suggestion["suggestedTurn"]).expect("turn plan");
+
+        let preview = server.handle("POST", &format!("/games/{game_id}/preview"), &turn_plan);
+        assert_eq!(json(&preview)["isValid"], true);
+        let applied = server.handle("POST", &format!("/games/{game_id}/turn"), &turn_plan);
+        assert_eq!(applied.status, 200);
+        assert_eq!(
+            json(&applied)["normalTurns"].as_array().map(Vec::len),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn errors_come_back_as_validation_json() {
+        let mut server = EngineServer::new();
+        let unknown = server.handle("GET", "/games/7", "");
+        assert_eq!(unknown.status, 404);
+        assert_eq!(json(&unknown)["isValid"], false);
+
+        let bad_preset = server.handle("POST", "/games", r#"{"preset":"nope"}"#);
+        assert_eq!(bad_preset.status, 400);
+
+        server.handle("POST", "/games", "");
+        let bad_level = server.handle("GET", "/games/1/suggest?level=99", "");
+        assert_eq!(bad_level.status, 400);
+        assert_eq!(server.handle("DELETE", "/games/1", "").status, 200);
+        assert_eq!(server.handle("GET", "/games/1", "").status, 404);
+    }
+
+    #[test]
+    fn oversized_bodies_and_old_games_are_turned_away() {
+        let at_limit = "x".repeat(MAX_BODY_BYTES as usize);
+        assert_eq!(read_body(at_limit.as_bytes()), Ok(at_limit.clone()));
+        let over_limit = format!("{at_limit}x");
+        assert_eq!(
+            read_body(over_limit.as_bytes()).map_err(|response| response.status),
+            Err(413)
+        );
+
+        let mut server = EngineServer::new();
+        for _ in 0..=MAX_GAMES {
+            assert_eq!(server.handle("POST", "/games", "").status, 200);
+        }
+        assert_eq!(server.games.len(), MAX_GAMES);
+        assert_eq!(server.handle("GET", "/games/1", "").status, 404);
+        assert_eq!(server.handle("GET", "/games/2", "").status, 200);
+    }
+}
@@ -1,4 +1,4 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 pub trait CancellationToken {
     fn is_cancellation_requested(&self) -> bool;
@@ -33,3 +33,83 @@ impl CancellationToken for AtomicCancellationToken {
         self.cancelled.load(Ordering::SeqCst)
     }
 }
+
+// cancels once clock() reaches deadline_ms; the clock is passed in because wasm has no
+// std::time. reading a browser clock isn't free, so polls only read it every so often
+pub struct DeadlineCancellationToken<Clock: Fn() -> f64> {
+    deadline_ms: f64,
+    clock: Clock,
+    num_polls: AtomicUsize,
+    expired: AtomicBool,
+}
+
+impl<Clock: Fn() -> f64> DeadlineCancellationToken<Clock> {
+    pub const POLLS_PER_CLOCK_READ: usize = 64;
+
+    pub fn new(deadline_ms: f64, clock: Clock) -> Self {
+        Self {
+            deadline_ms,
+            clock,
+            num_polls: AtomicUsize::new(0),
+            expired: AtomicBool::new(false),
+        }
+    }
+
+    // reads the clock now, unlike a poll
+    pub fn is_past_deadline(&self) -> bool {
+        if self.has_cancelled() {
+            return true;
+        }
+        let is_past_deadline = (self.clock)() >= self.deadline_ms;
+        if is_past_deadline {
+            self.expired.store(true, Ordering::SeqCst);
+        }
+        is_past_deadline
+    }
+
+    // whether a poll or is_past_deadline has seen the deadline pass; a search that ran while this
+    // stayed false was not cut short
+    pub fn has_cancelled(&self) -> bool {
+        self.expired.load(Ordering::SeqCst)
+    }
+}
+
+impl<Clock: Fn() -> f64> CancellationToken for DeadlineCancellationToken<Clock> {
+    fn is_cancellation_requested(&self) -> bool {
+        if self.has_cancelled() {
+            return true;
+        }
+        self.num_polls
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(Self::POLLS_PER_CLOCK_READ)
+            && self.is_past_deadline()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn deadline_token_reads_the_clock_every_few_polls_and_stays_cancelled() {
+        let now_ms = Cell::new(0.0);
+        let token = DeadlineCancellationToken::new(10.0, || now_ms.get());
+        assert!(!token.is_cancellation_requested());
+
+        now_ms.set(10.0);
+        let num_polls_until_cancelled = (0
+            ..DeadlineCancellationToken::<fn() -> f64>::POLLS_PER_CLOCK_READ)
+            .take_while(|_| !token.is_cancellation_requested())
+            .count();
+        assert_eq!(
+            num_polls_until_cancelled,
+            DeadlineCancellationToken::<fn() -> f64>::POLLS_PER_CLOCK_READ - 1
+        );
+        assert!(token.has_cancelled());
+
+        now_ms.set(0.0);
+        assert!(token.is_cancellation_requested());
+        assert!(token.is_past_deadline());
+    }
+}
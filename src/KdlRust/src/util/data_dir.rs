@@ -0,0 +1,239 @@
+use crate::core::{
+    board::{Board, BoardLoadError},
+    deck_spec::DeckSpec,
+};
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DATA_DIR_ENV_VAR: &str = "KDL_DATA_DIR";
+pub const DATA_DIR_FLAG: &str = "--data-dir";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DataDirOrigin {
+    CliArg,
+    EnvVar,
+}
+
+// where native builds look for reference data like the c# project's: a --data-dir argument, then
+// KDL_DATA_DIR. files are looked up in the directory and in its boards and decks subdirectories;
+// a board or deck not found there, or with no directory at all, comes from the embedded ones
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DataPaths {
+    pub dir: Option<(PathBuf, DataDirOrigin)>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DataFileKind {
+    Board,
+    Deck,
+}
+
+impl DataFileKind {
+    fn subdir(self) -> &'static str {
+        match self {
+            DataFileKind::Board => "boards",
+            DataFileKind::Deck => "decks",
+        }
+    }
+
+    // the c# reference data names files like BoardAltDown.json and DeckStandard.json
+    fn file_prefix(self) -> &'static str {
+        match self {
+            DataFileKind::Board => "Board",
+            DataFileKind::Deck => "Deck",
+        }
+    }
+}
+
+impl DataPaths {
+    pub fn resolve(cli_dir: Option<PathBuf>, env_dir: Option<OsString>) -> Self {
+        let dir = match (cli_dir, env_dir.filter(|dir| !dir.is_empty())) {
+            (Some(dir), _) => Some((dir, DataDirOrigin::CliArg)),
+            (None, Some(dir)) => Some((PathBuf::from(dir), DataDirOrigin::EnvVar)),
+            (None, None) => None,
+        };
+        Self { dir }
+    }
+
+    pub fn from_env(cli_dir: Option<PathBuf>) -> Self {
+        Self::resolve(cli_dir, env::var_os(DATA_DIR_ENV_VAR))
+    }
+
+    pub fn dir_path(&self) -> Option<&Path> {
+        self.dir.as_ref().map(|(dir, _)| dir.as_path())
+    }
+
+    // a path as given if it exists, else the first match under the data directory, trying a .json
+    // extension too
+    pub fn resolve_file(&self, path_text: &str, kind: DataFileKind) -> PathBuf {
+        let path = PathBuf::from(path_text);
+        if path.is_absolute() || path.exists() {
+            return path;
+        }
+        let Some(dir) = self.dir_path() else {
+            return path;
+        };
+        [dir.join(&path), dir.join(kind.subdir()).join(&path)]
+            .into_iter()
+            .flat_map(|candidate| [candidate.with_extension("json"), candidate])
+            .find(|candidate| candidate.is_file())
+            .unwrap_or(path)
+    }
+
+    // the data directory's file for a name like AltDown or BoardAltDown, if it has one
+    pub fn named_file(&self, name: &str, kind: DataFileKind) -> Option<PathBuf> {
+        let dir = self.dir_path()?;
+        let file_names = [
+            format!("{name}.json"),
+            format!("{}{name}.json", kind.file_prefix()),
+        ];
+        [dir.join(kind.subdir()), dir.to_path_buf()]
+            .into_iter()
+            .flat_map(|search_dir| {
+                file_names
+                    .iter()
+                    .map(|file_name| search_dir.join(file_name))
+                    .collect::<Vec<_>>()
+            })
+            .find(|candidate| candidate.is_file())
+    }
+
+    pub fn load_board<S>(
+        &self,
+        board_name: &str,
+        closed_wing_names: impl IntoIterator<Item = S>,
+    ) -> Result<Board, BoardLoadError>
+    where
+        S: AsRef<str>,
+    {
+        match self.named_file(board_name, DataFileKind::Board) {
+            Some(board_path) => {
+                Board::from_json_file_with_options(board_path, closed_wing_names, "")
+            }
+            None => Board::from_embedded_json_with_options(board_name, closed_wing_names, ""),
+        }
+    }
+
+    pub fn load_deck(&self, deck_name: &str) -> Result<DeckSpec, String> {
+        match self.named_file(deck_name, DataFileKind::Deck) {
+            Some(deck_path) => DeckSpec::from_json_file(&deck_path),
+            None => DeckSpec::find_embedded(deck_name)
+                .ok_or_else(|| format!("Unknown deck '{deck_name}'.")),
+        }
+    }
+
+    // names of the json files of a kind in the data directory, without prefix or extension, sorted
+    pub fn list_names(&self, kind: DataFileKind) -> Vec<String> {
+        let Some(dir) = self.dir_path() else {
+            return Vec::new();
+        };
+        let mut names = [dir.join(kind.subdir()), dir.to_path_buf()]
+            .iter()
+            .filter_map(|search_dir| fs::read_dir(search_dir).ok())
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if !path.is_file() || path.extension()? != "json" {
+                    return None;
+                }
+                let stem = path.file_stem()?.to_str()?;
+                let in_subdir = path.parent() == Some(dir.join(kind.subdir()).as_path());
+                match stem.strip_prefix(kind.file_prefix()) {
+                    Some(name) if !name.is_empty() => Some(name.to_string()),
+                    _ if in_subdir => Some(stem.to_string()),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+impl fmt::Display for DataPaths {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.dir {
+            Some((dir, DataDirOrigin::CliArg)) => write!(f, "{} ({DATA_DIR_FLAG})", dir.display()),
+            Some((dir, DataDirOrigin::EnvVar)) => {
+                write!(f, "{} ({DATA_DIR_ENV_VAR})", dir.display())
+            }
+            None => write!(f, "embedded only"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference_data_paths() -> DataPaths {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data");
+        DataPaths::resolve(Some(dir), Some(OsString::from("/ignored")))
+    }
+
+    #[test]
+    fn cli_arg_beats_env_var_and_neither_means_embedded() {
+        assert_eq!(
+            reference_data_paths().dir.map(|(_, origin)| origin),
+            Some(DataDirOrigin::CliArg)
+        );
+        let from_env = DataPaths::resolve(None, Some(OsString::from("/data")));
+        assert_eq!(from_env.dir_path(), Some(Path::new("/data")));
+        assert!(from_env.to_string().contains(DATA_DIR_ENV_VAR));
+
+        let embedded = DataPaths::resolve(None, Some(OsString::new()));
+        assert_eq!(embedded, DataPaths::default());
+        assert!(embedded.list_names(DataFileKind::Board).is_empty());
+        assert!(
+            embedded
+                .load_board("Tiny", std::iter::empty::<String>())
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn finds_and_lists_reference_boards_and_decks_on_disk() {
+        let data_paths = reference_data_paths();
+        let board_names = data_paths.list_names(DataFileKind::Board);
+        assert!(board_names.contains(&"AltDown".to_string()));
+        assert!(!board_names.iter().any(|name| name.starts_with("Board")));
+        assert_eq!(
+            data_paths.list_names(DataFileKind::Deck),
+            vec!["Standard".to_string()]
+        );
+
+        let board_path = data_paths
+            .named_file("AltDown", DataFileKind::Board)
+            .expect("AltDown should be on disk");
+        assert_eq!(
+            data_paths.named_file("BoardAltDown", DataFileKind::Board),
+            Some(board_path)
+        );
+        assert!(
+            data_paths
+                .resolve_file("BoardAltDown", DataFileKind::Board)
+                .is_file()
+        );
+
+        let board = data_paths
+            .load_board("AltDown", std::iter::empty::<String>())
+            .expect("board should load from disk");
+        assert_eq!(board.json_name, "BoardAltDown");
+        assert!(
+            data_paths
+                .load_board("Tiny", std::iter::empty::<String>())
+                .is_ok()
+        );
+
+        let deck = data_paths
+            .load_deck("DeckStandard")
+            .expect("deck should load from disk");
+        assert_eq!(deck, DeckSpec::standard());
+        assert!(DataPaths::default().load_deck("Standard").is_ok());
+        assert!(data_paths.load_deck("Pinochle").is_err());
+    }
+}
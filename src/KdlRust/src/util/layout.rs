@@ -0,0 +1,124 @@
+// positions for a graph with no coordinates of its own, from a fruchterman-reingold spring
+// layout: every pair of nodes pushes apart and every edge pulls its ends together. nodes start
+// evenly around a circle, so the same graph always gets the same layout. positions come back
+// scaled to fill 0..=1 on both axes
+pub fn force_directed_layout(
+    num_nodes: usize,
+    edges: &[(usize, usize)],
+    num_iterations: usize,
+) -> Vec<(f64, f64)> {
+    if num_nodes < 2 {
+        return vec![(0.5, 0.5); num_nodes];
+    }
+    let mut positions = (0..num_nodes)
+        .map(|idx| {
+            let angle = std::f64::consts::TAU * idx as f64 / num_nodes as f64;
+            (0.5 + 0.5 * angle.cos(), 0.5 + 0.5 * angle.sin())
+        })
+        .collect::<Vec<_>>();
+
+    let ideal_length = (1.0 / num_nodes as f64).sqrt();
+    let initial_temperature = 0.1;
+    for iteration in 0..num_iterations {
+        let mut displacements = vec![(0.0, 0.0); num_nodes];
+        for a in 0..num_nodes {
+            for b in a + 1..num_nodes {
+                let (dx, dy, dist) = offset(positions[a], positions[b]);
+                let force = ideal_length * ideal_length / dist;
+                push(
+                    &mut displacements,
+                    a,
+                    b,
+                    dx / dist * force,
+                    dy / dist * force,
+                );
+            }
+        }
+        for &(a, b) in edges {
+            if a == b || a >= num_nodes || b >= num_nodes {
+                continue;
+            }
+            let (dx, dy, dist) = offset(positions[a], positions[b]);
+            let force = dist * dist / ideal_length;
+            push(
+                &mut displacements,
+                a,
+                b,
+                -dx / dist * force,
+                -dy / dist * force,
+            );
+        }
+
+        let temperature =
+            initial_temperature * (1.0 - iteration as f64 / num_iterations.max(1) as f64);
+        for (position, (dx, dy)) in positions.iter_mut().zip(displacements) {
+            let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+            let step = length.min(temperature);
+            position.0 += dx / length * step;
+            position.1 += dy / length * step;
+        }
+    }
+
+    scaled_to_unit_square(positions)
+}
+
+// b's offset from a, with a distance kept away from zero so forces stay finite
+fn offset(a: (f64, f64), b: (f64, f64)) -> (f64, f64, f64) {
+    let (dx, dy) = (a.0 - b.0, a.1 - b.1);
+    (dx, dy, (dx * dx + dy * dy).sqrt().max(1e-6))
+}
+
+// moves a by (dx, dy) and b the opposite way
+fn push(displacements: &mut [(f64, f64)], a: usize, b: usize, dx: f64, dy: f64) {
+    displacements[a].0 += dx;
+    displacements[a].1 += dy;
+    displacements[b].0 -= dx;
+    displacements[b].1 -= dy;
+}
+
+fn scaled_to_unit_square(positions: Vec<(f64, f64)>) -> Vec<(f64, f64)> {
+    let bounds = |axis: fn(&(f64, f64)) -> f64| {
+        positions
+            .iter()
+            .map(axis)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+                (low.min(value), high.max(value))
+            })
+    };
+    let (x_low, x_high) = bounds(|position| position.0);
+    let (y_low, y_high) = bounds(|position| position.1);
+    let scale = |value: f64, low: f64, high: f64| {
+        if high - low > f64::EPSILON {
+            (value - low) / (high - low)
+        } else {
+            0.5
+        }
+    };
+    positions
+        .iter()
+        .map(|(x, y)| (scale(*x, x_low, x_high), scale(*y, y_low, y_high)))
+        .collect::<Vec<_>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn connected_nodes_end_up_closer_than_unconnected_ones() {
+        // two triangles joined by one edge
+        let edges = [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (2, 3)];
+        let positions = force_directed_layout(6, &edges, 200);
+        assert_eq!(positions, force_directed_layout(6, &edges, 200));
+        assert!(
+            positions
+                .iter()
+                .all(|(x, y)| (0.0..=1.0).contains(x) && (0.0..=1.0).contains(y))
+        );
+
+        let dist = |a: usize, b: usize| offset(positions[a], positions[b]).2;
+        assert!(dist(0, 1) < dist(0, 4));
+        assert!(dist(3, 5) < dist(1, 5));
+        assert_eq!(force_directed_layout(1, &[], 10), vec![(0.5, 0.5)]);
+    }
+}
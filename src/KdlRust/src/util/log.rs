@@ -0,0 +1,133 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+// a sink hears messages at its level and the more severe levels before it
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    pub const NAMES: [&'static str; 4] = ["error", "warn", "info", "debug"];
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(Self::NAMES[*self as usize])
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            _ => Err(format!(
+                "unknown log level '{text}'; use one of {}",
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+pub trait LogSink: Send + Sync {
+    fn log(&self, level: LogLevel, message: &str);
+}
+
+// stdout for info and debug, stderr for warnings and errors
+pub struct ConsoleLogSink;
+
+impl LogSink for ConsoleLogSink {
+    fn log(&self, level: LogLevel, message: &str) {
+        if level <= LogLevel::Warn {
+            eprintln!("{message}");
+        } else {
+            println!("{message}");
+        }
+    }
+}
+
+struct Logger {
+    max_level: LogLevel,
+    sink: Box<dyn LogSink>,
+}
+
+// silent until a program sets a sink, so tests and wasm builds print nothing by default
+static LOGGER: RwLock<Option<Logger>> = RwLock::new(None);
+
+pub fn set_log_sink(max_level: LogLevel, sink: impl LogSink + 'static) {
+    if let Ok(mut logger) = LOGGER.write() {
+        *logger = Some(Logger {
+            max_level,
+            sink: Box::new(sink),
+        });
+    }
+}
+
+pub fn clear_log_sink() {
+    if let Ok(mut logger) = LOGGER.write() {
+        *logger = None;
+    }
+}
+
+pub fn is_enabled(level: LogLevel) -> bool {
+    LOGGER.read().is_ok_and(|logger| {
+        logger
+            .as_ref()
+            .is_some_and(|logger| level <= logger.max_level)
+    })
+}
+
+// message is only built when some sink will hear it
+pub fn log(level: LogLevel, message: impl FnOnce() -> String) {
+    if let Ok(logger) = LOGGER.read()
+        && let Some(logger) = logger.as_ref()
+        && level <= logger.max_level
+    {
+        logger.sink.log(level, &message());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct CollectingSink(Arc<Mutex<Vec<String>>>);
+
+    impl LogSink for CollectingSink {
+        fn log(&self, level: LogLevel, message: &str) {
+            if let Ok(mut lines) = self.0.lock() {
+                lines.push(format!("{level}: {message}"));
+            }
+        }
+    }
+
+    #[test]
+    fn sink_hears_only_levels_up_to_its_own() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        set_log_sink(LogLevel::Info, CollectingSink(lines.clone()));
+        assert!(is_enabled(LogLevel::Warn));
+        assert!(!is_enabled(LogLevel::Debug));
+        log(LogLevel::Warn, || "log test warn".to_string());
+        log(LogLevel::Debug, || {
+            panic!("debug messages shouldn't be built")
+        });
+        clear_log_sink();
+        log(LogLevel::Error, || "log test after clearing".to_string());
+
+        let lines = lines.lock().expect("lines should lock");
+        assert!(lines.contains(&"warn: log test warn".to_string()));
+        assert!(!lines.iter().any(|line| line.contains("after clearing")));
+        assert_eq!("Info".parse::<LogLevel>(), Ok(LogLevel::Info));
+        assert!("loud".parse::<LogLevel>().is_err());
+    }
+}
@@ -0,0 +1,136 @@
+use std::ops::{Index, IndexMut};
+
+// row-major table in one allocation, so a lookup is a multiply and an add instead of a second
+// pointer to chase
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix<T> {
+    num_rows: usize,
+    num_cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn new(num_rows: usize, num_cols: usize, value: T) -> Self {
+        Self {
+            num_rows,
+            num_cols,
+            cells: vec![value; num_rows * num_cols],
+        }
+    }
+}
+
+impl<T> Matrix<T> {
+    pub fn from_fn(
+        num_rows: usize,
+        num_cols: usize,
+        mut cell: impl FnMut(usize, usize) -> T,
+    ) -> Self {
+        let cells = (0..num_rows)
+            .flat_map(|row| (0..num_cols).map(move |col| (row, col)))
+            .map(|(row, col)| cell(row, col))
+            .collect::<Vec<_>>();
+        Self {
+            num_rows,
+            num_cols,
+            cells,
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        (row < self.num_rows && col < self.num_cols).then(|| &self.cells[self.offset(row, col)])
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.num_cols..(row + 1) * self.num_cols]
+    }
+
+    pub fn row_mut(&mut self, row: usize) -> &mut [T] {
+        &mut self.cells[row * self.num_cols..(row + 1) * self.num_cols]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // chunks panics on zero, and a matrix without columns still has its rows
+        let num_cols = self.num_cols;
+        (0..self.num_rows).map(move |row| &self.cells[row * num_cols..(row + 1) * num_cols])
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.num_cols, "column {col} out of range");
+        self.cells.iter().skip(col).step_by(self.num_cols)
+    }
+
+    // every cell with its (row, col), row by row
+    pub fn indexed_iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let num_cols = self.num_cols.max(1);
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(idx, cell)| ((idx / num_cols, idx % num_cols), cell))
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    fn offset(&self, row: usize, col: usize) -> usize {
+        assert!(col < self.num_cols, "column {col} out of range");
+        row * self.num_cols + col
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        &self.cells[self.offset(row, col)]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        let offset = self.offset(row, col);
+        &mut self.cells[offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexes_rows_and_columns_of_a_flat_table() {
+        let mut matrix = Matrix::from_fn(2, 3, |row, col| row * 10 + col);
+        matrix[(1, 2)] = 99;
+
+        assert_eq!(matrix.row(0), [0, 1, 2]);
+        assert_eq!(matrix.row(1), [10, 11, 99]);
+        assert_eq!(matrix.column(1).copied().collect::<Vec<_>>(), vec![1, 11]);
+        assert_eq!(matrix.get(1, 0), Some(&10));
+        assert_eq!(matrix.get(2, 0), None);
+        assert_eq!(matrix.get(0, 3), None);
+        assert_eq!(
+            matrix
+                .indexed_iter()
+                .filter(|(_, cell)| **cell > 10)
+                .map(|(pos, _)| pos)
+                .collect::<Vec<_>>(),
+            vec![(1, 1), (1, 2)]
+        );
+        assert_eq!(Matrix::new(3, 0, false).rows().count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn column_past_the_end_panics_instead_of_wrapping_rows() {
+        let matrix = Matrix::new(2, 2, 0);
+        let _ = matrix[(0, 2)];
+    }
+}
@@ -1 +1,8 @@
 pub mod cancellation;
+pub mod data_dir;
+pub mod layout;
+pub mod log;
+pub mod matrix;
+pub mod rating;
+pub mod rng;
+pub mod stable_hash;
@@ -0,0 +1,173 @@
+use std::fmt;
+
+const ELO_PER_NATURAL_LOG: f64 = 400.0 / std::f64::consts::LN_10;
+const Z_95: f64 = 1.96;
+const MAX_ITERATIONS: usize = 10_000;
+const CONVERGENCE_TOLERANCE: f64 = 1e-10;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[readonly::make]
+pub struct GameScore {
+    pub first_idx: usize,
+    pub second_idx: usize,
+    pub first_score: f64,
+}
+
+impl GameScore {
+    pub fn new(first_idx: usize, second_idx: usize, first_score: f64) -> Self {
+        Self {
+            first_idx,
+            second_idx,
+            first_score,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EloRating {
+    pub name: String,
+    pub elo: f64,
+    pub ci95: f64,
+    pub num_games: usize,
+    pub score: f64,
+}
+
+impl fmt::Display for EloRating {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<16} elo={:+7.1} ±{:<6.1} games={:<5} score={:.1}",
+            self.name, self.elo, self.ci95, self.num_games, self.score
+        )
+    }
+}
+
+// Bradley-Terry fit with draws as half wins; each pairing gets one virtual draw so a perfect
+// score stays finite.
+pub fn elo_ratings(names: &[String], games: &[GameScore]) -> Vec<EloRating> {
+    let num_engines = names.len();
+    let mut pair_games = vec![vec![0.0; num_engines]; num_engines];
+    let mut wins = vec![0.0; num_engines];
+    let mut num_games = vec![0usize; num_engines];
+    let mut scores = vec![0.0; num_engines];
+
+    for game in games {
+        let (a, b) = (game.first_idx, game.second_idx);
+        if a == b || a >= num_engines || b >= num_engines {
+            continue;
+        }
+        let score = game.first_score.clamp(0.0, 1.0);
+        pair_games[a][b] += 1.0;
+        pair_games[b][a] += 1.0;
+        wins[a] += score;
+        wins[b] += 1.0 - score;
+        scores[a] += score;
+        scores[b] += 1.0 - score;
+        num_games[a] += 1;
+        num_games[b] += 1;
+    }
+
+    for (row, engine_wins) in pair_games.iter_mut().zip(wins.iter_mut()) {
+        for count in row.iter_mut().filter(|count| **count > 0.0) {
+            *count += 1.0;
+            *engine_wins += 0.5;
+        }
+    }
+
+    let mut strengths = vec![1.0; num_engines];
+    for _ in 0..MAX_ITERATIONS {
+        let mut max_change: f64 = 0.0;
+        for i in 0..num_engines {
+            let denominator = (0..num_engines)
+                .filter(|j| pair_games[i][*j] > 0.0)
+                .map(|j| pair_games[i][j] / (strengths[i] + strengths[j]))
+                .sum::<f64>();
+            if denominator <= 0.0 {
+                continue;
+            }
+            let updated = wins[i] / denominator;
+            max_change = max_change.max((updated / strengths[i]).ln().abs());
+            strengths[i] = updated;
+        }
+
+        let log_mean = strengths.iter().map(|s| s.ln()).sum::<f64>() / num_engines as f64;
+        for strength in &mut strengths {
+            *strength /= log_mean.exp();
+        }
+
+        if max_change < CONVERGENCE_TOLERANCE {
+            break;
+        }
+    }
+
+    (0..num_engines)
+        .map(|i| {
+            let information = (0..num_engines)
+                .filter(|j| pair_games[i][*j] > 0.0)
+                .map(|j| {
+                    let p = strengths[i] / (strengths[i] + strengths[j]);
+                    pair_games[i][j] * p * (1.0 - p)
+                })
+                .sum::<f64>();
+            let ci95 = if information > 0.0 {
+                Z_95 * ELO_PER_NATURAL_LOG / information.sqrt()
+            } else {
+                f64::INFINITY
+            };
+
+            EloRating {
+                name: names[i].clone(),
+                elo: ELO_PER_NATURAL_LOG * strengths[i].ln(),
+                ci95,
+                num_games: num_games[i],
+                score: scores[i],
+            }
+        })
+        .collect::<Vec<_>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).map(|idx| format!("E{idx}")).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn even_results_give_equal_ratings() {
+        let games = vec![GameScore::new(0, 1, 1.0), GameScore::new(0, 1, 0.0)];
+        let ratings = elo_ratings(&names(2), &games);
+
+        assert!(ratings[0].elo.abs() < 1e-6);
+        assert!(ratings[1].elo.abs() < 1e-6);
+        assert!(ratings[0].ci95.is_finite());
+        assert_eq!(ratings[0].num_games, 2);
+    }
+
+    #[test]
+    fn stronger_engine_rates_higher_and_ratings_center_on_zero() {
+        let mut games = Vec::new();
+        for _ in 0..8 {
+            games.push(GameScore::new(0, 1, 1.0));
+            games.push(GameScore::new(1, 2, 1.0));
+            games.push(GameScore::new(0, 2, 1.0));
+        }
+        games.push(GameScore::new(0, 1, 0.5));
+        let ratings = elo_ratings(&names(3), &games);
+
+        assert!(ratings[0].elo > ratings[1].elo);
+        assert!(ratings[1].elo > ratings[2].elo);
+        assert!(ratings.iter().all(|rating| rating.elo.is_finite()));
+        assert!(ratings.iter().map(|rating| rating.elo).sum::<f64>().abs() < 1e-6);
+        assert_eq!(ratings[0].score, 16.5);
+    }
+
+    #[test]
+    fn engine_without_games_has_unbounded_interval() {
+        let ratings = elo_ratings(&names(3), &[GameScore::new(0, 1, 1.0)]);
+
+        assert!(ratings[2].ci95.is_infinite());
+        assert_eq!(ratings[2].num_games, 0);
+    }
+}
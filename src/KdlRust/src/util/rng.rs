@@ -0,0 +1,38 @@
+// splitmix64: small, seedable and the same on every target, which is all the engine's deliberate
+// mistakes need
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in 0..1, from the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_numbers_in_unit_range() {
+        let mut rng = SplitMix64::new(7);
+        let draws = (0..100).map(|_| rng.next_f64()).collect::<Vec<_>>();
+        let mut again = SplitMix64::new(7);
+        assert!(draws.iter().all(|draw| *draw == again.next_f64()));
+        assert!(draws.iter().all(|draw| (0.0..1.0).contains(draw)));
+        assert_ne!(SplitMix64::new(8).next_u64(), SplitMix64::new(7).next_u64());
+    }
+}
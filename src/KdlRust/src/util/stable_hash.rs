@@ -0,0 +1,95 @@
+use std::hash::Hasher;
+
+// fnv-1a over little-endian bytes, with usize and isize widened to 64 bits, finished with
+// splitmix64's mixer so the low bits are as good as the high ones. DefaultHasher may change between
+// rust releases and its usize hashing differs between wasm32 and 64-bit targets, so keys that are
+// saved, sent to another device or used to pick a random outcome come from this instead
+#[derive(Clone, Debug)]
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_i64(i as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hash;
+
+    #[test]
+    fn keys_are_fixed_and_usize_hashes_like_u64() {
+        let mut hasher = StableHasher::new();
+        (3usize, "room", vec![1i32, -2]).hash(&mut hasher);
+        let mut widened = StableHasher::new();
+        widened.write_u64(3);
+        "room".hash(&mut widened);
+        vec![1i32, -2].hash(&mut widened);
+        assert_eq!(hasher.finish(), widened.finish());
+
+        // a changed value here means saved and remote position keys no longer match
+        let mut empty = StableHasher::new();
+        empty.write(&[]);
+        assert_eq!(empty.finish(), StableHasher::new().finish());
+        let mut fnv = StableHasher::new();
+        fnv.write(b"a");
+        assert_eq!(fnv.0, 0xaf63_dc4c_8601_ec8c); // fnv-1a's published value for "a"
+    }
+}
@@ -0,0 +1,156 @@
+// replays the recorded games in tests/data/golden_games and compares each one with the
+// expectations stored next to its turns; after an intended rules change, rewrite them with
+//   KDL_REGENERATE_GOLDEN=1 cargo test --test golden_games
+use kill_doctor_lucky_rust::core::{
+    board::Board, common_game_state::CommonGameState, mutable_game_state::MutableGameState,
+    simple_turn::SimpleTurn,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REGENERATE_ENV_VAR: &str = "KDL_REGENERATE_GOLDEN";
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GoldenGame {
+    description: String,
+    board_name: String,
+    num_normal_players: usize,
+    turns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected: Option<GoldenExpectation>,
+}
+
+#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GoldenExpectation {
+    final_state_hash: String,
+    winner: Option<String>,
+    draw_reason: Option<String>,
+    summaries: Vec<String>, // what each turn did, stranger moves after it included
+}
+
+fn golden_game_paths() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/golden_games");
+    let mut paths = fs::read_dir(&dir)
+        .unwrap_or_else(|err| panic!("failed to read {}: {err}", dir.display()))
+        .map(|entry| entry.expect("directory entry should be readable").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+fn replay(game: &GoldenGame) -> Result<GoldenExpectation, String> {
+    let board = Board::from_embedded_json(&game.board_name).map_err(|err| err.to_string())?;
+    let common = CommonGameState::from_num_normal_players(false, board, game.num_normal_players);
+    let mut state = MutableGameState::at_start(common);
+
+    let mut summaries = Vec::with_capacity(game.turns.len());
+    for (turn_idx, turn_text) in game.turns.iter().enumerate() {
+        let turn = turn_text
+            .parse::<SimpleTurn>()
+            .map_err(|err| format!("turn {turn_idx} '{turn_text}': {err}"))?;
+        state
+            .check_normal_turn(&turn)
+            .map_err(|err| format!("turn {turn_idx} '{turn_text}': {err}"))?;
+        state = state.after_turn(turn);
+        summaries.push(state.prev_turn_summaries_since_normal(false));
+    }
+
+    Ok(GoldenExpectation {
+        final_state_hash: format!(
+            "{:016x}",
+            fnv1a(&format!(
+                "{}\n{}",
+                state.summary(0),
+                state.normal_turn_hist()
+            ))
+        ),
+        winner: state
+            .result
+            .winner()
+            .map(|winner| state.player_text_for(winner)),
+        draw_reason: state
+            .draw_reason()
+            .map(|draw_reason| draw_reason.to_string()),
+        summaries,
+    })
+}
+
+// spelled out rather than DefaultHasher, whose output may change between toolchains
+fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn first_mismatch(expected: &GoldenExpectation, actual: &GoldenExpectation) -> String {
+    if let Some((turn_idx, (want, got))) = expected
+        .summaries
+        .iter()
+        .zip(&actual.summaries)
+        .enumerate()
+        .find(|(_, (want, got))| want != got)
+    {
+        return format!("summary after turn {turn_idx} was '{got}', expected '{want}'");
+    }
+    if expected.summaries.len() != actual.summaries.len() {
+        return format!(
+            "{} summaries, expected {}",
+            actual.summaries.len(),
+            expected.summaries.len()
+        );
+    }
+    if expected.winner != actual.winner || expected.draw_reason != actual.draw_reason {
+        return format!(
+            "winner {:?} / draw {:?}, expected {:?} / {:?}",
+            actual.winner, actual.draw_reason, expected.winner, expected.draw_reason
+        );
+    }
+    format!(
+        "final state hash {}, expected {}",
+        actual.final_state_hash, expected.final_state_hash
+    )
+}
+
+#[test]
+fn golden_games_replay_as_recorded() {
+    let regenerate = std::env::var_os(REGENERATE_ENV_VAR).is_some();
+    let paths = golden_game_paths();
+    assert!(!paths.is_empty(), "no golden games found");
+
+    let mut failures = Vec::new();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let text = fs::read_to_string(&path).expect("golden game should be readable");
+        let mut game = serde_json::from_str::<GoldenGame>(&text)
+            .unwrap_or_else(|err| panic!("{name} is not a golden game: {err}"));
+        let actual = match replay(&game) {
+            Ok(actual) => actual,
+            Err(err) => {
+                failures.push(format!("{name}: {err}"));
+                continue;
+            }
+        };
+
+        if regenerate {
+            game.expected = Some(actual);
+            let json = serde_json::to_string_pretty(&game).expect("golden game should serialize");
+            fs::write(&path, json + "\n").expect("golden game should be writable");
+            continue;
+        }
+        match game.expected.as_ref() {
+            None => failures.push(format!(
+                "{name}: no expectations yet; run with {REGENERATE_ENV_VAR}=1"
+            )),
+            Some(expected) if *expected != actual => {
+                failures.push(format!("{name}: {}", first_mismatch(expected, &actual)));
+            }
+            Some(_) => {}
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}